@@ -0,0 +1,4 @@
+def divide(a, b):
+    if b == 0:
+        raise ValueError("division by zero")
+    return a / b
@@ -0,0 +1,184 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::agent::diff::parse_diff;
+use crate::agent::findings::{Finding, inline_suppression};
+
+/// Default baseline file, relative to the working directory
+pub const BASELINE_FILE: &str = ".qitops-baseline.json";
+
+/// How the baseline is applied to a run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BaselineMode {
+    /// Report only findings missing from the baseline (if a baseline exists)
+    #[default]
+    Use,
+    /// Capture the current findings as the new baseline
+    Write,
+    /// Report every finding
+    Ignore,
+}
+
+impl FromStr for BaselineMode {
+    type Err = anyhow::Error;
+
+    /// Parse a string into a baseline mode
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "use" => Ok(BaselineMode::Use),
+            "write" | "update" => Ok(BaselineMode::Write),
+            "ignore" | "off" => Ok(BaselineMode::Ignore),
+            _ => Err(anyhow!("Unknown baseline mode: {} (expected use, write or ignore)", s)),
+        }
+    }
+}
+
+/// A baselined finding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    /// Finding fingerprint
+    pub fingerprint: String,
+
+    /// Finding title, for humans reading the file
+    pub title: String,
+
+    /// File the finding refers to
+    #[serde(default)]
+    pub file: Option<String>,
+
+    /// Why the finding is accepted
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Baseline file contents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    /// File format version
+    pub version: u32,
+
+    /// Accepted findings
+    pub findings: Vec<BaselineEntry>,
+}
+
+impl Default for Baseline {
+    fn default() -> Self {
+        Self {
+            version: 1,
+            findings: Vec::new(),
+        }
+    }
+}
+
+impl Baseline {
+    /// Load a baseline file, returning None if it doesn't exist
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read baseline {}: {}", path.display(), e))?;
+        let baseline = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Failed to parse baseline {}: {}", path.display(), e))?;
+
+        Ok(Some(baseline))
+    }
+
+    /// Save the baseline file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)
+            .map_err(|e| anyhow!("Failed to write baseline {}: {}", path.display(), e))
+    }
+
+    /// Find the baseline entry for a finding
+    pub fn get(&self, finding: &Finding) -> Option<&BaselineEntry> {
+        let fingerprint = finding.fingerprint();
+        self.findings.iter().find(|entry| entry.fingerprint == fingerprint)
+    }
+}
+
+/// A finding that was not reported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuppressedFinding {
+    /// The finding
+    pub finding: Finding,
+
+    /// Why it was suppressed
+    pub reason: String,
+}
+
+/// Findings after applying the baseline and inline suppressions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FindingsOutcome {
+    /// Findings to report
+    pub new: Vec<Finding>,
+
+    /// Findings matched by the baseline
+    pub baselined: Vec<SuppressedFinding>,
+
+    /// Findings suppressed inline in the code
+    pub suppressed: Vec<SuppressedFinding>,
+
+    /// Whether a baseline was written
+    pub baseline_written: bool,
+}
+
+/// Apply inline suppressions and the baseline file to a run's findings
+pub fn apply_baseline(findings: Vec<Finding>, diff: &str, mode: BaselineMode, path: &Path) -> Result<FindingsOutcome> {
+    let files = parse_diff(diff);
+    let mut outcome = FindingsOutcome::default();
+
+    // Inline suppressions always apply
+    let mut remaining = Vec::new();
+    for finding in findings {
+        match inline_suppression(&finding, &files) {
+            Some(reason) => outcome.suppressed.push(SuppressedFinding { finding, reason }),
+            None => remaining.push(finding),
+        }
+    }
+
+    match mode {
+        BaselineMode::Ignore => outcome.new = remaining,
+        BaselineMode::Write => {
+            // Keep the reasons of findings that were already baselined
+            let existing = Baseline::load(path)?.unwrap_or_default();
+            let mut baseline = Baseline::default();
+            for finding in remaining {
+                let reason = existing.get(&finding).and_then(|entry| entry.reason.clone());
+                if !baseline.findings.iter().any(|entry| entry.fingerprint == finding.fingerprint()) {
+                    baseline.findings.push(BaselineEntry {
+                        fingerprint: finding.fingerprint(),
+                        title: finding.title.clone(),
+                        file: finding.file.clone(),
+                        reason: reason.clone(),
+                    });
+                }
+                outcome.baselined.push(SuppressedFinding { finding, reason: reason.unwrap_or_else(|| "baselined".to_string()) });
+            }
+
+            baseline.save(path)?;
+            outcome.baseline_written = true;
+        },
+        BaselineMode::Use => match Baseline::load(path)? {
+            Some(baseline) => {
+                for finding in remaining {
+                    match baseline.get(&finding) {
+                        Some(entry) => {
+                            let reason = entry.reason.clone().unwrap_or_else(|| "baselined".to_string());
+                            outcome.baselined.push(SuppressedFinding { finding, reason });
+                        },
+                        None => outcome.new.push(finding),
+                    }
+                }
+            },
+            None => outcome.new = remaining,
+        },
+    }
+
+    Ok(outcome)
+}
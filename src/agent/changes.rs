@@ -0,0 +1,155 @@
+// Code changed since a git ref, for generating tests for the delta only
+
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use crate::agent::diff::{DiffLineKind, parse_diff};
+use crate::error::QitOpsError;
+use crate::git::git;
+
+/// A function definition in Rust, Python, JavaScript/TypeScript or Go, capturing its name
+static FUNCTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:export\s+)?(?:default\s+)?(?:async\s+|const\s+|unsafe\s+|static\s+)*(?:fn|def|function|func)\s+(?:\([^)]*\)\s*)?([A-Za-z_$][A-Za-z0-9_$]*)").unwrap()
+});
+
+/// A file changed since a git ref
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangedFile {
+    /// Absolute path
+    pub path: String,
+
+    /// Whether the file is new since the ref
+    pub added: bool,
+
+    /// Added or changed lines
+    pub lines: Vec<u32>,
+
+    /// Functions those lines are in, in file order
+    pub functions: Vec<String>,
+}
+
+impl ChangedFile {
+    /// What a test generation prompt should focus on, or `None` for a new file, which is tested whole
+    pub fn focus(&self, since: &str) -> Option<String> {
+        if self.added {
+            return None;
+        }
+
+        if !self.functions.is_empty() {
+            let functions: Vec<String> = self.functions.iter().map(|function| format!("`{}`", function)).collect();
+            return Some(format!(
+                "Only generate test cases for the code changed since `{}`: the function(s) {}. Use the rest of the code as context.",
+                since, functions.join(", "),
+            ));
+        }
+
+        Some(format!(
+            "Only generate test cases for the code changed since `{}` (lines {}); use the rest of the code as context.",
+            since, line_ranges(&self.lines),
+        ))
+    }
+}
+
+/// Lines as ranges, e.g. `3-5, 9`
+fn line_ranges(lines: &[u32]) -> String {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for &line in lines {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == line => *end = line,
+            _ => ranges.push((line, line)),
+        }
+    }
+
+    ranges.iter()
+        .map(|(start, end)| if start == end { start.to_string() } else { format!("{}-{}", start, end) })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Functions containing any of `lines` (1-based), in file order
+///
+/// A line belongs to the nearest function definition above it with less indentation, unless
+/// a block that isn't a function (a struct, a class body) encloses it first. Lines outside any
+/// function, such as imports, and blank lines belong to none.
+pub fn enclosing_functions(code: &str, lines: &[u32]) -> Vec<String> {
+    let source: Vec<&str> = code.lines().collect();
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let closes = |line: &str| line.trim_start().starts_with(['}', ')', ']']);
+
+    let mut functions: Vec<(usize, String)> = Vec::new();
+    for &line in lines {
+        let Some(index) = (line as usize).checked_sub(1).filter(|index| *index < source.len() && !source[*index].trim().is_empty()) else {
+            continue;
+        };
+
+        if let Some(captures) = FUNCTION.captures(source[index]) {
+            if !functions.iter().any(|(_, name)| name == &captures[1]) {
+                functions.push((index, captures[1].to_string()));
+            }
+            continue;
+        }
+
+        // A closing brace belongs to the block it closes
+        let mut limit = indent(source[index]) + usize::from(closes(source[index]));
+        for candidate in (0..index).rev() {
+            let text = source[candidate];
+            if text.trim().is_empty() || indent(text) >= limit || closes(text) {
+                continue;
+            }
+            if let Some(captures) = FUNCTION.captures(text) {
+                if !functions.iter().any(|(_, name)| name == &captures[1]) {
+                    functions.push((candidate, captures[1].to_string()));
+                }
+                break;
+            }
+            // Part of a multi-line signature, or the start of a block that isn't a function
+            limit = indent(text);
+            if limit == 0 {
+                break;
+            }
+        }
+    }
+
+    functions.sort();
+    functions.into_iter().map(|(_, name)| name).collect()
+}
+
+/// Files under a directory changed since a git ref, with the functions changed in each
+///
+/// Changes are taken against the merge base of the ref and `HEAD`, so on a branch only its own
+/// changes count, including uncommitted ones. Paths are absolute; deleted files are left out.
+pub fn changed_since(dir: &Path, since: &str) -> Result<Vec<ChangedFile>> {
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let root = PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"])?.trim());
+    let diff = git(dir, &["diff", "--merge-base", "--unified=0", "--no-color", "--no-ext-diff", since, "--", "."])
+        .map_err(|e| QitOpsError::InvalidInput(format!(
+            "Could not diff against {}: {} (in a shallow CI checkout, fetch the ref first, e.g. `git fetch origin main`)", since, e,
+        )))?;
+
+    let mut files = Vec::new();
+    for file in parse_diff(&diff) {
+        let Some(new_path) = &file.new_path else {
+            continue;
+        };
+        let path = root.join(new_path);
+
+        let lines: Vec<u32> = file.lines()
+            .filter(|line| line.kind == DiffLineKind::Added)
+            .filter_map(|line| line.new_line)
+            .collect();
+        let code = std::fs::read_to_string(&path).unwrap_or_default();
+        let functions = enclosing_functions(&code, &lines);
+
+        files.push(ChangedFile {
+            path: path.to_string_lossy().to_string(),
+            added: file.old_path.is_none(),
+            lines,
+            functions,
+        });
+    }
+
+    Ok(files)
+}
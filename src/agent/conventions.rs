@@ -0,0 +1,138 @@
+// Conventions of a project's existing tests, for generated tests to follow
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::scan::{ScanLimits, scan_files};
+
+/// Existing test files shown to the model per source file
+pub const MAX_SAMPLES: usize = 3;
+
+/// Lines of a sample test file shown to the model
+const SAMPLE_LINES: usize = 80;
+
+/// Directories holding tests
+const TEST_DIRS: &[&str] = &["tests", "test", "__tests__", "spec"];
+
+/// Directories of dependencies and build output, whose tests aren't the project's
+pub const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "vendor", "dist", "build", "venv"];
+
+/// Whether a file is a test, by its directory or its name
+pub fn is_test_file(root: &Path, file: &Path) -> bool {
+    file.strip_prefix(root).unwrap_or(file).components()
+        .any(|part| part.as_os_str().to_str().is_some_and(|part| TEST_DIRS.contains(&part)))
+        || file.file_name().is_some_and(|name| {
+            let name = name.to_string_lossy();
+            name.starts_with("test_") || name.contains("_test.") || name.contains(".test.") || name.contains(".spec.")
+        })
+}
+
+/// The root of the repository a directory is in, or the directory itself outside a repository
+pub fn project_root(dir: &Path) -> PathBuf {
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    dir.ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .unwrap_or(&dir)
+        .to_path_buf()
+}
+
+/// A project's test files, to pick samples for each source file from
+pub struct TestSamples {
+    /// Test files of the project
+    tests: Vec<PathBuf>,
+}
+
+impl TestSamples {
+    /// Find the test files under a project root with one of `extensions`
+    pub fn scan(root: &Path, limits: &ScanLimits, extensions: Vec<String>) -> Result<Self> {
+        let scan_root = root.to_path_buf();
+        let scan = scan_files(root, limits, move |file| {
+            let relative = file.strip_prefix(&scan_root).unwrap_or(file);
+            file.extension().is_some_and(|ext| extensions.iter().any(|wanted| *wanted == ext.to_string_lossy()))
+                && !relative.components().any(|part| part.as_os_str().to_str().is_some_and(|part| SKIPPED_DIRS.contains(&part)))
+                && is_test_file(&scan_root, file)
+        })?;
+
+        if let Some(limit) = scan.limit_summary() {
+            tracing::debug!("Test sample scan of {} incomplete: {}", root.display(), limit);
+        }
+
+        Ok(Self { tests: scan.files })
+    }
+
+    /// The test files most likely to show how a source file's tests should look
+    ///
+    /// Tests of the same module (named after the source file) come first, then tests closest
+    /// to it in the directory tree.
+    pub fn for_source(&self, source: &Path) -> Vec<PathBuf> {
+        let source = fs::canonicalize(source).unwrap_or_else(|_| source.to_path_buf());
+        let stem = source.file_stem().map(|stem| stem.to_string_lossy().to_lowercase()).unwrap_or_default();
+        let source_dir = source.parent().unwrap_or(Path::new(""));
+        let cwd = std::env::current_dir().unwrap_or_default();
+
+        let mut ranked: Vec<(bool, usize, &PathBuf)> = self.tests.iter()
+            .filter(|test| **test != source)
+            .map(|test| {
+                let name = test.file_stem().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+                let same_module = !stem.is_empty() && name.contains(&stem);
+                (!same_module, distance(source_dir, test.parent().unwrap_or(Path::new(""))), test)
+            })
+            .collect();
+        ranked.sort();
+
+        ranked.into_iter()
+            .take(MAX_SAMPLES)
+            .map(|(_, _, test)| test.strip_prefix(&cwd).unwrap_or(test).to_path_buf())
+            .collect()
+    }
+}
+
+/// Number of directory steps between two directories
+fn distance(from: &Path, to: &Path) -> usize {
+    let from: Vec<_> = from.components().collect();
+    let to: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    (from.len() - common) + (to.len() - common)
+}
+
+/// Instructions for the model to follow the project's test conventions
+///
+/// An explicit style guide replaces the samples.
+pub fn conventions_prompt(samples: &[PathBuf], guide: Option<&(String, String)>) -> String {
+    if let Some((path, guide)) = guide {
+        return format!(
+            "Follow the project's test style guide below for naming, assertion style and fixtures.\n\n{}",
+            crate::prompt::wrap_untrusted(&format!("test style guide {}", path), guide)
+        );
+    }
+
+    let mut excerpts = Vec::new();
+    for sample in samples {
+        let content = match fs::read_to_string(sample) {
+            Ok(content) => content,
+            Err(e) => {
+                tracing::debug!("Skipping test sample {}: {}", sample.display(), e);
+                continue;
+            },
+        };
+        let excerpt = content.lines().take(SAMPLE_LINES).collect::<Vec<_>>().join("\n");
+        excerpts.push(crate::prompt::wrap_untrusted(&format!("existing test {}", sample.display()), &excerpt));
+    }
+
+    if excerpts.is_empty() {
+        return String::new();
+    }
+
+    format!(
+        "The project already has tests. Match their conventions where they apply: how tests and test files are named, the assertion style, and the fixtures, helpers and setup they use.\n\n{}",
+        excerpts.join("\n\n")
+    )
+}
+
+/// Read an explicit test style guide
+pub fn read_guide(path: &str) -> Result<(String, String)> {
+    let guide = fs::read_to_string(path).context(format!("Failed to read test conventions: {}", path))?;
+    Ok((path.to_string(), guide))
+}
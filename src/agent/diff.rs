@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+
+/// Kind of a line in a diff hunk
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DiffLineKind {
+    /// Unchanged context line
+    Context,
+    /// Added line
+    Added,
+    /// Removed line
+    Removed,
+}
+
+/// A line in a diff hunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    /// Line kind
+    pub kind: DiffLineKind,
+
+    /// Line number in the old file (None for added lines)
+    pub old_line: Option<u32>,
+
+    /// Line number in the new file (None for removed lines)
+    pub new_line: Option<u32>,
+
+    /// Line content without the diff marker
+    pub content: String,
+}
+
+/// A hunk in a file diff
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunk {
+    /// Hunk header (the `@@ ... @@` line)
+    pub header: String,
+
+    /// First line number in the old file
+    pub old_start: u32,
+
+    /// First line number in the new file
+    pub new_start: u32,
+
+    /// Lines in the hunk
+    pub lines: Vec<DiffLine>,
+}
+
+/// Diff of a single file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiff {
+    /// Old path (None for added files)
+    pub old_path: Option<String>,
+
+    /// New path (None for deleted files)
+    pub new_path: Option<String>,
+
+    /// Hunks
+    pub hunks: Vec<DiffHunk>,
+}
+
+impl FileDiff {
+    /// Path of the file after the change, falling back to the old path for deletions
+    pub fn path(&self) -> &str {
+        self.new_path.as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or("")
+    }
+
+    /// All lines across hunks
+    pub fn lines(&self) -> impl Iterator<Item = &DiffLine> {
+        self.hunks.iter().flat_map(|hunk| hunk.lines.iter())
+    }
+
+    /// Find the line with the given number in the new file
+    pub fn new_line(&self, line: u32) -> Option<&DiffLine> {
+        self.lines().find(|l| l.new_line == Some(line))
+    }
+}
+
+/// Strip the `a/` or `b/` prefix from a diff path, mapping /dev/null to None
+fn parse_path(path: &str) -> Option<String> {
+    let path = path.split('\t').next().unwrap_or(path).trim();
+    if path == "/dev/null" {
+        return None;
+    }
+
+    Some(path.strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path)
+        .to_string())
+}
+
+/// Parse a hunk range like `-12,5` into its start line
+fn parse_range_start(range: &str) -> u32 {
+    range[1..].split(',').next()
+        .and_then(|start| start.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Parse a unified diff into typed file diffs
+pub fn parse_diff(diff: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    let mut old_line = 0;
+    let mut new_line = 0;
+    let mut lines = diff.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        let is_file_header = line.starts_with("--- ")
+            && lines.peek().is_some_and(|next| next.starts_with("+++ "));
+
+        if line.starts_with("diff --git ") {
+            files.push(FileDiff { old_path: None, new_path: None, hunks: Vec::new() });
+        } else if is_file_header {
+            // Plain unified diffs have no `diff --git` line before the headers
+            if files.last().is_none_or(|file| !file.hunks.is_empty()) {
+                files.push(FileDiff { old_path: None, new_path: None, hunks: Vec::new() });
+            }
+
+            let new_path = lines.next().and_then(|next| next.strip_prefix("+++ ")).and_then(parse_path);
+            if let Some(file) = files.last_mut() {
+                file.old_path = parse_path(&line[4..]);
+                file.new_path = new_path;
+            }
+        } else if line.starts_with("@@") {
+            let mut parts = line.split_whitespace().skip(1);
+            let old_start = parts.next().map(parse_range_start).unwrap_or(0);
+            let new_start = parts.next().map(parse_range_start).unwrap_or(0);
+            old_line = old_start;
+            new_line = new_start;
+
+            if files.is_empty() {
+                files.push(FileDiff { old_path: None, new_path: None, hunks: Vec::new() });
+            }
+            if let Some(file) = files.last_mut() {
+                file.hunks.push(DiffHunk { header: line.to_string(), old_start, new_start, lines: Vec::new() });
+            }
+        } else if let Some(hunk) = files.last_mut().and_then(|file| file.hunks.last_mut()) {
+            let (kind, content) = match line.chars().next() {
+                Some('+') => (DiffLineKind::Added, &line[1..]),
+                Some('-') => (DiffLineKind::Removed, &line[1..]),
+                Some(' ') => (DiffLineKind::Context, &line[1..]),
+                None => (DiffLineKind::Context, ""),
+                // "\ No newline at end of file" and other metadata
+                _ => continue,
+            };
+
+            let diff_line = match kind {
+                DiffLineKind::Added => {
+                    new_line += 1;
+                    DiffLine { kind, old_line: None, new_line: Some(new_line - 1), content: content.to_string() }
+                },
+                DiffLineKind::Removed => {
+                    old_line += 1;
+                    DiffLine { kind, old_line: Some(old_line - 1), new_line: None, content: content.to_string() }
+                },
+                DiffLineKind::Context => {
+                    old_line += 1;
+                    new_line += 1;
+                    DiffLine { kind, old_line: Some(old_line - 1), new_line: Some(new_line - 1), content: content.to_string() }
+                },
+            };
+            hunk.lines.push(diff_line);
+        }
+    }
+
+    files
+}
+
+/// Position of a new-file line in a file's patch, as GitHub anchors review comments
+///
+/// The position counts lines down from the first hunk header: the line below it is 1, and
+/// later hunk headers count too. Only added and context lines can be commented on, so
+/// removed lines and lines outside the hunks have no position.
+pub fn patch_position(patch: &str, line: u32) -> Option<u32> {
+    let mut position = 0;
+    let mut new_line = 0;
+    let mut in_hunk = false;
+
+    for text in patch.lines() {
+        if text.starts_with("@@") {
+            if in_hunk {
+                position += 1;
+            }
+            in_hunk = true;
+            new_line = text.split_whitespace().nth(2).map(parse_range_start).unwrap_or(0);
+            continue;
+        }
+        if !in_hunk {
+            continue;
+        }
+
+        position += 1;
+        match text.chars().next() {
+            Some('-') | Some('\\') => {},
+            _ => {
+                if new_line == line {
+                    return Some(position);
+                }
+                new_line += 1;
+            },
+        }
+    }
+
+    None
+}
@@ -0,0 +1,452 @@
+// Filters that strip noise (formatting, comments, import order) from diffs before analysis
+
+use anyhow::{Result, anyhow};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, LazyLock};
+
+/// Names of the built-in diff filters
+pub const BUILTIN_FILTERS: &[&str] = &["formatting", "comments", "imports"];
+
+/// Comment and import syntax of a language, for telling noise from changes
+pub struct Language {
+    /// Language name
+    pub name: &'static str,
+
+    /// File extensions, without the dot
+    pub extensions: &'static [&'static str],
+
+    /// Line comment markers
+    pub line_comments: &'static [&'static str],
+
+    /// Whether block comments are written `/* ... */`, with `*` continuation lines
+    pub block_comments: bool,
+
+    /// Matches a line that imports a module, if the language has imports
+    pub import: Option<&'static LazyLock<Regex>>,
+
+    /// Whether indentation changes meaning, so re-indenting isn't formatting
+    pub significant_whitespace: bool,
+}
+
+static RUST_IMPORT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*(pub(\([^)]*\))?\s+)?use\s+\S").unwrap());
+static PYTHON_IMPORT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*(import\s+\S|from\s+\S+\s+import\s)").unwrap());
+static JS_IMPORT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^\s*(import\s.*from\s+['"]|import\s+['"]|(const|let|var)\s+.*=\s*require\(['"])"#).unwrap()
+});
+static GO_IMPORT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"^\s*(import\s+)?([\w.]+\s+)?"[^"]+"\s*$"#).unwrap());
+static JVM_IMPORT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*import\s+(static\s+)?[\w.*]+\s*;?\s*$").unwrap());
+static C_INCLUDE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"^\s*#\s*include\s*[<"]"#).unwrap());
+static CSHARP_USING: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*using\s+(static\s+)?[\w.]+(\s*=\s*[\w.]+)?\s*;?\s*$").unwrap());
+static RUBY_REQUIRE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"^\s*require(_relative)?\s*\(?\s*['"]"#).unwrap());
+static PHP_USE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*use\s+[\w\\]+(\s+as\s+\w+)?\s*;?\s*$").unwrap());
+
+/// Languages the filters know
+pub static LANGUAGES: &[Language] = &[
+    Language { name: "Rust", extensions: &["rs"], line_comments: &["//"], block_comments: true, import: Some(&RUST_IMPORT), significant_whitespace: false },
+    Language { name: "Python", extensions: &["py", "pyi"], line_comments: &["#"], block_comments: false, import: Some(&PYTHON_IMPORT), significant_whitespace: true },
+    Language { name: "JavaScript", extensions: &["js", "jsx", "mjs", "cjs", "ts", "tsx"], line_comments: &["//"], block_comments: true, import: Some(&JS_IMPORT), significant_whitespace: false },
+    Language { name: "Go", extensions: &["go"], line_comments: &["//"], block_comments: true, import: Some(&GO_IMPORT), significant_whitespace: false },
+    Language { name: "Java", extensions: &["java", "kt", "kts", "scala", "groovy"], line_comments: &["//"], block_comments: true, import: Some(&JVM_IMPORT), significant_whitespace: false },
+    Language { name: "C", extensions: &["c", "h", "cc", "cpp", "cxx", "hpp", "hh"], line_comments: &["//"], block_comments: true, import: Some(&C_INCLUDE), significant_whitespace: false },
+    Language { name: "C#", extensions: &["cs"], line_comments: &["//"], block_comments: true, import: Some(&CSHARP_USING), significant_whitespace: false },
+    Language { name: "Ruby", extensions: &["rb"], line_comments: &["#"], block_comments: false, import: Some(&RUBY_REQUIRE), significant_whitespace: false },
+    Language { name: "PHP", extensions: &["php"], line_comments: &["//", "#"], block_comments: true, import: Some(&PHP_USE), significant_whitespace: false },
+    Language { name: "Shell", extensions: &["sh", "bash", "zsh"], line_comments: &["#"], block_comments: false, import: None, significant_whitespace: false },
+    Language { name: "YAML", extensions: &["yml", "yaml"], line_comments: &["#"], block_comments: false, import: None, significant_whitespace: true },
+];
+
+/// Language of a file, by its extension
+pub fn language_for(path: &str) -> Option<&'static Language> {
+    let extension = path.rsplit_once('.')?.1.to_lowercase();
+    LANGUAGES.iter().find(|language| language.extensions.contains(&extension.as_str()))
+}
+
+/// A filter that recognizes hunks whose changes don't change behavior
+///
+/// Filters only see hunks of files in a known [`Language`].
+pub trait DiffFilter: Send + Sync {
+    /// Name the filter is configured by and reported under
+    fn name(&self) -> &str;
+
+    /// Whether a hunk is noise
+    fn is_noise(&self, language: &Language, hunk: &HunkLines) -> bool;
+}
+
+/// Lines of a hunk, without their `+`, `-` or ` ` prefix
+pub struct HunkLines<'a> {
+    /// Removed lines
+    pub removed: Vec<&'a str>,
+
+    /// Added lines
+    pub added: Vec<&'a str>,
+
+    /// Context and removed lines, in order: the hunk before the change
+    pub before: Vec<&'a str>,
+
+    /// Context and added lines, in order: the hunk after the change
+    pub after: Vec<&'a str>,
+}
+
+impl<'a> HunkLines<'a> {
+    /// Split a hunk body (the lines after the `@@` line)
+    pub fn parse(body: &'a str) -> Self {
+        let mut hunk = Self { removed: Vec::new(), added: Vec::new(), before: Vec::new(), after: Vec::new() };
+        for line in body.lines() {
+            if let Some(line) = line.strip_prefix('-') {
+                hunk.removed.push(line);
+                hunk.before.push(line);
+            } else if let Some(line) = line.strip_prefix('+') {
+                hunk.added.push(line);
+                hunk.after.push(line);
+            } else if let Some(line) = line.strip_prefix(' ').or((line.is_empty()).then_some(line)) {
+                hunk.before.push(line);
+                hunk.after.push(line);
+            }
+        }
+        hunk
+    }
+
+    /// Number of added and removed lines
+    pub fn changed(&self) -> usize {
+        self.removed.len() + self.added.len()
+    }
+}
+
+/// Hunks that only change whitespace, line breaks or indentation (where indentation doesn't matter)
+pub struct FormattingFilter;
+
+impl DiffFilter for FormattingFilter {
+    fn name(&self) -> &str {
+        "formatting"
+    }
+
+    fn is_noise(&self, language: &Language, hunk: &HunkLines) -> bool {
+        // Both sides, context included, so moving a line isn't taken for formatting
+        if language.significant_whitespace {
+            // Only spacing within lines may change; indentation and line breaks matter
+            let normalize = |lines: &[&str]| -> Vec<String> {
+                lines.iter()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(|line| {
+                        let indent = &line[..line.len() - line.trim_start().len()];
+                        format!("{}{}", indent, line.split_whitespace().collect::<Vec<_>>().join(" "))
+                    })
+                    .collect()
+            };
+            return normalize(&hunk.before) == normalize(&hunk.after);
+        }
+
+        squash(&hunk.before) == squash(&hunk.after)
+    }
+}
+
+/// Lines joined with the whitespace outside string literals removed
+fn squash(lines: &[&str]) -> String {
+    let mut squashed = String::new();
+    for line in lines {
+        let mut quote = None;
+        let mut escaped = false;
+        for c in line.chars() {
+            match quote {
+                Some(q) => {
+                    if !escaped && c == q {
+                        quote = None;
+                    }
+                    escaped = !escaped && c == '\\';
+                },
+                None if c == '"' || c == '\'' || c == '`' => quote = Some(c),
+                None if c.is_whitespace() => continue,
+                None => {},
+            }
+            squashed.push(c);
+        }
+    }
+    squashed
+}
+
+/// Hunks that only add, remove or edit comments and blank lines
+pub struct CommentFilter;
+
+impl CommentFilter {
+    fn is_comment(language: &Language, line: &str) -> bool {
+        let line = line.trim();
+        line.is_empty()
+            || language.line_comments.iter().any(|marker| line.starts_with(marker))
+            || (language.block_comments && (line.starts_with("/*") || line.starts_with("*/") || line.starts_with("* ") || line == "*"))
+    }
+}
+
+impl DiffFilter for CommentFilter {
+    fn name(&self) -> &str {
+        "comments"
+    }
+
+    fn is_noise(&self, language: &Language, hunk: &HunkLines) -> bool {
+        hunk.removed.iter().chain(&hunk.added).all(|line| Self::is_comment(language, line))
+    }
+}
+
+/// Hunks that only reorder imports
+pub struct ImportOrderFilter;
+
+impl DiffFilter for ImportOrderFilter {
+    fn name(&self) -> &str {
+        "imports"
+    }
+
+    fn is_noise(&self, language: &Language, hunk: &HunkLines) -> bool {
+        let Some(import) = language.import else { return false };
+
+        let sorted = |lines: &[&str]| {
+            let mut lines: Vec<String> = lines.iter()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty())
+                .map(|line| line.trim_end_matches([';', ',']).to_string())
+                .collect();
+            lines.sort();
+            lines
+        };
+        let (removed, added) = (sorted(&hunk.removed), sorted(&hunk.added));
+        removed == added && removed.iter().all(|line| import.is_match(line))
+    }
+}
+
+/// The built-in filter with a name
+pub fn builtin_filter(name: &str) -> Option<Arc<dyn DiffFilter>> {
+    match name {
+        "formatting" => Some(Arc::new(FormattingFilter)),
+        "comments" => Some(Arc::new(CommentFilter)),
+        "imports" => Some(Arc::new(ImportOrderFilter)),
+        _ => None,
+    }
+}
+
+/// Which noise filters run on diffs before analysis (the `diff_filters` section of the config)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiffFilterConfig {
+    /// Filters to run, in order; empty turns filtering off
+    pub filters: Vec<String>,
+}
+
+impl Default for DiffFilterConfig {
+    fn default() -> Self {
+        Self { filters: BUILTIN_FILTERS.iter().map(|name| name.to_string()).collect() }
+    }
+}
+
+/// A hunk left out of the analysis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilteredHunk {
+    /// File the hunk is in
+    pub file: String,
+
+    /// Hunk header (the `@@ ... @@` line)
+    pub hunk: String,
+
+    /// Filter that recognized the hunk as noise
+    pub filter: String,
+
+    /// Added and removed lines in the hunk
+    pub lines: usize,
+}
+
+/// What filtering left out of a diff
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FilterReport {
+    /// Hunks left out
+    pub hunks: Vec<FilteredHunk>,
+
+    /// Files left out because all their hunks were noise
+    pub files: Vec<String>,
+
+    /// Whether the whole diff was noise and was kept as it is
+    pub kept_whole: bool,
+}
+
+impl FilterReport {
+    /// Whether nothing was filtered
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty()
+    }
+
+    /// One-line summary, e.g. `Filtered 3 noise hunk(s): 2 formatting, 1 imports`
+    pub fn summary(&self) -> Option<String> {
+        if self.hunks.is_empty() {
+            return None;
+        }
+
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for hunk in &self.hunks {
+            *counts.entry(&hunk.filter).or_default() += 1;
+        }
+        let counts: Vec<String> = counts.iter().map(|(filter, count)| format!("{} {}", count, filter)).collect();
+
+        let mut summary = format!("Filtered {} noise hunk(s): {}", self.hunks.len(), counts.join(", "));
+        if !self.files.is_empty() {
+            summary.push_str(&format!("; only noise in {}", self.files.join(", ")));
+        }
+        Some(summary)
+    }
+}
+
+/// A file section of a raw diff
+struct RawFile {
+    /// Lines before the first hunk (`diff --git`, `index`, `---`, `+++`)
+    header: String,
+
+    /// Path after the change
+    path: String,
+
+    /// Hunks: the `@@` line and the lines after it
+    hunks: Vec<(String, String)>,
+}
+
+/// Split a raw diff into the text before the first file and its file sections
+fn split_files(diff: &str) -> (String, Vec<RawFile>) {
+    let mut preamble = String::new();
+    let mut files: Vec<RawFile> = Vec::new();
+    let mut lines = diff.split_inclusive('\n').peekable();
+
+    while let Some(line) = lines.next() {
+        // Plain unified diffs have no `diff --git` line before the headers
+        let plain_header = line.starts_with("--- ")
+            && lines.peek().is_some_and(|next| next.starts_with("+++ "))
+            && files.last().is_none_or(|file| !file.hunks.is_empty());
+
+        if line.starts_with("diff --git ") || plain_header {
+            let path = line.strip_prefix("diff --git ")
+                .and_then(|paths| paths.trim_end().rsplit_once(" b/").map(|(_, b)| b.to_string()))
+                .unwrap_or_default();
+            files.push(RawFile { header: String::new(), path, hunks: Vec::new() });
+        }
+
+        match files.last_mut() {
+            None => preamble.push_str(line),
+            Some(file) if line.starts_with("@@") => file.hunks.push((line.to_string(), String::new())),
+            Some(file) => match file.hunks.last_mut() {
+                Some((_, body)) => body.push_str(line),
+                None => {
+                    if let Some(path) = line.strip_prefix("+++ ") {
+                        let path = path.trim_end().split('\t').next().unwrap_or_default();
+                        if path != "/dev/null" {
+                            file.path = path.strip_prefix("b/").unwrap_or(path).to_string();
+                        }
+                    }
+                    file.header.push_str(line);
+                },
+            },
+        }
+    }
+
+    (preamble, files)
+}
+
+/// The filters run on diffs before analysis
+#[derive(Clone)]
+pub struct DiffFilters {
+    /// Filters, in the order they are tried
+    filters: Vec<Arc<dyn DiffFilter>>,
+}
+
+impl Default for DiffFilters {
+    fn default() -> Self {
+        Self { filters: BUILTIN_FILTERS.iter().filter_map(|name| builtin_filter(name)).collect() }
+    }
+}
+
+impl DiffFilters {
+    /// No filtering
+    pub fn none() -> Self {
+        Self { filters: Vec::new() }
+    }
+
+    /// The filters of a configuration
+    pub fn from_config(config: &DiffFilterConfig) -> Result<Self> {
+        let filters = config.filters.iter()
+            .map(|name| builtin_filter(name).ok_or_else(|| anyhow!(
+                "Unknown diff filter '{}' (built-in: {})", name, BUILTIN_FILTERS.join(", ")
+            )))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { filters })
+    }
+
+    /// Add a filter, tried after the others
+    pub fn register(&mut self, filter: Arc<dyn DiffFilter>) {
+        self.filters.push(filter);
+    }
+
+    /// Whether no filters run
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Leave the noise hunks out of a diff, reporting what was left out
+    ///
+    /// Hunk headers keep their line numbers, so findings still point at the right lines. Files
+    /// in unknown languages are kept whole, and so is a diff that is all noise, so there is
+    /// still something to analyze.
+    pub fn apply(&self, diff: &str) -> (String, FilterReport) {
+        let mut report = FilterReport::default();
+        if self.filters.is_empty() {
+            return (diff.to_string(), report);
+        }
+
+        let (mut filtered, files) = split_files(diff);
+        for file in files {
+            let Some(language) = language_for(&file.path) else {
+                filtered.push_str(&file.header);
+                for (header, body) in &file.hunks {
+                    filtered.push_str(header);
+                    filtered.push_str(body);
+                }
+                continue;
+            };
+            tracing::debug!("Filtering noise from {} as {}", file.path, language.name);
+
+            let mut kept = String::new();
+            let mut kept_hunks = 0;
+            for (header, body) in &file.hunks {
+                let lines = HunkLines::parse(body);
+                let noise = match lines.changed() {
+                    0 => None,
+                    _ => self.filters.iter().find(|filter| filter.is_noise(language, &lines)),
+                };
+
+                match noise {
+                    Some(filter) => report.hunks.push(FilteredHunk {
+                        file: file.path.clone(),
+                        hunk: header.trim_end().to_string(),
+                        filter: filter.name().to_string(),
+                        lines: lines.changed(),
+                    }),
+                    None => {
+                        kept.push_str(header);
+                        kept.push_str(body);
+                        kept_hunks += 1;
+                    },
+                }
+            }
+
+            if kept_hunks == 0 && !file.hunks.is_empty() {
+                report.files.push(file.path.clone());
+                continue;
+            }
+            filtered.push_str(&file.header);
+            filtered.push_str(&kept);
+        }
+
+        if let Some(summary) = report.summary() {
+            tracing::info!("{}", summary);
+        }
+        if !filtered.contains("\n@@") && !filtered.starts_with("@@") && !report.hunks.is_empty() {
+            tracing::info!("Every hunk of the diff is noise; analyzing it unfiltered");
+            report.kept_whole = true;
+            return (diff.to_string(), report);
+        }
+        (filtered, report)
+    }
+}
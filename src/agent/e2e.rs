@@ -0,0 +1,310 @@
+// Repository context for runnable end-to-end tests (Playwright, Cypress)
+
+use regex::Regex;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use crate::agent::conventions;
+use crate::llm::postprocess::fenced_block;
+
+/// Most selectors listed in the prompt
+const MAX_SELECTORS: usize = 40;
+
+static BASE_URL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"baseU(?:RL|rl)\s*:\s*(?:[^,\n]*?\|\|\s*)?['"`]([^'"`]+)['"`]"#).unwrap()
+});
+static TEST_DIR: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"testDir\s*:\s*['"`]([^'"`]+)['"`]"#).unwrap());
+static SPEC_PATTERN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"specPattern\s*:\s*['"`]([^'"`]+)['"`]"#).unwrap());
+static TEST_ID_ATTRIBUTE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"testIdAttribute\s*:\s*['"`]([^'"`]+)['"`]"#).unwrap());
+static PORT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:--port[= ]|\s-p\s+)(\d{2,5})\b").unwrap());
+static ENV_URL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?m)^\s*(?:BASE_URL|APP_URL|PUBLIC_URL)\s*=\s*(https?://\S+)").unwrap());
+static SELECTOR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\b(data-testid|data-test-id|data-test|data-cy|data-qa|id|name|aria-label)\s*=\s*\{?\s*["'`]([^"'`{}$]+)["'`]"#).unwrap()
+});
+
+/// End-to-end test framework of a runnable test format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum E2eFramework {
+    /// Playwright Test (`@playwright/test`)
+    Playwright,
+    /// Cypress
+    Cypress,
+}
+
+impl E2eFramework {
+    /// Name of the framework
+    pub fn name(&self) -> &'static str {
+        match self {
+            E2eFramework::Playwright => "Playwright",
+            E2eFramework::Cypress => "Cypress",
+        }
+    }
+
+    /// Config file names, without the extension
+    fn config_stem(&self) -> &'static str {
+        match self {
+            E2eFramework::Playwright => "playwright.config",
+            E2eFramework::Cypress => "cypress.config",
+        }
+    }
+
+    /// Suffix before the extension the framework finds specs by
+    fn spec_suffix(&self) -> &'static str {
+        match self {
+            E2eFramework::Playwright => "spec",
+            E2eFramework::Cypress => "cy",
+        }
+    }
+
+    /// Attribute the framework's docs recommend for test IDs
+    fn default_test_id_attribute(&self) -> &'static str {
+        match self {
+            E2eFramework::Playwright => "data-testid",
+            E2eFramework::Cypress => "data-cy",
+        }
+    }
+}
+
+/// An element attribute the tests can select by, found in the source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Selector {
+    /// Attribute, e.g. `data-testid`
+    pub attribute: String,
+
+    /// Its value
+    pub value: String,
+}
+
+/// What a runnable spec needs to know about the project it runs in
+#[derive(Debug, Clone, Serialize)]
+pub struct E2eContext {
+    /// Framework the spec is written for
+    pub framework: E2eFramework,
+
+    /// Directory of the framework's config, or of the nearest `package.json`
+    pub root: PathBuf,
+
+    /// Config file found, if any
+    pub config_file: Option<PathBuf>,
+
+    /// Whether the project is written in TypeScript
+    pub typescript: bool,
+
+    /// URL the app is served at
+    pub base_url: Option<String>,
+
+    /// Whether the config sets the base URL, so specs can navigate with relative paths
+    pub configured_base_url: bool,
+
+    /// Directory the framework looks for specs in
+    pub spec_dir: PathBuf,
+
+    /// Attribute `getByTestId` (or the project's own convention) selects by
+    pub test_id_attribute: String,
+
+    /// Selectors found in the source file
+    pub selectors: Vec<Selector>,
+}
+
+impl E2eContext {
+    /// Infer the context of a spec for the source file at `source`
+    ///
+    /// The project is the nearest directory with a config for the framework or a
+    /// `package.json`, up to the repository root. Its config gives the base URL, spec directory
+    /// and test ID attribute; without one, the base URL comes from the dev server's port in
+    /// `package.json` or a `BASE_URL` in `.env`.
+    pub fn detect(framework: E2eFramework, source: &Path, code: &str) -> Self {
+        let start = source.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        let start = fs::canonicalize(start).unwrap_or_else(|_| start.to_path_buf());
+        let repo_root = conventions::project_root(&start);
+
+        let ancestors: Vec<&Path> = start.ancestors()
+            .take_while(|dir| dir.starts_with(&repo_root))
+            .collect();
+        let config_file = ancestors.iter().find_map(|dir| config_in(dir, framework));
+        let root = match &config_file {
+            Some(file) => file.parent().unwrap_or(&repo_root).to_path_buf(),
+            None => ancestors.iter()
+                .find(|dir| dir.join("package.json").exists())
+                .map(|dir| dir.to_path_buf())
+                .unwrap_or(repo_root),
+        };
+        let config = config_file.as_ref().and_then(|file| fs::read_to_string(file).ok()).unwrap_or_default();
+
+        let typescript = root.join("tsconfig.json").exists()
+            || config_file.as_ref().is_some_and(|file| file.extension().is_some_and(|ext| ext == "ts"))
+            || source.extension().is_some_and(|ext| ext == "ts" || ext == "tsx");
+
+        let configured = capture(&BASE_URL, &config);
+        let configured_base_url = configured.is_some();
+        let base_url = configured.or_else(|| dev_server_url(&root));
+
+        let spec_dir = match framework {
+            E2eFramework::Playwright => match capture(&TEST_DIR, &config) {
+                Some(dir) => root.join(dir.trim_start_matches("./")),
+                None if root.join("e2e").is_dir() => root.join("e2e"),
+                None => root.join("tests"),
+            },
+            E2eFramework::Cypress => match capture(&SPEC_PATTERN, &config) {
+                Some(pattern) => {
+                    let dir = pattern.split(['*', '{']).next().unwrap_or_default();
+                    let dir = dir.rsplit_once('/').map(|(dir, _)| dir).unwrap_or(dir);
+                    root.join(dir.trim_start_matches("./"))
+                },
+                None => root.join("cypress").join("e2e"),
+            },
+        };
+
+        let selectors = find_selectors(code);
+        let test_id_attribute = capture(&TEST_ID_ATTRIBUTE, &config)
+            .or_else(|| {
+                // Follow the attribute the code already uses when the config doesn't set one
+                selectors.iter()
+                    .map(|selector| selector.attribute.as_str())
+                    .find(|attribute| attribute.starts_with("data-"))
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| framework.default_test_id_attribute().to_string());
+
+        Self { framework, root, config_file, typescript, base_url, configured_base_url, spec_dir, test_id_attribute, selectors }
+    }
+
+    /// Extension of spec files: `ts` or `js`
+    pub fn extension(&self) -> &'static str {
+        if self.typescript { "ts" } else { "js" }
+    }
+
+    /// Path of the spec for a source file, e.g. `tests/login.spec.ts` or `cypress/e2e/login.cy.ts`
+    pub fn spec_path(&self, source: &Path, function: Option<&str>) -> PathBuf {
+        let stem = source.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        // `login.page.tsx` becomes `login.page`
+        let stem = stem.split_once('.').map(|(stem, rest)| match rest.rsplit_once('.') {
+            Some((middle, _)) => format!("{}.{}", stem, middle),
+            None => stem.to_string(),
+        }).unwrap_or(stem);
+        let name = match function {
+            Some(function) => format!("{}_{}", stem, function),
+            None => stem,
+        };
+        self.spec_dir.join(format!("{}.{}.{}", name, self.framework.spec_suffix(), self.extension()))
+    }
+
+    /// A selector as the framework's API
+    pub fn locator(&self, selector: &Selector) -> String {
+        let value = selector.value.replace('\'', "\\'");
+        match self.framework {
+            E2eFramework::Playwright if selector.attribute == self.test_id_attribute => format!("page.getByTestId('{}')", value),
+            E2eFramework::Playwright if selector.attribute == "aria-label" => format!("page.getByLabel('{}')", value),
+            E2eFramework::Playwright if selector.attribute == "id" => format!("page.locator('#{}')", value),
+            E2eFramework::Playwright => format!("page.locator('[{}=\"{}\"]')", selector.attribute, value),
+            E2eFramework::Cypress if selector.attribute == "id" => format!("cy.get('#{}')", value),
+            E2eFramework::Cypress => format!("cy.get('[{}=\"{}\"]')", selector.attribute, value),
+        }
+    }
+
+    /// Instructions for the spec: language, base URL and selectors
+    pub fn prompt(&self) -> String {
+        let language = if self.typescript { "TypeScript" } else { "JavaScript" };
+        let mut prompt = match self.framework {
+            E2eFramework::Playwright => format!(
+                "Write the tests as a runnable Playwright spec in {}, importing `test` and `expect` from `@playwright/test`.",
+                language
+            ),
+            E2eFramework::Cypress => format!(
+                "Write the tests as a runnable Cypress spec in {}, with `describe`/`it` blocks and the global `cy`.",
+                language
+            ),
+        };
+
+        match (&self.base_url, self.configured_base_url) {
+            (Some(_), true) => prompt.push_str(&format!(
+                " The base URL is set in the {} config, so navigate with relative paths, e.g. `{}`.",
+                self.framework.name(),
+                match self.framework {
+                    E2eFramework::Playwright => "await page.goto('/')",
+                    E2eFramework::Cypress => "cy.visit('/')",
+                }
+            )),
+            (Some(url), false) => prompt.push_str(&format!(
+                " The app is served at {}; use `process.env.BASE_URL` when set and that URL otherwise.",
+                url
+            )),
+            (None, _) => prompt.push_str(" No base URL is configured; read it from `process.env.BASE_URL`."),
+        }
+
+        if self.selectors.is_empty() {
+            prompt.push_str(&format!(
+                " The code has no test IDs; prefer accessible roles, labels and text over CSS structure, and `{}` attributes if you add any.",
+                self.test_id_attribute
+            ));
+        } else {
+            prompt.push_str("\n\nSelect elements with these selectors found in the code, in preference to text or CSS structure:\n");
+            for selector in &self.selectors {
+                prompt.push_str(&format!("- {}\n", self.locator(selector)));
+            }
+        }
+
+        prompt.push_str("\n\nOutput only the contents of the spec file.");
+        prompt
+    }
+}
+
+/// The framework's config file in `dir`, with any extension
+fn config_in(dir: &Path, framework: E2eFramework) -> Option<PathBuf> {
+    ["ts", "js", "mjs", "cjs", "mts"].iter()
+        .map(|ext| dir.join(format!("{}.{}", framework.config_stem(), ext)))
+        .find(|path| path.is_file())
+}
+
+/// First capture of `regex` in `text`
+fn capture(regex: &Regex, text: &str) -> Option<String> {
+    regex.captures(text).map(|captures| captures[1].to_string())
+}
+
+/// URL of the dev server: a `BASE_URL` in `.env`, or the port of the `dev`/`start` script
+fn dev_server_url(root: &Path) -> Option<String> {
+    for env in [".env.test", ".env.local", ".env"] {
+        if let Some(url) = fs::read_to_string(root.join(env)).ok().and_then(|content| capture(&ENV_URL, &content)) {
+            return Some(url);
+        }
+    }
+
+    let package: serde_json::Value = serde_json::from_str(&fs::read_to_string(root.join("package.json")).ok()?).ok()?;
+    let scripts = package.get("scripts")?;
+    let script = ["dev", "start", "serve"].iter()
+        .find_map(|name| scripts.get(name).and_then(|script| script.as_str()))?;
+
+    let port = match capture(&PORT, &format!(" {}", script)) {
+        Some(port) => port,
+        None if script.contains("vite") => "5173".to_string(),
+        None if script.contains("next") || script.contains("react-scripts") || script.contains("remix") => "3000".to_string(),
+        None if script.contains("ng serve") => "4200".to_string(),
+        None => return None,
+    };
+    Some(format!("http://localhost:{}", port))
+}
+
+/// Test IDs, IDs, names and labels of the elements in the code
+pub fn find_selectors(code: &str) -> Vec<Selector> {
+    let mut selectors: Vec<Selector> = Vec::new();
+    for captures in SELECTOR.captures_iter(code) {
+        let selector = Selector { attribute: captures[1].to_string(), value: captures[2].trim().to_string() };
+        if !selector.value.is_empty() && !selectors.contains(&selector) {
+            selectors.push(selector);
+        }
+    }
+
+    // Test IDs first, as the most stable selectors
+    selectors.sort_by_key(|selector| !selector.attribute.starts_with("data-"));
+    selectors.truncate(MAX_SELECTORS);
+    selectors
+}
+
+/// The code of a generated spec, without a surrounding Markdown fence or prose around it
+pub fn extract_code(text: &str) -> String {
+    let code = fenced_block(text).map(str::trim_end).unwrap_or_else(|| text.trim());
+    format!("{}\n", code)
+}
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+
+use crate::agent::diff::{DiffLineKind, FileDiff};
+use crate::severity::Severity;
+
+/// Marker for inline suppressions in source code, e.g. `// qitops-ignore: false positive`
+pub const SUPPRESSION_MARKER: &str = "qitops-ignore";
+
+/// Instructions appended to analysis prompts so findings can be extracted
+pub const FINDINGS_INSTRUCTIONS: &str = "After your analysis, list every concrete finding in a fenced ```json block of the form {\"findings\": [{\"file\": \"path/to/file\", \"line\": 42, \"severity\": \"info|low|medium|high|critical\", \"title\": \"short title\", \"description\": \"details\"}]}. Use the file paths and new-file line numbers from the diff; use null when a finding is not tied to a line. Use an empty list when there are no findings.";
+
+/// A finding reported by an analysis agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Finding {
+    /// File the finding refers to
+    #[serde(default)]
+    pub file: Option<String>,
+
+    /// Line in the new version of the file
+    #[serde(default)]
+    pub line: Option<u32>,
+
+    /// Severity
+    #[serde(default)]
+    pub severity: Severity,
+
+    /// Short title
+    pub title: String,
+
+    /// Details
+    #[serde(default)]
+    pub description: String,
+}
+
+impl Finding {
+    /// Stable fingerprint used to match findings across runs
+    ///
+    /// Line numbers are left out so findings survive unrelated edits that shift code.
+    pub fn fingerprint(&self) -> String {
+        let normalized_title = self.title
+            .to_lowercase()
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let key = format!("{}\n{}", self.file.as_deref().unwrap_or(""), normalized_title);
+
+        // FNV-1a, stable across platforms and Rust versions
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in key.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+
+        format!("{:016x}", hash)
+    }
+
+    /// Location as `file:line`, `file`, or an empty string
+    pub fn location(&self) -> String {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => format!("{}:{}", file, line),
+            (Some(file), None) => file.clone(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// Wrapper for the findings JSON block
+#[derive(Debug, Deserialize)]
+struct FindingsBlock {
+    /// Findings
+    #[serde(default)]
+    findings: Vec<Finding>,
+}
+
+/// Split an LLM response into the prose analysis and the structured findings
+pub fn extract_findings(text: &str) -> (String, Vec<Finding>) {
+    let Some(start) = text.rfind("```json") else {
+        return (text.to_string(), Vec::new());
+    };
+
+    let body_start = start + "```json".len();
+    let body_end = text[body_start..].find("```")
+        .map(|end| body_start + end)
+        .unwrap_or(text.len());
+
+    match serde_json::from_str::<FindingsBlock>(text[body_start..body_end].trim()) {
+        Ok(block) => {
+            let after = (body_end + 3).min(text.len());
+            let analysis = format!("{}{}", text[..start].trim_end(), text[after..].trim_end());
+            (analysis, block.findings)
+        },
+        Err(e) => {
+            tracing::warn!("Failed to parse findings block: {}", e);
+            (text.to_string(), Vec::new())
+        },
+    }
+}
+
+/// Find the inline suppression reason for a finding, if the flagged line or the line above carries the marker
+pub fn inline_suppression(finding: &Finding, files: &[FileDiff]) -> Option<String> {
+    let file = finding.file.as_deref()?;
+    let line = finding.line?;
+    let file_diff = files.iter().find(|f| f.path() == file)?;
+
+    [line, line.saturating_sub(1)].iter()
+        .filter_map(|n| file_diff.new_line(*n))
+        .filter(|l| l.kind != DiffLineKind::Removed)
+        .find_map(|l| {
+            let index = l.content.find(SUPPRESSION_MARKER)?;
+            let reason = l.content[index + SUPPRESSION_MARKER.len()..]
+                .trim_start_matches(':')
+                .trim()
+                .trim_end_matches("*/")
+                .trim();
+            Some(if reason.is_empty() { "suppressed inline".to_string() } else { reason.to_string() })
+        })
+}
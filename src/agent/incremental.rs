@@ -0,0 +1,117 @@
+// Incremental re-analysis of pull requests that were analyzed before
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::agent::findings::Finding;
+
+/// What the last analysis of a pull request found, kept to re-analyze only what changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriorAnalysis {
+    /// Head commit the analysis was of
+    pub head_sha: String,
+
+    /// Focus the analysis was made with; a different focus needs a full analysis
+    pub focus: String,
+
+    /// Prose analysis
+    pub analysis: String,
+
+    /// Findings before the baseline was applied
+    pub findings: Vec<Finding>,
+}
+
+/// On-disk store of the last analysis of each pull request
+pub struct AnalysisStore {
+    /// Store directory
+    dir: PathBuf,
+}
+
+impl AnalysisStore {
+    /// Open the store in the user's cache directory
+    pub fn new() -> Result<Self> {
+        let dir = dirs::cache_dir()
+            .ok_or_else(|| anyhow!("Could not determine cache directory"))?
+            .join("qitops")
+            .join("pr_analysis");
+
+        Ok(Self::at(dir))
+    }
+
+    /// Open the store in a directory
+    pub fn at(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Path of a pull request's entry
+    fn path(&self, owner: &str, repo: &str, number: u64) -> PathBuf {
+        self.dir.join(owner).join(repo).join(format!("{}.json", number))
+    }
+
+    /// Last analysis of a pull request
+    pub fn load(&self, owner: &str, repo: &str, number: u64) -> Option<PriorAnalysis> {
+        let content = fs::read_to_string(self.path(owner, repo, number)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Keep the analysis of a pull request for the next run
+    pub fn store(&self, owner: &str, repo: &str, number: u64, analysis: &PriorAnalysis) -> Result<()> {
+        let path = self.path(owner, repo, number);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| anyhow!("Failed to create PR analysis directory: {}", e))?;
+        }
+        fs::write(&path, serde_json::to_string(analysis)?)
+            .map_err(|e| anyhow!("Failed to write PR analysis {}: {}", path.display(), e))
+    }
+}
+
+/// What to re-analyze after new commits, and which earlier findings still hold
+#[derive(Debug, Clone)]
+pub struct Delta {
+    /// Files of the pull request changed since the last analysis
+    pub files: Vec<String>,
+
+    /// Findings of the last analysis on files that haven't changed since
+    pub carried_over: Vec<Finding>,
+}
+
+/// Work out the delta from the files changed since the last analysis
+///
+/// Only files that are still part of the pull request are re-analyzed, so changes merged in
+/// from the base branch are left out. Findings carry over for the pull request's other files;
+/// findings not tied to a file don't, since the new analysis covers the pull request as a whole.
+pub fn plan_delta(prior: &PriorAnalysis, changed_since: &[String], pr_files: &[String]) -> Delta {
+    let changed: HashSet<&str> = changed_since.iter().map(String::as_str).collect();
+    let files = pr_files.iter().filter(|file| changed.contains(file.as_str())).cloned().collect();
+
+    let carried_over = prior.findings.iter()
+        .filter(|finding| finding.file.as_deref().is_some_and(|file| {
+            !changed.contains(file) && pr_files.iter().any(|pr_file| pr_file == file)
+        }))
+        .cloned()
+        .collect();
+
+    Delta { files, carried_over }
+}
+
+/// The sections of a unified diff for some files
+pub fn diff_for_files(diff: &str, files: &[String]) -> String {
+    let mut sections = String::new();
+    let mut keep = false;
+
+    for line in diff.split_inclusive('\n') {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            let path = paths.trim_end().rsplit_once(" b/").map_or(paths.trim_end(), |(_, b)| b);
+            keep = files.iter().any(|file| file == path);
+        }
+        if keep {
+            sections.push_str(line);
+        }
+    }
+
+    sections
+}
@@ -0,0 +1,380 @@
+// Resource-level changes to infrastructure-as-code and configuration files
+
+use regex::Regex;
+use serde::Serialize;
+use std::sync::LazyLock;
+
+use crate::agent::diff::{DiffHunk, DiffLineKind, FileDiff, parse_diff};
+
+/// Kind of infrastructure or configuration file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InfraKind {
+    /// Terraform or other HCL configuration
+    Terraform,
+
+    /// Kubernetes manifest
+    Kubernetes,
+
+    /// Dockerfile or Containerfile
+    Dockerfile,
+
+    /// CI pipeline definition
+    Ci,
+}
+
+impl std::fmt::Display for InfraKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InfraKind::Terraform => write!(f, "Terraform"),
+            InfraKind::Kubernetes => write!(f, "Kubernetes"),
+            InfraKind::Dockerfile => write!(f, "Dockerfile"),
+            InfraKind::Ci => write!(f, "CI"),
+        }
+    }
+}
+
+/// What happened to a resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeAction {
+    /// The resource is new
+    Added,
+
+    /// The resource is gone
+    Removed,
+
+    /// Some of the resource's settings changed
+    Modified,
+}
+
+impl std::fmt::Display for ChangeAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChangeAction::Added => write!(f, "added"),
+            ChangeAction::Removed => write!(f, "removed"),
+            ChangeAction::Modified => write!(f, "modified"),
+        }
+    }
+}
+
+/// Infrastructure risk a change touches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskSignal {
+    /// Network exposure, access control or privileges
+    Exposure,
+
+    /// Capacity, resource limits and scaling
+    Quota,
+
+    /// Credentials and secret handling
+    Secrets,
+
+    /// How the change is rolled out and rolled back
+    Rollout,
+}
+
+impl std::fmt::Display for RiskSignal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RiskSignal::Exposure => write!(f, "exposure"),
+            RiskSignal::Quota => write!(f, "quota"),
+            RiskSignal::Secrets => write!(f, "secrets"),
+            RiskSignal::Rollout => write!(f, "rollout"),
+        }
+    }
+}
+
+/// A change to one resource of an infrastructure or configuration file
+#[derive(Debug, Clone, Serialize)]
+pub struct InfraChange {
+    /// File the resource is in
+    pub file: String,
+
+    /// Kind of file
+    pub kind: InfraKind,
+
+    /// Resource, e.g. `aws_security_group.web`, `Deployment/api`, a build stage or a CI job
+    pub resource: String,
+
+    /// What happened to the resource
+    pub action: ChangeAction,
+
+    /// Settings that changed, in the order they appear
+    pub attributes: Vec<String>,
+
+    /// Infrastructure risks the changed lines touch
+    pub signals: Vec<RiskSignal>,
+}
+
+impl InfraChange {
+    /// One-line description for prompts and reports
+    pub fn describe(&self) -> String {
+        let mut line = format!("{} `{}` in {} ({})", self.kind, self.resource, self.file, self.action);
+        if !self.attributes.is_empty() {
+            line.push_str(&format!(": {}", self.attributes.join(", ")));
+        }
+        if !self.signals.is_empty() {
+            let signals: Vec<String> = self.signals.iter().map(ToString::to_string).collect();
+            line.push_str(&format!(" [{}]", signals.join(", ")));
+        }
+        line
+    }
+}
+
+/// Criteria infrastructure changes are assessed against
+pub const INFRA_CRITERIA: &str = "Assess infrastructure and configuration changes against these criteria:\n- Exposure: ports, ingress and security group rules, public endpoints, IAM policies and privileged containers that widen who can reach or act on the system.\n- Quota: replicas, instance sizes, CPU and memory requests and limits, storage and autoscaling bounds that can exhaust capacity, raise cost or starve other workloads.\n- Secrets: credentials in plain text, build arguments or environment variables, and changes to how secrets are mounted, referenced or rotated.\n- Rollout strategy: whether the change can be applied without downtime and rolled back, including resource replacement, deletion protection, update strategies, health probes, base image tags and pipeline triggers or permissions.";
+
+static TERRAFORM_BLOCK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^\s*(resource|data|module|variable|output|provider)\s+"([^"]+)"(?:\s+"([^"]+)")?"#).unwrap()
+});
+
+static KUBERNETES_KIND: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^kind:\s*(\S+)").unwrap());
+
+static KUBERNETES_NAME: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^  name:\s*(\S+)").unwrap());
+
+static DOCKER_STAGE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^\s*FROM\s+(?:--\S+\s+)*(\S+)(?:\s+AS\s+(\S+))?").unwrap()
+});
+
+static DOCKER_INSTRUCTION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*([A-Za-z]+)\s").unwrap());
+
+static CONFIG_KEY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^\s*-?\s*"?([A-Za-z_][\w.-]*)"?\s*[:=]"#).unwrap()
+});
+
+static SIGNALS: LazyLock<Vec<(RiskSignal, Regex)>> = LazyLock::new(|| {
+    vec![
+        (RiskSignal::Exposure, Regex::new(r"(?i)(0\.0\.0\.0/0|::/0|\b(public\w*|ingress|egress|loadbalancer|nodeport|hostport|hostnetwork|expose|ports?|cidr_blocks|security_groups?|acl|firewall|privileged|allowprivilegeescalation|iam_\w+|policy|role)\b)").unwrap()),
+        (RiskSignal::Quota, Regex::new(r"(?i)\b(replicas|resources|limits|requests|cpu|memory|instance_type|instance_count|min_size|max_size|desired_capacity|node_count|minreplicas|maxreplicas|storage|allocated_storage|quota|concurrency|timeout-minutes|runs-on)\b").unwrap()),
+        (RiskSignal::Secrets, Regex::new(r"(?i)(secret|password|passwd|token|api_?key|private_key|credential|access_key|kms_key)").unwrap()),
+        (RiskSignal::Rollout, Regex::new(r"(?i)(^\s*from\s|:latest\b|\b(strategy|rollingupdate|maxunavailable|maxsurge|recreate|lifecycle|prevent_destroy|create_before_destroy|deletion_protection|force_destroy|skip_final_snapshot|readinessprobe|livenessprobe|image|needs|environment|permissions|branches|workflow_dispatch)\b)").unwrap()),
+    ]
+});
+
+/// Kind of infrastructure or configuration file a diffed file is, if any
+///
+/// YAML files are Kubernetes manifests when the diff shows `apiVersion` and `kind`.
+pub fn infra_kind(file: &FileDiff) -> Option<InfraKind> {
+    let path = file.path();
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let lower = name.to_lowercase();
+
+    if lower.ends_with(".tf") || lower.ends_with(".tfvars") || lower.ends_with(".hcl") {
+        return Some(InfraKind::Terraform);
+    }
+    if lower.starts_with("dockerfile") || lower.starts_with("containerfile") || lower.ends_with(".dockerfile") {
+        return Some(InfraKind::Dockerfile);
+    }
+    if path.contains(".github/workflows/") || path.contains(".circleci/")
+        || lower == ".gitlab-ci.yml" || lower == "azure-pipelines.yml" || lower == "bitbucket-pipelines.yml" || lower == "jenkinsfile"
+    {
+        return Some(InfraKind::Ci);
+    }
+    if lower.ends_with(".yaml") || lower.ends_with(".yml") {
+        let manifest = file.lines().any(|line| line.content.starts_with("apiVersion:"))
+            && file.lines().any(|line| line.content.starts_with("kind:"));
+        if manifest {
+            return Some(InfraKind::Kubernetes);
+        }
+    }
+
+    None
+}
+
+/// Tracks which resource the lines of a file belong to
+#[derive(Default)]
+struct ResourceTracker {
+    /// Current resource
+    resource: Option<String>,
+
+    /// Whether the current CI definition has a `jobs:` section
+    in_jobs: bool,
+}
+
+impl ResourceTracker {
+    /// Update the current resource from a line; returns true if the line starts a resource
+    fn observe(&mut self, kind: InfraKind, content: &str) -> bool {
+        match kind {
+            InfraKind::Terraform => match TERRAFORM_BLOCK.captures(content) {
+                Some(captures) => {
+                    let block = &captures[1];
+                    let resource = match (block, captures.get(3)) {
+                        ("resource", Some(name)) => format!("{}.{}", &captures[2], name.as_str()),
+                        ("data", Some(name)) => format!("data.{}.{}", &captures[2], name.as_str()),
+                        ("variable", _) => format!("var.{}", &captures[2]),
+                        (_, _) => format!("{}.{}", block, &captures[2]),
+                    };
+                    self.resource = Some(resource);
+                    true
+                },
+                None => false,
+            },
+            // Manifests name their resources after the fields, see `kubernetes_resources`
+            InfraKind::Kubernetes => KUBERNETES_KIND.is_match(content),
+            InfraKind::Dockerfile => match DOCKER_STAGE.captures(content) {
+                Some(captures) => {
+                    let stage = captures.get(2).map_or(&captures[1], |name| name.as_str());
+                    self.resource = Some(format!("stage {}", stage));
+                    true
+                },
+                None => false,
+            },
+            InfraKind::Ci => {
+                let indent = content.len() - content.trim_start().len();
+                let key = content.trim().strip_suffix(':').filter(|key| !key.contains(' ') && !key.is_empty());
+                match key {
+                    Some("jobs") if indent == 0 => {
+                        self.in_jobs = true;
+                        false
+                    },
+                    // Jobs are under `jobs:` in GitHub workflows and top-level keys elsewhere
+                    Some(job) if (self.in_jobs && indent == 2) || (!self.in_jobs && indent == 0) => {
+                        self.resource = Some(format!("job {}", job));
+                        true
+                    },
+                    _ => false,
+                }
+            },
+        }
+    }
+}
+
+/// `Kind/name` of the manifest each line of a hunk belongs to
+///
+/// The kind and name can come after the changed lines of a document, so each document of
+/// the hunk is read whole first.
+fn kubernetes_resources(hunk: &DiffHunk) -> Vec<Option<String>> {
+    let mut resources = Vec::with_capacity(hunk.lines.len());
+    for document in hunk.lines.split_inclusive(|line| line.content.starts_with("---")) {
+        let kind = document.iter().find_map(|line| KUBERNETES_KIND.captures(&line.content)).map(|captures| captures[1].to_string());
+        let name = document.iter().find_map(|line| KUBERNETES_NAME.captures(&line.content)).map(|captures| captures[1].to_string());
+        let resource = match (kind, name) {
+            (Some(kind), Some(name)) => Some(format!("{}/{}", kind, name)),
+            (Some(kind), None) => Some(kind),
+            (None, Some(name)) => Some(format!("resource/{}", name)),
+            (None, None) => None,
+        };
+        resources.extend(std::iter::repeat_n(resource, document.len()));
+    }
+    resources
+}
+
+/// Setting a changed line sets
+fn attribute(kind: InfraKind, content: &str) -> Option<String> {
+    if content.trim_start().starts_with('#') {
+        return None;
+    }
+
+    let captures = match kind {
+        InfraKind::Dockerfile => DOCKER_INSTRUCTION.captures(content),
+        _ => CONFIG_KEY.captures(content),
+    }?;
+    let attribute = captures[1].to_string();
+    Some(if kind == InfraKind::Dockerfile { attribute.to_uppercase() } else { attribute })
+}
+
+/// Resource-level changes to the infrastructure and configuration files of a diff
+///
+/// Parsed from the diff alone: lines before the first resource header of a hunk are
+/// attributed to the resource named in the hunk header, if any, or to the whole file.
+pub fn infra_changes(diff: &str) -> Vec<InfraChange> {
+    let mut changes = Vec::new();
+
+    for file in parse_diff(diff) {
+        let Some(kind) = infra_kind(&file) else { continue };
+        let path = file.path().to_string();
+        let file_action = match (&file.old_path, &file.new_path) {
+            (None, Some(_)) => Some(ChangeAction::Added),
+            (Some(_), None) => Some(ChangeAction::Removed),
+            _ => None,
+        };
+
+        let mut file_changes: Vec<InfraChange> = Vec::new();
+        let mut tracker = ResourceTracker::default();
+        for hunk in &file.hunks {
+            // Git names the enclosing block after the hunk range, e.g. `@@ -3,4 +3,5 @@ resource "aws_s3_bucket" "logs" {`
+            if let Some(context) = hunk.header.splitn(3, "@@").nth(2) {
+                tracker.observe(kind, context.trim_start());
+            }
+
+            let manifests = match kind {
+                InfraKind::Kubernetes => kubernetes_resources(hunk),
+                _ => Vec::new(),
+            };
+
+            for (index, line) in hunk.lines.iter().enumerate() {
+                let header = tracker.observe(kind, &line.content);
+                if kind == InfraKind::Kubernetes {
+                    tracker.resource = manifests[index].clone();
+                }
+                if line.kind == DiffLineKind::Context {
+                    continue;
+                }
+
+                let resource = tracker.resource.clone().unwrap_or_else(|| match kind {
+                    InfraKind::Dockerfile => "image".to_string(),
+                    InfraKind::Ci => "pipeline".to_string(),
+                    _ => "file".to_string(),
+                });
+                let index = match file_changes.iter().position(|change| change.resource == resource) {
+                    Some(index) => index,
+                    None => {
+                        file_changes.push(InfraChange {
+                            file: path.clone(),
+                            kind,
+                            resource,
+                            action: file_action.unwrap_or(ChangeAction::Modified),
+                            attributes: Vec::new(),
+                            signals: Vec::new(),
+                        });
+                        file_changes.len() - 1
+                    },
+                };
+                let change = &mut file_changes[index];
+
+                if header && file_action.is_none() {
+                    change.action = match (change.action, line.kind) {
+                        (ChangeAction::Modified, DiffLineKind::Added) if change.attributes.is_empty() => ChangeAction::Added,
+                        (ChangeAction::Modified, DiffLineKind::Removed) if change.attributes.is_empty() => ChangeAction::Removed,
+                        // A header both removed and added is a rename or an edit of the header
+                        (ChangeAction::Added, DiffLineKind::Removed) | (ChangeAction::Removed, DiffLineKind::Added) => ChangeAction::Modified,
+                        (action, _) => action,
+                    };
+                }
+                if let Some(attribute) = attribute(kind, &line.content)
+                    && !change.attributes.contains(&attribute)
+                {
+                    change.attributes.push(attribute);
+                }
+                for (signal, pattern) in SIGNALS.iter() {
+                    if !change.signals.contains(signal) && pattern.is_match(&line.content) {
+                        change.signals.push(*signal);
+                    }
+                }
+            }
+        }
+
+        for change in &mut file_changes {
+            change.signals.sort();
+        }
+        changes.extend(file_changes);
+    }
+
+    changes
+}
+
+/// Prompt section listing infrastructure changes with the criteria to assess them against
+pub fn infra_prompt(changes: &[InfraChange]) -> String {
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    let list: Vec<String> = changes.iter().map(|change| format!("- {}", change.describe())).collect();
+    format!(
+        "The diff changes infrastructure or configuration. These resource-level changes were parsed from it:\n{}\n\n{}",
+        list.join("\n"),
+        INFRA_CRITERIA
+    )
+}
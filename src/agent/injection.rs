@@ -0,0 +1,79 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::agent::diff::{DiffLineKind, parse_diff};
+use crate::agent::findings::Finding;
+use crate::severity::Severity;
+
+/// Title of prompt injection findings
+pub const INJECTION_FINDING_TITLE: &str = "Possible prompt injection";
+
+/// Longest excerpt of the matching text quoted in a finding
+const MAX_EXCERPT_CHARS: usize = 160;
+
+/// Patterns of text addressed to an AI reviewer rather than to people, with what each one tries to do
+fn patterns() -> &'static [(Regex, &'static str)] {
+    static PATTERNS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            (r"(?i)\b(ignore|disregard|forget|override)\s+(all\s+|any\s+)?(of\s+)?(the\s+|your\s+)?(previous|prior|above|earlier|preceding|system)\s+(instructions|prompts?|directions|rules|context)", "asks the model to drop its instructions"),
+            (r"(?i)\b(reveal|print|show|repeat|output)\s+(your|the)\s+(system\s+prompt|instructions|hidden\s+prompt)", "asks the model to reveal its prompt"),
+            (r"(?i)\b(do\s+not|don't|never)\s+(report|flag|mention|include)\b.{0,40}\b(findings?|issues?|vulnerabilit\w*|problems?|risks?)", "asks the model to hide findings"),
+            (r"(?i)\b(return|report|output|respond\s+with)\s+(an\s+empty\s+list\s+of\s+|no\s+|zero\s+)(findings|issues|risks)", "asks the model to hide findings"),
+            (r"(?i)\b(rate|mark|classify|assess)\s+(this|the)\s+(change|pr|pull\s+request|diff)\s+as\s+(safe|low[\s-]risk)", "dictates the review outcome"),
+            (r"(?i)\b(new|updated)\s+(system\s+)?instructions\s*:", "introduces new instructions"),
+            (r"(?i)<\|im_(start|end)\|>|<\|(system|assistant|user)\|>|\[/?INST\]|<</?SYS>>|</?untrusted-data", "contains chat or prompt delimiters"),
+        ]
+        .into_iter()
+        .map(|(pattern, reason)| (Regex::new(pattern).expect("valid injection pattern"), reason))
+        .collect()
+    })
+}
+
+/// What a line of text tries to make the model do, if it looks like a prompt injection attempt
+pub fn injection_reason(text: &str) -> Option<&'static str> {
+    patterns().iter()
+        .find(|(pattern, _)| pattern.is_match(text))
+        .map(|(_, reason)| *reason)
+}
+
+/// Build a finding for a line flagged as a likely injection attempt
+fn injection_finding(file: Option<String>, line: Option<u32>, text: &str, reason: &str, place: &str) -> Finding {
+    let mut excerpt: String = text.trim().chars().take(MAX_EXCERPT_CHARS).collect();
+    if excerpt.len() < text.trim().len() {
+        excerpt.push('…');
+    }
+
+    Finding {
+        file,
+        line,
+        severity: Severity::High,
+        title: INJECTION_FINDING_TITLE.to_string(),
+        description: format!(
+            "Text in {} {}: \"{}\". It was passed to the model as data, but check the change by hand.",
+            place, reason, excerpt
+        ),
+    }
+}
+
+/// Flag likely prompt injection attempts in a change: lines added by the diff and the PR title and description
+pub fn detect_injection(diff: &str, title: Option<&str>, description: Option<&str>) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for (place, text) in [("the pull request title", title), ("the pull request description", description)] {
+        let Some(text) = text else { continue };
+        if let Some((line, reason)) = text.lines().find_map(|line| injection_reason(line).map(|reason| (line, reason))) {
+            findings.push(injection_finding(None, None, line, reason, place));
+        }
+    }
+
+    for file in parse_diff(diff) {
+        for line in file.lines().filter(|line| line.kind == DiffLineKind::Added) {
+            if let Some(reason) = injection_reason(&line.content) {
+                findings.push(injection_finding(Some(file.path().to_string()), line.new_line, &line.content, reason, "the added line"));
+            }
+        }
+    }
+
+    findings
+}
@@ -0,0 +1,103 @@
+// Pull requests too large for one prompt: each file is analyzed on its own, then the analyses are combined
+
+use serde::{Deserialize, Serialize};
+
+/// How pull requests too large for one prompt are analyzed (the `large_pr` section of the config)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LargePrConfig {
+    /// Most files analyzed one by one; files with the fewest changed lines beyond it are skipped
+    pub max_files: usize,
+}
+
+impl Default for LargePrConfig {
+    fn default() -> Self {
+        Self { max_files: 50 }
+    }
+}
+
+/// One file's section of a unified diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSection {
+    /// Path of the file after the change
+    pub path: String,
+
+    /// The file's section of the diff, from its `diff --git` line
+    pub diff: String,
+}
+
+impl FileSection {
+    /// Added and removed lines
+    pub fn changed_lines(&self) -> usize {
+        self.diff.lines()
+            .filter(|line| (line.starts_with('+') && !line.starts_with("+++")) || (line.starts_with('-') && !line.starts_with("---")))
+            .count()
+    }
+}
+
+/// Analysis of one file of a large pull request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileAnalysis {
+    /// File analyzed
+    pub path: String,
+
+    /// What changed in the file and what could go wrong, as the model summarized it
+    pub summary: String,
+
+    /// Findings on the file
+    pub findings: usize,
+}
+
+/// Split a unified diff into its files, in diff order
+pub fn split_by_file(diff: &str) -> Vec<FileSection> {
+    let mut sections: Vec<FileSection> = Vec::new();
+
+    for line in diff.split_inclusive('\n') {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            let path = paths.trim_end().rsplit_once(" b/").map_or(paths.trim_end(), |(_, b)| b);
+            sections.push(FileSection { path: path.to_string(), diff: String::new() });
+        }
+        if let Some(section) = sections.last_mut() {
+            section.diff.push_str(line);
+        }
+    }
+
+    sections
+}
+
+/// Keep the `max_files` files with the most changed lines, in diff order, returning the paths of the others
+pub fn select_files(sections: Vec<FileSection>, max_files: usize) -> (Vec<FileSection>, Vec<String>) {
+    let mut by_size: Vec<usize> = (0..sections.len()).collect();
+    by_size.sort_by_key(|index| std::cmp::Reverse(sections[*index].changed_lines()));
+    let kept: Vec<usize> = by_size.into_iter().take(max_files.max(1)).collect();
+
+    let mut selected = Vec::new();
+    let mut skipped = Vec::new();
+    for (index, section) in sections.into_iter().enumerate() {
+        if kept.contains(&index) {
+            selected.push(section);
+        } else {
+            skipped.push(section.path);
+        }
+    }
+
+    (selected, skipped)
+}
+
+/// The per-file summaries of a large pull request as Markdown, to follow its combined review
+pub fn file_summaries(analyses: &[FileAnalysis], skipped: &[String]) -> String {
+    let mut markdown = String::from("## File Summaries\n");
+    for analysis in analyses {
+        markdown.push_str(&format!("\n### `{}`\n\n{}\n", analysis.path, analysis.summary.trim()));
+    }
+
+    if !skipped.is_empty() {
+        let paths: Vec<String> = skipped.iter().map(|path| format!("`{}`", path)).collect();
+        markdown.push_str(&format!(
+            "\n{} file(s) with the fewest changes were not analyzed (`large_pr.max_files`): {}\n",
+            skipped.len(), paths.join(", "),
+        ));
+    }
+
+    markdown
+}
@@ -1,9 +1,32 @@
 // Agent trait system
 pub mod traits;
 pub mod test_gen;
+pub mod conventions;
+pub mod changes;
+pub mod e2e;
 pub mod pr_analyze;
+pub mod ownership;
 pub mod risk;
+pub mod risk_score;
 pub mod test_data;
+pub mod test_review;
+pub mod perf_gen;
+pub mod session;
+#[cfg(feature = "pairing")]
+pub mod pairing;
+pub mod diff;
+pub mod diff_filter;
+pub mod findings;
+pub mod baseline;
+pub mod injection;
+pub mod patch;
+pub mod sources;
+#[cfg(feature = "github")]
+pub mod incremental;
+pub mod large_pr;
+pub mod infra;
+#[cfg(feature = "github")]
+pub mod release_notes;
 
 // Re-export commonly used types
 pub use traits::{Agent, AgentResponse, AgentStatus};
@@ -11,3 +34,8 @@ pub use test_gen::TestGenAgent;
 pub use pr_analyze::PrAnalyzeAgent;
 pub use risk::RiskAgent;
 pub use test_data::TestDataAgent;
+pub use test_review::TestReviewAgent;
+pub use perf_gen::PerfGenAgent;
+pub use session::SessionAgent;
+#[cfg(feature = "github")]
+pub use release_notes::ReleaseNotesQaAgent;
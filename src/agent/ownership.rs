@@ -0,0 +1,350 @@
+// Ownership of changed code: CODEOWNERS, git blame and churn, for risk assessment and reviewers
+
+use anyhow::{Result, anyhow};
+use globset::{GlobBuilder, GlobMatcher};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::git::{describe_age, git};
+
+/// Where CODEOWNERS files are looked for, in GitHub's order
+const CODEOWNERS_FILES: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// Days of history that count towards churn
+pub const CHURN_DAYS: u64 = 90;
+
+/// Commits within the churn window that make a file a hotspot
+pub const HOTSPOT_COMMITS: usize = 5;
+
+/// Most changed files blamed; the rest only get their CODEOWNERS owners
+const MAX_BLAMED_FILES: usize = 30;
+
+/// Most commits of a file read for its churn
+const MAX_LOG_COMMITS: usize = 200;
+
+/// Most reviewers suggested
+const MAX_REVIEWERS: usize = 5;
+
+/// Authors listed per file
+const MAX_AUTHORS: usize = 3;
+
+const DAY: u64 = 86_400;
+
+/// A CODEOWNERS rule
+#[derive(Debug, Clone)]
+struct OwnerRule {
+    /// Compiled pattern
+    matcher: GlobMatcher,
+
+    /// Owners (`@user`, `@org/team` or an email), empty to unassign
+    owners: Vec<String>,
+}
+
+/// A parsed CODEOWNERS file
+#[derive(Debug, Clone, Default)]
+pub struct Codeowners {
+    /// Rules in file order; the last matching one wins
+    rules: Vec<OwnerRule>,
+}
+
+impl Codeowners {
+    /// Parse a CODEOWNERS file, skipping patterns that can't be compiled
+    pub fn parse(content: &str) -> Self {
+        let rules = content.lines()
+            .map(|line| line.split_once(" #").map_or(line, |(rule, _)| rule).trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let pattern = fields.next()?;
+                match owner_matcher(pattern) {
+                    Ok(matcher) => Some(OwnerRule { matcher, owners: fields.map(str::to_string).collect() }),
+                    Err(e) => {
+                        tracing::debug!("Skipping CODEOWNERS pattern {}: {}", pattern, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        Self { rules }
+    }
+
+    /// Load the repository's CODEOWNERS file, if it has one
+    pub fn load(root: &Path) -> Option<Self> {
+        CODEOWNERS_FILES.iter()
+            .find_map(|file| std::fs::read_to_string(root.join(file)).ok())
+            .map(|content| Self::parse(&content))
+    }
+
+    /// Owners of a path, from the last rule that matches it
+    pub fn owners(&self, path: &str) -> &[String] {
+        let path = path.trim_start_matches("./");
+        self.rules.iter().rev()
+            .find(|rule| rule.matcher.is_match(path))
+            .map_or(&[], |rule| &rule.owners)
+    }
+}
+
+/// Compile a CODEOWNERS pattern, which follows gitignore rules
+///
+/// A pattern with a slash before its end is anchored at the repository root, one without
+/// matches at any depth. A pattern without wildcards in its last part also matches everything
+/// under a directory of that name; with them (`docs/*`) it doesn't match nested files.
+fn owner_matcher(pattern: &str) -> Result<GlobMatcher> {
+    let directory = pattern.ends_with('/');
+    let trimmed = pattern.trim_end_matches('/');
+    let glob = match trimmed.strip_prefix('/') {
+        Some(anchored) => anchored.to_string(),
+        None if trimmed.contains('/') => trimmed.to_string(),
+        None => format!("**/{}", trimmed),
+    };
+    let glob = match (glob.as_str(), directory) {
+        ("" | "**/*" | "**/", _) => "**".to_string(),
+        (_, true) => format!("{}/**", glob),
+        (_, false) if glob.rsplit('/').next().is_some_and(|last| last.contains(['*', '?', '['])) => glob,
+        (_, false) => format!("{{{},{}/**}}", glob, glob),
+    };
+
+    GlobBuilder::new(&glob)
+        .literal_separator(true)
+        .build()
+        .map(|glob| glob.compile_matcher())
+        .map_err(|e| anyhow!("{}", e))
+}
+
+/// An author's share of a file's lines
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuthorShare {
+    /// Author name, as in git
+    pub name: String,
+
+    /// Lines last changed by the author
+    pub lines: usize,
+
+    /// Percentage of the file's lines
+    pub percent: u32,
+}
+
+/// Ownership and history of a changed file
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FileOwnership {
+    /// Path in the repository
+    pub path: String,
+
+    /// Owners from CODEOWNERS
+    pub owners: Vec<String>,
+
+    /// Authors of most of the file's lines, by git blame
+    pub authors: Vec<AuthorShare>,
+
+    /// When the file was last committed, in seconds since the Unix epoch
+    pub last_changed: Option<u64>,
+
+    /// Author of its last commit
+    pub last_author: Option<String>,
+
+    /// Commits to it within the churn window
+    pub recent_commits: usize,
+}
+
+impl FileOwnership {
+    /// Whether the file changes often enough to be a churn hotspot
+    pub fn is_hotspot(&self) -> bool {
+        self.recent_commits >= HOTSPOT_COMMITS
+    }
+
+    /// One line for the prompt and report, e.g. `src/pay.rs: owners @payments; mostly written by Ann (80%); last changed 3 days ago by Ann; 7 commits in 90 days (hotspot)`
+    pub fn describe(&self, now: u64) -> String {
+        let mut parts = vec![];
+        if !self.owners.is_empty() {
+            parts.push(format!("owners {}", self.owners.join(" ")));
+        }
+        if !self.authors.is_empty() {
+            let authors: Vec<String> = self.authors.iter().map(|author| format!("{} ({}%)", author.name, author.percent)).collect();
+            parts.push(format!("mostly written by {}", authors.join(", ")));
+        }
+        match (self.last_changed, &self.last_author) {
+            (Some(time), Some(author)) => parts.push(format!("last changed {} by {}", describe_age(now.saturating_sub(time)), author)),
+            (Some(time), None) => parts.push(format!("last changed {}", describe_age(now.saturating_sub(time)))),
+            _ => parts.push("new or uncommitted".to_string()),
+        }
+        if self.recent_commits > 0 {
+            parts.push(format!(
+                "{} commit(s) in {} days{}",
+                self.recent_commits, CHURN_DAYS, if self.is_hotspot() { " (hotspot)" } else { "" },
+            ));
+        }
+
+        format!("{}: {}", self.path, parts.join("; "))
+    }
+}
+
+/// A suggested reviewer and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Reviewer {
+    /// CODEOWNERS owner or git author
+    pub name: String,
+
+    /// Changed files they own or wrote most of
+    pub files: Vec<String>,
+
+    /// Whether they are a CODEOWNERS owner of any of those files
+    pub owner: bool,
+}
+
+/// Ownership of the files a change touches
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Ownership {
+    /// Each changed file
+    pub files: Vec<FileOwnership>,
+
+    /// Suggested reviewers, best first
+    pub reviewers: Vec<Reviewer>,
+}
+
+impl Ownership {
+    /// Collect ownership of changed files in the repository containing `dir`
+    ///
+    /// Returns nothing outside a git repository. `author` is the change's author, who isn't
+    /// suggested as a reviewer.
+    pub fn collect(dir: &Path, files: &[String], author: Option<&str>, now: u64) -> Option<Self> {
+        let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+        let root = PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"]).ok()?.trim());
+        let codeowners = Codeowners::load(&root).unwrap_or_default();
+
+        let files = files.iter().enumerate().map(|(index, path)| {
+            let path = path.trim_start_matches("./").to_string();
+            let mut file = FileOwnership { owners: codeowners.owners(&path).to_vec(), ..FileOwnership::default() };
+            if index < MAX_BLAMED_FILES {
+                file.authors = blame(&root, &path);
+                let commits = log(&root, &path);
+                file.last_changed = commits.first().map(|(time, _)| *time);
+                file.last_author = commits.first().map(|(_, author)| author.clone());
+                file.recent_commits = commits.iter().filter(|(time, _)| *time + CHURN_DAYS * DAY >= now).count();
+            }
+            file.path = path;
+            file
+        }).collect::<Vec<_>>();
+
+        let reviewers = suggest_reviewers(&files, author);
+        Some(Self { files, reviewers })
+    }
+
+    /// Files changed often within the churn window
+    pub fn hotspots(&self) -> impl Iterator<Item = &FileOwnership> {
+        self.files.iter().filter(|file| file.is_hotspot())
+    }
+
+    /// Ownership for the risk prompt, empty when nothing is known
+    pub fn prompt(&self, now: u64) -> String {
+        if self.files.is_empty() {
+            return String::new();
+        }
+
+        let files: Vec<String> = self.files.iter().map(|file| format!("- {}", file.describe(now))).collect();
+        format!(
+            "Ownership and history of the changed files, from CODEOWNERS and git:\n{}\n\nWeigh these in the assessment: code changed by someone other than its owners or usual authors, code that hasn't changed in a long time, and hotspots with {} or more commits in {} days are more likely to regress.",
+            files.join("\n"), HOTSPOT_COMMITS, CHURN_DAYS,
+        )
+    }
+
+    /// Report section with the suggested reviewers and hotspots
+    pub fn to_markdown(&self, now: u64) -> String {
+        let mut markdown = "## Ownership\n\n".to_string();
+        if !self.reviewers.is_empty() {
+            let reviewers: Vec<String> = self.reviewers.iter()
+                .map(|reviewer| format!("{} ({})", reviewer.name, reviewer.files.join(", ")))
+                .collect();
+            markdown.push_str(&format!("**Suggested reviewers:** {}\n\n", reviewers.join("; ")));
+        }
+        let hotspots: Vec<String> = self.hotspots()
+            .map(|file| format!("{} ({} commits in {} days)", file.path, file.recent_commits, CHURN_DAYS))
+            .collect();
+        if !hotspots.is_empty() {
+            markdown.push_str(&format!("**Churn hotspots:** {}\n\n", hotspots.join(", ")));
+        }
+        for file in &self.files {
+            markdown.push_str(&format!("- {}\n", file.describe(now)));
+        }
+        markdown
+    }
+}
+
+/// Reviewers for changed files, excluding the change's author
+///
+/// CODEOWNERS owners come first, then the authors of most of each file, each ranked by how
+/// many of the changed files they cover.
+pub fn suggest_reviewers(files: &[FileOwnership], author: Option<&str>) -> Vec<Reviewer> {
+    let is_author = |name: &str| author.is_some_and(|author| {
+        let author = author.trim_start_matches('@');
+        name.trim_start_matches('@').eq_ignore_ascii_case(author)
+    });
+
+    let mut reviewers: Vec<Reviewer> = Vec::new();
+    let mut add = |name: &str, path: &str, owner: bool| {
+        if is_author(name) {
+            return;
+        }
+        match reviewers.iter_mut().find(|reviewer| reviewer.name == name) {
+            Some(reviewer) => {
+                if !reviewer.files.iter().any(|file| file == path) {
+                    reviewer.files.push(path.to_string());
+                }
+                reviewer.owner |= owner;
+            }
+            None => reviewers.push(Reviewer { name: name.to_string(), files: vec![path.to_string()], owner }),
+        }
+    };
+    for file in files {
+        for owner in &file.owners {
+            add(owner, &file.path, true);
+        }
+        // The main author, unless the file has owners
+        if let (true, Some(top)) = (file.owners.is_empty(), file.authors.first()) {
+            add(&top.name, &file.path, false);
+        }
+    }
+
+    // Stable, so ties keep the order of the files
+    reviewers.sort_by(|a, b| b.owner.cmp(&a.owner).then_with(|| b.files.len().cmp(&a.files.len())));
+    reviewers.truncate(MAX_REVIEWERS);
+    reviewers
+}
+
+/// Authors of most of a file's lines at `HEAD`, biggest share first
+fn blame(root: &Path, path: &str) -> Vec<AuthorShare> {
+    let Ok(output) = git(root, &["blame", "--line-porcelain", "-w", "HEAD", "--", path]) else {
+        return Vec::new();
+    };
+
+    let mut lines: HashMap<&str, usize> = HashMap::new();
+    for name in output.lines().filter_map(|line| line.strip_prefix("author ")) {
+        *lines.entry(name).or_default() += 1;
+    }
+    let total: usize = lines.values().sum();
+
+    let mut authors: Vec<AuthorShare> = lines.into_iter()
+        .filter(|(name, _)| *name != "Not Committed Yet")
+        .map(|(name, count)| AuthorShare {
+            name: name.to_string(),
+            lines: count,
+            percent: (count * 100 / total.max(1)) as u32,
+        })
+        .collect();
+    authors.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.name.cmp(&b.name)));
+    authors.truncate(MAX_AUTHORS);
+    authors
+}
+
+/// Commit times and authors of a file, newest first
+fn log(root: &Path, path: &str) -> Vec<(u64, String)> {
+    let limit = format!("-n{}", MAX_LOG_COMMITS);
+    git(root, &["log", &limit, "--format=%ct%x09%an", "--", path])
+        .map(|output| output.lines()
+            .filter_map(|line| line.split_once('\t'))
+            .filter_map(|(time, author)| Some((time.parse().ok()?, author.to_string())))
+            .collect())
+        .unwrap_or_default()
+}
+
@@ -0,0 +1,243 @@
+use aes_gcm::aead::OsRng;
+use aes_gcm::aead::rand_core::RngCore;
+use anyhow::{Result, Context, anyhow};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, mpsc};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+
+use crate::cli::branding;
+
+/// Event exchanged between the session host and joined participants
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PairEvent {
+    /// Input from a joined participant (participant to host), attributed by the host to the name they joined with
+    Input {
+        /// Message text
+        text: String,
+    },
+
+    /// A transcript message (host to participants)
+    Message {
+        /// Message author
+        author: String,
+        /// Message text
+        text: String,
+    },
+
+    /// The host ended the session
+    End,
+}
+
+/// Host side of a paired session
+pub struct PairHost {
+    /// Outgoing events to all participants
+    events: broadcast::Sender<PairEvent>,
+
+    /// Incoming input from participants as (author, text)
+    inputs: mpsc::Receiver<(String, String)>,
+
+    /// URL participants join with, including the join token
+    join_url: String,
+}
+
+impl PairHost {
+    /// Start a WebSocket server on the given address, accepting only participants who present the join token
+    #[allow(clippy::result_large_err)] // tungstenite's handshake callback returns its error response by value
+    pub async fn start(addr: &str, host: &str) -> Result<Self> {
+        crate::offline::ensure_local_url("Hosting a session for other machines", &format!("ws://{}", addr))?;
+        let listener = TcpListener::bind(addr).await
+            .context(format!("Failed to bind session host to {}", addr))?;
+        let (events, _) = broadcast::channel(256);
+        let (input_tx, inputs) = mpsc::channel(64);
+
+        let mut bytes = [0u8; 16];
+        OsRng.fill_bytes(&mut bytes);
+        let token = crate::hash::hex(&bytes);
+        let join_url = format!("ws://{}/?token={}", listener.local_addr()?, token);
+        let reserved = vec!["QitOps".to_string(), host.to_string()];
+
+        let accept_events = events.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, peer)) = listener.accept().await {
+                let mut participant = None;
+                let handshake = tokio_tungstenite::accept_hdr_async(stream, |request: &Request, response: Response| {
+                    let name = admit(request, &token, &reserved).map_err(|(status, reason)| refuse(status, &reason))?;
+                    participant = Some(name);
+                    Ok(response)
+                }).await;
+                let (websocket, author) = match (handshake, participant) {
+                    (Ok(websocket), Some(author)) => (websocket, author),
+                    (Err(e), _) => {
+                        tracing::warn!("Rejected session participant {}: {}", peer, e);
+                        continue;
+                    },
+                    (Ok(_), None) => continue,
+                };
+
+                branding::print_info(&format!("{} joined from {}", author, peer));
+                let (mut sink, mut stream) = websocket.split();
+
+                // Forward session events to the participant
+                let mut events = accept_events.subscribe();
+                tokio::spawn(async move {
+                    while let Ok(event) = events.recv().await {
+                        let end = matches!(event, PairEvent::End);
+                        let text = match serde_json::to_string(&event) {
+                            Ok(text) => text,
+                            Err(_) => continue,
+                        };
+                        if sink.send(Message::Text(text)).await.is_err() || end {
+                            break;
+                        }
+                    }
+                    let _ = sink.close().await;
+                });
+
+                // Forward the participant's input to the session
+                let input_tx = input_tx.clone();
+                tokio::spawn(async move {
+                    while let Some(Ok(message)) = stream.next().await {
+                        let text = match message {
+                            Message::Text(text) => text,
+                            Message::Close(_) => break,
+                            _ => continue,
+                        };
+
+                        if let Ok(PairEvent::Input { text }) = serde_json::from_str(&text)
+                            && input_tx.send((author.clone(), text)).await.is_err()
+                        {
+                            break;
+                        }
+                    }
+                    branding::print_info(&format!("{} disconnected", author));
+                });
+            }
+        });
+
+        Ok(Self { events, inputs, join_url })
+    }
+
+    /// URL participants join the session with, including the join token
+    pub fn join_url(&self) -> &str {
+        &self.join_url
+    }
+
+    /// Broadcast a transcript message to all participants
+    pub fn broadcast(&self, author: &str, text: &str) {
+        // Sending fails only when nobody has joined yet
+        let _ = self.events.send(PairEvent::Message { author: author.to_string(), text: text.to_string() });
+    }
+
+    /// Wait for the next input from a participant
+    pub async fn next_input(&mut self) -> Option<(String, String)> {
+        self.inputs.recv().await
+    }
+
+    /// Tell all participants the session has ended
+    pub fn end(&self) {
+        let _ = self.events.send(PairEvent::End);
+    }
+}
+
+/// Check a participant's handshake, returning the name they joined with
+fn admit(request: &Request, token: &str, reserved: &[String]) -> std::result::Result<String, (StatusCode, String)> {
+    let url = reqwest::Url::parse(&format!("ws://host{}", request.uri()))
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid session URL".to_string()))?;
+    let param = |key: &str| url.query_pairs().find(|(k, _)| k == key).map(|(_, v)| v.into_owned());
+
+    if param("token").as_deref() != Some(token) {
+        return Err((StatusCode::FORBIDDEN, "A valid join token is required; ask the host for the session URL".to_string()));
+    }
+    let name = param("name").map(|name| name.trim().to_string()).unwrap_or_default();
+    if name.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "A participant name is required".to_string()));
+    }
+    if reserved.iter().any(|reserved| reserved.eq_ignore_ascii_case(&name)) {
+        return Err((StatusCode::FORBIDDEN, format!("The name {} is taken; join with another name", name)));
+    }
+    Ok(name)
+}
+
+/// Build a handshake rejection
+fn refuse(status: StatusCode, reason: &str) -> ErrorResponse {
+    let mut response = ErrorResponse::new(Some(reason.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+/// Build the URL a participant joins with, adding their name to the host's session URL
+pub fn participant_url(url: &str, participant: &str) -> Result<String> {
+    let mut url = reqwest::Url::parse(url).map_err(|e| anyhow!("Invalid session URL {}: {}", url, e))?;
+    if !url.query_pairs().any(|(key, _)| key == "token") {
+        return Err(anyhow!("The session URL has no join token; use the URL the host printed"));
+    }
+    url.query_pairs_mut().append_pair("name", participant);
+    Ok(url.to_string())
+}
+
+/// Join a hosted session as a read/write participant
+pub async fn join_session(url: &str, participant: &str) -> Result<()> {
+    crate::offline::ensure_local_url("Joining a session on another machine", url)?;
+    let join_url = participant_url(url, participant)?;
+    let (websocket, _) = tokio_tungstenite::connect_async(join_url.as_str()).await
+        .context(format!("Failed to join session at {}", url))?;
+    let (mut sink, mut stream) = websocket.split();
+
+    println!("Joined session as {}. Type /exit to leave.", participant);
+    println!();
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        tokio::select! {
+            message = stream.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(_)) => continue,
+                    _ => break,
+                };
+
+                match serde_json::from_str::<PairEvent>(&text) {
+                    Ok(PairEvent::Message { author, text }) if author == participant => {
+                        tracing::debug!("Echo of own message: {}", text);
+                    },
+                    Ok(PairEvent::Message { author, text }) => {
+                        let color = if author == "QitOps" { branding::Color::Green } else { branding::Color::Blue };
+                        println!("{}: {}", branding::colorize(&author, color), text);
+                        println!();
+                    },
+                    Ok(PairEvent::End) => {
+                        branding::print_info("The host ended the session");
+                        break;
+                    },
+                    _ => {},
+                }
+            }
+            line = lines.next_line() => {
+                let line = match line? {
+                    Some(line) => line,
+                    None => break,
+                };
+
+                let trimmed = line.trim();
+                if trimmed.is_empty() {
+                    continue;
+                }
+                if trimmed.eq_ignore_ascii_case("/exit") || trimmed.eq_ignore_ascii_case("/quit") {
+                    break;
+                }
+
+                let event = PairEvent::Input { text: trimmed.to_string() };
+                sink.send(Message::Text(serde_json::to_string(&event)?)).await?;
+            }
+        }
+    }
+
+    let _ = sink.close().await;
+    Ok(())
+}
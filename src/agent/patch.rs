@@ -0,0 +1,181 @@
+use regex::Regex;
+use serde::Serialize;
+
+use crate::agent::diff::parse_diff;
+use crate::agent::findings::Finding;
+
+/// A patch from `git format-patch` output
+#[derive(Debug, Clone, Serialize)]
+pub struct Patch {
+    /// Position in the series, from `[PATCH n/m]` (1 for a single patch)
+    pub number: usize,
+
+    /// Subject without the `[PATCH ...]` prefix
+    pub subject: String,
+
+    /// Author from the `From:` header
+    pub author: String,
+
+    /// Commit message below the subject
+    pub message: String,
+
+    /// The patch's diff, without the diffstat and signature
+    #[serde(skip)]
+    pub diff: String,
+}
+
+impl Patch {
+    /// Files the patch changes
+    pub fn files(&self) -> Vec<String> {
+        parse_diff(&self.diff).iter().map(|file| file.path().to_string()).collect()
+    }
+}
+
+/// A patch series: the patches of an mbox, plus the cover letter if there is one
+#[derive(Debug, Clone, Serialize)]
+pub struct PatchSeries {
+    /// Subject of the cover letter (`[PATCH 0/m]`), else of the first patch
+    pub subject: String,
+
+    /// Body of the cover letter, if any
+    pub cover_letter: Option<String>,
+
+    /// Patches in series order
+    pub patches: Vec<Patch>,
+}
+
+impl PatchSeries {
+    /// All patch diffs, one after the other
+    pub fn combined_diff(&self) -> String {
+        self.patches.iter().map(|patch| patch.diff.as_str()).collect::<Vec<_>>().join("\n")
+    }
+
+    /// Description for series-level analysis: the cover letter and the patch subjects
+    pub fn description(&self) -> String {
+        let mut description = self.cover_letter.clone().unwrap_or_default();
+        if !description.is_empty() {
+            description.push_str("\n\n");
+        }
+        description.push_str("Patches in this series:\n");
+        for patch in &self.patches {
+            description.push_str(&format!("{}. {} ({})\n", patch.number, patch.subject, patch.author));
+        }
+        description
+    }
+}
+
+/// Line starting a message in `git format-patch` output: `From <commit> Mon Sep 17 00:00:00 2001`
+fn message_separator() -> Regex {
+    Regex::new(r"^From [0-9a-f]{7,40} ").expect("valid regex")
+}
+
+/// Split `git format-patch` output (one patch or a multi-patch mbox) into a series
+///
+/// Returns `None` for anything else, such as a plain diff.
+pub fn parse_series(text: &str) -> Option<PatchSeries> {
+    let separator = message_separator();
+    let mut messages: Vec<Vec<&str>> = Vec::new();
+    for line in text.lines() {
+        if separator.is_match(line) {
+            messages.push(Vec::new());
+        } else if let Some(message) = messages.last_mut() {
+            message.push(line);
+        }
+    }
+
+    let subject_prefix = Regex::new(r"^\[[^\]]*?(?:(\d+)/(\d+))?\]\s*").expect("valid regex");
+    let mut cover_letter = None;
+    let mut series_subject = None;
+    let mut patches = Vec::new();
+
+    for lines in messages {
+        let (headers, body) = split_headers(&lines);
+        let raw_subject = header(&headers, "Subject").unwrap_or_default();
+        let captures = subject_prefix.captures(&raw_subject);
+        let number = captures.as_ref()
+            .and_then(|c| c.get(1))
+            .and_then(|n| n.as_str().parse::<usize>().ok());
+        let subject = subject_prefix.replace(&raw_subject, "").trim().to_string();
+
+        let (message, diff) = split_body(&body);
+        if diff.is_empty() {
+            // A cover letter, or a message without a diff
+            if number == Some(0) {
+                series_subject = Some(subject);
+                cover_letter = Some(message);
+            }
+            continue;
+        }
+
+        patches.push(Patch {
+            number: number.unwrap_or(patches.len() + 1),
+            subject,
+            author: header(&headers, "From").unwrap_or_default(),
+            message,
+            diff,
+        });
+    }
+
+    if patches.is_empty() {
+        return None;
+    }
+
+    Some(PatchSeries {
+        subject: series_subject.unwrap_or_else(|| patches[0].subject.clone()),
+        cover_letter,
+        patches,
+    })
+}
+
+/// Split a message into its headers (with folded lines joined) and body
+fn split_headers<'a>(lines: &[&'a str]) -> (Vec<(String, String)>, Vec<&'a str>) {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut index = 0;
+    while index < lines.len() && !lines[index].is_empty() {
+        let line = lines[index];
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.to_string(), value.trim().to_string()));
+        }
+        index += 1;
+    }
+
+    (headers, lines[(index + 1).min(lines.len())..].to_vec())
+}
+
+/// Value of a header, matched case-insensitively
+fn header(headers: &[(String, String)], name: &str) -> Option<String> {
+    headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, value)| value.clone())
+}
+
+/// Split a patch body into the commit message and the diff
+///
+/// The message ends at the `---` line before the diffstat (or at the signature of a cover
+/// letter); the diff runs from the first `diff --git` line to the `-- ` signature line.
+fn split_body(body: &[&str]) -> (String, String) {
+    let diff_start = body.iter().position(|line| line.starts_with("diff --git ")).unwrap_or(body.len());
+    let message_end = body[..diff_start].iter().position(|line| *line == "---" || *line == "-- ").unwrap_or(diff_start);
+    let diff_end = body[diff_start..].iter()
+        .position(|line| *line == "-- ")
+        .map_or(body.len(), |end| diff_start + end);
+
+    let message = body[..message_end].join("\n").trim().to_string();
+    let diff = body[diff_start..diff_end].join("\n");
+    (message, if diff.trim().is_empty() { String::new() } else { diff + "\n" })
+}
+
+/// Findings of the series analysis, then those only found in a single patch
+pub fn merge_findings(series: Vec<Finding>, per_patch: impl IntoIterator<Item = Finding>) -> Vec<Finding> {
+    let mut findings = series;
+    for finding in per_patch {
+        let fingerprint = finding.fingerprint();
+        if !findings.iter().any(|f| f.fingerprint() == fingerprint) {
+            findings.push(finding);
+        }
+    }
+    findings
+}
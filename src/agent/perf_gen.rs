@@ -0,0 +1,275 @@
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::agent::test_data::TestDataAgent;
+use crate::agent::traits::{Agent, AgentResponse, AgentStatus};
+use crate::llm::{LlmRequest, LlmRouter};
+use crate::llm::postprocess::fenced_block;
+use crate::error::QitOpsError;
+
+/// Maximum number of bytes of the OpenAPI spec included in the prompt
+const MAX_SPEC_BYTES: usize = 60_000;
+
+/// Load profile
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum LoadProfile {
+    /// Steady expected load
+    Load,
+    /// Sudden burst of traffic
+    Spike,
+    /// Long-running sustained load
+    Soak,
+    /// Ramp until the system breaks
+    Stress,
+}
+
+impl FromStr for LoadProfile {
+    type Err = anyhow::Error;
+
+    /// Parse a string into a load profile
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "load" => Ok(LoadProfile::Load),
+            "spike" => Ok(LoadProfile::Spike),
+            "soak" | "endurance" => Ok(LoadProfile::Soak),
+            "stress" => Ok(LoadProfile::Stress),
+            _ => Err(QitOpsError::InvalidInput(format!("Unknown load profile: {}", s)).into()),
+        }
+    }
+}
+
+impl LoadProfile {
+    /// Ramp stages for this profile as (duration, target virtual users)
+    pub fn stages(&self) -> Vec<(&'static str, u32)> {
+        match self {
+            LoadProfile::Load => vec![("2m", 50), ("10m", 50), ("2m", 0)],
+            LoadProfile::Spike => vec![("30s", 10), ("10s", 500), ("1m", 500), ("10s", 10), ("30s", 0)],
+            LoadProfile::Soak => vec![("5m", 100), ("4h", 100), ("5m", 0)],
+            LoadProfile::Stress => vec![("2m", 100), ("5m", 200), ("5m", 400), ("5m", 800), ("2m", 0)],
+        }
+    }
+
+    /// Default pass/fail thresholds for this profile
+    pub fn thresholds(&self) -> &'static str {
+        match self {
+            LoadProfile::Load => "p95 latency < 500ms, error rate < 1%",
+            LoadProfile::Spike => "p95 latency < 1500ms during the spike, error rate < 5%, full recovery after the spike",
+            LoadProfile::Soak => "p95 latency < 500ms with no upward drift over time, error rate < 0.5%",
+            LoadProfile::Stress => "record the virtual user count at which p95 latency exceeds 2s or error rate exceeds 10%",
+        }
+    }
+}
+
+/// Load testing tool
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum PerfTool {
+    /// Grafana k6 (JavaScript)
+    K6,
+    /// Locust (Python)
+    Locust,
+}
+
+impl FromStr for PerfTool {
+    type Err = anyhow::Error;
+
+    /// Parse a string into a load testing tool
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "k6" => Ok(PerfTool::K6),
+            "locust" => Ok(PerfTool::Locust),
+            _ => Err(QitOpsError::InvalidInput(format!("Unknown load testing tool: {}", s)).into()),
+        }
+    }
+}
+
+impl PerfTool {
+    /// Get the file extension for this tool
+    pub fn extension(&self) -> &'static str {
+        match self {
+            PerfTool::K6 => "js",
+            PerfTool::Locust => "py",
+        }
+    }
+
+    /// Get the system prompt for this tool
+    pub fn system_prompt(&self) -> String {
+        match self {
+            PerfTool::K6 => "You are a performance engineer. Generate a complete, runnable k6 script in JavaScript. Use `options.stages` for the ramp profile, `options.thresholds` for pass/fail criteria, `group` and `check` for each scenario, and `SharedArray` to load data feeds. Output only the script.".to_string(),
+            PerfTool::Locust => "You are a performance engineer. Generate a complete, runnable Locust file in Python. Use `HttpUser` classes with weighted `@task` methods, a `LoadTestShape` for the ramp profile, and load data feeds from JSON. Output only the script.".to_string(),
+        }
+    }
+}
+
+/// Load/performance test script generator agent
+pub struct PerfGenAgent {
+    /// Path to the OpenAPI specification
+    openapi: String,
+
+    /// Load profile
+    profile: LoadProfile,
+
+    /// Load testing tool
+    tool: PerfTool,
+
+    /// Schema for the generated data feed (if any)
+    data_schema: Option<String>,
+
+    /// Number of data feed records to generate
+    data_count: usize,
+
+    /// System prompt override (replaces the built-in system prompt)
+    system_prompt_override: Option<String>,
+
+    /// LLM router
+    llm_router: LlmRouter,
+}
+
+impl PerfGenAgent {
+    /// Create a new performance test generator agent
+    pub async fn new(
+        openapi: String,
+        profile: &str,
+        tool: &str,
+        data_schema: Option<String>,
+        data_count: usize,
+        llm_router: LlmRouter,
+    ) -> Result<Self> {
+        let profile = LoadProfile::from_str(profile)?;
+        let tool = PerfTool::from_str(tool)?;
+
+        Ok(Self {
+            openapi,
+            profile,
+            tool,
+            data_schema,
+            data_count,
+            system_prompt_override: crate::prompt::load_system_prompt_override("perf-gen")?,
+            llm_router,
+        })
+    }
+
+    /// Read the OpenAPI specification
+    fn read_spec(&self) -> Result<String> {
+        let path = Path::new(&self.openapi);
+        if !path.exists() {
+            return Err(QitOpsError::InvalidInput(format!("OpenAPI spec not found: {}", self.openapi)).into());
+        }
+
+        let mut spec = fs::read_to_string(path).context(format!("Failed to read OpenAPI spec: {}", self.openapi))?;
+        if spec.len() > MAX_SPEC_BYTES {
+            let mut end = MAX_SPEC_BYTES;
+            while !spec.is_char_boundary(end) {
+                end -= 1;
+            }
+            spec.truncate(end);
+        }
+
+        Ok(spec)
+    }
+
+    /// Base name used for the generated files
+    fn output_stem(&self) -> String {
+        Path::new(&self.openapi)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "api".to_string())
+    }
+
+    /// Generate the prompt for the LLM
+    fn generate_prompt(&self, spec: &str, data_file: Option<&str>) -> String {
+        let stages = self.profile.stages().iter()
+            .map(|(duration, target)| format!("{} to {} virtual users", duration, target))
+            .collect::<Vec<String>>()
+            .join(", then ");
+
+        let data_instructions = match data_file {
+            Some(file) => format!("Read request payloads from the data feed file `{}` (a JSON array of records) and pick records per iteration.", file),
+            None => "Generate realistic inline request payloads for each endpoint.".to_string(),
+        };
+
+        format!(
+            "Generate a {:?} test for the API described by the following OpenAPI specification.\n\nRamp profile: {}.\nThresholds: {}.\n{}\n\nModel realistic user journeys across the endpoints (for example browse, then read, then write) with think time between requests, and weight read-heavy endpoints higher than writes.\n\nOpenAPI specification:\n```\n{}\n```",
+            self.profile, stages, self.profile.thresholds(), data_instructions, spec
+        )
+    }
+}
+
+impl Agent for PerfGenAgent {
+    fn init(&mut self) -> Result<()> {
+        // No initialization needed
+        Ok(())
+    }
+
+    async fn execute(&self) -> Result<AgentResponse> {
+        // Read the OpenAPI spec
+        let spec = self.read_spec()?;
+        crate::debug::record_piece("spec", &self.openapi, &spec);
+
+        let output_dir = Path::new("perf");
+        if !output_dir.exists() {
+            fs::create_dir_all(output_dir)?;
+        }
+
+        // Generate the data feed by reusing the test data generator
+        let data_file = match &self.data_schema {
+            Some(schema) => {
+                let data_agent = TestDataAgent::new(
+                    schema.clone(),
+                    self.data_count,
+                    Vec::new(),
+                    "json".to_string(),
+                    self.llm_router.clone(),
+                ).await?;
+
+                let records = data_agent.generate_records().await?;
+                let file = output_dir.join(format!("{}_data.json", self.output_stem()));
+                fs::write(&file, fenced_block(&records).unwrap_or(records.trim()))?;
+                Some(file.to_string_lossy().to_string())
+            },
+            None => None,
+        };
+
+        // Generate the prompt
+        let prompt = self.generate_prompt(&spec, data_file.as_deref());
+
+        // Create the LLM request
+        let model = self.llm_router.default_model().unwrap_or_else(|| "tinyllama".to_string());
+        let request = LlmRequest::new(prompt, model)
+            .with_system_message(self.system_prompt_override.clone().unwrap_or_else(|| self.tool.system_prompt()))
+            .with_max_tokens(4096);
+
+        // Send the request to the LLM
+        let response = self.llm_router.send(request, Some("perf-gen")).await?;
+
+        // Save the script
+        let script = fenced_block(&response.text).unwrap_or(response.text.trim()).to_string();
+        let profile_name = format!("{:?}", self.profile).to_lowercase();
+        let script_file = output_dir.join(format!("{}_{}.{}", self.output_stem(), profile_name, self.tool.extension()));
+        fs::write(&script_file, &script)?;
+        let script_file = script_file.to_string_lossy().to_string();
+
+        // Return the response
+        Ok(AgentResponse {
+            status: AgentStatus::Success,
+            message: format!("Generated {:?} script saved to {}", self.tool, script_file),
+            data: Some(serde_json::json!({
+                "output_file": script_file,
+                "data_file": data_file,
+                "profile": profile_name,
+                "tool": format!("{:?}", self.tool).to_lowercase(),
+                "script": script,
+            })),
+        })
+    }
+
+    fn name(&self) -> &str {
+        "perf-gen"
+    }
+
+    fn description(&self) -> &str {
+        "Load/performance test script generator"
+    }
+}
@@ -1,9 +1,33 @@
 use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
+#[cfg(feature = "github")]
+use std::path::PathBuf;
 
+use crate::agent::baseline::{BASELINE_FILE, BaselineMode, apply_baseline};
+#[cfg(feature = "github")]
+use crate::agent::diff::patch_position;
+use crate::agent::diff_filter::DiffFilters;
+#[cfg(feature = "github")]
+use crate::agent::diff_filter::FilterReport;
+use crate::agent::findings::{FINDINGS_INSTRUCTIONS, extract_findings};
+#[cfg(feature = "github")]
+use crate::agent::findings::Finding;
+#[cfg(feature = "github")]
+use crate::agent::incremental::{self, AnalysisStore, PriorAnalysis};
+use crate::agent::injection::detect_injection;
+#[cfg(feature = "github")]
+use crate::agent::large_pr::{FileAnalysis, FileSection, LargePrConfig, file_summaries, select_files, split_by_file};
+use crate::agent::patch::{self, PatchSeries};
+use crate::agent::sources::SourceSelection;
 use crate::agent::traits::{Agent, AgentResponse, AgentStatus};
-use crate::ci::github::GitHubClient;
+#[cfg(feature = "github")]
+use crate::ci::fallback::{self, PrCache, PrData};
+#[cfg(feature = "github")]
+use crate::ci::github::{GitHubClient, PullRequestFile, ReviewComment};
 use crate::llm::{LlmRequest, LlmRouter};
+use crate::prompt::wrap_untrusted;
+use crate::error::QitOpsError;
 
 /// PR analysis focus
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,7 +50,7 @@ impl PrFocus {
             "security" => Ok(PrFocus::Security),
             "performance" => Ok(PrFocus::Performance),
             "regression" => Ok(PrFocus::Regression),
-            _ => Err(anyhow::anyhow!("Unknown PR focus: {}", s)),
+            _ => Err(QitOpsError::InvalidInput(format!("Unknown PR focus: {}", s)).into()),
         }
     }
 
@@ -43,27 +67,79 @@ impl PrFocus {
 
 /// PR analysis agent
 pub struct PrAnalyzeAgent {
-    /// PR number or URL
+    /// PR number or URL, or the path to a patch series
     pr: String,
 
     /// PR focus
     focus: PrFocus,
 
-    /// GitHub client
-    github_client: GitHubClient,
+    /// GitHub client (unless analyzing a patch series)
+    #[cfg(feature = "github")]
+    github_client: Option<GitHubClient>,
+
+    /// Cache of PR data, used when GitHub can't be reached
+    #[cfg(feature = "github")]
+    pr_cache: PrCache,
+
+    /// Local clone to diff when GitHub can't be reached and nothing is cached
+    #[cfg(feature = "github")]
+    repo_dir: PathBuf,
+
+    /// System prompt override (replaces the built-in system prompt)
+    system_prompt_override: Option<String>,
 
     /// LLM router
     llm_router: LlmRouter,
 
     /// Repository owner
+    #[cfg(feature = "github")]
     owner: String,
 
     /// Repository name
+    #[cfg(feature = "github")]
     repo: String,
+
+    /// How the findings baseline is applied
+    baseline_mode: BaselineMode,
+
+    /// Sources to attach, explicitly or by changed paths
+    sources: SourceSelection,
+
+    /// Re-analyze only the files changed since the last analysis of the PR
+    #[cfg(feature = "github")]
+    incremental: bool,
+
+    /// Post the findings as a review with inline comments on the PR
+    #[cfg(feature = "github")]
+    review: bool,
+
+    /// Noise filters run on the diff before analysis
+    diff_filters: DiffFilters,
+
+    /// How a PR too large for one prompt is analyzed file by file
+    #[cfg(feature = "github")]
+    large_pr: LargePrConfig,
+}
+
+/// How a PR analysis builds on the last one
+#[cfg(feature = "github")]
+struct Increment {
+    /// Head commit of the last analysis
+    since: String,
+
+    /// Files re-analyzed
+    files: Vec<String>,
+
+    /// Findings of the last analysis on unchanged files
+    carried_over: Vec<Finding>,
+
+    /// Analysis of the last run, reused when nothing changed
+    prior_analysis: Option<String>,
 }
 
 impl PrAnalyzeAgent {
     /// Create a new PR analysis agent
+    #[cfg(feature = "github")]
     pub async fn new(
         pr: String,
         focus: Option<String>,
@@ -76,18 +152,135 @@ impl PrAnalyzeAgent {
             Some(f) => PrFocus::from_str(&f)?,
             None => PrFocus::General,
         };
+        let system_prompt_override = crate::prompt::load_system_prompt_override("pr-analyze")?;
 
         Ok(Self {
             pr,
             focus,
-            github_client,
+            github_client: Some(github_client),
+            pr_cache: PrCache::new()?,
+            repo_dir: std::env::current_dir()?,
+            system_prompt_override,
             llm_router,
             owner,
             repo,
+            baseline_mode: BaselineMode::default(),
+            sources: SourceSelection::default(),
+            incremental: true,
+            review: false,
+            diff_filters: DiffFilters::default(),
+            large_pr: LargePrConfig::default(),
+        })
+    }
+
+    /// Create a new agent for a patch series (`git format-patch` output) instead of a PR
+    pub async fn new_from_patches(patch_path: String, focus: Option<String>, llm_router: LlmRouter) -> Result<Self> {
+        let focus = match focus {
+            Some(f) => PrFocus::from_str(&f)?,
+            None => PrFocus::General,
+        };
+
+        Ok(Self {
+            pr: patch_path,
+            focus,
+            #[cfg(feature = "github")]
+            github_client: None,
+            #[cfg(feature = "github")]
+            pr_cache: PrCache::new()?,
+            #[cfg(feature = "github")]
+            repo_dir: std::env::current_dir()?,
+            system_prompt_override: crate::prompt::load_system_prompt_override("pr-analyze")?,
+            llm_router,
+            #[cfg(feature = "github")]
+            owner: String::new(),
+            #[cfg(feature = "github")]
+            repo: String::new(),
+            baseline_mode: BaselineMode::default(),
+            sources: SourceSelection::default(),
+            #[cfg(feature = "github")]
+            incremental: true,
+            #[cfg(feature = "github")]
+            review: false,
+            diff_filters: DiffFilters::default(),
+            #[cfg(feature = "github")]
+            large_pr: LargePrConfig::default(),
         })
     }
 
+    /// Set how the findings baseline is applied
+    pub fn with_baseline(mut self, mode: BaselineMode) -> Self {
+        self.baseline_mode = mode;
+        self
+    }
+
+    /// Set the sources to attach, explicitly or by changed paths
+    pub fn with_sources(mut self, sources: SourceSelection) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    /// Set whether a PR analyzed before is re-analyzed only where it changed (on by default)
+    #[cfg(feature = "github")]
+    pub fn with_incremental(mut self, incremental: bool) -> Self {
+        self.incremental = incremental;
+        self
+    }
+
+    /// Set whether the findings are posted as a review with inline comments on the PR
+    #[cfg(feature = "github")]
+    pub fn with_review(mut self, review: bool) -> Self {
+        self.review = review;
+        self
+    }
+
+    /// Set how a PR too large for one prompt is analyzed file by file
+    #[cfg(feature = "github")]
+    pub fn with_large_pr(mut self, config: LargePrConfig) -> Self {
+        self.large_pr = config;
+        self
+    }
+
+    /// Set the noise filters run on the diff before analysis
+    pub fn with_diff_filters(mut self, filters: DiffFilters) -> Self {
+        self.diff_filters = filters;
+        self
+    }
+
+    /// Work out what changed since the last analysis of a PR, if it can build on that one
+    ///
+    /// Falls back to a full analysis when there is no earlier analysis, the focus changed, or
+    /// the old head is no longer an ancestor of the new one (e.g. after a force push).
+    #[cfg(feature = "github")]
+    async fn increment(&self, github_client: &GitHubClient, prior: Option<PriorAnalysis>, head_sha: &str, pr_files: &[String]) -> Option<Increment> {
+        let prior = prior.filter(|prior| self.incremental && !head_sha.is_empty() && prior.focus == format!("{:?}", self.focus))?;
+
+        if prior.head_sha == head_sha {
+            return Some(Increment {
+                since: prior.head_sha.clone(),
+                files: Vec::new(),
+                carried_over: prior.findings.clone(),
+                prior_analysis: Some(prior.analysis),
+            });
+        }
+
+        let comparison = match github_client.compare_commits(&self.owner, &self.repo, &prior.head_sha, head_sha).await {
+            Ok(comparison) if comparison.status == "ahead" => comparison,
+            Ok(comparison) => {
+                tracing::info!("Head moved from {} to {} ({}); analyzing the whole PR", prior.head_sha, head_sha, comparison.status);
+                return None;
+            },
+            Err(e) => {
+                tracing::warn!("Could not compare {} with {}; analyzing the whole PR: {}", prior.head_sha, head_sha, e);
+                return None;
+            },
+        };
+
+        let delta = incremental::plan_delta(&prior, &comparison.files, pr_files);
+        Some(Increment { since: prior.head_sha, files: delta.files, carried_over: delta.carried_over, prior_analysis: None })
+    }
+
     /// Extract PR number from a PR string (number or URL)
+    #[cfg(feature = "github")]
     fn extract_pr_number(&self) -> Result<u64> {
         // If it's just a number, parse it directly
         if let Ok(num) = self.pr.parse::<u64>() {
@@ -103,75 +296,424 @@ impl PrAnalyzeAgent {
             }
         }
 
-        Err(anyhow::anyhow!("Invalid PR format: {}", self.pr))
+        Err(QitOpsError::InvalidInput(format!("Invalid PR format: {}", self.pr)).into())
     }
 
     /// Generate the prompt for the LLM
-    fn generate_prompt(&self, pr_info: &str, diff: &str) -> String {
-        format!(
-            "Analyze the following pull request:\n\n{}\n\nDiff:\n```\n{}\n```",
-            pr_info, diff
-        )
+    fn generate_prompt(&self, title: &str, description: &str, file_summary: &str, diff: &str) -> Result<String> {
+        self.generate_incremental_prompt(title, description, file_summary, diff, None)
     }
-}
 
-impl Agent for PrAnalyzeAgent {
-    fn init(&mut self) -> Result<()> {
-        // No initialization needed
-        Ok(())
+    /// Generate the prompt for the LLM, for the changes since an earlier analyzed commit if given
+    fn generate_incremental_prompt(&self, title: &str, description: &str, file_summary: &str, diff: &str, since: Option<&str>) -> Result<String> {
+        crate::prompt::render_prompt("pr-analyze", &serde_json::json!({
+            "title": wrap_untrusted("pull request title", title),
+            "description": wrap_untrusted("pull request description", description),
+            "files": file_summary,
+            "diff": wrap_untrusted("diff", diff),
+            "since": since,
+            "focus": format!("{:?}", self.focus),
+            "findings_instructions": FINDINGS_INSTRUCTIONS,
+        }))
     }
 
-    async fn execute(&self) -> Result<AgentResponse> {
-        // Extract PR number
-        let pr_number = self.extract_pr_number()?;
+    /// Create the LLM request for a prompt
+    fn request(&self, prompt: String, source_context: &str) -> LlmRequest {
+        let model = self.llm_router.default_model().unwrap_or_else(|| "tinyllama".to_string());
+        LlmRequest::new(prompt, model)
+            .with_system_message(self.system_prompt_override.clone().unwrap_or_else(|| self.focus.system_prompt()))
+            .with_source_context(source_context)
+    }
+
+    /// Summarize a diff in chunks if it is too large for one prompt
+    async fn condense(&self, text: &str, what: &str) -> Result<String> {
+        Ok(self.llm_router.condense(text, what, "a pull request review", Some("pr-analyze")).await?.text)
+    }
+
+    /// Read and split the patch series file
+    fn read_series(&self) -> Result<PatchSeries> {
+        let path = Path::new(&self.pr);
+        if !path.exists() {
+            return Err(QitOpsError::InvalidInput(format!("Patch file not found: {}", self.pr)).into());
+        }
+
+        let text = std::fs::read_to_string(path).context(format!("Failed to read patch file: {}", self.pr))?;
+        patch::parse_series(&text).ok_or_else(|| QitOpsError::InvalidInput(
+            format!("{} is not `git format-patch` output", self.pr)
+        ).into())
+    }
+
+    /// Analyze each patch of a series and the series as a whole
+    async fn execute_series(&self) -> Result<AgentResponse> {
+        let series = self.read_series()?;
+        let diff = series.combined_diff();
+        crate::debug::record_piece("diff", &self.pr, &diff);
+        let files = crate::agent::diff::parse_diff(&diff).iter().map(|file| file.path().to_string()).collect::<Vec<_>>();
+        let (source_context, sources, source_rules) = self.sources.load(&files)?;
+        let source_context = self.condense(&source_context, "set of source documents").await?;
+
+        // One request per patch, then one for the whole series
+        let patch_diffs = futures_util::future::try_join_all(
+            series.patches.iter().map(|patch| self.condense(&patch.diff, "diff"))
+        ).await?;
+        let requests = series.patches.iter().zip(&patch_diffs).map(|(patch, patch_diff)| {
+            let files = patch.files().join("\n");
+            self.generate_prompt(&patch.subject, &patch.message, &files, patch_diff).map(|prompt| self.request(prompt, &source_context))
+        }).collect::<Result<Vec<_>>>()?;
+        let per_patch = self.llm_router.send_batch(requests, Some("pr-analyze"), |_| {}).await;
+
+        let description = series.description();
+        let prompt = self.generate_prompt(&series.subject, &description, &files.join("\n"), &self.condense(&diff, "diff").await?)?;
+        let response = self.llm_router.send(self.request(prompt, &source_context), Some("pr-analyze")).await?;
+        let (series_analysis, series_findings) = extract_findings(&response.text);
 
-        // Get PR information
-        let pr_info = self.github_client.get_pull_request(&self.owner, &self.repo, pr_number).await?;
+        // Combine the analyses, keeping findings of single patches the series analysis missed
+        let mut analysis = series_analysis.trim().to_string();
+        let mut patches = Vec::new();
+        let mut patch_findings = Vec::new();
+        for (patch, response) in series.patches.iter().zip(per_patch) {
+            let (patch_analysis, findings) = extract_findings(&response?.text);
+            analysis.push_str(&format!(
+                "\n\n## Patch {}/{}: {}\n\n{}",
+                patch.number, series.patches.len(), patch.subject, patch_analysis.trim()
+            ));
+            patches.push(serde_json::json!({
+                "number": patch.number,
+                "subject": patch.subject,
+                "author": patch.author,
+                "files": patch.files(),
+                "analysis": patch_analysis,
+                "findings": findings,
+            }));
+            patch_findings.extend(findings);
+        }
+
+        let mut findings = patch::merge_findings(series_findings, patch_findings);
+        findings.extend(detect_injection(&diff, Some(&series.subject), Some(&description)));
+        let outcome = apply_baseline(findings, &diff, self.baseline_mode, Path::new(BASELINE_FILE))?;
+
+        Ok(AgentResponse {
+            status: AgentStatus::Success,
+            message: format!("Patch series analysis completed for {} patch(es)", series.patches.len()),
+            data: Some(serde_json::json!({
+                "pr_title": series.subject,
+                "analysis": analysis,
+                "focus": format!("{:?}", self.focus),
+                "files_changed": files.len(),
+                "patches": patches,
+                "sources": sources,
+                "source_rules": source_rules,
+                "findings": outcome.new,
+                "baselined": outcome.baselined,
+                "suppressed": outcome.suppressed,
+                "baseline_written": outcome.baseline_written,
+                "diff": diff,
+                "degraded": serde_json::Value::Null,
+            })),
+        })
+    }
 
-        // Get PR diff
-        let diff = self.github_client.get_pull_request_diff(&self.owner, &self.repo, pr_number).await?;
+    /// Analyze a pull request, building on its last analysis where possible
+    #[cfg(feature = "github")]
+    async fn execute_pr(&self, github_client: &GitHubClient) -> Result<AgentResponse> {
+        // Extract PR number
+        let pr_number = self.extract_pr_number()?;
 
-        // Get PR files
-        let files = self.github_client.get_pull_request_files(&self.owner, &self.repo, pr_number).await?;
+        // Get PR information, diff and files, falling back to local data if GitHub is unavailable
+        let fetch = fallback::fetch_pr(github_client, &self.owner, &self.repo, pr_number, &self.pr_cache, &self.repo_dir).await?;
+        let PrData { pull_request: pr_info, diff, files } = fetch.data;
+        crate::debug::record_piece("diff", &format!("{}/{}#{}", self.owner, self.repo, pr_number), &diff);
 
         // Generate file summary
         let file_summary = files.iter().map(|f| {
             format!("{} ({}, +{}, -{})", f.filename, f.status, f.additions, f.deletions)
         }).collect::<Vec<String>>().join("\n");
 
-        // Generate the prompt
-        let prompt = self.generate_prompt(
-            &format!(
-                "Title: {}\nDescription: {}\n\nFiles Changed:\n{}",
-                pr_info.title,
-                pr_info.body.unwrap_or_default(),
-                file_summary
+        // Build on the last analysis of this PR when only some files changed since
+        let paths = files.iter().map(|f| f.filename.clone()).collect::<Vec<_>>();
+        let store = AnalysisStore::new()?;
+        let increment = match fetch.degraded {
+            Some(_) => None,
+            None => self.increment(github_client, store.load(&self.owner, &self.repo, pr_number), &pr_info.head_sha, &paths).await,
+        };
+
+        let (analysis, mut findings, sources, source_rules, filtered, by_file) = match &increment {
+            // Nothing was pushed since the last analysis
+            Some(Increment { prior_analysis: Some(analysis), .. }) => (analysis.clone(), Vec::new(), Vec::new(), Vec::new(), FilterReport::default(), None),
+            // Only files without changes since the last analysis
+            Some(increment) if increment.files.is_empty() => (
+                format!("No files of the pull request changed since {}; earlier findings carry over.", short_sha(&increment.since)),
+                Vec::new(), Vec::new(), Vec::new(), FilterReport::default(), None,
             ),
-            &diff
-        );
+            _ => {
+                let analyzed_paths = increment.as_ref().map_or(&paths, |increment| &increment.files);
+                let analyzed_diff = match &increment {
+                    Some(increment) => incremental::diff_for_files(&diff, &increment.files),
+                    None => diff.clone(),
+                };
 
-        // Create the LLM request
-        let model = self.llm_router.default_model().unwrap_or_else(|| "tinyllama".to_string());
-        let request = LlmRequest::new(prompt, model)
-            .with_system_message(self.focus.system_prompt());
+                // Attach sources for the files analyzed
+                let (source_context, sources, source_rules) = self.sources.load(analyzed_paths)?;
+                let source_context = self.condense(&source_context, "set of source documents").await?;
 
-        // Send the request to the LLM
-        let response = self.llm_router.send(request, Some("pr-analyze")).await?;
+                // Analyze the diff without the noise, file by file if it is too large for one prompt
+                let (filtered_diff, filtered) = self.diff_filters.apply(&analyzed_diff);
+                let description = pr_info.body.as_deref().unwrap_or_default();
+                let sections = split_by_file(&filtered_diff);
+                let (analysis, mut findings, by_file) = if sections.len() > 1 && !self.llm_router.fits_prompt(&filtered_diff, Some("pr-analyze")) {
+                    let (analysis, findings, analyses, skipped) = self.analyze_by_file(&pr_info.title, description, &file_summary, sections, &source_context).await?;
+                    (analysis, findings, Some((analyses, skipped)))
+                } else {
+                    // A single file's diff is summarized in chunks if it is too large for one prompt
+                    let prompt_diff = self.condense(&filtered_diff, "diff").await?;
+                    let since = increment.as_ref().map(|increment| increment.since.as_str());
+                    let prompt = self.generate_incremental_prompt(&pr_info.title, description, &file_summary, &prompt_diff, since)?;
+
+                    // Send the request to the LLM
+                    let response = self.llm_router.send(self.request(prompt, &source_context), Some("pr-analyze")).await?;
+                    let (analysis, findings) = extract_findings(&response.text);
+                    (analysis, findings, None)
+                };
+                findings.extend(detect_injection(&analyzed_diff, Some(&pr_info.title), pr_info.body.as_deref()));
+                (analysis, findings, sources, source_rules, filtered, by_file)
+            },
+        };
+
+        // Keep this analysis to build on next time
+        let carried_over = increment.as_ref().map(|increment| increment.carried_over.clone()).unwrap_or_default();
+        if !pr_info.head_sha.is_empty() && fetch.degraded.is_none() {
+            let kept = PriorAnalysis {
+                head_sha: pr_info.head_sha.clone(),
+                focus: format!("{:?}", self.focus),
+                analysis: analysis.clone(),
+                findings: findings.iter().chain(&carried_over).cloned().collect(),
+            };
+            if let Err(e) = store.store(&self.owner, &self.repo, pr_number, &kept) {
+                tracing::warn!("Failed to keep the PR analysis for next time: {}", e);
+            }
+        }
+
+        // Apply the baseline to all findings, then split the carried-over ones back out
+        let carried_fingerprints: std::collections::HashSet<String> = carried_over.iter().map(Finding::fingerprint).collect();
+        findings.extend(carried_over);
+        let outcome = apply_baseline(findings, &diff, self.baseline_mode, Path::new(BASELINE_FILE))?;
+        let (carried_over, new_findings): (Vec<Finding>, Vec<Finding>) = outcome.new.into_iter()
+            .partition(|finding| carried_fingerprints.contains(&finding.fingerprint()));
+
+        // Post the new findings on the lines they are about
+        let review = match (self.review && !new_findings.is_empty(), &fetch.degraded) {
+            (true, None) => Some(self.post_review(github_client, pr_number, &pr_info.head_sha, &files, &new_findings).await?),
+            (true, Some(_)) => {
+                tracing::warn!("Not posting a review: GitHub could not be reached");
+                None
+            },
+            (false, _) => None,
+        };
 
         // Return the response
+        let message = match (&fetch.degraded, &increment) {
+            (Some(_), _) => format!("PR analysis completed for PR #{} (degraded)", pr_number),
+            (None, Some(Increment { prior_analysis: Some(_), since, .. })) => {
+                format!("PR #{} is unchanged since its last analysis at {}", pr_number, short_sha(since))
+            },
+            (None, Some(increment)) => format!(
+                "PR analysis completed for PR #{} ({} file(s) re-analyzed since {})",
+                pr_number, increment.files.len(), short_sha(&increment.since)
+            ),
+            (None, None) => format!("PR analysis completed for PR #{}", pr_number),
+        };
         Ok(AgentResponse {
             status: AgentStatus::Success,
-            message: format!("PR analysis completed for PR #{}", pr_number),
+            message,
             data: Some(serde_json::json!({
                 "pr_number": pr_number,
                 "pr_title": pr_info.title,
-                "analysis": response.text,
+                "analysis": analysis,
                 "focus": format!("{:?}", self.focus),
                 "files_changed": files.len(),
+                "sources": sources,
+                "source_rules": source_rules,
+                "findings": new_findings,
+                "carried_over": carried_over,
+                "filtered": filtered,
+                "incremental": increment.as_ref().map(|increment| serde_json::json!({
+                    "since": increment.since,
+                    "head": pr_info.head_sha,
+                    "files_reanalyzed": increment.files,
+                })),
+                "baselined": outcome.baselined,
+                "suppressed": outcome.suppressed,
+                "baseline_written": outcome.baseline_written,
+                "file_analyses": by_file.as_ref().map(|(analyses, _)| analyses),
+                "skipped_files": by_file.as_ref().map(|(_, skipped)| skipped),
+                "review": review,
+                "diff": diff,
+                "degraded": fetch.degraded,
             })),
         })
     }
 
+    /// Analyze a diff too large for one prompt file by file, concurrently, then combine the analyses into one review
+    ///
+    /// Only the `large_pr.max_files` files with the most changes are analyzed. Returns the
+    /// combined review followed by the per-file summaries, the findings on all files, the
+    /// per-file analyses, and the files skipped.
+    #[cfg(feature = "github")]
+    async fn analyze_by_file(
+        &self,
+        title: &str,
+        description: &str,
+        file_summary: &str,
+        sections: Vec<FileSection>,
+        source_context: &str,
+    ) -> Result<(String, Vec<Finding>, Vec<FileAnalysis>, Vec<String>)> {
+        let (sections, skipped) = select_files(sections, self.large_pr.max_files);
+        tracing::info!("Pull request is too large for one prompt; analyzing {} file(s) separately", sections.len());
+
+        // A single file's diff may still be too large for one prompt
+        let diffs = futures_util::future::try_join_all(
+            sections.iter().map(|section| self.condense(&section.diff, "diff"))
+        ).await?;
+        let requests = sections.iter().zip(&diffs).enumerate()
+            .map(|(index, (section, diff))| {
+                let prompt = crate::prompt::render_prompt("pr-analyze-file", &serde_json::json!({
+                    "title": wrap_untrusted("pull request title", title),
+                    "description": wrap_untrusted("pull request description", description),
+                    "path": section.path,
+                    "index": index + 1,
+                    "total": sections.len(),
+                    "diff": wrap_untrusted("diff", diff),
+                    "focus": format!("{:?}", self.focus),
+                    "findings_instructions": FINDINGS_INSTRUCTIONS,
+                }))?;
+                Ok(self.request(prompt, source_context))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let responses = self.llm_router.send_batch(requests, Some("pr-analyze"), |_| {}).await;
+
+        let mut findings = Vec::new();
+        let mut analyses = Vec::new();
+        for (section, response) in sections.iter().zip(responses) {
+            let (summary, mut file_findings) = extract_findings(&response?.text);
+            // Findings without a file are about the file analyzed
+            for finding in file_findings.iter_mut().filter(|finding| finding.file.is_none()) {
+                finding.file = Some(section.path.clone());
+            }
+            analyses.push(FileAnalysis { path: section.path.clone(), summary, findings: file_findings.len() });
+            findings.extend(file_findings);
+        }
+
+        // Combine the analyses, adding any findings that only show across files
+        let combined = analyses.iter()
+            .map(|analysis| format!("Analysis of `{}`:\n{}", analysis.path, analysis.summary.trim()))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let combined = self.condense(&combined, "set of file analyses").await?;
+        let prompt = crate::prompt::render_prompt("pr-analyze-synthesis", &serde_json::json!({
+            "title": wrap_untrusted("pull request title", title),
+            "description": wrap_untrusted("pull request description", description),
+            "files": file_summary,
+            "analyses": wrap_untrusted("file analyses", &combined),
+            "skipped": skipped,
+            "focus": format!("{:?}", self.focus),
+        }))?;
+        let response = self.llm_router.send(self.request(prompt, source_context), Some("pr-analyze")).await?;
+        let (review, cross_file) = extract_findings(&response.text);
+        for finding in cross_file {
+            if !findings.iter().any(|known| known.fingerprint() == finding.fingerprint()) {
+                findings.push(finding);
+            }
+        }
+
+        let analysis = format!("{}\n\n{}", review.trim(), file_summaries(&analyses, &skipped));
+        Ok((analysis, findings, analyses, skipped))
+    }
+
+    /// Post findings as a review, each on its line where the PR's patches show it
+    ///
+    /// Findings on lines outside the patches are listed in the review body instead.
+    #[cfg(feature = "github")]
+    async fn post_review(&self, github_client: &GitHubClient, pr_number: u64, head_sha: &str, files: &[PullRequestFile], findings: &[Finding]) -> Result<serde_json::Value> {
+        let (comments, unanchored) = review_comments(files, findings);
+
+        let mut body = format!("QitOps found {} issue(s) in this pull request.", findings.len());
+        if !unanchored.is_empty() {
+            body.push_str("\n\nNot on a changed line:\n");
+            for finding in &unanchored {
+                let location = finding.location();
+                body.push_str(&format!("\n- **[{}]** {}", finding.severity, finding.title));
+                if !location.is_empty() {
+                    body.push_str(&format!(" (`{}`)", location));
+                }
+                if !finding.description.is_empty() {
+                    body.push_str(&format!(": {}", finding.description));
+                }
+            }
+        }
+
+        let id = github_client.create_review(&self.owner, &self.repo, pr_number, Some(head_sha), &body, &comments).await?;
+        Ok(serde_json::json!({
+            "id": id,
+            "comments": comments.len(),
+            "unanchored": unanchored.len(),
+        }))
+    }
+}
+
+/// Inline review comments for findings on lines of the PR's patches, and the findings that aren't
+///
+/// Paths are matched without a leading `./`, `a/` or `b/`, which models sometimes copy from the diff.
+#[cfg(feature = "github")]
+pub fn review_comments<'a>(files: &[PullRequestFile], findings: &'a [Finding]) -> (Vec<ReviewComment>, Vec<&'a Finding>) {
+    let mut comments = Vec::new();
+    let mut unanchored = Vec::new();
+
+    for finding in findings {
+        let anchor = finding.file.as_deref().zip(finding.line).and_then(|(path, line)| {
+            let path = path.trim_start_matches("./");
+            let file = files.iter().find(|file| file.filename == path).or_else(|| {
+                let stripped = path.strip_prefix("a/").or_else(|| path.strip_prefix("b/"))?;
+                files.iter().find(|file| file.filename == stripped)
+            })?;
+            Some((file.filename.clone(), patch_position(file.patch.as_deref()?, line)?))
+        });
+
+        match anchor {
+            Some((path, position)) => {
+                let mut body = format!("**[{}]** {}", finding.severity, finding.title);
+                if !finding.description.is_empty() {
+                    body.push_str(&format!("\n\n{}", finding.description));
+                }
+                comments.push(ReviewComment { path, position, body });
+            },
+            None => unanchored.push(finding),
+        }
+    }
+
+    (comments, unanchored)
+}
+
+/// First seven characters of a commit SHA
+#[cfg(feature = "github")]
+fn short_sha(sha: &str) -> &str {
+    &sha[..sha.len().min(7)]
+}
+
+impl Agent for PrAnalyzeAgent {
+    fn init(&mut self) -> Result<()> {
+        // No initialization needed
+        Ok(())
+    }
+
+    async fn execute(&self) -> Result<AgentResponse> {
+        #[cfg(feature = "github")]
+        if let Some(github_client) = &self.github_client {
+            return self.execute_pr(github_client).await;
+        }
+
+        self.execute_series().await
+    }
+
     fn name(&self) -> &str {
         "pr-analyze"
     }
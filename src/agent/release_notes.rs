@@ -0,0 +1,359 @@
+// Release notes QA appendix: the stored analyses of a milestone's merged pull requests, summarized
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::agent::conventions::is_test_file;
+use crate::agent::findings::Finding;
+use crate::agent::risk::{ComponentRisk, RiskAssessment, RiskLevel};
+use crate::agent::traits::{Agent, AgentResponse, AgentStatus};
+use crate::ci::github::{GitHubClient, MergedPullRequest};
+use crate::error::QitOpsError;
+use crate::llm::{LlmRequest, LlmRouter};
+use crate::runs::findings::{FindingStatus, FindingsQuery, FindingsStore, TrackedFinding};
+use crate::runs::{RunHistory, RunRecord};
+use crate::severity::Severity;
+
+/// System prompt for the release readiness overview
+const SYSTEM_PROMPT: &str = "You are a QA lead writing the QA section of release notes. Write a short release readiness overview in Markdown (one or two paragraphs, no headings): call out the riskiest changes and areas, how well the release is covered by new tests and prior analysis, and the known gaps testers should focus on. Only use facts from the summary you are given.";
+
+/// Most PRs listed by name among those without test changes; the rest are counted
+const MAX_LISTED_PULL_REQUESTS: usize = 20;
+
+/// A merged pull request of the release, with what QitOps knows of it
+#[derive(Debug, Clone, Serialize)]
+pub struct ReleasePullRequest {
+    /// PR number
+    pub number: u64,
+
+    /// PR title
+    pub title: String,
+
+    /// PR author
+    pub author: String,
+
+    /// When it was merged
+    pub merged_at: String,
+
+    /// Label names
+    pub labels: Vec<String>,
+
+    /// Files it changed
+    pub changed_files: usize,
+
+    /// Test files it added or changed
+    pub test_files: Vec<String>,
+
+    /// Latest saved `pr-analyze` run of it
+    pub analysis_run: Option<String>,
+
+    /// Latest saved `risk` run of it
+    pub risk_run: Option<String>,
+
+    /// Overall risk of that assessment
+    pub overall_risk: Option<String>,
+
+    /// Components it assessed as high or critical risk
+    pub risky_components: Vec<ComponentRisk>,
+
+    /// Findings still open
+    pub open_findings: Vec<Finding>,
+
+    /// Findings resolved by a later run before merging
+    pub resolved_findings: usize,
+}
+
+impl ReleasePullRequest {
+    /// Whether it was analyzed or risk-assessed
+    pub fn analyzed(&self) -> bool {
+        self.analysis_run.is_some() || self.risk_run.is_some()
+    }
+
+    /// Whether it was assessed as high or critical risk, or has high or critical open findings
+    pub fn risky(&self) -> bool {
+        self.overall_risk.as_deref().is_some_and(|risk| ["high", "critical"].contains(&risk.to_lowercase().as_str()))
+            || self.open_findings.iter().any(|finding| finding.severity >= Severity::High)
+    }
+}
+
+/// QA summary of a release's merged pull requests
+#[derive(Debug, Clone, Serialize)]
+pub struct ReleaseSummary {
+    /// Milestone title
+    pub milestone: String,
+
+    /// Repository, as `owner/repo`
+    pub repository: String,
+
+    /// Merged pull requests, in merge order
+    pub pull_requests: Vec<ReleasePullRequest>,
+}
+
+impl ReleaseSummary {
+    /// The QA appendix as Markdown, with a readiness overview under its heading
+    pub fn appendix(&self, overview: &str) -> String {
+        let prs = &self.pull_requests;
+        let analyzed = prs.iter().filter(|pr| pr.analyzed()).count();
+        let risky: Vec<&ReleasePullRequest> = prs.iter().filter(|pr| pr.risky()).collect();
+        let tested: Vec<&ReleasePullRequest> = prs.iter().filter(|pr| !pr.test_files.is_empty()).collect();
+        let open: usize = prs.iter().map(|pr| pr.open_findings.len()).sum();
+
+        let mut out = format!("## QA Summary: {}\n\n", self.milestone);
+        out.push_str(&format!(
+            "{} merged pull request(s): {} analyzed by QitOps, {} high-risk, {} with test changes, {} open finding(s).\n",
+            prs.len(), analyzed, risky.len(), tested.len(), open,
+        ));
+        if !overview.trim().is_empty() {
+            out.push_str(&format!("\n{}\n", overview.trim()));
+        }
+
+        out.push_str("\n### Risky Areas\n\n");
+        if risky.is_empty() {
+            out.push_str("No pull request was assessed as high risk.\n");
+        }
+        for pr in &risky {
+            let risk = pr.overall_risk.as_ref().map(|risk| format!(" ({} risk)", risk)).unwrap_or_default();
+            out.push_str(&format!("- **#{}** {}{}\n", pr.number, pr.title, risk));
+            for component in &pr.risky_components {
+                out.push_str(&format!("  - {} ({}): {}\n", component.component, component.risk_level, component.description));
+            }
+        }
+
+        out.push_str("\n### Test Coverage Added\n\n");
+        if tested.is_empty() {
+            out.push_str("No pull request added or changed tests.\n");
+        } else {
+            out.push_str("| PR | Test files |\n|---|---|\n");
+            for pr in &tested {
+                let files: Vec<String> = pr.test_files.iter().map(|file| format!("`{}`", file)).collect();
+                out.push_str(&format!("| #{} {} | {} |\n", pr.number, pr.title, files.join(", ")));
+            }
+        }
+
+        out.push_str("\n### Known Gaps\n\n");
+        let mut gaps = false;
+        for pr in prs {
+            for finding in &pr.open_findings {
+                let location = match (&finding.file, finding.line) {
+                    (Some(file), Some(line)) => format!(" in `{}:{}`", file, line),
+                    (Some(file), None) => format!(" in `{}`", file),
+                    _ => String::new(),
+                };
+                out.push_str(&format!("- **{}** {}{} (#{})\n", finding.severity.as_str().to_uppercase(), finding.title, location, pr.number));
+                gaps = true;
+            }
+        }
+        let unanalyzed: Vec<String> = prs.iter().filter(|pr| !pr.analyzed()).map(|pr| format!("#{}", pr.number)).collect();
+        if !unanalyzed.is_empty() {
+            out.push_str(&format!("- Not analyzed by QitOps: {}\n", unanalyzed.join(", ")));
+            gaps = true;
+        }
+        let untested: Vec<&ReleasePullRequest> = prs.iter().filter(|pr| pr.test_files.is_empty() && pr.changed_files > 0).collect();
+        if !untested.is_empty() {
+            let mut names: Vec<String> = untested.iter().take(MAX_LISTED_PULL_REQUESTS).map(|pr| format!("#{} {}", pr.number, pr.title)).collect();
+            if untested.len() > MAX_LISTED_PULL_REQUESTS {
+                names.push(format!("and {} more", untested.len() - MAX_LISTED_PULL_REQUESTS));
+            }
+            out.push_str(&format!("- No test changes: {}\n", names.join("; ")));
+            gaps = true;
+        }
+        if !gaps {
+            out.push_str("None known.\n");
+        }
+
+        out
+    }
+}
+
+/// Pull request a saved report is about, if it's about one of `owner/repo`
+///
+/// Reports are titled after their source, e.g. `PR Analysis: owner/repo#42` or
+/// `Risk Assessment: https://github.com/owner/repo/pull/42`. A bare number refers to the
+/// configured default repository, which is assumed to be this one.
+pub fn pull_request_of(title: &str, owner: &str, repo: &str) -> Option<u64> {
+    let source = title.split_once(": ").map(|(_, source)| source).unwrap_or(title).trim();
+    let same_repo = |other: &str| other.eq_ignore_ascii_case(&format!("{}/{}", owner, repo));
+
+    if source.contains("github.com") {
+        let (other_owner, other_repo) = GitHubClient::extract_repo_info(source).ok()?;
+        return same_repo(&format!("{}/{}", other_owner, other_repo)).then(|| GitHubClient::extract_pr_number(source).ok()).flatten();
+    }
+    if let Some((other, number)) = source.rsplit_once('#') {
+        return same_repo(other).then(|| number.parse().ok()).flatten();
+    }
+    source.parse().ok()
+}
+
+/// Release notes QA agent
+pub struct ReleaseNotesQaAgent {
+    /// Milestone title
+    milestone: String,
+
+    /// Repository owner
+    owner: String,
+
+    /// Repository name
+    repo: String,
+
+    /// GitHub client
+    github_client: GitHubClient,
+
+    /// Saved runs, newest first
+    history: Vec<RunRecord>,
+
+    /// Tracked findings
+    findings: Vec<TrackedFinding>,
+
+    /// System prompt override (replaces the built-in system prompt)
+    system_prompt_override: Option<String>,
+
+    /// LLM router
+    llm_router: LlmRouter,
+}
+
+impl ReleaseNotesQaAgent {
+    /// Create a release notes QA agent over saved runs, newest first, and tracked findings
+    pub async fn new(milestone: String, owner: String, repo: String, github_client: GitHubClient, llm_router: LlmRouter,
+                     history: Vec<RunRecord>, findings: Vec<TrackedFinding>) -> Result<Self> {
+        Ok(Self {
+            milestone,
+            owner,
+            repo,
+            github_client,
+            history,
+            findings,
+            system_prompt_override: crate::prompt::load_system_prompt_override("release-notes-qa")?,
+            llm_router,
+        })
+    }
+
+    /// Read the run history and findings store, treating either one as empty when it can't be read
+    pub fn load_saved() -> (Vec<RunRecord>, Vec<TrackedFinding>) {
+        let history = RunHistory::open().and_then(|history| history.list()).unwrap_or_else(|e| {
+            tracing::debug!("Could not read saved runs: {}", e);
+            Vec::new()
+        });
+        let findings = FindingsStore::open().and_then(|store| store.query(&FindingsQuery::default())).unwrap_or_else(|e| {
+            tracing::debug!("Could not read the findings store: {}", e);
+            Vec::new()
+        });
+        (history, findings)
+    }
+
+    /// Latest saved run of a command about a pull request
+    fn latest_run(&self, command: &str, number: u64) -> Option<&RunRecord> {
+        self.history.iter()
+            .filter(|record| record.report.command == command)
+            .filter(|record| pull_request_of(&record.report.title, &self.owner, &self.repo) == Some(number))
+            .max_by(|a, b| a.recorded_at.cmp(&b.recorded_at).then_with(|| a.id.cmp(&b.id)))
+    }
+
+    /// What the stored analyses and the PR's files say about a merged pull request
+    async fn summarize(&self, pr: MergedPullRequest) -> Result<ReleasePullRequest> {
+        let files = self.github_client.get_pull_request_files(&self.owner, &self.repo, pr.number).await?;
+        let test_files = files.iter()
+            .filter(|file| file.status != "removed" && is_test_file(Path::new(""), Path::new(&file.filename)))
+            .map(|file| file.filename.clone())
+            .collect();
+
+        let analysis = self.latest_run("pr-analyze", pr.number);
+        let risk = self.latest_run("risk", pr.number);
+        let assessment = risk.and_then(|record| record.report.data.get("risk"))
+            .and_then(|risk| serde_json::from_value::<RiskAssessment>(risk.clone()).ok());
+
+        // The findings store knows which findings were fixed; without it, use the latest reports'
+        let tracked: Vec<&TrackedFinding> = self.findings.iter()
+            .filter(|tracked| pull_request_of(&tracked.scope, &self.owner, &self.repo) == Some(pr.number))
+            .collect();
+        let (open, resolved_findings): (Vec<Finding>, usize) = if tracked.is_empty() {
+            let reported: Vec<Finding> = analysis.into_iter().chain(risk).flat_map(|record| record.report.findings.iter().cloned()).collect();
+            (reported, 0)
+        } else {
+            let open = tracked.iter().filter(|tracked| tracked.status == FindingStatus::Open).map(|tracked| tracked.finding.clone()).collect();
+            (open, tracked.iter().filter(|tracked| tracked.status == FindingStatus::Resolved).count())
+        };
+        let mut fingerprints = HashSet::new();
+        let mut open_findings: Vec<Finding> = open.into_iter()
+            .filter(|finding| finding.severity > Severity::Info && fingerprints.insert(finding.fingerprint()))
+            .collect();
+        open_findings.sort_by_key(|finding| std::cmp::Reverse(finding.severity));
+
+        Ok(ReleasePullRequest {
+            number: pr.number,
+            title: pr.title,
+            author: pr.author,
+            merged_at: pr.merged_at,
+            labels: pr.labels,
+            changed_files: files.len(),
+            test_files,
+            analysis_run: analysis.map(|record| record.id.clone()),
+            risk_run: risk.map(|record| record.id.clone()),
+            overall_risk: assessment.as_ref().map(|assessment| assessment.overall_risk.to_string()),
+            risky_components: assessment.map(|assessment| assessment.component_risks.into_iter()
+                .filter(|component| matches!(component.risk_level, RiskLevel::High | RiskLevel::Critical))
+                .collect())
+                .unwrap_or_default(),
+            open_findings,
+            resolved_findings,
+        })
+    }
+}
+
+impl Agent for ReleaseNotesQaAgent {
+    fn init(&mut self) -> Result<()> {
+        // No initialization needed
+        Ok(())
+    }
+
+    async fn execute(&self) -> Result<AgentResponse> {
+        let merged = self.github_client.get_milestone_pull_requests(&self.owner, &self.repo, &self.milestone).await?;
+        if merged.is_empty() {
+            return Err(QitOpsError::InvalidInput(format!("No merged pull requests in milestone {} of {}/{}", self.milestone, self.owner, self.repo)).into());
+        }
+
+        let mut pull_requests = Vec::new();
+        for pr in merged {
+            pull_requests.push(self.summarize(pr).await?);
+        }
+        let summary = ReleaseSummary {
+            milestone: self.milestone.clone(),
+            repository: format!("{}/{}", self.owner, self.repo),
+            pull_requests,
+        };
+
+        // PR titles and findings come from contributors, so the summary is untrusted
+        let prompt = format!(
+            "Write the QA release readiness overview for milestone {} of {}.\n\n{}",
+            summary.milestone, summary.repository,
+            crate::prompt::wrap_untrusted("release summary", &serde_json::to_string_pretty(&summary)?),
+        );
+        let model = self.llm_router.default_model().unwrap_or_else(|| "tinyllama".to_string());
+        let request = LlmRequest::new(prompt, model)
+            .with_system_message(self.system_prompt_override.clone().unwrap_or_else(|| SYSTEM_PROMPT.to_string()))
+            .with_max_tokens(1024);
+        let response = self.llm_router.send(request, Some("release-notes-qa")).await?;
+
+        let appendix = summary.appendix(&response.text);
+        Ok(AgentResponse {
+            status: AgentStatus::Success,
+            message: format!("QA summary of {} merged pull request(s) in milestone {}", summary.pull_requests.len(), summary.milestone),
+            data: Some(serde_json::json!({
+                "appendix": appendix,
+                "overview": response.text.trim(),
+                "milestone": summary.milestone,
+                "repository": summary.repository,
+                "pull_requests": summary.pull_requests,
+            })),
+        })
+    }
+
+    fn name(&self) -> &str {
+        "release-notes-qa"
+    }
+
+    fn description(&self) -> &str {
+        "QA release notes appendix for a milestone"
+    }
+}
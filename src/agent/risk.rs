@@ -2,24 +2,70 @@ use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
-
+#[cfg(feature = "github")]
+use std::path::PathBuf;
+
+use crate::agent::baseline::{BASELINE_FILE, BaselineMode, apply_baseline};
+use crate::agent::diff_filter::{DiffFilters, FilterReport};
+use crate::agent::findings::Finding;
+use crate::agent::infra::{InfraChange, infra_changes, infra_prompt};
+use crate::agent::injection::detect_injection;
+use crate::agent::ownership::Ownership;
+use crate::agent::patch::{self, PatchSeries};
+use crate::agent::risk_score::RiskScoring;
+use crate::agent::sources::SourceSelection;
 use crate::agent::traits::{Agent, AgentResponse, AgentStatus};
+#[cfg(feature = "github")]
+use crate::ci::fallback::{self, PrCache};
+#[cfg(feature = "github")]
 use crate::ci::github::GitHubClient;
 use crate::llm::{LlmRequest, LlmRouter};
+use crate::error::QitOpsError;
+use crate::severity::Severity;
+
+/// Instructions for the JSON document the risk agent asks for
+const RISK_JSON_INSTRUCTIONS: &str = "Respond with a single JSON object of the form {\"overall_risk\": \"Low|Medium|High|Critical\", \"component_risks\": [{\"component\": \"name\", \"risk_level\": \"Low|Medium|High|Critical\", \"description\": \"why\"}], \"summary\": \"overall assessment\", \"recommendations\": [\"actionable recommendation\"], \"findings\": [{\"file\": \"path/to/file\", \"line\": 42, \"severity\": \"info|low|medium|high|critical\", \"title\": \"short title\", \"description\": \"details\"}]}. Use the file paths and new-file line numbers from the diff; use null when a finding is not tied to a line. Use empty lists when there is nothing to report.";
 
 /// Risk level
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum RiskLevel {
     /// Low risk
+    #[serde(alias = "low", alias = "LOW")]
     Low,
     /// Medium risk
+    #[serde(alias = "medium", alias = "MEDIUM")]
     Medium,
     /// High risk
+    #[serde(alias = "high", alias = "HIGH")]
     High,
     /// Critical risk
+    #[serde(alias = "critical", alias = "CRITICAL")]
     Critical,
 }
 
+impl RiskLevel {
+    /// The severity of the same name, for gates and scoring
+    pub fn severity(self) -> Severity {
+        match self {
+            RiskLevel::Low => Severity::Low,
+            RiskLevel::Medium => Severity::Medium,
+            RiskLevel::High => Severity::High,
+            RiskLevel::Critical => Severity::Critical,
+        }
+    }
+}
+
+impl std::fmt::Display for RiskLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RiskLevel::Low => write!(f, "Low"),
+            RiskLevel::Medium => write!(f, "Medium"),
+            RiskLevel::High => write!(f, "High"),
+            RiskLevel::Critical => write!(f, "Critical"),
+        }
+    }
+}
+
 /// Risk assessment result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RiskAssessment {
@@ -36,6 +82,41 @@ pub struct RiskAssessment {
     pub recommendations: Vec<String>,
 }
 
+impl RiskAssessment {
+    /// Render the assessment as a Markdown report
+    pub fn to_markdown(&self) -> String {
+        let mut report = format!("**Overall risk:** {}\n\n{}\n", self.overall_risk, self.summary.trim());
+
+        if !self.component_risks.is_empty() {
+            report.push_str("\n## Component Risks\n\n");
+            for risk in &self.component_risks {
+                report.push_str(&format!("- **{}** ({}): {}\n", risk.component, risk.risk_level, risk.description));
+            }
+        }
+
+        if !self.recommendations.is_empty() {
+            report.push_str("\n## Recommendations\n\n");
+            for recommendation in &self.recommendations {
+                report.push_str(&format!("- {}\n", recommendation));
+            }
+        }
+
+        report
+    }
+}
+
+/// JSON document returned by the model: the assessment plus its findings
+#[derive(Debug, Deserialize)]
+struct RiskReport {
+    /// Risk assessment
+    #[serde(flatten)]
+    assessment: RiskAssessment,
+
+    /// Findings
+    #[serde(default)]
+    findings: Vec<Finding>,
+}
+
 /// Component risk
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComponentRisk {
@@ -61,16 +142,38 @@ pub struct RiskAgent {
     focus_areas: Vec<String>,
 
     /// GitHub client (if using PR)
+    #[cfg(feature = "github")]
     github_client: Option<GitHubClient>,
 
+    /// System prompt override (replaces the built-in system prompt)
+    system_prompt_override: Option<String>,
+
     /// LLM router
     llm_router: LlmRouter,
 
     /// Repository owner (if using PR)
+    #[cfg(feature = "github")]
     owner: Option<String>,
 
     /// Repository name (if using PR)
+    #[cfg(feature = "github")]
     repo: Option<String>,
+
+    /// Local clone to diff when GitHub can't be reached and nothing is cached (if using PR)
+    #[cfg(feature = "github")]
+    repo_dir: Option<PathBuf>,
+
+    /// How the findings baseline is applied
+    baseline_mode: BaselineMode,
+
+    /// Sources to attach, explicitly or by changed paths
+    sources: SourceSelection,
+
+    /// Noise filters run on the diff before assessment
+    diff_filters: DiffFilters,
+
+    /// Rubric for the numeric risk score
+    scoring: RiskScoring,
 }
 
 impl RiskAgent {
@@ -85,14 +188,25 @@ impl RiskAgent {
             diff_source: diff_path,
             components,
             focus_areas,
+            #[cfg(feature = "github")]
             github_client: None,
+            system_prompt_override: crate::prompt::load_system_prompt_override("risk")?,
             llm_router,
+            #[cfg(feature = "github")]
             owner: None,
+            #[cfg(feature = "github")]
             repo: None,
+            #[cfg(feature = "github")]
+            repo_dir: None,
+            baseline_mode: BaselineMode::default(),
+            sources: SourceSelection::default(),
+            diff_filters: DiffFilters::default(),
+            scoring: RiskScoring::default(),
         })
     }
 
     /// Create a new risk assessment agent for a PR
+    #[cfg(feature = "github")]
     pub async fn new_from_pr(
         pr: String,
         components: Vec<String>,
@@ -107,23 +221,54 @@ impl RiskAgent {
             components,
             focus_areas,
             github_client: Some(github_client),
+            system_prompt_override: crate::prompt::load_system_prompt_override("risk")?,
             llm_router,
             owner: Some(owner),
             repo: Some(repo),
+            repo_dir: Some(std::env::current_dir()?),
+            baseline_mode: BaselineMode::default(),
+            sources: SourceSelection::default(),
+            diff_filters: DiffFilters::default(),
+            scoring: RiskScoring::default(),
         })
     }
 
+    /// Set how the findings baseline is applied
+    pub fn with_baseline(mut self, mode: BaselineMode) -> Self {
+        self.baseline_mode = mode;
+        self
+    }
+
+    /// Set the sources to attach, explicitly or by changed paths
+    pub fn with_sources(mut self, sources: SourceSelection) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    /// Set the noise filters run on the diff before assessment
+    pub fn with_diff_filters(mut self, filters: DiffFilters) -> Self {
+        self.diff_filters = filters;
+        self
+    }
+
+    /// Set the rubric for the numeric risk score
+    pub fn with_scoring(mut self, scoring: RiskScoring) -> Self {
+        self.scoring = scoring;
+        self
+    }
+
     /// Read the diff from a file
     fn read_diff_file(&self) -> Result<String> {
         let path = Path::new(&self.diff_source);
         if !path.exists() {
-            return Err(anyhow::anyhow!("Diff file not found: {}", self.diff_source));
+            return Err(QitOpsError::InvalidInput(format!("Diff file not found: {}", self.diff_source)).into());
         }
 
         fs::read_to_string(path).context(format!("Failed to read diff file: {}", self.diff_source))
     }
 
     /// Extract PR number from a PR string (number or URL)
+    #[cfg(feature = "github")]
     fn extract_pr_number(&self) -> Result<u64> {
         // If it's just a number, parse it directly
         if let Ok(num) = self.diff_source.parse::<u64>() {
@@ -139,32 +284,92 @@ impl RiskAgent {
             }
         }
 
-        Err(anyhow::anyhow!("Invalid PR format: {}", self.diff_source))
+        Err(QitOpsError::InvalidInput(format!("Invalid PR format: {}", self.diff_source)).into())
     }
 
     /// Generate the prompt for the LLM
-    fn generate_prompt(&self, diff: &str) -> String {
-        let components_str = if self.components.is_empty() {
-            "all components".to_string()
-        } else {
-            format!("the following components: {}", self.components.join(", "))
-        };
+    fn generate_prompt(&self, diff: &str, infra: &[InfraChange], ownership: &str) -> Result<String> {
+        crate::prompt::render_prompt("risk", &serde_json::json!({
+            "diff": crate::prompt::wrap_untrusted("diff", diff),
+            "components": self.components,
+            "focus_areas": self.focus_areas,
+            "infra": infra_prompt(infra),
+            "ownership": ownership,
+            "findings_instructions": RISK_JSON_INSTRUCTIONS,
+        }))
+    }
 
-        let focus_str = if self.focus_areas.is_empty() {
-            "general risk factors".to_string()
-        } else {
-            format!("the following risk areas: {}", self.focus_areas.join(", "))
-        };
+    /// Ask the model for a risk assessment of a diff
+    async fn assess(&self, diff: &str, source_context: &str, ownership: &str) -> Result<RiskReport> {
+        // Infrastructure changes are parsed before the diff is condensed, so none are lost
+        let infra = infra_changes(diff);
+
+        // Diffs too large for one prompt are summarized in chunks first
+        let diff = self.llm_router.condense(diff, "diff", "a risk assessment", Some("risk")).await?;
+        let prompt = self.generate_prompt(&diff.text, &infra, ownership)?;
+
+        // Create the LLM request
+        let model = self.llm_router.default_model().unwrap_or_else(|| "tinyllama".to_string());
+        let request = LlmRequest::new(prompt, model)
+            .with_system_message(self.system_prompt(!infra.is_empty()))
+            .with_source_context(source_context);
+
+        // Send the request to the LLM, re-prompting until the answer matches the report schema
+        let (report, _) = self.llm_router.send_json::<RiskReport, _>(request, Some("risk"), |report| {
+            if report.assessment.summary.trim().is_empty() {
+                return Err(anyhow::anyhow!("The 'summary' field must not be empty"));
+            }
+            Ok(())
+        }).await?;
 
-        format!(
-            "Assess the risk of the following code changes. Focus on {} and {}.\n\nDiff:\n```\n{}\n```\n\nProvide a risk assessment with an overall risk level (Low, Medium, High, or Critical), component-specific risks, a summary, and recommendations.",
-            components_str, focus_str, diff
-        )
+        Ok(report)
     }
 
-    /// Get the system prompt
-    fn system_prompt(&self) -> String {
-        "You are a risk assessment expert. Analyze code changes and provide a detailed risk assessment. Consider factors like complexity, scope of changes, critical components affected, potential for regressions, security implications, and performance impact. Provide your assessment in a structured format with an overall risk level, component-specific risks, a summary, and actionable recommendations.".to_string()
+    /// Assess each patch of a series and the series as a whole
+    ///
+    /// Returns the series-level report with the findings of all patches merged in, plus a
+    /// summary of each patch.
+    async fn assess_series(&self, series: &PatchSeries, source_context: &str, ownership: &str) -> Result<(RiskReport, Vec<serde_json::Value>)> {
+        let per_patch = futures_util::future::try_join_all(
+            series.patches.iter().map(|patch| self.assess(&patch.diff, source_context, ownership))
+        ).await?;
+        let mut report = self.assess(&series.combined_diff(), source_context, ownership).await?;
+
+        let mut patches = Vec::new();
+        let mut patch_findings = Vec::new();
+        report.assessment.summary.push_str("\n\n## Patches\n");
+        for (patch, patch_report) in series.patches.iter().zip(per_patch) {
+            report.assessment.summary.push_str(&format!(
+                "\n### Patch {}/{}: {}\n\n**Risk:** {}\n\n{}\n",
+                patch.number, series.patches.len(), patch.subject,
+                patch_report.assessment.overall_risk, patch_report.assessment.summary.trim()
+            ));
+            patches.push(serde_json::json!({
+                "number": patch.number,
+                "subject": patch.subject,
+                "author": patch.author,
+                "files": patch.files(),
+                "risk": patch_report.assessment,
+                "findings": patch_report.findings,
+            }));
+            patch_findings.extend(patch_report.findings);
+        }
+        report.findings = patch::merge_findings(report.findings, patch_findings);
+
+        Ok((report, patches))
+    }
+
+    /// Get the system prompt, with infrastructure expertise when the diff changes infrastructure
+    fn system_prompt(&self, infra: bool) -> String {
+        if let Some(system_prompt) = &self.system_prompt_override {
+            return system_prompt.clone();
+        }
+
+        let mut prompt = "You are a risk assessment expert. Analyze code changes and provide a detailed risk assessment. Consider factors like complexity, scope of changes, critical components affected, potential for regressions, security implications, and performance impact. Provide your assessment in a structured format with an overall risk level, component-specific risks, a summary, and actionable recommendations.".to_string();
+        if infra {
+            prompt.push_str(" You are also an infrastructure and platform engineer: treat each changed Terraform resource, Kubernetes manifest, Dockerfile stage and CI job as a component, and weigh what it exposes, the capacity it claims, the secrets it handles and how safely it can be rolled out and back.");
+        }
+        prompt
     }
 }
 
@@ -176,37 +381,99 @@ impl Agent for RiskAgent {
 
     async fn execute(&self) -> Result<AgentResponse> {
         // Get the diff
-        let diff = if self.github_client.is_some() {
-            // Get diff from GitHub PR
+        #[cfg(feature = "github")]
+        let (diff, degraded, author) = if let Some(github_client) = &self.github_client {
+            // Get diff from GitHub PR, falling back to local data if GitHub is unavailable
             let pr_number = self.extract_pr_number()?;
             let owner = self.owner.as_ref().ok_or_else(|| anyhow::anyhow!("Repository owner not specified"))?;
             let repo = self.repo.as_ref().ok_or_else(|| anyhow::anyhow!("Repository name not specified"))?;
+            let repo_dir = self.repo_dir.as_deref().unwrap_or(Path::new("."));
 
-            self.github_client.as_ref().unwrap().get_pull_request_diff(owner, repo, pr_number).await?
+            let fetch = fallback::fetch_pr(github_client, owner, repo, pr_number, &PrCache::new()?, repo_dir).await?;
+            (fetch.data.diff, fetch.degraded, Some(fetch.data.pull_request.author))
         } else {
             // Read diff from file
-            self.read_diff_file()?
+            (self.read_diff_file()?, None, None)
+        };
+        #[cfg(not(feature = "github"))]
+        let (diff, degraded, author) = (self.read_diff_file()?, None::<String>, None::<String>);
+        crate::debug::record_piece("diff", &self.diff_source, &diff);
+
+        // Attach sources for the files the diff touches
+        let files = crate::agent::diff::parse_diff(&diff).iter().map(|file| file.path().to_string()).collect::<Vec<_>>();
+        let (source_context, sources, source_rules) = self.sources.load(&files)?;
+        let source_context = self.llm_router.condense(&source_context, "set of source documents", "a risk assessment", Some("risk")).await?.text;
+
+        // Who owns the touched code, how recently it changed and how often, from CODEOWNERS and git
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        #[cfg(feature = "github")]
+        let repo_dir = self.repo_dir.as_deref().unwrap_or(Path::new("."));
+        #[cfg(not(feature = "github"))]
+        let repo_dir = Path::new(".");
+        let ownership = Ownership::collect(repo_dir, &files, author.as_deref(), now);
+        let ownership_prompt = ownership.as_ref().map(|ownership| ownership.prompt(now)).unwrap_or_default();
+
+        // Assess a `git format-patch` series patch by patch and as a whole, anything else in one
+        // go with the noise filtered out
+        let (diff, report, patches, filtered) = match patch::parse_series(&diff) {
+            Some(series) => {
+                let (report, patches) = self.assess_series(&series, &source_context, &ownership_prompt).await?;
+                (series.combined_diff(), report, Some(patches), FilterReport::default())
+            }
+            None => {
+                let (filtered_diff, filtered) = self.diff_filters.apply(&diff);
+                let report = self.assess(&filtered_diff, &source_context, &ownership_prompt).await?;
+                (diff, report, None, filtered)
+            }
         };
 
-        // Generate the prompt
-        let prompt = self.generate_prompt(&diff);
+        let infra = infra_changes(&diff);
+        let mut assessment = report.assessment.to_markdown();
+        if !infra.is_empty() {
+            assessment.push_str("\n## Infrastructure Changes\n\n");
+            for change in &infra {
+                assessment.push_str(&format!("- {}\n", change.describe()));
+            }
+        }
+        if let Some(ownership) = ownership.as_ref().filter(|ownership| !ownership.files.is_empty()) {
+            assessment.push('\n');
+            assessment.push_str(&ownership.to_markdown(now));
+        }
 
-        // Create the LLM request
-        let model = self.llm_router.default_model().unwrap_or_else(|| "tinyllama".to_string());
-        let request = LlmRequest::new(prompt, model)
-            .with_system_message(self.system_prompt());
+        // Flag injection attempts in the diff, then apply the baseline to the findings
+        let mut findings = report.findings;
+        findings.extend(detect_injection(&diff, None, None));
+        let outcome = apply_baseline(findings, &diff, self.baseline_mode, Path::new(BASELINE_FILE))?;
 
-        // Send the request to the LLM
-        let response = self.llm_router.send(request, Some("risk")).await?;
+        // Score the assessment and its new findings by the rubric
+        let score = self.scoring.score(&report.assessment, &outcome.new);
+        let assessment = format!("{}\n\n{}", score.to_markdown(), assessment);
 
         // Return the response
         Ok(AgentResponse {
             status: AgentStatus::Success,
-            message: "Risk assessment completed".to_string(),
+            message: match &degraded {
+                Some(_) => "Risk assessment completed (degraded)".to_string(),
+                None => "Risk assessment completed".to_string(),
+            },
             data: Some(serde_json::json!({
-                "assessment": response.text,
+                "assessment": assessment,
+                "risk": report.assessment,
+                "score": score,
                 "components": self.components,
                 "focus_areas": self.focus_areas,
+                "findings": outcome.new,
+                "baselined": outcome.baselined,
+                "suppressed": outcome.suppressed,
+                "baseline_written": outcome.baseline_written,
+                "patches": patches,
+                "infra_changes": infra,
+                "ownership": ownership,
+                "filtered": filtered,
+                "sources": sources,
+                "source_rules": source_rules,
+                "diff": diff,
+                "degraded": degraded,
             })),
         })
     }
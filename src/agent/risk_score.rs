@@ -0,0 +1,190 @@
+// Numeric risk scores: a rubric in the config turns a risk assessment into a score from 0 to 100
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::agent::findings::Finding;
+use crate::agent::risk::RiskAssessment;
+use crate::severity::Severity;
+
+/// Highest risk score
+pub const MAX_RISK_SCORE: f64 = 100.0;
+
+/// Rubric for numeric risk scores (the `risk_scoring` section of the config)
+///
+/// The score starts from the points for the overall risk level, or the weighted mean of the
+/// component scores if that is higher, and each finding adds the points for its severity,
+/// up to 100.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RiskScoring {
+    /// Points for an overall or component risk level
+    pub levels: HashMap<Severity, f64>,
+
+    /// Points added by each finding, by severity
+    pub findings: HashMap<Severity, f64>,
+
+    /// Weight of components in the overall score by name (case-insensitive); others weigh 1
+    pub weights: HashMap<String, f64>,
+
+    /// Score ranges, each from its `min` up to the next one's
+    pub thresholds: Vec<ScoreThreshold>,
+}
+
+impl Default for RiskScoring {
+    fn default() -> Self {
+        Self {
+            levels: HashMap::from([
+                (Severity::Low, 10.0),
+                (Severity::Medium, 35.0),
+                (Severity::High, 65.0),
+                (Severity::Critical, 90.0),
+            ]),
+            findings: HashMap::from([
+                (Severity::Critical, 10.0),
+                (Severity::High, 5.0),
+                (Severity::Medium, 2.0),
+                (Severity::Low, 1.0),
+            ]),
+            weights: HashMap::new(),
+            thresholds: vec![
+                ScoreThreshold::new(0.0, "low"),
+                ScoreThreshold::new(25.0, "medium"),
+                ScoreThreshold::new(50.0, "high"),
+                ScoreThreshold::new(75.0, "critical"),
+            ],
+        }
+    }
+}
+
+/// A score range with its label, and the exit code of `qitops run risk` for scores in it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreThreshold {
+    /// Lowest score in the range
+    pub min: f64,
+
+    /// Label for scores in the range
+    pub label: String,
+
+    /// Exit code for scores in the range; none exits normally
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+}
+
+impl ScoreThreshold {
+    /// A range without an exit code
+    pub fn new(min: f64, label: &str) -> Self {
+        Self { min, label: label.to_string(), exit_code: None }
+    }
+}
+
+/// Numeric score of a risk assessment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiskScore {
+    /// Score from 0 to 100
+    pub score: u32,
+
+    /// Label of the score's range
+    pub label: String,
+
+    /// Exit code of the score's range
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+
+    /// Sub-score of each component assessed
+    pub components: Vec<ComponentScore>,
+}
+
+impl RiskScore {
+    /// The score as a Markdown line, to lead the assessment
+    pub fn to_markdown(&self) -> String {
+        let mut line = format!("**Risk score:** {}/100 ({})", self.score, self.label);
+        if !self.components.is_empty() {
+            let components: Vec<String> = self.components.iter()
+                .map(|component| format!("{} {}", component.component, component.score))
+                .collect();
+            line.push_str(&format!("; components: {}", components.join(", ")));
+        }
+        line
+    }
+}
+
+/// Sub-score of one component
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentScore {
+    /// Component name
+    pub component: String,
+
+    /// Score from 0 to 100
+    pub score: u32,
+
+    /// Weight of the component in the overall score
+    pub weight: f64,
+}
+
+impl RiskScoring {
+    /// Check that points are within 0 to 100 and the thresholds start at 0 and rise
+    pub fn validate(&self) -> Result<()> {
+        for (severity, points) in self.levels.iter().chain(&self.findings) {
+            if !(0.0..=MAX_RISK_SCORE).contains(points) {
+                return Err(anyhow!("risk_scoring: points for {} must be between 0 and 100, not {}", severity, points));
+            }
+        }
+        if let Some((name, weight)) = self.weights.iter().find(|(_, weight)| **weight < 0.0) {
+            return Err(anyhow!("risk_scoring: the weight of {} must not be negative, not {}", name, weight));
+        }
+
+        if self.thresholds.first().is_none_or(|threshold| threshold.min != 0.0) {
+            return Err(anyhow!("risk_scoring: the first threshold must start at 0"));
+        }
+        if let Some(pair) = self.thresholds.windows(2).find(|pair| pair[1].min <= pair[0].min || pair[1].min > MAX_RISK_SCORE) {
+            return Err(anyhow!("risk_scoring: threshold {} must be above {} and at most 100", pair[1].min, pair[0].min));
+        }
+        if let Some(threshold) = self.thresholds.iter().find(|threshold| threshold.exit_code.is_some_and(|code| !(1..=125).contains(&code))) {
+            return Err(anyhow!("risk_scoring: the exit code of {} must be between 1 and 125", threshold.label));
+        }
+
+        Ok(())
+    }
+
+    /// Score an assessment and its findings
+    pub fn score(&self, assessment: &RiskAssessment, findings: &[Finding]) -> RiskScore {
+        let points = |level: Severity| self.levels.get(&level).copied().unwrap_or_default();
+
+        let components: Vec<ComponentScore> = assessment.component_risks.iter()
+            .map(|risk| ComponentScore {
+                component: risk.component.clone(),
+                score: points(risk.risk_level.severity()).round() as u32,
+                weight: self.weight(&risk.component),
+            })
+            .collect();
+
+        let total_weight: f64 = components.iter().map(|component| component.weight).sum();
+        let component_mean = match total_weight > 0.0 {
+            true => components.iter().map(|component| component.score as f64 * component.weight).sum::<f64>() / total_weight,
+            false => 0.0,
+        };
+        let finding_points: f64 = findings.iter()
+            .map(|finding| self.findings.get(&finding.severity).copied().unwrap_or_default())
+            .sum();
+        let score = (points(assessment.overall_risk.severity()).max(component_mean) + finding_points)
+            .min(MAX_RISK_SCORE)
+            .round();
+
+        let threshold = self.thresholds.iter().rev().find(|threshold| score >= threshold.min);
+        RiskScore {
+            score: score as u32,
+            label: threshold.map_or_else(String::new, |threshold| threshold.label.clone()),
+            exit_code: threshold.and_then(|threshold| threshold.exit_code),
+            components,
+        }
+    }
+
+    /// Weight of a component by name
+    fn weight(&self, component: &str) -> f64 {
+        self.weights.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(component))
+            .map_or(1.0, |(_, weight)| *weight)
+    }
+}
@@ -0,0 +1,556 @@
+use anyhow::{Result, Context};
+#[cfg(not(feature = "pairing"))]
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::Mutex;
+
+#[cfg(feature = "pairing")]
+use crate::agent::pairing::PairHost;
+use crate::agent::traits::{Agent, AgentResponse, AgentStatus};
+use crate::cli::branding;
+use crate::ci::CiContext;
+use crate::config::QitOpsConfigManager;
+use crate::llm::{Conversation, LlmRequest, LlmRouter, SOURCES_MARKER};
+
+/// Stand-in for the pairing host in builds without the `pairing` feature, which never host
+#[cfg(not(feature = "pairing"))]
+enum PairHost {}
+
+#[cfg(not(feature = "pairing"))]
+impl PairHost {
+    async fn next_input(&mut self) -> Option<(String, String)> {
+        match *self {}
+    }
+
+    fn broadcast(&self, _author: &str, _text: &str) {
+        match *self {}
+    }
+
+    fn end(&self) {
+        match *self {}
+    }
+}
+
+/// Slash command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlashCommand {
+    /// Propose the next exploratory testing steps
+    Plan,
+    /// Report which areas the session has covered so far
+    Coverage,
+    /// Summarize the session so far
+    Summarize,
+    /// Assess the risk of an area
+    Risk(String),
+    /// Show objective coverage
+    Status,
+    /// List the available commands
+    Help,
+    /// End the session
+    Exit,
+}
+
+/// Slash command names with their descriptions, used for help and autocomplete
+const SLASH_COMMANDS: &[(&str, &str)] = &[
+    ("/plan", "Propose the next exploratory testing steps"),
+    ("/coverage", "Report which areas the session has covered so far"),
+    ("/summarize", "Summarize the session so far"),
+    ("/risk", "Assess the risk of an area: /risk <area>"),
+    ("/status", "Show which session objectives are covered"),
+    ("/help", "List the available commands"),
+    ("/exit", "End the session and save the transcript"),
+];
+
+/// Result of parsing a line of session input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionInput {
+    /// Free-form chat message
+    Chat(String),
+    /// Slash command
+    Command(SlashCommand),
+    /// Slash command prefix matching several commands
+    Ambiguous(Vec<String>),
+    /// Unknown slash command
+    Unknown(String),
+}
+
+impl SlashCommand {
+    /// Complete a slash command prefix to the matching command names
+    pub fn complete(prefix: &str) -> Vec<&'static str> {
+        let prefix = prefix.to_lowercase();
+        SLASH_COMMANDS.iter()
+            .map(|(name, _)| *name)
+            .filter(|name| name.starts_with(&prefix))
+            .collect()
+    }
+
+    /// Parse a line of input, autocompleting unambiguous command prefixes
+    pub fn parse(input: &str) -> SessionInput {
+        let input = input.trim();
+        if !input.starts_with('/') {
+            if input.eq_ignore_ascii_case("exit") || input.eq_ignore_ascii_case("quit") {
+                return SessionInput::Command(SlashCommand::Exit);
+            }
+            return SessionInput::Chat(input.to_string());
+        }
+
+        let (word, rest) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+        let rest = rest.trim().to_string();
+
+        // Exact matches win over prefix matches
+        let candidates = match SLASH_COMMANDS.iter().find(|(name, _)| name.eq_ignore_ascii_case(word)) {
+            Some((name, _)) => vec![*name],
+            None => Self::complete(word),
+        };
+
+        match candidates.as_slice() {
+            [] if word == "/quit" => SessionInput::Command(SlashCommand::Exit),
+            [] => SessionInput::Unknown(word.to_string()),
+            [name] => match *name {
+                "/plan" => SessionInput::Command(SlashCommand::Plan),
+                "/coverage" => SessionInput::Command(SlashCommand::Coverage),
+                "/summarize" => SessionInput::Command(SlashCommand::Summarize),
+                "/risk" => SessionInput::Command(SlashCommand::Risk(rest)),
+                "/status" => SessionInput::Command(SlashCommand::Status),
+                "/help" => SessionInput::Command(SlashCommand::Help),
+                _ => SessionInput::Command(SlashCommand::Exit),
+            },
+            names => SessionInput::Ambiguous(names.iter().map(|n| n.to_string()).collect()),
+        }
+    }
+
+    /// Build the LLM instruction for this command, or None if it is handled locally
+    fn instruction(&self) -> Option<String> {
+        match self {
+            SlashCommand::Plan => Some("Based on the session so far, propose the next 3-5 concrete exploratory testing steps. For each step give the goal, what to try, and what to look for.".to_string()),
+            SlashCommand::Coverage => Some("List the areas and behaviours this session has covered so far, then list notable areas that have not been explored yet.".to_string()),
+            SlashCommand::Summarize => Some("Summarize the session so far: what was tested, issues or anomalies found, open questions, and suggested follow-ups.".to_string()),
+            SlashCommand::Risk(area) if area.is_empty() => Some("Identify the riskiest areas touched on in this session and explain why.".to_string()),
+            SlashCommand::Risk(area) => Some(format!("Assess the risk of the area '{}' in the context of this session: likely failure modes, impact, and the tests that would best reduce the risk.", area)),
+            SlashCommand::Status | SlashCommand::Help | SlashCommand::Exit => None,
+        }
+    }
+
+    /// LLM task name used for routing
+    fn task(&self) -> &'static str {
+        match self {
+            SlashCommand::Risk(_) => "risk",
+            _ => "session",
+        }
+    }
+}
+
+/// An item on the session objectives checklist
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChecklistItem {
+    /// What needs to be explored
+    pub description: String,
+
+    /// Whether the conversation has addressed this item
+    pub covered: bool,
+}
+
+/// Interactive exploratory testing session agent
+pub struct SessionAgent {
+    /// Session name
+    name: String,
+
+    /// Session objectives
+    objectives: Vec<String>,
+
+    /// Path to a session charter
+    charter: Option<String>,
+
+    /// Checklist derived from the objectives and charter
+    checklist: Mutex<Vec<ChecklistItem>>,
+
+    /// Name of the local participant, used to attribute messages
+    participant: String,
+
+    /// Address to host the session on for remote participants
+    host: Option<String>,
+
+    /// Sources to use
+    sources: Vec<String>,
+
+    /// Personas to use
+    personas: Vec<String>,
+
+    /// System prompt override (replaces the built-in system prompt)
+    system_prompt_override: Option<String>,
+
+    /// Session transcript
+    conversation: Mutex<Conversation>,
+
+    /// LLM router
+    llm_router: LlmRouter,
+}
+
+impl SessionAgent {
+    /// Create a new session agent
+    pub async fn new(
+        name: String,
+        sources: Vec<String>,
+        personas: Vec<String>,
+        llm_router: LlmRouter,
+    ) -> Result<Self> {
+        let mut agent = Self {
+            name,
+            objectives: Vec::new(),
+            charter: None,
+            checklist: Mutex::new(Vec::new()),
+            participant: "Tester".to_string(),
+            host: None,
+            sources,
+            personas,
+            system_prompt_override: crate::prompt::load_system_prompt_override("session")?,
+            conversation: Mutex::new(Conversation::new(String::new())),
+            llm_router,
+        };
+        *agent.conversation.get_mut() = Conversation::new(agent.system_prompt()?);
+
+        Ok(agent)
+    }
+
+    /// Set the session objectives
+    pub fn with_objectives(mut self, objectives: Vec<String>) -> Self {
+        self.objectives = objectives;
+        self
+    }
+
+    /// Set the session charter file
+    pub fn with_charter(mut self, charter: Option<String>) -> Self {
+        self.charter = charter;
+        self
+    }
+
+    /// Set the local participant name
+    pub fn with_participant(mut self, participant: String) -> Self {
+        self.participant = participant;
+        self
+    }
+
+    /// Host the session on the given address so others can join
+    pub fn with_host(mut self, host: Option<String>) -> Self {
+        self.host = host;
+        self
+    }
+
+    /// Build the checklist from the objectives and the charter
+    async fn build_checklist(&self) -> Result<()> {
+        let mut items = self.objectives.clone();
+
+        if let Some(charter_path) = &self.charter {
+            let charter = fs::read_to_string(charter_path)
+                .context(format!("Failed to read charter: {}", charter_path))?;
+            crate::debug::record_piece("charter", charter_path, &charter);
+
+            let prompt = format!(
+                "Derive a checklist of 3-10 concrete areas to explore from the following exploratory testing charter. Reply with one item per line and nothing else.\n\nCharter:\n{}",
+                charter
+            );
+            let model = self.llm_router.default_model().unwrap_or_else(|| "tinyllama".to_string());
+            let request = LlmRequest::new(prompt, model)
+                .with_system_message("You are an exploratory testing coach. Turn charters into short, checkable objectives.".to_string());
+            let response = self.llm_router.send(request, Some("session")).await?;
+
+            items.extend(response.text.lines()
+                .map(|line| line.trim().trim_start_matches(['-', '*', '•', ' ']).trim())
+                .map(|line| line.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.' || c == ')').trim())
+                .filter(|line| !line.is_empty())
+                .map(|line| line.to_string()));
+        }
+
+        *self.checklist.lock().await = items.into_iter()
+            .map(|description| ChecklistItem { description, covered: false })
+            .collect();
+
+        Ok(())
+    }
+
+    /// Ask the LLM which remaining checklist items the latest exchange covered
+    async fn update_coverage(&self) -> Result<()> {
+        let remaining: Vec<(usize, String)> = self.checklist.lock().await.iter()
+            .enumerate()
+            .filter(|(_, item)| !item.covered)
+            .map(|(index, item)| (index, item.description.clone()))
+            .collect();
+
+        if remaining.is_empty() {
+            return Ok(());
+        }
+
+        let exchange = {
+            let conversation = self.conversation.lock().await;
+            let start = conversation.len().saturating_sub(2);
+            Conversation::render(&conversation.turns()[start..])
+        };
+
+        let items = remaining.iter()
+            .map(|(index, description)| format!("{}. {}", index + 1, description))
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        let prompt = format!(
+            "Checklist:\n{}\n\nLatest exchange:\n{}\n\nWhich checklist items did the latest exchange actually explore or test? Reply with the item numbers separated by commas, or 'none'.",
+            items, exchange
+        );
+        let model = self.llm_router.default_model().unwrap_or_else(|| "tinyllama".to_string());
+        let request = LlmRequest::new(prompt, model)
+            .with_system_message("You classify exploratory testing conversations against a checklist. Only count an item when it was clearly addressed.".to_string())
+            .with_temperature(0.0);
+        let response = self.llm_router.send(request, Some("session")).await?;
+
+        let covered: Vec<usize> = response.text
+            .split(|c: char| !c.is_ascii_digit())
+            .filter_map(|n| n.parse::<usize>().ok())
+            .collect();
+
+        let mut checklist = self.checklist.lock().await;
+        for number in covered {
+            if remaining.iter().any(|(index, _)| index + 1 == number) {
+                checklist[number - 1].covered = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Render the checklist with coverage
+    async fn coverage_report(&self) -> String {
+        let checklist = self.checklist.lock().await;
+        if checklist.is_empty() {
+            return "No objectives set for this session (use --objectives or --charter).".to_string();
+        }
+
+        let covered = checklist.iter().filter(|item| item.covered).count();
+        let mut report = format!("Objective coverage: {}/{}\n", covered, checklist.len());
+        for item in checklist.iter() {
+            report.push_str(&format!("  [{}] {}\n", if item.covered { "x" } else { " " }, item.description));
+        }
+
+        report.trim_end().to_string()
+    }
+
+    /// Get the system prompt
+    fn system_prompt(&self) -> Result<String> {
+        let mut prompt = match &self.system_prompt_override {
+            Some(system_prompt) => system_prompt.clone(),
+            None => format!(
+                "You are QitOps, a pair tester in an interactive exploratory testing session named '{}'. Help the tester explore the system under test: suggest test ideas, question assumptions, point out risks, and keep track of what has been tested. Be concise.",
+                self.name
+            ),
+        };
+
+        // Add sources if available
+        if !self.sources.is_empty() {
+            let source_manager = crate::cli::source::SourceManager::new()?;
+            let source_content = source_manager.get_content_for_sources(&self.sources)?;
+
+            if !source_content.is_empty() {
+                prompt.push_str(SOURCES_MARKER);
+                prompt.push_str(&source_content);
+            }
+        }
+
+        // Add personas if available
+        if !self.personas.is_empty() {
+            let persona_manager = crate::cli::persona::PersonaManager::new()?;
+            let persona_prompt = persona_manager.get_prompt_for_personas(&self.personas)?;
+
+            if !persona_prompt.is_empty() {
+                prompt = format!("{}\n\n{}", persona_prompt, prompt);
+            }
+        }
+
+        Ok(prompt)
+    }
+
+    /// Send the transcript plus an optional instruction to the LLM
+    async fn ask(&self, instruction: Option<String>, task: &str) -> Result<String> {
+        let model = self.llm_router.default_model().unwrap_or_else(|| "tinyllama".to_string());
+        let request = self.conversation.lock().await
+            .request(model, instruction.as_deref(), self.llm_router.context_window(Some(task)));
+
+        let response = self.llm_router.send(request, Some(task)).await?;
+        Ok(response.text)
+    }
+
+    /// Handle a line of input from a participant, returning None when the session should end
+    pub async fn handle_input(&self, author: &str, input: &str) -> Result<Option<String>> {
+        match SlashCommand::parse(input) {
+            SessionInput::Chat(text) if text.is_empty() => Ok(Some(String::new())),
+            SessionInput::Chat(text) => {
+                self.conversation.lock().await.push_user(author, text);
+                let reply = self.ask(None, "session").await?;
+                self.conversation.lock().await.push_assistant("QitOps", reply.clone());
+
+                if let Err(e) = self.update_coverage().await {
+                    tracing::warn!("Failed to update session coverage: {}", e);
+                }
+
+                Ok(Some(reply))
+            },
+            SessionInput::Command(SlashCommand::Exit) => Ok(None),
+            SessionInput::Command(SlashCommand::Status) => Ok(Some(self.coverage_report().await)),
+            SessionInput::Command(SlashCommand::Help) => {
+                let help = SLASH_COMMANDS.iter()
+                    .map(|(name, description)| format!("  {:<12} {}", name, description))
+                    .collect::<Vec<String>>()
+                    .join("\n");
+                Ok(Some(format!("Commands (any unambiguous prefix works, e.g. /cov):\n{}", help)))
+            },
+            SessionInput::Command(command) => {
+                self.conversation.lock().await.push_user(author, input.trim().to_string());
+                let reply = self.ask(command.instruction(), command.task()).await?;
+                self.conversation.lock().await.push_assistant("QitOps", reply.clone());
+                Ok(Some(reply))
+            },
+            SessionInput::Ambiguous(names) => Ok(Some(format!("Did you mean: {}?", names.join(", ")))),
+            SessionInput::Unknown(word) => Ok(Some(format!("Unknown command: {}. Type /help for the list of commands.", word))),
+        }
+    }
+
+    /// Wait for input from a remote participant, or forever when not hosting
+    async fn next_remote_input(pair_host: &mut Option<PairHost>) -> Option<(String, String)> {
+        match pair_host {
+            Some(host) => host.next_input().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Save the transcript to a markdown file
+    async fn save_transcript(&self) -> Result<String> {
+        let output_dir = Path::new("sessions");
+        if !output_dir.exists() {
+            fs::create_dir_all(output_dir)?;
+        }
+
+        let session_name = self.name.replace(['/', '\\', ':', '*', '?', '"', '<', '>', '|', ' '], "_");
+        let output_file = output_dir.join(format!("{}.md", session_name));
+
+        let mut content = format!("# Session: {}\n\n", self.name);
+        for message in self.conversation.lock().await.turns() {
+            content.push_str(&format!("**{}:** {}\n\n", message.author, message.text));
+        }
+
+        if !self.checklist.lock().await.is_empty() {
+            content.push_str("## Coverage\n\n```\n");
+            content.push_str(&self.coverage_report().await);
+            content.push_str("\n```\n");
+        }
+
+        let config = QitOpsConfigManager::new()
+            .map(|manager| manager.get_config().clone())
+            .unwrap_or_default();
+        let output_file = crate::history::write(&output_file, &config.report.decorate(&content, &CiContext::detect()), &config.history)?;
+        Ok(output_file.to_string_lossy().to_string())
+    }
+}
+
+impl Agent for SessionAgent {
+    fn init(&mut self) -> Result<()> {
+        // No initialization needed
+        Ok(())
+    }
+
+    async fn execute(&self) -> Result<AgentResponse> {
+        println!("Session '{}' started. Type /help for commands or /exit to end the session.", self.name);
+        println!();
+
+        // Build the objectives checklist
+        if !self.objectives.is_empty() || self.charter.is_some() {
+            self.build_checklist().await?;
+            println!("{}", self.coverage_report().await);
+            println!();
+        }
+
+        // Start hosting the session for remote participants
+        let mut pair_host = match &self.host {
+            #[cfg(feature = "pairing")]
+            Some(addr) => {
+                let host = PairHost::start(addr, &self.participant).await?;
+                branding::print_info(&format!("Hosting session on {} (join with: qitops run session --join '{}')", addr, host.join_url()));
+                Some(host)
+            },
+            #[cfg(not(feature = "pairing"))]
+            Some(_) => return Err(anyhow!("Session pairing is not built into this binary; rebuild with the `pairing` feature")),
+            None => None,
+        };
+
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        loop {
+            print!("{}: ", branding::colorize("You", branding::Color::Blue));
+            std::io::Write::flush(&mut std::io::stdout())?;
+
+            let (author, input, remote) = tokio::select! {
+                line = lines.next_line() => match line? {
+                    Some(input) => (self.participant.clone(), input, false),
+                    None => break,
+                },
+                Some((author, input)) = Self::next_remote_input(&mut pair_host) => (author, input, true),
+            };
+
+            if remote {
+                println!();
+                println!("{}: {}", branding::colorize(&author, branding::Color::Blue), input);
+
+                // Only the host can end the session
+                if SlashCommand::parse(&input) == SessionInput::Command(SlashCommand::Exit) {
+                    continue;
+                }
+            }
+
+            match self.handle_input(&author, &input).await? {
+                Some(reply) if reply.is_empty() => continue,
+                Some(reply) => {
+                    println!("{}: {}", branding::colorize("QitOps", branding::Color::Green), reply);
+                    println!();
+
+                    if let Some(host) = &pair_host {
+                        host.broadcast(&author, input.trim());
+                        host.broadcast("QitOps", &reply);
+                    }
+                },
+                None => break,
+            }
+        }
+
+        if let Some(host) = &pair_host {
+            host.end();
+            // Give the participant connections a moment to receive the end event
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        }
+
+        // Save the transcript
+        let output_file = self.save_transcript().await?;
+        let transcript = self.conversation.lock().await.turns().to_vec();
+        let checklist = self.checklist.lock().await.clone();
+
+        if !checklist.is_empty() {
+            println!("{}", self.coverage_report().await);
+        }
+
+        Ok(AgentResponse {
+            status: AgentStatus::Success,
+            message: format!("Session '{}' ended; transcript saved to {}", self.name, output_file),
+            data: Some(serde_json::json!({
+                "output_file": output_file,
+                "messages": transcript.len(),
+                "transcript": transcript,
+                "checklist": checklist,
+            })),
+        })
+    }
+
+    fn name(&self) -> &str {
+        "session"
+    }
+
+    fn description(&self) -> &str {
+        "Interactive exploratory testing session"
+    }
+}
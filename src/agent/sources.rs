@@ -0,0 +1,106 @@
+use anyhow::{Result, anyhow};
+use globset::{GlobBuilder, GlobMatcher};
+use serde::Serialize;
+
+use crate::config::SourceRule;
+
+/// A source rule that matched files of a diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppliedRule {
+    /// The rule's path glob
+    pub path: String,
+
+    /// Sources the rule attached
+    pub sources: Vec<String>,
+
+    /// Changed files the glob matched
+    pub files: Vec<String>,
+}
+
+/// Sources attached to a run, explicitly or by rules
+#[derive(Debug, Clone, Default)]
+pub struct SourceSelection {
+    /// Sources given on the command line or as command defaults
+    pub explicit: Vec<String>,
+
+    /// Rules mapping path globs to sources
+    pub rules: Vec<SourceRule>,
+}
+
+impl SourceSelection {
+    /// Select explicit sources and path rules
+    pub fn new(explicit: Vec<String>, rules: Vec<SourceRule>) -> Self {
+        Self { explicit, rules }
+    }
+
+    /// Every source ID the selection can attach
+    pub fn all_ids(&self) -> Vec<String> {
+        let mut ids = self.explicit.clone();
+        for id in self.rules.iter().flat_map(|rule| &rule.sources) {
+            if !ids.contains(id) {
+                ids.push(id.clone());
+            }
+        }
+        ids
+    }
+
+    /// Match the rules against changed files, returning the sources to attach and the rules that applied
+    pub fn resolve(&self, files: &[String]) -> Result<(Vec<String>, Vec<AppliedRule>)> {
+        let applied = apply_rules(&self.rules, files)?;
+
+        let mut sources = self.explicit.clone();
+        for id in applied.iter().flat_map(|rule| &rule.sources) {
+            if !sources.contains(id) {
+                sources.push(id.clone());
+            }
+        }
+
+        Ok((sources, applied))
+    }
+
+    /// Resolve the sources for changed files and read their content
+    pub fn load(&self, files: &[String]) -> Result<(String, Vec<String>, Vec<AppliedRule>)> {
+        let (sources, applied) = self.resolve(files)?;
+        for rule in &applied {
+            tracing::info!("Source rule {} attached {} ({} file(s))", rule.path, rule.sources.join(", "), rule.files.len());
+        }
+
+        let content = match sources.is_empty() {
+            true => String::new(),
+            false => crate::cli::source::SourceManager::new()?.get_content_for_sources(&sources)?,
+        };
+        Ok((content, sources, applied))
+    }
+}
+
+/// Match source rules against changed files
+pub fn apply_rules(rules: &[SourceRule], files: &[String]) -> Result<Vec<AppliedRule>> {
+    let mut applied = Vec::new();
+
+    for rule in rules {
+        let matcher = matcher(&rule.path)?;
+        let matched: Vec<String> = files.iter()
+            .filter(|file| matcher.is_match(file.trim_start_matches("./")))
+            .cloned()
+            .collect();
+
+        if !matched.is_empty() {
+            applied.push(AppliedRule {
+                path: rule.path.clone(),
+                sources: rule.sources.clone(),
+                files: matched,
+            });
+        }
+    }
+
+    Ok(applied)
+}
+
+/// Compile a rule's path glob; `*` stays within a directory and `**` spans directories
+fn matcher(pattern: &str) -> Result<GlobMatcher> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map(|glob| glob.compile_matcher())
+        .map_err(|e| anyhow!("Invalid path glob in source rule '{}': {}", pattern, e))
+}
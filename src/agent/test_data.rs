@@ -1,16 +1,34 @@
 use anyhow::{Result, Context};
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use std::fs;
 use std::path::Path;
 
-use crate::agent::traits::{Agent, AgentResponse, AgentStatus};
-use crate::llm::{LlmRequest, LlmRouter};
+use crate::agent::traits::{Agent, AgentResponse, AgentStatus, Progress};
+use crate::llm::{LlmRequest, LlmRouter, batch};
+use crate::masking::MaskingDictionary;
+use crate::schema::{SchemaRegistry, TestDataSchema};
+
+/// Instructions for the JSON document asked for when generating JSON test data
+const JSON_RECORDS_INSTRUCTIONS: &str = "Respond with a single JSON object of the form {\"records\": [...]} where each record is a JSON object with the schema's fields.";
+
+/// Most JSON records asked for in one request; larger counts are split into a batch of requests
+pub const RECORDS_PER_REQUEST: usize = 50;
+
+/// JSON document returned by the model for JSON test data
+#[derive(Debug, Deserialize)]
+struct GeneratedRecords {
+    /// Generated records
+    records: Vec<serde_json::Map<String, serde_json::Value>>,
+}
 
 /// Test data generator agent
 pub struct TestDataAgent {
-    /// Schema definition
+    /// Schema definition or registered schema name
     schema: String,
 
+    /// Registered schema matching the schema name (if any)
+    registered_schema: Option<TestDataSchema>,
+
     /// Number of records to generate
     count: usize,
 
@@ -20,6 +38,15 @@ pub struct TestDataAgent {
     /// Output format (json, csv, yaml)
     format: String,
 
+    /// Fields to pseudonymize with the shared masking dictionary
+    mask_fields: Vec<String>,
+
+    /// System prompt override (replaces the built-in system prompt)
+    system_prompt_override: Option<String>,
+
+    /// Called with a status line as batched requests complete
+    progress: Option<Progress>,
+
     /// LLM router
     llm_router: LlmRouter,
 }
@@ -33,37 +60,154 @@ impl TestDataAgent {
         format: String,
         llm_router: LlmRouter,
     ) -> Result<Self> {
+        // Resolve registered schemas by name
+        let registered_schema = SchemaRegistry::new()
+            .ok()
+            .and_then(|registry| registry.get_schema(&schema).cloned());
+
         Ok(Self {
             schema,
+            registered_schema,
             count,
             constraints,
             format,
+            mask_fields: Vec::new(),
+            system_prompt_override: crate::prompt::load_system_prompt_override("test-data")?,
+            progress: None,
             llm_router,
         })
     }
 
-    /// Generate the prompt for the LLM
-    fn generate_prompt(&self) -> String {
-        let constraints_str = if self.constraints.is_empty() {
-            "".to_string()
-        } else {
-            format!("\n\nApply the following constraints: {}", self.constraints.join(", "))
+    /// Pseudonymize the given fields using the shared masking dictionary
+    pub fn with_mask_fields(mut self, mask_fields: Vec<String>) -> Self {
+        self.mask_fields = mask_fields;
+        self
+    }
+
+    /// Report batch progress, e.g. to update a spinner
+    pub fn with_progress(mut self, progress: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// Record counts of the requests generating `count` JSON records
+    pub fn batch_sizes(count: usize) -> Vec<usize> {
+        (0..count).step_by(RECORDS_PER_REQUEST)
+            .map(|start| RECORDS_PER_REQUEST.min(count - start))
+            .collect()
+    }
+
+    /// Generate the prompt for the LLM, asking for `count` records
+    fn generate_prompt(&self, count: usize) -> Result<String> {
+        let mut constraints = self.constraints.clone();
+        let schema = match &self.registered_schema {
+            Some(registered) => {
+                constraints.extend(registered.constraints.iter().cloned());
+                registered.to_prompt()
+            },
+            None => self.schema.clone(),
         };
 
-        format!(
-            "Generate {} test data records for the following schema: {}{}\n\nProvide the data in {} format.",
-            self.count, self.schema, constraints_str, self.format
-        )
+        crate::prompt::render_prompt("test-data", &serde_json::json!({
+            "count": count,
+            "schema": schema,
+            "constraints": constraints,
+            "format": self.format,
+        }))
     }
 
     /// Get the system prompt
     fn system_prompt(&self) -> String {
+        if let Some(system_prompt) = &self.system_prompt_override {
+            return system_prompt.clone();
+        }
+
         format!(
             "You are a test data generator. Generate realistic and diverse test data based on the provided schema. Ensure the data is valid and follows the specified constraints. Provide the data in {} format.",
             self.format
         )
     }
 
+    /// Generate the records without saving them
+    pub async fn generate_records(&self) -> Result<String> {
+        let model = self.llm_router.default_model().unwrap_or_else(|| "tinyllama".to_string());
+
+        // JSON output is validated and re-prompted until it holds the requested records
+        if self.format.eq_ignore_ascii_case("json") {
+            return self.generate_json_records(&model).await;
+        }
+
+        // Generate the prompt
+        let prompt = self.generate_prompt(self.count)?;
+
+        // Create the LLM request
+        let request = LlmRequest::new(prompt, model)
+            .with_system_message(self.system_prompt());
+
+        // Send the request to the LLM
+        let response = self.llm_router.send(request, Some("test-data")).await?;
+
+        Ok(response.text)
+    }
+
+    /// Generate JSON records, splitting large counts into a batch of concurrent requests
+    async fn generate_json_records(&self, model: &str) -> Result<String> {
+        let sizes = Self::batch_sizes(self.count);
+        let batches = sizes.len();
+
+        let mut requests = Vec::new();
+        for (index, count) in sizes.into_iter().enumerate() {
+            let mut prompt = self.generate_prompt(count)?;
+            if batches > 1 {
+                // Also keeps the batches from sharing a cached response
+                prompt.push_str(&format!("\n\nThis is batch {} of {}; make these records distinct from those of the other batches.", index + 1, batches));
+            }
+
+            let request = LlmRequest::new(format!("{}\n\n{}", prompt, JSON_RECORDS_INSTRUCTIONS), model.to_string())
+                .with_system_message(self.system_prompt());
+            requests.push((request, count));
+        }
+
+        let results = batch::run_batch(requests, self.llm_router.batch_concurrency(), |(request, count)| {
+            self.llm_router.send_json::<GeneratedRecords, _>(request, Some("test-data"), move |generated| {
+                if generated.records.len() != count {
+                    return Err(anyhow::anyhow!("Expected {} records in 'records' but got {}", count, generated.records.len()));
+                }
+                Ok(())
+            })
+        }, |counts| {
+            if let Some(progress) = &self.progress && batches > 1 {
+                progress(&format!("Generating test data... {}/{} batches", counts.completed, counts.total));
+            }
+        }).await;
+
+        let mut records = Vec::with_capacity(self.count);
+        for (index, result) in results.into_iter().enumerate() {
+            let (generated, _) = result.with_context(|| format!("Test data batch {} of {} failed", index + 1, batches))?;
+            records.extend(generated.records);
+        }
+
+        Ok(serde_json::to_string_pretty(&records)?)
+    }
+
+    /// Replace the masked fields with consistent fake values
+    fn mask_records(&self, test_data: &str) -> Result<(String, usize)> {
+        let start = test_data.find(['[', '{'])
+            .ok_or_else(|| anyhow::anyhow!("Generated test data is not JSON; cannot apply masking"))?;
+        let end = test_data.rfind([']', '}'])
+            .filter(|end| *end >= start)
+            .ok_or_else(|| anyhow::anyhow!("Generated test data is not JSON; cannot apply masking"))?;
+
+        let mut records: serde_json::Value = serde_json::from_str(&test_data[start..=end])
+            .context("Failed to parse generated test data for masking")?;
+
+        let mut dictionary = MaskingDictionary::load()?;
+        dictionary.mask_json(&mut records, &self.mask_fields);
+        dictionary.save()?;
+
+        Ok((serde_json::to_string_pretty(&records)?, dictionary.mapping_count()))
+    }
+
     /// Save the generated test data to a file
     fn save_test_data(&self, test_data: &str) -> Result<String> {
         // Create the output directory if it doesn't exist
@@ -90,19 +234,19 @@ impl Agent for TestDataAgent {
     }
 
     async fn execute(&self) -> Result<AgentResponse> {
-        // Generate the prompt
-        let prompt = self.generate_prompt();
-
-        // Create the LLM request
-        let model = self.llm_router.default_model().unwrap_or_else(|| "tinyllama".to_string());
-        let request = LlmRequest::new(prompt, model)
-            .with_system_message(self.system_prompt());
+        // Generate the records
+        let mut test_data = self.generate_records().await?;
 
-        // Send the request to the LLM
-        let response = self.llm_router.send(request, Some("test-data")).await?;
+        // Pseudonymize masked fields
+        let mut dictionary_size = None;
+        if !self.mask_fields.is_empty() {
+            let (masked, size) = self.mask_records(&test_data)?;
+            test_data = masked;
+            dictionary_size = Some(size);
+        }
 
         // Save the test data to a file
-        let output_file = self.save_test_data(&response.text)?;
+        let output_file = self.save_test_data(&test_data)?;
 
         // Return the response
         Ok(AgentResponse {
@@ -111,8 +255,11 @@ impl Agent for TestDataAgent {
             data: Some(serde_json::json!({
                 "output_file": output_file,
                 "schema": self.schema,
+                "registered_schema": self.registered_schema.is_some(),
                 "count": self.count,
                 "constraints": self.constraints,
+                "masked_fields": self.mask_fields,
+                "masking_dictionary_size": dictionary_size,
             })),
         })
     }
@@ -1,10 +1,76 @@
 use anyhow::{Result, Context};
+use globset::GlobBuilder;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
-
-use crate::agent::traits::{Agent, AgentResponse, AgentStatus};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::agent::changes::{self, ChangedFile};
+use crate::agent::conventions::{self, TestSamples};
+use crate::agent::e2e::{E2eContext, E2eFramework};
+use crate::context::summaries::{self, MAX_CONTEXT_CHARS, RepoSnapshot, SummaryCache};
+use crate::context::{RepositoryContext, SourceLanguage, TestPlacement};
+use crate::agent::traits::{Agent, AgentResponse, AgentStatus, Progress};
 use crate::llm::{LlmRequest, LlmRouter};
+use crate::checkpoint::Checkpoint;
+use crate::error::QitOpsError;
+use crate::llm::batch::run_batch;
+use crate::scan::scan_files;
+
+/// File extensions treated as source code when generating tests for a directory
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "rs", "py", "js", "jsx", "ts", "tsx", "java", "kt", "go", "rb", "php", "cs", "swift", "c", "cc", "cpp", "h", "hpp", "scala",
+];
+
+/// Characters that make a `--path` a glob pattern rather than a file or directory
+const GLOB_CHARS: &[char] = &['*', '?', '[', '{'];
+
+/// Whether a path is a glob pattern, e.g. `src/**/*.rs`
+pub fn is_glob(path: &str) -> bool {
+    path.contains(GLOB_CHARS)
+}
+
+/// The directory a glob pattern searches: its leading components without glob characters
+pub fn glob_base(pattern: &str) -> PathBuf {
+    let base: PathBuf = Path::new(pattern).components()
+        .take_while(|part| !part.as_os_str().to_string_lossy().contains(GLOB_CHARS))
+        .collect();
+    if base.as_os_str().is_empty() { PathBuf::from(".") } else { base }
+}
+
+/// Outcome of generating tests for one file of a directory or glob
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileResult {
+    /// Source file
+    pub file: String,
+
+    /// Test file written
+    pub output_file: Option<String>,
+
+    /// Test cases in the test file, when it was generated in this run
+    pub tests: Option<usize>,
+
+    /// Why no tests were generated
+    pub error: Option<String>,
+}
+
+/// Render per-file results as a table of source file, test count and test file or error
+pub fn results_table(results: &[FileResult]) -> String {
+    let width = results.iter().map(|result| result.file.chars().count()).chain(["File".len()]).max().unwrap_or(0);
+    let mut table = format!("{:<width$}  {:>5}  Output\n", "File", "Tests", width = width);
+    for result in results {
+        let tests = result.tests.map(|tests| tests.to_string()).unwrap_or_else(|| "-".to_string());
+        let output = match (&result.output_file, &result.error) {
+            (Some(output_file), _) => output_file.clone(),
+            (None, Some(error)) => format!("failed: {}", error),
+            (None, None) => String::new(),
+        };
+        table.push_str(&format!("{:<width$}  {:>5}  {}\n", result.file, tests, output, width = width));
+    }
+    table
+}
 
 /// Test case format
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -15,6 +81,14 @@ pub enum TestFormat {
     Yaml,
     /// Robot Framework format
     Robot,
+    /// Runnable Playwright spec
+    Playwright,
+    /// Runnable Cypress spec
+    Cypress,
+    /// Compilable Rust `#[test]` functions
+    RustTest,
+    /// Runnable pytest file
+    Pytest,
 }
 
 impl TestFormat {
@@ -24,7 +98,11 @@ impl TestFormat {
             "markdown" | "md" => Ok(TestFormat::Markdown),
             "yaml" | "yml" => Ok(TestFormat::Yaml),
             "robot" => Ok(TestFormat::Robot),
-            _ => Err(anyhow::anyhow!("Unknown test format: {}", s)),
+            "playwright" => Ok(TestFormat::Playwright),
+            "cypress" => Ok(TestFormat::Cypress),
+            "rust-test" => Ok(TestFormat::RustTest),
+            "pytest" => Ok(TestFormat::Pytest),
+            _ => Err(QitOpsError::InvalidInput(format!("Unknown test format: {}", s)).into()),
         }
     }
 
@@ -34,6 +112,28 @@ impl TestFormat {
             TestFormat::Markdown => "md",
             TestFormat::Yaml => "yaml",
             TestFormat::Robot => "robot",
+            TestFormat::Playwright => "spec.ts",
+            TestFormat::Cypress => "cy.ts",
+            TestFormat::RustTest => "rs",
+            TestFormat::Pytest => "py",
+        }
+    }
+
+    /// Language of a format generating unit tests that compile or import against the source
+    pub fn unit(&self) -> Option<SourceLanguage> {
+        match self {
+            TestFormat::RustTest => Some(SourceLanguage::Rust),
+            TestFormat::Pytest => Some(SourceLanguage::Python),
+            _ => None,
+        }
+    }
+
+    /// End-to-end framework of a format generating runnable specs
+    pub fn e2e(&self) -> Option<E2eFramework> {
+        match self {
+            TestFormat::Playwright => Some(E2eFramework::Playwright),
+            TestFormat::Cypress => Some(E2eFramework::Cypress),
+            _ => None,
         }
     }
 
@@ -43,6 +143,10 @@ impl TestFormat {
             TestFormat::Markdown => "Generate test cases in Markdown format. Use proper Markdown formatting with headers, lists, and code blocks.".to_string(),
             TestFormat::Yaml => "Generate test cases in YAML format. Follow proper YAML syntax and indentation.".to_string(),
             TestFormat::Robot => "Generate test cases in Robot Framework format. Follow proper Robot Framework syntax with settings, variables, and keywords.".to_string(),
+            TestFormat::Playwright => "Generate runnable end-to-end tests with Playwright Test. Output only the code of the spec file, without explanations.".to_string(),
+            TestFormat::Cypress => "Generate runnable end-to-end tests with Cypress. Output only the code of the spec file, without explanations.".to_string(),
+            TestFormat::RustTest => "Generate Rust unit tests that compile against the code as written. Output only the code of the test file, without explanations.".to_string(),
+            TestFormat::Pytest => "Generate pytest tests that run against the code as written. Output only the code of the test file, without explanations.".to_string(),
         }
     }
 }
@@ -61,8 +165,32 @@ pub struct TestGenAgent {
     /// Personas to use
     personas: Option<Vec<String>>,
 
+    /// System prompt override (replaces the built-in system prompt)
+    system_prompt_override: Option<String>,
+
     /// LLM router
     llm_router: LlmRouter,
+
+    /// Function to focus on (the rest of the file is context)
+    function: Option<String>,
+
+    /// Called with a status line as the files of a directory are processed
+    progress: Option<Progress>,
+
+    /// Test style guide to follow instead of sampling the project's existing tests
+    conventions: Option<String>,
+
+    /// Checkpoint of an interrupted directory run to resume
+    resume: Option<PathBuf>,
+
+    /// Directory to write the test files to instead of next to their sources
+    output_dir: Option<PathBuf>,
+
+    /// Git ref to generate tests for the changes since, leaving out unchanged files and functions
+    since: Option<String>,
+
+    /// Whether prompts include the repository context built from cached summaries
+    repo_summary: bool,
 }
 
 impl TestGenAgent {
@@ -75,79 +203,445 @@ impl TestGenAgent {
         llm_router: LlmRouter
     ) -> Result<Self> {
         let format = TestFormat::from_str(format)?;
+        let system_prompt_override = crate::prompt::load_system_prompt_override("test-gen")?;
 
         Ok(Self {
             path,
             format,
             sources,
             personas,
+            system_prompt_override,
             llm_router,
+            function: None,
+            progress: None,
+            conventions: None,
+            resume: None,
+            output_dir: None,
+            since: None,
+            repo_summary: false,
         })
     }
 
+    /// Only generate tests for the named function
+    pub fn with_function(mut self, function: Option<String>) -> Self {
+        self.function = function;
+        self
+    }
+
+    /// Follow a test style guide instead of the conventions of the project's existing tests
+    pub fn with_conventions(mut self, conventions: Option<String>) -> Self {
+        self.conventions = conventions;
+        self
+    }
+
+    /// Resume an interrupted directory run from its checkpoint, skipping the files already done
+    pub fn with_resume(mut self, checkpoint: Option<PathBuf>) -> Self {
+        self.resume = checkpoint;
+        self
+    }
+
+    /// Write the test files to a directory, mirroring the layout of their sources, instead of next to them
+    pub fn with_output_dir(mut self, output_dir: Option<PathBuf>) -> Self {
+        self.output_dir = output_dir;
+        self
+    }
+
+    /// Only generate tests for the files and functions changed since a git ref
+    pub fn with_since(mut self, since: Option<String>) -> Self {
+        self.since = since;
+        self
+    }
+
+    /// Include the repository context built from the summaries cached by `qitops context summarize`
+    pub fn with_repo_summary(mut self, repo_summary: bool) -> Self {
+        self.repo_summary = repo_summary;
+        self
+    }
+
+    /// Report progress on directories, e.g. to update a spinner
+    pub fn with_progress(mut self, progress: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
     /// Read the source code
-    fn read_source_code(&self) -> Result<String> {
-        let path = Path::new(&self.path);
-        if !path.exists() {
-            return Err(anyhow::anyhow!("File not found: {}", self.path));
+    fn read_source_code(path: &str) -> Result<String> {
+        if !Path::new(path).exists() {
+            return Err(QitOpsError::InvalidInput(format!("File not found: {}", path)).into());
+        }
+
+        fs::read_to_string(path).context(format!("Failed to read file: {}", path))
+    }
+
+    /// Collect the source files of a directory, leaving out existing tests
+    fn collect_source_files(dir: &Path) -> Result<Vec<String>> {
+        let limits = crate::config::QitOpsConfigManager::new()?.get_config().context.clone();
+        let root = dir.to_path_buf();
+        let scan = scan_files(dir, &limits, move |file| {
+            let is_source = file.extension()
+                .is_some_and(|ext| SOURCE_EXTENSIONS.contains(&ext.to_string_lossy().as_ref()));
+            is_source && !conventions::is_test_file(&root, file)
+        })?;
+
+        if let Some(limit) = scan.limit_summary() {
+            tracing::warn!("Scan of {} incomplete: {}", dir.display(), limit);
+        }
+
+        Ok(scan.files.iter().map(|file| file.to_string_lossy().to_string()).collect())
+    }
+
+    /// Collect the files matching a glob pattern, leaving out existing tests
+    ///
+    /// `*` stays within a directory and `**` spans directories. Any file the pattern matches is
+    /// a source, whatever its extension.
+    fn collect_glob_files(pattern: &str) -> Result<Vec<String>> {
+        let pattern = pattern.trim_start_matches("./");
+        let matcher = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| QitOpsError::InvalidInput(format!("Invalid path glob '{}': {}", pattern, e)))?
+            .compile_matcher();
+
+        let base = glob_base(pattern);
+        if !base.is_dir() {
+            return Ok(Vec::new());
+        }
+        let limits = crate::config::QitOpsConfigManager::new()?.get_config().context.clone();
+        let root = base.clone();
+        let scan = scan_files(&base, &limits, move |file| {
+            let relative = file.strip_prefix("./").unwrap_or(file);
+            matcher.is_match(relative) && !conventions::is_test_file(&root, file)
+        })?;
+
+        if let Some(limit) = scan.limit_summary() {
+            tracing::warn!("Scan of {} incomplete: {}", base.display(), limit);
         }
 
-        fs::read_to_string(path).context(format!("Failed to read file: {}", self.path))
+        let mut files: Vec<String> = scan.files.iter()
+            .map(|file| file.strip_prefix("./").unwrap_or(file).to_string_lossy().to_string())
+            .collect();
+        files.sort();
+        Ok(files)
     }
 
-    /// Generate the prompt for the LLM
-    async fn generate_prompt(&self, source_code: &str) -> Result<String> {
-        let mut prompt = format!(
-            "Generate comprehensive test cases for the following code. Focus on edge cases, error handling, and important functionality.\n\nCode:\n```\n{}\n```",
-            source_code
-        );
+    /// Keep the files changed since a git ref, returning what changed in each
+    fn retain_changed(&self, since: &str, files: &mut Vec<String>) -> Result<HashMap<String, ChangedFile>> {
+        let changed: HashMap<PathBuf, ChangedFile> = changes::changed_since(&self.base_dir(), since)?.into_iter()
+            .filter_map(|file| Some((fs::canonicalize(&file.path).ok()?, file)))
+            .collect();
+
+        let mut kept = HashMap::new();
+        files.retain(|file| {
+            let change = fs::canonicalize(file).ok().and_then(|path| changed.get(&path));
+            if let Some(change) = change {
+                kept.insert(file.clone(), change.clone());
+            }
+            change.is_some()
+        });
+        Ok(kept)
+    }
+
+    /// Whether the path is a glob pattern rather than an existing file or directory
+    fn is_glob(&self) -> bool {
+        is_glob(&self.path) && !Path::new(&self.path).exists()
+    }
+
+    /// Directory the files of the run are found in: the directory, the glob's base or the file's parent
+    fn base_dir(&self) -> PathBuf {
+        let path = Path::new(&self.path);
+        if self.is_glob() {
+            glob_base(self.path.trim_start_matches("./"))
+        } else if path.is_dir() {
+            path.to_path_buf()
+        } else {
+            path.parent().unwrap_or(Path::new("")).to_path_buf()
+        }
+    }
 
+    /// Source and persona context shared by every file's prompt
+    fn context(&self) -> Result<(String, String)> {
         // Add sources if available
+        let mut source_content = String::new();
         if let Some(sources) = &self.sources {
             if !sources.is_empty() {
                 let source_manager = crate::cli::source::SourceManager::new()?;
-                let source_content = source_manager.get_content_for_sources(sources)?;
-
-                if !source_content.is_empty() {
-                    prompt.push_str("\n\nAdditional context from sources:\n");
-                    prompt.push_str(&source_content);
-                }
+                source_content = source_manager.get_content_for_sources(sources)?;
             }
         }
 
         // Add personas if available
+        let mut persona_prompt = String::new();
         if let Some(personas) = &self.personas {
             if !personas.is_empty() {
                 let persona_manager = crate::cli::persona::PersonaManager::new()?;
-                let persona_prompt = persona_manager.get_prompt_for_personas(personas)?;
-
-                if !persona_prompt.is_empty() {
-                    prompt = format!("{}\n\n{}", persona_prompt, prompt);
-                }
+                persona_prompt = persona_manager.get_prompt_for_personas(personas)?;
             }
         }
 
-        Ok(prompt)
+        Ok((source_content, persona_prompt))
     }
 
-    /// Save the generated test cases to a file
-    fn save_test_cases(&self, test_cases: &str) -> Result<String> {
-        let path = Path::new(&self.path);
-        let file_name = path.file_name()
-            .ok_or_else(|| anyhow::anyhow!("Invalid file path"))?
-            .to_string_lossy();
+    /// Existing tests of the project to learn conventions from, or `None` with a style guide
+    ///
+    /// Tests are looked for in the repository of `dir` and must have one of the extensions of
+    /// the source files, or be Robot Framework suites or JavaScript specs when generating those.
+    fn test_samples(&self, dir: &Path, extensions: &[&str]) -> Result<Option<TestSamples>> {
+        if self.conventions.is_some() {
+            return Ok(None);
+        }
+
+        let mut extensions: Vec<String> = extensions.iter().map(|ext| ext.to_string()).collect();
+        if matches!(self.format, TestFormat::Robot) {
+            extensions.push("robot".to_string());
+        }
+        if self.format.e2e().is_some() {
+            // Specs are JavaScript or TypeScript whatever the page is written in
+            extensions = ["ts", "js", "tsx", "jsx"].iter().map(|ext| ext.to_string()).collect();
+        }
+        if let Some(language) = self.format.unit() {
+            extensions = vec![language.extension().to_string()];
+        }
+        let limits = crate::config::QitOpsConfigManager::new()?.get_config().context.clone();
+
+        TestSamples::scan(&conventions::project_root(dir), &limits, extensions).map(Some)
+    }
+
+    /// The conventions section of a file's prompt, with the test files it samples
+    fn conventions(&self, path: &str, samples: Option<&TestSamples>, guide: Option<&(String, String)>) -> (String, Vec<PathBuf>) {
+        let samples = samples.map(|samples| samples.for_source(Path::new(path))).unwrap_or_default();
+        for sample in &samples {
+            crate::debug::record_piece("test-sample", &sample.to_string_lossy(), &fs::read_to_string(sample).unwrap_or_default());
+        }
+
+        (conventions::conventions_prompt(&samples, guide), samples)
+    }
+
+    /// Repository context from the cached summaries, when asked for and there are any
+    fn overview(&self) -> Result<Option<String>> {
+        if !self.repo_summary {
+            return Ok(None);
+        }
+
+        let root = conventions::project_root(&self.base_dir());
+        let cache = SummaryCache::open(&root)?;
+        if cache.is_empty() {
+            tracing::warn!("No cached summaries of {}; run `qitops context summarize` first", root.display());
+            return Ok(None);
+        }
+
+        let limits = crate::config::QitOpsConfigManager::new()?.get_config().context.clone();
+        let snapshot = RepoSnapshot::scan(&root, &limits)?;
+        Ok(Some(summaries::generate_context(&snapshot, &cache, MAX_CONTEXT_CHARS)))
+    }
+
+    /// Where a file sits in its repository, when generating unit tests that import it
+    fn repository(&self, path: &str, source_code: &str) -> Result<Option<RepositoryContext>> {
+        self.format.unit()
+            .map(|language| RepositoryContext::for_file(Path::new(path), source_code, language, self.function.as_deref()))
+            .transpose()
+    }
+
+    /// Build the request generating tests for one file
+    fn request(&self, path: &str, source_code: &str, context: &(String, String), conventions: &str, changed: Option<&str>, overview: Option<&str>) -> Result<LlmRequest> {
+        let prompt = crate::prompt::render_prompt("test-gen", &serde_json::json!({
+            "code": source_code,
+            "path": path,
+            "function": self.function,
+            "changed": changed,
+            "format": format!("{:?}", self.format),
+            "personas": context.1,
+            "conventions": conventions,
+            "e2e": self.format.e2e().map(|framework| E2eContext::detect(framework, Path::new(path), source_code).prompt()),
+            "repository": self.repository(path, source_code)?.map(|repository| repository.prompt()),
+            // Summaries are made from the repository's files, so they're as untrusted as the files
+            "overview": overview.map(|overview| crate::prompt::wrap_untrusted("repository overview", overview)),
+        }))?;
+
+        let model = self.llm_router.default_model().unwrap_or_else(|| "tinyllama".to_string());
+        Ok(LlmRequest::new(prompt, model)
+            .with_system_message(self.system_prompt_override.clone().unwrap_or_else(|| self.format.system_prompt()))
+            .with_source_context(&context.0))
+    }
+
+    /// Generate tests for every source file of a directory or glob, with the requests sent as a batch
+    ///
+    /// With a git ref to start from, only the files changed since are generated for, each
+    /// focused on its changed functions.
+    ///
+    /// At most the router's batch concurrency of requests are in flight at once. Each file's output is recorded in a checkpoint as it is saved. If files fail, the
+    /// checkpoint is kept so a resumed run only generates the rest. After a rate limit or
+    /// budget error the files not yet sent are skipped, as they would fail too.
+    async fn execute_directory(&self) -> Result<AgentResponse> {
+        let mut files = if self.is_glob() {
+            Self::collect_glob_files(&self.path)?
+        } else if Path::new(&self.path).is_dir() {
+            Self::collect_source_files(Path::new(&self.path))?
+        } else {
+            vec![self.path.clone()]
+        };
+        if let Some(language) = self.format.unit() {
+            files.retain(|file| Path::new(file).extension().is_some_and(|ext| ext == language.extension()));
+        }
+        let changed = match &self.since {
+            Some(since) => self.retain_changed(since, &mut files)?,
+            None => HashMap::new(),
+        };
+        if let Some(since) = &self.since
+            && files.is_empty()
+        {
+            return Ok(AgentResponse {
+                status: AgentStatus::Success,
+                message: format!("No source files in {} changed since {}", self.path, since),
+                data: Some(serde_json::json!({ "output_files": [], "failed": [], "results": [], "since": since, "changed": [] })),
+            });
+        }
+        if files.is_empty() {
+            return Ok(AgentResponse {
+                status: AgentStatus::Failure,
+                message: format!("No source files found in {}", self.path),
+                data: None,
+            });
+        }
+
+        let mut target = format!("{} ({})", self.path, self.format.extension());
+        if let Some(since) = &self.since {
+            target.push_str(&format!(" since {}", since));
+        }
+        let checkpoint = match &self.resume {
+            Some(path) => Checkpoint::resume(path, "test-gen", &target)?,
+            None => Checkpoint::create("test-gen", &target)?,
+        };
+        let resumed = files.iter().filter(|file| checkpoint.is_done(file)).count();
+
+        let context = self.context()?;
+        let overview = self.overview()?;
+        let guide = self.conventions.as_deref().map(conventions::read_guide).transpose()?;
+        let samples = match self.format.unit() {
+            Some(language) => self.test_samples(&self.base_dir(), &[language.extension()])?,
+            None => self.test_samples(&self.base_dir(), SOURCE_EXTENSIONS)?,
+        };
+        let mut results = Vec::new();
+        let mut batch = Vec::new();
+        for file in files {
+            if let Some(output_file) = checkpoint.output::<String>(&file) {
+                results.push(FileResult { file, output_file: Some(output_file), tests: None, error: None });
+                continue;
+            }
 
-        let parent = path.parent().unwrap_or_else(|| Path::new("."));
-        let test_dir = parent.join("tests");
+            let (conventions, _) = self.conventions(&file, samples.as_ref(), guide.as_ref());
+            let focus = self.since.as_deref().and_then(|since| changed.get(&file)?.focus(since));
+            match Self::read_source_code(&file).and_then(|code| self.request(&file, &code, &context, &conventions, focus.as_deref(), overview.as_deref())) {
+                Ok(request) => batch.push((file, request)),
+                Err(e) => results.push(FileResult { file, output_file: None, tests: None, error: Some(e.to_string()) }),
+            }
+        }
 
-        // Create the test directory if it doesn't exist
-        if !test_dir.exists() {
-            fs::create_dir_all(&test_dir)?;
+        let checkpoint = Mutex::new(checkpoint);
+        let limited = AtomicBool::new(false);
+        let batch_files: Vec<String> = batch.iter().map(|(file, _)| file.clone()).collect();
+        let batch_results = run_batch(batch, self.llm_router.batch_concurrency(), |(file, request)| {
+            let (checkpoint, limited) = (&checkpoint, &limited);
+            async move {
+                if limited.load(Ordering::Relaxed) {
+                    return Err(anyhow::anyhow!("Skipped after the LLM rate limit or budget was exceeded"));
+                }
+
+                let response = self.llm_router.send(request, Some("test-gen")).await.inspect_err(|e| {
+                    if crate::error::exit_code(e) == crate::error::EXIT_LIMIT_EXCEEDED {
+                        limited.store(true, Ordering::Relaxed);
+                    }
+                })?;
+                let output_file = self.save_test_cases(&file, &response.text)?;
+                if let Err(e) = checkpoint.lock().expect("checkpoint lock poisoned").record(&file, &output_file) {
+                    tracing::warn!("{}", e);
+                }
+                Ok((output_file, crate::integrations::tcm::parse_test_cases(&response.text, self.format).len()))
+            }
+        }, |counts| {
+            if let Some(progress) = &self.progress {
+                progress(&format!("Generating test cases... {}/{} files", resumed + counts.completed, resumed + counts.total));
+            }
+        }).await;
+
+        for (file, result) in batch_files.into_iter().zip(batch_results) {
+            results.push(match result {
+                Ok((output_file, tests)) => FileResult { file, output_file: Some(output_file), tests: Some(tests), error: None },
+                Err(e) => FileResult { file, output_file: None, tests: None, error: Some(e.to_string()) },
+            });
+        }
+        results.sort_by(|a, b| a.file.cmp(&b.file));
+        let output_files: Vec<&String> = results.iter().filter_map(|result| result.output_file.as_ref()).collect();
+        let failed: Vec<serde_json::Value> = results.iter()
+            .filter_map(|result| Some(serde_json::json!({ "file": result.file, "error": result.error.as_ref()? })))
+            .collect();
+
+        let checkpoint = checkpoint.into_inner().expect("checkpoint lock poisoned");
+        let kept = checkpoint.finish(!failed.is_empty())?;
+        let mut changed: Vec<&ChangedFile> = changed.values().collect();
+        changed.sort_by(|a, b| a.path.cmp(&b.path));
+        let mut message = match failed.len() {
+            0 => format!("Generated test cases for {} file(s) in {}", output_files.len(), self.path),
+            n => format!("Generated test cases for {} file(s) in {}; {} failed", output_files.len(), self.path, n),
+        };
+        if resumed > 0 {
+            message.push_str(&format!(" ({} from the checkpoint)", resumed));
+        }
+        if let Some(path) = &kept {
+            message.push_str(&format!("; resume with --resume {}", path.display()));
         }
 
-        // Create the test file
-        let test_file = test_dir.join(format!("test_{}.{}", file_name, self.format.extension()));
-        fs::write(&test_file, test_cases)?;
+        Ok(AgentResponse {
+            status: if output_files.is_empty() { AgentStatus::Failure } else { AgentStatus::Success },
+            message,
+            data: Some(serde_json::json!({
+                "output_files": output_files,
+                "failed": failed,
+                "results": results,
+                "resumed": resumed,
+                "checkpoint": kept,
+                "since": self.since,
+                "changed": changed,
+            })),
+        })
+    }
+
+    /// Save the generated test cases to a file
+    ///
+    /// Runnable specs and unit tests are saved where their framework finds them, without the
+    /// prose or Markdown fence the model may have put around the code. With an output directory,
+    /// the file keeps its name and goes under the directory, in the source's place below the
+    /// directory or glob base of the run.
+    fn save_test_cases(&self, source_path: &str, test_cases: &str) -> Result<String> {
+        let path = Path::new(source_path);
+        let (test_file, content) = if let Some(framework) = self.format.e2e() {
+            let spec = E2eContext::detect(framework, path, "").spec_path(path, self.function.as_deref());
+            (spec, crate::agent::e2e::extract_code(test_cases))
+        } else if let Some(repository) = self.repository(source_path, &Self::read_source_code(source_path)?)? {
+            (repository.test_path, crate::agent::e2e::extract_code(test_cases))
+        } else {
+            let file_name = path.file_name()
+                .ok_or_else(|| anyhow::anyhow!("Invalid file path"))?
+                .to_string_lossy();
+            let test_dir = path.parent().unwrap_or_else(|| Path::new(".")).join("tests");
+            let test_file = match &self.function {
+                Some(function) => test_dir.join(format!("test_{}_{}.{}", file_name, function, self.format.extension())),
+                None => test_dir.join(format!("test_{}.{}", file_name, self.format.extension())),
+            };
+            (test_file, test_cases.to_string())
+        };
+
+        let test_file = match &self.output_dir {
+            Some(output_dir) => {
+                let parent = path.parent().unwrap_or(Path::new(""));
+                let relative = parent.strip_prefix(self.base_dir()).unwrap_or(Path::new(""));
+                output_dir.join(relative).join(test_file.file_name().unwrap_or_default())
+            },
+            None => test_file,
+        };
+        if let Some(dir) = test_file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&test_file, content)?;
 
         Ok(test_file.to_string_lossy().to_string())
     }
@@ -160,30 +654,50 @@ impl Agent for TestGenAgent {
     }
 
     async fn execute(&self) -> Result<AgentResponse> {
+        if Path::new(&self.path).is_dir() || self.is_glob() || self.since.is_some() {
+            return self.execute_directory().await;
+        }
+
         // Read the source code
-        let source_code = self.read_source_code()?;
+        let source_code = Self::read_source_code(&self.path)?;
+        crate::debug::record_piece("file", &self.path, &source_code);
 
-        // Generate the prompt
-        let prompt = self.generate_prompt(&source_code).await?;
+        // Learn the project's test conventions from a style guide or its existing tests
+        let guide = self.conventions.as_deref().map(conventions::read_guide).transpose()?;
+        let path = Path::new(&self.path);
+        let extension = path.extension().map(|ext| ext.to_string_lossy().to_string()).unwrap_or_default();
+        let samples = self.test_samples(path.parent().unwrap_or(Path::new("")), &[extension.as_str()])?;
+        let (conventions, sampled) = self.conventions(&self.path, samples.as_ref(), guide.as_ref());
 
         // Create the LLM request
-        let model = self.llm_router.default_model().unwrap_or_else(|| "tinyllama".to_string());
-        let request = LlmRequest::new(prompt, model)
-            .with_system_message(self.format.system_prompt());
+        let request = self.request(&self.path, &source_code, &self.context()?, &conventions, None, self.overview()?.as_deref())?;
 
         // Send the request to the LLM
         let response = self.llm_router.send(request, Some("test-gen")).await?;
 
         // Save the test cases to a file
-        let output_file = self.save_test_cases(&response.text)?;
+        let output_file = self.save_test_cases(&self.path, &response.text)?;
+
+        // A Rust test module only compiles once the source file includes it
+        let repository = self.repository(&self.path, &source_code)?;
+        let mut message = format!("Generated test cases saved to {}", output_file);
+        if let Some(TestPlacement::Inline { declaration }) = repository.as_ref().map(|repository| &repository.placement) {
+            message.push_str(&format!("; include them by adding this to {}:\n{}", self.path, declaration));
+        }
 
         // Return the response
         Ok(AgentResponse {
             status: AgentStatus::Success,
-            message: format!("Generated test cases saved to {}", output_file),
+            message,
             data: Some(serde_json::json!({
                 "output_file": output_file,
                 "test_cases": response.text,
+                "conventions": match &self.conventions {
+                    Some(guide) => serde_json::json!({ "guide": guide }),
+                    None => serde_json::json!({ "samples": sampled }),
+                },
+                "e2e": self.format.e2e().map(|framework| E2eContext::detect(framework, path, &source_code)),
+                "repository": repository,
             })),
         })
     }
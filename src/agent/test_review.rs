@@ -0,0 +1,324 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::agent::traits::{Agent, AgentResponse, AgentStatus, Progress};
+use crate::llm::{LlmRequest, LlmRouter};
+use crate::error::QitOpsError;
+use crate::scan::{self, Scan, ScanLimits, scan_files_with_progress};
+
+/// Maximum number of bytes of test code included in a single review prompt
+const MAX_REVIEW_BYTES: usize = 120_000;
+
+/// File extensions considered when scanning a directory for test files
+const TEST_EXTENSIONS: &[&str] = &["rs", "py", "js", "ts", "jsx", "tsx", "go", "java", "kt", "cs", "rb", "php", "robot"];
+
+/// Category of a flakiness smell
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SmellKind {
+    /// Fixed sleeps or timeouts
+    Sleep,
+    /// Dependence on the current time
+    TimeDependence,
+    /// Unseeded randomness
+    Randomness,
+    /// State shared between tests
+    SharedState,
+}
+
+impl SmellKind {
+    /// Human-readable label for this smell
+    pub fn label(&self) -> &'static str {
+        match self {
+            SmellKind::Sleep => "sleep",
+            SmellKind::TimeDependence => "time dependence",
+            SmellKind::Randomness => "randomness",
+            SmellKind::SharedState => "shared state",
+        }
+    }
+}
+
+/// A flakiness smell found by the static pre-scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestSmell {
+    /// File containing the smell
+    pub file: String,
+
+    /// 1-based line number
+    pub line: usize,
+
+    /// Smell category
+    pub kind: SmellKind,
+
+    /// The offending line, trimmed
+    pub snippet: String,
+}
+
+/// Patterns used to detect flakiness smells
+const SMELL_PATTERNS: &[(SmellKind, &str)] = &[
+    (SmellKind::Sleep, "sleep("),
+    (SmellKind::Sleep, "Thread.sleep"),
+    (SmellKind::Sleep, "setTimeout("),
+    (SmellKind::Sleep, "waitForTimeout("),
+    (SmellKind::TimeDependence, "Date.now("),
+    (SmellKind::TimeDependence, "new Date()"),
+    (SmellKind::TimeDependence, "datetime.now("),
+    (SmellKind::TimeDependence, "time.time("),
+    (SmellKind::TimeDependence, "SystemTime::now("),
+    (SmellKind::TimeDependence, "Instant::now("),
+    (SmellKind::TimeDependence, "LocalDateTime.now("),
+    (SmellKind::Randomness, "Math.random("),
+    (SmellKind::Randomness, "random."),
+    (SmellKind::Randomness, "rand::"),
+    (SmellKind::SharedState, "static mut "),
+    (SmellKind::SharedState, "lazy_static!"),
+    (SmellKind::SharedState, "global "),
+    (SmellKind::SharedState, "beforeAll("),
+    (SmellKind::SharedState, "setUpClass"),
+];
+
+/// Existing-test quality review agent
+pub struct TestReviewAgent {
+    /// Path to a test file or a directory of tests
+    path: String,
+
+    /// Sources to use
+    sources: Option<Vec<String>>,
+
+    /// Personas to use
+    personas: Option<Vec<String>>,
+
+    /// System prompt override (replaces the built-in system prompt)
+    system_prompt_override: Option<String>,
+
+    /// Limits on scanning a directory of tests
+    scan_limits: ScanLimits,
+
+    /// Called with a status line while the directory is scanned
+    progress: Option<Progress>,
+
+    /// LLM router
+    llm_router: LlmRouter,
+}
+
+impl TestReviewAgent {
+    /// Create a new test review agent
+    pub async fn new(
+        path: String,
+        sources: Option<Vec<String>>,
+        personas: Option<Vec<String>>,
+        llm_router: LlmRouter,
+    ) -> Result<Self> {
+        Ok(Self {
+            path,
+            sources,
+            personas,
+            system_prompt_override: crate::prompt::load_system_prompt_override("test-review")?,
+            scan_limits: crate::config::QitOpsConfigManager::new()?.get_config().context.clone(),
+            progress: None,
+            llm_router,
+        })
+    }
+
+    /// Report scan progress, e.g. to update a spinner
+    pub fn with_progress(mut self, progress: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// Collect the test files to review
+    fn collect_test_files(&self) -> Result<Scan> {
+        let path = Path::new(&self.path);
+        if !path.exists() {
+            return Err(QitOpsError::InvalidInput(format!("Path not found: {}", self.path)).into());
+        }
+
+        if path.is_file() {
+            return Ok(Scan { files: vec![path.to_path_buf()], ..Scan::default() });
+        }
+
+        let is_test_file = |file: &Path| {
+            file.extension()
+                .map(|ext| TEST_EXTENSIONS.contains(&ext.to_string_lossy().as_ref()))
+                .unwrap_or(false)
+        };
+
+        scan_files_with_progress(path, &self.scan_limits, is_test_file, |counts| {
+            if let Some(progress) = &self.progress {
+                progress(&format!("Scanning tests... {} file(s) in {} director(ies)", counts.files, counts.directories));
+            }
+        })
+    }
+
+    /// Scan test code for flakiness smells
+    pub fn detect_smells(file: &str, content: &str) -> Vec<TestSmell> {
+        let mut smells = Vec::new();
+
+        for (index, line) in content.lines().enumerate() {
+            for (kind, pattern) in SMELL_PATTERNS {
+                if line.contains(pattern) {
+                    smells.push(TestSmell {
+                        file: file.to_string(),
+                        line: index + 1,
+                        kind: *kind,
+                        snippet: line.trim().to_string(),
+                    });
+                    break;
+                }
+            }
+        }
+
+        smells
+    }
+
+    /// Generate the prompt for the LLM
+    fn generate_prompt(&self, files: &[(String, String)], smells: &[TestSmell]) -> Result<String> {
+        let mut prompt = String::from(
+            "Review the following existing test files. For each file, evaluate assertion quality, duplication between tests, missing negative and edge cases, and flakiness risks.\n\n",
+        );
+
+        for (name, content) in files {
+            prompt.push_str(&format!("File: {}\n```\n{}\n```\n\n", name, content));
+        }
+
+        if !smells.is_empty() {
+            prompt.push_str("A static pre-scan flagged these potential flakiness smells:\n");
+            for smell in smells {
+                prompt.push_str(&format!("- {}:{} [{}] {}\n", smell.file, smell.line, smell.kind.label(), smell.snippet));
+            }
+            prompt.push('\n');
+        }
+
+        prompt.push_str("Produce a report with an overall score from 0 to 10, a score per file, and concrete refactoring suggestions that reference the affected test names or lines.");
+
+        // Add personas if available
+        if let Some(personas) = self.personas.as_ref().filter(|p| !p.is_empty()) {
+            let persona_manager = crate::cli::persona::PersonaManager::new()?;
+            let persona_prompt = persona_manager.get_prompt_for_personas(personas)?;
+
+            if !persona_prompt.is_empty() {
+                prompt = format!("{}\n\n{}", persona_prompt, prompt);
+            }
+        }
+
+        Ok(prompt)
+    }
+
+    /// Content of the sources, if any
+    fn source_context(&self) -> Result<String> {
+        match self.sources.as_ref().filter(|s| !s.is_empty()) {
+            Some(sources) => crate::cli::source::SourceManager::new()?.get_content_for_sources(sources),
+            None => Ok(String::new()),
+        }
+    }
+
+    /// Get the system prompt
+    fn system_prompt(&self) -> String {
+        if let Some(system_prompt) = &self.system_prompt_override {
+            return system_prompt.clone();
+        }
+
+        "You are a senior test engineer reviewing an existing test suite. Be specific and critical: call out weak or missing assertions, copy-pasted tests, untested failure paths, and sources of flakiness such as sleeps, wall-clock time, randomness, and shared mutable state. Every suggestion must be actionable.".to_string()
+    }
+}
+
+impl Agent for TestReviewAgent {
+    fn init(&mut self) -> Result<()> {
+        // No initialization needed
+        Ok(())
+    }
+
+    async fn execute(&self) -> Result<AgentResponse> {
+        // Collect the test files
+        let scan = self.collect_test_files()?;
+        let scan_limit = scan.limit_summary();
+
+        let paths = scan.files;
+        if paths.is_empty() {
+            return Ok(AgentResponse {
+                status: AgentStatus::Failure,
+                message: match &scan_limit {
+                    Some(limit) => format!("No test files found in {} (scan {})", self.path, limit),
+                    None => format!("No test files found in {}", self.path),
+                },
+                data: None,
+            });
+        }
+
+        // Read the files, staying within the prompt budget
+        let mut files = Vec::new();
+        let mut smells = Vec::new();
+        let mut total_bytes = 0;
+        let mut skipped = Vec::new();
+        let mut duplicates = Vec::new();
+        let mut seen: HashMap<String, String> = HashMap::new();
+
+        if let Some(progress) = &self.progress {
+            progress(&format!("Reading {} test file(s)...", paths.len()));
+        }
+
+        for (path, file) in paths.iter().zip(scan::read_files_hashed(&paths)) {
+            let name = path.to_string_lossy().to_string();
+            let Some(file) = file else { continue };
+
+            // Copies of a file already in the prompt would only use up the budget
+            if let Some(original) = seen.get(&file.hash) {
+                duplicates.push(serde_json::json!({ "file": name, "same_as": original }));
+                continue;
+            }
+            seen.insert(file.hash, name.clone());
+            let content = file.text;
+
+            if total_bytes + content.len() > MAX_REVIEW_BYTES {
+                skipped.push(name);
+                continue;
+            }
+
+            total_bytes += content.len();
+            crate::debug::record_piece("file", &name, &content);
+            smells.extend(Self::detect_smells(&name, &content));
+            files.push((name, content));
+        }
+
+        // Generate the prompt
+        let prompt = self.generate_prompt(&files, &smells)?;
+
+        // Create the LLM request
+        let model = self.llm_router.default_model().unwrap_or_else(|| "tinyllama".to_string());
+        let request = LlmRequest::new(prompt, model)
+            .with_system_message(self.system_prompt())
+            .with_source_context(&self.source_context()?);
+
+        // Send the request to the LLM
+        if let Some(progress) = &self.progress {
+            progress("Reviewing tests...");
+        }
+        let response = self.llm_router.send(request, Some("test-review")).await?;
+
+        // Return the response
+        Ok(AgentResponse {
+            status: AgentStatus::Success,
+            message: match &scan_limit {
+                Some(_) => format!("Reviewed {} test file(s) (scan incomplete)", files.len()),
+                None => format!("Reviewed {} test file(s)", files.len()),
+            },
+            data: Some(serde_json::json!({
+                "files_reviewed": files.iter().map(|(name, _)| name.clone()).collect::<Vec<String>>(),
+                "files_skipped": skipped,
+                "files_duplicate": duplicates,
+                "scan_limit": scan_limit,
+                "smells": smells,
+                "review": response.text,
+            })),
+        })
+    }
+
+    fn name(&self) -> &str {
+        "test-review"
+    }
+
+    fn description(&self) -> &str {
+        "Existing test quality reviewer"
+    }
+}
@@ -1,6 +1,9 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
+/// Callback receiving status lines while an agent works through many files or requests
+pub type Progress = Box<dyn Fn(&str) + Send + Sync>;
+
 /// Agent trait for defining common behavior across all QitOps agents
 pub trait Agent {
     /// Initialize the agent with configuration
@@ -0,0 +1,177 @@
+// Signed result attestations
+
+use anyhow::Result;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::QitOpsError;
+use crate::hash::sha256_hex;
+
+/// Signature algorithm
+pub const ALGORITHM: &str = "hmac-sha256";
+
+/// Field of a signed result holding its attestation
+pub const ATTESTATION_FIELD: &str = "attestation";
+
+/// Environment variable holding the signing key
+pub const SIGNING_KEY_ENV: &str = "QITOPS_SIGNING_KEY";
+
+/// Shortest accepted signing key, in bytes
+pub const MIN_KEY_BYTES: usize = 32;
+
+/// Signing configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SigningConfig {
+    /// File holding the signing key; the `QITOPS_SIGNING_KEY` environment variable takes precedence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_file: Option<String>,
+}
+
+/// Shared key that signs and verifies results
+pub struct SigningKey(Vec<u8>);
+
+impl SigningKey {
+    /// Create a key, rejecting keys too short to be safe
+    pub fn new(key: &str) -> Result<Self> {
+        let key = key.trim();
+        if key.len() < MIN_KEY_BYTES {
+            return Err(QitOpsError::Attestation(format!(
+                "Signing key is {} bytes; use at least {} (e.g. `openssl rand -base64 32`)",
+                key.len(), MIN_KEY_BYTES
+            )).into());
+        }
+
+        Ok(Self(key.as_bytes().to_vec()))
+    }
+
+    /// Read a key from a file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let key = std::fs::read_to_string(path)
+            .map_err(|e| QitOpsError::Attestation(format!("Failed to read signing key {}: {}", path.display(), e)))?;
+        Self::new(&key)
+    }
+
+    /// The configured key: `QITOPS_SIGNING_KEY`, else `signing.key_file`, else none
+    pub fn from_config(config: &SigningConfig) -> Result<Option<Self>> {
+        if let Ok(key) = std::env::var(SIGNING_KEY_ENV)
+            && !key.trim().is_empty()
+        {
+            return Self::new(&key).map(Some);
+        }
+
+        config.key_file.as_deref()
+            .map(|path| Self::from_file(Path::new(path)))
+            .transpose()
+    }
+
+    /// Short identifier of the key, safe to publish
+    pub fn key_id(&self) -> String {
+        sha256_hex(&self.0)[..16].to_string()
+    }
+
+    /// MAC over a message
+    fn mac(&self) -> Hmac<Sha256> {
+        Hmac::<Sha256>::new_from_slice(&self.0).expect("HMAC accepts keys of any length")
+    }
+}
+
+/// Signature attached to a result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attestation {
+    /// Signature algorithm
+    pub algorithm: String,
+
+    /// Identifier of the signing key
+    pub key_id: String,
+
+    /// QitOps version that produced the result
+    pub version: String,
+
+    /// When the result was signed, in seconds since the Unix epoch
+    pub signed_at: u64,
+
+    /// Base64 signature over the result, including the other attestation fields
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub signature: String,
+}
+
+/// Sign a JSON result, adding an `attestation` field
+pub fn sign(document: &mut serde_json::Value, key: &SigningKey) -> Result<()> {
+    let Some(object) = document.as_object_mut() else {
+        return Err(QitOpsError::Attestation("Only JSON objects can be signed".to_string()).into());
+    };
+
+    let mut attestation = Attestation {
+        algorithm: ALGORITHM.to_string(),
+        key_id: key.key_id(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        signed_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default(),
+        signature: String::new(),
+    };
+    object.insert(ATTESTATION_FIELD.to_string(), serde_json::to_value(&attestation)?);
+
+    let mut mac = key.mac();
+    mac.update(canonical_json(document).as_bytes());
+    attestation.signature = base64::engine::general_purpose::STANDARD.encode(mac.finalize().into_bytes());
+
+    document[ATTESTATION_FIELD] = serde_json::to_value(&attestation)?;
+    Ok(())
+}
+
+/// Check a signed JSON result, returning its attestation
+///
+/// Fails if the result is unsigned, was signed with another key, or changed after signing.
+pub fn verify(document: &serde_json::Value, key: &SigningKey) -> Result<Attestation> {
+    let attestation: Attestation = document.get(ATTESTATION_FIELD)
+        .ok_or_else(|| QitOpsError::Attestation("The result is not signed".to_string()))
+        .and_then(|value| serde_json::from_value(value.clone())
+            .map_err(|e| QitOpsError::Attestation(format!("Malformed attestation: {}", e))))?;
+
+    if attestation.algorithm != ALGORITHM {
+        return Err(QitOpsError::Attestation(format!("Unsupported signature algorithm: {}", attestation.algorithm)).into());
+    }
+    if attestation.key_id != key.key_id() {
+        return Err(QitOpsError::Attestation(format!(
+            "The result was signed with key {}, not {}",
+            attestation.key_id, key.key_id()
+        )).into());
+    }
+
+    let signature = base64::engine::general_purpose::STANDARD.decode(&attestation.signature)
+        .map_err(|_| QitOpsError::Attestation("Malformed signature".to_string()))?;
+
+    let mut unsigned = document.clone();
+    if let Some(value) = unsigned.get_mut(ATTESTATION_FIELD).and_then(|value| value.as_object_mut()) {
+        value.remove("signature");
+    }
+
+    let mut mac = key.mac();
+    mac.update(canonical_json(&unsigned).as_bytes());
+    mac.verify_slice(&signature)
+        .map_err(|_| QitOpsError::Attestation("Signature mismatch: the result was changed after it was signed".to_string()))?;
+
+    Ok(attestation)
+}
+
+/// Compact JSON with object keys sorted, so the same document always produces the same bytes
+fn canonical_json(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Object(object) => {
+            let mut entries: Vec<(&String, &serde_json::Value)> = object.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+
+            let fields: Vec<String> = entries.into_iter()
+                .map(|(key, value)| format!("{}:{}", serde_json::Value::String(key.clone()), canonical_json(value)))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        },
+        serde_json::Value::Array(items) => {
+            format!("[{}]", items.iter().map(canonical_json).collect::<Vec<_>>().join(","))
+        },
+        other => other.to_string(),
+    }
+}
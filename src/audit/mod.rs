@@ -0,0 +1,310 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Environment variable overriding the audit log location
+pub const AUDIT_LOG_ENV: &str = "QITOPS_AUDIT_LOG";
+
+/// Environment variable naming the actor recorded for actions
+pub const ACTOR_ENV: &str = "QITOPS_ACTOR";
+
+/// Environment variable setting the run ID recorded for actions
+pub const RUN_ID_ENV: &str = "QITOPS_RUN_ID";
+
+/// Kind of external side effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuditAction {
+    /// Comment posted on a pull request or issue
+    CommentPosted,
+    /// Label applied to a pull request or issue
+    LabelApplied,
+    /// Issue created
+    IssueCreated,
+    /// Command executed on behalf of a bot user
+    CommandExecuted,
+    /// Comment deleted, e.g. when undoing a run
+    CommentDeleted,
+    /// Test case created in a test case management tool
+    TestCaseCreated,
+    /// Report published to Confluence or a docs branch
+    ReportPublished,
+    /// Notification sent, e.g. a report emailed to a distribution list
+    NotificationSent,
+    /// Sensitive prompt approved for a remote LLM provider
+    PromptApproved,
+    /// Sensitive prompt rejected, or expired without a decision
+    PromptRejected,
+    /// Comment edited, e.g. a PR analysis refreshed by a later run
+    CommentUpdated,
+    /// Pull request review posted with inline comments
+    ReviewPosted,
+    /// Comment posted in a pull request review thread, e.g. a reply to a review comment
+    ReviewCommentPosted,
+    /// Review comment deleted, e.g. when undoing a run
+    ReviewCommentDeleted,
+}
+
+impl AuditAction {
+    /// Kebab-case name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::CommentPosted => "comment-posted",
+            AuditAction::LabelApplied => "label-applied",
+            AuditAction::IssueCreated => "issue-created",
+            AuditAction::CommandExecuted => "command-executed",
+            AuditAction::CommentDeleted => "comment-deleted",
+            AuditAction::TestCaseCreated => "test-case-created",
+            AuditAction::ReportPublished => "report-published",
+            AuditAction::NotificationSent => "notification-sent",
+            AuditAction::PromptApproved => "prompt-approved",
+            AuditAction::PromptRejected => "prompt-rejected",
+            AuditAction::CommentUpdated => "comment-updated",
+            AuditAction::ReviewPosted => "review-posted",
+            AuditAction::ReviewCommentPosted => "review-comment-posted",
+            AuditAction::ReviewCommentDeleted => "review-comment-deleted",
+        }
+    }
+
+    /// Action recorded when undoing this one, for actions that can be undone
+    pub fn undone_by(&self) -> Option<AuditAction> {
+        match self {
+            AuditAction::CommentPosted => Some(AuditAction::CommentDeleted),
+            AuditAction::ReviewCommentPosted => Some(AuditAction::ReviewCommentDeleted),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().replace('_', "-").as_str() {
+            "comment-posted" | "comment" => Ok(AuditAction::CommentPosted),
+            "label-applied" | "label" => Ok(AuditAction::LabelApplied),
+            "issue-created" | "issue" => Ok(AuditAction::IssueCreated),
+            "command-executed" | "command" => Ok(AuditAction::CommandExecuted),
+            "comment-deleted" | "delete" => Ok(AuditAction::CommentDeleted),
+            "test-case-created" | "test-case" => Ok(AuditAction::TestCaseCreated),
+            "report-published" | "publish" => Ok(AuditAction::ReportPublished),
+            "notification-sent" | "notify" => Ok(AuditAction::NotificationSent),
+            "prompt-approved" | "approve" => Ok(AuditAction::PromptApproved),
+            "prompt-rejected" | "reject" => Ok(AuditAction::PromptRejected),
+            "comment-updated" | "edit" => Ok(AuditAction::CommentUpdated),
+            "review-posted" | "review" => Ok(AuditAction::ReviewPosted),
+            "review-comment-posted" | "review-comment" => Ok(AuditAction::ReviewCommentPosted),
+            "review-comment-deleted" => Ok(AuditAction::ReviewCommentDeleted),
+            _ => Err(anyhow!("Unknown audit action: {} (expected comment, label, issue, command, delete, test-case, publish, notify, approve, reject, edit, review or review-comment)", s)),
+        }
+    }
+}
+
+/// One recorded side effect
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEvent {
+    /// When the action happened (seconds since the Unix epoch)
+    pub timestamp: u64,
+
+    /// Who triggered the action
+    pub actor: String,
+
+    /// What was done
+    pub action: AuditAction,
+
+    /// What it was done to, e.g. `owner/repo#12` or the command line
+    pub target: String,
+
+    /// Whether the action succeeded
+    pub success: bool,
+
+    /// Extra detail, such as the error of a failed action
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+
+    /// Invocation of QitOps that performed the action
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+
+    /// ID of the created resource, such as a GitHub comment ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_id: Option<u64>,
+}
+
+impl AuditEvent {
+    /// Create an event for an action happening now
+    pub fn new(actor: impl Into<String>, action: AuditAction, target: impl Into<String>) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            actor: actor.into(),
+            action,
+            target: target.into(),
+            success: true,
+            details: None,
+            run_id: Some(run_id().to_string()),
+            resource_id: None,
+        }
+    }
+
+    /// Record the ID of the created resource
+    pub fn with_resource_id(mut self, resource_id: u64) -> Self {
+        self.resource_id = Some(resource_id);
+        self
+    }
+
+    /// Record the outcome of the action
+    pub fn with_outcome<T>(mut self, result: &Result<T>) -> Self {
+        if let Err(e) = result {
+            self.success = false;
+            self.details = Some(e.to_string());
+        }
+        self
+    }
+}
+
+/// Append-only log of external side effects, stored as JSON lines
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    /// Default audit log: `$QITOPS_AUDIT_LOG` or `~/.qitops/audit.log`
+    pub fn default_path() -> Result<PathBuf> {
+        if let Ok(path) = std::env::var(AUDIT_LOG_ENV) {
+            return Ok(PathBuf::from(path));
+        }
+
+        dirs::home_dir()
+            .map(|home| home.join(".qitops").join("audit.log"))
+            .ok_or_else(|| anyhow!("Could not determine home directory"))
+    }
+
+    /// Open the default audit log
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::open(Self::default_path()?))
+    }
+
+    /// Open an audit log at a path
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Log file path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an event
+    ///
+    /// Each event is written with a single `write` to a file opened in append mode, so
+    /// concurrent writers never interleave and existing entries are never rewritten.
+    pub fn append(&self, event: &AuditEvent) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut line = serde_json::to_string(event)?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| anyhow!("Failed to open audit log {}: {}", self.path.display(), e))?;
+        file.write_all(line.as_bytes())
+            .map_err(|e| anyhow!("Failed to write audit log {}: {}", self.path.display(), e))?;
+
+        Ok(())
+    }
+
+    /// Read all events, oldest first
+    pub fn read(&self) -> Result<Vec<AuditEvent>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| anyhow!("Failed to read audit log {}: {}", self.path.display(), e))?;
+
+        let mut events = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(event) => events.push(event),
+                Err(e) => tracing::warn!("Skipping malformed audit log entry on line {}: {}", index + 1, e),
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+/// Who is acting: `$QITOPS_ACTOR`, the GitHub Actions actor, or the local user
+pub fn current_actor() -> String {
+    [ACTOR_ENV, "GITHUB_ACTOR", "USER", "USERNAME"].iter()
+        .find_map(|var| std::env::var(var).ok().filter(|value| !value.is_empty()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// ID of this invocation: `$QITOPS_RUN_ID`, or generated from the start time and process ID
+pub fn run_id() -> &'static str {
+    static RUN_ID: OnceLock<String> = OnceLock::new();
+
+    RUN_ID.get_or_init(|| {
+        std::env::var(RUN_ID_ENV).ok()
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| {
+                let started = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default();
+                format!("{}-{}", started, std::process::id())
+            })
+    })
+}
+
+/// Record an event in the default audit log
+///
+/// Failing to audit must not hide the action's own result, so errors are logged, not returned.
+pub fn record(event: AuditEvent) {
+    let result = AuditLog::open_default().and_then(|log| log.append(&event));
+    if let Err(e) = result {
+        tracing::error!("Failed to record audit event ({} {}): {}", event.action, event.target, e);
+    }
+}
+
+/// Format a Unix timestamp as `YYYY-MM-DD HH:MM:SS UTC`
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let secs = timestamp % 86_400;
+
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC", year, month, day, secs / 3600, (secs % 3600) / 60, secs % 60)
+}
@@ -0,0 +1,413 @@
+// Agent benchmark harness: run agents over fixtures and score them against rubrics
+
+use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use crate::agent::baseline::BaselineMode;
+use crate::agent::traits::{Agent, AgentStatus};
+use crate::agent::{RiskAgent, TestGenAgent, TestReviewAgent};
+use crate::checkpoint::Checkpoint;
+use crate::error::QitOpsError;
+use crate::llm::{LlmClient, LlmRequest, LlmResponse, LlmRouter, RouterConfig};
+use crate::output::Report;
+
+/// Scores saved by `--update-baseline`, in the suite directory
+pub const BASELINE_FILE: &str = "baseline.json";
+
+/// Agents the harness can run
+pub const AGENTS: &[&str] = &["test-gen", "risk", "test-review"];
+
+/// Score drop below the baseline that counts as a regression
+const SCORE_TOLERANCE: f64 = 1e-6;
+
+/// A fixture: an agent input, recorded answers and what a good result looks like
+#[derive(Debug, Clone, Deserialize)]
+pub struct BenchCase {
+    /// Case name; defaults to the file name
+    #[serde(default)]
+    pub name: String,
+
+    /// Agent to run (`test-gen`, `risk` or `test-review`)
+    pub agent: String,
+
+    /// Input file relative to the case file: source for test-gen, diff for risk, tests for test-review
+    pub input: PathBuf,
+
+    /// Files with the recorded LLM answers, in request order, relative to the case file
+    #[serde(default)]
+    pub replay: Vec<PathBuf>,
+
+    /// Checks the result is scored on
+    #[serde(default)]
+    pub rubric: Rubric,
+}
+
+/// Checks a result is scored on; each one set counts equally
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Rubric {
+    /// Phrases the result must mention (case-insensitive)
+    pub must_contain: Vec<String>,
+
+    /// Phrases the result must not mention (case-insensitive)
+    pub must_not_contain: Vec<String>,
+
+    /// Fewest findings expected
+    pub min_findings: Option<usize>,
+
+    /// Most findings expected
+    pub max_findings: Option<usize>,
+
+    /// Overall risk level expected from a risk assessment, e.g. `high`
+    pub overall_risk: Option<String>,
+
+    /// Fewest test cases expected from test generation
+    pub min_test_cases: Option<usize>,
+}
+
+/// Outcome of one rubric check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Check {
+    /// What was checked, e.g. `contains "sql injection"`
+    pub name: String,
+
+    /// Whether the result passed
+    pub passed: bool,
+}
+
+/// Outcome of one case
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseOutcome {
+    /// Case name
+    pub name: String,
+
+    /// Agent run
+    pub agent: String,
+
+    /// Share of checks passed, from 0 to 1; 0 when the agent failed
+    pub score: f64,
+
+    /// Rubric checks
+    pub checks: Vec<Check>,
+
+    /// How long the agent ran, in milliseconds
+    pub duration_ms: u64,
+
+    /// Error, if the agent failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A case that scored lower than its baseline
+#[derive(Debug, Clone, Serialize)]
+pub struct Regression {
+    /// Case name
+    pub case: String,
+
+    /// Score in the baseline
+    pub baseline: f64,
+
+    /// Score now
+    pub score: f64,
+}
+
+/// Results of a suite run
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    /// Whether recorded answers (`replay`) or the configured providers (`live`) answered
+    pub mode: String,
+
+    /// Outcome of every case
+    pub cases: Vec<CaseOutcome>,
+
+    /// Cases that scored lower than the baseline
+    pub regressions: Vec<Regression>,
+
+    /// Checkpoint to resume from when cases failed, so only those are run again
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checkpoint: Option<PathBuf>,
+}
+
+impl BenchReport {
+    /// Mean score of all cases, from 0 to 1
+    pub fn mean_score(&self) -> f64 {
+        if self.cases.is_empty() {
+            return 0.0;
+        }
+        self.cases.iter().map(|case| case.score).sum::<f64>() / self.cases.len() as f64
+    }
+}
+
+/// LLM client that answers with recorded responses, in order
+pub struct ReplayClient {
+    /// Recorded answers
+    answers: Vec<String>,
+
+    /// Index of the next answer
+    next: AtomicUsize,
+}
+
+impl ReplayClient {
+    /// Create a client answering with `answers` in order
+    pub fn new(answers: Vec<String>) -> Self {
+        Self { answers, next: AtomicUsize::new(0) }
+    }
+}
+
+#[async_trait]
+impl LlmClient for ReplayClient {
+    async fn send(&self, request: LlmRequest) -> Result<LlmResponse> {
+        let index = self.next.fetch_add(1, Ordering::SeqCst);
+        let answer = self.answers.get(index)
+            .ok_or_else(|| anyhow!("No recorded answer for request {} ({} recorded)", index + 1, self.answers.len()))?;
+
+        Ok(LlmResponse::new(answer.clone(), request.model, self.name().to_string()))
+    }
+
+    fn name(&self) -> &str {
+        "replay"
+    }
+
+    async fn is_available(&self) -> bool {
+        true
+    }
+}
+
+/// Load the cases of a suite: every `*.json` file in the directory except the baseline
+pub fn load_suite(dir: &Path) -> Result<Vec<(PathBuf, BenchCase)>> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| QitOpsError::InvalidInput(format!("Cannot read suite {}: {}", dir.display(), e)))?;
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter(|path| path.file_name().is_none_or(|name| name != BASELINE_FILE))
+        .collect();
+    paths.sort();
+
+    let mut cases = Vec::new();
+    for path in paths {
+        let mut case: BenchCase = serde_json::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| QitOpsError::InvalidInput(format!("Invalid bench case {}: {}", path.display(), e)))?;
+        if case.name.is_empty() {
+            case.name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        }
+        if !AGENTS.contains(&case.agent.as_str()) {
+            return Err(QitOpsError::InvalidInput(format!(
+                "Bench case {} uses agent {}; supported agents are {}", case.name, case.agent, AGENTS.join(", ")
+            )).into());
+        }
+        cases.push((path, case));
+    }
+
+    if cases.is_empty() {
+        return Err(QitOpsError::InvalidInput(format!("No bench cases (*.json) in {}", dir.display())).into());
+    }
+
+    Ok(cases)
+}
+
+/// Run a suite; with `live` set the configured providers answer instead of the recorded answers
+pub async fn run_suite(dir: &Path, live: Option<LlmRouter>, progress: impl FnMut(&str)) -> Result<BenchReport> {
+    run_suite_resumable(dir, live, None, progress).await
+}
+
+/// Run a suite, checkpointing each case that completes; `resume` skips the cases of an interrupted run
+pub async fn run_suite_resumable(
+    dir: &Path,
+    live: Option<LlmRouter>,
+    resume: Option<&Path>,
+    mut progress: impl FnMut(&str),
+) -> Result<BenchReport> {
+    let cases = load_suite(dir)?;
+    let mode = if live.is_some() { "live" } else { "replay" };
+
+    let target = format!("{} ({})", dir.display(), mode);
+    let mut checkpoint = match resume {
+        Some(path) => Checkpoint::resume(path, "bench", &target)?,
+        None => Checkpoint::create("bench", &target)?,
+    };
+
+    let mut outcomes = Vec::new();
+    for (index, (path, case)) in cases.iter().enumerate() {
+        if let Some(outcome) = checkpoint.output::<CaseOutcome>(&case.name) {
+            outcomes.push(outcome);
+            continue;
+        }
+
+        progress(&format!("Running {} ({}/{})...", case.name, index + 1, cases.len()));
+        let case_dir = path.parent().unwrap_or(Path::new("."));
+
+        let router = match &live {
+            Some(router) => router.clone(),
+            None => replay_router(case_dir, case)?,
+        };
+        let outcome = run_case(case_dir, case, router).await;
+        if outcome.error.is_none()
+            && let Err(e) = checkpoint.record(&case.name, &outcome)
+        {
+            tracing::warn!("{}", e);
+        }
+        outcomes.push(outcome);
+    }
+
+    let checkpoint = checkpoint.finish(outcomes.iter().any(|outcome| outcome.error.is_some()))?;
+    let regressions = regressions(&outcomes, &load_baseline(dir)?);
+    Ok(BenchReport { mode: mode.to_string(), cases: outcomes, regressions, checkpoint })
+}
+
+/// Router answering a case with its recorded answers
+fn replay_router(case_dir: &Path, case: &BenchCase) -> Result<LlmRouter> {
+    if case.replay.is_empty() {
+        return Err(QitOpsError::InvalidInput(format!(
+            "Bench case {} has no recorded answers; add `replay` files or run with --live", case.name
+        )).into());
+    }
+
+    let answers = case.replay.iter()
+        .map(|file| fs::read_to_string(case_dir.join(file))
+            .with_context(|| format!("Failed to read recorded answer {}", file.display())))
+        .collect::<Result<Vec<String>>>()?;
+
+    let config = RouterConfig { providers: Vec::new(), default_provider: "replay".to_string(), ..RouterConfig::default() };
+    Ok(LlmRouter::with_clients(config, vec![Arc::new(ReplayClient::new(answers))]))
+}
+
+/// Run one case and score its result
+async fn run_case(case_dir: &Path, case: &BenchCase, router: LlmRouter) -> CaseOutcome {
+    let started = Instant::now();
+    let result = execute(case_dir, case, router).await;
+
+    let (score, checks, error) = match result {
+        Ok(report) => {
+            let checks = score(&case.rubric, &report);
+            let passed = checks.iter().filter(|check| check.passed).count();
+            let score = if checks.is_empty() { 1.0 } else { passed as f64 / checks.len() as f64 };
+            (score, checks, None)
+        },
+        Err(e) => (0.0, Vec::new(), Some(e.to_string())),
+    };
+
+    CaseOutcome {
+        name: case.name.clone(),
+        agent: case.agent.clone(),
+        score,
+        checks,
+        duration_ms: started.elapsed().as_millis() as u64,
+        error,
+    }
+}
+
+/// Run a case's agent, returning its result as a report
+async fn execute(case_dir: &Path, case: &BenchCase, router: LlmRouter) -> Result<Report> {
+    let input = case_dir.join(&case.input);
+    if !input.exists() {
+        return Err(QitOpsError::InvalidInput(format!("Input of bench case {} not found: {}", case.name, input.display())).into());
+    }
+
+    // test-gen writes its output next to the input, so give it a copy
+    let workspace = std::env::temp_dir().join(format!("qitops-bench-{}-{}", std::process::id(), case.name));
+    let response = match case.agent.as_str() {
+        "test-gen" => {
+            fs::create_dir_all(&workspace)?;
+            let copy = workspace.join(input.file_name().unwrap_or_default());
+            fs::copy(&input, &copy)?;
+
+            let agent = TestGenAgent::new(copy.to_string_lossy().to_string(), "markdown", None, None, router).await?;
+            let response = agent.execute().await;
+            let _ = fs::remove_dir_all(&workspace);
+            response?
+        },
+        "risk" => {
+            RiskAgent::new_from_diff(input.to_string_lossy().to_string(), Vec::new(), Vec::new(), router).await?
+                .with_baseline(BaselineMode::Ignore)
+                .execute().await?
+        },
+        "test-review" => {
+            TestReviewAgent::new(input.to_string_lossy().to_string(), None, None, router).await?
+                .execute().await?
+        },
+        agent => return Err(QitOpsError::InvalidInput(format!("Unsupported bench agent: {}", agent)).into()),
+    };
+
+    if !matches!(response.status, AgentStatus::Success) {
+        return Err(anyhow!("{} failed: {}", case.agent, response.message));
+    }
+
+    Ok(Report::from_response(&case.agent, &case.name, &response))
+}
+
+/// Check a result against a rubric
+pub fn score(rubric: &Rubric, report: &Report) -> Vec<Check> {
+    let mut text = report.body.to_lowercase();
+    for finding in &report.findings {
+        text.push('\n');
+        text.push_str(&finding.title.to_lowercase());
+        text.push('\n');
+        text.push_str(&finding.description.to_lowercase());
+    }
+
+    let mut checks = Vec::new();
+    for phrase in &rubric.must_contain {
+        checks.push(Check { name: format!("contains \"{}\"", phrase), passed: text.contains(&phrase.to_lowercase()) });
+    }
+    for phrase in &rubric.must_not_contain {
+        checks.push(Check { name: format!("does not contain \"{}\"", phrase), passed: !text.contains(&phrase.to_lowercase()) });
+    }
+    if let Some(min) = rubric.min_findings {
+        checks.push(Check { name: format!("at least {} findings", min), passed: report.findings.len() >= min });
+    }
+    if let Some(max) = rubric.max_findings {
+        checks.push(Check { name: format!("at most {} findings", max), passed: report.findings.len() <= max });
+    }
+    if let Some(expected) = &rubric.overall_risk {
+        let actual = report.data.pointer("/risk/overall_risk").and_then(|risk| risk.as_str()).unwrap_or_default();
+        checks.push(Check { name: format!("overall risk {}", expected), passed: actual.eq_ignore_ascii_case(expected) });
+    }
+    if let Some(min) = rubric.min_test_cases {
+        let cases = crate::llm::ensemble::test_cases(&report.body).len();
+        checks.push(Check { name: format!("at least {} test cases", min), passed: cases >= min });
+    }
+
+    checks
+}
+
+/// Baseline scores of a suite, empty when none was saved
+pub fn load_baseline(dir: &Path) -> Result<BTreeMap<String, f64>> {
+    let path = dir.join(BASELINE_FILE);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    serde_json::from_str(&fs::read_to_string(&path)?)
+        .with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Save the scores of a run as the suite's baseline
+pub fn save_baseline(dir: &Path, report: &BenchReport) -> Result<PathBuf> {
+    let scores: BTreeMap<&str, f64> = report.cases.iter().map(|case| (case.name.as_str(), case.score)).collect();
+    let path = dir.join(BASELINE_FILE);
+    fs::write(&path, serde_json::to_string_pretty(&scores)? + "\n")?;
+    Ok(path)
+}
+
+/// Cases that scored lower than their baseline
+pub fn regressions(outcomes: &[CaseOutcome], baseline: &BTreeMap<String, f64>) -> Vec<Regression> {
+    outcomes.iter()
+        .filter_map(|outcome| {
+            let expected = *baseline.get(&outcome.name)?;
+            (outcome.score + SCORE_TOLERANCE < expected).then(|| Regression {
+                case: outcome.name.clone(),
+                baseline: expected,
+                score: outcome.score,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,426 @@
+use anyhow::{Result, anyhow};
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+
+use super::http::{read_request, respond};
+use super::quota::QuotaManager;
+use crate::agent::{Agent, AgentResponse, PrAnalyzeAgent, RiskAgent, TestGenAgent};
+use crate::ci::github::GitHubClient;
+use crate::llm::LazyRouter;
+use crate::output::{MarkdownFormatter, OutputFormatter, Report};
+
+/// Mention that addresses the bot in a comment
+pub const MENTION: &str = "@qitops";
+
+/// Longest reply GitHub accepts in a comment
+const MAX_COMMENT_CHARS: usize = 65_000;
+
+/// A command given to the bot in a comment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MentionCommand {
+    /// `@qitops risk`
+    Risk,
+
+    /// `@qitops analyze` (or `review`, `pr-analyze`)
+    PrAnalyze,
+
+    /// `@qitops generate tests for <path>` (or `test-gen <path>`)
+    TestGen { path: String },
+
+    /// `@qitops help`
+    Help,
+
+    /// Anything the bot doesn't understand
+    Unknown(String),
+}
+
+impl MentionCommand {
+    /// Name of the command, as used in replies and the audit log
+    pub fn name(&self) -> &str {
+        match self {
+            MentionCommand::Risk => "risk",
+            MentionCommand::PrAnalyze => "pr-analyze",
+            MentionCommand::TestGen { .. } => "test-gen",
+            MentionCommand::Help => "help",
+            MentionCommand::Unknown(_) => "unknown",
+        }
+    }
+}
+
+/// Find the first `@qitops` mention in a comment and parse the command after it
+///
+/// Mentions inside quoted replies (`> ...`) and code blocks are ignored, so quoting an earlier
+/// command doesn't run it again.
+pub fn parse_mention(body: &str) -> Option<MentionCommand> {
+    let mut in_code = false;
+
+    for line in body.lines() {
+        let line = line.trim();
+        if line.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code || line.starts_with('>') {
+            continue;
+        }
+
+        // ASCII lowercasing keeps byte offsets, so the match maps back onto the original line
+        let Some(start) = line.to_ascii_lowercase().find(MENTION) else {
+            continue;
+        };
+        let rest = &line[start + MENTION.len()..];
+        // `@qitopsbot` is somebody else
+        if rest.starts_with(|c: char| c.is_alphanumeric() || c == '-' || c == '_') {
+            continue;
+        }
+
+        let words: Vec<&str> = rest.split_whitespace().collect();
+        let lowered: Vec<String> = words.iter().map(|word| word.to_lowercase()).collect();
+        let command = match lowered.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+            [] | ["help", ..] => MentionCommand::Help,
+            ["risk", ..] => MentionCommand::Risk,
+            ["analyze" | "analyse" | "review" | "pr-analyze", ..] => MentionCommand::PrAnalyze,
+            ["test-gen", _, ..] => MentionCommand::TestGen { path: unquote(words[1]) },
+            ["generate", "tests", "for", _, ..] => MentionCommand::TestGen { path: unquote(words[3]) },
+            _ => MentionCommand::Unknown(rest.trim().to_string()),
+        };
+        return Some(command);
+    }
+
+    None
+}
+
+/// Strip Markdown code quotes from a path
+fn unquote(path: &str) -> String {
+    path.trim_matches(|c| c == '`' || c == '"' || c == '\'').to_string()
+}
+
+/// A repository role, from least to most privileged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RepoRole {
+    None,
+    Read,
+    Triage,
+    Write,
+    Maintain,
+    Admin,
+}
+
+impl FromStr for RepoRole {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(RepoRole::None),
+            "read" | "pull" => Ok(RepoRole::Read),
+            "triage" => Ok(RepoRole::Triage),
+            "write" | "push" => Ok(RepoRole::Write),
+            "maintain" => Ok(RepoRole::Maintain),
+            "admin" => Ok(RepoRole::Admin),
+            _ => Err(anyhow!("Unknown repository role: {} (expected read, triage, write, maintain or admin)", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for RepoRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            RepoRole::None => "none",
+            RepoRole::Read => "read",
+            RepoRole::Triage => "triage",
+            RepoRole::Write => "write",
+            RepoRole::Maintain => "maintain",
+            RepoRole::Admin => "admin",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A new comment that mentions the bot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommentEvent {
+    /// Repository owner
+    pub owner: String,
+
+    /// Repository name
+    pub repo: String,
+
+    /// Issue or pull request number
+    pub number: u64,
+
+    /// Whether the comment is on a pull request
+    pub is_pull_request: bool,
+
+    /// Comment ID
+    pub comment_id: u64,
+
+    /// Whether the comment is a review comment on a line of a pull request's diff
+    pub review_comment: bool,
+
+    /// Login of the commenter
+    pub author: String,
+
+    /// The command the comment gives
+    pub command: MentionCommand,
+}
+
+/// Read a webhook delivery into a comment event, if it is a new comment that mentions the bot
+///
+/// Handles `issue_comment` (issues and pull request conversations) and
+/// `pull_request_review_comment` (threads on diff lines). Comments by bots are ignored so
+/// the bot never answers itself.
+pub fn parse_event(event: &str, payload: &Value) -> Option<CommentEvent> {
+    if payload["action"].as_str() != Some("created") {
+        return None;
+    }
+
+    let comment = &payload["comment"];
+    if comment["user"]["type"].as_str() == Some("Bot") {
+        return None;
+    }
+
+    let (number, is_pull_request, review_comment) = match event {
+        "issue_comment" => (
+            payload["issue"]["number"].as_u64()?,
+            !payload["issue"]["pull_request"].is_null(),
+            false,
+        ),
+        "pull_request_review_comment" => (payload["pull_request"]["number"].as_u64()?, true, true),
+        _ => return None,
+    };
+
+    Some(CommentEvent {
+        owner: payload["repository"]["owner"]["login"].as_str()?.to_string(),
+        repo: payload["repository"]["name"].as_str()?.to_string(),
+        number,
+        is_pull_request,
+        comment_id: comment["id"].as_u64()?,
+        review_comment,
+        author: comment["user"]["login"].as_str()?.to_string(),
+        command: parse_mention(comment["body"].as_str()?)?,
+    })
+}
+
+/// Check a delivery's `X-Hub-Signature-256` header against the webhook secret
+pub fn verify_signature(secret: &[u8], payload: &[u8], header: Option<&str>) -> bool {
+    let Some(signature) = header.and_then(|header| header.strip_prefix("sha256=")) else {
+        return false;
+    };
+    let Some(signature) = decode_hex(signature) else {
+        return false;
+    };
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(payload);
+    mac.verify_slice(&signature).is_ok()
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2)
+        .map(|index| u8::from_str_radix(text.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+/// Help text posted for `@qitops help` and commands the bot doesn't know
+fn help_reply(unknown: Option<&str>) -> String {
+    let mut reply = String::new();
+    if let Some(unknown) = unknown {
+        reply.push_str(&format!("I don't know how to `{}`.\n\n", unknown));
+    }
+    reply.push_str(&format!(
+        "Mention me with one of these commands:\n\n\
+        - `{m} risk`: assess the risk of this pull request\n\
+        - `{m} analyze`: analyze this pull request\n\
+        - `{m} generate tests for <path>`: generate test cases for a file\n\
+        - `{m} help`: show this message",
+        m = MENTION
+    ));
+    reply
+}
+
+/// Answers `@qitops` mentions by running the matching agent and replying in the thread
+pub struct MentionBot {
+    /// GitHub client used to read pull requests and post replies
+    github: GitHubClient,
+
+    /// LLM router shared by all requests
+    router: LazyRouter,
+
+    /// Least role a commenter needs for the bot to run a command
+    min_role: RepoRole,
+
+    /// Per-user and per-repository request quotas
+    quotas: QuotaManager,
+}
+
+impl MentionBot {
+    /// Create a bot that answers commenters with at least `min_role`
+    pub fn new(github: GitHubClient, router: LazyRouter, min_role: RepoRole, quotas: QuotaManager) -> Self {
+        Self { github, router, min_role, quotas }
+    }
+
+    /// Run the command of a comment and reply in its thread, returning the reply
+    pub async fn handle(&self, event: &CommentEvent) -> Result<String> {
+        let reply = self.answer(event).await;
+        let reply = match reply {
+            Ok(reply) => reply,
+            Err(e) => {
+                tracing::warn!("{} for {}/{}#{} failed: {}", event.command.name(), event.owner, event.repo, event.number, e);
+                format!("@{} `{}` failed: {}", event.author, event.command.name(), e)
+            }
+        };
+        let reply = truncate_reply(reply);
+
+        if event.review_comment {
+            self.github.reply_to_review_comment(&event.owner, &event.repo, event.number, event.comment_id, &reply).await?;
+        } else {
+            self.github.create_pull_request_comment(&event.owner, &event.repo, event.number, &reply).await?;
+        }
+
+        Ok(reply)
+    }
+
+    /// The reply to a comment, after checking the commenter may run its command
+    async fn answer(&self, event: &CommentEvent) -> Result<String> {
+        let agent_command = match &event.command {
+            MentionCommand::Help => return Ok(help_reply(None)),
+            MentionCommand::Unknown(text) => return Ok(help_reply(Some(text))),
+            command => command,
+        };
+
+        let role: RepoRole = self.github.get_collaborator_role(&event.owner, &event.repo, &event.author).await?
+            .parse()
+            .unwrap_or(RepoRole::None);
+        if role < self.min_role {
+            return Ok(format!(
+                "@{} running `{}` needs the {} role on this repository; you have {}.",
+                event.author, agent_command.name(), self.min_role, role
+            ));
+        }
+
+        let channel = format!("{}/{}", event.owner, event.repo);
+        let _permit = match self.quotas.try_acquire(&event.author, &channel) {
+            Ok(permit) => permit,
+            Err(rejection) => return Ok(rejection.friendly_message()),
+        };
+
+        let router = self.router.get().await?;
+        let number = event.number.to_string();
+        let (title, response) = match agent_command {
+            MentionCommand::Risk | MentionCommand::PrAnalyze if !event.is_pull_request => {
+                return Ok(format!("@{} `{}` only works on pull requests.", event.author, agent_command.name()));
+            },
+            MentionCommand::Risk => {
+                let agent = RiskAgent::new_from_pr(number, Vec::new(), Vec::new(), event.owner.clone(), event.repo.clone(), self.github.clone(), router).await?;
+                ("Risk Assessment", agent.execute().await?)
+            },
+            MentionCommand::PrAnalyze => {
+                let agent = PrAnalyzeAgent::new(number, None, event.owner.clone(), event.repo.clone(), self.github.clone(), router).await?;
+                ("PR Analysis", agent.execute().await?)
+            },
+            MentionCommand::TestGen { path } => ("Test Cases", self.generate_tests(event, path, router).await?),
+            MentionCommand::Help | MentionCommand::Unknown(_) => unreachable!("answered above"),
+        };
+
+        let report = Report::from_response(agent_command.name(), &format!("{}: {}/{}#{}", title, event.owner, event.repo, event.number), &response);
+        Ok(format!("@{} {}", event.author, MarkdownFormatter.format(&report)?))
+    }
+
+    /// Generate test cases for a file of the repository, from the pull request's branch if there is one
+    async fn generate_tests(&self, event: &CommentEvent, path: &str, router: crate::llm::LlmRouter) -> Result<AgentResponse> {
+        let branch = match event.is_pull_request {
+            true => Some(self.github.get_pull_request(&event.owner, &event.repo, event.number).await?.head_branch),
+            false => None,
+        };
+        let content = match self.github.get_file_content(&event.owner, &event.repo, path, branch.as_deref()).await {
+            Ok(content) => content,
+            // Branches of forks aren't in the base repository
+            Err(_) if branch.is_some() => self.github.get_file_content(&event.owner, &event.repo, path, None).await?,
+            Err(e) => return Err(e),
+        };
+
+        // The agent works on files, so the content goes to a scratch copy with the same name
+        let dir = std::env::temp_dir().join(format!("qitops-mention-{}", event.comment_id));
+        let file_name = std::path::Path::new(path).file_name().ok_or_else(|| anyhow!("Not a file path: {}", path))?;
+        std::fs::create_dir_all(&dir)?;
+        let local = dir.join(file_name);
+        std::fs::write(&local, content)?;
+
+        let result = async {
+            TestGenAgent::new(local.to_string_lossy().to_string(), "markdown", None, None, router).await?
+                .execute()
+                .await
+        }.await;
+        let _ = std::fs::remove_dir_all(&dir);
+        result
+    }
+}
+
+/// Cut a reply to the size GitHub accepts
+fn truncate_reply(mut reply: String) -> String {
+    if reply.len() > MAX_COMMENT_CHARS {
+        reply.truncate(reply.floor_char_boundary(MAX_COMMENT_CHARS));
+        reply.push_str("\n\n_(truncated)_");
+    }
+    reply
+}
+
+/// Receive GitHub webhook deliveries on `addr` and answer comments that mention the bot
+///
+/// Deliveries are acknowledged before the agent runs, since GitHub gives up on a delivery after
+/// ten seconds. Deliveries without a valid signature are rejected.
+pub async fn serve(addr: SocketAddr, secret: Vec<u8>, bot: MentionBot) -> Result<()> {
+    let listener = TcpListener::bind(addr).await
+        .map_err(|e| anyhow!("Failed to listen on {}: {}", addr, e))?;
+    let secret = Arc::new(secret);
+    let bot = Arc::new(bot);
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let secret = Arc::clone(&secret);
+        let bot = Arc::clone(&bot);
+        tokio::spawn(async move {
+            if let Err(e) = handle_delivery(stream, &secret, bot).await {
+                tracing::warn!("Webhook delivery from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+/// Read one HTTP request, acknowledge it, then answer its comment in the background
+async fn handle_delivery(mut stream: TcpStream, secret: &[u8], bot: Arc<MentionBot>) -> Result<()> {
+    let request = read_request(&mut stream).await?;
+    let body = request.body.as_slice();
+
+    if !verify_signature(secret, body, request.header("x-hub-signature-256")) {
+        respond(&mut stream, "401 Unauthorized", "invalid signature").await?;
+        return Ok(());
+    }
+
+    let event = request.header("x-github-event").unwrap_or_default().to_string();
+    let payload: Value = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(_) => {
+            respond(&mut stream, "400 Bad Request", "invalid JSON").await?;
+            return Ok(());
+        }
+    };
+
+    let Some(comment) = parse_event(&event, &payload) else {
+        respond(&mut stream, "200 OK", "ignored").await?;
+        return Ok(());
+    };
+    respond(&mut stream, "202 Accepted", "accepted").await?;
+
+    tracing::info!("{} asked for {} on {}/{}#{}", comment.author, comment.command.name(), comment.owner, comment.repo, comment.number);
+    bot.handle(&comment).await?;
+    Ok(())
+}
@@ -0,0 +1,132 @@
+// Minimal HTTP/1.1 handling for the bot's servers
+
+use anyhow::{Result, anyhow};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+
+/// Largest request body accepted
+const MAX_PAYLOAD_BYTES: usize = 5 * 1024 * 1024;
+
+/// How long a client may take to send a whole request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An HTTP request read from a connection
+#[derive(Debug, Clone)]
+pub struct Request {
+    /// Method, e.g. `POST`
+    pub method: String,
+
+    /// Path without the query string
+    pub path: String,
+
+    /// Headers in the order received
+    pub headers: Vec<(String, String)>,
+
+    /// Body
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Value of a header, by case-insensitive name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Token of an `Authorization: Bearer <token>` header
+    pub fn bearer_token(&self) -> Option<&str> {
+        self.header("authorization")?.strip_prefix("Bearer ").map(str::trim)
+    }
+}
+
+/// Read an HTTP request's request line, headers and body
+pub async fn read_request(stream: &mut TcpStream) -> Result<Request> {
+    read_request_within(stream, REQUEST_TIMEOUT).await
+}
+
+/// Read an HTTP request, answering `408 Request Timeout` if it doesn't arrive in full within `limit`
+///
+/// The limit covers the whole request, so a client trickling in a byte at a time can't hold
+/// the connection open any longer than one that sends nothing.
+pub async fn read_request_within(stream: &mut TcpStream, limit: Duration) -> Result<Request> {
+    let deadline = Instant::now() + limit;
+    let mut data = Vec::new();
+    let mut buffer = [0u8; 8192];
+
+    let header_end = loop {
+        let read = read_before(stream, &mut buffer, deadline).await?;
+        if read == 0 {
+            return Err(anyhow!("Connection closed before the request headers ended"));
+        }
+        data.extend_from_slice(&buffer[..read]);
+        if let Some(end) = data.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+        if data.len() > MAX_PAYLOAD_BYTES {
+            return Err(anyhow!("Request headers too large"));
+        }
+    };
+
+    let head = String::from_utf8_lossy(&data[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let target = request_line.next().unwrap_or("/");
+    let path = target.split_once('?').map_or(target, |(path, _)| path).to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+
+    let length: usize = headers.iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(0);
+    if length > MAX_PAYLOAD_BYTES {
+        return Err(anyhow!("Payload of {} bytes is too large", length));
+    }
+
+    while data.len() < header_end + length {
+        let read = read_before(stream, &mut buffer, deadline).await?;
+        if read == 0 {
+            return Err(anyhow!("Connection closed before the request body ended"));
+        }
+        data.extend_from_slice(&buffer[..read]);
+    }
+
+    Ok(Request { method, path, headers, body: data[header_end..header_end + length].to_vec() })
+}
+
+/// Read from the connection, giving up at the deadline
+async fn read_before(stream: &mut TcpStream, buffer: &mut [u8], deadline: Instant) -> Result<usize> {
+    match tokio::time::timeout_at(deadline, stream.read(buffer)).await {
+        Ok(read) => Ok(read?),
+        Err(_) => {
+            respond(stream, "408 Request Timeout", "Request not received in time").await.ok();
+            Err(anyhow!("Timed out reading the request"))
+        }
+    }
+}
+
+/// Write a short plain-text HTTP response
+pub async fn respond(stream: &mut TcpStream, status: &str, body: &str) -> Result<()> {
+    write_response(stream, status, "text/plain", body).await
+}
+
+/// Write a JSON HTTP response
+pub async fn respond_json(stream: &mut TcpStream, status: &str, body: &serde_json::Value) -> Result<()> {
+    write_response(stream, status, "application/json", &body.to_string()).await
+}
+
+/// Write a complete HTTP response and close the exchange
+async fn write_response(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) -> Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, content_type, body.len(), body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
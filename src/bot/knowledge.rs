@@ -1,8 +1,9 @@
 use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 /// Command documentation
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,78 +66,250 @@ pub struct Example {
     pub tags: Vec<String>,
 }
 
-/// Knowledge base
+/// Current knowledge base schema version
+pub const KB_SCHEMA_VERSION: u32 = 2;
+
+/// Knowledge base manifest (manifest.json)
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct KnowledgeBase {
-    /// Command documentation
-    pub commands: HashMap<String, CommandDoc>,
-    
-    /// Configuration documentation
-    pub config: ConfigDoc,
-    
-    /// FAQ
-    pub faq: Vec<FaqEntry>,
-    
-    /// Examples
-    pub examples: Vec<Example>,
+pub struct KbManifest {
+    /// Schema version of the knowledge base files
+    pub schema_version: u32,
 }
 
-impl KnowledgeBase {
-    /// Load knowledge base from files
-    pub fn load(path: &Path) -> Result<Self> {
+/// Result of validating a knowledge base
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KbValidationReport {
+    /// Schema version found on disk
+    pub schema_version: u32,
+
+    /// Whether the knowledge base needed migrating
+    pub migrated: bool,
+
+    /// Problems that prevent the knowledge base from loading
+    pub errors: Vec<String>,
+
+    /// Problems that degrade answers but don't prevent loading
+    pub warnings: Vec<String>,
+}
+
+/// Knowledge base files as untyped JSON, before migration
+#[derive(Debug, Clone)]
+struct RawKnowledgeBase {
+    /// Schema version
+    schema_version: u32,
+
+    /// commands.json
+    commands: Option<Value>,
+
+    /// config.json
+    config: Option<Value>,
+
+    /// faq.json
+    faq: Option<Value>,
+
+    /// examples.json
+    examples: Option<Value>,
+}
+
+impl RawKnowledgeBase {
+    /// Read the knowledge base files from a directory
+    fn read(path: &Path) -> Result<Self> {
         // Check if the path exists
         if !path.exists() {
             return Err(anyhow!("Knowledge base path does not exist: {}", path.display()));
         }
-        
-        // Load command documentation
-        let commands_path = path.join("commands.json");
-        let commands = if commands_path.exists() {
-            let commands_str = fs::read_to_string(&commands_path)?;
-            serde_json::from_str(&commands_str)?
+
+        // Knowledge bases without a manifest predate schema versioning
+        let manifest_path = path.join("manifest.json");
+        let schema_version = if manifest_path.exists() {
+            let manifest: KbManifest = serde_json::from_str(&fs::read_to_string(&manifest_path)?)
+                .map_err(|e| anyhow!("Invalid manifest.json: {}", e))?;
+            manifest.schema_version
         } else {
-            HashMap::new()
+            1
         };
-        
-        // Load configuration documentation
-        let config_path = path.join("config.json");
-        let config = if config_path.exists() {
-            let config_str = fs::read_to_string(&config_path)?;
-            serde_json::from_str(&config_str)?
-        } else {
-            ConfigDoc {
+
+        if schema_version > KB_SCHEMA_VERSION {
+            return Err(anyhow!(
+                "Knowledge base schema version {} is newer than supported version {}; upgrade QitOps Agent",
+                schema_version, KB_SCHEMA_VERSION
+            ));
+        }
+
+        let read_file = |name: &str| -> Result<Option<Value>> {
+            let file_path = path.join(name);
+            if !file_path.exists() {
+                return Ok(None);
+            }
+            let content = fs::read_to_string(&file_path)?;
+            serde_json::from_str(&content)
+                .map(Some)
+                .map_err(|e| anyhow!("{}: invalid JSON: {}", name, e))
+        };
+
+        Ok(Self {
+            schema_version,
+            commands: read_file("commands.json")?,
+            config: read_file("config.json")?,
+            faq: read_file("faq.json")?,
+            examples: read_file("examples.json")?,
+        })
+    }
+
+    /// Apply migrations up to the current schema version
+    fn migrate(mut self) -> Result<Self> {
+        while self.schema_version < KB_SCHEMA_VERSION {
+            match self.schema_version {
+                1 => self.migrate_v1_to_v2(),
+                version => return Err(anyhow!("No migration from knowledge base schema version {}", version)),
+            }
+            self.schema_version += 1;
+        }
+
+        Ok(self)
+    }
+
+    /// Version 2 only added manifest.json, which is written with the migrated files; the
+    /// files themselves are unchanged
+    fn migrate_v1_to_v2(&mut self) {}
+
+    /// Parse the migrated files into a knowledge base
+    fn parse(self) -> Result<KnowledgeBase> {
+        let commands = match self.commands {
+            Some(value) => serde_json::from_value(value).map_err(|e| anyhow!("commands.json: {}", e))?,
+            None => HashMap::new(),
+        };
+
+        let config = match self.config {
+            Some(value) => serde_json::from_value(value).map_err(|e| anyhow!("config.json: {}", e))?,
+            None => ConfigDoc {
                 file_path: "~/.config/qitops/config.json".to_string(),
                 sections: HashMap::new(),
                 examples: Vec::new(),
-            }
+            },
         };
-        
-        // Load FAQ
-        let faq_path = path.join("faq.json");
-        let faq = if faq_path.exists() {
-            let faq_str = fs::read_to_string(&faq_path)?;
-            serde_json::from_str(&faq_str)?
-        } else {
-            Vec::new()
+
+        let faq = match self.faq {
+            Some(value) => serde_json::from_value(value).map_err(|e| anyhow!("faq.json: {}", e))?,
+            None => Vec::new(),
         };
-        
-        // Load examples
-        let examples_path = path.join("examples.json");
-        let examples = if examples_path.exists() {
-            let examples_str = fs::read_to_string(&examples_path)?;
-            serde_json::from_str(&examples_str)?
-        } else {
-            Vec::new()
+
+        let examples = match self.examples {
+            Some(value) => serde_json::from_value(value).map_err(|e| anyhow!("examples.json: {}", e))?,
+            None => Vec::new(),
         };
-        
-        Ok(Self {
+
+        Ok(KnowledgeBase {
             commands,
             config,
             faq,
             examples,
         })
     }
+
+    /// Write the files and manifest back to a directory
+    fn write(&self, path: &Path) -> Result<()> {
+        let files = [
+            ("commands.json", &self.commands),
+            ("config.json", &self.config),
+            ("faq.json", &self.faq),
+            ("examples.json", &self.examples),
+        ];
+
+        for (name, value) in files {
+            if let Some(value) = value {
+                fs::write(path.join(name), serde_json::to_string_pretty(value)?)?;
+            }
+        }
+
+        let manifest = KbManifest { schema_version: self.schema_version };
+        fs::write(path.join("manifest.json"), serde_json::to_string_pretty(&manifest)?)?;
+
+        Ok(())
+    }
+}
+
+/// Knowledge base
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeBase {
+    /// Command documentation
+    pub commands: HashMap<String, CommandDoc>,
     
+    /// Configuration documentation
+    pub config: ConfigDoc,
+    
+    /// FAQ
+    pub faq: Vec<FaqEntry>,
+    
+    /// Examples
+    pub examples: Vec<Example>,
+}
+
+impl KnowledgeBase {
+    /// Load knowledge base from files, migrating older schema versions in memory
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = RawKnowledgeBase::read(path)?;
+        if raw.schema_version < KB_SCHEMA_VERSION {
+            tracing::info!("Migrating knowledge base {} from schema version {} to {}",
+                path.display(), raw.schema_version, KB_SCHEMA_VERSION);
+        }
+
+        raw.migrate()?.parse()
+    }
+
+    /// Validate a knowledge base, optionally writing the migrated files back to disk
+    pub fn validate(path: &Path, write_migrated: bool) -> Result<KbValidationReport> {
+        let raw = RawKnowledgeBase::read(path)?;
+        let original_version = raw.schema_version;
+        let raw = raw.migrate()?;
+
+        let mut report = KbValidationReport {
+            schema_version: original_version,
+            migrated: original_version < KB_SCHEMA_VERSION,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        };
+
+        match raw.clone().parse() {
+            Ok(kb) => kb.check(&mut report),
+            Err(e) => report.errors.push(format!("{:#}", e)),
+        }
+
+        if write_migrated && report.migrated && report.errors.is_empty() {
+            raw.write(path)?;
+        }
+
+        Ok(report)
+    }
+
+    /// Semantic checks that don't prevent loading
+    fn check(&self, report: &mut KbValidationReport) {
+        for (key, command) in &self.commands {
+            if key != &command.name {
+                report.warnings.push(format!("commands.json: key '{}' does not match command name '{}'", key, command.name));
+            }
+            if command.description.trim().is_empty() {
+                report.warnings.push(format!("commands.json: command '{}' has no description", key));
+            }
+        }
+
+        let mut questions = HashSet::new();
+        for entry in &self.faq {
+            if !questions.insert(entry.question.to_lowercase()) {
+                report.warnings.push(format!("faq.json: duplicate question '{}'", entry.question));
+            }
+            if entry.answer.trim().is_empty() {
+                report.warnings.push(format!("faq.json: question '{}' has no answer", entry.question));
+            }
+        }
+
+        for example in &self.examples {
+            if example.code.trim().is_empty() {
+                report.warnings.push(format!("examples.json: example '{}' has no code", example.title));
+            }
+        }
+    }
+
     /// Get documentation for a command
     pub fn get_command_doc(&self, command: &str) -> Option<&CommandDoc> {
         self.commands.get(command)
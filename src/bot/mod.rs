@@ -1,24 +1,22 @@
 use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 use std::io::{self, Write};
-use std::path::PathBuf;
-use std::fs;
+use std::path::{Path, PathBuf};
 
+pub mod github;
+pub mod http;
 pub mod knowledge;
+pub mod quota;
+pub mod server;
+pub mod tenant;
 use knowledge::KnowledgeBase;
+use quota::QuotaConfig;
 
-use crate::llm::{LlmRouter, LlmRequest};
+use crate::audit::{self, AuditAction, AuditEvent};
+use crate::llm::client::{ToolCall, ToolDefinition};
+use crate::llm::{Conversation, LlmRouter, LlmResponse};
 use crate::cli::branding;
-
-/// Chat message
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum ChatMessage {
-    /// User message
-    User(String),
-
-    /// Bot message
-    Bot(String),
-}
+use crate::i18n::{Msg, t};
 
 /// Bot configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +29,31 @@ pub struct BotConfig {
 
     /// Max history length
     pub max_history_length: usize,
+
+    /// Request quotas when serving multiple users
+    #[serde(default)]
+    pub quotas: QuotaConfig,
+
+    /// Home directory for `!exec` commands, set per tenant so commands only see that tenant's
+    /// configuration, cache and credentials
+    #[serde(skip)]
+    pub command_home: Option<PathBuf>,
+
+    /// Keyring namespace of the credentials `!exec` commands use, set per tenant
+    #[serde(skip)]
+    pub secrets_namespace: Option<String>,
+
+    /// Who `!exec` commands are attributed to in the audit log (defaults to the local actor)
+    #[serde(skip)]
+    pub actor: Option<String>,
+
+    /// Whether `!exec` messages run commands; off for messages from other services
+    #[serde(skip)]
+    pub allow_exec: bool,
+
+    /// Directory the file paths of the model's commands are confined to, set per tenant
+    #[serde(skip)]
+    pub workspace: Option<PathBuf>,
 }
 
 impl Default for BotConfig {
@@ -39,6 +62,12 @@ impl Default for BotConfig {
             system_prompt: DEFAULT_SYSTEM_PROMPT.to_string(),
             knowledge_base_path: None,
             max_history_length: 10,
+            quotas: QuotaConfig::default(),
+            command_home: None,
+            secrets_namespace: None,
+            actor: None,
+            allow_exec: true,
+            workspace: None,
         }
     }
 }
@@ -66,13 +95,174 @@ Be helpful, concise, and accurate. If you don't know something, say so.
 Provide examples when appropriate.
 "#;
 
+/// Name the user's messages are attributed to in the chat history
+pub const USER_NAME: &str = "User";
+
+/// Name the bot's messages are attributed to in the chat history
+pub const BOT_NAME: &str = "QitOps Bot";
+
+/// Credentials that isolated commands must not inherit from the server environment
+const ISOLATED_ENV_VARS: &[&str] = &[
+    "GITHUB_TOKEN",
+    "OPENAI_API_KEY",
+    "ANTHROPIC_API_KEY",
+    "HF_TOKEN",
+    "HUGGINGFACE_API_KEY",
+    "QITOPS_OPENAI_API_KEY",
+    "QITOPS_ANTHROPIC_API_KEY",
+    "QITOPS_GITHUB_TOKEN",
+    "QITOPS_MASKING_KEY",
+    crate::secrets::NAMESPACE_ENV,
+];
+
+/// Reply to `!exec` when the bot doesn't run commands for the sender
+pub const EXEC_DISABLED: &str = "Commands can't be run with !exec here. Ask me what you need instead.";
+
+/// Commands the bot never runs, not even with `!exec`: a held prompt needs a decision from
+/// someone other than whoever sent it, and the bot's commands act for the user asking
+const NEVER_EXECUTED: &[&str] = &["approvals"];
+
+/// Name of the tool the model calls to run a QitOps command
+pub const RUN_COMMAND_TOOL: &str = "run_qitops_command";
+
+/// Read-only commands the model may run on its own; anything that posts to external services,
+/// changes configuration or starts a long-running server is left to an explicit `!exec`
+const TOOL_COMMANDS: &[&[&str]] = &[
+    &["run", "test-gen"],
+    &["run", "pr-analyze"],
+    &["run", "risk"],
+    &["run", "test-data"],
+    &["run", "test-review"],
+    &["llm", "list"],
+    &["llm", "status"],
+    &["llm", "usage"],
+    &["llm", "models"],
+    &["github", "status"],
+    &["source", "list"],
+    &["source", "show"],
+    &["persona", "list"],
+    &["persona", "show"],
+    &["schema", "list"],
+    &["schema", "show"],
+    &["prompt", "list"],
+    &["prompt", "show"],
+    &["version"],
+];
+
+/// Options the model may pass to those commands; the others post results, write files or
+/// change configuration (`--comment`, `--jira`, `--push-to`, `--output`, `--baseline`, ...)
+const TOOL_OPTIONS: &[&str] = &[
+    "-p", "--path", "--sources", "--personas", "--seed", "--conventions",
+    "--pr", "--patch", "--full", "--keep-noise",
+    "-d", "--diff", "-c", "--components", "-f", "--focus",
+    "-s", "--schema", "--count", "--mask",
+    "--since", "--json", "--provider", "-i", "--id", "-n", "--name", "--default", "--format",
+];
+
+/// Options of those commands naming a file or directory
+const PATH_OPTIONS: &[&str] = &["-p", "--path", "-d", "--diff", "--patch", "--conventions"];
+
+/// Tool that lets the model run a QitOps command instead of describing one
+pub fn run_command_tool() -> ToolDefinition {
+    ToolDefinition::new(
+        RUN_COMMAND_TOOL,
+        "Run a QitOps Agent command and return its output. Only call this when the user asks for a command to be run.",
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "args": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": format!(
+                        "Arguments after `qitops`, e.g. [\"run\", \"risk\", \"--diff\", \"changes.diff\"]. The command must be one of: {}",
+                        TOOL_COMMANDS.iter().map(|command| command.join(" ")).collect::<Vec<_>>().join(", ")
+                    ),
+                }
+            },
+            "required": ["args"],
+        }),
+    )
+}
+
+/// The command line a `run_qitops_command` call asks for, checked against the commands the model may run
+pub fn tool_command(call: &ToolCall) -> Result<String> {
+    if call.name != RUN_COMMAND_TOOL {
+        return Err(anyhow!("Unknown tool: {}", call.name));
+    }
+
+    let args: Vec<&str> = call.arguments["args"].as_array()
+        .ok_or_else(|| anyhow!("{} call is missing its 'args' array", RUN_COMMAND_TOOL))?
+        .iter()
+        .map(|arg| arg.as_str().ok_or_else(|| anyhow!("{} arguments must be strings", RUN_COMMAND_TOOL)))
+        .collect::<Result<_>>()?;
+
+    if args.is_empty() {
+        return Err(anyhow!("{} call has no command", RUN_COMMAND_TOOL));
+    }
+    if !TOOL_COMMANDS.iter().any(|command| args.starts_with(command)) {
+        let command = args.iter().take(2).copied().collect::<Vec<_>>().join(" ");
+        return Err(anyhow!("The bot may not run `qitops {}`; use !exec to run it yourself", command));
+    }
+    if let Some(option) = args.iter()
+        .filter(|arg| arg.starts_with('-'))
+        .map(|arg| arg.split_once('=').map_or(*arg, |(name, _)| name))
+        .find(|option| !TOOL_OPTIONS.contains(option))
+    {
+        return Err(anyhow!("The bot may not pass `{}`; use !exec to run the command yourself", option));
+    }
+
+    shlex::try_join(args).map_err(|e| anyhow!("Invalid command arguments: {}", e))
+}
+
+/// Resolve the file paths of a command line in a workspace, refusing paths leading out of it
+pub fn confine_command(command: &str, workspace: &Path) -> Result<String> {
+    let mut args = shlex::split(command).ok_or_else(|| anyhow!("Failed to parse command"))?;
+    let mut index = 0;
+    while index < args.len() {
+        match args[index].split_once('=') {
+            Some((option, path)) if PATH_OPTIONS.contains(&option) => {
+                args[index] = format!("{}={}", option, tenant::confine_path(workspace, path)?);
+            },
+            None if PATH_OPTIONS.contains(&args[index].as_str()) && index + 1 < args.len() => {
+                index += 1;
+                args[index] = tenant::confine_path(workspace, &args[index])?;
+            },
+            _ => {},
+        }
+        index += 1;
+    }
+    shlex::try_join(args.iter().map(String::as_str)).map_err(|e| anyhow!("Invalid command arguments: {}", e))
+}
+
+/// Reply for a command the bot executed
+pub fn command_reply(command: &str, result: &str) -> String {
+    format!("I executed the command: `{}`\n\nResult:\n```\n{}\n```", command, result)
+}
+
+/// Audit event for a command executed by the bot
+pub fn command_audit_event(actor: String, command: &str, output: &io::Result<std::process::Output>) -> AuditEvent {
+    let mut event = AuditEvent::new(actor, AuditAction::CommandExecuted, format!("qitops {}", command));
+    match output {
+        Ok(output) if !output.status.success() => {
+            event.success = false;
+            event.details = Some(output.status.to_string());
+        },
+        Ok(_) => {},
+        Err(e) => {
+            event.success = false;
+            event.details = Some(e.to_string());
+        },
+    }
+    event
+}
+
 /// QitOps Bot
 pub struct QitOpsBot {
     /// LLM router
     llm_router: LlmRouter,
 
     /// Chat history
-    chat_history: Vec<ChatMessage>,
+    conversation: Conversation,
 
     /// Bot configuration
     config: BotConfig,
@@ -104,7 +294,7 @@ impl QitOpsBot {
 
         Self {
             llm_router,
-            chat_history: Vec::new(),
+            conversation: Conversation::new(config.system_prompt.clone()).with_max_turns(config.max_history_length * 2),
             config,
             knowledge_base,
         }
@@ -114,13 +304,13 @@ impl QitOpsBot {
     pub async fn start_chat_session(&mut self) -> Result<()> {
         // Print welcome message
         branding::print_command_header("QitOps Bot");
-        println!("Welcome to QitOps Bot! Type 'exit' or 'quit' to end the session.");
+        println!("{}", t(Msg::BotWelcome));
         println!();
 
         // Initial bot message
         let initial_message = "Hello! I'm the QitOps Bot. How can I help you with QitOps Agent today?";
         println!("{}: {}", branding::colorize("QitOps Bot", branding::Color::Green), initial_message);
-        self.chat_history.push(ChatMessage::Bot(initial_message.to_string()));
+        self.conversation.push_assistant(BOT_NAME, initial_message.to_string());
 
         // Chat loop
         loop {
@@ -134,8 +324,7 @@ impl QitOpsBot {
 
             // Check for exit command
             if input.eq_ignore_ascii_case("exit") || input.eq_ignore_ascii_case("quit") {
-                println!("\n{}: Goodbye! Feel free to chat again if you need help with QitOps Agent.",
-                    branding::colorize("QitOps Bot", branding::Color::Green));
+                println!("\n{}: {}", branding::colorize("QitOps Bot", branding::Color::Green), t(Msg::BotGoodbye));
                 break;
             }
 
@@ -152,32 +341,30 @@ impl QitOpsBot {
 
     /// Process a user message
     pub async fn process_message(&mut self, message: &str) -> Result<String> {
-        // Add user message to chat history
-        self.chat_history.push(ChatMessage::User(message.to_string()));
-
-        // Trim chat history if it's too long
-        if self.chat_history.len() > self.config.max_history_length * 2 {
-            let new_start = self.chat_history.len() - self.config.max_history_length * 2;
-            self.chat_history = self.chat_history[new_start..].to_vec();
-        }
+        // Add user message to chat history, forgetting the oldest messages
+        self.conversation.push_user(USER_NAME, message.to_string());
 
         // Check if the message is a command execution request
         if message.starts_with("!exec ") {
+            if !self.config.allow_exec {
+                self.conversation.push_assistant(BOT_NAME, EXEC_DISABLED.to_string());
+                return Ok(EXEC_DISABLED.to_string());
+            }
             let command = message.trim_start_matches("!exec ").trim();
             let result = self.execute_command(command).await?;
-            let response = format!("I executed the command: `{}`\n\nResult:\n```\n{}\n```", command, result);
+            let response = command_reply(command, &result);
 
             // Add bot response to chat history
-            self.chat_history.push(ChatMessage::Bot(response.clone()));
+            self.conversation.push_assistant(BOT_NAME, response.clone());
 
             return Ok(response);
         }
 
         // Create the LLM request
-        let prompt = self.generate_prompt();
         let model = self.llm_router.default_model().unwrap_or_else(|| "mistral".to_string());
-        let mut request = LlmRequest::new(prompt, model)
-            .with_system_message(self.config.system_prompt.clone());
+        let mut request = self.conversation.request(model, None, self.llm_router.context_window(None))
+            .with_tool(run_command_tool())
+            .with_cache(false);
 
         // Add knowledge base information if available
         if let Some(kb) = &self.knowledge_base {
@@ -232,45 +419,75 @@ impl QitOpsBot {
         // Send the request to the LLM
         let llm_response = self.llm_router.send(request, None).await?;
 
-        // Extract the text from the response
-        let response_text = llm_response.text;
+        // Run any commands the model asked for
+        let response_text = self.answer(llm_response).await?;
 
         // Add bot response to chat history
-        self.chat_history.push(ChatMessage::Bot(response_text.clone()));
+        self.conversation.push_assistant(BOT_NAME, response_text.clone());
 
         Ok(response_text)
     }
 
-    /// Generate the prompt for the LLM
-    fn generate_prompt(&self) -> String {
-        // Convert chat history to a prompt
-        let mut prompt = String::new();
+    /// The reply to a response, running the commands it calls for
+    async fn answer(&self, response: LlmResponse) -> Result<String> {
+        let mut parts = Vec::new();
+        if !response.text.trim().is_empty() {
+            parts.push(response.text.trim().to_string());
+        }
 
-        for message in &self.chat_history {
-            match message {
-                ChatMessage::User(text) => {
-                    prompt.push_str(&format!("User: {}\n", text));
-                },
-                ChatMessage::Bot(text) => {
-                    prompt.push_str(&format!("QitOps Bot: {}\n", text));
+        for call in &response.tool_calls {
+            let command = tool_command(call).and_then(|command| match &self.config.workspace {
+                Some(workspace) => confine_command(&command, workspace),
+                None => Ok(command),
+            });
+            match command {
+                Ok(command) => {
+                    let result = self.execute_command(&command).await?;
+                    parts.push(command_reply(&command, &result));
                 },
+                Err(e) => parts.push(format!("I couldn't run that command: {}", e)),
             }
         }
 
-        prompt
+        Ok(parts.join("\n\n"))
     }
 
     /// Execute a QitOps Agent command
     pub async fn execute_command(&self, command: &str) -> Result<String> {
         // Parse the command
         let args = shlex::split(command).ok_or_else(|| anyhow!("Failed to parse command"))?;
+        if let Some(never) = args.first().filter(|first| NEVER_EXECUTED.contains(&first.as_str())) {
+            return Err(anyhow!("The bot doesn't run `qitops {}`; run it yourself", never));
+        }
 
         // Create a new process
         let mut process = std::process::Command::new("qitops");
         process.args(&args);
 
+        // Confine the command to the tenant's home so it can't read shared config or credentials
+        if let Some(home) = &self.config.command_home {
+            process.current_dir(home)
+                .env("HOME", home)
+                .env_remove("XDG_CONFIG_HOME")
+                .env_remove("XDG_CACHE_HOME");
+            for var in ISOLATED_ENV_VARS {
+                process.env_remove(var);
+            }
+        }
+        if let Some(namespace) = &self.config.secrets_namespace {
+            process.env(crate::secrets::NAMESPACE_ENV, namespace);
+        }
+
+        // Actions the command records, such as the comments it posts, are the requesting user's
+        let actor = self.config.actor.clone().unwrap_or_else(audit::current_actor);
+        process.env(audit::ACTOR_ENV, &actor);
+
         // Execute the command
-        let output = process.output()?;
+        let output = process.output();
+
+        audit::record(command_audit_event(actor, command, &output));
+
+        let output = output?;
 
         // Return the output
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
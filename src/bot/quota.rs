@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Quota configuration for the bot when it serves multiple users
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaConfig {
+    /// Maximum requests per user within the window (0 = unlimited)
+    #[serde(default = "default_per_user")]
+    pub per_user: usize,
+
+    /// Maximum requests per channel within the window (0 = unlimited)
+    #[serde(default = "default_per_channel")]
+    pub per_channel: usize,
+
+    /// Maximum number of requests processed at the same time
+    #[serde(default = "default_max_concurrent")]
+    pub max_concurrent: usize,
+
+    /// Window length in seconds
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+}
+
+fn default_per_user() -> usize { 20 }
+fn default_per_channel() -> usize { 100 }
+fn default_max_concurrent() -> usize { 4 }
+fn default_window_secs() -> u64 { 3600 }
+
+impl Default for QuotaConfig {
+    fn default() -> Self {
+        Self {
+            per_user: default_per_user(),
+            per_channel: default_per_channel(),
+            max_concurrent: default_max_concurrent(),
+            window_secs: default_window_secs(),
+        }
+    }
+}
+
+/// Reason a request was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuotaRejection {
+    /// The user exceeded their quota
+    User { retry_after: Duration },
+
+    /// The channel exceeded its quota
+    Channel { retry_after: Duration },
+
+    /// Too many requests are already in flight
+    Concurrency,
+
+    /// The tenant used up its LLM token budget
+    Budget { retry_after: Duration },
+}
+
+impl QuotaRejection {
+    /// Friendly message to send back to the user
+    pub fn friendly_message(&self) -> String {
+        match self {
+            QuotaRejection::User { retry_after } => format!(
+                "You've reached your request limit for now. Please try again in {}.",
+                format_duration(*retry_after)
+            ),
+            QuotaRejection::Channel { retry_after } => format!(
+                "This channel has reached its request limit for now. Please try again in {}.",
+                format_duration(*retry_after)
+            ),
+            QuotaRejection::Concurrency => "I'm busy answering other requests right now. Please try again in a moment.".to_string(),
+            QuotaRejection::Budget { retry_after } => format!(
+                "Your team has used its LLM budget for this period. It resets in {}.",
+                format_duration(*retry_after)
+            ),
+        }
+    }
+}
+
+/// Format a duration as a short human-readable string
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs().max(1);
+    if secs >= 3600 {
+        format!("{}h {}m", secs / 3600, (secs % 3600) / 60)
+    } else if secs >= 60 {
+        format!("{}m", secs.div_ceil(60))
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// Quota metrics
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuotaMetrics {
+    /// Requests accepted
+    pub accepted: u64,
+
+    /// Requests rejected by the per-user quota
+    pub rejected_user: u64,
+
+    /// Requests rejected by the per-channel quota
+    pub rejected_channel: u64,
+
+    /// Requests rejected by the concurrency cap
+    pub rejected_concurrency: u64,
+}
+
+/// Request windows per user and channel
+#[derive(Debug, Default)]
+struct QuotaState {
+    /// Request timestamps per user
+    users: HashMap<String, VecDeque<Instant>>,
+
+    /// Request timestamps per channel
+    channels: HashMap<String, VecDeque<Instant>>,
+
+    /// Metrics
+    metrics: QuotaMetrics,
+}
+
+/// Permit held while a request is processed; releases the concurrency slot on drop
+pub struct QuotaPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+/// Per-user and per-channel quota enforcement, shared between bot conversations
+#[derive(Clone)]
+pub struct QuotaManager {
+    /// Configuration
+    config: QuotaConfig,
+
+    /// Request windows and metrics
+    state: Arc<Mutex<QuotaState>>,
+
+    /// Concurrency cap
+    in_flight: Arc<Semaphore>,
+}
+
+impl QuotaManager {
+    /// Create a new quota manager
+    pub fn new(config: QuotaConfig) -> Self {
+        let in_flight = Arc::new(Semaphore::new(config.max_concurrent.max(1)));
+
+        Self {
+            config,
+            state: Arc::new(Mutex::new(QuotaState::default())),
+            in_flight,
+        }
+    }
+
+    /// Try to admit a request from a user in a channel
+    pub fn try_acquire(&self, user: &str, channel: &str) -> Result<QuotaPermit, QuotaRejection> {
+        let now = Instant::now();
+        let window = Duration::from_secs(self.config.window_secs);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(retry_after) = Self::check_window(state.users.entry(user.to_string()).or_default(), self.config.per_user, window, now) {
+            state.metrics.rejected_user += 1;
+            tracing::info!("Bot quota rejection for user {}", user);
+            return Err(QuotaRejection::User { retry_after });
+        }
+
+        if let Some(retry_after) = Self::check_window(state.channels.entry(channel.to_string()).or_default(), self.config.per_channel, window, now) {
+            state.metrics.rejected_channel += 1;
+            tracing::info!("Bot quota rejection for channel {}", channel);
+            return Err(QuotaRejection::Channel { retry_after });
+        }
+
+        let permit = match self.in_flight.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                state.metrics.rejected_concurrency += 1;
+                tracing::info!("Bot concurrency cap reached ({} in flight)", self.config.max_concurrent);
+                return Err(QuotaRejection::Concurrency);
+            },
+        };
+
+        if let Some(requests) = state.users.get_mut(user) {
+            requests.push_back(now);
+        }
+        if let Some(requests) = state.channels.get_mut(channel) {
+            requests.push_back(now);
+        }
+        state.metrics.accepted += 1;
+
+        Ok(QuotaPermit { _permit: permit })
+    }
+
+    /// Drop expired requests from a window and return the retry delay if the limit is reached
+    fn check_window(requests: &mut VecDeque<Instant>, limit: usize, window: Duration, now: Instant) -> Option<Duration> {
+        while requests.front().is_some_and(|t| now.duration_since(*t) >= window) {
+            requests.pop_front();
+        }
+
+        if limit == 0 || requests.len() < limit {
+            return None;
+        }
+
+        requests.front().map(|oldest| window.saturating_sub(now.duration_since(*oldest)))
+    }
+
+    /// Get a snapshot of the quota metrics
+    pub fn metrics(&self) -> QuotaMetrics {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).metrics.clone()
+    }
+}
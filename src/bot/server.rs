@@ -0,0 +1,224 @@
+// HTTP API serving the bot to chat backends (Slack, Discord, ...) and other services
+
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+
+use super::http::{Request, read_request, respond, respond_json};
+use super::quota::{QuotaManager, QuotaMetrics};
+use super::tenant::TenantRegistry;
+use super::{BotConfig, QitOpsBot};
+use crate::llm::LazyRouter;
+
+/// A message posted to `/v1/messages`
+#[derive(Debug, Clone, Deserialize)]
+pub struct MessageRequest {
+    /// Who sent the message, e.g. a Slack user ID
+    pub user: String,
+
+    /// Where it was sent, e.g. a Slack channel ID; quotas and conversations are per channel
+    #[serde(default = "default_channel")]
+    pub channel: String,
+
+    /// Message text
+    pub message: String,
+}
+
+fn default_channel() -> String {
+    "api".to_string()
+}
+
+/// A conversation with the bot
+type Conversation = Arc<Mutex<QitOpsBot>>;
+
+/// The bot as one service for everyone: one LLM configuration, one set of quotas
+pub struct SharedBot {
+    /// API key requests authenticate with in the `Authorization: Bearer` header
+    api_key: String,
+
+    /// LLM router shared by all conversations
+    router: LazyRouter,
+
+    /// Bot configuration for new conversations
+    config: BotConfig,
+
+    /// Request quotas per user and channel
+    quotas: QuotaManager,
+
+    /// Conversations by channel and user
+    conversations: Mutex<HashMap<(String, String), Conversation>>,
+}
+
+impl SharedBot {
+    /// Create a service enforcing the quotas of a bot configuration for requests with an API key
+    pub fn new(router: LazyRouter, config: BotConfig, api_key: String) -> Self {
+        Self {
+            api_key,
+            quotas: QuotaManager::new(config.quotas.clone()),
+            router,
+            config,
+            conversations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The request quotas
+    pub fn quotas(&self) -> &QuotaManager {
+        &self.quotas
+    }
+
+    /// Whether a request's API key is the service's
+    pub fn authenticate(&self, api_key: &str) -> bool {
+        api_key == self.api_key
+    }
+
+    /// Answer a message in the conversation of its user and channel
+    ///
+    /// Requests over quota are answered with a friendly "try later" message before a
+    /// conversation is started, and `!exec` messages are refused.
+    pub async fn process_message(&self, user: &str, channel: &str, message: &str) -> Result<String> {
+        let _permit = match self.quotas.try_acquire(user, channel) {
+            Ok(permit) => permit,
+            Err(rejection) => return Ok(rejection.friendly_message()),
+        };
+
+        let bot = self.conversation(user, channel).await?;
+        let mut bot = bot.lock().await;
+        bot.process_message(message).await
+    }
+
+    /// Get or start the conversation for a user in a channel
+    async fn conversation(&self, user: &str, channel: &str) -> Result<Conversation> {
+        let mut conversations = self.conversations.lock().await;
+        let key = (channel.to_string(), user.to_string());
+
+        if let Some(bot) = conversations.get(&key) {
+            return Ok(bot.clone());
+        }
+
+        let bot_config = BotConfig {
+            actor: Some(format!("{}/{}", channel, user)),
+            allow_exec: false,
+            ..self.config.clone()
+        };
+        let bot = Arc::new(Mutex::new(QitOpsBot::new(self.router.get().await?, Some(bot_config)).await));
+        conversations.insert(key, bot.clone());
+
+        Ok(bot)
+    }
+}
+
+/// Who answers the messages of a server
+pub enum BotService {
+    /// One bot for everyone
+    Shared(Box<SharedBot>),
+
+    /// A bot per tenant, chosen by the API key in the `Authorization: Bearer` header
+    Tenants(TenantRegistry),
+}
+
+impl From<SharedBot> for BotService {
+    fn from(bot: SharedBot) -> Self {
+        Self::Shared(Box::new(bot))
+    }
+}
+
+impl From<TenantRegistry> for BotService {
+    fn from(tenants: TenantRegistry) -> Self {
+        Self::Tenants(tenants)
+    }
+}
+
+impl BotService {
+    /// Answer a message, or `None` if the request doesn't authenticate
+    async fn process_message(&self, request: &Request, message: &MessageRequest) -> Option<Result<String>> {
+        match self {
+            Self::Shared(bot) => {
+                if !bot.authenticate(request.bearer_token()?) {
+                    return None;
+                }
+                Some(bot.process_message(&message.user, &message.channel, &message.message).await)
+            },
+            Self::Tenants(tenants) => {
+                let api_key = request.bearer_token()?;
+                tenants.authenticate(api_key)?;
+                Some(tenants.process_message(api_key, &message.user, &message.channel, &message.message).await)
+            },
+        }
+    }
+
+    /// The quotas the request is subject to, or `None` if it doesn't authenticate
+    fn quotas(&self, request: &Request) -> Option<QuotaMetrics> {
+        match self {
+            Self::Shared(bot) => bot.authenticate(request.bearer_token()?).then(|| bot.quotas().metrics()),
+            Self::Tenants(tenants) => Some(tenants.authenticate(request.bearer_token()?)?.quotas().metrics()),
+        }
+    }
+}
+
+/// Answer bot messages over HTTP on a bound listener
+///
+/// - `POST /v1/messages` with `{"user", "channel", "message"}` answers `{"reply"}`
+/// - `GET /v1/quotas` answers the quota metrics
+///
+/// Requests without the service's or a tenant's API key are answered with 401.
+pub async fn serve(listener: TcpListener, service: impl Into<BotService>) -> Result<()> {
+    let service = Arc::new(service.into());
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let service = Arc::clone(&service);
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, &service).await {
+                tracing::warn!("Bot request from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+/// Read one request and answer it
+async fn handle_connection(mut stream: TcpStream, service: &BotService) -> Result<()> {
+    let request = read_request(&mut stream).await?;
+
+    match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/v1/messages") => {
+            let message = match parse_message(&request) {
+                Ok(message) => message,
+                Err(e) => return respond(&mut stream, "400 Bad Request", &e.to_string()).await,
+            };
+
+            match service.process_message(&request, &message).await {
+                Some(Ok(reply)) => respond_json(&mut stream, "200 OK", &json!({ "reply": reply })).await,
+                Some(Err(e)) => {
+                    tracing::warn!("Bot message from {} in {} failed: {}", message.user, message.channel, e);
+                    respond_json(&mut stream, "500 Internal Server Error", &json!({ "error": e.to_string() })).await
+                },
+                None => unauthorized(&mut stream).await,
+            }
+        },
+        ("GET", "/v1/quotas") => match service.quotas(&request) {
+            Some(metrics) => respond_json(&mut stream, "200 OK", &json!(metrics)).await,
+            None => unauthorized(&mut stream).await,
+        },
+        _ => respond(&mut stream, "404 Not Found", "not found").await,
+    }
+}
+
+/// Refuse a request without a known API key
+async fn unauthorized(stream: &mut TcpStream) -> Result<()> {
+    respond_json(stream, "401 Unauthorized", &json!({ "error": "Missing or unknown API key" })).await
+}
+
+/// Parse the body of a message request
+fn parse_message(request: &Request) -> Result<MessageRequest> {
+    let message: MessageRequest = serde_json::from_slice(&request.body)
+        .map_err(|e| anyhow!("Invalid message: {}", e))?;
+    if message.user.trim().is_empty() {
+        return Err(anyhow!("Invalid message: user is empty"));
+    }
+
+    Ok(message)
+}
@@ -0,0 +1,486 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::Mutex;
+
+use super::quota::{QuotaConfig, QuotaManager, QuotaRejection};
+use super::{BotConfig, QitOpsBot};
+use crate::agent::test_gen::{glob_base, is_glob};
+use crate::ci::GitHubConfig;
+use crate::llm::cost::UsageLedger;
+use crate::llm::{LazyRouter, RouterConfig};
+use crate::secrets;
+
+/// Environment variable overriding the tenants file location
+pub const TENANTS_FILE_ENV: &str = "QITOPS_TENANTS_FILE";
+
+/// LLM token budget for a tenant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantBudget {
+    /// Maximum tokens per window (0 = unlimited)
+    #[serde(default)]
+    pub max_tokens: u64,
+
+    /// Window length in seconds
+    #[serde(default = "default_budget_window_secs")]
+    pub window_secs: u64,
+}
+
+fn default_budget_window_secs() -> u64 { 30 * 24 * 3600 }
+
+impl Default for TenantBudget {
+    fn default() -> Self {
+        Self {
+            max_tokens: 0,
+            window_secs: default_budget_window_secs(),
+        }
+    }
+}
+
+/// A tenant served by a shared deployment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantConfig {
+    /// Tenant identifier, also used as its directory and cache namespace
+    pub id: String,
+
+    /// API keys that authenticate requests as this tenant
+    pub api_keys: Vec<String>,
+
+    /// LLM configuration; the server's configuration is used when omitted
+    #[serde(default)]
+    pub llm: Option<RouterConfig>,
+
+    /// GitHub configuration; the tenant has no GitHub access when omitted
+    #[serde(default)]
+    pub github: Option<GitHubConfig>,
+
+    /// Request quotas
+    #[serde(default)]
+    pub quotas: QuotaConfig,
+
+    /// LLM token budget
+    #[serde(default)]
+    pub budget: TenantBudget,
+
+    /// Directory the tenant's file paths are resolved in and confined to; defaults to
+    /// `workspace` in the tenant directory
+    #[serde(default)]
+    pub workspace: Option<PathBuf>,
+}
+
+/// Tenants file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TenantsConfig {
+    /// Tenants
+    pub tenants: Vec<TenantConfig>,
+}
+
+impl TenantsConfig {
+    /// Default tenants file: `$QITOPS_TENANTS_FILE` or `~/.qitops/tenants.json`
+    pub fn default_path() -> Result<PathBuf> {
+        if let Ok(path) = std::env::var(TENANTS_FILE_ENV) {
+            return Ok(PathBuf::from(path));
+        }
+
+        dirs::home_dir()
+            .map(|home| home.join(".qitops").join("tenants.json"))
+            .ok_or_else(|| anyhow!("Could not determine home directory"))
+    }
+
+    /// Load and validate a tenants file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read tenants file {}: {}", path.display(), e))?;
+        let config: Self = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Failed to parse tenants file {}: {}", path.display(), e))?;
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check that tenant IDs are usable as directory names and that IDs and keys are unique
+    pub fn validate(&self) -> Result<()> {
+        let mut ids = HashMap::new();
+        let mut keys = HashMap::new();
+
+        for tenant in &self.tenants {
+            let valid_id = !tenant.id.is_empty()
+                && tenant.id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid_id {
+                return Err(anyhow!("Invalid tenant ID '{}': use letters, digits, '-' and '_'", tenant.id));
+            }
+            if ids.insert(tenant.id.as_str(), ()).is_some() {
+                return Err(anyhow!("Duplicate tenant ID: {}", tenant.id));
+            }
+            if tenant.api_keys.is_empty() {
+                return Err(anyhow!("Tenant '{}' has no API keys", tenant.id));
+            }
+            for key in &tenant.api_keys {
+                if let Some(other) = keys.insert(key.as_str(), tenant.id.as_str()) {
+                    return Err(anyhow!("API key shared by tenants '{}' and '{}'", other, tenant.id));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Token usage in the current budget window, persisted in the tenant directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct BudgetUsage {
+    /// Start of the window (seconds since the Unix epoch)
+    window_start: u64,
+
+    /// Tokens used in the window
+    tokens: u64,
+}
+
+/// Budget usage and how much of it the router has reported
+#[derive(Debug, Default)]
+struct UsageState {
+    /// Persisted usage
+    usage: BudgetUsage,
+
+    /// Router token count already added to the usage
+    router_tokens_seen: u64,
+
+    /// Token count of the commands' usage ledger already added to the usage
+    ledger_tokens_seen: u64,
+}
+
+/// A conversation with the bot
+type Conversation = Arc<Mutex<QitOpsBot>>;
+
+/// Seconds since the Unix epoch
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Resolve a file, directory or glob pattern in a workspace
+///
+/// Paths are relative to the workspace. Absolute paths, `..` and symlinks leading out of
+/// the workspace are refused, so a tenant can't read another tenant's or the server's files.
+pub fn confine_path(workspace: &Path, path: &str) -> Result<String> {
+    if Path::new(path).components().any(|part| !matches!(part, Component::Normal(_) | Component::CurDir)) {
+        return Err(anyhow!("Path must be relative to the tenant workspace: {}", path));
+    }
+
+    let workspace = fs::canonicalize(workspace)?;
+    let resolved = workspace.join(path);
+
+    // The part of the path that exists must stay in the workspace once symlinks are followed
+    let searched = if is_glob(path) { workspace.join(glob_base(path)) } else { resolved.clone() };
+    let existing = searched.ancestors().find(|ancestor| ancestor.exists()).unwrap_or(&workspace);
+    if !fs::canonicalize(existing)?.starts_with(&workspace) {
+        return Err(anyhow!("Path leads out of the tenant workspace: {}", path));
+    }
+
+    Ok(resolved.to_string_lossy().to_string())
+}
+
+/// Tokens the tenant's commands have recorded in the usage ledger of its home directory
+fn ledger_tokens(dir: &Path) -> u64 {
+    UsageLedger::open(dir.join(".qitops").join("usage.json")).read()
+        .map(|entries| entries.iter().map(|entry| entry.input_tokens + entry.output_tokens).sum())
+        .unwrap_or_default()
+}
+
+/// A tenant and its isolated state
+pub struct Tenant {
+    /// Configuration
+    config: TenantConfig,
+
+    /// Tenant directory holding its configuration, cache and usage
+    dir: PathBuf,
+
+    /// Directory the tenant's file paths are confined to
+    workspace: PathBuf,
+
+    /// LLM router, initialized on first use
+    router: LazyRouter,
+
+    /// Request quotas
+    quotas: QuotaManager,
+
+    /// Budget usage
+    usage: std::sync::Mutex<UsageState>,
+
+    /// Conversations by channel and user
+    conversations: Mutex<HashMap<(String, String), Conversation>>,
+
+    /// Bot configuration for new conversations
+    bot_config: BotConfig,
+}
+
+impl Tenant {
+    /// Set up a tenant's directory and state
+    fn new(config: TenantConfig, dir: PathBuf, server_llm: &RouterConfig, bot_config: &BotConfig) -> Result<Self> {
+        // Lay the directory out like a home directory so `!exec` commands run with HOME set to
+        // it find this tenant's configuration in the usual places
+        let qitops_dir = dir.join(".qitops");
+        let github_dir = dir.join(".config").join("qitops");
+        fs::create_dir_all(&qitops_dir)?;
+        fs::create_dir_all(&github_dir)?;
+        let workspace = config.workspace.clone().unwrap_or_else(|| dir.join("workspace"));
+        fs::create_dir_all(&workspace)?;
+
+        let namespace = format!("tenant-{}", config.id);
+        let mut llm = config.llm.clone().unwrap_or_else(|| server_llm.clone());
+        llm.cache.namespace = Some(namespace.clone());
+        // Keep held prompts in the tenant's own queue, where its `!exec approvals` commands look
+        if let Some(approval) = &mut llm.approval
+            && approval.queue.is_none()
+        {
+            approval.queue = Some(github_dir.join("approvals.db"));
+        }
+
+        // Credentials go to the keyring under the tenant's namespace, where its `!exec` commands
+        // look for them; the files only hold them when the keyring can't be used
+        let mut saved_llm = llm.clone();
+        for provider in &mut saved_llm.providers {
+            let account = secrets::namespaced_account(&namespace, &secrets::provider_account(&provider.provider_type));
+            provider.api_key = secrets::store(&account, provider.api_key.as_deref());
+        }
+        secrets::write_private(&qitops_dir.join("config.json"), serde_json::to_string_pretty(&saved_llm)?.as_bytes())?;
+
+        let mut github = config.github.clone().unwrap_or_default();
+        github.token = secrets::store(&secrets::namespaced_account(&namespace, secrets::GITHUB_ACCOUNT), github.token.as_deref());
+        secrets::write_private(&github_dir.join("github.json"), serde_json::to_string_pretty(&github)?.as_bytes())?;
+
+        let usage = fs::read_to_string(dir.join("usage.json")).ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        // Commands only run as the model's read-only tool calls, with paths in the workspace
+        let bot_config = BotConfig {
+            command_home: Some(dir.clone()),
+            secrets_namespace: Some(namespace),
+            workspace: Some(workspace.clone()),
+            allow_exec: false,
+            ..bot_config.clone()
+        };
+
+        Ok(Self {
+            quotas: QuotaManager::new(config.quotas.clone()),
+            router: LazyRouter::with_config(llm),
+            usage: std::sync::Mutex::new(UsageState { usage, router_tokens_seen: 0, ledger_tokens_seen: ledger_tokens(&dir) }),
+            conversations: Mutex::new(HashMap::new()),
+            bot_config,
+            config,
+            dir,
+            workspace,
+        })
+    }
+
+    /// Tenant ID
+    pub fn id(&self) -> &str {
+        &self.config.id
+    }
+
+    /// Tenant directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Resolve a file, directory or glob pattern of a request in the tenant's workspace
+    pub fn resolve_path(&self, path: &str) -> Result<String> {
+        confine_path(&self.workspace, path)
+    }
+
+    /// The tenant's LLM router
+    pub fn router(&self) -> &LazyRouter {
+        &self.router
+    }
+
+    /// The tenant's GitHub configuration
+    pub fn github(&self) -> GitHubConfig {
+        self.config.github.clone().unwrap_or_default()
+    }
+
+    /// The tenant's request quotas
+    pub fn quotas(&self) -> &QuotaManager {
+        &self.quotas
+    }
+
+    /// Tokens used in the current budget window
+    pub fn tokens_used(&self) -> u64 {
+        let mut state = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        self.roll_window(&mut state.usage);
+        state.usage.tokens
+    }
+
+    /// Start of the current budget window (seconds since the Unix epoch)
+    pub fn window_start(&self) -> u64 {
+        let mut state = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        self.roll_window(&mut state.usage);
+        state.usage.window_start
+    }
+
+    /// Token budget
+    pub fn budget(&self) -> &TenantBudget {
+        &self.config.budget
+    }
+
+    /// Check the budget, returning the time until it resets if it's used up
+    pub fn check_budget(&self) -> Result<(), QuotaRejection> {
+        let budget = &self.config.budget;
+        if budget.max_tokens == 0 {
+            return Ok(());
+        }
+
+        let tokens = self.tokens_used();
+        if tokens < budget.max_tokens {
+            return Ok(());
+        }
+
+        let resets_at = self.window_start() + budget.window_secs;
+        tracing::info!("LLM budget exhausted for tenant {}", self.config.id);
+        Err(QuotaRejection::Budget { retry_after: Duration::from_secs(resets_at.saturating_sub(unix_now())) })
+    }
+
+    /// Add the tokens the tenant's router and commands used since the last call to the budget and save it
+    ///
+    /// Commands run in their own process with the tenant directory as home, so their usage is
+    /// read from the usage ledger they record there. Call this after each request that may have
+    /// used the LLM.
+    pub fn record_usage(&self) -> Result<()> {
+        let mut state = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        let router_total = self.router.get_if_initialized().map_or(state.router_tokens_seen, |router| router.tokens_used());
+        let ledger_total = ledger_tokens(&self.dir);
+        let delta = router_total.saturating_sub(state.router_tokens_seen) + ledger_total.saturating_sub(state.ledger_tokens_seen);
+        state.router_tokens_seen = router_total;
+        state.ledger_tokens_seen = ledger_total;
+        if delta == 0 {
+            return Ok(());
+        }
+
+        self.roll_window(&mut state.usage);
+        state.usage.tokens += delta;
+        fs::write(self.dir.join("usage.json"), serde_json::to_string_pretty(&state.usage)?)?;
+
+        Ok(())
+    }
+
+    /// Start a new budget window if the current one has expired
+    fn roll_window(&self, usage: &mut BudgetUsage) {
+        let now = unix_now();
+        if now >= usage.window_start + self.config.budget.window_secs {
+            *usage = BudgetUsage { window_start: now, tokens: 0 };
+        }
+    }
+
+    /// Get or start the conversation for a user in a channel
+    async fn conversation(&self, user: &str, channel: &str) -> Result<Conversation> {
+        let mut conversations = self.conversations.lock().await;
+        let key = (channel.to_string(), user.to_string());
+
+        if let Some(bot) = conversations.get(&key) {
+            return Ok(bot.clone());
+        }
+
+        let router = self.router.get().await?;
+        let bot_config = BotConfig {
+            actor: Some(format!("{}/{}", self.config.id, user)),
+            ..self.bot_config.clone()
+        };
+        let bot = Arc::new(Mutex::new(QitOpsBot::new(router, Some(bot_config)).await));
+        conversations.insert(key, bot.clone());
+
+        Ok(bot)
+    }
+}
+
+/// Tenants of a shared deployment, looked up by API key
+///
+/// Each tenant gets its own directory under the tenants root with its LLM and GitHub
+/// configuration, a namespaced response cache, its own conversation history, quotas and
+/// token budget, so teams sharing a deployment never share credentials or LLM spend.
+pub struct TenantRegistry {
+    /// Tenants by ID
+    tenants: HashMap<String, Arc<Tenant>>,
+
+    /// Tenant IDs by API key
+    keys: HashMap<String, String>,
+}
+
+impl TenantRegistry {
+    /// Default root for tenant directories: `~/.qitops/tenants`
+    pub fn default_root() -> Result<PathBuf> {
+        dirs::home_dir()
+            .map(|home| home.join(".qitops").join("tenants"))
+            .ok_or_else(|| anyhow!("Could not determine home directory"))
+    }
+
+    /// Set up all tenants from a tenants configuration
+    ///
+    /// Tenants without their own LLM configuration use `server_llm`, with a separate cache.
+    pub fn new(config: TenantsConfig, root: &Path, server_llm: &RouterConfig, bot_config: &BotConfig) -> Result<Self> {
+        config.validate()?;
+
+        let mut tenants = HashMap::new();
+        let mut keys = HashMap::new();
+        for tenant_config in config.tenants {
+            for key in &tenant_config.api_keys {
+                keys.insert(key.clone(), tenant_config.id.clone());
+            }
+
+            let id = tenant_config.id.clone();
+            let tenant = Tenant::new(tenant_config, root.join(&id), server_llm, bot_config)
+                .map_err(|e| anyhow!("Failed to set up tenant '{}': {}", id, e))?;
+            tenants.insert(id, Arc::new(tenant));
+        }
+
+        Ok(Self { tenants, keys })
+    }
+
+    /// Find the tenant an API key belongs to
+    pub fn authenticate(&self, api_key: &str) -> Option<Arc<Tenant>> {
+        self.keys.get(api_key).and_then(|id| self.tenants.get(id)).cloned()
+    }
+
+    /// Get a tenant by ID
+    pub fn get(&self, id: &str) -> Option<Arc<Tenant>> {
+        self.tenants.get(id).cloned()
+    }
+
+    /// List tenant IDs
+    pub fn ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self.tenants.keys().map(String::as_str).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Process a message for the tenant owning an API key, enforcing its quotas and budget
+    ///
+    /// Like [`SharedBot::process_message`](super::server::SharedBot::process_message), rejections are answered with a friendly
+    /// message; an unknown API key is an error.
+    pub async fn process_message(&self, api_key: &str, user: &str, channel: &str, message: &str) -> Result<String> {
+        let tenant = self.authenticate(api_key)
+            .ok_or_else(|| anyhow!("Unknown API key"))?;
+
+        if let Err(rejection) = tenant.check_budget() {
+            return Ok(rejection.friendly_message());
+        }
+        let _permit = match tenant.quotas.try_acquire(user, channel) {
+            Ok(permit) => permit,
+            Err(rejection) => return Ok(rejection.friendly_message()),
+        };
+
+        let bot = tenant.conversation(user, channel).await?;
+        let response = bot.lock().await.process_message(message).await;
+
+        if let Err(e) = tenant.record_usage() {
+            tracing::warn!("Failed to record usage for tenant {}: {}", tenant.id(), e);
+        }
+
+        response
+    }
+}
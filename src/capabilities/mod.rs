@@ -0,0 +1,376 @@
+// Capability discovery: what this installation can do, for orchestration tooling and the bot
+
+use anyhow::Result;
+use clap::CommandFactory;
+use serde::Serialize;
+
+use crate::cli::commands::{Cli, TEST_CASE_FORMATS};
+use crate::llm::RouterConfig;
+use crate::output::FormatterRegistry;
+
+/// A required setting and the environment variable that can provide it instead
+type Requirement = (&'static str, Option<&'static str>);
+
+/// Provider types the router can create clients for, with the settings each needs
+const PROVIDERS: &[(&str, &[Requirement])] = &[
+    ("openai", &[("api_key", Some("OPENAI_API_KEY"))]),
+    ("anthropic", &[("api_key", Some("ANTHROPIC_API_KEY"))]),
+    #[cfg(feature = "local-llm")]
+    ("ollama", &[]),
+    ("huggingface", &[("api_key", Some("HUGGINGFACE_API_KEY"))]),
+    #[cfg(feature = "local-llm")]
+    ("local", &[("default_model", None)]),
+    ("mock", &[]),
+];
+
+/// Cargo features the installation was built with
+pub const FEATURES: &[&str] = &[
+    #[cfg(feature = "github")]
+    "github",
+    #[cfg(feature = "monitoring")]
+    "monitoring",
+    #[cfg(feature = "bot")]
+    "bot",
+    #[cfg(feature = "plugins")]
+    "plugins",
+    #[cfg(feature = "local-llm")]
+    "local-llm",
+    #[cfg(feature = "lsp")]
+    "lsp",
+    #[cfg(feature = "pairing")]
+    "pairing",
+    #[cfg(feature = "email")]
+    "email",
+];
+
+/// A setting a provider or integration needs before it can be used
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Setting {
+    /// Setting name in the configuration
+    pub name: String,
+
+    /// Environment variable that can provide it instead
+    pub env: Option<String>,
+
+    /// Whether it's set, in the configuration or the environment
+    pub set: bool,
+}
+
+impl Setting {
+    /// A setting, set if configured or if its environment variable is
+    fn new(name: &str, env: Option<&str>, configured: bool) -> Self {
+        let from_env = env.is_some_and(|env| std::env::var(env).is_ok_and(|value| !value.is_empty()));
+        Self { name: name.to_string(), env: env.map(str::to_string), set: configured || from_env }
+    }
+}
+
+/// An agent run with `qitops run <name>`
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentCapability {
+    /// Command name
+    pub name: String,
+
+    /// What it does
+    pub description: String,
+
+    /// Version of the agent, that of the installation
+    pub version: String,
+
+    /// Options it takes, e.g. `--path`
+    pub options: Vec<String>,
+
+    /// Options it can't run without
+    pub required: Vec<String>,
+
+    /// Daemon method running it, if it's served over JSON-RPC
+    pub daemon_method: Option<String>,
+
+    /// Prompt override files in effect
+    pub prompt_overrides: Vec<String>,
+}
+
+/// An LLM provider type
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderCapability {
+    /// Provider type, as given to `qitops llm add --provider`
+    pub provider_type: String,
+
+    /// Settings it needs
+    pub required_config: Vec<Setting>,
+
+    /// Whether it's in the LLM configuration
+    pub configured: bool,
+
+    /// Whether it's the default provider
+    pub default: bool,
+
+    /// Its default model, when configured
+    pub default_model: Option<String>,
+}
+
+/// An output format for `--format`
+#[derive(Debug, Clone, Serialize)]
+pub struct FormatterCapability {
+    /// Format name
+    pub name: String,
+
+    /// Short description
+    pub description: String,
+
+    /// Whether it's built in rather than a plugin
+    pub builtin: bool,
+
+    /// Version of a built-in formatter, that of the installation
+    pub version: Option<String>,
+}
+
+/// A plugin found in the plugin directories
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginCapability {
+    /// Plugin name
+    pub name: String,
+
+    /// What it extends, e.g. `formatter`
+    pub kind: String,
+
+    /// Program implementing it
+    pub path: String,
+}
+
+/// An external service QitOps can work with
+#[derive(Debug, Clone, Serialize)]
+pub struct IntegrationCapability {
+    /// Integration name
+    pub name: String,
+
+    /// What it's for, e.g. `tcm` or `notification`
+    pub kind: String,
+
+    /// Whether every required setting is set
+    pub configured: bool,
+
+    /// Settings it needs
+    pub required_config: Vec<Setting>,
+
+    /// Command configuring it
+    pub configure: String,
+}
+
+/// Everything this installation can do
+#[derive(Debug, Clone, Serialize)]
+pub struct Capabilities {
+    /// Program name
+    pub name: String,
+
+    /// Installed version
+    pub version: String,
+
+    /// Cargo features it was built with
+    pub features: Vec<String>,
+
+    /// Agents run with `qitops run`
+    pub agents: Vec<AgentCapability>,
+
+    /// LLM provider types
+    pub providers: Vec<ProviderCapability>,
+
+    /// Output formats for `--format`
+    pub formatters: Vec<FormatterCapability>,
+
+    /// Test case file formats `test-gen` writes itself
+    pub test_formats: Vec<String>,
+
+    /// Plugins found
+    pub plugins: Vec<PluginCapability>,
+
+    /// External services
+    pub integrations: Vec<IntegrationCapability>,
+
+    /// Methods served by `qitops daemon`
+    pub daemon_methods: Vec<String>,
+}
+
+impl Capabilities {
+    /// Introspect the installation: its commands, configuration and plugins
+    pub fn collect() -> Result<Self> {
+        let registry = FormatterRegistry::load()?;
+        let llm = crate::llm::ConfigManager::new()?;
+
+        Ok(Self {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            features: FEATURES.iter().map(|feature| feature.to_string()).collect(),
+            agents: agents(),
+            providers: providers(llm.get_config()),
+            formatters: formatters(&registry),
+            test_formats: TEST_CASE_FORMATS.iter().map(|format| format.to_string()).collect(),
+            plugins: plugins(&registry),
+            integrations: integrations()?,
+            daemon_methods: crate::daemon::METHODS.iter().map(|method| method.to_string()).collect(),
+        })
+    }
+}
+
+/// The agents of `qitops run`, read from the command line definition
+pub fn agents() -> Vec<AgentCapability> {
+    let cli = Cli::command();
+    let Some(run) = cli.find_subcommand("run") else {
+        return Vec::new();
+    };
+
+    run.get_subcommands()
+        .map(|agent| {
+            let name = agent.get_name().to_string();
+            let arguments: Vec<&clap::Arg> = agent.get_arguments()
+                .filter(|arg| !arg.is_global_set() && !["help", "version"].contains(&arg.get_id().as_str()))
+                .collect();
+            let option = |arg: &clap::Arg| arg.get_long().map(|long| format!("--{}", long)).unwrap_or_else(|| arg.get_id().to_string());
+
+            AgentCapability {
+                description: agent.get_about().map(|about| about.to_string()).unwrap_or_default(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                options: arguments.iter().map(|arg| option(arg)).collect(),
+                required: arguments.iter().filter(|arg| arg.is_required_set()).map(|arg| option(arg)).collect(),
+                daemon_method: Some(daemon_method(&name)).filter(|method| crate::daemon::METHODS.contains(&method.as_str())),
+                prompt_overrides: prompt_overrides(&name),
+                name,
+            }
+        })
+        .collect()
+}
+
+/// Daemon method name of a command, e.g. `testGen` for `test-gen`
+fn daemon_method(command: &str) -> String {
+    let mut parts = command.split('-');
+    let mut method = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            method.push(first.to_ascii_uppercase());
+            method.extend(chars);
+        }
+    }
+    method
+}
+
+/// System prompt and template overrides of an agent that exist
+fn prompt_overrides(agent: &str) -> Vec<String> {
+    let mut overrides: Vec<String> = crate::prompt::system_prompt_override_paths(agent).unwrap_or_default().into_iter()
+        .filter(|path| path.exists())
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    if crate::prompt::templates::builtin_template(agent).is_ok()
+        && let Ok(Some(path)) = crate::prompt::templates::find_template_override(agent)
+    {
+        overrides.push(path.to_string_lossy().to_string());
+    }
+    overrides
+}
+
+/// Supported provider types, with whether and how each is configured
+pub fn providers(config: &RouterConfig) -> Vec<ProviderCapability> {
+    PROVIDERS.iter()
+        .map(|(provider_type, settings)| {
+            let configured = config.providers.iter().find(|provider| provider.provider_type == *provider_type);
+            let required_config = settings.iter()
+                .map(|(name, env)| {
+                    let set = configured.is_some_and(|provider| match *name {
+                        "api_key" => provider.api_key.as_deref().is_some_and(|key| !key.is_empty()),
+                        _ => !provider.default_model.is_empty(),
+                    });
+                    Setting::new(name, *env, set)
+                })
+                .collect();
+
+            ProviderCapability {
+                provider_type: provider_type.to_string(),
+                required_config,
+                configured: configured.is_some(),
+                default: configured.is_some() && config.default_provider == *provider_type,
+                default_model: configured.map(|provider| provider.default_model.clone()),
+            }
+        })
+        .collect()
+}
+
+/// The output formats of a formatter registry
+pub fn formatters(registry: &FormatterRegistry) -> Vec<FormatterCapability> {
+    registry.formatters().iter()
+        .map(|formatter| {
+            let builtin = registry.is_builtin(formatter.name());
+            FormatterCapability {
+                name: formatter.name().to_string(),
+                description: formatter.description().to_string(),
+                builtin,
+                version: builtin.then(|| env!("CARGO_PKG_VERSION").to_string()),
+            }
+        })
+        .collect()
+}
+
+/// The plugins of a formatter registry
+pub fn plugins(registry: &FormatterRegistry) -> Vec<PluginCapability> {
+    registry.formatters().iter()
+        .filter_map(|formatter| Some(PluginCapability {
+            name: formatter.name().to_string(),
+            kind: "formatter".to_string(),
+            path: formatter.program()?.to_string_lossy().to_string(),
+        }))
+        .collect()
+}
+
+/// The external services, with whether each is configured
+pub fn integrations() -> Result<Vec<IntegrationCapability>> {
+    let (jira, tcm, publish) = (
+        crate::integrations::jira::JiraConfigManager::new()?,
+        crate::integrations::tcm::TcmConfigManager::new()?,
+        crate::integrations::publish::PublishConfigManager::new()?,
+    );
+    let (jira, tcm, publish) = (jira.get_config(), tcm.get_config(), publish.get_config());
+
+    let integration = |name: &str, kind: &str, required_config: Vec<Setting>, configure: &str| IntegrationCapability {
+        name: name.to_string(),
+        kind: kind.to_string(),
+        configured: required_config.iter().all(|setting| setting.set),
+        required_config,
+        configure: configure.to_string(),
+    };
+
+    Ok(vec![
+        #[cfg(feature = "github")]
+        integration("github", "ci", vec![
+            Setting::new("token", Some("GITHUB_TOKEN"), crate::ci::GitHubConfigManager::new()?.get_config().token.is_some()),
+        ], "qitops github config --token <token>"),
+        integration("jira", "issues", vec![
+            Setting::new("url", Some("JIRA_URL"), jira.url.is_some()),
+            Setting::new("api_token", Some("JIRA_API_TOKEN"), jira.api_token.is_some()),
+            Setting::new("project", None, jira.project.is_some()),
+        ], "qitops jira config"),
+        integration("testrail", "tcm", vec![
+            Setting::new("url", Some("TESTRAIL_URL"), tcm.testrail.is_some()),
+            Setting::new("username", Some("TESTRAIL_USERNAME"), tcm.testrail.is_some()),
+            Setting::new("api_key", Some("TESTRAIL_API_KEY"), tcm.testrail.is_some()),
+        ], "qitops tcm config --tool testrail"),
+        integration("xray", "tcm", vec![
+            Setting::new("client_id", Some("XRAY_CLIENT_ID"), tcm.xray.is_some()),
+            Setting::new("client_secret", Some("XRAY_CLIENT_SECRET"), tcm.xray.is_some()),
+        ], "qitops tcm config --tool xray"),
+        integration("zephyr", "tcm", vec![
+            Setting::new("api_token", Some("ZEPHYR_API_TOKEN"), tcm.zephyr.is_some()),
+        ], "qitops tcm config --tool zephyr"),
+        integration("confluence", "publish", vec![
+            Setting::new("url", Some("CONFLUENCE_URL"), publish.confluence.is_some()),
+            Setting::new("api_token", Some("CONFLUENCE_API_TOKEN"), publish.confluence.is_some()),
+        ], "qitops publish config --confluence-url <url> --api-token <token>"),
+        integration("git", "publish", Vec::new(), "qitops publish config --git-remote <remote>"),
+        #[cfg(feature = "email")]
+        integration("email", "notification", {
+            let notify = crate::notification::NotifyConfigManager::new()?;
+            let email = notify.get_config().email.as_ref();
+            vec![
+                Setting::new("host", None, email.is_some()),
+                Setting::new("to", None, email.is_some_and(|email| !email.to.is_empty())),
+            ]
+        }, "qitops notify config email"),
+    ])
+}
@@ -0,0 +1,148 @@
+// Resumable checkpoints of long batch runs
+
+use anyhow::{Result, anyhow};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::QitOpsError;
+use crate::hash::sha256_hex;
+
+/// Progress of a batch run, saved after every unit so an interrupted run can resume
+///
+/// A unit is whatever the batch is made of, e.g. a source file for test generation or a case
+/// for a bench run; its key is what identifies it across runs (the file's path, the case's
+/// name). Units that failed aren't recorded, so they are tried again on resume.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Operation the batch is of, e.g. `test-gen`
+    pub operation: String,
+
+    /// What the batch runs over, e.g. the directory tests are generated for
+    pub target: String,
+
+    /// When the run started, in seconds since the Unix epoch
+    pub started_at: u64,
+
+    /// Output of each completed unit, by unit key
+    pub completed: BTreeMap<String, serde_json::Value>,
+
+    /// File the checkpoint is saved to
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Checkpoint {
+    /// Directory new checkpoints are saved in (`checkpoints/` in the user's cache directory)
+    pub fn default_dir() -> Result<PathBuf> {
+        Ok(dirs::cache_dir()
+            .ok_or_else(|| anyhow!("Could not determine cache directory"))?
+            .join("qitops")
+            .join("checkpoints"))
+    }
+
+    /// Start a checkpoint in the default directory
+    ///
+    /// The file is named after the operation and target, so a run after a crash finds the
+    /// checkpoint at the same path as the run before it (and replaces it unless resumed).
+    pub fn create(operation: &str, target: &str) -> Result<Self> {
+        let digest = &sha256_hex(target.as_bytes())[..12];
+        let path = Self::default_dir()?.join(format!("{}-{}.json", operation, digest));
+        tracing::info!("Checkpointing {} over {} to {}", operation, target, path.display());
+        Ok(Self::at(path, operation, target))
+    }
+
+    /// Start a checkpoint saved to `path`; nothing is written until a unit completes
+    pub fn at(path: impl Into<PathBuf>, operation: &str, target: &str) -> Self {
+        Self {
+            operation: operation.to_string(),
+            target: target.to_string(),
+            started_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default(),
+            completed: BTreeMap::new(),
+            path: path.into(),
+        }
+    }
+
+    /// Load a checkpoint to resume a run of `operation` over `target`
+    pub fn resume(path: &Path, operation: &str, target: &str) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| QitOpsError::InvalidInput(format!("Failed to read checkpoint {}: {}", path.display(), e)))?;
+        let mut checkpoint: Self = serde_json::from_str(&content)
+            .map_err(|e| QitOpsError::InvalidInput(format!("Invalid checkpoint {}: {}", path.display(), e)))?;
+
+        if checkpoint.operation != operation || checkpoint.target != target {
+            return Err(QitOpsError::InvalidInput(format!(
+                "Checkpoint {} is of {} over {}, not {} over {}",
+                path.display(), checkpoint.operation, checkpoint.target, operation, target
+            )).into());
+        }
+
+        tracing::info!("Resuming {} over {}: {} unit(s) already done", operation, target, checkpoint.completed.len());
+        checkpoint.path = path.to_path_buf();
+        Ok(checkpoint)
+    }
+
+    /// Number of completed units
+    pub fn len(&self) -> usize {
+        self.completed.len()
+    }
+
+    /// Whether no unit has completed yet
+    pub fn is_empty(&self) -> bool {
+        self.completed.is_empty()
+    }
+
+    /// Whether a unit completed in an earlier run
+    pub fn is_done(&self, unit: &str) -> bool {
+        self.completed.contains_key(unit)
+    }
+
+    /// Output of a completed unit
+    pub fn output<T: DeserializeOwned>(&self, unit: &str) -> Option<T> {
+        self.completed.get(unit).and_then(|output| serde_json::from_value(output.clone()).ok())
+    }
+
+    /// Record a completed unit and its output, and save the checkpoint
+    pub fn record(&mut self, unit: &str, output: &impl Serialize) -> Result<()> {
+        self.completed.insert(unit.to_string(), serde_json::to_value(output)?);
+        self.save()
+    }
+
+    /// Write the checkpoint, replacing the file at once so an interrupted write can't corrupt it
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| anyhow!("Failed to create checkpoint directory: {}", e))?;
+        }
+
+        let temp = self.path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_string_pretty(self)?)
+            .map_err(|e| anyhow!("Failed to write checkpoint {}: {}", temp.display(), e))?;
+        fs::rename(&temp, &self.path)
+            .map_err(|e| anyhow!("Failed to write checkpoint {}: {}", self.path.display(), e))
+    }
+
+    /// Delete the checkpoint once the run is complete
+    pub fn remove(&self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(anyhow!("Failed to remove checkpoint {}: {}", self.path.display(), e))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Keep the checkpoint of an incomplete run, returning its path to resume from, or delete it
+    ///
+    /// Nothing is kept when no unit completed, as there would be nothing to skip on resume.
+    pub fn finish(&self, incomplete: bool) -> Result<Option<PathBuf>> {
+        if incomplete && !self.is_empty() {
+            return Ok(Some(self.path.clone()));
+        }
+        self.remove()?;
+        Ok(None)
+    }
+}
@@ -1,7 +1,7 @@
 use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
 /// GitHub configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +17,10 @@ pub struct GitHubConfig {
     
     /// Default repository name
     pub default_repo: Option<String>,
+
+    /// HTTP(S) proxy URL, overriding the `HTTPS_PROXY`/`HTTP_PROXY` environment variables
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
 }
 
 impl Default for GitHubConfig {
@@ -26,6 +30,7 @@ impl Default for GitHubConfig {
             api_base: Some("https://api.github.com".to_string()),
             default_owner: None,
             default_repo: None,
+            proxy: None,
         }
     }
 }
@@ -73,10 +78,29 @@ impl GitHubConfigManager {
             GitHubConfig::default()
         };
         
-        Ok(Self {
+        let mut manager = Self {
             config_path,
             config,
-        })
+        };
+        
+        // Move a token saved in plaintext by earlier versions into the OS keyring
+        if crate::secrets::is_enabled()
+            && let Some(token) = &manager.config.token
+        {
+            match crate::secrets::set(crate::secrets::GITHUB_ACCOUNT, token) {
+                Ok(()) => {
+                    manager.save_config()?;
+                    tracing::info!("Moved the GitHub token from {} to the OS keyring", manager.config_path.display());
+                },
+                Err(e) => tracing::debug!("Keeping the GitHub token in {}: {}", manager.config_path.display(), e),
+            }
+        }
+        
+        if manager.config.token.is_none() {
+            manager.config.token = crate::secrets::get(crate::secrets::GITHUB_ACCOUNT);
+        }
+        
+        Ok(manager)
     }
     
     /// Get the configuration
@@ -96,6 +120,12 @@ impl GitHubConfigManager {
         self.save_config()
     }
     
+    /// Set the HTTP(S) proxy URL
+    pub fn set_proxy(&mut self, proxy: String) -> Result<()> {
+        self.config.proxy = Some(proxy);
+        self.save_config()
+    }
+
     /// Set the default repository owner
     pub fn set_default_owner(&mut self, owner: String) -> Result<()> {
         self.config.default_owner = Some(owner);
@@ -109,8 +139,13 @@ impl GitHubConfigManager {
     }
     
     /// Save the configuration
+    ///
+    /// The token goes to the OS keyring unless it's disabled or can't be written.
     pub fn save_config(&self) -> Result<()> {
-        let config_str = serde_json::to_string_pretty(&self.config)
+        let mut config = self.config.clone();
+        config.token = crate::secrets::store(crate::secrets::GITHUB_ACCOUNT, config.token.as_deref());
+        
+        let config_str = serde_json::to_string_pretty(&config)
             .map_err(|e| anyhow!("Failed to serialize config: {}", e))?;
             
         fs::write(&self.config_path, config_str)
@@ -0,0 +1,145 @@
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Footer added to reports and comments in CI when none is configured
+pub const DEFAULT_FOOTER: &str =
+    "_Generated by QitOps for build {{ ci.build_number }} ({{ ci.pipeline_url }}) of {{ ci.commit_short }}, triggered by {{ ci.actor }}_";
+
+/// CI system a run is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CiProvider {
+    /// GitHub Actions
+    GitHubActions,
+    /// GitLab CI/CD
+    GitLab,
+    /// Jenkins
+    Jenkins,
+}
+
+impl fmt::Display for CiProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CiProvider::GitHubActions => write!(f, "GitHub Actions"),
+            CiProvider::GitLab => write!(f, "GitLab"),
+            CiProvider::Jenkins => write!(f, "Jenkins"),
+        }
+    }
+}
+
+/// CI metadata detected from the environment
+#[derive(Debug, Clone, Default)]
+pub struct CiContext {
+    /// CI system, if running in one
+    pub provider: Option<CiProvider>,
+
+    /// Build or pipeline number
+    pub build_number: Option<String>,
+
+    /// Link to the pipeline run
+    pub pipeline_url: Option<String>,
+
+    /// Commit being built
+    pub commit_sha: Option<String>,
+
+    /// User who triggered the run
+    pub actor: Option<String>,
+
+    /// Branch being built
+    pub branch: Option<String>,
+
+    /// Repository or project path
+    pub repository: Option<String>,
+}
+
+impl CiContext {
+    /// Detect GitHub Actions, GitLab CI or Jenkins from their environment variables
+    pub fn detect() -> Self {
+        Self::from_env(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+    }
+
+    /// Detect the CI context from a variable lookup
+    pub fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        if var("GITHUB_ACTIONS").as_deref() == Some("true") {
+            let server = var("GITHUB_SERVER_URL").unwrap_or_else(|| "https://github.com".to_string());
+            let pipeline_url = match (var("GITHUB_REPOSITORY"), var("GITHUB_RUN_ID")) {
+                (Some(repository), Some(run_id)) => Some(format!("{}/{}/actions/runs/{}", server, repository, run_id)),
+                _ => None,
+            };
+
+            return Self {
+                provider: Some(CiProvider::GitHubActions),
+                build_number: var("GITHUB_RUN_NUMBER"),
+                pipeline_url,
+                commit_sha: var("GITHUB_SHA"),
+                actor: var("GITHUB_ACTOR"),
+                branch: var("GITHUB_HEAD_REF").or_else(|| var("GITHUB_REF_NAME")),
+                repository: var("GITHUB_REPOSITORY"),
+            };
+        }
+
+        if var("GITLAB_CI").as_deref() == Some("true") {
+            return Self {
+                provider: Some(CiProvider::GitLab),
+                build_number: var("CI_PIPELINE_IID").or_else(|| var("CI_PIPELINE_ID")),
+                pipeline_url: var("CI_PIPELINE_URL"),
+                commit_sha: var("CI_COMMIT_SHA"),
+                actor: var("GITLAB_USER_LOGIN"),
+                branch: var("CI_MERGE_REQUEST_SOURCE_BRANCH_NAME").or_else(|| var("CI_COMMIT_REF_NAME")),
+                repository: var("CI_PROJECT_PATH"),
+            };
+        }
+
+        if var("JENKINS_URL").is_some() {
+            return Self {
+                provider: Some(CiProvider::Jenkins),
+                build_number: var("BUILD_NUMBER"),
+                pipeline_url: var("BUILD_URL"),
+                commit_sha: var("GIT_COMMIT"),
+                actor: var("BUILD_USER_ID").or_else(|| var("CHANGE_AUTHOR")),
+                branch: var("CHANGE_BRANCH").or_else(|| var("BRANCH_NAME")).or_else(|| var("GIT_BRANCH")),
+                repository: var("JOB_NAME"),
+            };
+        }
+
+        Self::default()
+    }
+
+    /// Whether a CI system was detected
+    pub fn is_ci(&self) -> bool {
+        self.provider.is_some()
+    }
+
+    /// Template variables, without the `ci.` prefix
+    pub fn variables(&self) -> HashMap<&'static str, String> {
+        let commit_short = self.commit_sha.as_ref().map(|sha| sha.chars().take(7).collect());
+
+        [
+            ("provider", self.provider.map(|provider| provider.to_string())),
+            ("build_number", self.build_number.clone()),
+            ("pipeline_url", self.pipeline_url.clone()),
+            ("commit_sha", self.commit_sha.clone()),
+            ("commit_short", commit_short),
+            ("actor", self.actor.clone()),
+            ("branch", self.branch.clone()),
+            ("repository", self.repository.clone()),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name, value.unwrap_or_default()))
+        .collect()
+    }
+
+    /// Replace `{{ ci.<name> }}` variables in a template
+    ///
+    /// Known variables that weren't detected become empty; unknown ones are left as they are.
+    pub fn render(&self, template: &str) -> String {
+        static VARIABLE: OnceLock<Regex> = OnceLock::new();
+        let variable = VARIABLE.get_or_init(|| Regex::new(r"\{\{\s*ci\.([a-z_]+)\s*\}\}").unwrap());
+
+        let variables = self.variables();
+        variable.replace_all(template, |captures: &Captures| {
+            variables.get(&captures[1]).cloned().unwrap_or_else(|| captures[0].to_string())
+        }).into_owned()
+    }
+}
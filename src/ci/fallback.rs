@@ -0,0 +1,267 @@
+// Fallbacks for when the GitHub API can't be reached
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::ci::github::{GitHubClient, GitHubError, PullRequest, PullRequestFile};
+use crate::git::{describe_age, git};
+
+/// Pull request data an analysis works from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrData {
+    /// Pull request information
+    pub pull_request: PullRequest,
+
+    /// Unified diff
+    pub diff: String,
+
+    /// Changed files
+    pub files: Vec<PullRequestFile>,
+}
+
+/// Pull request data, and why it is incomplete or stale if it didn't come from GitHub
+#[derive(Debug, Clone)]
+pub struct PrFetch {
+    /// The data
+    pub data: PrData,
+
+    /// Reason the analysis is degraded, if a fallback was used
+    pub degraded: Option<String>,
+}
+
+/// A cached copy of pull request data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedPr {
+    /// When the data was fetched, in seconds since the Unix epoch
+    fetched_at: u64,
+
+    /// The data
+    data: PrData,
+}
+
+/// On-disk cache of the pull request data last fetched from GitHub
+pub struct PrCache {
+    /// Cache directory
+    dir: PathBuf,
+}
+
+impl PrCache {
+    /// Open the cache in the user's cache directory
+    pub fn new() -> Result<Self> {
+        let dir = dirs::cache_dir()
+            .ok_or_else(|| anyhow!("Could not determine cache directory"))?
+            .join("qitops")
+            .join("pr_cache");
+
+        Ok(Self::at(dir))
+    }
+
+    /// Open the cache in a directory
+    pub fn at(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Path of a pull request's entry
+    fn path(&self, owner: &str, repo: &str, number: u64) -> PathBuf {
+        self.dir.join(owner).join(repo).join(format!("{}.json", number))
+    }
+
+    /// Cached data of a pull request, with when it was fetched
+    pub fn load(&self, owner: &str, repo: &str, number: u64) -> Option<(PrData, u64)> {
+        let content = fs::read_to_string(self.path(owner, repo, number)).ok()?;
+        let cached: CachedPr = serde_json::from_str(&content).ok()?;
+        Some((cached.data, cached.fetched_at))
+    }
+
+    /// Cache the data of a pull request
+    pub fn store(&self, owner: &str, repo: &str, data: &PrData) -> Result<()> {
+        let path = self.path(owner, repo, data.pull_request.number);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| anyhow!("Failed to create PR cache directory: {}", e))?;
+        }
+
+        let cached = CachedPr {
+            fetched_at: now(),
+            data: data.clone(),
+        };
+        fs::write(&path, serde_json::to_string(&cached)?)
+            .map_err(|e| anyhow!("Failed to write PR cache {}: {}", path.display(), e))
+    }
+}
+
+/// Fetch a pull request from GitHub, falling back to local data when GitHub can't be reached
+///
+/// A successful fetch is cached. When a request fails for any reason other than the pull
+/// request not existing, the cached copy is used, or failing that the diff of the local clone
+/// in `repo_dir` against the base branch. Either way the result says why it is degraded.
+pub async fn fetch_pr(
+    client: &GitHubClient,
+    owner: &str,
+    repo: &str,
+    number: u64,
+    cache: &PrCache,
+    repo_dir: &Path,
+) -> Result<PrFetch> {
+    let error = match fetch_from_github(client, owner, repo, number).await {
+        Ok(data) => {
+            if let Err(e) = cache.store(owner, repo, &data) {
+                tracing::warn!("Failed to cache PR data: {}", e);
+            }
+            return Ok(PrFetch { data, degraded: None });
+        },
+        Err(e) if matches!(e.downcast_ref::<GitHubError>(), Some(GitHubError::NotFound(_))) => return Err(e),
+        Err(e) => e,
+    };
+    tracing::warn!("GitHub API request failed, falling back to local data: {}", error);
+
+    if let Some((data, fetched_at)) = cache.load(owner, repo, number) {
+        return Ok(PrFetch {
+            data,
+            degraded: Some(format!(
+                "GitHub unavailable ({}); using PR data cached {}",
+                error, describe_age(now().saturating_sub(fetched_at))
+            )),
+        });
+    }
+
+    match local_diff(repo_dir, number).await {
+        Ok((diff, base, head)) => {
+            let pull_request = PullRequest {
+                number,
+                title: format!("PR #{}", number),
+                body: None,
+                author: String::new(),
+                state: "unknown".to_string(),
+                base_branch: base.clone(),
+                head_branch: head.clone(),
+                created_at: String::new(),
+                updated_at: String::new(),
+                head_sha: String::new(),
+            };
+            let files = files_from_diff(&diff);
+
+            Ok(PrFetch {
+                data: PrData { pull_request, diff, files },
+                degraded: Some(format!(
+                    "GitHub unavailable ({}); using the local diff of {} against {}, without the PR title or description",
+                    error, head, base
+                )),
+            })
+        },
+        // Keep the GitHub error as the cause so it still decides the exit code and hint
+        Err(local) => Err(error.context(format!(
+            "GitHub unavailable, no cached data for {}/{}#{}, and no local diff ({})",
+            owner, repo, number, local
+        ))),
+    }
+}
+
+/// Fetch pull request information, diff and files from GitHub
+async fn fetch_from_github(client: &GitHubClient, owner: &str, repo: &str, number: u64) -> Result<PrData> {
+    let pull_request = client.get_pull_request(owner, repo, number).await?;
+    let diff = client.get_pull_request_diff(owner, repo, number).await?;
+    let files = client.get_pull_request_files(owner, repo, number).await?;
+
+    Ok(PrData { pull_request, diff, files })
+}
+
+/// Diff of a pull request in a local clone, with the base and head it was taken between
+///
+/// The head is the pull request's ref if it has been fetched (`refs/pull/<n>/head` or
+/// `refs/remotes/origin/pr/<n>`), otherwise the checked-out commit, as in a CI checkout of the
+/// pull request. The base is the remote's default branch, or `main` or `master`.
+pub async fn local_diff(repo_dir: &Path, number: u64) -> Result<(String, String, String)> {
+    git(repo_dir, &["rev-parse", "--git-dir"])
+        .map_err(|_| anyhow!("{} is not a git repository", repo_dir.display()))?;
+
+    let pr_refs = [format!("refs/pull/{}/head", number), format!("refs/remotes/origin/pr/{}", number)];
+    let mut head = None;
+    for candidate in &pr_refs {
+        if resolves(repo_dir, candidate) {
+            head = Some(candidate.clone());
+            break;
+        }
+    }
+    let head = head.unwrap_or_else(|| "HEAD".to_string());
+
+    let mut bases: Vec<String> = Vec::new();
+    if let Ok(default) = git(repo_dir, &["symbolic-ref", "--quiet", "--short", "refs/remotes/origin/HEAD"]) {
+        bases.push(default.trim().to_string());
+    }
+    bases.extend(["origin/main", "origin/master", "main", "master"].map(String::from));
+
+    let mut base = None;
+    for candidate in bases {
+        if resolves(repo_dir, &candidate) {
+            base = Some(candidate);
+            break;
+        }
+    }
+    let base = base.ok_or_else(|| anyhow!("Could not find the base branch in {}", repo_dir.display()))?;
+
+    let diff = git(repo_dir, &["diff", &format!("{}...{}", base, head)])
+        .map_err(|e| anyhow!("git diff {}...{} failed: {}", base, head, e))?;
+    if diff.trim().is_empty() {
+        return Err(anyhow!("{} has no changes against {}", head, base));
+    }
+
+    Ok((diff, base, head))
+}
+
+/// Changed files of a unified diff, with their line counts
+pub fn files_from_diff(diff: &str) -> Vec<PullRequestFile> {
+    let mut files: Vec<PullRequestFile> = Vec::new();
+
+    for line in diff.lines() {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            let filename = paths.rsplit_once(" b/").map_or(paths, |(_, b)| b).to_string();
+            files.push(PullRequestFile {
+                filename,
+                status: "modified".to_string(),
+                additions: 0,
+                deletions: 0,
+                changes: 0,
+                contents_url: String::new(),
+                patch: None,
+            });
+            continue;
+        }
+
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        if line.starts_with("new file mode") {
+            file.status = "added".to_string();
+        } else if line.starts_with("deleted file mode") {
+            file.status = "removed".to_string();
+        } else if line.starts_with("rename from") {
+            file.status = "renamed".to_string();
+        } else if line.starts_with('+') && !line.starts_with("+++") {
+            file.additions += 1;
+            file.changes += 1;
+        } else if line.starts_with('-') && !line.starts_with("---") {
+            file.deletions += 1;
+            file.changes += 1;
+        }
+    }
+
+    files
+}
+
+/// Whether a revision exists in a repository
+fn resolves(repo_dir: &Path, revision: &str) -> bool {
+    git(repo_dir, &["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", revision)]).is_ok()
+}
+
+/// Seconds since the Unix epoch
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
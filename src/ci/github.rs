@@ -1,22 +1,36 @@
 use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use std::collections::HashMap;
 use regex::Regex;
 use base64::Engine;
+use crate::audit::{self, AuditAction, AuditEvent};
 use crate::ci::config::GitHubConfig;
+use crate::ci::env::{CiContext, CiProvider};
+use crate::config::QitOpsConfigManager;
 
 /// GitHub API error
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum GitHubError {
     /// API error
-    #[error("API error: {0}")]
-    ApiError(String),
+    #[error("GitHub API error ({status}): {message}")]
+    ApiError { status: u16, message: String },
 
     /// Authentication error
     #[error("Authentication error: {0}")]
     AuthError(String),
 
+    /// Token lacks access to the resource
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    /// Repository, pull request or comment doesn't exist
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    /// Request rejected as invalid
+    #[error("Validation error: {0}")]
+    ValidationError(String),
+
     /// Rate limit error
     #[error("Rate limit error: {0}")]
     RateLimitError(String),
@@ -24,6 +38,24 @@ pub enum GitHubError {
     /// Network error
     #[error("Network error: {0}")]
     NetworkError(String),
+
+    /// Configuration error
+    #[error("Configuration error: {0}")]
+    ConfigurationError(String),
+}
+
+impl GitHubError {
+    /// Classify an unsuccessful response by status code
+    pub fn from_status(status: reqwest::StatusCode, message: String) -> Self {
+        match status.as_u16() {
+            401 => GitHubError::AuthError(message),
+            403 => GitHubError::Forbidden(message),
+            404 => GitHubError::NotFound(message),
+            422 => GitHubError::ValidationError(message),
+            429 => GitHubError::RateLimitError(message),
+            status => GitHubError::ApiError { status, message },
+        }
+    }
 }
 
 /// GitHub PR information
@@ -55,6 +87,20 @@ pub struct PullRequest {
 
     /// PR updated at
     pub updated_at: String,
+
+    /// SHA of the PR's head commit; empty when it isn't known
+    #[serde(default)]
+    pub head_sha: String,
+}
+
+/// How two commits compare, from GitHub's compare API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comparison {
+    /// `ahead`, `behind`, `identical` or `diverged` (e.g. after a force push)
+    pub status: String,
+
+    /// Files changed between the two commits
+    pub files: Vec<String>,
 }
 
 /// GitHub PR file information
@@ -107,6 +153,19 @@ pub struct PullRequestComment {
     pub line: Option<u64>,
 }
 
+/// Inline comment of a pull request review
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewComment {
+    /// File commented on
+    pub path: String,
+
+    /// Position in the file's patch: lines down from its first hunk header
+    pub position: u32,
+
+    /// Comment body
+    pub body: String,
+}
+
 /// Repository information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Repository {
@@ -160,7 +219,27 @@ pub struct Commit {
     pub date: String,
 }
 
+/// A merged pull request of a milestone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergedPullRequest {
+    /// PR number
+    pub number: u64,
+
+    /// PR title
+    pub title: String,
+
+    /// PR author
+    pub author: String,
+
+    /// When it was merged
+    pub merged_at: String,
+
+    /// Label names
+    pub labels: Vec<String>,
+}
+
 /// GitHub client
+#[derive(Clone)]
 pub struct GitHubClient {
     /// API token
     token: String,
@@ -169,7 +248,7 @@ pub struct GitHubClient {
     base_url: String,
 
     /// HTTP client
-    http_client: reqwest::Client,
+    http_client: crate::proxy::RemoteClient,
 }
 
 impl GitHubClient {
@@ -178,22 +257,24 @@ impl GitHubClient {
         Self {
             token,
             base_url: "https://api.github.com".to_string(),
-            http_client: reqwest::Client::new(),
+            http_client: crate::proxy::RemoteClient::new("GitHub", reqwest::Client::new()),
         }
     }
 
     /// Create a new GitHub client from config
     pub fn from_config(config: &GitHubConfig) -> Result<Self> {
+        // Offline, the client is only used to fall back to cached PR data, so no token is needed
         let token = config.token.clone()
             .or_else(|| std::env::var("GITHUB_TOKEN").ok())
-            .ok_or_else(|| anyhow!("GitHub token not found in config or GITHUB_TOKEN environment variable"))?;
+            .or_else(|| crate::offline::is_enabled().then(String::new))
+            .ok_or_else(|| GitHubError::ConfigurationError("GitHub token not found in config or GITHUB_TOKEN environment variable".to_string()))?;
 
         let base_url = config.api_base.clone().unwrap_or_else(|| "https://api.github.com".to_string());
 
         Ok(Self {
             token,
             base_url,
-            http_client: reqwest::Client::new(),
+            http_client: crate::proxy::remote("GitHub", config.proxy.as_deref())?,
         })
     }
 
@@ -246,26 +327,20 @@ impl GitHubClient {
     pub async fn get_pull_request(&self, owner: &str, repo: &str, number: u64) -> Result<PullRequest> {
         let url = format!("{}/repos/{}/{}/pulls/{}", self.base_url, owner, repo, number);
 
-        let response = self.http_client.get(&url)
+        let response = self.http_client.get(&url)?
             .header("Accept", "application/vnd.github.v3+json")
             .header("Authorization", format!("token {}", self.token))
             .header("User-Agent", "QitOps-Agent")
             .send()
             .await
-            .map_err(|e| anyhow!("Failed to send request to GitHub API: {}", e))?;
+            .map_err(|e| GitHubError::NetworkError(format!("Failed to send request to GitHub API: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await
                 .unwrap_or_else(|_| "Could not read error response".to_string());
 
-            return match status.as_u16() {
-                401 => Err(anyhow!("Authentication error: {}", error_text)),
-                403 => Err(anyhow!("Forbidden: {}", error_text)),
-                404 => Err(anyhow!("Not found: {}", error_text)),
-                422 => Err(anyhow!("Validation error: {}", error_text)),
-                _ => Err(anyhow!("GitHub API error ({}): {}", status, error_text)),
-            };
+            return Err(GitHubError::from_status(status, error_text).into());
         }
 
         let pr_data: serde_json::Value = response.json()
@@ -283,6 +358,7 @@ impl GitHubClient {
             head_branch: pr_data["head"]["ref"].as_str().unwrap_or_default().to_string(),
             created_at: pr_data["created_at"].as_str().unwrap_or_default().to_string(),
             updated_at: pr_data["updated_at"].as_str().unwrap_or_default().to_string(),
+            head_sha: pr_data["head"]["sha"].as_str().unwrap_or_default().to_string(),
         };
 
         Ok(pr)
@@ -292,26 +368,20 @@ impl GitHubClient {
     pub async fn get_pull_request_diff(&self, owner: &str, repo: &str, number: u64) -> Result<String> {
         let url = format!("{}/repos/{}/{}/pulls/{}", self.base_url, owner, repo, number);
 
-        let response = self.http_client.get(&url)
+        let response = self.http_client.get(&url)?
             .header("Accept", "application/vnd.github.v3.diff")
             .header("Authorization", format!("token {}", self.token))
             .header("User-Agent", "QitOps-Agent")
             .send()
             .await
-            .map_err(|e| anyhow!("Failed to send request to GitHub API: {}", e))?;
+            .map_err(|e| GitHubError::NetworkError(format!("Failed to send request to GitHub API: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await
                 .unwrap_or_else(|_| "Could not read error response".to_string());
 
-            return match status.as_u16() {
-                401 => Err(anyhow!("Authentication error: {}", error_text)),
-                403 => Err(anyhow!("Forbidden: {}", error_text)),
-                404 => Err(anyhow!("Not found: {}", error_text)),
-                422 => Err(anyhow!("Validation error: {}", error_text)),
-                _ => Err(anyhow!("GitHub API error ({}): {}", status, error_text)),
-            };
+            return Err(GitHubError::from_status(status, error_text).into());
         }
 
         let diff = response.text()
@@ -325,26 +395,20 @@ impl GitHubClient {
     pub async fn get_pull_request_files(&self, owner: &str, repo: &str, number: u64) -> Result<Vec<PullRequestFile>> {
         let url = format!("{}/repos/{}/{}/pulls/{}/files", self.base_url, owner, repo, number);
 
-        let response = self.http_client.get(&url)
+        let response = self.http_client.get(&url)?
             .header("Accept", "application/vnd.github.v3+json")
             .header("Authorization", format!("token {}", self.token))
             .header("User-Agent", "QitOps-Agent")
             .send()
             .await
-            .map_err(|e| anyhow!("Failed to send request to GitHub API: {}", e))?;
+            .map_err(|e| GitHubError::NetworkError(format!("Failed to send request to GitHub API: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await
                 .unwrap_or_else(|_| "Could not read error response".to_string());
 
-            return match status.as_u16() {
-                401 => Err(anyhow!("Authentication error: {}", error_text)),
-                403 => Err(anyhow!("Forbidden: {}", error_text)),
-                404 => Err(anyhow!("Not found: {}", error_text)),
-                422 => Err(anyhow!("Validation error: {}", error_text)),
-                _ => Err(anyhow!("GitHub API error ({}): {}", status, error_text)),
-            };
+            return Err(GitHubError::from_status(status, error_text).into());
         }
 
         let files_data: Vec<serde_json::Value> = response.json()
@@ -368,30 +432,56 @@ impl GitHubClient {
         Ok(files)
     }
 
+    /// Compare two commits, e.g. a PR's head before and after new commits were pushed
+    pub async fn compare_commits(&self, owner: &str, repo: &str, base: &str, head: &str) -> Result<Comparison> {
+        let url = format!("{}/repos/{}/{}/compare/{}...{}", self.base_url, owner, repo, base, head);
+
+        let response = self.http_client.get(&url)?
+            .header("Accept", "application/vnd.github.v3+json")
+            .header("Authorization", format!("token {}", self.token))
+            .header("User-Agent", "QitOps-Agent")
+            .send()
+            .await
+            .map_err(|e| GitHubError::NetworkError(format!("Failed to send request to GitHub API: {}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "Could not read error response".to_string());
+
+            return Err(GitHubError::from_status(status, error_text).into());
+        }
+
+        let comparison: serde_json::Value = response.json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse GitHub API response: {}", e))?;
+
+        Ok(Comparison {
+            status: comparison["status"].as_str().unwrap_or_default().to_string(),
+            files: comparison["files"].as_array().into_iter().flatten()
+                .filter_map(|file| file["filename"].as_str().map(str::to_string))
+                .collect(),
+        })
+    }
+
     /// Get pull request comments
     pub async fn get_pull_request_comments(&self, owner: &str, repo: &str, number: u64) -> Result<Vec<PullRequestComment>> {
         let url = format!("{}/repos/{}/{}/pulls/{}/comments", self.base_url, owner, repo, number);
 
-        let response = self.http_client.get(&url)
+        let response = self.http_client.get(&url)?
             .header("Accept", "application/vnd.github.v3+json")
             .header("Authorization", format!("token {}", self.token))
             .header("User-Agent", "QitOps-Agent")
             .send()
             .await
-            .map_err(|e| anyhow!("Failed to send request to GitHub API: {}", e))?;
+            .map_err(|e| GitHubError::NetworkError(format!("Failed to send request to GitHub API: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await
                 .unwrap_or_else(|_| "Could not read error response".to_string());
 
-            return match status.as_u16() {
-                401 => Err(anyhow!("Authentication error: {}", error_text)),
-                403 => Err(anyhow!("Forbidden: {}", error_text)),
-                404 => Err(anyhow!("Not found: {}", error_text)),
-                422 => Err(anyhow!("Validation error: {}", error_text)),
-                _ => Err(anyhow!("GitHub API error ({}): {}", status, error_text)),
-            };
+            return Err(GitHubError::from_status(status, error_text).into());
         }
 
         let comments_data: Vec<serde_json::Value> = response.json()
@@ -419,26 +509,20 @@ impl GitHubClient {
     pub async fn get_repository(&self, owner: &str, repo: &str) -> Result<Repository> {
         let url = format!("{}/repos/{}/{}", self.base_url, owner, repo);
 
-        let response = self.http_client.get(&url)
+        let response = self.http_client.get(&url)?
             .header("Accept", "application/vnd.github.v3+json")
             .header("Authorization", format!("token {}", self.token))
             .header("User-Agent", "QitOps-Agent")
             .send()
             .await
-            .map_err(|e| anyhow!("Failed to send request to GitHub API: {}", e))?;
+            .map_err(|e| GitHubError::NetworkError(format!("Failed to send request to GitHub API: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await
                 .unwrap_or_else(|_| "Could not read error response".to_string());
 
-            return match status.as_u16() {
-                401 => Err(anyhow!("Authentication error: {}", error_text)),
-                403 => Err(anyhow!("Forbidden: {}", error_text)),
-                404 => Err(anyhow!("Not found: {}", error_text)),
-                422 => Err(anyhow!("Validation error: {}", error_text)),
-                _ => Err(anyhow!("GitHub API error ({}): {}", status, error_text)),
-            };
+            return Err(GitHubError::from_status(status, error_text).into());
         }
 
         let repo_data: serde_json::Value = response.json()
@@ -466,26 +550,20 @@ impl GitHubClient {
         let limit = limit.unwrap_or(10);
         let url = format!("{}/repos/{}/{}/commits?per_page={}", self.base_url, owner, repo, limit);
 
-        let response = self.http_client.get(&url)
+        let response = self.http_client.get(&url)?
             .header("Accept", "application/vnd.github.v3+json")
             .header("Authorization", format!("token {}", self.token))
             .header("User-Agent", "QitOps-Agent")
             .send()
             .await
-            .map_err(|e| anyhow!("Failed to send request to GitHub API: {}", e))?;
+            .map_err(|e| GitHubError::NetworkError(format!("Failed to send request to GitHub API: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await
                 .unwrap_or_else(|_| "Could not read error response".to_string());
 
-            return match status.as_u16() {
-                401 => Err(anyhow!("Authentication error: {}", error_text)),
-                403 => Err(anyhow!("Forbidden: {}", error_text)),
-                404 => Err(anyhow!("Not found: {}", error_text)),
-                422 => Err(anyhow!("Validation error: {}", error_text)),
-                _ => Err(anyhow!("GitHub API error ({}): {}", status, error_text)),
-            };
+            return Err(GitHubError::from_status(status, error_text).into());
         }
 
         let commits_data: Vec<serde_json::Value> = response.json()
@@ -507,32 +585,81 @@ impl GitHubClient {
         Ok(commits)
     }
 
+    /// Merged pull requests of a milestone, by its title (e.g. `v2.0`), in merge order
+    pub async fn get_milestone_pull_requests(&self, owner: &str, repo: &str, milestone: &str) -> Result<Vec<MergedPullRequest>> {
+        let url = format!("{}/search/issues", self.base_url);
+        let query = format!("repo:{}/{} is:pr is:merged milestone:\"{}\"", owner, repo, milestone);
+
+        let mut pull_requests = Vec::new();
+        // The search API returns at most 1000 results, in pages of 100
+        for page in 1..=10 {
+            let response = self.http_client.get(&url)?
+                .query(&[("q", query.as_str()), ("per_page", "100"), ("page", &page.to_string())])
+                .header("Accept", "application/vnd.github.v3+json")
+                .header("Authorization", format!("token {}", self.token))
+                .header("User-Agent", "QitOps-Agent")
+                .send()
+                .await
+                .map_err(|e| GitHubError::NetworkError(format!("Failed to send request to GitHub API: {}", e)))?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response.text().await
+                    .unwrap_or_else(|_| "Could not read error response".to_string());
+
+                return Err(GitHubError::from_status(status, error_text).into());
+            }
+
+            let data: serde_json::Value = response.json()
+                .await
+                .map_err(|e| anyhow!("Failed to parse GitHub API response: {}", e))?;
+
+            let items = data["items"].as_array().cloned().unwrap_or_default();
+            for item in &items {
+                pull_requests.push(MergedPullRequest {
+                    number: item["number"].as_u64().unwrap_or_default(),
+                    title: item["title"].as_str().unwrap_or_default().to_string(),
+                    author: item["user"]["login"].as_str().unwrap_or_default().to_string(),
+                    merged_at: item["pull_request"]["merged_at"].as_str()
+                        .or_else(|| item["closed_at"].as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                    labels: item["labels"].as_array().into_iter().flatten()
+                        .filter_map(|label| label["name"].as_str().map(str::to_string))
+                        .collect(),
+                });
+            }
+
+            let total = data["total_count"].as_u64().unwrap_or_default() as usize;
+            if items.len() < 100 || pull_requests.len() >= total {
+                break;
+            }
+        }
+
+        pull_requests.sort_by(|a, b| a.merged_at.cmp(&b.merged_at).then_with(|| a.number.cmp(&b.number)));
+        Ok(pull_requests)
+    }
+
     /// Get file content from a repository
     pub async fn get_file_content(&self, owner: &str, repo: &str, path: &str, branch: Option<&str>) -> Result<String> {
         let branch_param = branch.map(|b| format!("?ref={}", b)).unwrap_or_default();
         let url = format!("{}/repos/{}/{}/contents/{}{}",
             self.base_url, owner, repo, path, branch_param);
 
-        let response = self.http_client.get(&url)
+        let response = self.http_client.get(&url)?
             .header("Accept", "application/vnd.github.v3+json")
             .header("Authorization", format!("token {}", self.token))
             .header("User-Agent", "QitOps-Agent")
             .send()
             .await
-            .map_err(|e| anyhow!("Failed to send request to GitHub API: {}", e))?;
+            .map_err(|e| GitHubError::NetworkError(format!("Failed to send request to GitHub API: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await
                 .unwrap_or_else(|_| "Could not read error response".to_string());
 
-            return match status.as_u16() {
-                401 => Err(anyhow!("Authentication error: {}", error_text)),
-                403 => Err(anyhow!("Forbidden: {}", error_text)),
-                404 => Err(anyhow!("Not found: {}", error_text)),
-                422 => Err(anyhow!("Validation error: {}", error_text)),
-                _ => Err(anyhow!("GitHub API error ({}): {}", status, error_text)),
-            };
+            return Err(GitHubError::from_status(status, error_text).into());
         }
 
         let file_data: serde_json::Value = response.json()
@@ -553,35 +680,355 @@ impl GitHubClient {
         Ok(content_str)
     }
 
-    /// Create a comment on a pull request
+    /// Create a comment on a pull request, recording it in the audit log
+    ///
+    /// The configured report header and footer are added, with CI variables filled in.
     pub async fn create_pull_request_comment(&self, owner: &str, repo: &str, number: u64, body: &str) -> Result<PullRequestComment> {
         let url = format!("{}/repos/{}/{}/issues/{}/comments", self.base_url, owner, repo, number);
+        self.post_audited_comment(&url, AuditAction::CommentPosted, &format!("{}/{}#{}", owner, repo, number), body).await
+    }
+
+    /// Reply in the thread of a pull request review comment, recording it in the audit log
+    pub async fn reply_to_review_comment(&self, owner: &str, repo: &str, number: u64, comment_id: u64, body: &str) -> Result<PullRequestComment> {
+        let url = format!("{}/repos/{}/{}/pulls/{}/comments/{}/replies", self.base_url, owner, repo, number, comment_id);
+        self.post_audited_comment(&url, AuditAction::ReviewCommentPosted, &format!("{}/{}#{}", owner, repo, number), body).await
+    }
+
+    /// A user's role in a repository (`admin`, `maintain`, `write`, `triage`, `read` or `none`)
+    pub async fn get_collaborator_role(&self, owner: &str, repo: &str, user: &str) -> Result<String> {
+        let url = format!("{}/repos/{}/{}/collaborators/{}/permission", self.base_url, owner, repo, user);
+
+        let response = self.http_client.get(&url)?
+            .header("Accept", "application/vnd.github.v3+json")
+            .header("Authorization", format!("token {}", self.token))
+            .header("User-Agent", "QitOps-Agent")
+            .send()
+            .await
+            .map_err(|e| GitHubError::NetworkError(format!("Failed to send request to GitHub API: {}", e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "Could not read error response".to_string());
+
+            // Users who aren't collaborators have no role
+            return match GitHubError::from_status(status, error_text) {
+                GitHubError::NotFound(_) => Ok("none".to_string()),
+                e => Err(e.into()),
+            };
+        }
+
+        let data: serde_json::Value = response.json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse GitHub API response: {}", e))?;
+
+        // `role_name` distinguishes maintain and triage, which `permission` folds into write and read
+        Ok(data["role_name"].as_str()
+            .or_else(|| data["permission"].as_str())
+            .unwrap_or("none")
+            .to_string())
+    }
+
+    /// Edit a comment on a pull request or issue, recording it in the audit log
+    ///
+    /// The configured report header and footer are added, as when posting.
+    pub async fn update_issue_comment(&self, owner: &str, repo: &str, number: u64, comment_id: u64, body: &str) -> Result<PullRequestComment> {
+        let url = format!("{}/repos/{}/{}/issues/comments/{}", self.base_url, owner, repo, comment_id);
+        let result = self.send_comment(reqwest::Method::PATCH, &url, &decorate(body)).await;
+
+        audit::record(
+            AuditEvent::new(audit::current_actor(), AuditAction::CommentUpdated, format!("{}/{}#{}", owner, repo, number))
+                .with_resource_id(comment_id)
+                .with_outcome(&result)
+        );
+
+        result
+    }
+
+    /// Login of the account the token belongs to
+    ///
+    /// Installation tokens, such as the `GITHUB_TOKEN` of GitHub Actions, can't read `/user`; in
+    /// GitHub Actions their comments are posted as `github-actions[bot]`.
+    pub async fn authenticated_login(&self) -> Result<String> {
+        let url = format!("{}/user", self.base_url);
+
+        let response = self.http_client.get(&url)?
+            .header("Accept", "application/vnd.github.v3+json")
+            .header("Authorization", format!("token {}", self.token))
+            .header("User-Agent", "QitOps-Agent")
+            .send()
+            .await
+            .map_err(|e| GitHubError::NetworkError(format!("Failed to send request to GitHub API: {}", e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "Could not read error response".to_string());
+
+            return match GitHubError::from_status(status, error_text) {
+                GitHubError::Forbidden(_) if CiContext::detect().provider == Some(CiProvider::GitHubActions) => {
+                    Ok("github-actions[bot]".to_string())
+                },
+                e => Err(e.into()),
+            };
+        }
+
+        let user: serde_json::Value = response.json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse GitHub API response: {}", e))?;
+        user["login"].as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("GitHub didn't return the login of the authenticated user"))
+    }
+
+    /// Create a comment on a pull request, or edit the one an earlier run posted under the same key
+    ///
+    /// The comment starts with a hidden `<!-- qitops:<key> -->` marker, so repeated runs keep a
+    /// single comment up to date instead of adding another. Only comments by the authenticated
+    /// account are edited. Returns the comment and whether an
+    /// existing one was edited.
+    pub async fn upsert_pull_request_comment(&self, owner: &str, repo: &str, number: u64, key: &str, body: &str) -> Result<(PullRequestComment, bool)> {
+        let marker = comment_marker(key);
+        let body = format!("{}\n{}", marker, body);
+
+        // Anyone can paste the marker, so only the token's own comments are edited
+        let login = self.authenticated_login().await?;
+        let existing = self.get_issue_comments(owner, repo, number).await?
+            .into_iter()
+            .find(|comment| comment.user == login && comment.body.contains(&marker));
+        match existing {
+            Some(comment) => Ok((self.update_issue_comment(owner, repo, number, comment.id, &body).await?, true)),
+            None => Ok((self.create_pull_request_comment(owner, repo, number, &body).await?, false)),
+        }
+    }
+
+    /// Conversation comments on a pull request or issue, oldest first
+    pub async fn get_issue_comments(&self, owner: &str, repo: &str, number: u64) -> Result<Vec<PullRequestComment>> {
+        let url = format!("{}/repos/{}/{}/issues/{}/comments", self.base_url, owner, repo, number);
+        self.list_comments(&url).await
+    }
 
+    /// Inline comments of a pull request review, oldest first
+    pub async fn get_review_comments(&self, owner: &str, repo: &str, number: u64, review_id: u64) -> Result<Vec<PullRequestComment>> {
+        let url = format!("{}/repos/{}/{}/pulls/{}/reviews/{}/comments", self.base_url, owner, repo, number, review_id);
+        self.list_comments(&url).await
+    }
+
+    /// Every page of comments at a comments endpoint
+    async fn list_comments(&self, url: &str) -> Result<Vec<PullRequestComment>> {
+        let mut comments = Vec::new();
+        for page in 1.. {
+            let response = self.http_client.get(url)?
+                .query(&[("per_page", "100"), ("page", &page.to_string())])
+                .header("Accept", "application/vnd.github.v3+json")
+                .header("Authorization", format!("token {}", self.token))
+                .header("User-Agent", "QitOps-Agent")
+                .send()
+                .await
+                .map_err(|e| GitHubError::NetworkError(format!("Failed to send request to GitHub API: {}", e)))?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response.text().await
+                    .unwrap_or_else(|_| "Could not read error response".to_string());
+
+                return Err(GitHubError::from_status(status, error_text).into());
+            }
+
+            let comments_data: Vec<serde_json::Value> = response.json()
+                .await
+                .map_err(|e| anyhow!("Failed to parse GitHub API response: {}", e))?;
+
+            let count = comments_data.len();
+            for comment_data in comments_data {
+                comments.push(PullRequestComment {
+                    id: comment_data["id"].as_u64().unwrap_or_default(),
+                    body: comment_data["body"].as_str().unwrap_or_default().to_string(),
+                    user: comment_data["user"]["login"].as_str().unwrap_or_default().to_string(),
+                    created_at: comment_data["created_at"].as_str().unwrap_or_default().to_string(),
+                    updated_at: comment_data["updated_at"].as_str().unwrap_or_default().to_string(),
+                    path: None,
+                    line: None,
+                });
+            }
+
+            if count < 100 {
+                break;
+            }
+        }
+
+        Ok(comments)
+    }
+
+    /// Submit a review of a pull request with inline comments, recording it in the audit log
+    ///
+    /// The review is a plain comment, neither approving nor requesting changes. The configured
+    /// report header and footer are added to its body. Its inline comments are recorded too, so
+    /// `qitops github undo` can delete them. Returns the review's ID.
+    pub async fn create_review(&self, owner: &str, repo: &str, number: u64, commit_id: Option<&str>, body: &str, comments: &[ReviewComment]) -> Result<u64> {
+        let url = format!("{}/repos/{}/{}/pulls/{}/reviews", self.base_url, owner, repo, number);
+        let mut payload = serde_json::json!({
+            "body": decorate(body),
+            "event": "COMMENT",
+            "comments": comments,
+        });
+        if let Some(commit_id) = commit_id.filter(|commit_id| !commit_id.is_empty()) {
+            payload["commit_id"] = serde_json::json!(commit_id);
+        }
+
+        let result = async {
+            let response = self.http_client.post(&url)?
+                .header("Accept", "application/vnd.github.v3+json")
+                .header("Authorization", format!("token {}", self.token))
+                .header("User-Agent", "QitOps-Agent")
+                .json(&payload)
+                .send()
+                .await
+                .map_err(|e| GitHubError::NetworkError(format!("Failed to send request to GitHub API: {}", e)))?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response.text().await
+                    .unwrap_or_else(|_| "Could not read error response".to_string());
+
+                return Err(GitHubError::from_status(status, error_text).into());
+            }
+
+            let review: serde_json::Value = response.json()
+                .await
+                .map_err(|e| anyhow!("Failed to parse GitHub API response: {}", e))?;
+            Ok(review["id"].as_u64().unwrap_or_default())
+        }.await;
+
+        let target = format!("{}/{}#{}", owner, repo, number);
+        let mut event = AuditEvent::new(audit::current_actor(), AuditAction::ReviewPosted, target.clone())
+            .with_outcome(&result);
+        if let Ok(id) = &result {
+            event = event.with_resource_id(*id);
+        }
+        audit::record(event);
+
+        // A submitted review can't be deleted, but its inline comments can
+        if let Ok(id) = &result
+            && !comments.is_empty()
+        {
+            match self.get_review_comments(owner, repo, number, *id).await {
+                Ok(posted) => for comment in posted {
+                    audit::record(
+                        AuditEvent::new(audit::current_actor(), AuditAction::ReviewCommentPosted, target.clone())
+                            .with_resource_id(comment.id)
+                    );
+                },
+                Err(e) => tracing::warn!("Could not list the comments of review {} on {}, so they can't be undone: {}", id, target, e),
+            }
+        }
+
+        result
+    }
+
+    /// Post a comment with the report header and footer, recording it in the audit log
+    async fn post_audited_comment(&self, url: &str, action: AuditAction, target: &str, body: &str) -> Result<PullRequestComment> {
+        let result = self.send_comment(reqwest::Method::POST, url, &decorate(body)).await;
+
+        let mut event = AuditEvent::new(audit::current_actor(), action, target.to_string())
+            .with_outcome(&result);
+        if let Ok(comment) = &result {
+            event = event.with_resource_id(comment.id);
+        }
+        audit::record(event);
+
+        result
+    }
+
+    /// Delete a comment on a pull request or issue, recording it in the audit log
+    ///
+    /// Returns `false` if the comment no longer exists.
+    pub async fn delete_issue_comment(&self, owner: &str, repo: &str, comment_id: u64) -> Result<bool> {
+        let url = format!("{}/repos/{}/{}/issues/comments/{}", self.base_url, owner, repo, comment_id);
+        self.delete_audited_comment(&url, AuditAction::CommentDeleted, owner, repo, comment_id).await
+    }
+
+    /// Delete a pull request review comment, recording it in the audit log
+    ///
+    /// Returns `false` if the comment no longer exists.
+    pub async fn delete_review_comment(&self, owner: &str, repo: &str, comment_id: u64) -> Result<bool> {
+        let url = format!("{}/repos/{}/{}/pulls/comments/{}", self.base_url, owner, repo, comment_id);
+        self.delete_audited_comment(&url, AuditAction::ReviewCommentDeleted, owner, repo, comment_id).await
+    }
+
+    /// Delete what a recorded action posted, with the endpoint for its kind of comment
+    ///
+    /// Returns `false` if it no longer exists.
+    pub async fn undo(&self, event: &AuditEvent) -> Result<bool> {
+        // Targets are recorded as `owner/repo#number`
+        let (owner, repo) = event.target.split_once('#')
+            .and_then(|(repository, _)| repository.split_once('/'))
+            .ok_or_else(|| anyhow!("Unrecognized target {}", event.target))?;
+        let id = event.resource_id
+            .ok_or_else(|| anyhow!("No {} ID was recorded for {}", event.action, event.target))?;
+
+        match event.action {
+            AuditAction::CommentPosted => self.delete_issue_comment(owner, repo, id).await,
+            AuditAction::ReviewCommentPosted => self.delete_review_comment(owner, repo, id).await,
+            action => Err(anyhow!("Cannot undo {}", action)),
+        }
+    }
+
+    /// Delete a comment at a comment endpoint, recording `action` in the audit log
+    async fn delete_audited_comment(&self, url: &str, action: AuditAction, owner: &str, repo: &str, comment_id: u64) -> Result<bool> {
+        let result = async {
+            let response = self.http_client.delete(url)?
+                .header("Accept", "application/vnd.github.v3+json")
+                .header("Authorization", format!("token {}", self.token))
+                .header("User-Agent", "QitOps-Agent")
+                .send()
+                .await
+                .map_err(|e| GitHubError::NetworkError(format!("Failed to send request to GitHub API: {}", e)))?;
+
+            let status = response.status();
+            if status.is_success() {
+                return Ok(true);
+            }
+
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "Could not read error response".to_string());
+
+            match GitHubError::from_status(status, error_text) {
+                GitHubError::NotFound(_) => Ok(false),
+                e => Err(e.into()),
+            }
+        }.await;
+
+        audit::record(
+            AuditEvent::new(audit::current_actor(), action, format!("{}/{}", owner, repo))
+                .with_resource_id(comment_id)
+                .with_outcome(&result)
+        );
+
+        result
+    }
+
+    /// Post or edit a comment at a comments endpoint
+    async fn send_comment(&self, method: reqwest::Method, url: &str, body: &str) -> Result<PullRequestComment> {
         let payload = serde_json::json!({
             "body": body
         });
 
-        let response = self.http_client.post(&url)
+        let response = self.http_client.request(method, url)?
             .header("Accept", "application/vnd.github.v3+json")
             .header("Authorization", format!("token {}", self.token))
             .header("User-Agent", "QitOps-Agent")
             .json(&payload)
             .send()
             .await
-            .map_err(|e| anyhow!("Failed to send request to GitHub API: {}", e))?;
+            .map_err(|e| GitHubError::NetworkError(format!("Failed to send request to GitHub API: {}", e)))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await
                 .unwrap_or_else(|_| "Could not read error response".to_string());
 
-            return match status.as_u16() {
-                401 => Err(anyhow!("Authentication error: {}", error_text)),
-                403 => Err(anyhow!("Forbidden: {}", error_text)),
-                404 => Err(anyhow!("Not found: {}", error_text)),
-                422 => Err(anyhow!("Validation error: {}", error_text)),
-                _ => Err(anyhow!("GitHub API error ({}): {}", status, error_text)),
-            };
+            return Err(GitHubError::from_status(status, error_text).into());
         }
 
         let comment_data: serde_json::Value = response.json()
@@ -601,3 +1048,16 @@ impl GitHubClient {
         Ok(comment)
     }
 }
+
+/// Hidden marker identifying the comment QitOps keeps up to date under a key, e.g. `pr-analyze`
+pub fn comment_marker(key: &str) -> String {
+    format!("<!-- qitops:{} -->", key)
+}
+
+/// A comment body with the configured report header and footer, CI variables filled in
+fn decorate(body: &str) -> String {
+    let report = QitOpsConfigManager::new()
+        .map(|manager| manager.get_config().report.clone())
+        .unwrap_or_default();
+    report.decorate(body, &CiContext::detect())
+}
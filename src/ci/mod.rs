@@ -1,7 +1,13 @@
 // CI/CD integration
+#[cfg(feature = "github")]
 pub mod github;
 pub mod config;
+pub mod env;
+#[cfg(feature = "github")]
+pub mod fallback;
 
 // Re-export commonly used types
-pub use github::{GitHubClient, PullRequest, PullRequestFile, PullRequestComment, Repository, Commit};
+#[cfg(feature = "github")]
+pub use github::{GitHubClient, PullRequestComment};
 pub use config::{GitHubConfig, GitHubConfigManager};
+pub use env::CiContext;
@@ -0,0 +1,89 @@
+use colored::*;
+
+use crate::agent::diff::{DiffLineKind, FileDiff};
+use crate::agent::findings::Finding;
+use crate::cli::branding::{colorize, severity_color};
+
+/// Format a finding as an inline annotation
+fn annotation(finding: &Finding, line: Option<u32>) -> String {
+    let marker = colorize(&format!("  ▲ [{}]", finding.severity), severity_color(finding.severity));
+    let mut text = match line {
+        Some(line) => format!("{} {} (line {})", marker, finding.title.bold(), line),
+        None => format!("{} {}", marker, finding.title.bold()),
+    };
+
+    if !finding.description.is_empty() {
+        for description_line in finding.description.lines() {
+            text.push_str(&format!("\n      {}", description_line.dimmed()));
+        }
+    }
+
+    text
+}
+
+/// Render a diff with findings anchored to the lines they refer to
+pub fn render_annotated_diff(files: &[FileDiff], findings: &[Finding]) -> String {
+    let mut output = Vec::new();
+
+    for file in files {
+        let file_findings: Vec<&Finding> = findings.iter()
+            .filter(|f| f.file.as_deref() == Some(file.path()))
+            .collect();
+        let additions = file.lines().filter(|l| l.kind == DiffLineKind::Added).count();
+        let deletions = file.lines().filter(|l| l.kind == DiffLineKind::Removed).count();
+
+        output.push(format!(
+            "{} {} {}",
+            file.path().cyan().bold(),
+            format!("+{}", additions).green(),
+            format!("-{}", deletions).red()
+        ));
+
+        // Findings that can't be anchored to a line in the diff go under the file header
+        for finding in &file_findings {
+            let anchored = finding.line.is_some_and(|n| file.new_line(n).is_some());
+            if !anchored {
+                output.push(annotation(finding, finding.line));
+            }
+        }
+
+        for hunk in &file.hunks {
+            output.push(hunk.header.cyan().to_string());
+
+            for line in &hunk.lines {
+                let number = line.new_line.or(line.old_line).map(|n| n.to_string()).unwrap_or_default();
+                let text = match line.kind {
+                    DiffLineKind::Added => format!("+{}", line.content).green().to_string(),
+                    DiffLineKind::Removed => format!("-{}", line.content).red().to_string(),
+                    DiffLineKind::Context => format!(" {}", line.content),
+                };
+                output.push(format!("{:>5} │ {}", number.dimmed(), text));
+
+                if let Some(new_line) = line.new_line {
+                    for finding in file_findings.iter().filter(|f| f.line == Some(new_line)) {
+                        output.push(annotation(finding, None));
+                    }
+                }
+            }
+        }
+
+        output.push(String::new());
+    }
+
+    // Findings that don't belong to any file in the diff
+    let unanchored: Vec<&Finding> = findings.iter()
+        .filter(|f| !f.file.as_deref().is_some_and(|path| files.iter().any(|file| file.path() == path)))
+        .collect();
+    if !unanchored.is_empty() {
+        output.push("General".cyan().bold().to_string());
+        for finding in unanchored {
+            let location = finding.location();
+            output.push(annotation(finding, None));
+            if !location.is_empty() {
+                output.push(format!("      {}", location.dimmed()));
+            }
+        }
+    }
+
+    output.join("\n")
+}
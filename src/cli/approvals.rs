@@ -0,0 +1,117 @@
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::audit::{self, format_timestamp};
+use crate::cli::branding;
+use crate::error::QitOpsError;
+use crate::llm::ConfigManager;
+use crate::llm::approval::{ApprovalQueue, ApprovalRequest};
+
+/// Prompt approval CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct ApprovalsArgs {
+    /// Approvals subcommand
+    #[clap(subcommand)]
+    pub command: ApprovalsCommand,
+}
+
+/// Prompt approval subcommands
+#[derive(Debug, Subcommand)]
+pub enum ApprovalsCommand {
+    /// List prompts waiting for approval, oldest first
+    #[clap(name = "list")]
+    List {
+        /// Include decided and expired prompts
+        #[clap(long)]
+        all: bool,
+
+        /// Print the prompts as JSON
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Show a queued prompt in full
+    #[clap(name = "show")]
+    Show {
+        /// Prompt ID
+        id: String,
+    },
+
+    /// Let a queued prompt be sent
+    #[clap(name = "approve")]
+    Approve {
+        /// Prompt ID
+        id: String,
+
+        /// Note recorded with the decision
+        #[clap(long)]
+        note: Option<String>,
+    },
+
+    /// Keep a queued prompt from being sent
+    #[clap(name = "reject")]
+    Reject {
+        /// Prompt ID
+        id: String,
+
+        /// Reason given to the requester and recorded with the decision
+        #[clap(long)]
+        reason: Option<String>,
+    },
+}
+
+/// Handle approvals commands
+pub async fn handle_approvals_command(args: &ApprovalsArgs) -> Result<()> {
+    let config = ConfigManager::new()?.get_config().approval.clone()
+        .ok_or_else(|| QitOpsError::InvalidInput("Prompt approval is not configured; add an `approval` section to the LLM config.json".to_string()))?;
+    let queue = ApprovalQueue::open(&config)?;
+
+    match &args.command {
+        ApprovalsCommand::List { all, json } => {
+            let requests = queue.list(*all)?;
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&requests)?);
+                return Ok(());
+            }
+            if requests.is_empty() {
+                branding::print_info(&format!("No prompts waiting for approval in {}", queue.path().display()));
+                return Ok(());
+            }
+
+            for request in &requests {
+                print_summary(request);
+            }
+        },
+        ApprovalsCommand::Show { id } => {
+            let request = queue.get(id)?
+                .ok_or_else(|| QitOpsError::InvalidInput(format!("No queued prompt {}", id)))?;
+            print_summary(&request);
+            println!("\n{}", request.prompt);
+        },
+        ApprovalsCommand::Approve { id, note } => {
+            let request = queue.decide(id, true, &audit::current_actor(), note.as_deref())?;
+            branding::print_success(&format!("Approved prompt {} to {}", request.id, request.provider));
+        },
+        ApprovalsCommand::Reject { id, reason } => {
+            let request = queue.decide(id, false, &audit::current_actor(), reason.as_deref())?;
+            branding::print_success(&format!("Rejected prompt {} to {}", request.id, request.provider));
+        },
+    }
+
+    Ok(())
+}
+
+/// Print one line about a queued prompt, and why it was held
+fn print_summary(request: &ApprovalRequest) {
+    let task = request.task.as_deref().unwrap_or("-");
+    println!(
+        "{}  {:<8} {:<8} {:<10} to {}/{}, requested by {} at {}",
+        request.id, request.status, request.sensitivity.level.as_str().to_uppercase(), task,
+        request.provider, request.model, request.requested_by, format_timestamp(request.requested_at),
+    );
+    println!("{:<10}{}", "", request.sensitivity.reasons.join("; "));
+    if let (Some(by), Some(at)) = (&request.decided_by, request.decided_at) {
+        let note = request.note.as_ref().map(|note| format!(": {}", note)).unwrap_or_default();
+        println!("{:<10}{} by {} at {}{}", "", request.status, by, format_timestamp(at), note);
+    }
+}
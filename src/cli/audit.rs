@@ -0,0 +1,102 @@
+use anyhow::Result;
+use clap::Subcommand;
+use colored::Colorize;
+
+use crate::audit::{AuditAction, AuditLog, format_timestamp};
+use crate::cli::branding;
+
+/// Audit CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct AuditArgs {
+    /// Audit subcommand
+    #[clap(subcommand)]
+    pub command: AuditCommand,
+}
+
+/// Audit subcommands
+#[derive(Debug, Subcommand)]
+pub enum AuditCommand {
+    /// List recorded external actions, newest first
+    #[clap(name = "list")]
+    List {
+        /// Only show this action (comment, label, issue, command, delete, test-case, publish, notify, approve, reject, edit, review)
+        #[clap(short, long)]
+        action: Option<AuditAction>,
+
+        /// Only show actions by this actor
+        #[clap(long)]
+        actor: Option<String>,
+
+        /// Only show actions whose target contains this text
+        #[clap(short, long)]
+        target: Option<String>,
+
+        /// Only show actions from this run
+        #[clap(long)]
+        run: Option<String>,
+
+        /// Maximum number of entries
+        #[clap(short = 'n', long, default_value = "50")]
+        limit: usize,
+
+        /// Print entries as JSON lines
+        #[clap(long)]
+        json: bool,
+    },
+}
+
+/// Handle audit commands
+pub async fn handle_audit_command(args: &AuditArgs) -> Result<()> {
+    match &args.command {
+        AuditCommand::List { action, actor, target, run, limit, json } => {
+            list_events(*action, actor.as_deref(), target.as_deref(), run.as_deref(), *limit, *json)
+        },
+    }
+}
+
+/// List audit log entries
+fn list_events(action: Option<AuditAction>, actor: Option<&str>, target: Option<&str>, run: Option<&str>, limit: usize, json: bool) -> Result<()> {
+    let log = AuditLog::open_default()?;
+    let events: Vec<_> = log.read()?
+        .into_iter()
+        .rev()
+        .filter(|event| action.is_none_or(|action| event.action == action))
+        .filter(|event| actor.is_none_or(|actor| event.actor == actor))
+        .filter(|event| target.is_none_or(|target| event.target.contains(target)))
+        .filter(|event| run.is_none_or(|run| event.run_id.as_deref() == Some(run)))
+        .take(limit)
+        .collect();
+
+    if json {
+        for event in &events {
+            println!("{}", serde_json::to_string(event)?);
+        }
+        return Ok(());
+    }
+
+    if events.is_empty() {
+        branding::print_info(&format!("No audit entries in {}", log.path().display()));
+        return Ok(());
+    }
+
+    for event in &events {
+        let status = if event.success { "ok".green() } else { "failed".red() };
+        println!(
+            "{}  {:<17}  {:<12}  {}  {}",
+            format_timestamp(event.timestamp).dimmed(),
+            event.action.as_str().bright_cyan(),
+            event.actor,
+            event.target,
+            status
+        );
+        if let Some(run_id) = &event.run_id {
+            let resource = event.resource_id.map(|id| format!(", id {}", id)).unwrap_or_default();
+            println!("    run {}{}", run_id, resource);
+        }
+        if let Some(details) = &event.details {
+            println!("    {}", details.dimmed());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use anyhow::{Result, anyhow};
+use clap::Subcommand;
+use colored::*;
+use std::path::Path;
+
+use crate::bench::{self, BenchReport};
+use crate::cli::branding;
+use crate::cli::progress::ProgressIndicator;
+use crate::llm::{ConfigManager, LlmRouter};
+
+/// Bench CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct BenchArgs {
+    /// Bench subcommand
+    #[clap(subcommand)]
+    pub command: BenchCommand,
+}
+
+/// Bench subcommands
+#[derive(Debug, Subcommand)]
+pub enum BenchCommand {
+    /// Run agents over a fixture suite and score the results against its rubrics
+    #[clap(name = "agents")]
+    Agents {
+        /// Directory of bench cases (*.json)
+        #[clap(long, default_value = "fixtures/")]
+        suite: String,
+
+        /// Send requests to the configured providers (e.g. a local model) instead of replaying recorded answers
+        #[clap(long)]
+        live: bool,
+
+        /// Save the scores as the suite's baseline
+        #[clap(long)]
+        update_baseline: bool,
+
+        /// Print results as JSON
+        #[clap(long)]
+        json: bool,
+
+        /// Resume an interrupted run from its checkpoint, only running the cases not yet done
+        #[clap(long)]
+        resume: Option<String>,
+    },
+}
+
+/// Handle bench commands
+pub async fn handle_bench_command(args: &BenchArgs) -> Result<()> {
+    match &args.command {
+        BenchCommand::Agents { suite, live, update_baseline, json, resume } => {
+            bench_agents(Path::new(suite), *live, *update_baseline, *json, resume.as_deref().map(Path::new)).await
+        },
+    }
+}
+
+/// Run an agent bench suite and report regressions against its baseline
+async fn bench_agents(suite: &Path, live: bool, update_baseline: bool, json: bool, resume: Option<&Path>) -> Result<()> {
+    let router = match live {
+        true => Some(LlmRouter::new(ConfigManager::new()?.get_config().clone()).await?),
+        false => None,
+    };
+
+    let progress = (!json).then(|| ProgressIndicator::new("Running bench cases..."));
+    let reporter = progress.as_ref().map(|p| p.reporter());
+    let report = bench::run_suite_resumable(suite, router, resume, |status| {
+        if let Some(reporter) = &reporter {
+            reporter(status);
+        }
+    }).await;
+    if let Some(progress) = progress {
+        progress.finish();
+    }
+    let report = report?;
+
+    if update_baseline {
+        let path = bench::save_baseline(suite, &report)?;
+        if !json {
+            branding::print_success(&format!("Saved scores of {} cases to {}", report.cases.len(), path.display()));
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report);
+    }
+
+    if !update_baseline && !report.regressions.is_empty() {
+        return Err(anyhow!("{} case(s) scored lower than the baseline", report.regressions.len()));
+    }
+
+    Ok(())
+}
+
+/// Print bench results as a table, with failed checks and regressions
+fn print_report(report: &BenchReport) {
+    branding::print_section(&format!("Bench Results ({})", report.mode));
+    println!("{:<32} {:<12} {:>7} {:>9}", "Case", "Agent", "Score", "Time");
+    for case in &report.cases {
+        let score = format!("{:.0}%", case.score * 100.0);
+        let score = if case.score >= 1.0 { score.green() } else { score.yellow() };
+        println!("{:<32} {:<12} {:>7} {:>9}", case.name, case.agent, score, format!("{}ms", case.duration_ms));
+
+        if let Some(error) = &case.error {
+            println!("  {} {}", "error:".red(), error);
+        }
+        for check in case.checks.iter().filter(|check| !check.passed) {
+            println!("  {} {}", "failed:".red(), check.name);
+        }
+    }
+    println!();
+    println!("Mean score: {:.0}%", report.mean_score() * 100.0);
+
+    for regression in &report.regressions {
+        branding::print_error(&format!(
+            "Regression in {}: {:.0}% (baseline {:.0}%)",
+            regression.case, regression.score * 100.0, regression.baseline * 100.0
+        ));
+    }
+    if let Some(checkpoint) = &report.checkpoint {
+        branding::print_info(&format!("Re-run the failed cases with --resume {}", checkpoint.display()));
+    }
+}
@@ -3,8 +3,13 @@ use clap::Subcommand;
 use std::io::{self, Write};
 use std::path::PathBuf;
 
-use crate::llm::{LlmRouter, LlmRequest, RouterConfig};
+use crate::llm::{Conversation, LazyRouter};
 use crate::cli::branding;
+use crate::i18n::{Msg, t};
+use crate::audit;
+use crate::error::QitOpsError;
+use crate::bot::{BOT_NAME, USER_NAME, command_audit_event, command_reply, run_command_tool, tool_command};
+use crate::bot::knowledge::{KnowledgeBase, KB_SCHEMA_VERSION};
 
 // Define the QitOpsBot and BotConfig here
 #[derive(Debug, Clone)]
@@ -29,6 +34,12 @@ impl Default for BotConfig {
     }
 }
 
+/// Environment variable holding the secret GitHub signs webhook deliveries with
+const WEBHOOK_SECRET_ENV: &str = "QITOPS_WEBHOOK_SECRET";
+
+/// Environment variable holding the API key requests to `bot serve` authenticate with
+const API_KEY_ENV: &str = "QITOPS_BOT_API_KEY";
+
 /// Default system prompt
 const DEFAULT_SYSTEM_PROMPT: &str = r#"You are QitOps Bot, an assistant for the QitOps Agent toolchain.
 Your purpose is to help users learn and use QitOps Agent effectively.
@@ -52,36 +63,22 @@ Be helpful, concise, and accurate. If you don't know something, say so.
 Provide examples when appropriate.
 "#;
 
-/// Chat message
-#[derive(Debug, Clone)]
-pub enum ChatMessage {
-    /// User message
-    User(String),
-
-    /// Bot message
-    Bot(String),
-}
-
 pub struct QitOpsBot {
-    /// LLM router
-    llm_router: LlmRouter,
-
-    /// Chat history
-    chat_history: Vec<ChatMessage>,
+    /// LLM router, rebuilt when the LLM configuration file changes
+    llm_router: LazyRouter,
 
-    /// Bot configuration
-    config: BotConfig,
+    /// Chat history, with the configured system prompt
+    conversation: Conversation,
 }
 
 impl QitOpsBot {
     /// Create a new QitOps Bot
-    pub async fn new(llm_router: LlmRouter, config: Option<BotConfig>) -> Self {
+    pub async fn new(llm_router: LazyRouter, config: Option<BotConfig>) -> Self {
         let config = config.unwrap_or_default();
 
         Self {
             llm_router,
-            chat_history: Vec::new(),
-            config,
+            conversation: Conversation::new(config.system_prompt).with_max_turns(config.max_history_length * 2),
         }
     }
 
@@ -89,13 +86,13 @@ impl QitOpsBot {
     pub async fn start_chat_session(&mut self) -> Result<()> {
         // Print welcome message
         branding::print_command_header("QitOps Bot");
-        println!("Welcome to QitOps Bot! Type 'exit' or 'quit' to end the session.");
+        println!("{}", t(Msg::BotWelcome));
         println!();
 
         // Initial bot message
         let initial_message = "Hello! I'm the QitOps Bot. How can I help you with QitOps Agent today?";
         println!("{}: {}", branding::colorize("QitOps Bot", branding::Color::Green), initial_message);
-        self.chat_history.push(ChatMessage::Bot(initial_message.to_string()));
+        self.conversation.push_assistant(BOT_NAME, initial_message.to_string());
 
         // Chat loop
         loop {
@@ -109,8 +106,7 @@ impl QitOpsBot {
 
             // Check for exit command
             if input.eq_ignore_ascii_case("exit") || input.eq_ignore_ascii_case("quit") {
-                println!("\n{}: Goodbye! Feel free to chat again if you need help with QitOps Agent.",
-                    branding::colorize("QitOps Bot", branding::Color::Green));
+                println!("\n{}: {}", branding::colorize("QitOps Bot", branding::Color::Green), t(Msg::BotGoodbye));
                 break;
             }
 
@@ -127,44 +123,39 @@ impl QitOpsBot {
 
     /// Process a user message
     pub async fn process_message(&mut self, message: &str) -> Result<String> {
-        // Add user message to chat history
-        self.chat_history.push(ChatMessage::User(message.to_string()));
+        // Add user message to chat history, forgetting the oldest messages
+        self.conversation.push_user(USER_NAME, message.to_string());
 
         // Create the LLM request
-        let prompt = self.generate_prompt();
-        let model = self.llm_router.default_model().unwrap_or_else(|| "mistral".to_string());
-        let request = LlmRequest::new(prompt, model)
-            .with_system_message(self.config.system_prompt.clone());
+        let llm_router = self.llm_router.get().await?;
+        let model = llm_router.default_model().unwrap_or_else(|| "mistral".to_string());
+        let request = self.conversation.request(model, None, llm_router.context_window(None))
+            .with_tool(run_command_tool())
+            .with_cache(false);
 
         // Send the request to the LLM
-        let llm_response = self.llm_router.send(request, None).await?;
-
-        // Extract the text from the response
-        let response_text = llm_response.text;
-
-        // Add bot response to chat history
-        self.chat_history.push(ChatMessage::Bot(response_text.clone()));
+        let llm_response = llm_router.send(request, None).await?;
 
-        Ok(response_text)
-    }
-
-    /// Generate the prompt for the LLM
-    fn generate_prompt(&self) -> String {
-        // Convert chat history to a prompt
-        let mut prompt = String::new();
-
-        for message in &self.chat_history {
-            match message {
-                ChatMessage::User(text) => {
-                    prompt.push_str(&format!("User: {}\n", text));
-                },
-                ChatMessage::Bot(text) => {
-                    prompt.push_str(&format!("QitOps Bot: {}\n", text));
+        // Extract the text from the response, running any commands the model asked for
+        let mut parts = Vec::new();
+        if !llm_response.text.trim().is_empty() {
+            parts.push(llm_response.text.trim().to_string());
+        }
+        for call in &llm_response.tool_calls {
+            match tool_command(call) {
+                Ok(command) => {
+                    let result = self.execute_command(&command).await?;
+                    parts.push(command_reply(&command, &result));
                 },
+                Err(e) => parts.push(format!("I couldn't run that command: {}", e)),
             }
         }
+        let response_text = parts.join("\n\n");
+
+        // Add bot response to chat history
+        self.conversation.push_assistant(BOT_NAME, response_text.clone());
 
-        prompt
+        Ok(response_text)
     }
 
     /// Execute a QitOps Agent command
@@ -177,7 +168,9 @@ impl QitOpsBot {
         process.args(&args);
 
         // Execute the command
-        let output = process.output()?;
+        let output = process.output();
+        audit::record(command_audit_event(audit::current_actor(), command, &output));
+        let output = output?;
 
         // Return the output
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
@@ -213,6 +206,86 @@ pub enum BotCommand {
         #[clap(short, long)]
         knowledge_base: Option<String>,
     },
+
+    /// List the tenants of a shared deployment and their LLM budget usage
+    #[clap(name = "tenants")]
+    Tenants {
+        /// Tenants file (default: $QITOPS_TENANTS_FILE or ~/.qitops/tenants.json)
+        #[clap(short, long)]
+        file: Option<String>,
+    },
+
+    /// Answer `@qitops` mentions in GitHub issue and pull request comments, received by webhook
+    #[clap(name = "github")]
+    Github {
+        /// Address to receive webhook deliveries on
+        #[clap(short, long, default_value = "127.0.0.1:8787")]
+        listen: String,
+
+        /// Least repository role a commenter needs to run commands (read, triage, write, maintain or admin)
+        #[clap(long, default_value = "write")]
+        min_role: String,
+    },
+
+    /// Answer messages from chat backends and other services over HTTP, with per-user and per-channel quotas
+    #[clap(name = "serve")]
+    Serve {
+        /// Address to listen on
+        #[clap(short, long, default_value = "127.0.0.1:8788")]
+        listen: String,
+
+        /// System prompt file
+        #[clap(short, long)]
+        system_prompt: Option<String>,
+
+        /// Knowledge base path
+        #[clap(short, long)]
+        knowledge_base: Option<String>,
+
+        /// Requests per user within the quota window (0 = unlimited)
+        #[clap(long)]
+        per_user: Option<usize>,
+
+        /// Requests per channel within the quota window (0 = unlimited)
+        #[clap(long)]
+        per_channel: Option<usize>,
+
+        /// Requests answered at the same time
+        #[clap(long)]
+        max_concurrent: Option<usize>,
+
+        /// Quota window in seconds
+        #[clap(long)]
+        window_secs: Option<u64>,
+
+        /// Serve several tenants from a tenants file, each with its own quotas (default: $QITOPS_TENANTS_FILE or ~/.qitops/tenants.json)
+        #[clap(long, value_name = "FILE", num_args = 0..=1, default_missing_value = "")]
+        tenants: Option<String>,
+    },
+
+    /// Knowledge base maintenance
+    #[clap(name = "kb")]
+    Kb {
+        /// Knowledge base subcommand
+        #[clap(subcommand)]
+        command: KbCommand,
+    },
+}
+
+/// Knowledge base subcommands
+#[derive(Debug, Subcommand)]
+pub enum KbCommand {
+    /// Validate a knowledge base and check its schema version
+    #[clap(name = "validate")]
+    Validate {
+        /// Knowledge base directory
+        #[clap(short, long, default_value = "knowledge")]
+        path: String,
+
+        /// Write migrated files back when the knowledge base uses an older schema version
+        #[clap(long)]
+        migrate: bool,
+    },
 }
 
 /// Handle bot commands
@@ -221,13 +294,140 @@ pub async fn handle_bot_command(args: &BotArgs) -> Result<()> {
         BotCommand::Chat { system_prompt, knowledge_base } => {
             chat(system_prompt, knowledge_base).await
         },
+        BotCommand::Tenants { file } => list_tenants(file.as_deref().unwrap_or_default()),
+        BotCommand::Github { listen, min_role } => serve_github(listen, min_role).await,
+        BotCommand::Serve { listen, tenants: Some(file), .. } => serve_tenants(listen, file).await,
+        BotCommand::Serve { listen, system_prompt, knowledge_base, per_user, per_channel, max_concurrent, window_secs, tenants: None } => {
+            let mut quotas = crate::bot::quota::QuotaConfig::default();
+            quotas.per_user = per_user.unwrap_or(quotas.per_user);
+            quotas.per_channel = per_channel.unwrap_or(quotas.per_channel);
+            quotas.max_concurrent = max_concurrent.unwrap_or(quotas.max_concurrent);
+            quotas.window_secs = window_secs.unwrap_or(quotas.window_secs);
+            serve(listen, system_prompt, knowledge_base, quotas).await
+        },
+        BotCommand::Kb { command: KbCommand::Validate { path, migrate } } => {
+            validate_knowledge_base(path, *migrate).await
+        },
+    }
+}
+
+/// Answer `@qitops` mentions received as GitHub webhook deliveries
+async fn serve_github(listen: &str, min_role: &str) -> Result<()> {
+    use crate::bot::github::{self, MentionBot, RepoRole};
+
+    let addr = listen.parse()
+        .map_err(|_| QitOpsError::InvalidInput(format!("Invalid listen address: {}", listen)))?;
+    let min_role: RepoRole = min_role.parse().map_err(|e: anyhow::Error| QitOpsError::InvalidInput(e.to_string()))?;
+    let secret = std::env::var(WEBHOOK_SECRET_ENV).ok()
+        .filter(|secret| !secret.is_empty())
+        .ok_or_else(|| QitOpsError::InvalidInput(format!("Set {} to the secret of the GitHub webhook", WEBHOOK_SECRET_ENV)))?;
+
+    let github_config = crate::ci::GitHubConfigManager::new()?;
+    let github_client = crate::ci::GitHubClient::from_config(github_config.get_config())?;
+    let quotas = crate::bot::quota::QuotaManager::new(crate::bot::quota::QuotaConfig::default());
+    let bot = MentionBot::new(github_client, LazyRouter::new(), min_role, quotas);
+
+    branding::print_info(&format!(
+        "Listening for GitHub webhook deliveries on {} (issue_comment and pull_request_review_comment events)", addr
+    ));
+    github::serve(addr, secret.into_bytes(), bot).await
+}
+
+/// Answer bot messages over HTTP
+async fn serve(
+    listen: &str,
+    system_prompt: &Option<String>,
+    knowledge_base: &Option<String>,
+    quotas: crate::bot::quota::QuotaConfig,
+) -> Result<()> {
+    let mut config = crate::bot::BotConfig { quotas, ..Default::default() };
+    if let Some(path) = system_prompt {
+        config.system_prompt = std::fs::read_to_string(path)?;
+    }
+    config.knowledge_base_path = knowledge_base.as_ref().map(PathBuf::from);
+    let api_key = std::env::var(API_KEY_ENV).ok()
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| QitOpsError::InvalidInput(format!("Set {} to the API key requests authenticate with", API_KEY_ENV)))?;
+
+    let listener = tokio::net::TcpListener::bind(listen).await
+        .map_err(|e| QitOpsError::InvalidInput(format!("Failed to listen on {}: {}", listen, e)))?;
+    branding::print_info(&format!("Answering bot messages on http://{}/v1/messages", listener.local_addr()?));
+    crate::bot::server::serve(listener, crate::bot::server::SharedBot::new(LazyRouter::new(), config, api_key)).await
+}
+
+/// Answer bot messages of several tenants over HTTP
+async fn serve_tenants(listen: &str, file: &str) -> Result<()> {
+    let registry = crate::cli::daemon::load_tenants(file)?;
+
+    let listener = tokio::net::TcpListener::bind(listen).await
+        .map_err(|e| QitOpsError::InvalidInput(format!("Failed to listen on {}: {}", listen, e)))?;
+    branding::print_info(&format!(
+        "Answering bot messages for {} tenant(s) on http://{}/v1/messages",
+        registry.ids().len(),
+        listener.local_addr()?
+    ));
+    crate::bot::server::serve(listener, registry).await
+}
+
+/// List tenants and their budget usage
+fn list_tenants(file: &str) -> Result<()> {
+    let registry = crate::cli::daemon::load_tenants(file)?;
+
+    branding::print_section("Tenants");
+    for id in registry.ids() {
+        let Some(tenant) = registry.get(id) else {
+            continue;
+        };
+
+        let budget = tenant.budget();
+        let usage = if budget.max_tokens == 0 {
+            format!("{} tokens (no budget)", tenant.tokens_used())
+        } else {
+            format!("{} / {} tokens", tenant.tokens_used(), budget.max_tokens)
+        };
+
+        println!("- {}", id);
+        println!("  Directory: {}", tenant.dir().display());
+        println!("  Usage: {}", usage);
     }
+
+    Ok(())
+}
+
+/// Validate a knowledge base
+async fn validate_knowledge_base(path: &str, migrate: bool) -> Result<()> {
+    let report = KnowledgeBase::validate(&PathBuf::from(path), migrate)?;
+
+    println!("Knowledge base: {}", path);
+    println!("Schema version: {} (current: {})", report.schema_version, KB_SCHEMA_VERSION);
+
+    for warning in &report.warnings {
+        branding::print_warning(warning);
+    }
+    for error in &report.errors {
+        branding::print_error(error);
+    }
+
+    if !report.errors.is_empty() {
+        return Err(anyhow::anyhow!("Knowledge base is invalid ({} error(s))", report.errors.len()));
+    }
+
+    if report.migrated && migrate {
+        branding::print_success(&format!("Knowledge base migrated to schema version {}", KB_SCHEMA_VERSION));
+    } else if report.migrated {
+        branding::print_info("Knowledge base uses an older schema version and is migrated on load; run with --migrate to update the files");
+    } else {
+        branding::print_success("Knowledge base is valid");
+    }
+
+    Ok(())
 }
 
 /// Start a chat session with QitOps Bot
 async fn chat(system_prompt: &Option<String>, knowledge_base: &Option<String>) -> Result<()> {
-    // Initialize LLM router
-    let llm_router = LlmRouter::new(RouterConfig::default()).await?;
+    // Initialize LLM router; it's rebuilt when the configuration file changes, so a session survives key rotation
+    let llm_router = LazyRouter::new();
+    llm_router.get().await?;
 
     // Create bot configuration
     let mut config = BotConfig::default();
@@ -1,4 +1,8 @@
 use colored::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::i18n::{Msg, t, tf};
+use crate::severity::Severity;
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -25,6 +29,15 @@ pub fn colorize(text: &str, color: Color) -> String {
     }
 }
 
+pub fn severity_color(severity: Severity) -> Color {
+    match severity {
+        Severity::Critical | Severity::High => Color::Red,
+        Severity::Medium => Color::Yellow,
+        Severity::Low => Color::Cyan,
+        Severity::Info => Color::Blue,
+    }
+}
+
 pub fn print_banner() {
     let banner = r#"
   ██████╗ ██╗████████╗ ██████╗ ██████╗ ███████╗
@@ -36,32 +49,52 @@ pub fn print_banner() {
                                                 "#;
 
     println!("{}", banner.bright_cyan());
-    println!("{}", format!("QitOps Agent v{} - AI-powered QA Assistant", VERSION).cyan().bold());
-    println!("{}", "Developed by QitOps Team".cyan());
+    println!("{}", tf(Msg::Tagline, &[&VERSION]).cyan().bold());
+    println!("{}", t(Msg::DevelopedByTeam).cyan());
     println!();
 }
 
+/// Whether status messages go to stderr, leaving stdout to machine-readable output
+static STATUS_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Send status messages (headers, success, warnings, info, sections) to stderr from now on
+pub fn status_to_stderr() {
+    STATUS_TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+/// Print a status message on stdout, or stderr when stdout carries machine-readable output
+fn print_status(text: String) {
+    if STATUS_TO_STDERR.load(Ordering::Relaxed) {
+        eprintln!("{}", text);
+    } else {
+        println!("{}", text);
+    }
+}
+
 pub fn print_command_header(command: &str) {
-    println!("\n{} {}\n", "▶".bright_cyan(), command.cyan().bold());
+    print_status(format!("\n{} {}\n", "▶".bright_cyan(), command.cyan().bold()));
 }
 
 pub fn print_success(message: &str) {
-    println!("\n{} {}\n", "✓".bright_green(), message.green());
+    print_status(format!("\n{} {}\n", "✓".bright_green(), message.green()));
 }
 
 pub fn print_warning(message: &str) {
-    println!("\n{} {}\n", "⚠".yellow(), message.yellow());
+    print_status(format!("\n{} {}\n", "⚠".yellow(), message.yellow()));
 }
 
 pub fn print_error(message: &str) {
     eprintln!("\n{} {}\n", "✗".bright_red(), message.red());
 }
 
+pub fn print_hint(message: &str) {
+    eprintln!("{} {}\n", t(Msg::Hint).bright_blue().bold(), message.blue());
+}
+
 pub fn print_info(message: &str) {
-    println!("\n{} {}\n", "ℹ".bright_blue(), message.blue());
+    print_status(format!("\n{} {}\n", "ℹ".bright_blue(), message.blue()));
 }
 
 pub fn print_section(title: &str) {
-    println!("\n{}", title.cyan().underline().bold());
-    println!("{}\n", "─".repeat(title.len()).cyan());
+    print_status(format!("\n{}\n{}\n", title.cyan().underline().bold(), "─".repeat(title.len()).cyan()));
 }
@@ -0,0 +1,86 @@
+use anyhow::Result;
+
+use crate::capabilities::{Capabilities, Setting};
+use crate::cli::branding;
+
+/// Capability discovery CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct CapabilitiesArgs {
+    /// Print the capabilities as JSON, for orchestration tooling
+    #[clap(long)]
+    pub json: bool,
+}
+
+/// Show the agents, providers, formatters, plugins and integrations this installation has
+pub async fn handle_capabilities_command(args: &CapabilitiesArgs) -> Result<()> {
+    let capabilities = Capabilities::collect()?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&capabilities)?);
+        return Ok(());
+    }
+
+    branding::print_section(&format!("{} {}", capabilities.name, capabilities.version));
+    match capabilities.features.is_empty() {
+        true => println!("Features: none\n"),
+        false => println!("Features: {}\n", capabilities.features.join(", ")),
+    }
+
+    println!("Agents:");
+    for agent in &capabilities.agents {
+        let daemon = agent.daemon_method.as_ref().map(|method| format!(" (daemon: {})", method)).unwrap_or_default();
+        println!("  {:<12} {}{}", agent.name, agent.description, daemon);
+        for path in &agent.prompt_overrides {
+            println!("  {:<12} prompt override: {}", "", path);
+        }
+    }
+
+    println!("\nLLM providers:");
+    for provider in &capabilities.providers {
+        let status = match (&provider.default_model, provider.default) {
+            (Some(model), true) => format!("configured, default ({})", model),
+            (Some(model), false) => format!("configured ({})", model),
+            (None, _) => "not configured".to_string(),
+        };
+        println!("  {:<12} {}{}", provider.provider_type, status, missing(&provider.required_config));
+    }
+
+    println!("\nOutput formats:");
+    for formatter in &capabilities.formatters {
+        println!("  {:<12} {}", formatter.name, formatter.description);
+    }
+    println!("  Test case formats: {}", capabilities.test_formats.join(", "));
+
+    println!("\nPlugins:");
+    if capabilities.plugins.is_empty() {
+        println!("  None");
+    }
+    for plugin in &capabilities.plugins {
+        println!("  {:<12} {} ({})", plugin.name, plugin.kind, plugin.path);
+    }
+
+    println!("\nIntegrations:");
+    for integration in &capabilities.integrations {
+        let status = match integration.configured {
+            true => "configured".to_string(),
+            false => format!("not configured{}; run `{}`", missing(&integration.required_config), integration.configure),
+        };
+        println!("  {:<12} {:<13} {}", integration.name, integration.kind, status);
+    }
+
+    Ok(())
+}
+
+/// ` (missing: ...)` naming the unset settings, with the environment variables that can provide them
+fn missing(settings: &[Setting]) -> String {
+    let unset: Vec<String> = settings.iter()
+        .filter(|setting| !setting.set)
+        .map(|setting| match &setting.env {
+            Some(env) => format!("{} or {}", setting.name, env),
+            None => setting.name.clone(),
+        })
+        .collect();
+    match unset.is_empty() {
+        true => String::new(),
+        false => format!(" (missing: {})", unset.join(", ")),
+    }
+}
@@ -1,10 +1,33 @@
 use clap::{Parser, Subcommand};
 
-use crate::cli::llm::LlmArgs;
+use crate::cli::llm::{LlmArgs, LlmCommand};
+#[cfg(feature = "github")]
 use crate::cli::github::GitHubArgs;
 use crate::cli::source::SourceArgs;
 use crate::cli::persona::PersonaArgs;
+#[cfg(feature = "bot")]
 use crate::cli::bot::BotArgs;
+use crate::cli::schema::SchemaArgs;
+use crate::cli::daemon::DaemonArgs;
+use crate::cli::audit::{AuditArgs, AuditCommand};
+use crate::cli::tcm::TcmArgs;
+use crate::cli::jira::JiraArgs;
+use crate::cli::publish::PublishArgs;
+use crate::cli::notify::NotifyArgs;
+use crate::cli::prompt::PromptArgs;
+use crate::cli::verify::VerifyArgs;
+use crate::cli::history::HistoryArgs;
+#[cfg(feature = "monitoring")]
+use crate::cli::telemetry::TelemetryArgs;
+use crate::cli::diff_runs::DiffRunsArgs;
+use crate::cli::bench::{BenchArgs, BenchCommand};
+use crate::cli::costs::CostsArgs;
+use crate::cli::triage::TriageArgs;
+use crate::cli::digest::DigestArgs;
+use crate::cli::capabilities::CapabilitiesArgs;
+use crate::cli::findings::{FindingsArgs, FindingsCommand};
+use crate::cli::approvals::{ApprovalsArgs, ApprovalsCommand};
+use crate::cli::context::ContextArgs;
 
 /// QitOps Agent CLI
 #[derive(Debug, Parser)]
@@ -14,6 +37,14 @@ pub struct Cli {
     #[clap(short, long)]
     pub verbose: bool,
 
+    /// Forbid network calls: only local LLM providers, no GitHub or other API integrations
+    #[clap(long, global = true)]
+    pub offline: bool,
+
+    /// Keep API keys and tokens in the config files instead of the OS keyring
+    #[clap(long, global = true)]
+    pub no_keyring: bool,
+
     /// Subcommand to execute
     #[clap(subcommand)]
     pub command: Command,
@@ -28,6 +59,22 @@ pub enum Command {
         /// Subcommand to run
         #[clap(subcommand)]
         command: RunCommand,
+
+        /// Send each request to these providers concurrently and merge the answers (comma-separated)
+        #[clap(long, global = true)]
+        ensemble: Option<String>,
+
+        /// Write the prompts, context, responses and timing of the run to a zip archive, with secrets redacted
+        #[clap(long, global = true, value_name = "ZIP")]
+        debug_bundle: Option<String>,
+
+        /// Output format: markdown (md), json, junit, sarif, html or a formatter plugin (test-gen also takes yaml, robot, playwright, cypress, rust-test and pytest)
+        #[clap(long, global = true, visible_alias = "output-format")]
+        format: Option<String>,
+
+        /// Write the formatted output to a file instead of stdout, in the format its extension implies unless --format is given; for test-gen of a directory or glob, the directory to write each file's tests to
+        #[clap(long, global = true, value_name = "FILE")]
+        output: Option<String>,
     },
 
     /// LLM configuration and management
@@ -35,6 +82,7 @@ pub enum Command {
     Llm(LlmArgs),
 
     /// GitHub integration
+    #[cfg(feature = "github")]
     #[clap(name = "github")]
     GitHub(GitHubArgs),
 
@@ -46,28 +94,153 @@ pub enum Command {
     #[clap(name = "persona", about = "Manage personas for context-aware generation")]
     Persona(PersonaArgs),
 
+    /// Schema registry (add, list, remove, show test data schemas)
+    #[clap(name = "schema", about = "Manage reusable test data schemas")]
+    Schema(SchemaArgs),
+
+    /// Prompt templates (list, show, edit)
+    #[clap(name = "prompt", about = "Inspect and override the prompt templates used by agents")]
+    Prompt(PromptArgs),
+
     /// QitOps Bot - Interactive assistant
+    #[cfg(feature = "bot")]
     #[clap(name = "bot", about = "Interactive assistant for QitOps Agent")]
     Bot(BotArgs),
 
+    /// Language server for editor integration
+    #[cfg(feature = "lsp")]
+    #[clap(name = "lsp", about = "Run a language server over stdio for editor integration")]
+    Lsp,
+
+    /// JSON-RPC daemon for editor extensions and other tools
+    #[clap(name = "daemon", about = "Serve agents over JSON-RPC with warm state between requests")]
+    Daemon(DaemonArgs),
+
+    /// Audit log of external actions
+    #[clap(name = "audit", about = "Inspect the audit log of external actions (comments, commands)")]
+    Audit(AuditArgs),
+
+    /// Test case management tools (TestRail, Xray, Zephyr Scale)
+    #[clap(name = "tcm", about = "Sync generated test cases with TestRail, Xray or Zephyr Scale")]
+    Tcm(TcmArgs),
+
+    /// Jira issue filing for findings
+    #[clap(name = "jira", about = "Configure filing Jira issues for high-severity findings")]
+    Jira(JiraArgs),
+
+    /// Report publishing to Confluence or a docs branch
+    #[clap(name = "publish", about = "Publish reports to Confluence or a docs branch, updating them in place")]
+    Publish(PublishArgs),
+
+    /// Report delivery notifications
+    #[clap(name = "notify", about = "Email reports to a distribution list")]
+    Notify(NotifyArgs),
+
+    /// Signature check for JSON results
+    #[clap(name = "verify", about = "Check that a signed JSON result came from QitOps and wasn't edited")]
+    Verify(VerifyArgs),
+
+    /// Session transcript storage
+    #[clap(name = "history", about = "Show or encrypt saved session transcripts, or list saved runs")]
+    History(HistoryArgs),
+
+    /// Usage telemetry settings
+    #[cfg(feature = "monitoring")]
+    #[clap(name = "telemetry", about = "Opt in to or out of anonymous usage telemetry (off by default)")]
+    Telemetry(TelemetryArgs),
+
+    /// Comparison of saved runs
+    #[clap(name = "diff-runs", about = "Compare the findings and test cases of two saved runs")]
+    DiffRuns(DiffRunsArgs),
+
+    /// Agent benchmarks
+    #[clap(name = "bench", about = "Score agents on a fixture suite to catch prompt regressions")]
+    Bench(BenchArgs),
+
+    /// LLM spend reports
+    #[clap(name = "costs", about = "Report LLM spend by command, repository, pull request or user, or export it as CSV")]
+    Costs(CostsArgs),
+
+    /// Failure triage of saved runs
+    #[clap(name = "triage", about = "Ask follow-up questions about the latest failed run without re-running it")]
+    Triage(TriageArgs),
+
+    /// QA digest of saved runs
+    #[clap(name = "digest", about = "Summarize the week's runs into a QA digest for standups or a notification channel")]
+    Digest(DigestArgs),
+
+    /// Capability discovery
+    #[clap(name = "capabilities", about = "List the agents, providers, formatters, plugins and integrations this installation has")]
+    Capabilities(CapabilitiesArgs),
+
+    /// Findings store
+    #[clap(name = "findings", about = "Query the findings of past analyses and whether they were ever addressed")]
+    Findings(FindingsArgs),
+
+    /// Cached repository summaries
+    #[clap(name = "context", about = "Summarize a repository's files and directories once, for short repository context in prompts")]
+    Context(ContextArgs),
+
+    /// Approval queue of sensitive prompts
+    #[clap(name = "approvals", about = "Review sensitive prompts held for approval before they're sent to remote LLM providers")]
+    Approvals(ApprovalsArgs),
+
     /// Show version information
     #[clap(name = "version")]
     Version,
 }
 
+impl Command {
+    /// Whether the command prints JSON that must not be mixed with the banner or logs
+    pub fn json_output(&self) -> bool {
+        match self {
+            Command::Run { format: Some(format), output: None, .. } => !is_test_case_format(format),
+            _ => matches!(
+                self,
+                Command::Audit(AuditArgs { command: AuditCommand::List { json: true, .. } })
+                    | Command::Llm(LlmArgs { command: LlmCommand::Benchmark { json: true, .. } })
+                    | Command::Llm(LlmArgs { command: LlmCommand::Doctor { json: true, .. } })
+                    | Command::DiffRuns(DiffRunsArgs { json: true, .. })
+                    | Command::Bench(BenchArgs { command: BenchCommand::Agents { json: true, .. } })
+                    | Command::Costs(CostsArgs { csv: true, output: None, .. })
+                    | Command::Costs(CostsArgs { json: true, output: None, .. })
+                    | Command::Capabilities(CapabilitiesArgs { json: true })
+                    | Command::Findings(FindingsArgs { command: FindingsCommand::List { json: true, .. } })
+                    | Command::Approvals(ApprovalsArgs { command: ApprovalsCommand::List { json: true, .. } })
+            ),
+        }
+    }
+}
+
+/// Test case file formats `test-gen` writes itself rather than through an output formatter
+pub const TEST_CASE_FORMATS: &[&str] = &["yaml", "robot", "playwright", "cypress", "rust-test", "pytest"];
+
+/// Whether `--format` names a test case file format
+pub fn is_test_case_format(format: &str) -> bool {
+    TEST_CASE_FORMATS.iter().any(|f| f.eq_ignore_ascii_case(format))
+}
+
+/// Subcommand path of a parsed command line, e.g. `run test-gen`; arguments are left out
+pub fn command_name(matches: &clap::ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut current = matches;
+    while let Some((name, sub)) = current.subcommand() {
+        names.push(name.to_string());
+        current = sub;
+    }
+
+    names.join(" ")
+}
+
 /// Run commands
 #[derive(Debug, Subcommand)]
 pub enum RunCommand {
     /// Generate test cases
     #[clap(name = "test-gen")]
     TestGen {
-        /// Path to the source code
-        #[clap(short, long)]
-        path: String,
-
-        /// Output format (markdown, yaml, robot)
-        #[clap(short, long, default_value = "markdown")]
-        format: String,
+        /// Path to a source file, or a directory or glob (e.g. 'src/**/*.rs') to generate tests for each file in it
+        #[clap(short, long, required_unless_present_any = ["watch", "since"])]
+        path: Option<String>,
 
         /// Sources to use (comma-separated)
         #[clap(long)]
@@ -76,14 +249,50 @@ pub enum RunCommand {
         /// Personas to use (comma-separated)
         #[clap(long)]
         personas: Option<String>,
+
+        /// Watch a file or directory and regenerate tests for files as they are saved
+        #[clap(long)]
+        watch: Option<String>,
+
+        /// Push the generated test cases to a test case management tool, as
+        /// <tool>:<project>[-<suite>], e.g. testrail:3-12
+        #[clap(long, conflicts_with = "watch")]
+        push_to: Option<String>,
+
+        /// Sampling seed, for reproducible output from providers that support seeds
+        #[clap(long)]
+        seed: Option<u64>,
+
+        /// Test style guide to follow, instead of learning conventions from the project's existing tests
+        #[clap(long)]
+        conventions: Option<String>,
+
+        /// Resume an interrupted directory run from its checkpoint, skipping the files already done
+        #[clap(long, conflicts_with = "watch")]
+        resume: Option<String>,
+
+        /// Only generate tests for the files and functions changed since a git ref (e.g. origin/main),
+        /// in --path or the current directory
+        #[clap(long, conflicts_with = "watch")]
+        since: Option<String>,
+
+        /// Include the repository context built from the summaries cached by `qitops context summarize`
+        #[clap(long)]
+        repo_summary: bool,
     },
 
     /// Analyze a pull request
     #[clap(name = "pr-analyze")]
     PrAnalyze {
         /// PR number or URL
-        #[clap(short, long)]
-        pr: String,
+        #[cfg(feature = "github")]
+        #[clap(short, long, required_unless_present = "patch", conflicts_with = "patch")]
+        pr: Option<String>,
+
+        /// Patch series to analyze instead of a PR (`git format-patch` output, one or more patches)
+        #[clap(long)]
+        #[cfg_attr(not(feature = "github"), clap(required = true))]
+        patch: Option<String>,
 
         /// Sources to use (comma-separated)
         #[clap(long)]
@@ -92,14 +301,57 @@ pub enum RunCommand {
         /// Personas to use (comma-separated)
         #[clap(long)]
         personas: Option<String>,
+
+        /// Findings baseline mode (use, write, ignore)
+        #[clap(long, default_value = "use")]
+        baseline: String,
+
+        /// Print the diff with findings annotated inline
+        #[clap(long)]
+        annotate: bool,
+
+        /// File Jira issues for new findings at or above the configured severity
+        #[clap(long)]
+        jira: bool,
+
+        /// Analyze the whole PR even if it was analyzed before, instead of only what changed since
+        #[cfg(feature = "github")]
+        #[clap(long, conflicts_with = "patch")]
+        full: bool,
+
+        /// Post the analysis as a comment on the PR, editing the one an earlier run posted
+        #[cfg(feature = "github")]
+        #[clap(long, conflicts_with = "patch")]
+        comment: bool,
+
+        /// Post the findings as a review on the PR, with comments on the lines they are about
+        #[cfg(feature = "github")]
+        #[clap(long, conflicts_with = "patch")]
+        review: bool,
+
+        /// Analyze formatting, comment and import-order hunks instead of filtering them out
+        #[clap(long)]
+        keep_noise: bool,
+
+        /// Exit with code 7 when the severity policy's gate fails, to block CI
+        #[clap(long)]
+        gate: bool,
+
+        /// Fail the gate on a finding severity (`high`), an overall risk (`high-risk`) or the policy (`gate`); comma-separated
+        #[clap(long, value_delimiter = ',')]
+        fail_on: Vec<String>,
+
+        /// Fail the gate below this quality score (0-10)
+        #[clap(long)]
+        min_score: Option<f64>,
     },
 
     /// Estimate risk of changes
     #[clap(name = "risk")]
     Risk {
         /// Path to the diff file or PR URL/number
-        #[clap(short, long)]
-        diff: String,
+        #[clap(short, long, required_unless_present = "watch")]
+        diff: Option<String>,
 
         /// Components to focus on (comma-separated)
         #[clap(short, long)]
@@ -116,12 +368,52 @@ pub enum RunCommand {
         /// Personas to use (comma-separated)
         #[clap(long)]
         personas: Option<String>,
+
+        /// Findings baseline mode (use, write, ignore)
+        #[clap(long, default_value = "use")]
+        baseline: String,
+
+        /// Print the diff with findings annotated inline
+        #[clap(long)]
+        annotate: bool,
+
+        /// Watch a directory and re-assess its uncommitted changes (git diff HEAD) on save
+        #[clap(long)]
+        watch: Option<String>,
+
+        /// File Jira issues for new findings at or above the configured severity
+        #[clap(long, conflicts_with = "watch")]
+        jira: bool,
+
+        /// Publish the assessment to confluence:<SPACE>[/<parent>] or git:<branch>[:<dir>]
+        #[clap(long, conflicts_with = "watch")]
+        publish: Option<String>,
+
+        /// Send the assessment on a notification channel (email)
+        #[clap(long, conflicts_with = "watch")]
+        notify: Option<String>,
+
+        /// Assess formatting, comment and import-order hunks instead of filtering them out
+        #[clap(long)]
+        keep_noise: bool,
+
+        /// Exit with code 7 when the severity policy's gate fails, to block CI
+        #[clap(long, conflicts_with = "watch")]
+        gate: bool,
+
+        /// Fail the gate on a finding severity (`high`), an overall risk (`high-risk`) or the policy (`gate`); comma-separated
+        #[clap(long, value_delimiter = ',', conflicts_with = "watch")]
+        fail_on: Vec<String>,
+
+        /// Fail the gate below this quality score (0-10)
+        #[clap(long, conflicts_with = "watch")]
+        min_score: Option<f64>,
     },
 
     /// Generate test data
     #[clap(name = "test-data")]
     TestData {
-        /// Schema definition
+        /// Schema definition or registered schema name
         #[clap(short, long)]
         schema: String,
 
@@ -129,6 +421,10 @@ pub enum RunCommand {
         #[clap(short, long, default_value = "10")]
         count: usize,
 
+        /// Fields to pseudonymize consistently across runs (comma-separated)
+        #[clap(long)]
+        mask: Option<String>,
+
         /// Sources to use (comma-separated)
         #[clap(long)]
         sources: Option<String>,
@@ -136,14 +432,94 @@ pub enum RunCommand {
         /// Personas to use (comma-separated)
         #[clap(long)]
         personas: Option<String>,
+
+        /// Sampling seed, for reproducible output from providers that support seeds
+        #[clap(long)]
+        seed: Option<u64>,
+    },
+
+    /// Review the quality of existing tests
+    #[clap(name = "test-review")]
+    TestReview {
+        /// Path to a test file or directory of tests
+        #[clap(short, long)]
+        path: String,
+
+        /// Sources to use (comma-separated)
+        #[clap(long)]
+        sources: Option<String>,
+
+        /// Personas to use (comma-separated)
+        #[clap(long)]
+        personas: Option<String>,
+    },
+
+    /// Generate load/performance test scripts
+    #[clap(name = "perf-gen")]
+    PerfGen {
+        /// Path to the OpenAPI specification
+        #[clap(long)]
+        openapi: String,
+
+        /// Load profile (load, spike, soak, stress)
+        #[clap(long, default_value = "load")]
+        profile: String,
+
+        /// Load testing tool (k6, locust)
+        #[clap(long, default_value = "k6")]
+        tool: String,
+
+        /// Schema for a generated data feed (uses the test data generator)
+        #[clap(long)]
+        data_schema: Option<String>,
+
+        /// Number of data feed records to generate
+        #[clap(long, default_value = "50")]
+        data_count: usize,
+    },
+
+    /// Summarize a milestone's merged PRs into a QA appendix for the release notes
+    #[cfg(feature = "github")]
+    #[clap(name = "release-notes-qa")]
+    ReleaseNotesQa {
+        /// Milestone title, e.g. v2.0
+        #[clap(short, long)]
+        milestone: String,
+
+        /// Repository as owner/repo, instead of the configured default
+        #[clap(long)]
+        repo: Option<String>,
     },
 
     /// Start an interactive testing session
     #[clap(name = "session")]
     Session {
         /// Session name
+        #[cfg_attr(feature = "pairing", clap(short, long, required_unless_present = "join"))]
+        #[cfg_attr(not(feature = "pairing"), clap(short, long, required = true))]
+        name: Option<String>,
+
+        /// Host the session for remote pairing on this address (e.g. 0.0.0.0:8765)
+        #[cfg(feature = "pairing")]
+        #[clap(long, conflicts_with = "join")]
+        host: Option<String>,
+
+        /// Join a hosted session with the URL the host printed (e.g. ws://127.0.0.1:8765/?token=...)
+        #[cfg(feature = "pairing")]
+        #[clap(long)]
+        join: Option<String>,
+
+        /// Your name in the session transcript
+        #[clap(long = "as", value_name = "NAME")]
+        participant: Option<String>,
+
+        /// Session objectives to track coverage against (comma-separated)
         #[clap(short, long)]
-        name: String,
+        objectives: Option<String>,
+
+        /// Exploratory testing charter file to derive objectives from
+        #[clap(long)]
+        charter: Option<String>,
 
         /// Sources to use (comma-separated)
         #[clap(long)]
@@ -152,5 +528,10 @@ pub enum RunCommand {
         /// Personas to use (comma-separated)
         #[clap(long)]
         personas: Option<String>,
+
+        /// Publish the session summary to confluence:<SPACE>[/<parent>] or git:<branch>[:<dir>]
+        #[cfg_attr(feature = "pairing", clap(long, conflicts_with = "join"))]
+        #[cfg_attr(not(feature = "pairing"), clap(long))]
+        publish: Option<String>,
     },
 }
@@ -0,0 +1,95 @@
+use anyhow::Result;
+use clap::Subcommand;
+use std::path::Path;
+
+use crate::agent::conventions::project_root;
+use crate::cli::branding;
+use crate::cli::progress::ProgressIndicator;
+use crate::config::QitOpsConfigManager;
+use crate::context::summaries::{self, MAX_CONTEXT_CHARS, RepoSnapshot, SummaryCache};
+use crate::llm::{ConfigManager, LlmRouter};
+
+/// Repository context CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct ContextArgs {
+    /// Repository context subcommand
+    #[clap(subcommand)]
+    pub command: ContextCommand,
+}
+
+/// Repository context subcommands
+#[derive(Debug, Subcommand)]
+pub enum ContextCommand {
+    /// Summarize the new and changed files and directories of the repository, caching the summaries
+    #[clap(name = "summarize")]
+    Summarize {
+        /// Directory in the repository (defaults to the current directory)
+        #[clap(short, long, default_value = ".")]
+        path: String,
+    },
+
+    /// Print the repository context built from the cached summaries
+    #[clap(name = "show")]
+    Show {
+        /// Directory in the repository (defaults to the current directory)
+        #[clap(short, long, default_value = ".")]
+        path: String,
+
+        /// Longest context to print, in characters
+        #[clap(long, default_value_t = MAX_CONTEXT_CHARS)]
+        max_chars: usize,
+    },
+}
+
+/// Handle repository context commands
+pub async fn handle_context_command(args: &ContextArgs) -> Result<()> {
+    match &args.command {
+        ContextCommand::Summarize { path } => summarize(Path::new(path)).await,
+        ContextCommand::Show { path, max_chars } => {
+            let (snapshot, cache) = open(Path::new(path))?;
+            if cache.is_empty() {
+                branding::print_warning("No cached summaries yet; run `qitops context summarize` first");
+            }
+            println!("{}", summaries::generate_context(&snapshot, &cache, *max_chars));
+            Ok(())
+        },
+    }
+}
+
+/// Snapshot and summary cache of the repository a directory is in
+fn open(dir: &Path) -> Result<(RepoSnapshot, SummaryCache)> {
+    let root = project_root(dir);
+    let limits = QitOpsConfigManager::new()?.get_config().context.clone();
+
+    let progress = ProgressIndicator::new("Scanning repository...");
+    let snapshot = RepoSnapshot::scan(&root, &limits);
+    progress.finish();
+    let snapshot = snapshot?;
+    if let Some(limit) = &snapshot.limits_hit {
+        branding::print_warning(&format!("Scan incomplete: {}", limit));
+    }
+
+    Ok((snapshot, SummaryCache::open(&root)?))
+}
+
+/// Bring the cached summaries of a repository up to date
+async fn summarize(dir: &Path) -> Result<()> {
+    let (snapshot, mut cache) = open(dir)?;
+    let router = LlmRouter::new(ConfigManager::new()?.get_config().clone()).await?;
+
+    let progress = ProgressIndicator::new("Summarizing files...");
+    let report = summaries::refresh(&snapshot, &mut cache, &router, progress.reporter()).await;
+    progress.finish();
+    let report = report?;
+
+    branding::print_success(&format!(
+        "Summarized {} of {} files and {} directories of {}; {} unchanged, {} removed",
+        report.files_summarized, snapshot.file_count(), report.dirs_summarized, snapshot.root.display(), report.unchanged, report.removed,
+    ));
+    for (path, error) in &report.failed {
+        branding::print_warning(&format!("Couldn't summarize {}: {}", path, error));
+    }
+    branding::print_info(&format!("Summaries are cached in {}", cache.path().display()));
+
+    Ok(())
+}
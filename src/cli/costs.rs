@@ -0,0 +1,92 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::cli::branding;
+use crate::llm::cost::{self, CostDimension, UsageLedger};
+
+/// Cost report CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct CostsArgs {
+    /// What to roll spend up by: command, repo, pr, user, provider or day (comma-separated for nested groups)
+    #[clap(long, default_value = "command", value_delimiter = ',')]
+    pub by: Vec<CostDimension>,
+
+    /// Period to report: days or weeks back (e.g. 30d, 2w) or a start date (YYYY-MM-DD)
+    #[clap(long, default_value = "30d")]
+    pub since: String,
+
+    /// Print the report as CSV
+    #[clap(long, conflicts_with = "json")]
+    pub csv: bool,
+
+    /// Print the report as JSON
+    #[clap(long)]
+    pub json: bool,
+
+    /// Write the report to a file instead of printing it
+    #[clap(short, long)]
+    pub output: Option<String>,
+}
+
+/// Report LLM spend rolled up by command, repository, pull request or user
+pub async fn handle_costs_command(args: &CostsArgs) -> Result<()> {
+    let ledger = UsageLedger::open_default()?;
+    let first_day = cost::since_day(&args.since)?;
+    let entries = ledger.since(&first_day)?;
+    let groups = cost::roll_up(&entries, &args.by);
+
+    let report = if args.csv {
+        cost::roll_up_csv(&groups, &args.by)
+    } else if args.json {
+        let rows: Vec<_> = groups.iter().map(|(keys, totals)| {
+            let mut row = serde_json::Map::new();
+            for (dimension, key) in args.by.iter().zip(keys) {
+                row.insert(dimension.heading().to_lowercase().replace(' ', "_"), key.clone().into());
+            }
+            row.insert("totals".to_string(), serde_json::to_value(totals).unwrap_or_default());
+            row
+        }).collect();
+        serde_json::to_string_pretty(&serde_json::json!({ "since": first_day, "groups": rows }))?
+    } else {
+        print_table(&first_day, &groups, &args.by, &ledger);
+        return Ok(());
+    };
+
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, report)?;
+            branding::print_success(&format!("Wrote {} cost group(s) since {} to {}", groups.len(), first_day, path));
+        },
+        None => print!("{}", report),
+    }
+    Ok(())
+}
+
+/// Print a roll-up as a table with the total spend
+fn print_table(first_day: &str, groups: &[(Vec<String>, cost::UsageTotals)], by: &[CostDimension], ledger: &UsageLedger) {
+    let headings: Vec<&str> = by.iter().map(CostDimension::heading).collect();
+    branding::print_section(&format!("LLM Costs by {} since {}", headings.join(" and ").to_lowercase(), first_day));
+
+    if groups.is_empty() {
+        branding::print_info(&format!("No usage recorded since {} in {}", first_day, ledger.path().display()));
+        return;
+    }
+
+    let widths: Vec<usize> = (0..by.len())
+        .map(|column| groups.iter().map(|(keys, _)| keys[column].chars().count()).chain([headings[column].len()]).max().unwrap_or(0))
+        .collect();
+    let row = |keys: &[String]| keys.iter().zip(&widths).map(|(key, width)| format!("{:<width$}", key, width = width)).collect::<Vec<_>>().join("  ");
+
+    let heading_keys: Vec<String> = headings.iter().map(|heading| heading.to_string()).collect();
+    println!("{}  {:>9} {:>12} {:>12} {:>10}", row(&heading_keys).bright_cyan(), "Requests", "Tokens in", "Tokens out", "Cost");
+    for (keys, totals) in groups {
+        println!(
+            "{}  {:>9} {:>12} {:>12} {:>10}",
+            row(keys), totals.requests, totals.input_tokens, totals.output_tokens, format!("${:.4}", totals.cost_usd)
+        );
+    }
+
+    let total = groups.iter().fold(0.0, |sum, (_, totals)| sum + totals.cost_usd);
+    println!();
+    println!("Total since {}: {}", first_day, format!("${:.4}", total).bright_yellow());
+}
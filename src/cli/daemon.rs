@@ -0,0 +1,57 @@
+use anyhow::{Result, anyhow};
+
+#[cfg(feature = "bot")]
+use crate::bot::BotConfig;
+#[cfg(feature = "bot")]
+use crate::bot::tenant::{TenantRegistry, TenantsConfig};
+#[cfg(feature = "bot")]
+use crate::llm::{ConfigManager, RouterConfig};
+
+/// Daemon CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct DaemonArgs {
+    /// Serve JSON-RPC over stdin/stdout
+    #[clap(long)]
+    pub stdio: bool,
+
+    /// Serve several tenants from a tenants file (default: $QITOPS_TENANTS_FILE or ~/.qitops/tenants.json)
+    #[cfg(feature = "bot")]
+    #[clap(long, value_name = "FILE", num_args = 0..=1, default_missing_value = "")]
+    pub tenants: Option<String>,
+}
+
+/// Handle daemon command
+pub async fn handle_daemon_command(args: &DaemonArgs) -> Result<()> {
+    if !args.stdio {
+        return Err(anyhow!("No transport selected; use `qitops daemon --stdio`"));
+    }
+
+    #[cfg(feature = "bot")]
+    let tenants = match &args.tenants {
+        Some(path) => Some(load_tenants(path)?),
+        None => None,
+    };
+
+    crate::daemon::run_stdio(#[cfg(feature = "bot")] tenants).await
+}
+
+/// Load the tenants file and set up each tenant's directory
+#[cfg(feature = "bot")]
+pub fn load_tenants(path: &str) -> Result<TenantRegistry> {
+    let path = if path.is_empty() {
+        TenantsConfig::default_path()?
+    } else {
+        path.into()
+    };
+    let config = TenantsConfig::load(&path)?;
+
+    // Tenants without their own LLM configuration use the server's
+    let server_llm = ConfigManager::new()
+        .map(|manager| manager.get_config().clone())
+        .unwrap_or_else(|_| RouterConfig::default());
+
+    let registry = TenantRegistry::new(config, &TenantRegistry::default_root()?, &server_llm, &BotConfig::default())?;
+    tracing::info!("Loaded {} tenant(s) from {}", registry.ids().len(), path.display());
+
+    Ok(registry)
+}
@@ -0,0 +1,94 @@
+use anyhow::Result;
+
+use crate::cli::branding;
+use crate::runs::{self, RunHistory, RunRecord};
+
+/// Run comparison CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct DiffRunsArgs {
+    /// First run: an ID from `qitops history runs`, a unique prefix of one, or `latest~N`
+    pub run_a: String,
+
+    /// Second run, compared against the first
+    pub run_b: String,
+
+    /// Print the differences as JSON
+    #[clap(long)]
+    pub json: bool,
+}
+
+/// Compare the findings and test cases of two saved runs
+pub async fn handle_diff_runs_command(args: &DiffRunsArgs) -> Result<()> {
+    let history = RunHistory::open()?;
+    let a = history.get(&args.run_a)?;
+    let b = history.get(&args.run_b)?;
+
+    if a.report.command != b.report.command {
+        branding::print_warning(&format!(
+            "Comparing a {} run with a {} run", a.report.command, b.report.command
+        ));
+    }
+
+    let diff = runs::diff(&a.report, &b.report);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+            "run_a": a.id,
+            "run_b": b.id,
+            "diff": diff,
+        }))?);
+        return Ok(());
+    }
+
+    println!("--- {}", describe(&a));
+    println!("+++ {}", describe(&b));
+    println!();
+
+    if diff.is_empty() {
+        branding::print_success("No differences");
+        return Ok(());
+    }
+
+    if let Some((before, after)) = &diff.overall_risk && before != after {
+        println!("Overall risk: {} -> {}", before.as_deref().unwrap_or("none"), after.as_deref().unwrap_or("none"));
+    }
+    if let Some((before, after)) = &diff.gate && before != after {
+        let name = |gate: &Option<crate::severity::GateAction>| gate
+            .map(|g| format!("{:?}", g).to_lowercase())
+            .unwrap_or_else(|| "none".to_string());
+        println!("Gate: {} -> {}", name(before), name(after));
+    }
+
+    if !diff.findings_added.is_empty() || !diff.findings_removed.is_empty() || !diff.severity_changed.is_empty() {
+        println!("\nFindings ({} unchanged):", diff.findings_unchanged);
+        for finding in &diff.findings_added {
+            println!("  + [{}] {}", finding.severity, finding.title);
+        }
+        for finding in &diff.findings_removed {
+            println!("  - [{}] {}", finding.severity, finding.title);
+        }
+        for change in &diff.severity_changed {
+            println!("  ~ [{} -> {}] {}", change.was, change.finding.severity, change.finding.title);
+        }
+    }
+
+    if !diff.cases_added.is_empty() || !diff.cases_removed.is_empty() {
+        println!("\nTest cases ({} unchanged):", diff.cases_unchanged);
+        for title in &diff.cases_added {
+            println!("  + {}", title);
+        }
+        for title in &diff.cases_removed {
+            println!("  - {}", title);
+        }
+    }
+
+    if diff.body_lines_added > 0 || diff.body_lines_removed > 0 {
+        println!("\nText: {} lines added, {} removed", diff.body_lines_added, diff.body_lines_removed);
+    }
+
+    Ok(())
+}
+
+/// One-line description of a saved run
+fn describe(record: &RunRecord) -> String {
+    format!("{} ({}, {})", record.id, record.report.title, crate::audit::format_timestamp(record.recorded_at))
+}
@@ -0,0 +1,59 @@
+use anyhow::Result;
+
+use crate::cli::branding;
+use crate::cli::notify::send_report;
+use crate::llm::cost;
+use crate::notification::ChannelKind;
+use crate::runs::RunHistory;
+use crate::runs::digest::Digest;
+
+/// Digest CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct DigestArgs {
+    /// Period to summarize: days or weeks back (e.g. 7d, 2w) or a start date (YYYY-MM-DD)
+    #[clap(long, default_value = "7d")]
+    pub since: String,
+
+    /// Render the digest as HTML
+    #[clap(long, conflicts_with = "json")]
+    pub html: bool,
+
+    /// Print the digest as JSON
+    #[clap(long)]
+    pub json: bool,
+
+    /// Write the digest to a file instead of printing it
+    #[clap(short, long)]
+    pub output: Option<String>,
+
+    /// Also send the digest on a notification channel (e.g. email)
+    #[clap(long)]
+    pub notify: Option<ChannelKind>,
+}
+
+/// Summarize the saved runs of a period: analyses, high-risk changes, tests generated and open findings
+pub async fn handle_digest_command(args: &DigestArgs) -> Result<()> {
+    let first_day = cost::since_day(&args.since)?;
+    let digest = Digest::from_runs(&RunHistory::open()?.list()?, &first_day, &cost::today());
+
+    let text = if args.json {
+        serde_json::to_string_pretty(&digest)?
+    } else if args.html {
+        digest.report().html()
+    } else {
+        digest.markdown()
+    };
+
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, &text)?;
+            branding::print_success(&format!("Wrote the digest of {} run(s) since {} to {}", digest.runs, first_day, path));
+        },
+        None => print!("{}", text),
+    }
+
+    if let Some(channel) = args.notify {
+        send_report(channel, &digest.report()).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::audit::format_timestamp;
+use crate::cli::branding;
+use crate::runs::findings::{FindingStatus, FindingsQuery, FindingsStore};
+use crate::severity::Severity;
+
+/// Findings store CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct FindingsArgs {
+    /// Findings subcommand
+    #[clap(subcommand)]
+    pub command: FindingsCommand,
+}
+
+/// Findings store subcommands
+#[derive(Debug, Subcommand)]
+pub enum FindingsCommand {
+    /// List tracked findings, most severe first
+    #[clap(name = "list")]
+    List {
+        /// Lowest severity to list (info, low, medium, high, critical)
+        #[clap(long)]
+        severity: Option<Severity>,
+
+        /// Repository, as owner/repo
+        #[clap(long)]
+        repo: Option<String>,
+
+        /// Only findings still reported by the latest run of their PR or change
+        #[clap(long, conflicts_with = "resolved")]
+        open: bool,
+
+        /// Only findings a later run no longer reported
+        #[clap(long)]
+        resolved: bool,
+
+        /// Command that reported them, e.g. pr-analyze
+        #[clap(long)]
+        command: Option<String>,
+
+        /// Most findings to list
+        #[clap(long, default_value = "50")]
+        limit: usize,
+
+        /// Print the findings as JSON
+        #[clap(long)]
+        json: bool,
+    },
+}
+
+/// Handle findings commands
+pub async fn handle_findings_command(args: &FindingsArgs) -> Result<()> {
+    match &args.command {
+        FindingsCommand::List { severity, repo, open, resolved, command, limit, json } => {
+            let status = match (open, resolved) {
+                (true, _) => Some(FindingStatus::Open),
+                (_, true) => Some(FindingStatus::Resolved),
+                _ => None,
+            };
+            let store = FindingsStore::open()?;
+            let findings = store.query(&FindingsQuery {
+                min_severity: *severity,
+                repo: repo.clone(),
+                status,
+                command: command.clone(),
+                seen_since: None,
+                limit: Some(*limit),
+            })?;
+
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&findings)?);
+                return Ok(());
+            }
+            if findings.is_empty() {
+                branding::print_info(&format!("No findings match in {}", store.path().display()));
+                return Ok(());
+            }
+
+            for tracked in &findings {
+                let location = match (&tracked.finding.file, tracked.finding.line) {
+                    (Some(file), Some(line)) => format!(" {}:{}", file, line),
+                    (Some(file), None) => format!(" {}", file),
+                    _ => String::new(),
+                };
+                println!("{:<10} {:<8} {}{}", tracked.status, tracked.finding.severity.as_str().to_uppercase(), tracked.finding.title, location);
+
+                let repo = if tracked.repo.is_empty() { String::new() } else { format!("{}, ", tracked.repo) };
+                let mut history = format!("first seen {} ({}), seen {} time(s)", format_timestamp(tracked.first_seen), tracked.first_run, tracked.times_seen);
+                if let (Some(at), Some(run)) = (tracked.resolved_at, &tracked.resolved_run) {
+                    history.push_str(&format!(", resolved {} ({})", format_timestamp(at), run));
+                }
+                println!("{:<19} {}{}: {}; {}", "", repo, tracked.command, tracked.scope, history);
+            }
+        },
+    }
+
+    Ok(())
+}
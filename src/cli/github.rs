@@ -1,6 +1,8 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use clap::Subcommand;
+use std::collections::HashSet;
 
+use crate::audit::{AuditAction, AuditEvent, AuditLog};
 use crate::ci::{GitHubConfigManager, GitHubClient};
 use crate::cli::branding;
 
@@ -18,7 +20,7 @@ pub enum GitHubCommand {
     /// Configure GitHub integration
     #[clap(name = "config")]
     Config {
-        /// GitHub API token
+        /// GitHub API token, stored in the OS keyring unless `--no-keyring` is given
         #[clap(short = 't', long)]
         token: Option<String>,
         
@@ -33,6 +35,10 @@ pub enum GitHubCommand {
         /// Default repository name
         #[clap(short = 'r', long)]
         repo: Option<String>,
+
+        /// HTTP(S) proxy URL (default: HTTPS_PROXY/HTTP_PROXY environment variables)
+        #[clap(long)]
+        proxy: Option<String>,
     },
     
     /// Test GitHub integration
@@ -50,13 +56,25 @@ pub enum GitHubCommand {
     /// Show GitHub configuration
     #[clap(name = "status")]
     Status,
+
+    /// Delete the comments posted by a run (see `qitops audit list` for run IDs)
+    #[clap(name = "undo")]
+    Undo {
+        /// Run ID to undo
+        #[clap(long)]
+        run: String,
+
+        /// Only show what would be deleted
+        #[clap(long)]
+        dry_run: bool,
+    },
 }
 
 /// Handle GitHub commands
 pub async fn handle_github_command(args: &GitHubArgs) -> Result<()> {
     match &args.command {
-        GitHubCommand::Config { token, api_base, owner, repo } => {
-            configure_github(token.clone(), api_base.clone(), owner.clone(), repo.clone()).await
+        GitHubCommand::Config { token, api_base, owner, repo, proxy } => {
+            configure_github(token.clone(), api_base.clone(), owner.clone(), repo.clone(), proxy.clone()).await
         },
         GitHubCommand::Test { owner, repo } => {
             test_github_integration(owner.clone(), repo.clone()).await
@@ -64,11 +82,14 @@ pub async fn handle_github_command(args: &GitHubArgs) -> Result<()> {
         GitHubCommand::Status => {
             show_github_status().await
         },
+        GitHubCommand::Undo { run, dry_run } => {
+            undo_run(run, *dry_run).await
+        },
     }
 }
 
 /// Configure GitHub integration
-async fn configure_github(token: Option<String>, api_base: Option<String>, owner: Option<String>, repo: Option<String>) -> Result<()> {
+async fn configure_github(token: Option<String>, api_base: Option<String>, owner: Option<String>, repo: Option<String>, proxy: Option<String>) -> Result<()> {
     let mut config_manager = GitHubConfigManager::new()?;
     
     if let Some(token) = token {
@@ -90,6 +111,12 @@ async fn configure_github(token: Option<String>, api_base: Option<String>, owner
         config_manager.set_default_repo(repo)?;
         branding::print_success("Default repository name configured");
     }
+
+    if let Some(proxy) = proxy {
+        crate::proxy::client(Some(&proxy))?;
+        config_manager.set_proxy(proxy)?;
+        branding::print_success("GitHub proxy configured");
+    }
     
     Ok(())
 }
@@ -161,6 +188,11 @@ async fn show_github_status() -> Result<()> {
     } else {
         println!("GitHub API URL: https://api.github.com (default)");
     }
+
+    // Check proxy
+    if let Some(proxy) = &config.proxy {
+        println!("Proxy: {}", proxy);
+    }
     
     // Check default repository
     if let Some(owner) = &config.default_owner {
@@ -176,3 +208,82 @@ async fn show_github_status() -> Result<()> {
     
     Ok(())
 }
+
+/// Delete the comments a run posted, as recorded in the audit log
+async fn undo_run(run: &str, dry_run: bool) -> Result<()> {
+    let log = AuditLog::open_default()?;
+    let events = log.read()?;
+
+    let run_events: Vec<_> = events.iter()
+        .filter(|event| event.run_id.as_deref() == Some(run) && event.success)
+        .collect();
+    if run_events.is_empty() {
+        return Err(anyhow!("No successful actions recorded for run {} in {}", run, log.path().display()));
+    }
+
+    // Comments deleted by an earlier undo, by the action that deleted them
+    let deleted: HashSet<(AuditAction, u64)> = events.iter()
+        .filter(|event| event.success)
+        .filter_map(|event| Some((event.action, event.resource_id?)))
+        .collect();
+
+    let mut comments = Vec::new();
+    for event in &run_events {
+        match (event.action.undone_by(), event.resource_id) {
+            (Some(undo), Some(id)) if deleted.contains(&(undo, id)) => {
+                branding::print_info(&format!("{} {} on {} was already deleted", comment_kind(event), id, event.target));
+            },
+            (Some(_), Some(_)) => comments.push(*event),
+            _ if matches!(event.action, AuditAction::CommentDeleted | AuditAction::ReviewCommentDeleted) => {},
+            // The review's inline comments are recorded on their own and deleted with the rest
+            _ if event.action == AuditAction::ReviewPosted => {
+                branding::print_info(&format!("The summary of review {} on {} stays, as GitHub can't delete submitted reviews", event.resource_id.unwrap_or_default(), event.target));
+            },
+            _ => {
+                branding::print_warning(&format!("Cannot undo {} on {}", event.action, event.target));
+            },
+        }
+    }
+
+    if comments.is_empty() {
+        branding::print_info(&format!("Nothing to undo for run {}", run));
+        return Ok(());
+    }
+
+    if dry_run {
+        for event in &comments {
+            println!("Would delete {} {} on {}", comment_kind(event).to_lowercase(), event.resource_id.unwrap_or_default(), event.target);
+        }
+        return Ok(());
+    }
+
+    let config_manager = GitHubConfigManager::new()?;
+    let github_client = GitHubClient::from_config(config_manager.get_config())?;
+
+    let mut failed = 0;
+    for event in &comments {
+        let (kind, id) = (comment_kind(event), event.resource_id.unwrap_or_default());
+        match github_client.undo(event).await {
+            Ok(true) => branding::print_success(&format!("Deleted {} {} on {}", kind.to_lowercase(), id, event.target)),
+            Ok(false) => branding::print_info(&format!("{} {} on {} no longer exists", kind, id, event.target)),
+            Err(e) => {
+                branding::print_error(&format!("Failed to delete {} {} on {}: {}", kind.to_lowercase(), id, event.target, e));
+                failed += 1;
+            },
+        }
+    }
+
+    if failed > 0 {
+        return Err(anyhow!("{} of {} comments could not be deleted", failed, comments.len()));
+    }
+
+    Ok(())
+}
+
+/// What kind of comment an event posted, for messages
+fn comment_kind(event: &AuditEvent) -> &'static str {
+    match event.action {
+        AuditAction::ReviewCommentPosted => "Review comment",
+        _ => "Comment",
+    }
+}
@@ -0,0 +1,184 @@
+use anyhow::Result;
+use clap::Subcommand;
+use std::fs;
+use std::path::Path;
+
+use crate::cli::branding;
+use crate::history::{self, HistoryConfig};
+use crate::llm::cost;
+use crate::runs::RunHistory;
+use crate::runs::risk::{ComponentTrend, RiskQuery, RiskStore, component_trends};
+
+/// History CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct HistoryArgs {
+    /// History subcommand
+    #[clap(subcommand)]
+    pub command: HistoryCommand,
+}
+
+/// History subcommands
+#[derive(Debug, Subcommand)]
+pub enum HistoryCommand {
+    /// Print a session transcript, decrypting it if needed
+    #[clap(name = "show")]
+    Show {
+        /// Transcript file, e.g. sessions/login.md.enc
+        file: String,
+    },
+
+    /// Encrypt existing plaintext transcripts, removing the originals
+    #[clap(name = "encrypt")]
+    Encrypt {
+        /// Transcript files to encrypt
+        #[clap(required = true)]
+        files: Vec<String>,
+    },
+
+    /// List saved runs, newest first, for `qitops diff-runs`
+    #[clap(name = "runs")]
+    Runs {
+        /// Most runs to list
+        #[clap(long, default_value = "20")]
+        limit: usize,
+    },
+
+    /// Show how the risk of components developed across recorded risk assessments
+    #[clap(name = "risk")]
+    Risk {
+        /// Only assessments of this component, listed one by one
+        #[clap(long)]
+        component: Option<String>,
+
+        /// Repository, as owner/repo
+        #[clap(long)]
+        repo: Option<String>,
+
+        /// Period to show: days or weeks back (e.g. 30d, 4w) or a start date (YYYY-MM-DD)
+        #[clap(long)]
+        since: Option<String>,
+
+        /// Most assessments to look at, newest first
+        #[clap(long, default_value = "100")]
+        limit: usize,
+
+        /// Print the assessments and trends as JSON
+        #[clap(long)]
+        json: bool,
+    },
+}
+
+/// Handle history commands
+pub async fn handle_history_command(args: &HistoryArgs) -> Result<()> {
+    match &args.command {
+        HistoryCommand::Show { file } => {
+            println!("{}", history::read(Path::new(file))?);
+        },
+        HistoryCommand::Encrypt { files } => {
+            // Encrypt even when `history.encrypt` is off
+            let config = HistoryConfig { encrypt: true };
+
+            for file in files {
+                let path = Path::new(file);
+                if path.extension().is_some_and(|ext| ext == history::ENCRYPTED_EXTENSION) {
+                    branding::print_warning(&format!("{} is already encrypted", file));
+                    continue;
+                }
+
+                let content = fs::read_to_string(path)?;
+                let encrypted = history::write(path, &content, &config)?;
+                fs::remove_file(path)?;
+                branding::print_success(&format!("Encrypted {} to {}", file, encrypted.display()));
+            }
+        },
+        HistoryCommand::Runs { limit } => {
+            let records = RunHistory::open()?.list()?;
+            if records.is_empty() {
+                branding::print_info("No saved runs");
+            }
+
+            for record in records.iter().take(*limit) {
+                println!("{}  {}  {}  {} findings",
+                    record.id,
+                    crate::audit::format_timestamp(record.recorded_at),
+                    record.report.title,
+                    record.report.findings.len(),
+                );
+            }
+        },
+        HistoryCommand::Risk { component, repo, since, limit, json } => {
+            let store = RiskStore::open()?;
+            let records = store.query(&RiskQuery {
+                repo: repo.clone(),
+                component: component.clone(),
+                since_day: since.as_deref().map(cost::since_day).transpose()?,
+                limit: Some(*limit),
+            })?;
+            let mut trends = component_trends(&records);
+            if let Some(component) = component {
+                trends.retain(|trend| trend.component.eq_ignore_ascii_case(component));
+            }
+
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&serde_json::json!({"assessments": records, "trends": trends}))?);
+                return Ok(());
+            }
+            if records.is_empty() {
+                branding::print_info(&format!("No risk assessments match in {}", store.path().display()));
+                return Ok(());
+            }
+
+            match component {
+                // Each assessment of the component
+                Some(component) => {
+                    branding::print_section(&format!("Risk of {}", component));
+                    for record in &records {
+                        let Some(entry) = record.components.iter().find(|entry| entry.component.eq_ignore_ascii_case(component)) else {
+                            continue;
+                        };
+                        let scope = match (record.repo.as_str(), record.pr) {
+                            ("", _) => record.scope.clone(),
+                            (repo, Some(pr)) => format!("{}#{}", repo, pr),
+                            (repo, None) => format!("{}: {}", repo, record.scope),
+                        };
+                        println!("{}  {:<8} {:>5}  {}", record.day, entry.risk_level.as_str().to_uppercase(), score(entry.score), scope);
+                    }
+                },
+                // Every component, most often high-risk first
+                None => {
+                    branding::print_section(&format!("Component Risk across {} Assessment(s)", records.len()));
+                    println!("{:<24} {:>8} {:>6} {:>5}  {:<14} CHANGE", "COMPONENT", "ASSESSED", "HIGH+", "AVG", "LATEST");
+                    for trend in &trends {
+                        println!("{:<24} {:>8} {:>6} {:>5}  {:<14} {}",
+                            trend.component, trend.assessments, trend.high_risk, score(trend.average_score),
+                            format!("{} {}", trend.latest_level, score(trend.latest_score)), change(trend),
+                        );
+                    }
+                },
+            }
+
+            for trend in trends.iter().filter(|trend| trend.is_recurring()) {
+                branding::print_warning(&format!(
+                    "{} keeps coming up high-risk: high or critical in {} of {} assessments",
+                    trend.component, trend.high_risk, trend.assessments,
+                ));
+            }
+        },
+    }
+
+    Ok(())
+}
+
+/// A score, or `-` for assessments without one
+fn score(score: Option<u32>) -> String {
+    score.map_or_else(|| "-".to_string(), |score| score.to_string())
+}
+
+/// How a component's latest score compares with its earlier ones, e.g. `+12`
+fn change(trend: &ComponentTrend) -> String {
+    match trend.change {
+        Some(change) if change > 0 => format!("+{}", change),
+        Some(change) => change.to_string(),
+        None => "-".to_string(),
+    }
+}
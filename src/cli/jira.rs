@@ -0,0 +1,139 @@
+use anyhow::Result;
+use clap::Subcommand;
+
+use crate::cli::branding;
+use crate::integrations::jira::JiraConfigManager;
+use crate::severity::Severity;
+
+/// Jira CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct JiraArgs {
+    /// Jira subcommand
+    #[clap(subcommand)]
+    pub command: JiraCommand,
+}
+
+/// Jira subcommands
+#[derive(Debug, Subcommand)]
+pub enum JiraCommand {
+    /// Configure Jira issue filing
+    #[clap(name = "config")]
+    Config {
+        /// Jira instance URL
+        #[clap(long)]
+        url: Option<String>,
+
+        /// Account email (Jira Cloud); leave unset to use a personal access token
+        #[clap(long)]
+        email: Option<String>,
+
+        /// API token or personal access token
+        #[clap(long)]
+        api_token: Option<String>,
+
+        /// Project key issues are filed in
+        #[clap(short, long)]
+        project: Option<String>,
+
+        /// Issue type of filed issues
+        #[clap(long)]
+        issue_type: Option<String>,
+
+        /// Labels added to filed issues (comma-separated)
+        #[clap(long)]
+        labels: Option<String>,
+
+        /// Lowest finding severity that gets an issue (info, low, medium, high, critical)
+        #[clap(long)]
+        min_severity: Option<Severity>,
+
+        /// HTTP(S) proxy URL (default: HTTPS_PROXY/HTTP_PROXY environment variables)
+        #[clap(long)]
+        proxy: Option<String>,
+    },
+
+    /// Show Jira configuration
+    #[clap(name = "status")]
+    Status,
+}
+
+/// Handle Jira commands
+pub async fn handle_jira_command(args: &JiraArgs) -> Result<()> {
+    match &args.command {
+        JiraCommand::Config { url, email, api_token, project, issue_type, labels, min_severity, proxy } => {
+            let mut config_manager = JiraConfigManager::new()?;
+            let mut config = config_manager.get_config().clone();
+
+            if let Some(url) = url {
+                config.url = Some(url.clone());
+            }
+            if let Some(email) = email {
+                config.email = Some(email.clone()).filter(|email| !email.is_empty());
+            }
+            if let Some(api_token) = api_token {
+                config.api_token = Some(api_token.clone());
+            }
+            if let Some(project) = project {
+                config.project = Some(project.clone());
+            }
+            if let Some(issue_type) = issue_type {
+                config.issue_type = issue_type.clone();
+            }
+            if let Some(labels) = labels {
+                config.labels = labels.split(',')
+                    .map(|label| label.trim().to_string())
+                    .filter(|label| !label.is_empty())
+                    .collect();
+            }
+            if let Some(min_severity) = min_severity {
+                config.min_severity = *min_severity;
+            }
+            if let Some(proxy) = proxy {
+                crate::proxy::client(Some(proxy))?;
+                config.proxy = Some(proxy.clone());
+            }
+
+            config_manager.set_config(config)?;
+            branding::print_success("Jira configuration saved");
+            Ok(())
+        },
+        JiraCommand::Status => show_jira_status(),
+    }
+}
+
+/// Show Jira configuration status
+fn show_jira_status() -> Result<()> {
+    let config_manager = JiraConfigManager::new()?;
+    let config = config_manager.get_config();
+
+    println!("Jira Configuration:");
+
+    match &config.url {
+        Some(url) => println!("Jira URL: {}", url),
+        None if std::env::var("JIRA_URL").is_ok() => println!("Jira URL: Using JIRA_URL environment variable"),
+        None => branding::print_error("Jira URL: Not configured"),
+    }
+
+    if config.api_token.is_some() {
+        branding::print_success("Jira API token: Configured");
+    } else if std::env::var("JIRA_API_TOKEN").is_ok() {
+        branding::print_success("Jira API token: Using JIRA_API_TOKEN environment variable");
+    } else {
+        branding::print_error("Jira API token: Not configured");
+    }
+
+    match &config.project {
+        Some(project) => println!("Project: {}", project),
+        None => branding::print_warning("Project not configured"),
+    }
+    println!("Issue type: {}", config.issue_type);
+    if !config.labels.is_empty() {
+        println!("Labels: {}", config.labels.join(", "));
+    }
+    println!("Files issues for findings at or above: {}", config.min_severity);
+    if let Some(proxy) = &config.proxy {
+        println!("Proxy: {}", proxy);
+    }
+
+    Ok(())
+}
@@ -3,7 +3,14 @@ use clap::{Args, Subcommand};
 use std::collections::HashMap;
 use colored::Colorize;
 
-use crate::llm::{ConfigManager, ProviderConfig, LlmRequest, LlmRouter, CacheConfig};
+use crate::config::QitOpsConfigManager;
+use crate::llm::{ConfigManager, ProviderConfig, ProviderAuth, LlmRequest, LlmRouter, RouterConfig, UsageLedger};
+use crate::llm::benchmark::{self, ProviderBenchmark};
+use crate::llm::cache::ResponseCache;
+use crate::llm::providers::{client_for, model_listed};
+use crate::llm::cost::{self, today};
+use crate::llm::probe::{self, CheckStatus};
+use crate::audit::format_timestamp;
 use crate::cli::branding;
 use crate::cli::progress::ProgressIndicator;
 
@@ -35,11 +42,19 @@ pub enum CacheCommand {
         /// Enable or disable disk cache
         #[clap(long)]
         disk: Option<bool>,
+
+        /// Maximum cache size in megabytes (0 for no limit)
+        #[clap(long)]
+        max_size_mb: Option<u64>,
     },
 
     /// Show cache status
     #[clap(name = "status")]
     Status,
+
+    /// Show cache entries, size and hits
+    #[clap(name = "stats")]
+    Stats,
 }
 
 /// LLM subcommands
@@ -52,11 +67,11 @@ pub enum LlmCommand {
     /// Add a new LLM provider
     #[clap(name = "add")]
     Add {
-        /// Provider type (openai, ollama, anthropic)
+        /// Provider type (openai, ollama, anthropic, huggingface, local, mock)
         #[clap(short = 'p', long)]
         provider: String,
 
-        /// API key (if needed)
+        /// API key (if needed), stored in the OS keyring unless `--no-keyring` is given
         #[clap(short = 'k', long)]
         api_key: Option<String>,
 
@@ -64,9 +79,53 @@ pub enum LlmCommand {
         #[clap(short = 'b', long)]
         api_base: Option<String>,
 
-        /// Default model to use
+        /// Default model to use (path to a GGUF file for the local provider)
         #[clap(short = 'm', long)]
         model: String,
+
+        /// Provider option as key=value (e.g. context_size=8192, threads=8); can be repeated
+        #[clap(short = 'o', long = "option", value_parser = parse_key_value)]
+        options: Vec<(String, String)>,
+
+        /// Maximum requests per minute; further requests are queued
+        #[clap(long)]
+        rpm: Option<u32>,
+
+        /// Maximum tokens per minute; further requests are queued
+        #[clap(long)]
+        tpm: Option<u32>,
+
+        /// Most requests in flight at once; further requests are queued [default: 4 for ollama, 0 (no limit) otherwise]
+        #[clap(long)]
+        max_concurrent: Option<u32>,
+
+        /// HTTP(S) proxy URL (default: HTTPS_PROXY/HTTP_PROXY environment variables)
+        #[clap(long)]
+        proxy: Option<String>,
+
+        /// Seconds to wait for a response (default: fallback.timeout_secs, 120)
+        #[clap(long)]
+        timeout: Option<u64>,
+
+        /// Seconds to wait for a connection [default: 10]
+        #[clap(long)]
+        connect_timeout: Option<u64>,
+
+        /// Retries of timeouts and server errors (default: fallback.max_retries, 2)
+        #[clap(long)]
+        max_retries: Option<u32>,
+
+        /// Header sent with every request as name=value, e.g. for an LLM gateway; can be repeated
+        #[clap(short = 'H', long = "header", value_parser = parse_key_value)]
+        headers: Vec<(String, String)>,
+
+        /// Bearer token sent instead of the API key, e.g. '${GATEWAY_TOKEN}'
+        #[clap(long, conflicts_with = "auth_basic")]
+        auth_bearer: Option<String>,
+
+        /// Basic auth credentials as username:password, sent instead of the API key
+        #[clap(long)]
+        auth_basic: Option<String>,
     },
 
     /// Remove an LLM provider
@@ -97,6 +156,34 @@ pub enum LlmCommand {
         provider: String,
     },
 
+    /// Configure retries and the provider fallback chain
+    #[clap(name = "set-fallback")]
+    SetFallback {
+        /// Providers to fall back to after the primary one, in order (comma-separated)
+        #[clap(short = 'c', long, value_delimiter = ',', conflicts_with = "default_chain")]
+        chain: Option<Vec<String>>,
+
+        /// Fall back to all other providers in configuration order
+        #[clap(long)]
+        default_chain: bool,
+
+        /// Retries per provider before falling through
+        #[clap(short = 'r', long)]
+        retries: Option<u32>,
+
+        /// Delay before the first retry in milliseconds (doubled on each retry)
+        #[clap(long)]
+        backoff_ms: Option<u64>,
+
+        /// Upper bound for the retry delay in milliseconds
+        #[clap(long)]
+        max_backoff_ms: Option<u64>,
+
+        /// Per-request timeout in seconds
+        #[clap(long)]
+        timeout: Option<u64>,
+    },
+
     /// Test an LLM provider
     #[clap(name = "test")]
     Test {
@@ -120,26 +207,104 @@ pub enum LlmCommand {
         #[clap(subcommand)]
         command: CacheCommand,
     },
+
+    /// Show LLM requests, tokens and estimated spend by command and provider, and budgets
+    #[clap(name = "usage")]
+    Usage {
+        /// Period to show: days or weeks back (e.g. 7d, 2w) or a start date (YYYY-MM-DD)
+        #[clap(long, default_value = "7d")]
+        since: String,
+
+        /// Number of days to show (same as `--since <days>d`)
+        #[clap(short, long, hide = true, conflicts_with = "since")]
+        days: Option<usize>,
+
+        /// Print usage entries as JSON
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// List the models a provider serves and check the configured model is one of them
+    #[clap(name = "models")]
+    Models {
+        /// Provider to list models for; defaults to all configured providers
+        #[clap(short, long)]
+        provider: Option<String>,
+    },
+
+    /// Probe each provider's reachability, credentials, model and latency, with hints for problems
+    #[clap(name = "doctor")]
+    Doctor {
+        /// Provider to probe; defaults to all configured providers
+        #[clap(short, long)]
+        provider: Option<String>,
+
+        /// Also request a short completion to check the model answers (costs a few tokens)
+        #[clap(long)]
+        completion: bool,
+
+        /// Print the results as JSON
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Benchmark providers on a fixed prompt suite
+    #[clap(name = "benchmark")]
+    Benchmark {
+        /// Providers to benchmark (comma-separated); defaults to all configured providers
+        #[clap(short, long)]
+        providers: Option<String>,
+
+        /// Times to run the suite against each provider
+        #[clap(short, long, default_value = "1")]
+        runs: usize,
+
+        /// Print results as JSON
+        #[clap(long)]
+        json: bool,
+    },
 }
 
 /// Handle LLM commands
 pub async fn handle_llm_command(args: &LlmArgs) -> Result<()> {
     match &args.command {
         LlmCommand::List => list_providers().await,
-        LlmCommand::Add { provider, api_key, api_base, model } => {
-            add_provider(provider, api_key.clone(), api_base.clone(), model).await
+        LlmCommand::Add { provider, api_key, api_base, model, options, rpm, tpm, max_concurrent, proxy, timeout, connect_timeout, max_retries, headers, auth_bearer, auth_basic } => {
+            let deadlines = Deadlines { timeout_secs: *timeout, connect_timeout_secs: *connect_timeout, max_retries: *max_retries };
+            let auth = match (auth_bearer, auth_basic) {
+                (Some(token), _) => Some(ProviderAuth::Bearer { token: token.clone() }),
+                (None, Some(credentials)) => {
+                    let (username, password) = credentials.split_once(':').unwrap_or((credentials, ""));
+                    Some(ProviderAuth::Basic { username: username.to_string(), password: password.to_string() })
+                },
+                (None, None) => None,
+            };
+            let gateway = Gateway { headers: headers.iter().cloned().collect(), auth };
+            add_provider(provider, api_key.clone(), api_base.clone(), model, options.iter().cloned().collect(), *rpm, *tpm, *max_concurrent, proxy.clone(), deadlines, gateway).await
         },
         LlmCommand::Remove { provider } => remove_provider(provider).await,
         LlmCommand::SetDefault { provider } => set_default_provider(provider).await,
         LlmCommand::SetTask { task, provider } => set_task_provider(task, provider).await,
+        LlmCommand::SetFallback { chain, default_chain, retries, backoff_ms, max_backoff_ms, timeout } => {
+            let chain = if *default_chain { Some(Vec::new()) } else { chain.clone() };
+            set_fallback(chain, *retries, *backoff_ms, *max_backoff_ms, *timeout).await
+        },
         LlmCommand::Test { provider, prompt, no_cache } => test_provider(provider.as_deref(), prompt, *no_cache).await,
         LlmCommand::Cache { command } => {
             match command {
                 CacheCommand::Clear => clear_cache().await,
-                CacheCommand::Config { enabled, ttl, disk } => configure_cache(*enabled, *ttl, *disk).await,
+                CacheCommand::Config { enabled, ttl, disk, max_size_mb } => configure_cache(*enabled, *ttl, *disk, *max_size_mb).await,
                 CacheCommand::Status => show_cache_status().await,
+                CacheCommand::Stats => show_cache_stats().await,
             }
         },
+        LlmCommand::Usage { since, days, json } => {
+            let since = days.map_or_else(|| since.clone(), |days| format!("{}d", days));
+            show_usage(&since, *json)
+        },
+        LlmCommand::Models { provider } => list_models(provider.as_deref()).await,
+        LlmCommand::Doctor { provider, completion, json } => doctor(provider.as_deref(), *completion, *json).await,
+        LlmCommand::Benchmark { providers, runs, json } => benchmark_providers(providers.as_deref(), *runs, *json).await,
     }
 }
 
@@ -162,6 +327,36 @@ async fn list_providers() -> Result<()> {
         if let Some(api_base) = &provider.api_base {
             println!("  API Base: {}", api_base);
         }
+        if let Some(rpm) = provider.requests_per_minute {
+            println!("  Rate limit: {} requests/min", rpm);
+        }
+        if let Some(tpm) = provider.tokens_per_minute {
+            println!("  Rate limit: {} tokens/min", tpm);
+        }
+        if let Some(max) = crate::llm::pool::max_concurrent(provider) {
+            println!("  Concurrency limit: {} requests", max);
+        }
+        if let Some(proxy) = &provider.proxy {
+            println!("  Proxy: {}", proxy);
+        }
+        if let Some(timeout) = provider.timeout_secs {
+            println!("  Timeout: {}s", timeout);
+        }
+        if let Some(connect_timeout) = provider.connect_timeout_secs {
+            println!("  Connect timeout: {}s", connect_timeout);
+        }
+        if let Some(max_retries) = provider.max_retries {
+            println!("  Max retries: {}", max_retries);
+        }
+        if !provider.headers.is_empty() {
+            // Header values often hold credentials
+            let mut names: Vec<&String> = provider.headers.keys().collect();
+            names.sort();
+            println!("  Headers: {}", names.iter().map(|name| name.as_str()).collect::<Vec<_>>().join(", "));
+        }
+        if let Some(auth) = &provider.auth {
+            println!("  Auth: {}", auth.scheme());
+        }
         if !provider.options.is_empty() {
             println!("  Options:");
             for (key, value) in &provider.options {
@@ -178,6 +373,9 @@ async fn list_providers() -> Result<()> {
         }
     }
 
+    branding::print_section("Fallback");
+    print_fallback(config);
+
     // Try to initialize the router and check which providers are actually available
     match LlmRouter::new(config.clone()).await {
         Ok(router) => {
@@ -194,22 +392,89 @@ async fn list_providers() -> Result<()> {
     Ok(())
 }
 
+/// Timeouts and retries given to `qitops llm add`
+struct Deadlines {
+    timeout_secs: Option<u64>,
+    connect_timeout_secs: Option<u64>,
+    max_retries: Option<u32>,
+}
+
+/// Static headers and authentication given to `qitops llm add` for a gateway
+struct Gateway {
+    headers: HashMap<String, String>,
+    auth: Option<ProviderAuth>,
+}
+
 /// Add a new LLM provider
-async fn add_provider(provider_type: &str, api_key: Option<String>, api_base: Option<String>, model: &str) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+async fn add_provider(provider_type: &str, api_key: Option<String>, api_base: Option<String>, model: &str, options: HashMap<String, String>, rpm: Option<u32>, tpm: Option<u32>, max_concurrent: Option<u32>, proxy: Option<String>, deadlines: Deadlines, gateway: Gateway) -> Result<()> {
     let mut config_manager = ConfigManager::new()?;
 
+    if deadlines.timeout_secs == Some(0) || deadlines.connect_timeout_secs == Some(0) {
+        return Err(anyhow!("--timeout and --connect-timeout must be at least 1 second"));
+    }
+
+    // Reject a malformed proxy URL before saving it
+    if let Some(proxy) = &proxy {
+        crate::proxy::client(Some(proxy))?;
+    }
+
+    // Store an absolute mock fixtures directory so the provider works from any directory
+    let mut options = options;
+    if provider_type == "mock" && let Some(fixtures) = options.get_mut("fixtures") {
+        *fixtures = std::env::current_dir()?.join(&*fixtures).to_string_lossy().to_string();
+    }
+
+    // Store an absolute model path so the provider works from any directory
+    let model = if provider_type == "local" {
+        let path = std::path::Path::new(model);
+        if !path.is_file() {
+            return Err(anyhow!("Model file not found: {}", model));
+        }
+        path.canonicalize()?.to_string_lossy().to_string()
+    } else {
+        model.to_string()
+    };
+
     let provider_config = ProviderConfig {
         provider_type: provider_type.to_string(),
         api_key,
         api_base,
-        default_model: model.to_string(),
-        options: HashMap::new(),
+        default_model: model.clone(),
+        options,
+        requests_per_minute: rpm,
+        tokens_per_minute: tpm,
+        max_concurrent_requests: max_concurrent,
+        proxy,
+        timeout_secs: deadlines.timeout_secs,
+        connect_timeout_secs: deadlines.connect_timeout_secs,
+        max_retries: deadlines.max_retries,
+        headers: gateway.headers,
+        auth: gateway.auth,
     };
 
-    match config_manager.add_provider(provider_config) {
+    // Reject malformed headers before saving them; `${VAR}` references are resolved at request time
+    for name in provider_config.headers.keys() {
+        reqwest::header::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| anyhow!("Invalid header name: {}", name))?;
+    }
+
+    match config_manager.add_provider(provider_config.clone()) {
         Ok(_) => {
             config_manager.save_config()?;
             branding::print_success(&format!("Added provider '{}' with model '{}'", provider_type, model));
+
+            // Catch a mistyped model now rather than at request time; skipped if the provider can't be reached
+            let listing = tokio::time::timeout(MODEL_CHECK_TIMEOUT, fetch_models(&provider_config)).await;
+            if let Ok(Ok(models)) = listing
+                && !models.is_empty()
+                && !model_listed(&models, &model)
+            {
+                branding::print_warning(&format!(
+                    "Model '{}' is not served by {}; see `qitops llm models --provider {}`",
+                    model, provider_type, provider_type
+                ));
+            }
             Ok(())
         },
         Err(e) => {
@@ -219,6 +484,141 @@ async fn add_provider(provider_type: &str, api_key: Option<String>, api_base: Op
     }
 }
 
+/// How long `llm add` waits for a provider's model list
+const MODEL_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Fetch a provider's model list
+async fn fetch_models(config: &ProviderConfig) -> Result<Vec<String>> {
+    let client = client_for(config)
+        .ok_or_else(|| anyhow!("Unknown provider type: {}", config.provider_type))??;
+    client.list_models().await
+}
+
+/// List the models of providers and check that their configured models exist
+async fn list_models(provider: Option<&str>) -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let config = config_manager.get_config();
+
+    let providers: Vec<&ProviderConfig> = match provider {
+        Some(name) => vec![
+            config.providers.iter()
+                .find(|p| p.provider_type == name)
+                .ok_or_else(|| anyhow!("Provider '{}' not found in configuration", name))?
+        ],
+        None => config.providers.iter().collect(),
+    };
+
+    let mut missing = Vec::new();
+    for provider_config in providers {
+        let name = &provider_config.provider_type;
+        branding::print_section(&format!("{} models", name));
+
+        let progress = ProgressIndicator::new(&format!("Fetching {} models...", name));
+        let result = fetch_models(provider_config).await;
+        progress.finish();
+
+        let models = match result {
+            Ok(models) => models,
+            // Listing all providers shouldn't stop at one that is unreachable
+            Err(e) if provider.is_none() => {
+                branding::print_warning(&format!("Could not list {} models: {}", name, e));
+                continue;
+            },
+            Err(e) => return Err(e),
+        };
+
+        for model in &models {
+            if model_listed(std::slice::from_ref(model), &provider_config.default_model) {
+                println!("- {} {}", model.bright_cyan(), "(configured)".bright_green());
+            } else {
+                println!("- {}", model);
+            }
+        }
+        if models.is_empty() {
+            branding::print_info("No models found");
+        }
+
+        if model_listed(&models, &provider_config.default_model) {
+            branding::print_success(&format!("Configured model '{}' is available", provider_config.default_model));
+        } else {
+            branding::print_error(&format!("Configured model '{}' is not served by {}", provider_config.default_model, name));
+            missing.push(format!("{}/{}", name, provider_config.default_model));
+        }
+    }
+
+    if !missing.is_empty() {
+        return Err(anyhow!("Configured models not available: {}", missing.join(", ")));
+    }
+
+    Ok(())
+}
+
+/// Probe providers and print what is wrong with them and how to fix it
+async fn doctor(provider: Option<&str>, completion: bool, json: bool) -> Result<()> {
+    let config = ConfigManager::new()?.get_config().clone();
+    let providers: Vec<&ProviderConfig> = match provider {
+        Some(name) => vec![
+            config.providers.iter()
+                .find(|p| p.provider_type == name)
+                .ok_or_else(|| anyhow!("Provider '{}' not found in configuration", name))?
+        ],
+        None => config.providers.iter().collect(),
+    };
+    if providers.is_empty() {
+        return Err(anyhow!("No LLM providers are configured; add one with `qitops llm add`"));
+    }
+
+    // Probe concurrently, so the run takes as long as the slowest provider
+    let progress = (!json).then(|| ProgressIndicator::new(&format!("Probing {} provider(s)...", providers.len())));
+    let probes = futures_util::future::join_all(
+        providers.iter().map(|provider| probe::probe_provider(provider, completion))
+    ).await;
+    if let Some(progress) = progress {
+        progress.finish();
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&probes)?);
+    } else {
+        for probe in &probes {
+            let default_marker = if probe.provider == config.default_provider { " (default)" } else { "" };
+            branding::print_section(&format!("{}{}: {}", probe.provider, default_marker, probe.model));
+            for check in &probe.checks {
+                let line = format!("{}: {}", check.check, check.detail);
+                match check.status {
+                    CheckStatus::Ok => branding::print_success(&line),
+                    CheckStatus::Warning => branding::print_warning(&line),
+                    CheckStatus::Failed => branding::print_error(&line),
+                }
+                if let Some(hint) = &check.hint {
+                    branding::print_hint(hint);
+                }
+            }
+            if let Some(latency) = probe.latency_ms {
+                branding::print_info(&format!("Latency: {} ms", latency));
+            }
+        }
+        println!();
+    }
+
+    let unhealthy: Vec<&str> = probes.iter().filter(|probe| !probe.healthy()).map(|probe| probe.provider.as_str()).collect();
+    if !unhealthy.is_empty() {
+        return Err(anyhow!("{} of {} provider(s) have problems: {}", unhealthy.len(), probes.len(), unhealthy.join(", ")));
+    }
+    if !json {
+        branding::print_success(&format!("All {} provider(s) are healthy", probes.len()));
+    }
+
+    Ok(())
+}
+
+/// Parse a key=value option
+fn parse_key_value(s: &str) -> Result<(String, String)> {
+    let (key, value) = s.split_once('=')
+        .ok_or_else(|| anyhow!("Invalid option '{}': expected key=value", s))?;
+    Ok((key.trim().to_string(), value.trim().to_string()))
+}
+
 /// Remove an LLM provider
 async fn remove_provider(provider_type: &str) -> Result<()> {
     let mut config_manager = ConfigManager::new()?;
@@ -270,6 +670,59 @@ async fn set_task_provider(task: &str, provider_type: &str) -> Result<()> {
     }
 }
 
+/// Configure retries and the provider fallback chain
+async fn set_fallback(chain: Option<Vec<String>>, retries: Option<u32>, backoff_ms: Option<u64>, max_backoff_ms: Option<u64>, timeout: Option<u64>) -> Result<()> {
+    let mut config_manager = ConfigManager::new()?;
+    let mut fallback = config_manager.get_config().fallback.clone();
+
+    if let Some(chain) = chain {
+        fallback.chain = chain;
+    }
+    if let Some(retries) = retries {
+        fallback.max_retries = retries;
+    }
+    if let Some(backoff_ms) = backoff_ms {
+        fallback.initial_backoff_ms = backoff_ms;
+    }
+    if let Some(max_backoff_ms) = max_backoff_ms {
+        fallback.max_backoff_ms = max_backoff_ms;
+    }
+    if let Some(timeout) = timeout {
+        fallback.timeout_secs = timeout;
+    }
+
+    match config_manager.set_fallback(fallback.clone()) {
+        Ok(_) => {
+            config_manager.save_config()?;
+            branding::print_success("Fallback configuration updated");
+            print_fallback(config_manager.get_config());
+            Ok(())
+        },
+        Err(e) => {
+            branding::print_error(&format!("Failed to set fallback: {}", e));
+            Err(e)
+        }
+    }
+}
+
+/// Print the fallback chain and retry settings
+fn print_fallback(config: &RouterConfig) {
+    let chain = if config.fallback.chain.is_empty() {
+        "all other providers in configuration order".to_string()
+    } else {
+        config.fallback.chain.join(" → ")
+    };
+
+    println!("Fallback chain: {} → {}", config.default_provider.bright_cyan(), chain);
+    println!(
+        "Retries: {} per provider, backoff {}ms doubling up to {}ms, timeout {}s",
+        config.fallback.max_retries,
+        config.fallback.initial_backoff_ms,
+        config.fallback.max_backoff_ms,
+        config.fallback.timeout_secs
+    );
+}
+
 /// Test an LLM provider
 async fn test_provider(provider_type: Option<&str>, prompt: &str, no_cache: bool) -> Result<()> {
     let config_manager = ConfigManager::new()?;
@@ -341,6 +794,9 @@ async fn test_provider(provider_type: Option<&str>, prompt: &str, no_cache: bool
                     if let Some(tokens) = response.tokens_used {
                         println!("Tokens used: {}", tokens.to_string().bright_yellow());
                     }
+                    if let Some(cost) = response.metadata.get("cost_usd").and_then(|cost| cost.as_f64()) {
+                        println!("Estimated cost: {}", format!("${:.4}", cost).bright_yellow());
+                    }
 
                     // Show cache status
                     if response.cached {
@@ -384,7 +840,7 @@ async fn clear_cache() -> Result<()> {
         return Ok(());
     }
 
-    match crate::llm::cache::ResponseCache::new(config.cache.ttl_seconds, config.cache.use_disk) {
+    match ResponseCache::from_config(&config.cache) {
         Ok(mut cache) => {
             progress.update_message("Clearing cache...");
             match cache.clear() {
@@ -409,7 +865,7 @@ async fn clear_cache() -> Result<()> {
 }
 
 /// Configure the LLM cache
-async fn configure_cache(enabled: Option<bool>, ttl: Option<u64>, disk: Option<bool>) -> Result<()> {
+async fn configure_cache(enabled: Option<bool>, ttl: Option<u64>, disk: Option<bool>, max_size_mb: Option<u64>) -> Result<()> {
     branding::print_command_header("Configuring LLM Cache");
 
     let mut config_manager = ConfigManager::new()?;
@@ -430,6 +886,10 @@ async fn configure_cache(enabled: Option<bool>, ttl: Option<u64>, disk: Option<b
         config.cache.use_disk = disk;
     }
 
+    if let Some(max_size_mb) = max_size_mb {
+        config.cache.max_size_mb = max_size_mb;
+    }
+
     // Update the configuration and save it
     *config_manager.get_config_mut() = config.clone();
     config_manager.save_config()?;
@@ -440,6 +900,7 @@ async fn configure_cache(enabled: Option<bool>, ttl: Option<u64>, disk: Option<b
     println!("Cache enabled: {}", if config.cache.enabled { "yes".bright_green() } else { "no".bright_red() });
     println!("Cache TTL: {} seconds", config.cache.ttl_seconds.to_string().bright_yellow());
     println!("Disk cache: {}", if config.cache.use_disk { "yes".bright_green() } else { "no".bright_red() });
+    println!("Maximum size: {}", format_max_size(config.cache.max_size_mb).bright_yellow());
 
     Ok(())
 }
@@ -455,10 +916,11 @@ async fn show_cache_status() -> Result<()> {
     println!("Cache enabled: {}", if config.cache.enabled { "yes".bright_green() } else { "no".bright_red() });
     println!("Cache TTL: {} seconds", config.cache.ttl_seconds.to_string().bright_yellow());
     println!("Disk cache: {}", if config.cache.use_disk { "yes".bright_green() } else { "no".bright_red() });
+    println!("Maximum size: {}", format_max_size(config.cache.max_size_mb).bright_yellow());
 
     // Try to initialize the cache to check if it's working
     if config.cache.enabled {
-        match crate::llm::cache::ResponseCache::new(config.cache.ttl_seconds, config.cache.use_disk) {
+        match ResponseCache::from_config(&config.cache) {
             Ok(_) => {
                 println!("\nCache status: {}", "working".bright_green());
             },
@@ -470,4 +932,202 @@ async fn show_cache_status() -> Result<()> {
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+/// Show LLM cache statistics
+async fn show_cache_stats() -> Result<()> {
+    branding::print_command_header("LLM Cache Statistics");
+
+    let config_manager = ConfigManager::new()?;
+    let config = config_manager.get_config().clone();
+
+    if !config.cache.use_disk {
+        branding::print_info("Disk cache is disabled, so responses are only cached for the lifetime of a command");
+        return Ok(());
+    }
+
+    let cache = ResponseCache::from_config(&config.cache)?;
+    let stats = cache.stats()?;
+
+    if let Some(path) = cache.path() {
+        println!("Database: {}", path.display());
+    }
+    println!("Entries: {} ({} expired)", stats.entries.to_string().bright_yellow(), stats.expired);
+    println!(
+        "Size: {} of {}",
+        format!("{:.1} MB", stats.size_bytes as f64 / (1024.0 * 1024.0)).bright_yellow(),
+        format_max_size(config.cache.max_size_mb)
+    );
+    println!("Hits: {}", stats.hits.to_string().bright_yellow());
+    if let (Some(oldest), Some(newest)) = (stats.oldest, stats.newest) {
+        println!("Oldest entry: {}", format_timestamp(oldest));
+        println!("Newest entry: {}", format_timestamp(newest));
+    }
+
+    Ok(())
+}
+
+/// Describe the maximum cache size
+fn format_max_size(max_size_mb: u64) -> String {
+    if max_size_mb == 0 { "unlimited".to_string() } else { format!("{} MB", max_size_mb) }
+}
+
+/// Show LLM usage over a period by command and by provider
+fn show_usage(since: &str, json: bool) -> Result<()> {
+    let ledger = UsageLedger::open_default()?;
+    let first_day = cost::since_day(since)?;
+    let entries = ledger.since(&first_day)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    branding::print_section(&format!("LLM Usage since {}", first_day));
+
+    if entries.is_empty() {
+        branding::print_info(&format!("No usage recorded since {} in {}", first_day, ledger.path().display()));
+    } else {
+        let by_command = cost::totals_by(&entries, |e| match e.command.as_str() {
+            "" => "(unknown)".to_string(),
+            command => command.to_string(),
+        });
+        print_usage_table("Command", &by_command);
+        println!();
+        print_usage_table("Provider", &cost::totals_by(&entries, |e| format!("{}/{}", e.provider, e.model)));
+    }
+
+    let total = entries.iter().fold(0.0, |sum, e| sum + e.cost_usd);
+    let today_spend = ledger.spent_on(&today())?;
+    println!();
+    println!("Today: {}", format!("${:.4}", today_spend).bright_yellow());
+    println!("Since {}: {}", first_day, format!("${:.4}", total).bright_yellow());
+
+    let budget = QitOpsConfigManager::new()?.get_config().budget.clone();
+    match budget.per_day_usd {
+        Some(limit) => println!("Daily budget: ${:.2} (${:.4} left)", limit, (limit - today_spend).max(0.0)),
+        None => println!("Daily budget: none"),
+    }
+    match budget.per_command_usd {
+        Some(limit) => println!("Per-command budget: ${:.2}", limit),
+        None => println!("Per-command budget: none"),
+    }
+
+    Ok(())
+}
+
+/// Print usage totals as a table
+fn print_usage_table(heading: &str, groups: &[(String, cost::UsageTotals)]) {
+    println!("{:<32} {:>9} {:>12} {:>12} {:>10}", heading.bright_cyan(), "Requests", "Tokens in", "Tokens out", "Cost");
+    for (name, totals) in groups {
+        println!(
+            "{:<32} {:>9} {:>12} {:>12} {:>10}",
+            name, totals.requests, totals.input_tokens, totals.output_tokens, format!("${:.4}", totals.cost_usd)
+        );
+    }
+}
+
+/// Benchmark providers and recommend a default
+async fn benchmark_providers(providers: Option<&str>, runs: usize, json: bool) -> Result<()> {
+    if runs == 0 {
+        return Err(anyhow!("--runs must be at least 1"));
+    }
+
+    let config = ConfigManager::new()?.get_config().clone();
+    let names: Vec<String> = match providers {
+        Some(providers) => providers.split(',').map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect(),
+        None => config.providers.iter().map(|p| p.provider_type.clone()).collect(),
+    };
+    if let Some(unknown) = names.iter().find(|name| !config.providers.iter().any(|p| p.provider_type == **name)) {
+        return Err(anyhow!("Provider '{}' not found in configuration", unknown));
+    }
+
+    let mut benchmarks = Vec::new();
+    for name in &names {
+        // Benchmark each provider on its own, without fallback or ensemble answers
+        let mut provider_config = config.clone();
+        provider_config.providers.retain(|p| p.provider_type == *name);
+        provider_config.default_provider = name.clone();
+        provider_config.task_providers.clear();
+        provider_config.ensemble.clear();
+
+        let progress = (!json).then(|| ProgressIndicator::new(&format!("Benchmarking {} ({} prompts)...", name, benchmark::SUITE.len() * runs)));
+        let result = match LlmRouter::new(provider_config).await {
+            Ok(router) => Ok(benchmark::run(&router, runs).await),
+            Err(e) => Err(e),
+        };
+        if let Some(progress) = progress {
+            progress.finish();
+        }
+
+        match result {
+            Ok(benchmark) => benchmarks.push(benchmark),
+            Err(e) if !json => branding::print_warning(&format!("Skipping {}: {}", name, e)),
+            Err(_) => {},
+        }
+    }
+
+    // Best quality first, then fastest
+    benchmarks.sort_by(|a, b| {
+        b.quality().total_cmp(&a.quality())
+            .then(a.mean_latency_ms().unwrap_or(u64::MAX).cmp(&b.mean_latency_ms().unwrap_or(u64::MAX)))
+    });
+
+    if json {
+        let report: Vec<_> = benchmarks.iter().map(|b| serde_json::json!({
+            "provider": b.provider,
+            "model": b.model,
+            "quality": b.quality(),
+            "mean_latency_ms": b.mean_latency_ms(),
+            "tokens_per_second": b.tokens_per_second(),
+            "cost_usd": b.cost_usd(),
+            "failures": b.failures(),
+            "results": b.results,
+        })).collect();
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    if benchmarks.is_empty() {
+        return Err(anyhow!("No providers could be benchmarked"));
+    }
+
+    branding::print_section("Benchmark Results");
+    println!("{:<14} {:<24} {:>8} {:>10} {:>9} {:>9} {:>7}", "Provider", "Model", "Quality", "Latency", "Tokens/s", "Cost", "Failed");
+    for b in &benchmarks {
+        println!(
+            "{:<14} {:<24} {:>8.0} {:>10} {:>9} {:>9} {:>7}",
+            b.provider,
+            b.model,
+            b.quality(),
+            b.mean_latency_ms().map_or("-".to_string(), |ms| format!("{}ms", ms)),
+            b.tokens_per_second().map_or("-".to_string(), |tps| format!("{:.1}", tps)),
+            format!("${:.4}", b.cost_usd()),
+            b.failures(),
+        );
+    }
+    println!();
+
+    print_recommendation(&benchmarks);
+    Ok(())
+}
+
+/// Print the best, fastest and cheapest providers of a benchmark sorted by quality
+fn print_recommendation(benchmarks: &[ProviderBenchmark]) {
+    let working: Vec<&ProviderBenchmark> = benchmarks.iter()
+        .filter(|b| b.failures() < b.results.len())
+        .collect();
+    let Some(best) = working.first() else {
+        branding::print_warning("Every request failed; check the providers with `qitops llm test`");
+        return;
+    };
+
+    println!("Best quality: {} ({:.0}/100)", best.provider.bright_cyan(), best.quality());
+    if let Some(fastest) = working.iter().filter_map(|b| Some((b.mean_latency_ms()?, b))).min_by_key(|(ms, _)| *ms) {
+        println!("Fastest: {} ({}ms)", fastest.1.provider.bright_cyan(), fastest.0);
+    }
+    if let Some(cheapest) = working.iter().min_by(|a, b| a.cost_usd().total_cmp(&b.cost_usd())) {
+        println!("Cheapest: {} (${:.4})", cheapest.provider.bright_cyan(), cheapest.cost_usd());
+    }
+
+    branding::print_info(&format!("Make {} the default with: qitops llm default --provider {}", best.provider, best.provider));
+}
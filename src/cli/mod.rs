@@ -1,9 +1,36 @@
 // CLI interface
 pub mod commands;
 pub mod llm;
+#[cfg(feature = "github")]
 pub mod github;
 pub mod source;
 pub mod persona;
+#[cfg(feature = "bot")]
 pub mod bot;
+pub mod schema;
 pub mod branding;
 pub mod progress;
+pub mod annotate;
+pub mod watch;
+pub mod daemon;
+pub mod audit;
+pub mod tcm;
+pub mod jira;
+pub mod publish;
+pub mod notify;
+pub mod prompt;
+pub mod verify;
+pub mod history;
+#[cfg(feature = "monitoring")]
+pub mod telemetry;
+pub mod diff_runs;
+pub mod bench;
+pub mod summary;
+pub mod suggest;
+pub mod costs;
+pub mod triage;
+pub mod digest;
+pub mod capabilities;
+pub mod findings;
+pub mod approvals;
+pub mod context;
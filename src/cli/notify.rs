@@ -0,0 +1,166 @@
+use anyhow::{Result, anyhow};
+use clap::Subcommand;
+use std::path::Path;
+
+use crate::cli::branding;
+use crate::integrations::publish::Report;
+use crate::notification::{self, ChannelKind, EmailConfig, Notification, NotifyConfigManager, SmtpSecurity};
+
+/// Notification CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct NotifyArgs {
+    /// Notification subcommand
+    #[clap(subcommand)]
+    pub command: NotifyCommand,
+}
+
+/// Notification subcommands
+#[derive(Debug, Subcommand)]
+pub enum NotifyCommand {
+    /// Configure a notification channel
+    #[clap(name = "config")]
+    Config {
+        /// Channel to configure
+        #[clap(subcommand)]
+        channel: NotifyChannelConfig,
+    },
+
+    /// Show notification configuration
+    #[clap(name = "status")]
+    Status,
+
+    /// Send a test notification
+    #[clap(name = "test")]
+    Test {
+        /// Channel to test
+        #[clap(short, long, default_value = "email")]
+        channel: String,
+    },
+
+    /// Send a markdown or HTML report
+    #[clap(name = "send")]
+    Send {
+        /// Report file (.md or .html)
+        #[clap(short, long)]
+        file: String,
+
+        /// Subject (defaults to the report's first heading)
+        #[clap(short, long)]
+        subject: Option<String>,
+
+        /// Channel to send on
+        #[clap(short, long, default_value = "email")]
+        channel: String,
+    },
+}
+
+/// Notification channel configuration
+#[derive(Debug, Subcommand)]
+pub enum NotifyChannelConfig {
+    /// Configure email delivery over SMTP
+    #[clap(name = "email")]
+    Email {
+        /// SMTP server host
+        #[clap(long)]
+        host: Option<String>,
+
+        /// SMTP server port (defaults to 587 for starttls, 465 for tls, 25 for none)
+        #[clap(long)]
+        port: Option<u16>,
+
+        /// Connection security (starttls, tls, none)
+        #[clap(long)]
+        security: Option<SmtpSecurity>,
+
+        /// SMTP username
+        #[clap(long)]
+        username: Option<String>,
+
+        /// SMTP password (or set QITOPS_SMTP_PASSWORD)
+        #[clap(long)]
+        password: Option<String>,
+
+        /// Sender address, e.g. "QitOps <qitops@example.com>"
+        #[clap(long)]
+        from: Option<String>,
+
+        /// Recipients (comma-separated)
+        #[clap(long)]
+        to: Option<String>,
+    },
+}
+
+/// Handle notification commands
+pub async fn handle_notify_command(args: &NotifyArgs) -> Result<()> {
+    match &args.command {
+        NotifyCommand::Config { channel: NotifyChannelConfig::Email { host, port, security, username, password, from, to } } => {
+            let mut config_manager = NotifyConfigManager::new()?;
+            let existing = config_manager.get_config().email.clone();
+
+            let to = match to {
+                Some(to) => to.split(',').map(|address| address.trim().to_string()).filter(|a| !a.is_empty()).collect(),
+                None => existing.as_ref().map(|c| c.to.clone()).unwrap_or_default(),
+            };
+            let config = EmailConfig {
+                host: host.clone()
+                    .or_else(|| existing.as_ref().map(|c| c.host.clone()))
+                    .ok_or_else(|| anyhow!("--host is required"))?,
+                port: port.or_else(|| existing.as_ref().and_then(|c| c.port)),
+                security: security.or_else(|| existing.as_ref().map(|c| c.security)).unwrap_or_default(),
+                username: username.clone().or_else(|| existing.as_ref().and_then(|c| c.username.clone())),
+                password: password.clone().or_else(|| existing.as_ref().and_then(|c| c.password.clone())),
+                from: from.clone()
+                    .or_else(|| existing.as_ref().map(|c| c.from.clone()))
+                    .ok_or_else(|| anyhow!("--from is required"))?,
+                to,
+            };
+
+            // Validate the addresses before saving
+            #[cfg(feature = "email")]
+            notification::EmailChannel::new(config.clone())?;
+            config_manager.set_email(config)?;
+            branding::print_success("Email delivery configured");
+            Ok(())
+        },
+        NotifyCommand::Status => show_notify_status(),
+        NotifyCommand::Test { channel } => {
+            let notification = Notification {
+                subject: "QitOps Agent test notification".to_string(),
+                text: "This is a test notification from QitOps Agent.".to_string(),
+                html: None,
+                attachment_name: String::new(),
+            };
+            let recipients = notification::send(channel.parse()?, &notification).await?;
+            branding::print_success(&format!("Test notification sent to {}", recipients));
+            Ok(())
+        },
+        NotifyCommand::Send { file, subject, channel } => {
+            let report = Report::from_file(Path::new(file), subject.as_deref())?;
+            send_report(channel.parse()?, &report).await
+        },
+    }
+}
+
+/// Show notification configuration status
+fn show_notify_status() -> Result<()> {
+    let config_manager = NotifyConfigManager::new()?;
+
+    println!("Notification Configuration:");
+    match &config_manager.get_config().email {
+        Some(email) => {
+            branding::print_success(&format!("Email: {}:{} ({})", email.host, email.port(), email.security));
+            println!("  From: {}", email.from);
+            println!("  To: {}", email.to.join(", "));
+        },
+        None => branding::print_warning("Email: Not configured"),
+    }
+
+    Ok(())
+}
+
+/// Send a report and print who it went to
+pub async fn send_report(channel: ChannelKind, report: &Report) -> Result<()> {
+    let recipients = notification::send(channel, &Notification::from_report(report)).await?;
+    branding::print_success(&format!("Sent '{}' by {} to {}", report.title, channel, recipients));
+    Ok(())
+}
@@ -1,8 +1,12 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::error::QitOpsError;
 
 // Define the Persona and PersonaManager here
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Persona {
     pub id: String,
     pub name: String,
@@ -42,52 +46,117 @@ impl Persona {
     }
 }
 
+/// Personas as stored in `personas.json`
+#[derive(Debug, Serialize, Deserialize)]
+struct PersonaFile {
+    personas: std::collections::HashMap<String, Persona>,
+}
+
 pub struct PersonaManager {
     personas: std::collections::HashMap<String, Persona>,
+    config_path: PathBuf,
 }
 
 impl PersonaManager {
     pub fn new() -> Result<Self> {
+        let config_path = crate::config::config_dir()?.join("personas.json");
+        if config_path.exists() {
+            let content = std::fs::read_to_string(&config_path)
+                .map_err(|e| anyhow!("Failed to read {}: {}", config_path.display(), e))?;
+            let file: PersonaFile = serde_json::from_str(&content)
+                .map_err(|e| anyhow!("Failed to parse {}: {}", config_path.display(), e))?;
+            let mut manager = Self {
+                personas: file.personas,
+                config_path,
+            };
+            manager.load_from_environment();
+            return Ok(manager);
+        }
+
         let mut manager = Self {
             personas: std::collections::HashMap::new(),
+            config_path,
         };
 
         // Add default personas
-        manager.add_persona(Persona::new(
+        manager.insert(Persona::new(
             "qa-engineer".to_string(),
             "QA Engineer".to_string(),
             vec!["testing".to_string(), "quality".to_string(), "coverage".to_string()],
             "Focus on comprehensive test coverage and edge cases.".to_string(),
             None,
-        ))?;
+        ));
 
-        manager.add_persona(Persona::new(
+        manager.insert(Persona::new(
             "security-analyst".to_string(),
             "Security Analyst".to_string(),
             vec!["security".to_string(), "vulnerabilities".to_string(), "compliance".to_string()],
             "Focus on security vulnerabilities and compliance issues.".to_string(),
             None,
-        ))?;
+        ));
 
-        manager.add_persona(Persona::new(
+        manager.insert(Persona::new(
             "performance-engineer".to_string(),
             "Performance Engineer".to_string(),
             vec!["performance".to_string(), "optimization".to_string(), "scalability".to_string()],
             "Focus on performance implications and bottlenecks.".to_string(),
             None,
-        ))?;
+        ));
 
+        manager.load_from_environment();
         Ok(manager)
     }
 
+    /// Add the personas of `QITOPS_PERSONAS`, formatted `id:name:focus1;focus2:description[:prompt],...`
+    fn load_from_environment(&mut self) {
+        let Ok(personas) = std::env::var("QITOPS_PERSONAS") else {
+            return;
+        };
+        for entry in personas.split(',').filter(|entry| !entry.trim().is_empty()) {
+            let parts: Vec<&str> = entry.splitn(5, ':').map(str::trim).collect();
+            if parts.len() < 4 {
+                tracing::warn!("Invalid persona format in QITOPS_PERSONAS: {}", entry);
+                continue;
+            }
+            let focus_areas = parts[2].split(';').map(|area| area.trim().to_string()).collect();
+            let prompt_template = parts.get(4).map(|prompt| prompt.to_string());
+            self.insert(Persona::new(parts[0].to_string(), parts[1].to_string(), focus_areas, parts[3].to_string(), prompt_template));
+        }
+    }
+
     pub fn add_persona(&mut self, persona: Persona) -> Result<()> {
+        self.insert(persona);
+        self.save()
+    }
+
+    fn insert(&mut self, persona: Persona) {
         self.personas.insert(persona.id.clone(), persona);
-        Ok(())
     }
 
     pub fn remove_persona(&mut self, id: &str) -> Result<()> {
-        self.personas.remove(id);
-        Ok(())
+        if self.personas.remove(id).is_none() {
+            return Err(QitOpsError::Source(self.unknown(&[id.to_string()]).join("; ")).into());
+        }
+        self.save()
+    }
+
+    /// Check that every persona ID is defined, suggesting close matches for the ones that aren't
+    pub fn validate_ids(&self, ids: &[String]) -> Result<()> {
+        let unknown = self.unknown(ids);
+        if unknown.is_empty() {
+            return Ok(());
+        }
+        Err(QitOpsError::Source(unknown.join("; ")).into())
+    }
+
+    fn unknown(&self, ids: &[String]) -> Vec<String> {
+        crate::cli::suggest::unknown_ids("persona", ids, self.personas.keys().map(String::as_str))
+    }
+
+    fn save(&self) -> Result<()> {
+        let file = PersonaFile { personas: self.personas.clone() };
+        std::fs::write(&self.config_path, serde_json::to_string_pretty(&file)?)
+            .map_err(|e| anyhow!("Failed to write {}: {}", self.config_path.display(), e))
     }
 
     pub fn get_persona(&self, id: &str) -> Option<&Persona> {
@@ -103,8 +172,10 @@ impl PersonaManager {
 
         for persona_id in personas {
             if let Some(persona) = self.get_persona(persona_id) {
+                let persona_prompt = persona.get_prompt();
+                crate::debug::record_piece("persona", persona_id, &persona_prompt);
                 prompt.push_str(&format!("# Persona: {}\n\n", persona.name));
-                prompt.push_str(&persona.get_prompt());
+                prompt.push_str(&persona_prompt);
                 prompt.push_str("\n\n");
             }
         }
@@ -250,7 +321,7 @@ async fn show_persona(id: &str) -> Result<()> {
     let persona_manager = PersonaManager::new()?;
 
     let persona = persona_manager.get_persona(id)
-        .ok_or_else(|| anyhow::anyhow!("Persona not found: {}", id))?;
+        .ok_or_else(|| QitOpsError::Source(format!("Persona not found: {}", id)))?;
 
     println!("Persona: {}", persona.id);
     println!("Name: {}", persona.name);
@@ -24,6 +24,12 @@ impl ProgressIndicator {
         self.progress_bar.set_message(message.to_string());
     }
     
+    /// A callback that updates the message, for work that reports its own progress
+    pub fn reporter(&self) -> impl Fn(&str) + Send + Sync + 'static {
+        let progress_bar = self.progress_bar.clone();
+        move |message| progress_bar.set_message(message.to_string())
+    }
+    
     pub fn finish_with_message(&self, message: &str) {
         self.progress_bar.finish_with_message(message.to_string());
     }
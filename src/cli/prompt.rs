@@ -0,0 +1,143 @@
+use anyhow::Result;
+use clap::Subcommand;
+use std::fs;
+use std::process::Command;
+
+use crate::cli::branding;
+use crate::prompt::templates::{self, BUILTIN_TEMPLATES};
+
+/// Prompt CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct PromptArgs {
+    /// Prompt subcommand
+    #[clap(subcommand)]
+    pub command: PromptCommand,
+}
+
+/// Prompt subcommands
+#[derive(Debug, Subcommand)]
+pub enum PromptCommand {
+    /// List prompt templates
+    #[clap(name = "list")]
+    List,
+
+    /// Show a prompt template
+    #[clap(name = "show")]
+    Show {
+        /// Template name
+        name: String,
+
+        /// Show the built-in template even if it is overridden
+        #[clap(long)]
+        default: bool,
+    },
+
+    /// Edit a prompt template, creating an override from the built-in one
+    #[clap(name = "edit")]
+    Edit {
+        /// Template name
+        name: String,
+
+        /// Edit the override in `.qitops/prompts/` of the current project instead of the user config
+        #[clap(long)]
+        project: bool,
+    },
+}
+
+/// Handle prompt commands
+pub async fn handle_prompt_command(args: &PromptArgs) -> Result<()> {
+    match &args.command {
+        PromptCommand::List => {
+            list_templates()
+        },
+        PromptCommand::Show { name, default } => {
+            show_template(name, *default)
+        },
+        PromptCommand::Edit { name, project } => {
+            edit_template(name, *project)
+        },
+    }
+}
+
+/// List prompt templates
+fn list_templates() -> Result<()> {
+    println!("Prompt templates:");
+    for template in BUILTIN_TEMPLATES {
+        println!("  Name: {}", template.name);
+        println!("    Description: {}", template.description);
+        println!("    Variables: {}", template.variables.join(", "));
+        match templates::find_template_override(template.name)? {
+            Some(path) => println!("    Source: {}", path.display()),
+            None => println!("    Source: built-in"),
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Show a prompt template
+fn show_template(name: &str, default: bool) -> Result<()> {
+    let builtin = templates::builtin_template(name)?;
+
+    let (source, template) = match templates::find_template_override(name)? {
+        Some(path) if !default => (path.display().to_string(), templates::template_source(name)?),
+        _ => ("built-in".to_string(), builtin.template.to_string()),
+    };
+
+    println!("Template: {}", builtin.name);
+    println!("Source: {}", source);
+    println!("Variables: {}", builtin.variables.join(", "));
+    println!();
+    println!("{}", template);
+
+    Ok(())
+}
+
+/// Edit a prompt template in the user's editor
+fn edit_template(name: &str, project: bool) -> Result<()> {
+    let builtin = templates::builtin_template(name)?;
+
+    let path = match templates::find_template_override(name)? {
+        Some(path) if !project || path.starts_with(".qitops") => path,
+        _ => {
+            let paths = templates::template_override_paths(name)?;
+            if project { paths[0].clone() } else { paths[1].clone() }
+        },
+    };
+
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, builtin.template)?;
+        branding::print_info(&format!("Created {} from the built-in template", path.display()));
+    }
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| if cfg!(windows) { "notepad".to_string() } else { "vi".to_string() });
+    let mut parts = shlex::split(&editor).unwrap_or_else(|| vec![editor.clone()]);
+    if parts.is_empty() {
+        return Err(anyhow::anyhow!("No editor configured; set VISUAL or EDITOR"));
+    }
+    let program = parts.remove(0);
+
+    let status = Command::new(&program)
+        .args(&parts)
+        .arg(&path)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Failed to start editor {}: {}", program, e))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("Editor {} exited with {}", program, status));
+    }
+
+    // Catch syntax errors now rather than on the next run
+    let template = fs::read_to_string(&path)?;
+    templates::validate_template(name, &template)?;
+
+    branding::print_success(&format!("Prompt template '{}' saved to {}", name, path.display()));
+
+    Ok(())
+}
+
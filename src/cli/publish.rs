@@ -0,0 +1,136 @@
+use anyhow::{Result, anyhow};
+use clap::Subcommand;
+use std::path::Path;
+
+use crate::cli::branding;
+use crate::integrations::publish::{self, ConfluenceConfig, PublishConfigManager, PublishOutcome, PublishTarget, Report};
+
+/// Report publishing CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct PublishArgs {
+    /// Publish subcommand
+    #[clap(subcommand)]
+    pub command: PublishCommand,
+}
+
+/// Report publishing subcommands
+#[derive(Debug, Subcommand)]
+pub enum PublishCommand {
+    /// Publish a markdown or HTML report, updating an earlier one with the same title
+    #[clap(name = "report")]
+    Report {
+        /// Report file (.md or .html)
+        #[clap(short, long)]
+        file: String,
+
+        /// Target as confluence:<SPACE>[/<parent page ID>] or git:<branch>[:<dir>]
+        #[clap(short, long)]
+        to: String,
+
+        /// Page title (defaults to the report's first heading)
+        #[clap(long)]
+        title: Option<String>,
+    },
+
+    /// Configure report publishing
+    #[clap(name = "config")]
+    Config {
+        /// Confluence base URL, e.g. https://acme.atlassian.net/wiki
+        #[clap(long)]
+        confluence_url: Option<String>,
+
+        /// Confluence account email (Confluence Cloud); leave unset to use a personal access token
+        #[clap(long)]
+        email: Option<String>,
+
+        /// Confluence API token or personal access token
+        #[clap(long)]
+        api_token: Option<String>,
+
+        /// Git remote report branches are pushed to
+        #[clap(long)]
+        git_remote: Option<String>,
+
+        /// HTTP(S) proxy URL for Confluence (default: HTTPS_PROXY/HTTP_PROXY environment variables)
+        #[clap(long)]
+        proxy: Option<String>,
+    },
+
+    /// Show report publishing configuration
+    #[clap(name = "status")]
+    Status,
+}
+
+/// Handle publish commands
+pub async fn handle_publish_command(args: &PublishArgs) -> Result<()> {
+    match &args.command {
+        PublishCommand::Report { file, to, title } => {
+            let target: PublishTarget = to.parse()?;
+            let report = Report::from_file(Path::new(file), title.as_deref())?;
+            publish_report(&target, &report).await
+        },
+        PublishCommand::Config { confluence_url, email, api_token, git_remote, proxy } => {
+            let mut config_manager = PublishConfigManager::new()?;
+
+            if confluence_url.is_some() || email.is_some() || api_token.is_some() {
+                let existing = config_manager.get_config().confluence.clone();
+                let url = confluence_url.clone()
+                    .or_else(|| existing.as_ref().map(|c| c.url.clone()))
+                    .ok_or_else(|| anyhow!("--confluence-url is required"))?;
+                let api_token = api_token.clone()
+                    .or_else(|| existing.as_ref().map(|c| c.api_token.clone()))
+                    .ok_or_else(|| anyhow!("--api-token is required"))?;
+                let email = match email {
+                    Some(email) => Some(email.clone()).filter(|email| !email.is_empty()),
+                    None => existing.and_then(|c| c.email),
+                };
+
+                config_manager.set_confluence(ConfluenceConfig { url, email, api_token })?;
+                branding::print_success("Confluence configured");
+            }
+
+            if let Some(remote) = git_remote {
+                config_manager.set_git_remote(remote.clone())?;
+                branding::print_success("Git remote configured");
+            }
+
+            if let Some(proxy) = proxy {
+                crate::proxy::client(Some(proxy))?;
+                config_manager.set_proxy(proxy.clone())?;
+                branding::print_success("Confluence proxy configured");
+            }
+
+            Ok(())
+        },
+        PublishCommand::Status => {
+            let config_manager = PublishConfigManager::new()?;
+            let config = config_manager.get_config();
+
+            println!("Report Publishing Configuration:");
+            match &config.confluence {
+                Some(confluence) => branding::print_success(&format!("Confluence: Configured ({})", confluence.url)),
+                None if std::env::var("CONFLUENCE_URL").is_ok() && std::env::var("CONFLUENCE_API_TOKEN").is_ok() => {
+                    branding::print_success("Confluence: Using CONFLUENCE_* environment variables")
+                },
+                None => branding::print_warning("Confluence: Not configured"),
+            }
+            println!("Git remote: {}", config.git_remote.as_deref().unwrap_or("origin (default)"));
+            if let Some(proxy) = &config.proxy {
+                println!("Proxy: {}", proxy);
+            }
+
+            Ok(())
+        },
+    }
+}
+
+/// Publish a report and print where it went
+pub async fn publish_report(target: &PublishTarget, report: &Report) -> Result<()> {
+    match publish::publish(target, report).await? {
+        PublishOutcome::Created(location) => branding::print_success(&format!("Published '{}' to {}", report.title, location)),
+        PublishOutcome::Updated(location) => branding::print_success(&format!("Updated '{}' at {}", report.title, location)),
+        PublishOutcome::Unchanged(location) => branding::print_info(&format!("'{}' is already up to date at {}", report.title, location)),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use anyhow::Result;
+use clap::Subcommand;
+use std::fs;
+
+use crate::cli::branding;
+use crate::schema::{SchemaRegistry, TestDataSchema};
+
+/// Schema CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct SchemaArgs {
+    /// Schema subcommand
+    #[clap(subcommand)]
+    pub command: SchemaCommand,
+}
+
+/// Schema subcommands
+#[derive(Debug, Subcommand)]
+pub enum SchemaCommand {
+    /// Register a schema
+    #[clap(name = "add")]
+    Add {
+        /// Schema name
+        #[clap(short, long)]
+        name: String,
+
+        /// Schema definition
+        #[clap(long, conflicts_with = "file", required_unless_present = "file")]
+        definition: Option<String>,
+
+        /// Read the schema definition from a file (JSON Schema, SQL DDL, plain text)
+        #[clap(short, long)]
+        file: Option<String>,
+
+        /// Constraint applied to generated data (repeatable)
+        #[clap(short, long = "constraint")]
+        constraints: Vec<String>,
+
+        /// Relationship to another schema (repeatable)
+        #[clap(short, long = "relationship")]
+        relationships: Vec<String>,
+
+        /// Schema description
+        #[clap(short, long)]
+        description: Option<String>,
+    },
+
+    /// List schemas
+    #[clap(name = "list")]
+    List,
+
+    /// Show a schema
+    #[clap(name = "show")]
+    Show {
+        /// Schema name
+        #[clap(short, long)]
+        name: String,
+    },
+
+    /// Remove a schema
+    #[clap(name = "remove")]
+    Remove {
+        /// Schema name
+        #[clap(short, long)]
+        name: String,
+    },
+}
+
+/// Handle schema commands
+pub async fn handle_schema_command(args: &SchemaArgs) -> Result<()> {
+    match &args.command {
+        SchemaCommand::Add { name, definition, file, constraints, relationships, description } => {
+            add_schema(name, definition.clone(), file.clone(), constraints.clone(), relationships.clone(), description.clone()).await
+        },
+        SchemaCommand::List => {
+            list_schemas().await
+        },
+        SchemaCommand::Show { name } => {
+            show_schema(name).await
+        },
+        SchemaCommand::Remove { name } => {
+            remove_schema(name).await
+        },
+    }
+}
+
+/// Register a schema
+async fn add_schema(
+    name: &str,
+    definition: Option<String>,
+    file: Option<String>,
+    constraints: Vec<String>,
+    relationships: Vec<String>,
+    description: Option<String>,
+) -> Result<()> {
+    let mut registry = SchemaRegistry::new()?;
+
+    let definition = match (definition, file) {
+        (Some(definition), _) => definition,
+        (None, Some(file)) => fs::read_to_string(&file)
+            .map_err(|e| anyhow::anyhow!("Failed to read schema file {}: {}", file, e))?,
+        (None, None) => return Err(anyhow::anyhow!("Either --definition or --file is required")),
+    };
+
+    let mut schema = TestDataSchema::new(name.to_string(), definition);
+    schema.constraints = constraints;
+    schema.relationships = relationships;
+    schema.description = description;
+
+    let replaced = registry.get_schema(name).is_some();
+    registry.add_schema(schema)?;
+
+    if replaced {
+        branding::print_success(&format!("Schema '{}' updated successfully", name));
+    } else {
+        branding::print_success(&format!("Schema '{}' added successfully", name));
+    }
+
+    Ok(())
+}
+
+/// List schemas
+async fn list_schemas() -> Result<()> {
+    let registry = SchemaRegistry::new()?;
+
+    let schemas = registry.list_schemas();
+
+    if schemas.is_empty() {
+        println!("No schemas found");
+        return Ok(());
+    }
+
+    println!("Schemas:");
+    for schema in schemas {
+        println!("  Name: {}", schema.name);
+        if let Some(description) = &schema.description {
+            println!("    Description: {}", description);
+        }
+        println!("    Constraints: {}", schema.constraints.len());
+        println!("    Relationships: {}", schema.relationships.len());
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Show a schema
+async fn show_schema(name: &str) -> Result<()> {
+    let registry = SchemaRegistry::new()?;
+
+    let schema = registry.get_schema(name)
+        .ok_or_else(|| anyhow::anyhow!("Schema not found: {}", name))?;
+
+    println!("Schema: {}", schema.name);
+    if let Some(description) = &schema.description {
+        println!("Description: {}", description);
+    }
+
+    if !schema.constraints.is_empty() {
+        println!("Constraints:");
+        for constraint in &schema.constraints {
+            println!("  - {}", constraint);
+        }
+    }
+
+    if !schema.relationships.is_empty() {
+        println!("Relationships:");
+        for relationship in &schema.relationships {
+            println!("  - {}", relationship);
+        }
+    }
+
+    println!();
+    println!("{}", schema.definition);
+
+    Ok(())
+}
+
+/// Remove a schema
+async fn remove_schema(name: &str) -> Result<()> {
+    let mut registry = SchemaRegistry::new()?;
+
+    registry.remove_schema(name)?;
+
+    branding::print_success(&format!("Schema '{}' removed successfully", name));
+
+    Ok(())
+}
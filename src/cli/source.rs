@@ -1,9 +1,12 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use clap::Subcommand;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use crate::error::QitOpsError;
+
 // Define the Source, SourceType, and SourceManager here
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SourceType {
     Requirements,
     Standard,
@@ -31,7 +34,7 @@ impl SourceType {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Source {
     pub id: String,
     pub source_type: SourceType,
@@ -54,25 +57,84 @@ impl Source {
     }
 }
 
+/// Registered sources as stored in `sources.json`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SourceFile {
+    sources: std::collections::HashMap<String, Source>,
+}
+
 pub struct SourceManager {
     sources: std::collections::HashMap<String, Source>,
+    config_path: PathBuf,
 }
 
 impl SourceManager {
     pub fn new() -> Result<Self> {
-        Ok(Self {
-            sources: std::collections::HashMap::new(),
-        })
+        let config_path = crate::config::config_dir()?.join("sources.json");
+        let file: SourceFile = if config_path.exists() {
+            let content = std::fs::read_to_string(&config_path)
+                .map_err(|e| anyhow!("Failed to read {}: {}", config_path.display(), e))?;
+            serde_json::from_str(&content)
+                .map_err(|e| anyhow!("Failed to parse {}: {}", config_path.display(), e))?
+        } else {
+            SourceFile::default()
+        };
+
+        let mut manager = Self {
+            sources: file.sources,
+            config_path,
+        };
+        manager.load_from_environment()?;
+        Ok(manager)
+    }
+
+    /// Add the sources of `QITOPS_SOURCES`, formatted `id:type:path[:description],...`
+    fn load_from_environment(&mut self) -> Result<()> {
+        let Ok(sources) = std::env::var("QITOPS_SOURCES") else {
+            return Ok(());
+        };
+        for entry in sources.split(',').filter(|entry| !entry.trim().is_empty()) {
+            let parts: Vec<&str> = entry.splitn(4, ':').map(str::trim).collect();
+            if parts.len() < 3 {
+                tracing::warn!("Invalid source format in QITOPS_SOURCES: {}", entry);
+                continue;
+            }
+            let description = parts.get(3).map(|description| description.to_string());
+            let source = Source::new(parts[0].to_string(), SourceType::from_str(parts[1])?, PathBuf::from(parts[2]), description);
+            self.sources.insert(source.id.clone(), source);
+        }
+        Ok(())
     }
 
     pub fn add_source(&mut self, source: Source) -> Result<()> {
         self.sources.insert(source.id.clone(), source);
-        Ok(())
+        self.save()
     }
 
     pub fn remove_source(&mut self, id: &str) -> Result<()> {
-        self.sources.remove(id);
-        Ok(())
+        if self.sources.remove(id).is_none() {
+            return Err(QitOpsError::Source(self.unknown(&[id.to_string()]).join("; ")).into());
+        }
+        self.save()
+    }
+
+    /// Check that every source ID is registered, suggesting close matches for the ones that aren't
+    pub fn validate_ids(&self, ids: &[String]) -> Result<()> {
+        let unknown = self.unknown(ids);
+        if unknown.is_empty() {
+            return Ok(());
+        }
+        Err(QitOpsError::Source(unknown.join("; ")).into())
+    }
+
+    fn unknown(&self, ids: &[String]) -> Vec<String> {
+        crate::cli::suggest::unknown_ids("source", ids, self.sources.keys().map(String::as_str))
+    }
+
+    fn save(&self) -> Result<()> {
+        let file = SourceFile { sources: self.sources.clone() };
+        std::fs::write(&self.config_path, serde_json::to_string_pretty(&file)?)
+            .map_err(|e| anyhow!("Failed to write {}: {}", self.config_path.display(), e))
     }
 
     pub fn get_source(&self, id: &str) -> Option<&Source> {
@@ -88,8 +150,10 @@ impl SourceManager {
 
         for source_id in sources {
             if let Some(source) = self.get_source(source_id) {
+                let source_content = source.get_content()?;
+                crate::debug::record_piece("source", source_id, &source_content);
                 content.push_str(&format!("# Source: {} ({})\n\n", source_id, source.source_type.to_string()));
-                content.push_str(&source.get_content()?);
+                content.push_str(&crate::prompt::wrap_untrusted(&format!("source document '{}'", source_id), &source_content));
                 content.push_str("\n\n");
             }
         }
@@ -231,7 +295,7 @@ async fn show_source(id: &str) -> Result<()> {
     let source_manager = SourceManager::new()?;
 
     let source = source_manager.get_source(id)
-        .ok_or_else(|| anyhow::anyhow!("Source not found: {}", id))?;
+        .ok_or_else(|| QitOpsError::Source(format!("Source not found: {}", id)))?;
 
     let content = source.get_content()?;
 
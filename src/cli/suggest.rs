@@ -0,0 +1,47 @@
+/// Describe each ID that is not among the known ones, suggesting the closest known ID
+pub fn unknown_ids<'a>(kind: &str, ids: &[String], known: impl Iterator<Item = &'a str> + Clone) -> Vec<String> {
+    ids.iter()
+        .filter(|id| !known.clone().any(|k| k == id.as_str()))
+        .map(|id| match did_you_mean(id, known.clone()) {
+            Some(suggestion) => format!("Unknown {} '{}' (did you mean '{}'?)", kind, id, suggestion),
+            None => format!("Unknown {} '{}'", kind, id),
+        })
+        .collect()
+}
+
+/// The candidate closest to a mistyped name, if one is close enough to be a likely typo
+pub fn did_you_mean<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let name = name.to_lowercase();
+    if name.is_empty() {
+        return None;
+    }
+    // Allow roughly one edit per three characters
+    let threshold = (name.chars().count() / 3).max(1);
+
+    candidates.into_iter()
+        .filter_map(|candidate| {
+            let lowered = candidate.to_lowercase();
+            let distance = edit_distance(&name, &lowered);
+            let abbreviated = lowered.starts_with(&name) || name.starts_with(&lowered);
+            (distance <= threshold || abbreviated).then_some((candidate, distance))
+        })
+        .min_by(|(a, da), (b, db)| da.cmp(db).then_with(|| a.cmp(b)))
+        .map(|(candidate, _)| candidate)
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
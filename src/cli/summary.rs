@@ -0,0 +1,164 @@
+use colored::*;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::llm::cost::UsageTotals;
+use crate::output::Report;
+use crate::severity::{GateAction, Severity};
+
+/// Severities in the order they are shown, most severe first
+const SEVERITIES: [Severity; 5] = [Severity::Critical, Severity::High, Severity::Medium, Severity::Low, Severity::Info];
+
+/// Fields of an agent's data naming files it wrote
+const OUTPUT_FIELDS: &[&str] = &["output_file", "output_files", "data_file"];
+
+/// Summary of the last run, shown once the command has finished
+static LAST_RUN: Mutex<Option<RunSummary>> = Mutex::new(None);
+
+/// The at-a-glance result block printed at the end of a run
+#[derive(Debug, Clone)]
+pub struct RunSummary {
+    /// Run command (e.g. `risk`)
+    pub command: String,
+
+    /// One-line outcome
+    pub outcome: String,
+
+    /// Number of new findings per severity, most severe first
+    pub findings: Vec<(Severity, usize)>,
+
+    /// Gate outcome, for commands that gate
+    pub gate: Option<GateAction>,
+
+    /// LLM usage of the run
+    pub usage: UsageTotals,
+
+    /// Wall-clock time of the run
+    pub duration: Option<Duration>,
+
+    /// Files the run wrote
+    pub outputs: Vec<String>,
+
+    /// ID in the run history
+    pub run_id: Option<String>,
+}
+
+impl RunSummary {
+    /// Summarize a run's report
+    pub fn from_report(report: &Report) -> Self {
+        let findings = SEVERITIES.iter()
+            .map(|severity| (*severity, report.findings.iter().filter(|f| f.severity == *severity).count()))
+            .collect();
+
+        let mut outputs = Vec::new();
+        for field in OUTPUT_FIELDS {
+            match report.data.get(*field) {
+                Some(serde_json::Value::String(path)) => outputs.push(path.clone()),
+                Some(serde_json::Value::Array(paths)) => outputs.extend(paths.iter().filter_map(|p| p.as_str()).map(String::from)),
+                _ => {},
+            }
+        }
+        if report.data.get("baseline_written").and_then(|v| v.as_bool()).unwrap_or(false) {
+            outputs.push(crate::agent::baseline::BASELINE_FILE.to_string());
+        }
+        outputs.dedup();
+
+        Self {
+            command: report.command.clone(),
+            outcome: report.summary.clone(),
+            findings,
+            gate: report.gate,
+            usage: UsageTotals::default(),
+            duration: None,
+            outputs,
+            run_id: None,
+        }
+    }
+
+    /// Add a file the run wrote
+    pub fn with_output(mut self, path: &str) -> Self {
+        if !self.outputs.iter().any(|output| output == path) {
+            self.outputs.push(path.to_string());
+        }
+        self
+    }
+
+    /// Set the run's ID in the run history
+    pub fn with_run_id(mut self, run_id: String) -> Self {
+        self.run_id = Some(run_id);
+        self
+    }
+
+    /// Set the LLM usage of the run
+    pub fn with_usage(mut self, usage: UsageTotals) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    /// Set the wall-clock time of the run
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Render the summary as a colored table
+    pub fn render(&self) -> String {
+        let mut rows: Vec<(&str, String)> = vec![
+            ("Command", self.command.clone()),
+            ("Result", self.outcome.clone()),
+        ];
+
+        if self.gate.is_some() || self.findings.iter().any(|(_, count)| *count > 0) {
+            let counts = self.findings.iter().map(|(severity, count)| {
+                let text = format!("{} {}", count, severity);
+                match count {
+                    0 => text.dimmed().to_string(),
+                    _ => super::branding::colorize(&text, super::branding::severity_color(*severity)).bold().to_string(),
+                }
+            }).collect::<Vec<_>>();
+            rows.push(("Findings", counts.join("  ")));
+        }
+
+        if let Some(gate) = self.gate {
+            let gate = match gate {
+                GateAction::Pass => "PASS".green(),
+                GateAction::Warn => "WARN".yellow(),
+                GateAction::Fail => "FAIL".red(),
+            };
+            rows.push(("Gate", gate.bold().to_string()));
+        }
+
+        rows.push(("Cost", format!(
+            "${:.4} ({} request(s), {} tokens)",
+            self.usage.cost_usd, self.usage.requests, self.usage.input_tokens + self.usage.output_tokens
+        )));
+
+        if let Some(duration) = self.duration {
+            rows.push(("Duration", format!("{:.1}s", duration.as_secs_f64())));
+        }
+
+        for (index, output) in self.outputs.iter().enumerate() {
+            rows.push((if index == 0 { "Outputs" } else { "" }, output.clone()));
+        }
+
+        if let Some(run_id) = &self.run_id {
+            rows.push(("Run", format!("{} (compare with `qitops diff-runs`)", run_id)));
+        }
+
+        let mut table = format!("{}\n", "Run Summary".cyan().bold());
+        for (label, value) in rows {
+            table.push_str(&format!("  {} {}\n", format!("{:<10}", label).bold(), value));
+        }
+        table
+    }
+}
+
+/// Keep a run's summary to print when the command finishes
+pub fn record(summary: RunSummary) {
+    *LAST_RUN.lock().unwrap_or_else(|e| e.into_inner()) = Some(summary);
+}
+
+/// Take the summary of the last run, if a run recorded one
+pub fn take() -> Option<RunSummary> {
+    LAST_RUN.lock().unwrap_or_else(|e| e.into_inner()).take()
+}
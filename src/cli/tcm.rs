@@ -0,0 +1,190 @@
+use anyhow::{Result, anyhow};
+use clap::Subcommand;
+
+use crate::cli::branding;
+use crate::integrations::tcm::config::{TestRailConfig, XrayConfig, ZephyrConfig};
+use crate::integrations::tcm::{self, TcmConfigManager, TcmTarget, TcmTool};
+
+/// Test case management CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct TcmArgs {
+    /// Test case management subcommand
+    #[clap(subcommand)]
+    pub command: TcmCommand,
+}
+
+/// Test case management subcommands
+#[derive(Debug, Subcommand)]
+pub enum TcmCommand {
+    /// Configure a test case management tool
+    #[clap(name = "config")]
+    Config {
+        /// Tool to configure (testrail, xray, zephyr)
+        #[clap(long)]
+        tool: String,
+
+        /// TestRail instance URL
+        #[clap(long)]
+        url: Option<String>,
+
+        /// TestRail username (email)
+        #[clap(long)]
+        username: Option<String>,
+
+        /// TestRail API key
+        #[clap(long)]
+        api_key: Option<String>,
+
+        /// Xray API client ID
+        #[clap(long)]
+        client_id: Option<String>,
+
+        /// Xray API client secret
+        #[clap(long)]
+        client_secret: Option<String>,
+
+        /// Zephyr Scale API token
+        #[clap(long)]
+        api_token: Option<String>,
+
+        /// Xray or Zephyr Scale API base URL (for self-hosted or regional instances)
+        #[clap(long)]
+        base_url: Option<String>,
+
+        /// HTTP(S) proxy URL for every tool (default: HTTPS_PROXY/HTTP_PROXY environment variables)
+        #[clap(long)]
+        proxy: Option<String>,
+    },
+
+    /// Show test case management configuration
+    #[clap(name = "status")]
+    Status,
+
+    /// List the test cases at a target
+    #[clap(name = "list")]
+    List {
+        /// Target as <tool>:<project>[-<suite>], e.g. testrail:3-12 or xray:PROJ-Login
+        #[clap(short, long)]
+        target: String,
+    },
+}
+
+/// Handle test case management commands
+pub async fn handle_tcm_command(args: &TcmArgs) -> Result<()> {
+    match &args.command {
+        TcmCommand::Config { tool, url, username, api_key, client_id, client_secret, api_token, base_url, proxy } => {
+            let mut config_manager = TcmConfigManager::new()?;
+            let config = config_manager.get_config().clone();
+
+            if let Some(proxy) = proxy {
+                crate::proxy::client(Some(proxy))?;
+                config_manager.set_proxy(proxy.clone())?;
+                branding::print_success("Test case management proxy configured");
+
+                // Only the proxy was given, so the tool's settings are left alone
+                if [url, username, api_key, client_id, client_secret, api_token, base_url].iter().all(|option| option.is_none()) {
+                    return Ok(());
+                }
+            }
+
+            // Options not given keep their configured values
+            let keep = |given: &Option<String>, configured: Option<&String>, name: &str| {
+                given.clone()
+                    .or_else(|| configured.cloned())
+                    .ok_or_else(|| anyhow!("--{} is required for {}", name, tool))
+            };
+
+            match tool.parse::<TcmTool>()? {
+                TcmTool::TestRail => {
+                    let existing = config.testrail.as_ref();
+                    config_manager.set_testrail(TestRailConfig {
+                        url: keep(url, existing.map(|c| &c.url), "url")?,
+                        username: keep(username, existing.map(|c| &c.username), "username")?,
+                        api_key: keep(api_key, existing.map(|c| &c.api_key), "api-key")?,
+                    })?;
+                    branding::print_success("TestRail configured");
+                },
+                TcmTool::Xray => {
+                    let existing = config.xray.as_ref();
+                    config_manager.set_xray(XrayConfig {
+                        client_id: keep(client_id, existing.map(|c| &c.client_id), "client-id")?,
+                        client_secret: keep(client_secret, existing.map(|c| &c.client_secret), "client-secret")?,
+                        base_url: base_url.clone().or_else(|| existing.and_then(|c| c.base_url.clone())),
+                    })?;
+                    branding::print_success("Xray configured");
+                },
+                TcmTool::Zephyr => {
+                    let existing = config.zephyr.as_ref();
+                    config_manager.set_zephyr(ZephyrConfig {
+                        api_token: keep(api_token, existing.map(|c| &c.api_token), "api-token")?,
+                        base_url: base_url.clone().or_else(|| existing.and_then(|c| c.base_url.clone())),
+                    })?;
+                    branding::print_success("Zephyr Scale configured");
+                },
+            }
+
+            Ok(())
+        },
+        TcmCommand::Status => show_tcm_status(),
+        TcmCommand::List { target } => list_cases(&target.parse()?).await,
+    }
+}
+
+/// Show test case management configuration status
+fn show_tcm_status() -> Result<()> {
+    let config_manager = TcmConfigManager::new()?;
+    let config = config_manager.get_config();
+
+    println!("Test Case Management Configuration:");
+
+    let env_set = |name: &str| std::env::var(name).is_ok_and(|value| !value.is_empty());
+
+    if let Some(testrail) = &config.testrail {
+        branding::print_success(&format!("TestRail: Configured ({} as {})", testrail.url, testrail.username));
+    } else if env_set("TESTRAIL_URL") && env_set("TESTRAIL_USERNAME") && env_set("TESTRAIL_API_KEY") {
+        branding::print_success("TestRail: Using TESTRAIL_* environment variables");
+    } else {
+        branding::print_warning("TestRail: Not configured");
+    }
+
+    if config.xray.is_some() {
+        branding::print_success("Xray: Configured");
+    } else if env_set("XRAY_CLIENT_ID") && env_set("XRAY_CLIENT_SECRET") {
+        branding::print_success("Xray: Using XRAY_* environment variables");
+    } else {
+        branding::print_warning("Xray: Not configured");
+    }
+
+    if config.zephyr.is_some() {
+        branding::print_success("Zephyr Scale: Configured");
+    } else if env_set("ZEPHYR_API_TOKEN") {
+        branding::print_success("Zephyr Scale: Using ZEPHYR_API_TOKEN environment variable");
+    } else {
+        branding::print_warning("Zephyr Scale: Not configured");
+    }
+
+    if let Some(proxy) = &config.proxy {
+        println!("Proxy: {}", proxy);
+    }
+
+    Ok(())
+}
+
+/// List the test cases at a target
+async fn list_cases(target: &TcmTarget) -> Result<()> {
+    let config_manager = TcmConfigManager::new()?;
+    let client = tcm::client_for(target.tool, config_manager.get_config())?;
+
+    let cases = client.list_cases(target).await?;
+    if cases.is_empty() {
+        branding::print_info(&format!("No test cases in {}", target));
+        return Ok(());
+    }
+
+    println!("Test cases in {} ({}):", target, cases.len());
+    for case in &cases {
+        println!("  {:<12} {}", case.id, case.title);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use anyhow::Result;
+use clap::Subcommand;
+use std::path::Path;
+
+use crate::cli::branding;
+use crate::telemetry::{self, Telemetry};
+
+/// Telemetry CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct TelemetryArgs {
+    /// Telemetry subcommand
+    #[clap(subcommand)]
+    pub command: TelemetryCommand,
+}
+
+/// Telemetry subcommands
+#[derive(Debug, Subcommand)]
+pub enum TelemetryCommand {
+    /// Opt in to anonymous usage telemetry
+    #[clap(name = "on")]
+    On,
+
+    /// Opt out, deleting the installation ID and queued events
+    #[clap(name = "off")]
+    Off,
+
+    /// Show whether telemetry is on and what is queued
+    #[clap(name = "status")]
+    Status,
+
+    /// Write the queued events as JSON lines
+    #[clap(name = "export")]
+    Export {
+        /// File to write instead of stdout
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+
+    /// Delete the queued events
+    #[clap(name = "clear")]
+    Clear,
+}
+
+/// Handle telemetry commands
+pub async fn handle_telemetry_command(args: &TelemetryArgs) -> Result<()> {
+    let mut telemetry = Telemetry::open()?;
+
+    match &args.command {
+        TelemetryCommand::On => {
+            telemetry.set_enabled(true)?;
+            branding::print_success("Telemetry is on. QitOps records the commands you run, how long they take and the kind of error when one fails; never arguments, file names or content.");
+            println!("Events stay in {} until you export them with `qitops telemetry export`.", telemetry.queue_path().display());
+            if telemetry::disabled_by_env() {
+                branding::print_warning(&format!("{} or DO_NOT_TRACK is set, so nothing is recorded in this environment", telemetry::TELEMETRY_ENV));
+            }
+        },
+        TelemetryCommand::Off => {
+            telemetry.set_enabled(false)?;
+            branding::print_success("Telemetry is off; the installation ID and queued events were deleted");
+        },
+        TelemetryCommand::Status => {
+            let state = match (telemetry.is_opted_in(), telemetry.is_enabled()) {
+                (false, _) => "off",
+                (true, true) => "on",
+                (true, false) => "on, but disabled by the environment",
+            };
+            println!("Telemetry: {}", state);
+            if let Some(install_id) = telemetry.install_id() {
+                println!("Installation ID: {}", install_id);
+            }
+            println!("Queued events: {} ({})", telemetry.queued()?.len(), telemetry.queue_path().display());
+        },
+        TelemetryCommand::Export { output } => {
+            let count = telemetry::export(&telemetry, output.as_deref().map(Path::new))?;
+            if let Some(output) = output {
+                branding::print_success(&format!("Exported {} event(s) to {}", count, output));
+            }
+        },
+        TelemetryCommand::Clear => {
+            telemetry.clear()?;
+            branding::print_success("Queued telemetry events deleted");
+        },
+    }
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use anyhow::Result;
+use std::io::{self, Write};
+
+use crate::cli::branding;
+use crate::cli::progress::ProgressIndicator;
+use crate::llm::LazyRouter;
+use crate::runs::RunHistory;
+use crate::runs::triage::{self, TriageSession};
+
+/// Triage CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct TriageArgs {
+    /// Run to triage: an ID from `qitops history runs`, a unique prefix of one, or `latest~N` [default: latest failed run]
+    #[clap(long)]
+    pub run: Option<String>,
+}
+
+/// Open a REPL about a saved run, answering follow-up questions from its stored artifacts
+pub async fn handle_triage_command(args: &TriageArgs) -> Result<()> {
+    let history = RunHistory::open()?;
+    let record = match &args.run {
+        Some(run) => history.get(run)?,
+        None => history.latest_failed()?,
+    };
+    let mut session = TriageSession::new(record, &triage::repo_context(&std::env::current_dir()?));
+    if !session.record().failed() {
+        branding::print_warning(&format!("Run {} didn't fail its gate", session.record().id));
+    }
+
+    let router = LazyRouter::new();
+
+    println!("{}", session.summary());
+    println!();
+    println!("{}", session.finding_list());
+    println!();
+    branding::print_info("Ask about the run, or type /help for commands and exit to quit");
+
+    loop {
+        print!("{}: ", branding::colorize("You", branding::Color::Blue));
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            break;
+        }
+        let input = input.trim();
+        if input.is_empty() {
+            continue;
+        }
+        if input.eq_ignore_ascii_case("exit") || input.eq_ignore_ascii_case("quit") {
+            break;
+        }
+
+        let answer = match session.command(input) {
+            Some(output) => output,
+            None => {
+                let progress = ProgressIndicator::new("Thinking...");
+                let answer = match router.get().await {
+                    Ok(router) => session.ask(&router, input).await,
+                    Err(e) => Err(e),
+                };
+                progress.finish();
+                match answer {
+                    Ok(answer) => answer,
+                    Err(e) => {
+                        branding::print_error(&format!("Couldn't answer: {}", e));
+                        continue;
+                    },
+                }
+            },
+        };
+        println!("{}: {}", branding::colorize(triage::TRIAGE_NAME, branding::Color::Green), answer);
+        println!();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use anyhow::Result;
+use std::path::Path;
+
+use crate::attest::{self, SigningKey};
+use crate::audit::format_timestamp;
+use crate::cli::branding;
+use crate::config::QitOpsConfigManager;
+use crate::error::QitOpsError;
+
+/// Verify CLI arguments
+#[derive(Debug, clap::Args)]
+pub struct VerifyArgs {
+    /// Signed JSON result, written with `--format json`
+    pub file: String,
+
+    /// File holding the signing key, instead of QITOPS_SIGNING_KEY or signing.key_file
+    #[clap(long)]
+    pub key_file: Option<String>,
+}
+
+/// Check the signature of a JSON result
+pub async fn handle_verify_command(args: &VerifyArgs) -> Result<()> {
+    let key = match &args.key_file {
+        Some(path) => SigningKey::from_file(Path::new(path))?,
+        None => {
+            let config = QitOpsConfigManager::new()?.get_config().clone();
+            SigningKey::from_config(&config.signing)?
+                .ok_or_else(|| QitOpsError::Attestation(format!(
+                    "No signing key configured; set {} or signing.key_file, or pass --key-file",
+                    attest::SIGNING_KEY_ENV
+                )))?
+        },
+    };
+
+    let text = std::fs::read_to_string(&args.file)
+        .map_err(|e| QitOpsError::InvalidInput(format!("Failed to read {}: {}", args.file, e)))?;
+    let document: serde_json::Value = serde_json::from_str(&text)
+        .map_err(|e| QitOpsError::InvalidInput(format!("{} is not JSON: {}", args.file, e)))?;
+
+    let attestation = attest::verify(&document, &key)?;
+
+    branding::print_success(&format!("Valid signature on {}", args.file));
+    if let Some(command) = document.get("command").and_then(|c| c.as_str()) {
+        println!("Command: {}", command);
+    }
+    if let Some(gate) = document.get("gate").and_then(|g| g.as_str()) {
+        println!("Gate: {}", gate);
+    }
+    println!("Signed: {} by QitOps {} with key {}", format_timestamp(attestation.signed_at), attestation.version, attestation.key_id);
+
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use anyhow::{Result, anyhow};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Default quiet period before a batch of changes is processed
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Directories that never trigger a re-run
+const IGNORED_DIRS: &[&str] = &[".git", "target", "node_modules", ".qitops", "sessions"];
+
+/// Watches a path and yields debounced batches of changed files
+pub struct FileWatcher {
+    /// Keeps the OS watcher alive
+    _watcher: RecommendedWatcher,
+
+    /// Raw filesystem events
+    events: mpsc::UnboundedReceiver<notify::Result<Event>>,
+
+    /// Content hashes of files seen so far, used to skip saves that didn't change anything
+    hashes: HashMap<PathBuf, u64>,
+
+    /// Quiet period before a batch is emitted
+    debounce: Duration,
+}
+
+impl FileWatcher {
+    /// Start watching a file or directory (recursively)
+    pub fn new(path: &Path, debounce: Duration) -> Result<Self> {
+        if !path.exists() {
+            return Err(anyhow!("Watch path not found: {}", path.display()));
+        }
+
+        let (tx, events) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        }).map_err(|e| anyhow!("Failed to start file watcher: {}", e))?;
+
+        watcher.watch(path, RecursiveMode::Recursive)
+            .map_err(|e| anyhow!("Failed to watch {}: {}", path.display(), e))?;
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            hashes: HashMap::new(),
+            debounce,
+        })
+    }
+
+    /// Wait for the next batch of changed files
+    ///
+    /// Events are collected until no new event arrives for the debounce period, so an editor
+    /// writing a file in several steps triggers a single run. Files whose content is unchanged
+    /// since the last batch are dropped.
+    pub async fn next_changes(&mut self) -> Option<Vec<PathBuf>> {
+        loop {
+            let mut paths = BTreeSet::new();
+
+            // Block until something happens
+            let first = self.events.recv().await?;
+            collect_paths(first, &mut paths);
+
+            // Then drain until things go quiet
+            while let Ok(Some(event)) = tokio::time::timeout(self.debounce, self.events.recv()).await {
+                collect_paths(event, &mut paths);
+            }
+
+            let changed: Vec<PathBuf> = paths.into_iter()
+                .filter(|path| self.content_changed(path))
+                .collect();
+
+            if !changed.is_empty() {
+                return Some(changed);
+            }
+        }
+    }
+
+    /// Check and record whether a file's content differs from the last time it was seen
+    fn content_changed(&mut self, path: &Path) -> bool {
+        let Ok(content) = fs::read(path) else {
+            // Deleted or unreadable
+            return false;
+        };
+
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        self.hashes.insert(path.to_path_buf(), hash) != Some(hash)
+    }
+}
+
+/// Whether a path is in a directory that should never trigger a re-run
+fn is_ignored(path: &Path) -> bool {
+    path.components().any(|component| {
+        let name = component.as_os_str().to_string_lossy();
+        IGNORED_DIRS.contains(&name.as_ref())
+    }) || path.file_name().is_some_and(|name| {
+        let name = name.to_string_lossy();
+        // Editor swap and backup files
+        name.starts_with(".#") || name.ends_with('~') || name.ends_with(".swp")
+    })
+}
+
+/// Add the relevant file paths from an event to a batch
+fn collect_paths(event: notify::Result<Event>, paths: &mut BTreeSet<PathBuf>) {
+    let event = match event {
+        Ok(event) => event,
+        Err(e) => {
+            tracing::warn!("File watcher error: {}", e);
+            return;
+        }
+    };
+
+    if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+        return;
+    }
+
+    paths.extend(event.paths.into_iter().filter(|path| path.is_file() && !is_ignored(path)));
+}
@@ -1,8 +1,18 @@
-use anyhow::{Result, anyhow, Context};
+use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
+
+use crate::agent::diff_filter::DiffFilterConfig;
+use crate::agent::large_pr::LargePrConfig;
+use crate::agent::risk_score::RiskScoring;
+use crate::attest::SigningConfig;
+use crate::ci::env::{CiContext, DEFAULT_FOOTER};
+use crate::history::HistoryConfig;
+use crate::llm::BudgetConfig;
+use crate::scan::ScanLimits;
+use crate::severity::SeverityPolicy;
 
 /// Command configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +50,10 @@ pub struct SourcesConfig {
     /// Source paths
     #[serde(default)]
     pub paths: HashMap<String, String>,
+
+    /// Sources attached automatically when a diff touches matching paths
+    #[serde(default)]
+    pub rules: Vec<SourceRule>,
 }
 
 impl Default for SourcesConfig {
@@ -47,10 +61,21 @@ impl Default for SourcesConfig {
         Self {
             default: None,
             paths: HashMap::new(),
+            rules: Vec::new(),
         }
     }
 }
 
+/// Attach sources to `pr-analyze` and `risk` when a changed file matches a path glob
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SourceRule {
+    /// Path glob relative to the repository root (e.g. `payments/**`)
+    pub path: String,
+
+    /// Source IDs to attach
+    pub sources: Vec<String>,
+}
+
 /// Personas configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PersonasConfig {
@@ -67,6 +92,38 @@ impl Default for PersonasConfig {
     }
 }
 
+/// Report configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReportConfig {
+    /// Template added before reports and posted comments
+    #[serde(default)]
+    pub header: Option<String>,
+    
+    /// Template added after reports and posted comments; defaults to a build summary in CI,
+    /// and an empty footer disables it
+    #[serde(default)]
+    pub footer: Option<String>,
+}
+
+impl ReportConfig {
+    /// Add the rendered header and footer to a report
+    pub fn decorate(&self, body: &str, ci: &CiContext) -> String {
+        let footer = match &self.footer {
+            Some(footer) => Some(footer.as_str()),
+            None if ci.is_ci() => Some(DEFAULT_FOOTER),
+            None => None,
+        };
+        
+        let header = self.header.as_deref().map(|header| ci.render(header)).filter(|h| !h.trim().is_empty());
+        let footer = footer.map(|footer| ci.render(footer)).filter(|f| !f.trim().is_empty());
+        
+        [header, Some(body.to_string()), footer].into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
 /// QitOps configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QitOpsConfig {
@@ -82,6 +139,46 @@ pub struct QitOpsConfig {
     #[serde(default)]
     pub personas: PersonasConfig,
     
+    /// Severity mapping for gates and annotations
+    #[serde(default)]
+    pub severity: SeverityPolicy,
+    
+    /// LLM spending budget
+    #[serde(default)]
+    pub budget: BudgetConfig,
+    
+    /// Report header and footer
+    #[serde(default)]
+    pub report: ReportConfig,
+    
+    /// Limits on repository scanning
+    #[serde(default)]
+    pub context: ScanLimits,
+    
+    /// Noise filters run on diffs before analysis
+    #[serde(default)]
+    pub diff_filters: DiffFilterConfig,
+    
+    /// Analysis of pull requests too large for one prompt
+    #[serde(default)]
+    pub large_pr: LargePrConfig,
+    
+    /// Rubric for numeric risk scores and their thresholds
+    #[serde(default)]
+    pub risk_scoring: RiskScoring,
+    
+    /// Key for signing JSON results
+    #[serde(default)]
+    pub signing: SigningConfig,
+    
+    /// Encryption of saved session transcripts
+    #[serde(default)]
+    pub history: HistoryConfig,
+    
+    /// Language for CLI messages (`en`, `es`, `de` or `ja`); defaults to the `LANG` locale
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    
     /// Other configuration
     #[serde(flatten)]
     pub other: serde_json::Value,
@@ -93,11 +190,41 @@ impl Default for QitOpsConfig {
             commands: HashMap::new(),
             sources: SourcesConfig::default(),
             personas: PersonasConfig::default(),
+            severity: SeverityPolicy::default(),
+            budget: BudgetConfig::default(),
+            report: ReportConfig::default(),
+            context: ScanLimits::default(),
+            diff_filters: DiffFilterConfig::default(),
+            large_pr: LargePrConfig::default(),
+            risk_scoring: RiskScoring::default(),
+            signing: SigningConfig::default(),
+            history: HistoryConfig::default(),
+            locale: None,
             other: serde_json::Value::Object(serde_json::Map::new()),
         }
     }
 }
 
+/// Get the QitOps config directory, creating it if it doesn't exist
+pub fn config_dir() -> Result<PathBuf> {
+    let config_dir = if cfg!(windows) {
+        let app_data = std::env::var("APPDATA")
+            .map_err(|_| anyhow!("APPDATA environment variable not set"))?;
+        PathBuf::from(app_data).join("qitops")
+    } else {
+        let home = std::env::var("HOME")
+            .map_err(|_| anyhow!("HOME environment variable not set"))?;
+        PathBuf::from(home).join(".config").join("qitops")
+    };
+
+    if !config_dir.exists() {
+        fs::create_dir_all(&config_dir)
+            .map_err(|e| anyhow!("Failed to create config directory: {}", e))?;
+    }
+
+    Ok(config_dir)
+}
+
 /// QitOps configuration manager
 pub struct QitOpsConfigManager {
     /// Configuration
@@ -111,21 +238,7 @@ impl QitOpsConfigManager {
     /// Create a new QitOps configuration manager
     pub fn new() -> Result<Self> {
         // Get config directory
-        let config_dir = if cfg!(windows) {
-            let app_data = std::env::var("APPDATA")
-                .map_err(|_| anyhow!("APPDATA environment variable not set"))?;
-            PathBuf::from(app_data).join("qitops")
-        } else {
-            let home = std::env::var("HOME")
-                .map_err(|_| anyhow!("HOME environment variable not set"))?;
-            PathBuf::from(home).join(".config").join("qitops")
-        };
-        
-        // Create config directory if it doesn't exist
-        if !config_dir.exists() {
-            fs::create_dir_all(&config_dir)
-                .map_err(|e| anyhow!("Failed to create config directory: {}", e))?;
-        }
+        let config_dir = config_dir()?;
         
         // Config file path
         let config_path = config_dir.join("config.json");
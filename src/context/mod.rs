@@ -0,0 +1,306 @@
+// Repository context: where a source file sits in its project and how its tests import it
+pub mod summaries;
+
+use anyhow::Result;
+use regex::Regex;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use crate::error::QitOpsError;
+
+/// Most public items listed in the prompt
+const MAX_ITEMS: usize = 50;
+
+static CARGO_NAME: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"(?m)^\s*name\s*=\s*"([^"]+)""#).unwrap());
+static RUST_ITEM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^(pub\s+)?(?:pub\([^)]*\)\s+)?(?:async\s+|const\s+|unsafe\s+)*(fn|struct|enum|trait|type|const|static)\s+([A-Za-z_][A-Za-z0-9_]*)").unwrap()
+});
+static PYTHON_ITEM: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?m)^()(?:async\s+)?(def|class)\s+([A-Za-z][A-Za-z0-9_]*)").unwrap());
+
+/// Language of a source file tests can be compiled or run for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceLanguage {
+    /// Rust, tested with `#[test]` functions
+    Rust,
+    /// Python, tested with pytest
+    Python,
+}
+
+impl SourceLanguage {
+    /// Name of the language
+    pub fn name(&self) -> &'static str {
+        match self {
+            SourceLanguage::Rust => "Rust",
+            SourceLanguage::Python => "Python",
+        }
+    }
+
+    /// Extension of its source files
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SourceLanguage::Rust => "rs",
+            SourceLanguage::Python => "py",
+        }
+    }
+}
+
+/// How tests reach the code under test
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum TestPlacement {
+    /// A separate test file importing the module, e.g. a Rust integration test or a pytest file
+    External,
+
+    /// A Rust `#[cfg(test)]` module of the source file itself, included with `#[path]`, for
+    /// modules that aren't reachable from outside the crate
+    Inline {
+        /// Declaration to add to the source file
+        declaration: String,
+    },
+}
+
+/// A function, type or constant of the source file tests can use
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PublicItem {
+    /// Kind, e.g. `fn`, `struct`, `def` or `class`
+    pub kind: String,
+
+    /// Name
+    pub name: String,
+}
+
+/// Where a source file sits in its repository, for tests that compile or import against it
+#[derive(Debug, Clone, Serialize)]
+pub struct RepositoryContext {
+    /// Language of the file
+    pub language: SourceLanguage,
+
+    /// Project root: the crate's `Cargo.toml` directory, or the Python project's
+    pub root: PathBuf,
+
+    /// Crate name (as used in paths) or top-level Python package, if any
+    pub package: Option<String>,
+
+    /// Path of the file's module, e.g. `crate::agent::diff` or `app.users.service`
+    pub module: String,
+
+    /// Statements tests use to import the file's public items
+    pub imports: Vec<String>,
+
+    /// Items of the file tests can use: public ones, or all of them from a Rust test module
+    pub items: Vec<PublicItem>,
+
+    /// Where the generated tests go
+    pub placement: TestPlacement,
+
+    /// Test file for the source file
+    pub test_path: PathBuf,
+}
+
+impl RepositoryContext {
+    /// Resolve the context of the source file at `path` with the contents `code`, for tests of
+    /// the whole file or of one `function`
+    pub fn for_file(path: &Path, code: &str, language: SourceLanguage, function: Option<&str>) -> Result<Self> {
+        if path.extension().is_none_or(|ext| ext != language.extension()) {
+            return Err(QitOpsError::InvalidInput(format!(
+                "{} tests can only be generated for .{} files, not {}", language.name(), language.extension(), path.display()
+            )).into());
+        }
+
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        Ok(match language {
+            SourceLanguage::Rust => Self::rust(&path, code, function),
+            SourceLanguage::Python => Self::python(&path, code, function),
+        })
+    }
+
+    /// Rust: an integration test in `tests/` when the module is public in a library crate,
+    /// otherwise a test module of the file itself
+    fn rust(path: &Path, code: &str, function: Option<&str>) -> Self {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let root = dir.ancestors().find(|ancestor| ancestor.join("Cargo.toml").is_file()).unwrap_or(dir).to_path_buf();
+        let package = fs::read_to_string(root.join("Cargo.toml")).ok()
+            .and_then(|manifest| CARGO_NAME.captures(&manifest).map(|captures| captures[1].replace('-', "_")));
+
+        let src = root.join("src");
+        let segments = rust_module_segments(&src, path);
+        let public = items(&RUST_ITEM, code, |public, _| public);
+        let stem = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        let suffix = function.map(|function| format!("_{}", function)).unwrap_or_default();
+
+        let reachable = src.join("lib.rs").is_file() && segments.as_ref().is_some_and(|segments| public_path(&src, segments));
+        let module_path = segments.clone().unwrap_or_default().join("::");
+        let tests_file = format!("{}{}_tests.rs", stem, suffix);
+        match (&package, reachable, public.is_empty()) {
+            (Some(package), true, false) => {
+                let module = if module_path.is_empty() { package.clone() } else { format!("{}::{}", package, module_path) };
+                let names: Vec<&str> = public.iter().map(|item| item.name.as_str()).collect();
+                let test_name = if module_path.is_empty() { stem.clone() } else { module_path.replace("::", "_") };
+                Self {
+                    language: SourceLanguage::Rust,
+                    test_path: root.join("tests").join(format!("{}{}_test.rs", test_name, suffix)),
+                    imports: vec![match names.as_slice() {
+                        [name] => format!("use {}::{};", module, name),
+                        _ => format!("use {}::{{{}}};", module, names.join(", ")),
+                    }],
+                    root,
+                    package: Some(package.clone()),
+                    module,
+                    items: public,
+                    placement: TestPlacement::External,
+                }
+            },
+            _ => Self {
+                language: SourceLanguage::Rust,
+                test_path: dir.join(&tests_file),
+                imports: vec!["use super::*;".to_string()],
+                root,
+                package,
+                module: if module_path.is_empty() { "crate".to_string() } else { format!("crate::{}", module_path) },
+                // Private items are in scope of a test module too
+                items: items(&RUST_ITEM, code, |_, _| true),
+                placement: TestPlacement::Inline {
+                    declaration: format!("#[cfg(test)]\n#[path = \"{}\"]\nmod tests;", tests_file),
+                },
+            },
+        }
+    }
+
+    /// Python: a pytest file in the project's `tests/` importing the module by its package path
+    fn python(path: &Path, code: &str, function: Option<&str>) -> Self {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let stem = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+
+        // Packages go up as long as directories have an `__init__.py`
+        let mut import_root = dir;
+        let mut packages = Vec::new();
+        while import_root.join("__init__.py").is_file() {
+            packages.insert(0, import_root.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default());
+            match import_root.parent() {
+                Some(parent) => import_root = parent,
+                None => break,
+            }
+        }
+
+        let root = dir.ancestors()
+            .find(|ancestor| ["pyproject.toml", "setup.py", "setup.cfg", "pytest.ini", "tox.ini"].iter().any(|file| ancestor.join(file).is_file()))
+            .or_else(|| dir.ancestors().find(|ancestor| ancestor.join(".git").exists()))
+            .unwrap_or(import_root)
+            .to_path_buf();
+
+        let mut segments = packages.clone();
+        if stem != "__init__" {
+            segments.push(stem.clone());
+        }
+        let module = segments.join(".");
+        let items = items(&PYTHON_ITEM, code, |_, name| !name.starts_with('_'));
+        let names: Vec<&str> = items.iter().map(|item| item.name.as_str()).collect();
+
+        let mut imports = Vec::new();
+        // Modules outside the project root's import path need it added, e.g. a `src/` layout
+        if import_root != root
+            && let Ok(relative) = import_root.strip_prefix(&root)
+        {
+            imports.push(format!("# {} must be on the import path (e.g. `pythonpath = [\"{}\"]` in the pytest config)", relative.display(), relative.display()));
+        }
+        imports.push(match names.is_empty() {
+            true => format!("import {}", module),
+            false => format!("from {} import {}", module, names.join(", ")),
+        });
+
+        Self {
+            language: SourceLanguage::Python,
+            test_path: root.join("tests").join(format!(
+                "test_{}{}.py",
+                if stem == "__init__" { packages.last().cloned().unwrap_or(stem) } else { stem },
+                function.map(|function| format!("_{}", function)).unwrap_or_default()
+            )),
+            root,
+            package: packages.first().cloned(),
+            module,
+            imports,
+            items,
+            placement: TestPlacement::External,
+        }
+    }
+
+    /// Instructions for tests that compile or import against the file
+    pub fn prompt(&self) -> String {
+        let mut prompt = match (&self.language, &self.placement) {
+            (SourceLanguage::Rust, TestPlacement::External) => format!(
+                "Write the tests as a Rust integration test file (`{}`) of the `{}` crate: `#[test]` functions (`#[tokio::test]` for async code) that compile against the module's public API.",
+                self.relative(&self.test_path), self.package.as_deref().unwrap_or_default()
+            ),
+            (SourceLanguage::Rust, TestPlacement::Inline { .. }) => format!(
+                "Write the tests as the body of a Rust test module of `{}`: `#[test]` functions (`#[tokio::test]` for async code). The file is included into the module with `#[path]`, so it must not contain a `mod tests {{ }}` wrapper or `#[cfg(test)]`.",
+                self.module
+            ),
+            (SourceLanguage::Python, _) => format!(
+                "Write the tests as a pytest file (`{}`): plain `test_*` functions with `assert`, fixtures and `pytest.mark.parametrize` where they help, `pytest.raises` for errors.",
+                self.relative(&self.test_path)
+            ),
+        };
+
+        prompt.push_str(&format!("\n\nStart the file with these imports, adding only what the tests use from the standard library or the project's dependencies:\n```\n{}\n```", self.imports.join("\n")));
+        if !self.items.is_empty() {
+            let items: Vec<String> = self.items.iter().map(|item| format!("{} {}", item.kind, item.name)).collect();
+            prompt.push_str(&format!("\n\nThe module defines: {}. Call them exactly as they are declared in the code; don't invent functions, fields or arguments.", items.join(", ")));
+        }
+        prompt.push_str("\n\nOutput only the contents of the test file.");
+        prompt
+    }
+
+    /// A path relative to the project root, for the prompt
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root).unwrap_or(path).to_string_lossy().to_string()
+    }
+}
+
+/// Module path of a Rust file below `src/`, e.g. `["agent", "diff"]`; empty for the crate root
+fn rust_module_segments(src: &Path, path: &Path) -> Option<Vec<String>> {
+    let relative = path.strip_prefix(src).ok()?;
+    let mut segments: Vec<String> = relative.with_extension("").iter().map(|part| part.to_string_lossy().to_string()).collect();
+    if matches!(segments.last().map(String::as_str), Some("mod")) || (segments.len() == 1 && matches!(segments[0].as_str(), "lib" | "main")) {
+        segments.pop();
+    }
+    // Binaries under `src/bin/` aren't modules of the library
+    (segments.first().is_none_or(|first| first != "bin")).then_some(segments)
+}
+
+/// Whether every module of the path is declared `pub mod` by its parent
+fn public_path(src: &Path, segments: &[String]) -> bool {
+    let mut parent_dir = src.to_path_buf();
+    let mut parent_file = src.join("lib.rs");
+    for segment in segments {
+        let declared = Regex::new(&format!(r"(?m)^\s*pub\s+mod\s+{}\s*;", regex::escape(segment))).unwrap();
+        if !fs::read_to_string(&parent_file).is_ok_and(|code| declared.is_match(&code)) {
+            return false;
+        }
+
+        parent_file = match parent_dir.join(segment).join("mod.rs") {
+            file if file.is_file() => file,
+            _ => parent_dir.join(format!("{}.rs", segment)),
+        };
+        parent_dir = parent_dir.join(segment);
+    }
+    true
+}
+
+/// Top-level items matched by `regex` that `include` accepts given whether they're `pub` and their name
+///
+/// The regex captures a `pub ` prefix (empty for languages without one), the kind and the name.
+fn items(regex: &Regex, code: &str, include: impl Fn(bool, &str) -> bool) -> Vec<PublicItem> {
+    let mut items: Vec<PublicItem> = Vec::new();
+    for captures in regex.captures_iter(code) {
+        let item = PublicItem { kind: captures[2].to_string(), name: captures[3].to_string() };
+        let public = captures.get(1).is_some_and(|prefix| !prefix.as_str().is_empty());
+        if include(public, &item.name) && !items.iter().any(|existing| existing.name == item.name) {
+            items.push(item);
+        }
+    }
+    items.truncate(MAX_ITEMS);
+    items
+}
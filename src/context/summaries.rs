@@ -0,0 +1,433 @@
+// Cached summaries of a repository's files and directories, for short repository context
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::agent::conventions::SKIPPED_DIRS;
+use crate::hash::{hex, sha256_hex};
+use crate::llm::batch::run_batch;
+use crate::llm::{LlmRequest, LlmRouter};
+use crate::scan::{self, ScanLimits, scan_files};
+
+/// Characters of a file sent to be summarized
+const MAX_FILE_CHARS: usize = 12_000;
+
+/// Tokens of a summary
+const SUMMARY_TOKENS: usize = 120;
+
+/// Default length of the repository context, in characters
+pub const MAX_CONTEXT_CHARS: usize = 8_000;
+
+/// A summary and the hash of what it summarizes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Summary {
+    /// SHA-256 of the file's contents, or of a directory's entries
+    pub hash: String,
+
+    /// One or two sentences
+    pub text: String,
+}
+
+/// Summaries of one repository, by path relative to its root (`""` is the root directory)
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SummaryCache {
+    /// Where the cache is stored
+    #[serde(skip)]
+    path: PathBuf,
+
+    /// File summaries
+    #[serde(default)]
+    files: BTreeMap<String, Summary>,
+
+    /// Directory summaries
+    #[serde(default)]
+    dirs: BTreeMap<String, Summary>,
+}
+
+impl SummaryCache {
+    /// Open the cache of the repository at `root`, in the user's cache directory
+    pub fn open(root: &Path) -> Result<Self> {
+        let root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+        let dir = dirs::cache_dir()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine cache directory"))?
+            .join("qitops")
+            .join("summaries");
+
+        Self::open_at(dir.join(format!("{}.json", &sha256_hex(root.to_string_lossy().as_bytes())[..16])))
+    }
+
+    /// Open a cache file, empty if it doesn't exist yet
+    pub fn open_at(path: PathBuf) -> Result<Self> {
+        let mut cache: Self = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .context(format!("Failed to parse summary cache: {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => return Err(e).context(format!("Failed to read summary cache: {}", path.display())),
+        };
+        cache.path = path;
+        Ok(cache)
+    }
+
+    /// Where the cache is stored
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Summary of a file, if it was made from the contents with `hash`
+    pub fn file(&self, path: &str, hash: &str) -> Option<&str> {
+        self.files.get(path).filter(|summary| summary.hash == hash).map(|summary| summary.text.as_str())
+    }
+
+    /// Summary of a directory, if it was made from the entries with `hash`
+    pub fn dir(&self, path: &str, hash: &str) -> Option<&str> {
+        self.dirs.get(path).filter(|summary| summary.hash == hash).map(|summary| summary.text.as_str())
+    }
+
+    /// Number of file and directory summaries
+    pub fn len(&self) -> usize {
+        self.files.len() + self.dirs.len()
+    }
+
+    /// Whether there are no summaries
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Save the cache
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(self)?)
+            .context(format!("Failed to write summary cache: {}", self.path.display()))
+    }
+}
+
+/// The files of a repository with their contents and hashes, and the hashes of its directories
+///
+/// A directory's hash covers the names and hashes of its entries, so it changes whenever a
+/// file below it does.
+#[derive(Debug)]
+pub struct RepoSnapshot {
+    /// Repository root
+    pub root: PathBuf,
+
+    /// Text files by relative path
+    files: BTreeMap<String, scan::FileContent>,
+
+    /// Directory hashes by relative path
+    dirs: BTreeMap<String, String>,
+
+    /// Entries of each directory, in name order: its files, and its subdirectories with a trailing `/`
+    children: HashMap<String, Vec<String>>,
+
+    /// Number of files below each directory other than the root, through its subdirectories
+    file_counts: HashMap<String, usize>,
+
+    /// Limits the scan ran into, if any
+    pub limits_hit: Option<String>,
+}
+
+impl RepoSnapshot {
+    /// Read and hash the text files under `root`, within the scan limits
+    ///
+    /// Hidden entries and dependency or build directories are left out.
+    pub fn scan(root: &Path, limits: &ScanLimits) -> Result<Self> {
+        let root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+        let scan_root = root.clone();
+        let scan = scan_files(&root, limits, move |file| {
+            !file.strip_prefix(&scan_root).unwrap_or(file).components()
+                .any(|part| part.as_os_str().to_str().is_some_and(|part| SKIPPED_DIRS.contains(&part)))
+        })?;
+
+        let mut files = BTreeMap::new();
+        for (path, content) in scan.files.iter().zip(scan::read_files_hashed(&scan.files)) {
+            // Binary files aren't readable as text and aren't summarized
+            if let Some(content) = content {
+                files.insert(relative(&root, path), content);
+            }
+        }
+
+        let mut snapshot = Self {
+            root,
+            files,
+            dirs: BTreeMap::new(),
+            children: HashMap::new(),
+            file_counts: HashMap::new(),
+            limits_hit: scan.limit_summary(),
+        };
+        snapshot.index_dirs();
+        snapshot.hash_dirs();
+        Ok(snapshot)
+    }
+
+    /// Record the entries of every directory holding a file, and how many files are below it
+    fn index_dirs(&mut self) {
+        let mut known = HashSet::from([String::new()]);
+        for file in self.files.keys() {
+            self.children.entry(parent(file).to_string()).or_default().push(file.clone());
+
+            let mut dir = parent(file);
+            while !dir.is_empty() {
+                *self.file_counts.entry(dir.to_string()).or_default() += 1;
+                if known.insert(dir.to_string()) {
+                    self.children.entry(parent(dir).to_string()).or_default().push(format!("{}/", dir));
+                }
+                dir = parent(dir);
+            }
+        }
+
+        self.children.entry(String::new()).or_default();
+        for entries in self.children.values_mut() {
+            entries.sort();
+        }
+    }
+
+    /// Hash every directory holding a file, deepest first so subdirectories are hashed before their parents
+    fn hash_dirs(&mut self) {
+        let mut dirs: Vec<String> = self.children.keys().cloned().collect();
+        dirs.sort_by_key(|dir| std::cmp::Reverse(depth(dir)));
+
+        for dir in dirs {
+            let mut hasher = Sha256::new();
+            for (name, hash) in self.entries(&dir) {
+                hasher.update(format!("{}\t{}\n", name, hash));
+            }
+            let hash = hex(&hasher.finalize());
+            self.dirs.insert(dir, hash);
+        }
+    }
+
+    /// Names and hashes of a directory's files and hashed subdirectories, in name order
+    fn entries(&self, dir: &str) -> Vec<(String, String)> {
+        self.children.get(dir).into_iter().flatten()
+            .filter_map(|name| {
+                let hash = match name.strip_suffix('/') {
+                    Some(subdir) => self.dirs.get(subdir)?,
+                    None => &self.files.get(name)?.hash,
+                };
+                Some((name.clone(), hash.clone()))
+            })
+            .collect()
+    }
+
+    /// Number of text files
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Directories from the deepest up, so a directory comes after its subdirectories
+    fn dirs_deepest_first(&self) -> Vec<&String> {
+        let mut dirs: Vec<&String> = self.dirs.keys().collect();
+        dirs.sort_by_key(|dir| std::cmp::Reverse(depth(dir)));
+        dirs
+    }
+}
+
+/// Outcome of refreshing the summaries of a repository
+#[derive(Debug, Default, Serialize)]
+pub struct RefreshReport {
+    /// Files summarized because they're new or changed
+    pub files_summarized: usize,
+
+    /// Directories summarized because something below them changed
+    pub dirs_summarized: usize,
+
+    /// Files and directories whose cached summary is still current
+    pub unchanged: usize,
+
+    /// Summaries dropped because their file or directory is gone
+    pub removed: usize,
+
+    /// Files or directories that couldn't be summarized, with the error
+    pub failed: Vec<(String, String)>,
+}
+
+/// Summarize the files and directories of a snapshot whose cached summary is missing or out of
+/// date, and drop the summaries of ones that are gone
+///
+/// Files are summarized from their contents, then directories from the deepest up, from the
+/// summaries of their entries. A directory with an entry that failed isn't summarized, so it's
+/// retried on the next refresh. The cache is saved even when some summaries failed.
+pub async fn refresh(snapshot: &RepoSnapshot, cache: &mut SummaryCache, router: &LlmRouter, progress: impl Fn(&str)) -> Result<RefreshReport> {
+    let mut report = RefreshReport::default();
+    let model = router.default_model().unwrap_or_else(|| "tinyllama".to_string());
+
+    let before = cache.len();
+    cache.files.retain(|path, _| snapshot.files.contains_key(path));
+    cache.dirs.retain(|path, _| snapshot.dirs.contains_key(path));
+    report.removed = before - cache.len();
+
+    let stale: Vec<(&String, &scan::FileContent)> = snapshot.files.iter()
+        .filter(|(path, content)| cache.file(path, &content.hash).is_none())
+        .collect();
+    report.unchanged += snapshot.files.len() - stale.len();
+
+    let requests = stale.iter().map(|(path, content)| {
+        let code: String = content.text.chars().take(MAX_FILE_CHARS).collect();
+        let prompt = format!(
+            "Summarize the file `{}` in one or two sentences for a developer new to the project: what it's for and its main types or functions. Answer with the summary only.\n\n{}",
+            path, crate::prompt::wrap_untrusted(&format!("file {}", path), &code)
+        );
+        LlmRequest::new(prompt, model.clone()).with_max_tokens(SUMMARY_TOKENS)
+    }).collect();
+    let results = send_all(router, requests, |done, total| progress(&format!("Summarizing files... {}/{}", done, total))).await;
+    for ((path, content), result) in stale.into_iter().zip(results) {
+        match result {
+            Ok(text) => {
+                cache.files.insert(path.clone(), Summary { hash: content.hash.clone(), text });
+                report.files_summarized += 1;
+            },
+            Err(e) => report.failed.push((path.clone(), e.to_string())),
+        }
+    }
+
+    // Each level of directories is summarized once the level below it is done
+    let dirs = snapshot.dirs_deepest_first();
+    let mut level_start = 0;
+    while level_start < dirs.len() {
+        let level = depth(dirs[level_start]);
+        let level_end = dirs[level_start..].iter().position(|dir| depth(dir) != level).map_or(dirs.len(), |end| level_start + end);
+
+        let mut stale = Vec::new();
+        for dir in &dirs[level_start..level_end] {
+            let hash = &snapshot.dirs[*dir];
+            if cache.dir(dir, hash).is_some() {
+                report.unchanged += 1;
+                continue;
+            }
+
+            let mut lines = Vec::new();
+            let mut complete = true;
+            for (name, entry_hash) in snapshot.entries(dir) {
+                let summary = match name.strip_suffix('/') {
+                    Some(subdir) => cache.dir(subdir, &entry_hash),
+                    None => cache.file(&name, &entry_hash),
+                };
+                match summary {
+                    Some(summary) => lines.push(format!("- {}: {}", name, summary)),
+                    None => complete = false,
+                }
+            }
+            if complete {
+                stale.push((*dir, hash, lines));
+            }
+        }
+
+        let requests = stale.iter().map(|(dir, _, lines)| {
+            let name = if dir.is_empty() { "the repository root".to_string() } else { format!("the directory `{}/`", dir) };
+            let prompt = format!(
+                "Summarize {} in one or two sentences for a developer new to the project, from the summaries of its entries. Answer with the summary only.\n\n{}",
+                name, crate::prompt::wrap_untrusted("summaries of its entries", &lines.join("\n"))
+            );
+            LlmRequest::new(prompt, model.clone()).with_max_tokens(SUMMARY_TOKENS)
+        }).collect();
+        let results = send_all(router, requests, |done, total| progress(&format!("Summarizing directories... {}/{}", done, total))).await;
+        for ((dir, hash, _), result) in stale.into_iter().zip(results) {
+            match result {
+                Ok(text) => {
+                    cache.dirs.insert(dir.clone(), Summary { hash: hash.clone(), text });
+                    report.dirs_summarized += 1;
+                },
+                Err(e) => report.failed.push((format!("{}/", dir), e.to_string())),
+            }
+        }
+
+        level_start = level_end;
+    }
+
+    cache.save()?;
+    Ok(report)
+}
+
+/// Send summary requests as a batch, returning each answer on one line
+async fn send_all(router: &LlmRouter, requests: Vec<LlmRequest>, progress: impl Fn(usize, usize)) -> Vec<Result<String>> {
+    run_batch(requests, router.batch_concurrency(), |request| async move {
+        let response = router.send(request, Some("context")).await?;
+        Ok(response.text.split_whitespace().collect::<Vec<_>>().join(" "))
+    }, |counts| progress(counts.completed, counts.total)).await
+}
+
+/// Repository context from the current summaries: the directory tree with a summary for each
+/// directory and file, within `max_chars`
+///
+/// Files and directories without a current summary are listed by name. When the tree doesn't
+/// fit, files are left out and directories show their file count; directories that still
+/// don't fit are counted at the end.
+pub fn generate_context(snapshot: &RepoSnapshot, cache: &SummaryCache, max_chars: usize) -> String {
+    let name = snapshot.root.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let header = format!("Repository `{}`, summarized by directory and file:", name);
+
+    let lines = tree_lines(snapshot, cache, "", 0, true);
+    let fits = |lines: &[String]| header.len() + lines.iter().map(|line| line.len() + 1).sum::<usize>() <= max_chars;
+    if fits(&lines) {
+        return format!("{}\n{}", header, lines.join("\n"));
+    }
+
+    let mut lines = tree_lines(snapshot, cache, "", 0, false);
+    let total = lines.len();
+    while !lines.is_empty() && !fits(&lines) {
+        lines.pop();
+    }
+    if lines.len() < total {
+        lines.push(format!("- ... and {} more directories", total - lines.len()));
+    }
+    format!("{}\n{}", header, lines.join("\n"))
+}
+
+/// Lines of a directory's entries, indented by `level`, with or without its files
+fn tree_lines(snapshot: &RepoSnapshot, cache: &SummaryCache, dir: &str, level: usize, with_files: bool) -> Vec<String> {
+    let indent = "  ".repeat(level);
+    let mut lines = Vec::new();
+    if dir.is_empty()
+        && let Some(summary) = cache.dir("", &snapshot.dirs[""])
+    {
+        lines.push(format!("{}- ./: {}", indent, summary));
+    }
+
+    for (name, hash) in snapshot.entries(dir) {
+        let short = name.trim_end_matches('/').rsplit('/').next().unwrap_or_default();
+        match name.strip_suffix('/') {
+            Some(subdir) => {
+                let files = snapshot.file_counts.get(subdir).copied().unwrap_or_default();
+                let mut line = match with_files {
+                    true => format!("{}- {}/", indent, short),
+                    false => format!("{}- {}/ ({} files)", indent, short, files),
+                };
+                if let Some(summary) = cache.dir(subdir, &hash) {
+                    line.push_str(&format!(": {}", summary));
+                }
+                lines.push(line);
+                lines.extend(tree_lines(snapshot, cache, subdir, level + 1, with_files));
+            },
+            None if with_files => match cache.file(&name, &hash) {
+                Some(summary) => lines.push(format!("{}- {}: {}", indent, short, summary)),
+                None => lines.push(format!("{}- {}", indent, short)),
+            },
+            None => {},
+        }
+    }
+    lines
+}
+
+/// A path relative to the root, with `/` separators
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root).unwrap_or(path).components()
+        .map(|part| part.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Directory of a relative path, `""` at the root
+fn parent(path: &str) -> &str {
+    path.trim_end_matches('/').rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+/// Depth of a relative directory, the root being 0
+fn depth(dir: &str) -> usize {
+    if dir.is_empty() { 0 } else { dir.matches('/').count() + 1 }
+}
@@ -0,0 +1,479 @@
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+
+use crate::agent::traits::{Agent, AgentResponse};
+#[cfg(feature = "github")]
+use crate::agent::PrAnalyzeAgent;
+use crate::agent::{RiskAgent, TestDataAgent, TestGenAgent, TestReviewAgent};
+#[cfg(feature = "bot")]
+use crate::bot::tenant::TenantRegistry;
+#[cfg(feature = "github")]
+use crate::ci::{GitHubClient, GitHubConfig, GitHubConfigManager};
+use crate::audit;
+use crate::llm::LazyRouter;
+use crate::llm::approval::ApprovalQueue;
+
+/// Methods exposed by the daemon
+pub const METHODS: &[&str] = &[
+    "initialize", "status", "capabilities", "testGen", "testReview", "testData", "risk",
+    #[cfg(feature = "github")]
+    "prAnalyze",
+    "approvals", "approve", "reject", "shutdown", "exit",
+];
+
+/// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+#[cfg(feature = "bot")]
+const UNAUTHORIZED: i64 = -32001;
+#[cfg(feature = "bot")]
+const QUOTA_EXCEEDED: i64 = -32002;
+
+/// A JSON-RPC error
+struct RpcError {
+    /// Error code
+    code: i64,
+
+    /// Error message
+    message: String,
+}
+
+impl RpcError {
+    /// Create an internal error from any error
+    fn internal(e: anyhow::Error) -> Self {
+        Self { code: INTERNAL_ERROR, message: e.to_string() }
+    }
+}
+
+/// Parameters for `testGen`
+#[derive(Debug, Deserialize)]
+struct TestGenParams {
+    path: String,
+    #[serde(default = "default_test_format")]
+    format: String,
+    sources: Option<Vec<String>>,
+    personas: Option<Vec<String>>,
+    function: Option<String>,
+    since: Option<String>,
+}
+
+fn default_test_format() -> String {
+    "markdown".to_string()
+}
+
+/// Parameters for `testReview`
+#[derive(Debug, Deserialize)]
+struct TestReviewParams {
+    path: String,
+    sources: Option<Vec<String>>,
+    personas: Option<Vec<String>>,
+}
+
+/// Parameters for `testData`
+#[derive(Debug, Deserialize)]
+struct TestDataParams {
+    schema: String,
+    #[serde(default = "default_count")]
+    count: usize,
+    #[serde(default)]
+    constraints: Vec<String>,
+    #[serde(default = "default_data_format")]
+    format: String,
+    #[serde(default)]
+    mask: Vec<String>,
+}
+
+fn default_count() -> usize {
+    10
+}
+
+fn default_data_format() -> String {
+    "json".to_string()
+}
+
+/// Parameters for `risk`
+#[derive(Debug, Deserialize)]
+struct RiskParams {
+    diff: String,
+    #[serde(default)]
+    components: Vec<String>,
+    #[serde(default)]
+    focus: Vec<String>,
+    #[serde(default = "default_baseline")]
+    baseline: String,
+}
+
+/// Parameters for `prAnalyze`
+#[cfg(feature = "github")]
+#[derive(Debug, Deserialize)]
+struct PrAnalyzeParams {
+    pr: String,
+    owner: Option<String>,
+    repo: Option<String>,
+    #[serde(default = "default_baseline")]
+    baseline: String,
+}
+
+fn default_baseline() -> String {
+    "use".to_string()
+}
+
+/// Parameters for `approvals`
+#[derive(Debug, Default, Deserialize)]
+struct ApprovalsParams {
+    #[serde(default)]
+    all: bool,
+}
+
+/// Parameters for `approve` and `reject`
+#[derive(Debug, Deserialize)]
+struct DecisionParams {
+    id: String,
+    note: Option<String>,
+    actor: Option<String>,
+}
+
+/// State kept warm across requests
+struct DaemonState {
+    /// LLM router, shared by all requests so its clients and response cache stay warm
+    router: LazyRouter,
+
+    /// GitHub configuration, loaded once
+    #[cfg(feature = "github")]
+    github: GitHubConfig,
+
+    /// When the daemon started
+    started: Instant,
+
+    /// Number of requests served
+    requests: AtomicU64,
+
+    /// Tenants, when serving several teams; requests then carry an `apiKey`
+    #[cfg(feature = "bot")]
+    tenants: Option<TenantRegistry>,
+}
+
+impl DaemonState {
+    /// Handle a request, scoping it to the caller's tenant when tenants are configured
+    async fn handle(&self, method: &str, params: Value) -> std::result::Result<Value, RpcError> {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+
+        // Lifecycle methods don't touch tenant state
+        #[cfg(feature = "bot")]
+        if let Some(tenants) = &self.tenants
+            && !matches!(method, "initialize" | "status" | "shutdown")
+        {
+            return self.handle_for_tenant(tenants, method, params).await;
+        }
+
+        self.dispatch(method, params, &self.router, #[cfg(feature = "github")] &self.github).await
+    }
+
+    /// Handle a request with the LLM router, GitHub configuration, quotas and budget of the caller's tenant
+    #[cfg(feature = "bot")]
+    async fn handle_for_tenant(&self, tenants: &TenantRegistry, method: &str, mut params: Value) -> std::result::Result<Value, RpcError> {
+        let api_key = params.as_object_mut()
+            .and_then(|params| params.remove("apiKey"))
+            .and_then(|key| key.as_str().map(str::to_string))
+            .ok_or_else(|| RpcError { code: UNAUTHORIZED, message: "Missing apiKey".to_string() })?;
+        let tenant = tenants.authenticate(&api_key)
+            .ok_or_else(|| RpcError { code: UNAUTHORIZED, message: "Unknown API key".to_string() })?;
+
+        tenant.check_budget()
+            .map_err(|rejection| RpcError { code: QUOTA_EXCEEDED, message: rejection.friendly_message() })?;
+        let _permit = tenant.quotas().try_acquire(tenant.id(), "daemon")
+            .map_err(|rejection| RpcError { code: QUOTA_EXCEEDED, message: rejection.friendly_message() })?;
+
+        // Files are read from the tenant's workspace only
+        if matches!(method, "testGen" | "testReview")
+            && let Some(path) = params.get("path").and_then(Value::as_str)
+        {
+            let path = tenant.resolve_path(path)
+                .map_err(|e| RpcError { code: INVALID_PARAMS, message: e.to_string() })?;
+            params["path"] = json!(path);
+        }
+
+        // Decisions are attributed to the tenant, like the bot's commands
+        if matches!(method, "approve" | "reject")
+            && let Some(params) = params.as_object_mut()
+        {
+            let actor = params.get("actor").and_then(Value::as_str).unwrap_or("daemon");
+            params.insert("actor".to_string(), json!(format!("{}/{}", tenant.id(), actor)));
+        }
+
+        let result = self.dispatch(method, params, tenant.router(), &tenant.github()).await;
+        if let Err(e) = tenant.record_usage() {
+            tracing::warn!("Failed to record usage for tenant {}: {}", tenant.id(), e);
+        }
+
+        result
+    }
+
+    /// IDs of the tenants served, if any
+    fn tenant_ids(&self) -> Option<Vec<&str>> {
+        #[cfg(feature = "bot")]
+        return self.tenants.as_ref().map(|tenants| tenants.ids());
+        #[cfg(not(feature = "bot"))]
+        None
+    }
+
+    /// Dispatch a request to its handler
+    async fn dispatch(&self, method: &str, params: Value, router: &LazyRouter, #[cfg(feature = "github")] github: &GitHubConfig) -> std::result::Result<Value, RpcError> {
+        match method {
+            "initialize" => Ok(json!({
+                "name": "qitops",
+                "version": env!("CARGO_PKG_VERSION"),
+                "methods": METHODS,
+            })),
+            "status" => {
+                // Queue metrics only once the router is up, so a status check doesn't start it
+                let queues = match router.is_initialized() {
+                    true => router.get().await.ok().map(|router| router.queue_metrics()),
+                    false => None,
+                };
+                Ok(json!({
+                    "uptime_secs": self.started.elapsed().as_secs(),
+                    "requests": self.requests.load(Ordering::Relaxed),
+                    "router_ready": self.router.is_initialized(),
+                    "tenants": self.tenant_ids(),
+                    "llm_queues": queues,
+                }))
+            },
+            "capabilities" => crate::capabilities::Capabilities::collect()
+                .and_then(|capabilities| Ok(serde_json::to_value(capabilities)?))
+                .map_err(RpcError::internal),
+            "testGen" => {
+                let params: TestGenParams = parse_params(params)?;
+                let agent = TestGenAgent::new(params.path, &params.format, params.sources, params.personas, router.get().await.map_err(RpcError::internal)?)
+                    .await
+                    .map_err(RpcError::internal)?
+                    .with_function(params.function)
+                    .with_since(params.since);
+                run_agent(agent).await
+            },
+            "testReview" => {
+                let params: TestReviewParams = parse_params(params)?;
+                let agent = TestReviewAgent::new(params.path, params.sources, params.personas, router.get().await.map_err(RpcError::internal)?)
+                    .await
+                    .map_err(RpcError::internal)?;
+                run_agent(agent).await
+            },
+            "testData" => {
+                let params: TestDataParams = parse_params(params)?;
+                let agent = TestDataAgent::new(params.schema, params.count, params.constraints, params.format, router.get().await.map_err(RpcError::internal)?)
+                    .await
+                    .map_err(RpcError::internal)?
+                    .with_mask_fields(params.mask);
+                run_agent(agent).await
+            },
+            "risk" => {
+                let params: RiskParams = parse_params(params)?;
+                let baseline = params.baseline.parse().map_err(RpcError::internal)?;
+                let agent = RiskAgent::new_from_diff(params.diff, params.components, params.focus, router.get().await.map_err(RpcError::internal)?)
+                    .await
+                    .map_err(RpcError::internal)?
+                    .with_baseline(baseline);
+                run_agent(agent).await
+            },
+            #[cfg(feature = "github")]
+            "prAnalyze" => {
+                let params: PrAnalyzeParams = parse_params(params)?;
+                let baseline = params.baseline.parse().map_err(RpcError::internal)?;
+                let (owner, repo, pr) = resolve_pr(github, params.pr, params.owner, params.repo).map_err(RpcError::internal)?;
+                let github_client = GitHubClient::from_config(github).map_err(RpcError::internal)?;
+                let agent = PrAnalyzeAgent::new(pr, None, owner, repo, github_client, router.get().await.map_err(RpcError::internal)?)
+                    .await
+                    .map_err(RpcError::internal)?
+                    .with_baseline(baseline);
+                run_agent(agent).await
+            },
+            "approvals" => {
+                let params: ApprovalsParams = match params {
+                    Value::Null => ApprovalsParams::default(),
+                    params => parse_params(params)?,
+                };
+                let queue = approval_queue(router).await?;
+                let requests = queue.list(params.all).map_err(RpcError::internal)?;
+                serde_json::to_value(requests).map_err(|e| RpcError::internal(e.into()))
+            },
+            "approve" | "reject" => {
+                let params: DecisionParams = parse_params(params)?;
+                let queue = approval_queue(router).await?;
+                let actor = params.actor.unwrap_or_else(audit::current_actor);
+                let request = queue.decide(&params.id, method == "approve", &actor, params.note.as_deref())
+                    .map_err(|e| RpcError { code: INVALID_PARAMS, message: e.to_string() })?;
+                serde_json::to_value(request).map_err(|e| RpcError::internal(e.into()))
+            },
+            "shutdown" => Ok(Value::Null),
+            _ => Err(RpcError { code: METHOD_NOT_FOUND, message: format!("Unknown method: {}", method) }),
+        }
+    }
+}
+
+/// Resolve the repository and PR number from a PR URL or number
+#[cfg(feature = "github")]
+fn resolve_pr(github: &GitHubConfig, pr: String, owner: Option<String>, repo: Option<String>) -> Result<(String, String, String)> {
+    if let Ok((owner, repo)) = GitHubClient::extract_repo_info(&pr) {
+        let number = GitHubClient::extract_pr_number(&pr)?;
+        return Ok((owner, repo, number.to_string()));
+    }
+
+    let owner = owner.or_else(|| github.default_owner.clone())
+        .ok_or_else(|| anyhow!("Repository owner not specified and no default configured"))?;
+    let repo = repo.or_else(|| github.default_repo.clone())
+        .ok_or_else(|| anyhow!("Repository name not specified and no default configured"))?;
+
+    Ok((owner, repo, pr))
+}
+
+/// Approval queue of the router's configuration
+async fn approval_queue(router: &LazyRouter) -> std::result::Result<ApprovalQueue, RpcError> {
+    let router = router.get().await.map_err(RpcError::internal)?;
+    let config = router.approval_config()
+        .ok_or_else(|| RpcError { code: INVALID_PARAMS, message: "Prompt approval is not configured".to_string() })?;
+    ApprovalQueue::open(config).map_err(RpcError::internal)
+}
+
+/// Deserialize request parameters
+fn parse_params<T: DeserializeOwned>(params: Value) -> std::result::Result<T, RpcError> {
+    serde_json::from_value(params)
+        .map_err(|e| RpcError { code: INVALID_PARAMS, message: format!("Invalid params: {}", e) })
+}
+
+/// Execute an agent and serialize its response
+async fn run_agent<A: Agent>(agent: A) -> std::result::Result<Value, RpcError> {
+    let response: AgentResponse = agent.execute().await.map_err(RpcError::internal)?;
+    serde_json::to_value(response).map_err(|e| RpcError::internal(e.into()))
+}
+
+/// Read one Content-Length framed message, returning None at end of input
+async fn read_message<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> Result<Option<String>> {
+    let mut content_length = None;
+
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            content_length = Some(value.trim().parse::<usize>()?);
+        }
+    }
+
+    let length = content_length.ok_or_else(|| anyhow!("Missing Content-Length header"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+
+    Ok(Some(String::from_utf8(body)?))
+}
+
+/// Write one Content-Length framed message
+async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, message: &Value) -> Result<()> {
+    let body = serde_json::to_string(message)?;
+    writer.write_all(format!("Content-Length: {}\r\n\r\n{}", body.len(), body).as_bytes()).await?;
+    writer.flush().await?;
+
+    Ok(())
+}
+
+/// Build a JSON-RPC response
+fn response(id: Value, result: std::result::Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": error.code, "message": error.message } }),
+    }
+}
+
+/// Serve JSON-RPC requests over stdin/stdout until `exit` or end of input
+///
+/// Messages use the same Content-Length framing as the Language Server Protocol, so editor
+/// extensions can reuse their existing JSON-RPC clients. Requests run concurrently. With
+/// tenants, each request runs with the LLM router, GitHub configuration, quotas and budget
+/// of the tenant its `apiKey` belongs to.
+pub async fn run_stdio(#[cfg(feature = "bot")] tenants: Option<TenantRegistry>) -> Result<()> {
+    let state = Arc::new(DaemonState {
+        router: LazyRouter::new(),
+        #[cfg(feature = "github")]
+        github: GitHubConfigManager::new()
+            .map(|manager| manager.get_config().clone())
+            .unwrap_or_default(),
+        started: Instant::now(),
+        requests: AtomicU64::new(0),
+        #[cfg(feature = "bot")]
+        tenants,
+    });
+
+    // Warm the router in the background so the first request doesn't pay for it
+    let warm_state = state.clone();
+    tokio::spawn(async move {
+        if warm_state.tenant_ids().is_some() {
+            return;
+        }
+        if let Err(e) = warm_state.router.get().await {
+            tracing::warn!("Failed to initialize LLM router: {}", e);
+        }
+    });
+
+    // A single writer keeps concurrent responses from interleaving
+    let (tx, mut rx) = mpsc::unbounded_channel::<Value>();
+    let writer = tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Some(message) = rx.recv().await {
+            if let Err(e) = write_message(&mut stdout, &message).await {
+                tracing::error!("Failed to write response: {}", e);
+                break;
+            }
+        }
+    });
+
+    let mut reader = BufReader::new(tokio::io::stdin());
+    while let Some(body) = read_message(&mut reader).await? {
+        let request: Value = match serde_json::from_str(&body) {
+            Ok(request) => request,
+            Err(e) => {
+                let _ = tx.send(response(Value::Null, Err(RpcError { code: PARSE_ERROR, message: e.to_string() })));
+                continue;
+            }
+        };
+
+        let method = request.get("method").and_then(Value::as_str).unwrap_or_default().to_string();
+        if method == "exit" {
+            break;
+        }
+
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+        let id = request.get("id").cloned();
+        let state = state.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let result = state.handle(&method, params).await;
+            // Notifications (no id) get no response
+            if let Some(id) = id {
+                let _ = tx.send(response(id, result));
+            }
+        });
+    }
+
+    drop(tx);
+    let _ = writer.await;
+
+    // The blocking stdin reader would keep the runtime alive until the client closes the pipe
+    std::process::exit(0)
+}
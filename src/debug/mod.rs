@@ -0,0 +1,264 @@
+// Debug bundles: prompts, context and responses of a run, with secrets redacted
+
+use anyhow::{Result, anyhow};
+use regex::Regex;
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::llm::{LlmRequest, LlmResponse};
+
+/// Replacement for redacted secrets
+const REDACTED: &str = "[REDACTED]";
+
+/// Secrets shorter than this are not redacted by value, so short words aren't blanked out everywhere
+const MIN_SECRET_LEN: usize = 8;
+
+/// Recorder of the current run, if a bundle was requested
+static RECORDER: Mutex<Option<Recorder>> = Mutex::new(None);
+
+/// What a run has recorded so far
+struct Recorder {
+    /// When recording started
+    started: Instant,
+
+    /// When recording started, in seconds since the Unix epoch
+    started_at: u64,
+
+    /// Secret values to redact
+    secrets: Vec<String>,
+
+    /// Context pieces
+    pieces: Vec<Piece>,
+
+    /// LLM requests and their outcomes
+    exchanges: Vec<Exchange>,
+}
+
+/// A piece of context that went into a prompt
+#[derive(Debug, Clone, Serialize)]
+struct Piece {
+    /// Kind of piece, e.g. `source` or `persona`
+    kind: String,
+
+    /// Where it came from
+    name: String,
+
+    /// Content
+    content: String,
+}
+
+/// An LLM request and its outcome
+#[derive(Debug, Clone, Serialize)]
+struct Exchange {
+    /// Provider the request was sent to
+    provider: String,
+
+    /// Milliseconds from the start of the run to the request
+    started_ms: u64,
+
+    /// Milliseconds the request took
+    latency_ms: u64,
+
+    /// Whether the response came from the cache
+    cached: bool,
+
+    /// Request as sent
+    request: LlmRequest,
+
+    /// Response text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response: Option<String>,
+
+    /// Tokens used by the response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tokens_used: Option<usize>,
+
+    /// Error, if the request failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Start recording the run for a debug bundle
+pub fn start() {
+    let started_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    // Values of secret-looking environment variables are redacted wherever they appear
+    let secrets = std::env::vars()
+        .filter(|(name, _)| {
+            let name = name.to_uppercase();
+            ["KEY", "TOKEN", "SECRET", "PASSWORD"].iter().any(|word| name.contains(word))
+        })
+        .map(|(_, value)| value)
+        .filter(|value| value.len() >= MIN_SECRET_LEN)
+        .collect();
+
+    *lock() = Some(Recorder {
+        started: Instant::now(),
+        started_at,
+        secrets,
+        pieces: Vec::new(),
+        exchanges: Vec::new(),
+    });
+}
+
+/// Add secret values, such as configured API keys, to redact from the bundle
+pub fn add_secrets(secrets: impl IntoIterator<Item = String>) {
+    if let Some(recorder) = lock().as_mut() {
+        recorder.secrets.extend(secrets.into_iter().filter(|secret| secret.len() >= MIN_SECRET_LEN));
+    }
+}
+
+/// Record a piece of context, such as a source file or the persona prompt
+pub fn record_piece(kind: &str, name: &str, content: &str) {
+    if let Some(recorder) = lock().as_mut() {
+        recorder.pieces.push(Piece {
+            kind: kind.to_string(),
+            name: name.to_string(),
+            content: content.to_string(),
+        });
+    }
+}
+
+/// Record an LLM request sent at `started` and its outcome
+pub fn record_exchange(provider: &str, request: &LlmRequest, started: Instant, outcome: Result<&LlmResponse, &anyhow::Error>) {
+    if let Some(recorder) = lock().as_mut() {
+        let (response, tokens_used, cached, error) = match outcome {
+            Ok(response) => (Some(response.text.clone()), response.tokens_used, response.cached, None),
+            Err(e) => (None, None, false, Some(e.to_string())),
+        };
+
+        recorder.exchanges.push(Exchange {
+            provider: provider.to_string(),
+            started_ms: started.saturating_duration_since(recorder.started).as_millis() as u64,
+            latency_ms: started.elapsed().as_millis() as u64,
+            cached,
+            request: request.clone(),
+            response,
+            tokens_used,
+            error,
+        });
+    }
+}
+
+/// Write the recorded run to a zip archive, noting the error the run failed with
+///
+/// The archive holds `manifest.json` (command line, timing and outcome), one
+/// `exchanges/NNN-<provider>.json` per LLM request and one `context/NNN-<kind>.txt` per
+/// context piece.
+pub fn write_bundle(path: &Path, error: Option<&anyhow::Error>) -> Result<()> {
+    let recorder = lock().take()
+        .ok_or_else(|| anyhow!("No debug bundle is being recorded"))?;
+    let redactor = Redactor::new(&recorder.secrets);
+
+    let file = std::fs::File::create(path)
+        .map_err(|e| anyhow!("Failed to create debug bundle {}: {}", path.display(), e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default();
+
+    let manifest = serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "run_id": crate::audit::run_id(),
+        "command": redactor.redact(&std::env::args().collect::<Vec<String>>().join(" ")),
+        "started_at": recorder.started_at,
+        "duration_ms": recorder.started.elapsed().as_millis() as u64,
+        "error": error.map(|e| redactor.redact(&format!("{:#}", e))),
+        "exchanges": recorder.exchanges.iter().map(|exchange| serde_json::json!({
+            "provider": exchange.provider,
+            "model": exchange.request.model,
+            "started_ms": exchange.started_ms,
+            "latency_ms": exchange.latency_ms,
+            "cached": exchange.cached,
+            "failed": exchange.error.is_some(),
+        })).collect::<Vec<_>>(),
+        "context": recorder.pieces.iter().map(|piece| serde_json::json!({
+            "kind": piece.kind,
+            "name": redactor.redact(&piece.name),
+            "bytes": piece.content.len(),
+        })).collect::<Vec<_>>(),
+    });
+    zip.start_file("manifest.json", options)?;
+    zip.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+
+    for (index, exchange) in recorder.exchanges.iter().enumerate() {
+        let mut exchange = exchange.clone();
+        for message in &mut exchange.request.messages {
+            message.content = redactor.redact(&message.content);
+        }
+        exchange.response = exchange.response.map(|text| redactor.redact(&text));
+        exchange.error = exchange.error.map(|error| redactor.redact(&error));
+
+        zip.start_file(format!("exchanges/{:03}-{}.json", index + 1, exchange.provider), options)?;
+        zip.write_all(serde_json::to_string_pretty(&exchange)?.as_bytes())?;
+    }
+
+    for (index, piece) in recorder.pieces.iter().enumerate() {
+        zip.start_file(format!("context/{:03}-{}.txt", index + 1, piece.kind), options)?;
+        zip.write_all(format!("# {}\n\n{}", redactor.redact(&piece.name), redactor.redact(&piece.content)).as_bytes())?;
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+/// Redact common credential formats from text, such as a model answer echoing a token
+pub fn redact_credentials(text: &str) -> String {
+    Redactor::new(&[]).redact(text)
+}
+
+/// Lock the recorder, recovering from a panic in another thread
+fn lock() -> std::sync::MutexGuard<'static, Option<Recorder>> {
+    RECORDER.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Redacts known secret values and common credential formats
+struct Redactor<'a> {
+    /// Secret values
+    secrets: &'a [String],
+}
+
+impl<'a> Redactor<'a> {
+    /// Create a redactor for the given secret values
+    fn new(secrets: &'a [String]) -> Self {
+        Self { secrets }
+    }
+
+    /// Patterns of well-known credential formats, and of values assigned to secret-looking names
+    fn patterns() -> &'static [(Regex, &'static str)] {
+        static PATTERNS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+
+        PATTERNS.get_or_init(|| {
+            [
+                (r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----", REDACTED),
+                (r"\b(?:sk|pk|rk)-[A-Za-z0-9_-]{16,}", REDACTED),
+                (r"\bgh[pousr]_[A-Za-z0-9]{20,}", REDACTED),
+                (r"\bgithub_pat_[A-Za-z0-9_]{20,}", REDACTED),
+                (r"\bxox[abprs]-[A-Za-z0-9-]{10,}", REDACTED),
+                (r"\bAKIA[0-9A-Z]{16}\b", REDACTED),
+                (r"(?i)\b(bearer|basic)\s+[A-Za-z0-9._~+/=-]{12,}", "$1 [REDACTED]"),
+                (r#"(?i)\b([A-Za-z_]*(?:password|passwd|secret|api[_-]?key|token))(["']?\s*[:=]\s*["']?)[^\s"',;]+"#, "$1$2[REDACTED]"),
+            ]
+            .into_iter()
+            .map(|(pattern, replacement)| (Regex::new(pattern).expect("valid redaction pattern"), replacement))
+            .collect()
+        })
+    }
+
+    /// Redact secrets from text
+    fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for secret in self.secrets {
+            text = text.replace(secret.as_str(), REDACTED);
+        }
+        for (pattern, replacement) in Self::patterns() {
+            text = pattern.replace_all(&text, *replacement).into_owned();
+        }
+
+        text
+    }
+}
@@ -0,0 +1,204 @@
+// Error taxonomy: typed errors drive exit codes and hints instead of message text
+
+use thiserror::Error;
+
+#[cfg(feature = "github")]
+use crate::ci::github::GitHubError;
+use crate::llm::client::LlmError;
+
+/// Exit code for errors without a more specific category
+pub const EXIT_FAILURE: i32 = 1;
+
+/// Exit code for invalid input: bad arguments, missing files, unknown PRs
+pub const EXIT_INVALID_INPUT: i32 = 2;
+
+/// Exit code for missing or invalid configuration: API keys, tokens, sources, personas
+pub const EXIT_CONFIGURATION: i32 = 3;
+
+/// Exit code for credentials that were rejected
+pub const EXIT_AUTHENTICATION: i32 = 4;
+
+/// Exit code for services that couldn't be reached, including in offline mode
+pub const EXIT_UNAVAILABLE: i32 = 5;
+
+/// Exit code for rate limits and spending budgets
+pub const EXIT_LIMIT_EXCEEDED: i32 = 6;
+
+/// Exit code for runs that completed but failed their quality gate (`--gate`)
+pub const EXIT_GATE_FAILED: i32 = 7;
+
+/// QitOps error
+#[derive(Debug, Clone, Error)]
+pub enum QitOpsError {
+    /// LLM error
+    #[error(transparent)]
+    Llm(#[from] LlmError),
+
+    /// GitHub error
+    #[cfg(feature = "github")]
+    #[error(transparent)]
+    GitHub(#[from] GitHubError),
+
+    /// Invalid input to an agent (missing file, unknown format, malformed PR reference)
+    #[error("{0}")]
+    InvalidInput(String),
+
+    /// Source or persona that isn't configured or can't be read
+    #[error("{0}")]
+    Source(String),
+
+    /// Network access needed in offline mode
+    #[error("{0}")]
+    Offline(String),
+
+    /// Result signature that is missing, invalid or can't be checked
+    #[error("{0}")]
+    Attestation(String),
+
+    /// Run that failed its quality gate
+    #[error("{0}")]
+    GateFailed(String),
+
+    /// Risk score in a range configured to exit with its own code
+    #[error("{message}")]
+    RiskThreshold {
+        /// What the score was
+        message: String,
+        /// Exit code of the score's range
+        exit_code: i32,
+    },
+}
+
+impl QitOpsError {
+    /// Process exit code for the error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            QitOpsError::Llm(e) => match e {
+                LlmError::AuthError(_) => EXIT_AUTHENTICATION,
+                LlmError::ConfigurationError(_) => EXIT_CONFIGURATION,
+                LlmError::RateLimitError(_) | LlmError::BudgetExceeded(_) => EXIT_LIMIT_EXCEEDED,
+                LlmError::NetworkError(_) | LlmError::Timeout(_) | LlmError::ServerError(_)
+                    | LlmError::ProviderNotAvailable(_) => EXIT_UNAVAILABLE,
+                LlmError::ApiError(_) | LlmError::InvalidResponse(_) | LlmError::NotApproved(_) => EXIT_FAILURE,
+            },
+            #[cfg(feature = "github")]
+            QitOpsError::GitHub(e) => match e {
+                GitHubError::AuthError(_) | GitHubError::Forbidden(_) => EXIT_AUTHENTICATION,
+                GitHubError::ConfigurationError(_) => EXIT_CONFIGURATION,
+                GitHubError::NotFound(_) | GitHubError::ValidationError(_) => EXIT_INVALID_INPUT,
+                GitHubError::RateLimitError(_) => EXIT_LIMIT_EXCEEDED,
+                GitHubError::NetworkError(_) => EXIT_UNAVAILABLE,
+                GitHubError::ApiError { .. } => EXIT_FAILURE,
+            },
+            QitOpsError::InvalidInput(_) => EXIT_INVALID_INPUT,
+            QitOpsError::Source(_) => EXIT_CONFIGURATION,
+            QitOpsError::Offline(_) => EXIT_UNAVAILABLE,
+            QitOpsError::Attestation(_) => EXIT_FAILURE,
+            QitOpsError::GateFailed(_) => EXIT_GATE_FAILED,
+            QitOpsError::RiskThreshold { exit_code, .. } => *exit_code,
+        }
+    }
+
+    /// Short name of the kind of error, without any details, e.g. `llm.rate_limit`
+    #[cfg(feature = "monitoring")]
+    pub fn class(&self) -> &'static str {
+        match self {
+            QitOpsError::Llm(e) => match e {
+                LlmError::AuthError(_) => "llm.auth",
+                LlmError::ConfigurationError(_) => "llm.configuration",
+                LlmError::RateLimitError(_) => "llm.rate_limit",
+                LlmError::BudgetExceeded(_) => "llm.budget",
+                LlmError::NetworkError(_) => "llm.network",
+                LlmError::Timeout(_) => "llm.timeout",
+                LlmError::ServerError(_) => "llm.server",
+                LlmError::ProviderNotAvailable(_) => "llm.unavailable",
+                LlmError::InvalidResponse(_) => "llm.invalid_response",
+                LlmError::NotApproved(_) => "llm.not_approved",
+                LlmError::ApiError(_) => "llm.api",
+            },
+            #[cfg(feature = "github")]
+            QitOpsError::GitHub(e) => match e {
+                GitHubError::AuthError(_) => "github.auth",
+                GitHubError::Forbidden(_) => "github.forbidden",
+                GitHubError::ConfigurationError(_) => "github.configuration",
+                GitHubError::NotFound(_) => "github.not_found",
+                GitHubError::ValidationError(_) => "github.validation",
+                GitHubError::RateLimitError(_) => "github.rate_limit",
+                GitHubError::NetworkError(_) => "github.network",
+                GitHubError::ApiError { .. } => "github.api",
+            },
+            QitOpsError::InvalidInput(_) => "invalid_input",
+            QitOpsError::Source(_) => "source",
+            QitOpsError::Offline(_) => "offline",
+            QitOpsError::Attestation(_) => "attestation",
+            QitOpsError::GateFailed(_) => "gate_failed",
+            QitOpsError::RiskThreshold { .. } => "risk_threshold",
+        }
+    }
+
+    /// What the user can do about the error, if there is anything specific
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            QitOpsError::Llm(e) => match e {
+                LlmError::AuthError(_) => Some("Check the provider's API key with `qitops llm list`, or set OPENAI_API_KEY / ANTHROPIC_API_KEY"),
+                LlmError::ConfigurationError(_) => Some("Configure a provider with `qitops llm add` and pick the default with `qitops llm default`"),
+                LlmError::RateLimitError(_) => Some("Wait and try again, or set a rate limit with `qitops llm add --rpm --tpm` so requests are queued"),
+                LlmError::BudgetExceeded(_) => Some("Check spend with `qitops llm usage` and raise the limits in the `budget` section of config.json"),
+                LlmError::NetworkError(_) | LlmError::Timeout(_) => Some("Check your network connection and the provider's api_base, or configure a fallback with `qitops llm set-fallback`"),
+                LlmError::ProviderNotAvailable(_) => Some("Diagnose the configured providers with `qitops llm doctor`"),
+                LlmError::ServerError(_) => Some("The provider is having problems; try again later or configure a fallback with `qitops llm set-fallback`"),
+                LlmError::InvalidResponse(_) => Some("The model could not produce the expected structure; try a more capable model with `qitops llm default`"),
+                LlmError::NotApproved(_) => Some("A reviewer rejected the prompt or didn't decide in time; see `qitops approvals list --all`"),
+                LlmError::ApiError(_) => None,
+            },
+            #[cfg(feature = "github")]
+            QitOpsError::GitHub(e) => match e {
+                GitHubError::AuthError(_) => Some("Check your GitHub token with `qitops github status`, or set GITHUB_TOKEN"),
+                GitHubError::Forbidden(_) => Some("The GitHub token doesn't have access to this repository; check its scopes"),
+                GitHubError::ConfigurationError(_) => Some("Check the GitHub configuration with `qitops github status` and set it with `qitops github config`"),
+                GitHubError::NotFound(_) => Some("Check the repository owner, name and PR number"),
+                GitHubError::RateLimitError(_) => Some("Wait for the GitHub rate limit to reset and try again"),
+                GitHubError::NetworkError(_) => Some("Check your network connection and the GitHub api_base"),
+                GitHubError::ValidationError(_) | GitHubError::ApiError { .. } => None,
+            },
+            QitOpsError::InvalidInput(_) => Some("Run the command with --help to see the expected arguments"),
+            QitOpsError::Source(_) => Some("List the configured sources and personas with `qitops source list` and `qitops persona list`"),
+            QitOpsError::Offline(_) => Some("Run without --offline (and unset QITOPS_OFFLINE) to use this command"),
+            QitOpsError::Attestation(_) => Some("Verify the result file exactly as QitOps wrote it, with the key in QITOPS_SIGNING_KEY or signing.key_file that signed it"),
+            QitOpsError::GateFailed(_) | QitOpsError::RiskThreshold { .. } => None,
+        }
+    }
+}
+
+/// Find the typed error behind an error, looking through any added context
+///
+/// LLM and GitHub errors are usually returned bare rather than wrapped in [`QitOpsError`],
+/// so they are classified as if they had been.
+pub fn classify(error: &anyhow::Error) -> Option<QitOpsError> {
+    error.chain().find_map(|cause| {
+        if let Some(e) = cause.downcast_ref::<QitOpsError>() {
+            return Some(e.clone());
+        }
+        #[cfg(feature = "github")]
+        if let Some(e) = cause.downcast_ref::<GitHubError>() {
+            return Some(QitOpsError::GitHub(e.clone()));
+        }
+        cause.downcast_ref::<LlmError>().map(|e| QitOpsError::Llm(e.clone()))
+    })
+}
+
+/// Process exit code for an error
+pub fn exit_code(error: &anyhow::Error) -> i32 {
+    classify(error).map_or(EXIT_FAILURE, |e| e.exit_code())
+}
+
+/// Kind of an error for telemetry, `other` when it isn't typed
+#[cfg(feature = "monitoring")]
+pub fn error_class(error: &anyhow::Error) -> &'static str {
+    classify(error).map_or("other", |e| e.class())
+}
+
+/// What the user can do about an error, if there is anything specific
+pub fn hint(error: &anyhow::Error) -> Option<&'static str> {
+    classify(error).and_then(|e| e.hint())
+}
@@ -0,0 +1,38 @@
+// Running git and describing commit ages, shared by the agents and the CI fallbacks
+
+use anyhow::{Result, anyhow};
+use std::path::Path;
+use std::process::Command;
+
+/// Seconds in a day
+const DAY: u64 = 86_400;
+
+/// Run git in a directory, returning its output
+pub fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| anyhow!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!("{}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// How long ago something happened, from the seconds since, e.g. `3 hours ago`
+pub fn describe_age(seconds: u64) -> String {
+    let (amount, unit) = match seconds {
+        0..60 => return "just now".to_string(),
+        60..3600 => (seconds / 60, "minute"),
+        3600..DAY => (seconds / 3600, "hour"),
+        _ => match seconds / DAY {
+            days @ 0..60 => (days, "day"),
+            days @ 60..730 => (days / 30, "month"),
+            days => (days / 365, "year"),
+        },
+    };
+
+    format!("{} {}{} ago", amount, unit, if amount == 1 { "" } else { "s" })
+}
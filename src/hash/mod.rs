@@ -0,0 +1,13 @@
+// Hex digests for content hashes, cache keys and short IDs
+
+use sha2::{Digest, Sha256};
+
+/// Bytes in lowercase hex
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// SHA-256 of `bytes` in lowercase hex
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
@@ -0,0 +1,75 @@
+// Session histories, optionally encrypted at rest
+
+use aes_gcm::{Aes256Gcm, Key};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::masking;
+
+/// Keyring account holding the history encryption key
+pub const KEY_ACCOUNT: &str = "history";
+
+/// Environment variable holding a base64-encoded 256-bit history key, instead of the keyring
+pub const KEY_ENV: &str = "QITOPS_HISTORY_KEY";
+
+/// Extension added to encrypted history files
+pub const ENCRYPTED_EXTENSION: &str = "enc";
+
+/// History configuration (the `history` section of the config)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// Encrypt session transcripts with AES-256-GCM, keyed from the OS keyring
+    pub encrypt: bool,
+}
+
+/// The history key: `QITOPS_HISTORY_KEY`, else the keyring entry, else a new key saved to the keyring
+///
+/// The key is never written to disk, so encryption needs the keyring or the environment variable.
+pub fn load_key() -> Result<Key<Aes256Gcm>> {
+    match masking::stored_key(KEY_ACCOUNT, KEY_ENV, "history")? {
+        Some(key) => Ok(key),
+        None => masking::create_key(KEY_ACCOUNT, KEY_ENV, "history"),
+    }
+}
+
+/// Encrypt text, returning the contents of an encrypted history file
+pub fn encrypt(plaintext: &str, key: &Key<Aes256Gcm>) -> Result<String> {
+    masking::encrypt_file(plaintext.as_bytes(), key, "history")
+}
+
+/// Decrypt the contents of an encrypted history file
+pub fn decrypt(contents: &str, key: &Key<Aes256Gcm>) -> Result<String> {
+    let plaintext = masking::decrypt_file(contents, key, "history")?;
+    String::from_utf8(plaintext).map_err(|_| anyhow!("Decrypted history is not UTF-8"))
+}
+
+/// Write a history file, encrypted to `<path>.enc` when configured; returns the path written
+pub fn write(path: &Path, content: &str, config: &HistoryConfig) -> Result<PathBuf> {
+    if !config.encrypt {
+        fs::write(path, content)?;
+        return Ok(path.to_path_buf());
+    }
+
+    let mut encrypted_path = path.as_os_str().to_owned();
+    encrypted_path.push(format!(".{}", ENCRYPTED_EXTENSION));
+    let encrypted_path = PathBuf::from(encrypted_path);
+
+    crate::secrets::write_private(&encrypted_path, encrypt(content, &load_key()?)?.as_bytes())?;
+
+    Ok(encrypted_path)
+}
+
+/// Read a history file, decrypting it if it's encrypted
+pub fn read(path: &Path) -> Result<String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+
+    if path.extension().is_some_and(|ext| ext == ENCRYPTED_EXTENSION) {
+        decrypt(&contents, &load_key()?)
+    } else {
+        Ok(contents)
+    }
+}
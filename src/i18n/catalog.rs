@@ -0,0 +1,284 @@
+use crate::i18n::Locale;
+
+/// User-facing messages
+///
+/// Placeholders are written `{}` and filled in order by [`crate::i18n::tf`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    // Branding
+    Tagline,
+    DevelopedByTeam,
+    DevelopedBy,
+    VersionLine,
+    Hint,
+
+    // Command headers
+    LlmManagement,
+    GitHubIntegration,
+    SourceManagement,
+    PersonaManagement,
+    SchemaRegistry,
+    PromptTemplates,
+    AuditLog,
+    TestCaseManagement,
+    JiraIntegration,
+    ReportPublishing,
+    Notifications,
+    ResultVerification,
+    GeneratingTestCases,
+    GeneratingTestCasesFor,
+    AnalyzingPullRequest,
+    EstimatingRisk,
+    EstimatingRiskForChanges,
+    GeneratingTestData,
+    ReviewingExistingTests,
+    GeneratingPerformanceTests,
+    SummarizingRelease,
+    JoiningSession,
+    StartingSession,
+
+    // Result sections
+    TestCases,
+    Analysis,
+    RiskAssessment,
+    TestData,
+    TestReview,
+    Script,
+    AnnotatedDiff,
+    NewFindings,
+    CarriedOverFindings,
+
+    // Status
+    DebugBundleWritten,
+    DegradedAnalysis,
+    ScanIncomplete,
+    WatchingForChanges,
+    NoUncommittedChanges,
+    GatePassed,
+    GateFailed,
+    ReviewPosted,
+
+    // Bot
+    BotWelcome,
+    BotGoodbye,
+}
+
+impl Msg {
+    /// The message in a locale
+    pub fn text(self, locale: Locale) -> &'static str {
+        match locale {
+            Locale::En => english(self),
+            Locale::Es => spanish(self),
+            Locale::De => german(self),
+            Locale::Ja => japanese(self),
+        }
+    }
+}
+
+fn english(msg: Msg) -> &'static str {
+    match msg {
+        Msg::Tagline => "QitOps Agent v{} - AI-powered QA Assistant",
+        Msg::DevelopedByTeam => "Developed by QitOps Team",
+        Msg::DevelopedBy => "Developed by {}",
+        Msg::VersionLine => "QitOps Agent v{}",
+        Msg::Hint => "Hint:",
+        Msg::LlmManagement => "LLM Management",
+        Msg::GitHubIntegration => "GitHub Integration",
+        Msg::SourceManagement => "Source Management",
+        Msg::PersonaManagement => "Persona Management",
+        Msg::SchemaRegistry => "Schema Registry",
+        Msg::PromptTemplates => "Prompt Templates",
+        Msg::AuditLog => "Audit Log",
+        Msg::TestCaseManagement => "Test Case Management",
+        Msg::JiraIntegration => "Jira Integration",
+        Msg::ReportPublishing => "Report Publishing",
+        Msg::Notifications => "Notifications",
+        Msg::ResultVerification => "Result Verification",
+        Msg::GeneratingTestCases => "Generating Test Cases",
+        Msg::GeneratingTestCasesFor => "Generating Test Cases for {}",
+        Msg::AnalyzingPullRequest => "Analyzing Pull Request",
+        Msg::EstimatingRisk => "Estimating Risk",
+        Msg::EstimatingRiskForChanges => "Estimating Risk ({} file(s) changed)",
+        Msg::GeneratingTestData => "Generating Test Data",
+        Msg::ReviewingExistingTests => "Reviewing Existing Tests",
+        Msg::GeneratingPerformanceTests => "Generating Performance Tests",
+        Msg::SummarizingRelease => "Summarizing Release for QA",
+        Msg::JoiningSession => "Joining Interactive Testing Session",
+        Msg::StartingSession => "Starting Interactive Testing Session",
+        Msg::TestCases => "Test Cases",
+        Msg::Analysis => "Analysis",
+        Msg::RiskAssessment => "Risk Assessment",
+        Msg::TestData => "Test Data",
+        Msg::TestReview => "Test Review",
+        Msg::Script => "Script",
+        Msg::AnnotatedDiff => "Annotated Diff",
+        Msg::NewFindings => "New Findings",
+        Msg::CarriedOverFindings => "Unchanged Since the Last Analysis",
+        Msg::DebugBundleWritten => "Debug bundle written to {}",
+        Msg::DegradedAnalysis => "Degraded analysis: {}",
+        Msg::ScanIncomplete => "Scan incomplete: {}",
+        Msg::WatchingForChanges => "Watching {} for changes (Ctrl+C to stop)",
+        Msg::NoUncommittedChanges => "No uncommitted changes to assess",
+        Msg::GatePassed => "Quality gate passed (score {})",
+        Msg::GateFailed => "Quality gate failed (score {}): {}",
+        Msg::ReviewPosted => "Posted review {} with {} inline comment(s); {} finding(s) not on a changed line are in its summary",
+        Msg::BotWelcome => "Welcome to QitOps Bot! Type 'exit' or 'quit' to end the session.",
+        Msg::BotGoodbye => "Goodbye! Feel free to chat again if you need help with QitOps Agent.",
+    }
+}
+
+fn spanish(msg: Msg) -> &'static str {
+    match msg {
+        Msg::Tagline => "QitOps Agent v{} - Asistente de QA impulsado por IA",
+        Msg::DevelopedByTeam => "Desarrollado por el equipo de QitOps",
+        Msg::DevelopedBy => "Desarrollado por {}",
+        Msg::VersionLine => "QitOps Agent v{}",
+        Msg::Hint => "Sugerencia:",
+        Msg::LlmManagement => "Gestión de LLM",
+        Msg::GitHubIntegration => "Integración con GitHub",
+        Msg::SourceManagement => "Gestión de fuentes",
+        Msg::PersonaManagement => "Gestión de perfiles",
+        Msg::SchemaRegistry => "Registro de esquemas",
+        Msg::PromptTemplates => "Plantillas de prompts",
+        Msg::AuditLog => "Registro de auditoría",
+        Msg::TestCaseManagement => "Gestión de casos de prueba",
+        Msg::JiraIntegration => "Integración con Jira",
+        Msg::ReportPublishing => "Publicación de informes",
+        Msg::Notifications => "Notificaciones",
+        Msg::ResultVerification => "Verificación de resultados",
+        Msg::GeneratingTestCases => "Generando casos de prueba",
+        Msg::GeneratingTestCasesFor => "Generando casos de prueba para {}",
+        Msg::AnalyzingPullRequest => "Analizando la pull request",
+        Msg::EstimatingRisk => "Estimando el riesgo",
+        Msg::EstimatingRiskForChanges => "Estimando el riesgo ({} archivo(s) modificado(s))",
+        Msg::GeneratingTestData => "Generando datos de prueba",
+        Msg::ReviewingExistingTests => "Revisando las pruebas existentes",
+        Msg::GeneratingPerformanceTests => "Generando pruebas de rendimiento",
+        Msg::SummarizingRelease => "Resumiendo la versión para QA",
+        Msg::JoiningSession => "Uniéndose a la sesión de pruebas interactiva",
+        Msg::StartingSession => "Iniciando la sesión de pruebas interactiva",
+        Msg::TestCases => "Casos de prueba",
+        Msg::Analysis => "Análisis",
+        Msg::RiskAssessment => "Evaluación de riesgos",
+        Msg::TestData => "Datos de prueba",
+        Msg::TestReview => "Revisión de pruebas",
+        Msg::Script => "Script",
+        Msg::AnnotatedDiff => "Diff anotado",
+        Msg::NewFindings => "Nuevos hallazgos",
+        Msg::CarriedOverFindings => "Sin cambios desde el último análisis",
+        Msg::DebugBundleWritten => "Paquete de depuración escrito en {}",
+        Msg::DegradedAnalysis => "Análisis degradado: {}",
+        Msg::ScanIncomplete => "Escaneo incompleto: {}",
+        Msg::WatchingForChanges => "Vigilando cambios en {} (Ctrl+C para detener)",
+        Msg::NoUncommittedChanges => "No hay cambios sin confirmar que evaluar",
+        Msg::GatePassed => "Quality gate superado (puntuación {})",
+        Msg::GateFailed => "Quality gate no superado (puntuación {}): {}",
+        Msg::ReviewPosted => "Revisión {} publicada con {} comentario(s) en línea; {} hallazgo(s) fuera de las líneas modificadas están en su resumen",
+        Msg::BotWelcome => "¡Bienvenido a QitOps Bot! Escribe 'exit' o 'quit' para terminar la sesión.",
+        Msg::BotGoodbye => "¡Hasta luego! Vuelve cuando necesites ayuda con QitOps Agent.",
+    }
+}
+
+fn german(msg: Msg) -> &'static str {
+    match msg {
+        Msg::Tagline => "QitOps Agent v{} - KI-gestützter QA-Assistent",
+        Msg::DevelopedByTeam => "Entwickelt vom QitOps-Team",
+        Msg::DevelopedBy => "Entwickelt von {}",
+        Msg::VersionLine => "QitOps Agent v{}",
+        Msg::Hint => "Hinweis:",
+        Msg::LlmManagement => "LLM-Verwaltung",
+        Msg::GitHubIntegration => "GitHub-Integration",
+        Msg::SourceManagement => "Quellenverwaltung",
+        Msg::PersonaManagement => "Persona-Verwaltung",
+        Msg::SchemaRegistry => "Schema-Registry",
+        Msg::PromptTemplates => "Prompt-Vorlagen",
+        Msg::AuditLog => "Audit-Log",
+        Msg::TestCaseManagement => "Testfallverwaltung",
+        Msg::JiraIntegration => "Jira-Integration",
+        Msg::ReportPublishing => "Berichtsveröffentlichung",
+        Msg::Notifications => "Benachrichtigungen",
+        Msg::ResultVerification => "Ergebnisprüfung",
+        Msg::GeneratingTestCases => "Testfälle werden generiert",
+        Msg::GeneratingTestCasesFor => "Testfälle für {} werden generiert",
+        Msg::AnalyzingPullRequest => "Pull Request wird analysiert",
+        Msg::EstimatingRisk => "Risiko wird bewertet",
+        Msg::EstimatingRiskForChanges => "Risiko wird bewertet ({} Datei(en) geändert)",
+        Msg::GeneratingTestData => "Testdaten werden generiert",
+        Msg::ReviewingExistingTests => "Vorhandene Tests werden geprüft",
+        Msg::GeneratingPerformanceTests => "Performancetests werden generiert",
+        Msg::SummarizingRelease => "Release wird für QA zusammengefasst",
+        Msg::JoiningSession => "Interaktiver Testsitzung wird beigetreten",
+        Msg::StartingSession => "Interaktive Testsitzung wird gestartet",
+        Msg::TestCases => "Testfälle",
+        Msg::Analysis => "Analyse",
+        Msg::RiskAssessment => "Risikobewertung",
+        Msg::TestData => "Testdaten",
+        Msg::TestReview => "Testprüfung",
+        Msg::Script => "Skript",
+        Msg::AnnotatedDiff => "Kommentierter Diff",
+        Msg::NewFindings => "Neue Befunde",
+        Msg::CarriedOverFindings => "Unverändert seit der letzten Analyse",
+        Msg::DebugBundleWritten => "Debug-Paket nach {} geschrieben",
+        Msg::DegradedAnalysis => "Eingeschränkte Analyse: {}",
+        Msg::ScanIncomplete => "Scan unvollständig: {}",
+        Msg::WatchingForChanges => "{} wird auf Änderungen überwacht (Strg+C zum Beenden)",
+        Msg::NoUncommittedChanges => "Keine nicht committeten Änderungen zu bewerten",
+        Msg::GatePassed => "Quality Gate bestanden (Punktzahl {})",
+        Msg::GateFailed => "Quality Gate nicht bestanden (Punktzahl {}): {}",
+        Msg::ReviewPosted => "Review {} mit {} Inline-Kommentar(en) veröffentlicht; {} Befund(e) außerhalb geänderter Zeilen stehen in der Zusammenfassung",
+        Msg::BotWelcome => "Willkommen bei QitOps Bot! Gib 'exit' oder 'quit' ein, um die Sitzung zu beenden.",
+        Msg::BotGoodbye => "Auf Wiedersehen! Melde dich gern wieder, wenn du Hilfe mit QitOps Agent brauchst.",
+    }
+}
+
+fn japanese(msg: Msg) -> &'static str {
+    match msg {
+        Msg::Tagline => "QitOps Agent v{} - AI搭載のQAアシスタント",
+        Msg::DevelopedByTeam => "開発: QitOps チーム",
+        Msg::DevelopedBy => "開発: {}",
+        Msg::VersionLine => "QitOps Agent v{}",
+        Msg::Hint => "ヒント:",
+        Msg::LlmManagement => "LLM 管理",
+        Msg::GitHubIntegration => "GitHub 連携",
+        Msg::SourceManagement => "ソース管理",
+        Msg::PersonaManagement => "ペルソナ管理",
+        Msg::SchemaRegistry => "スキーマレジストリ",
+        Msg::PromptTemplates => "プロンプトテンプレート",
+        Msg::AuditLog => "監査ログ",
+        Msg::TestCaseManagement => "テストケース管理",
+        Msg::JiraIntegration => "Jira 連携",
+        Msg::ReportPublishing => "レポートの公開",
+        Msg::Notifications => "通知",
+        Msg::ResultVerification => "結果の検証",
+        Msg::GeneratingTestCases => "テストケースを生成しています",
+        Msg::GeneratingTestCasesFor => "{} のテストケースを生成しています",
+        Msg::AnalyzingPullRequest => "プルリクエストを分析しています",
+        Msg::EstimatingRisk => "リスクを評価しています",
+        Msg::EstimatingRiskForChanges => "リスクを評価しています ({} 個のファイルが変更済み)",
+        Msg::GeneratingTestData => "テストデータを生成しています",
+        Msg::ReviewingExistingTests => "既存のテストをレビューしています",
+        Msg::GeneratingPerformanceTests => "パフォーマンステストを生成しています",
+        Msg::SummarizingRelease => "リリースのQAサマリーを作成しています",
+        Msg::JoiningSession => "インタラクティブテストセッションに参加しています",
+        Msg::StartingSession => "インタラクティブテストセッションを開始しています",
+        Msg::TestCases => "テストケース",
+        Msg::Analysis => "分析",
+        Msg::RiskAssessment => "リスク評価",
+        Msg::TestData => "テストデータ",
+        Msg::TestReview => "テストレビュー",
+        Msg::Script => "スクリプト",
+        Msg::AnnotatedDiff => "注釈付き差分",
+        Msg::NewFindings => "新しい指摘事項",
+        Msg::CarriedOverFindings => "前回の分析以降変更なし",
+        Msg::DebugBundleWritten => "デバッグバンドルを {} に書き出しました",
+        Msg::DegradedAnalysis => "縮退モードでの分析: {}",
+        Msg::ScanIncomplete => "スキャンが途中で終了しました: {}",
+        Msg::WatchingForChanges => "{} の変更を監視しています (Ctrl+C で停止)",
+        Msg::NoUncommittedChanges => "評価する未コミットの変更はありません",
+        Msg::GatePassed => "品質ゲートに合格しました (スコア {})",
+        Msg::GateFailed => "品質ゲートに不合格です (スコア {}): {}",
+        Msg::ReviewPosted => "レビュー {} を {} 件のインラインコメント付きで投稿しました。変更行以外の {} 件の指摘事項はサマリーに記載されています",
+        Msg::BotWelcome => "QitOps Bot へようこそ! セッションを終了するには 'exit' または 'quit' と入力してください。",
+        Msg::BotGoodbye => "さようなら! QitOps Agent でお困りの際はいつでもどうぞ。",
+    }
+}
@@ -0,0 +1,89 @@
+// Localized user-facing messages
+
+mod catalog;
+
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+pub use catalog::Msg;
+
+/// Languages the CLI speaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    /// English
+    En,
+    /// Spanish
+    Es,
+    /// German
+    De,
+    /// Japanese
+    Ja,
+}
+
+impl Locale {
+    /// Parse a locale tag such as `de`, `es_ES.UTF-8` or `ja-JP`
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag.split(['_', '-', '.', '@']).next().unwrap_or_default().to_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "es" => Some(Locale::Es),
+            "de" => Some(Locale::De),
+            "ja" => Some(Locale::Ja),
+            _ => None,
+        }
+    }
+}
+
+/// Locale for the rest of the process
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// Choose the locale: the `locale` in config.json, then `LC_ALL`, `LC_MESSAGES` and `LANG`
+///
+/// Unsupported or unset locales fall back to English. Only the first call has an effect.
+pub fn init(configured: Option<&str>) {
+    let _ = LOCALE.set(resolve(configured));
+}
+
+/// Resolve the locale from the configured value and the environment
+fn resolve(configured: Option<&str>) -> Locale {
+    if let Some(locale) = configured.and_then(Locale::from_tag) {
+        return locale;
+    }
+
+    ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| Locale::from_tag(&value))
+        .unwrap_or(Locale::En)
+}
+
+/// The locale in use
+pub fn locale() -> Locale {
+    *LOCALE.get_or_init(|| resolve(None))
+}
+
+/// A message in the current locale
+pub fn t(msg: Msg) -> &'static str {
+    msg.text(locale())
+}
+
+/// A message in the current locale with its `{}` placeholders filled in order
+pub fn tf(msg: Msg, args: &[&dyn Display]) -> String {
+    fill(t(msg), args)
+}
+
+/// Fill `{}` placeholders in order
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+    let mut result = parts.next().unwrap_or_default().to_string();
+
+    for part in parts {
+        if let Some(arg) = args.next() {
+            result.push_str(&arg.to_string());
+        }
+        result.push_str(part);
+    }
+
+    result
+}
@@ -0,0 +1,293 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::agent::findings::Finding;
+use crate::audit::{self, AuditAction, AuditEvent};
+use crate::integrations::{setting, urlencode};
+use crate::severity::Severity;
+
+/// Prefix of the label carrying a finding's fingerprint, used to find issues filed on earlier runs
+pub const FINGERPRINT_LABEL_PREFIX: &str = "qitops-";
+
+/// Jira configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JiraConfig {
+    /// Instance URL, e.g. `https://acme.atlassian.net`
+    #[serde(default)]
+    pub url: Option<String>,
+
+    /// Account email for Jira Cloud; without one the token is sent as a bearer token (Data Center)
+    #[serde(default)]
+    pub email: Option<String>,
+
+    /// API token or personal access token
+    #[serde(default)]
+    pub api_token: Option<String>,
+
+    /// Project key issues are filed in
+    #[serde(default)]
+    pub project: Option<String>,
+
+    /// Issue type of filed issues
+    #[serde(default = "default_issue_type")]
+    pub issue_type: String,
+
+    /// Labels added to filed issues
+    #[serde(default)]
+    pub labels: Vec<String>,
+
+    /// Lowest finding severity that gets an issue
+    #[serde(default = "default_min_severity")]
+    pub min_severity: Severity,
+
+    /// HTTP(S) proxy URL (default: the `HTTPS_PROXY`/`HTTP_PROXY` environment variables)
+    #[serde(default)]
+    pub proxy: Option<String>,
+}
+
+fn default_issue_type() -> String {
+    "Bug".to_string()
+}
+
+fn default_min_severity() -> Severity {
+    Severity::High
+}
+
+impl Default for JiraConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            email: None,
+            api_token: None,
+            project: None,
+            issue_type: default_issue_type(),
+            labels: Vec::new(),
+            min_severity: default_min_severity(),
+            proxy: None,
+        }
+    }
+}
+
+/// Jira configuration manager, storing `jira.json` in the QitOps config directory
+pub struct JiraConfigManager {
+    /// Configuration file path
+    config_path: PathBuf,
+
+    /// Configuration
+    config: JiraConfig,
+}
+
+impl JiraConfigManager {
+    /// Create a new configuration manager
+    pub fn new() -> Result<Self> {
+        let config_path = crate::config::config_dir()?.join("jira.json");
+
+        let config = if config_path.exists() {
+            let config_str = fs::read_to_string(&config_path)
+                .map_err(|e| anyhow!("Failed to read config file: {}", e))?;
+
+            serde_json::from_str(&config_str)
+                .map_err(|e| anyhow!("Failed to parse config file: {}", e))?
+        } else {
+            JiraConfig::default()
+        };
+
+        Ok(Self {
+            config_path,
+            config,
+        })
+    }
+
+    /// Get the configuration
+    pub fn get_config(&self) -> &JiraConfig {
+        &self.config
+    }
+
+    /// Replace the configuration
+    pub fn set_config(&mut self, config: JiraConfig) -> Result<()> {
+        self.config = config;
+        self.save_config()
+    }
+
+    /// Save the configuration
+    pub fn save_config(&self) -> Result<()> {
+        let config_str = serde_json::to_string_pretty(&self.config)
+            .map_err(|e| anyhow!("Failed to serialize config: {}", e))?;
+
+        fs::write(&self.config_path, config_str)
+            .map_err(|e| anyhow!("Failed to write config file: {}", e))?;
+
+        Ok(())
+    }
+}
+
+/// Jira REST API v2 client
+pub struct JiraClient {
+    http_client: crate::proxy::RemoteClient,
+    base_url: String,
+    email: Option<String>,
+    api_token: String,
+}
+
+impl JiraClient {
+    /// Create a client
+    pub fn new(base_url: &str, email: Option<String>, api_token: String) -> Self {
+        Self {
+            http_client: crate::proxy::RemoteClient::new("Jira", reqwest::Client::new()),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            email,
+            api_token,
+        }
+    }
+
+    /// Create a client from configuration or `JIRA_URL`, `JIRA_EMAIL` and `JIRA_API_TOKEN`
+    pub fn from_config(config: &JiraConfig) -> Result<Self> {
+
+        let email = config.email.clone()
+            .or_else(|| std::env::var("JIRA_EMAIL").ok().filter(|email| !email.is_empty()));
+
+        Ok(Self {
+            http_client: crate::proxy::remote("Jira", config.proxy.as_deref())?,
+            ..Self::new(
+                &setting(config.url.as_deref(), "JIRA_URL", "Jira URL", "qitops jira config")?,
+                email,
+                setting(config.api_token.as_deref(), "JIRA_API_TOKEN", "Jira API token", "qitops jira config")?,
+            )
+        })
+    }
+
+    /// Send an API request
+    async fn request(&self, method: reqwest::Method, path: &str, body: Option<Value>) -> Result<Value> {
+        let mut request = self.http_client.request(method, format!("{}/rest/api/2/{}", self.base_url, path))?;
+        request = match &self.email {
+            Some(email) => request.basic_auth(email, Some(&self.api_token)),
+            None => request.bearer_auth(&self.api_token),
+        };
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request.send().await
+            .map_err(|e| anyhow!("Failed to send request to Jira: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "Could not read error response".to_string());
+
+            return match status.as_u16() {
+                401 => Err(anyhow!("Jira authentication error: {}", error_text)),
+                403 => Err(anyhow!("Jira access denied: {}", error_text)),
+                404 => Err(anyhow!("Jira resource not found: {}", error_text)),
+                _ => Err(anyhow!("Jira API error ({}): {}", status, error_text)),
+            };
+        }
+
+        response.json().await
+            .map_err(|e| anyhow!("Failed to parse Jira response: {}", e))
+    }
+
+    /// Key of an issue in a project carrying a label
+    pub async fn find_issue_with_label(&self, project: &str, label: &str) -> Result<Option<String>> {
+        let jql = format!("project = \"{}\" AND labels = \"{}\"", project, label);
+        let query = format!("jql={}&fields=key&maxResults=1", urlencode(&jql));
+
+        // Jira Cloud replaced `search` with `search/jql`; Data Center only has `search`
+        let result = match self.request(reqwest::Method::GET, &format!("search/jql?{}", query), None).await {
+            Ok(result) => result,
+            Err(_) => self.request(reqwest::Method::GET, &format!("search?{}", query), None).await?,
+        };
+
+        Ok(result["issues"].as_array()
+            .and_then(|issues| issues.first())
+            .and_then(|issue| issue["key"].as_str())
+            .map(str::to_string))
+    }
+
+    /// Create an issue, returning its key
+    pub async fn create_issue(&self, project: &str, issue_type: &str, summary: &str, description: &str, labels: &[String]) -> Result<String> {
+        let created = self.request(reqwest::Method::POST, "issue", Some(json!({
+            "fields": {
+                "project": { "key": project },
+                "issuetype": { "name": issue_type },
+                "summary": summary,
+                "description": description,
+                "labels": labels,
+            }
+        }))).await?;
+
+        created["key"].as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("Jira did not return an issue key"))
+    }
+}
+
+/// Label identifying the issue filed for a finding
+pub fn fingerprint_label(finding: &Finding) -> String {
+    format!("{}{}", FINGERPRINT_LABEL_PREFIX, finding.fingerprint())
+}
+
+/// Issue description for a finding, in Jira wiki markup
+fn issue_description(finding: &Finding, source: &str) -> String {
+    let mut description = format!("*Severity:* {}\n", finding.severity);
+    let location = finding.location();
+    if !location.is_empty() {
+        description.push_str(&format!("*Location:* {{{{{}}}}}\n", location));
+    }
+    description.push_str(&format!("*Found in:* {}\n", source));
+    if !finding.description.is_empty() {
+        description.push_str(&format!("\n{}\n", finding.description));
+    }
+    description.push_str(&format!("\n_Filed by QitOps Agent (fingerprint {})_", finding.fingerprint()));
+
+    description
+}
+
+/// Outcome of filing issues for findings
+#[derive(Debug, Clone, Default)]
+pub struct FiledIssues {
+    /// Created issues as (key, finding title)
+    pub created: Vec<(String, String)>,
+
+    /// Issues filed on earlier runs as (key, finding title)
+    pub existing: Vec<(String, String)>,
+}
+
+/// File an issue for each finding at or above the configured severity, skipping findings
+/// that already have one
+///
+/// `source` describes where the findings came from, e.g. `acme/web#42`.
+pub async fn file_findings(client: &JiraClient, config: &JiraConfig, findings: &[Finding], source: &str) -> Result<FiledIssues> {
+    let project = config.project.as_deref()
+        .ok_or_else(|| anyhow!("Jira project not configured; run `qitops jira config --project <KEY>`"))?;
+
+    let mut filed = FiledIssues::default();
+    for finding in findings.iter().filter(|finding| finding.severity >= config.min_severity) {
+        let label = fingerprint_label(finding);
+
+        if let Some(key) = client.find_issue_with_label(project, &label).await? {
+            filed.existing.push((key, finding.title.clone()));
+            continue;
+        }
+
+        let mut labels = config.labels.clone();
+        labels.push(label);
+        let summary = format!("[{}] {}", finding.severity, finding.title);
+
+        let result = client.create_issue(project, &config.issue_type, &summary, &issue_description(finding, source), &labels).await;
+
+        let mut event = AuditEvent::new(audit::current_actor(), AuditAction::IssueCreated, project)
+            .with_outcome(&result);
+        if let Ok(key) = &result {
+            event.details = Some(format!("{}: {}", key, finding.title));
+        }
+        audit::record(event);
+
+        filed.created.push((result?, finding.title.clone()));
+    }
+
+    Ok(filed)
+}
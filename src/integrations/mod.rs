@@ -0,0 +1,23 @@
+// Third-party tool integrations
+pub mod jira;
+pub mod publish;
+pub mod tcm;
+
+use anyhow::{Result, anyhow};
+
+/// Read a setting from configuration, falling back to an environment variable
+pub(crate) fn setting(configured: Option<&str>, env: &str, description: &str, configure_command: &str) -> Result<String> {
+    configured.map(str::to_string)
+        .or_else(|| std::env::var(env).ok().filter(|value| !value.is_empty()))
+        .ok_or_else(|| anyhow!("{} not configured; run `{}` or set {}", description, configure_command, env))
+}
+
+/// Percent-encode a query parameter value
+pub(crate) fn urlencode(value: &str) -> String {
+    value.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
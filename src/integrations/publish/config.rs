@@ -0,0 +1,99 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Confluence configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfluenceConfig {
+    /// Base URL, e.g. `https://acme.atlassian.net/wiki` for Confluence Cloud
+    pub url: String,
+
+    /// Account email for Confluence Cloud; without one the token is sent as a bearer token (Data Center)
+    #[serde(default)]
+    pub email: Option<String>,
+
+    /// API token or personal access token
+    pub api_token: String,
+}
+
+/// Report publishing configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PublishConfig {
+    /// Confluence
+    #[serde(default)]
+    pub confluence: Option<ConfluenceConfig>,
+
+    /// Remote that report branches are pushed to (defaults to `origin`)
+    #[serde(default)]
+    pub git_remote: Option<String>,
+
+    /// HTTP(S) proxy URL for Confluence (default: the `HTTPS_PROXY`/`HTTP_PROXY` environment variables)
+    #[serde(default)]
+    pub proxy: Option<String>,
+}
+
+/// Report publishing configuration manager, storing `publish.json` in the QitOps config directory
+pub struct PublishConfigManager {
+    /// Configuration file path
+    config_path: PathBuf,
+
+    /// Configuration
+    config: PublishConfig,
+}
+
+impl PublishConfigManager {
+    /// Create a new configuration manager
+    pub fn new() -> Result<Self> {
+        let config_path = crate::config::config_dir()?.join("publish.json");
+
+        let config = if config_path.exists() {
+            let config_str = fs::read_to_string(&config_path)
+                .map_err(|e| anyhow!("Failed to read config file: {}", e))?;
+
+            serde_json::from_str(&config_str)
+                .map_err(|e| anyhow!("Failed to parse config file: {}", e))?
+        } else {
+            PublishConfig::default()
+        };
+
+        Ok(Self {
+            config_path,
+            config,
+        })
+    }
+
+    /// Get the configuration
+    pub fn get_config(&self) -> &PublishConfig {
+        &self.config
+    }
+
+    /// Set the Confluence configuration
+    pub fn set_confluence(&mut self, config: ConfluenceConfig) -> Result<()> {
+        self.config.confluence = Some(config);
+        self.save_config()
+    }
+
+    /// Set the remote report branches are pushed to
+    pub fn set_git_remote(&mut self, remote: String) -> Result<()> {
+        self.config.git_remote = Some(remote);
+        self.save_config()
+    }
+
+    /// Set the proxy
+    pub fn set_proxy(&mut self, proxy: String) -> Result<()> {
+        self.config.proxy = Some(proxy);
+        self.save_config()
+    }
+
+    /// Save the configuration
+    pub fn save_config(&self) -> Result<()> {
+        let config_str = serde_json::to_string_pretty(&self.config)
+            .map_err(|e| anyhow!("Failed to serialize config: {}", e))?;
+
+        fs::write(&self.config_path, config_str)
+            .map_err(|e| anyhow!("Failed to write config file: {}", e))?;
+
+        Ok(())
+    }
+}
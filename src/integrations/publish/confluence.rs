@@ -0,0 +1,134 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+
+use crate::integrations::publish::config::ConfluenceConfig;
+use crate::integrations::publish::{PublishOutcome, Publisher, Report};
+use crate::integrations::{setting, urlencode};
+
+/// Publishes reports as pages in a Confluence space, matched by title
+pub struct ConfluencePublisher {
+    http_client: crate::proxy::RemoteClient,
+    base_url: String,
+    email: Option<String>,
+    api_token: String,
+    space: String,
+    parent: Option<String>,
+}
+
+impl ConfluencePublisher {
+    /// Create a publisher
+    pub fn new(base_url: &str, email: Option<String>, api_token: String, space: String, parent: Option<String>) -> Self {
+        Self {
+            http_client: crate::proxy::RemoteClient::new("Confluence", reqwest::Client::new()),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            email,
+            api_token,
+            space,
+            parent,
+        }
+    }
+
+    /// Create a publisher from configuration or `CONFLUENCE_URL`, `CONFLUENCE_EMAIL` and `CONFLUENCE_API_TOKEN`
+    pub fn from_config(config: Option<&ConfluenceConfig>, proxy: Option<&str>, space: String, parent: Option<String>) -> Result<Self> {
+        let email = config.and_then(|c| c.email.clone())
+            .or_else(|| std::env::var("CONFLUENCE_EMAIL").ok().filter(|email| !email.is_empty()));
+
+        Ok(Self {
+            http_client: crate::proxy::remote("Confluence", proxy)?,
+            ..Self::new(
+                &setting(config.map(|c| c.url.as_str()), "CONFLUENCE_URL", "Confluence URL", "qitops publish config")?,
+                email,
+                setting(config.map(|c| c.api_token.as_str()), "CONFLUENCE_API_TOKEN", "Confluence API token", "qitops publish config")?,
+                space,
+                parent,
+            )
+        })
+    }
+
+    /// Send an API request
+    async fn request(&self, method: reqwest::Method, path: &str, body: Option<Value>) -> Result<Value> {
+        let mut request = self.http_client.request(method, format!("{}/rest/api/{}", self.base_url, path))?;
+        request = match &self.email {
+            Some(email) => request.basic_auth(email, Some(&self.api_token)),
+            None => request.bearer_auth(&self.api_token),
+        };
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request.send().await
+            .map_err(|e| anyhow!("Failed to send request to Confluence: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "Could not read error response".to_string());
+
+            return match status.as_u16() {
+                401 => Err(anyhow!("Confluence authentication error: {}", error_text)),
+                403 => Err(anyhow!("Confluence access denied: {}", error_text)),
+                404 => Err(anyhow!("Confluence resource not found: {}", error_text)),
+                _ => Err(anyhow!("Confluence API error ({}): {}", status, error_text)),
+            };
+        }
+
+        response.json().await
+            .map_err(|e| anyhow!("Failed to parse Confluence response: {}", e))
+    }
+
+    /// Browser URL of a page
+    fn page_url(&self, page: &Value) -> String {
+        page["_links"]["webui"].as_str()
+            .map(|path| format!("{}{}", self.base_url, path))
+            .unwrap_or_else(|| format!("{}/pages/viewpage.action?pageId={}", self.base_url, page["id"].as_str().unwrap_or_default()))
+    }
+}
+
+#[async_trait]
+impl Publisher for ConfluencePublisher {
+    async fn publish(&self, report: &Report) -> Result<PublishOutcome> {
+        let html = report.html();
+
+        let found = self.request(
+            reqwest::Method::GET,
+            &format!("content?spaceKey={}&title={}&type=page&expand=version,body.storage", urlencode(&self.space), urlencode(&report.title)),
+            None,
+        ).await?;
+
+        match found["results"].as_array().and_then(|results| results.first()) {
+            Some(page) => {
+                let id = page["id"].as_str()
+                    .ok_or_else(|| anyhow!("Confluence did not return a page ID"))?;
+                if page["body"]["storage"]["value"].as_str() == Some(html.as_str()) {
+                    return Ok(PublishOutcome::Unchanged(self.page_url(page)));
+                }
+
+                let version = page["version"]["number"].as_u64().unwrap_or(1);
+                let updated = self.request(reqwest::Method::PUT, &format!("content/{}", id), Some(json!({
+                    "id": id,
+                    "type": "page",
+                    "title": report.title,
+                    "version": { "number": version + 1 },
+                    "body": { "storage": { "value": html, "representation": "storage" } },
+                }))).await?;
+
+                Ok(PublishOutcome::Updated(self.page_url(&updated)))
+            },
+            None => {
+                let mut body = json!({
+                    "type": "page",
+                    "title": report.title,
+                    "space": { "key": self.space },
+                    "body": { "storage": { "value": html, "representation": "storage" } },
+                });
+                if let Some(parent) = &self.parent {
+                    body["ancestors"] = json!([{ "id": parent }]);
+                }
+
+                let created = self.request(reqwest::Method::POST, "content", Some(body)).await?;
+                Ok(PublishOutcome::Created(self.page_url(&created)))
+            },
+        }
+    }
+}
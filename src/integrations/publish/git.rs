@@ -0,0 +1,134 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use std::path::PathBuf;
+use tokio::process::Command;
+
+use crate::integrations::publish::{PublishOutcome, Publisher, Report, ReportBody};
+
+/// Publishes reports as markdown files committed to a branch of a git repository
+///
+/// Commits are built with plumbing commands against a temporary index, so the working tree
+/// and the checked-out branch are left alone.
+pub struct GitPublisher {
+    /// Repository directory
+    repo: PathBuf,
+
+    /// Branch reports are committed to
+    branch: String,
+
+    /// Directory in the branch
+    dir: Option<String>,
+
+    /// Remote the branch is fetched from and pushed to
+    remote: String,
+}
+
+impl GitPublisher {
+    /// Create a publisher
+    pub fn new(repo: PathBuf, branch: String, dir: Option<String>, remote: Option<String>) -> Self {
+        Self {
+            repo,
+            branch,
+            dir,
+            remote: remote.unwrap_or_else(|| "origin".to_string()),
+        }
+    }
+
+    /// Run git, returning trimmed stdout
+    async fn git(&self, args: &[&str], index: Option<&PathBuf>) -> Result<String> {
+        let mut command = Command::new("git");
+        command.arg("-C").arg(&self.repo).args(args);
+        if let Some(index) = index {
+            command.env("GIT_INDEX_FILE", index);
+        }
+
+        let output = command.output().await
+            .map_err(|e| anyhow!("Failed to run git: {}", e))?;
+        if !output.status.success() {
+            return Err(anyhow!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Current tip of the branch, preferring the remote's
+    async fn branch_tip(&self, has_remote: bool) -> Option<String> {
+        if has_remote && self.git(&["fetch", "--quiet", &self.remote, &self.branch], None).await.is_ok() {
+            return self.git(&["rev-parse", "FETCH_HEAD"], None).await.ok();
+        }
+
+        self.git(&["rev-parse", "--verify", "--quiet", &format!("refs/heads/{}", self.branch)], None).await.ok()
+    }
+}
+
+#[async_trait]
+impl Publisher for GitPublisher {
+    async fn publish(&self, report: &Report) -> Result<PublishOutcome> {
+        let current = self.git(&["symbolic-ref", "--quiet", "--short", "HEAD"], None).await.unwrap_or_default();
+        if current == self.branch {
+            return Err(anyhow!("Cannot publish to {}: it is the checked-out branch", self.branch));
+        }
+
+        // In offline mode the report is only committed locally
+        let has_remote = !crate::offline::is_enabled() && self.git(&["remote"], None).await?
+            .lines()
+            .any(|remote| remote == self.remote);
+        let parent = self.branch_tip(has_remote).await;
+
+        let file_name = report.file_name("md");
+        let path = match &self.dir {
+            Some(dir) => format!("{}/{}", dir, file_name),
+            None => file_name,
+        };
+        let location = format!("{}:{}", self.branch, path);
+
+        let content = match &report.body {
+            ReportBody::Markdown(markdown) => markdown.clone(),
+            ReportBody::Html(html) => html.clone(),
+        };
+
+        // Stage the report on top of the branch in a throwaway index
+        let scratch = std::env::temp_dir().join(format!("qitops-publish-{}", std::process::id()));
+        std::fs::create_dir_all(&scratch)?;
+        let index = scratch.join("index");
+        let content_file = scratch.join("report");
+        std::fs::write(&content_file, content)?;
+
+        let result = async {
+            match &parent {
+                Some(parent) => self.git(&["read-tree", parent], Some(&index)).await?,
+                None => self.git(&["read-tree", "--empty"], Some(&index)).await?,
+            };
+            let blob = self.git(&["hash-object", "-w", &content_file.to_string_lossy()], None).await?;
+            self.git(&["update-index", "--add", "--cacheinfo", &format!("100644,{},{}", blob, path)], Some(&index)).await?;
+            let tree = self.git(&["write-tree"], Some(&index)).await?;
+
+            let existed = match &parent {
+                Some(parent) => {
+                    if self.git(&["rev-parse", &format!("{}^{{tree}}", parent)], None).await? == tree {
+                        return Ok(PublishOutcome::Unchanged(location.clone()));
+                    }
+                    self.git(&["cat-file", "-e", &format!("{}:{}", parent, path)], None).await.is_ok()
+                },
+                None => false,
+            };
+
+            let message = format!("Publish {}", report.title);
+            let mut args = vec!["commit-tree", tree.as_str(), "-m", message.as_str()];
+            if let Some(parent) = &parent {
+                args.extend(["-p", parent.as_str()]);
+            }
+            let commit = self.git(&args, None).await?;
+
+            self.git(&["update-ref", &format!("refs/heads/{}", self.branch), &commit], None).await?;
+            if has_remote {
+                self.git(&["push", "--quiet", &self.remote, &format!("{}:refs/heads/{}", commit, self.branch)], None).await?;
+            }
+
+            Ok(if existed { PublishOutcome::Updated(location.clone()) } else { PublishOutcome::Created(location.clone()) })
+        }.await;
+
+        let _ = std::fs::remove_dir_all(&scratch);
+        result
+    }
+}
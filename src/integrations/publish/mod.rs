@@ -0,0 +1,216 @@
+// Report publishing
+pub mod config;
+pub mod confluence;
+pub mod git;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::audit::{self, AuditAction, AuditEvent};
+
+pub use config::{ConfluenceConfig, PublishConfig, PublishConfigManager};
+pub use confluence::ConfluencePublisher;
+pub use git::GitPublisher;
+
+/// Where reports are published
+///
+/// `confluence:<SPACE>[/<parent page ID>]` publishes a page in a Confluence space.
+/// `git:<branch>[:<directory>]` commits a markdown file to a branch of the current repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublishTarget {
+    /// Confluence space
+    Confluence {
+        /// Space key
+        space: String,
+        /// Page new pages are created under
+        parent: Option<String>,
+    },
+    /// Branch of the current git repository, e.g. a docs branch
+    Git {
+        /// Branch name
+        branch: String,
+        /// Directory in the branch
+        dir: Option<String>,
+    },
+}
+
+impl FromStr for PublishTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (kind, location) = s.split_once(':')
+            .ok_or_else(|| anyhow!("Invalid publish target {}: expected confluence:<SPACE>[/<parent>] or git:<branch>[:<dir>]", s))?;
+        if location.is_empty() {
+            return Err(anyhow!("Invalid publish target {}: missing space or branch", s));
+        }
+
+        match kind.trim().to_lowercase().as_str() {
+            "confluence" => {
+                let (space, parent) = match location.split_once('/') {
+                    Some((space, parent)) => (space, Some(parent.to_string()).filter(|p| !p.is_empty())),
+                    None => (location, None),
+                };
+                Ok(PublishTarget::Confluence { space: space.to_string(), parent })
+            },
+            "git" => {
+                let (branch, dir) = match location.split_once(':') {
+                    Some((branch, dir)) => (branch, Some(dir.trim_matches('/').to_string()).filter(|d| !d.is_empty())),
+                    None => (location, None),
+                };
+                Ok(PublishTarget::Git { branch: branch.to_string(), dir })
+            },
+            _ => Err(anyhow!("Unknown publish target kind: {} (expected confluence or git)", kind)),
+        }
+    }
+}
+
+impl fmt::Display for PublishTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PublishTarget::Confluence { space, parent: Some(parent) } => write!(f, "confluence:{}/{}", space, parent),
+            PublishTarget::Confluence { space, parent: None } => write!(f, "confluence:{}", space),
+            PublishTarget::Git { branch, dir: Some(dir) } => write!(f, "git:{}:{}", branch, dir),
+            PublishTarget::Git { branch, dir: None } => write!(f, "git:{}", branch),
+        }
+    }
+}
+
+/// Body of a report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReportBody {
+    /// Markdown
+    Markdown(String),
+    /// HTML
+    Html(String),
+}
+
+/// A report to publish
+#[derive(Debug, Clone)]
+pub struct Report {
+    /// Title, which identifies the report so re-publishing updates it in place
+    pub title: String,
+
+    /// Body
+    pub body: ReportBody,
+}
+
+impl Report {
+    /// Create a markdown report
+    pub fn markdown(title: impl Into<String>, markdown: impl Into<String>) -> Self {
+        Self { title: title.into(), body: ReportBody::Markdown(markdown.into()) }
+    }
+
+    /// Read a report from a markdown or HTML file
+    ///
+    /// Without a title, the first heading or `<title>` is used, falling back to the file name.
+    pub fn from_file(path: &Path, title: Option<&str>) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read report {}: {}", path.display(), e))?;
+
+        let is_html = path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"));
+
+        let found_title = if is_html {
+            content.find("<title>")
+                .and_then(|start| content[start + 7..].find("</title>").map(|end| content[start + 7..start + 7 + end].trim().to_string()))
+        } else {
+            content.lines()
+                .find_map(|line| line.strip_prefix("# "))
+                .map(|heading| heading.trim().to_string())
+        };
+
+        let title = title.map(str::to_string)
+            .or(found_title.filter(|t| !t.is_empty()))
+            .or_else(|| path.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+            .ok_or_else(|| anyhow!("Could not determine a title for {}; pass --title", path.display()))?;
+
+        Ok(Self {
+            title,
+            body: if is_html { ReportBody::Html(content) } else { ReportBody::Markdown(content) },
+        })
+    }
+
+    /// Body as HTML
+    pub fn html(&self) -> String {
+        match &self.body {
+            ReportBody::Html(html) => html.clone(),
+            ReportBody::Markdown(markdown) => {
+                let parser = pulldown_cmark::Parser::new_ext(markdown, pulldown_cmark::Options::ENABLE_TABLES | pulldown_cmark::Options::ENABLE_STRIKETHROUGH);
+                let mut html = String::new();
+                pulldown_cmark::html::push_html(&mut html, parser);
+                html
+            },
+        }
+    }
+
+    /// File name derived from the title, stable across runs
+    pub fn file_name(&self, extension: &str) -> String {
+        let slug = self.title.to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join("-");
+        format!("{}.{}", if slug.is_empty() { "report" } else { &slug }, extension)
+    }
+}
+
+/// Result of publishing a report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublishOutcome {
+    /// A new page or file was created
+    Created(String),
+    /// An existing page or file was updated
+    Updated(String),
+    /// The published content was already up to date
+    Unchanged(String),
+}
+
+impl PublishOutcome {
+    /// URL or path of the published report
+    pub fn location(&self) -> &str {
+        match self {
+            PublishOutcome::Created(location) | PublishOutcome::Updated(location) | PublishOutcome::Unchanged(location) => location,
+        }
+    }
+}
+
+/// Destination for reports
+#[async_trait]
+pub trait Publisher: Send + Sync {
+    /// Publish a report, replacing an earlier one with the same title
+    async fn publish(&self, report: &Report) -> Result<PublishOutcome>;
+}
+
+/// Create a publisher for a target from configuration
+pub fn publisher_for(target: &PublishTarget, config: &PublishConfig) -> Result<Box<dyn Publisher>> {
+    Ok(match target {
+        PublishTarget::Confluence { space, parent } => {
+            Box::new(ConfluencePublisher::from_config(config.confluence.as_ref(), config.proxy.as_deref(), space.clone(), parent.clone())?)
+        },
+        PublishTarget::Git { branch, dir } => {
+            Box::new(GitPublisher::new(std::env::current_dir()?, branch.clone(), dir.clone(), config.git_remote.clone()))
+        },
+    })
+}
+
+/// Publish a report to a target, recording it in the audit log
+pub async fn publish(target: &PublishTarget, report: &Report) -> Result<PublishOutcome> {
+    let config_manager = PublishConfigManager::new()?;
+    let publisher = publisher_for(target, config_manager.get_config())?;
+
+    let result = publisher.publish(report).await;
+
+    let mut event = AuditEvent::new(audit::current_actor(), AuditAction::ReportPublished, target.to_string())
+        .with_outcome(&result);
+    if let Ok(outcome) = &result {
+        event.details = Some(format!("{}: {}", report.title, outcome.location()));
+    }
+    audit::record(event);
+
+    result
+}
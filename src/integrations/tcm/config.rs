@@ -0,0 +1,133 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// TestRail configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestRailConfig {
+    /// Instance URL, e.g. `https://example.testrail.io`
+    pub url: String,
+
+    /// User email
+    pub username: String,
+
+    /// API key
+    pub api_key: String,
+}
+
+/// Xray cloud configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct XrayConfig {
+    /// API client ID
+    pub client_id: String,
+
+    /// API client secret
+    pub client_secret: String,
+
+    /// API base URL (defaults to Xray cloud)
+    #[serde(default)]
+    pub base_url: Option<String>,
+}
+
+/// Zephyr Scale cloud configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZephyrConfig {
+    /// API access token
+    pub api_token: String,
+
+    /// API base URL (defaults to Zephyr Scale cloud)
+    #[serde(default)]
+    pub base_url: Option<String>,
+}
+
+/// Test case management tool configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TcmConfig {
+    /// TestRail
+    #[serde(default)]
+    pub testrail: Option<TestRailConfig>,
+
+    /// Xray
+    #[serde(default)]
+    pub xray: Option<XrayConfig>,
+
+    /// Zephyr Scale
+    #[serde(default)]
+    pub zephyr: Option<ZephyrConfig>,
+
+    /// HTTP(S) proxy URL for every tool (default: the `HTTPS_PROXY`/`HTTP_PROXY` environment variables)
+    #[serde(default)]
+    pub proxy: Option<String>,
+}
+
+/// Test case management configuration manager, storing `tcm.json` in the QitOps config directory
+pub struct TcmConfigManager {
+    /// Configuration file path
+    config_path: PathBuf,
+
+    /// Configuration
+    config: TcmConfig,
+}
+
+impl TcmConfigManager {
+    /// Create a new configuration manager
+    pub fn new() -> Result<Self> {
+        let config_path = crate::config::config_dir()?.join("tcm.json");
+
+        let config = if config_path.exists() {
+            let config_str = fs::read_to_string(&config_path)
+                .map_err(|e| anyhow!("Failed to read config file: {}", e))?;
+
+            serde_json::from_str(&config_str)
+                .map_err(|e| anyhow!("Failed to parse config file: {}", e))?
+        } else {
+            TcmConfig::default()
+        };
+
+        Ok(Self {
+            config_path,
+            config,
+        })
+    }
+
+    /// Get the configuration
+    pub fn get_config(&self) -> &TcmConfig {
+        &self.config
+    }
+
+    /// Set the TestRail configuration
+    pub fn set_testrail(&mut self, config: TestRailConfig) -> Result<()> {
+        self.config.testrail = Some(config);
+        self.save_config()
+    }
+
+    /// Set the Xray configuration
+    pub fn set_xray(&mut self, config: XrayConfig) -> Result<()> {
+        self.config.xray = Some(config);
+        self.save_config()
+    }
+
+    /// Set the Zephyr Scale configuration
+    pub fn set_zephyr(&mut self, config: ZephyrConfig) -> Result<()> {
+        self.config.zephyr = Some(config);
+        self.save_config()
+    }
+
+    /// Set the proxy
+    pub fn set_proxy(&mut self, proxy: String) -> Result<()> {
+        self.config.proxy = Some(proxy);
+        self.save_config()
+    }
+
+    /// Save the configuration
+    pub fn save_config(&self) -> Result<()> {
+        let config_str = serde_json::to_string_pretty(&self.config)
+            .map_err(|e| anyhow!("Failed to serialize config: {}", e))?;
+
+        fs::write(&self.config_path, config_str)
+            .map_err(|e| anyhow!("Failed to write config file: {}", e))?;
+
+        Ok(())
+    }
+}
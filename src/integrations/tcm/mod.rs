@@ -0,0 +1,196 @@
+// Test case management tool integrations
+pub mod config;
+pub mod parse;
+pub mod testrail;
+pub mod xray;
+pub mod zephyr;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::audit::{self, AuditAction, AuditEvent};
+
+pub use config::{TcmConfig, TcmConfigManager};
+pub use parse::parse_test_cases;
+pub use testrail::TestRailClient;
+pub use xray::XrayClient;
+pub use zephyr::ZephyrClient;
+
+/// Test case management tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TcmTool {
+    /// TestRail
+    TestRail,
+    /// Xray for Jira (cloud)
+    Xray,
+    /// Zephyr Scale (cloud)
+    Zephyr,
+}
+
+impl fmt::Display for TcmTool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TcmTool::TestRail => write!(f, "testrail"),
+            TcmTool::Xray => write!(f, "xray"),
+            TcmTool::Zephyr => write!(f, "zephyr"),
+        }
+    }
+}
+
+impl FromStr for TcmTool {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "testrail" => Ok(TcmTool::TestRail),
+            "xray" => Ok(TcmTool::Xray),
+            "zephyr" | "zephyr-scale" => Ok(TcmTool::Zephyr),
+            _ => Err(anyhow!("Unknown test case management tool: {} (expected testrail, xray or zephyr)", s)),
+        }
+    }
+}
+
+/// Where test cases live: `<tool>:<project>[-<suite>]`
+///
+/// The project is a TestRail project ID or a Jira project key. The suite is a TestRail suite
+/// ID, an Xray folder path or a Zephyr Scale folder ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcmTarget {
+    /// Tool
+    pub tool: TcmTool,
+
+    /// Project
+    pub project: String,
+
+    /// Suite or folder
+    pub suite: Option<String>,
+}
+
+impl FromStr for TcmTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (tool, location) = s.split_once(':')
+            .ok_or_else(|| anyhow!("Invalid target {}: expected <tool>:<project>[-<suite>], e.g. testrail:3-12", s))?;
+
+        let (project, suite) = match location.split_once('-') {
+            Some((project, suite)) => (project, Some(suite.to_string())),
+            None => (location, None),
+        };
+        if project.is_empty() {
+            return Err(anyhow!("Invalid target {}: missing project", s));
+        }
+
+        Ok(Self {
+            tool: tool.parse()?,
+            project: project.to_string(),
+            suite: suite.filter(|suite| !suite.is_empty()),
+        })
+    }
+}
+
+impl fmt::Display for TcmTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.suite {
+            Some(suite) => write!(f, "{}:{}-{}", self.tool, self.project, suite),
+            None => write!(f, "{}:{}", self.tool, self.project),
+        }
+    }
+}
+
+/// Test case to push to a tool
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TestCase {
+    /// Title
+    pub title: String,
+
+    /// Preconditions
+    pub preconditions: Option<String>,
+
+    /// Steps, in order
+    pub steps: Vec<String>,
+
+    /// Expected result
+    pub expected: Option<String>,
+}
+
+/// Test case already in a tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExistingCase {
+    /// ID or key in the tool
+    pub id: String,
+
+    /// Title
+    pub title: String,
+}
+
+/// Client for a test case management tool
+#[async_trait]
+pub trait TcmClient: Send + Sync {
+    /// List the test cases at a target
+    async fn list_cases(&self, target: &TcmTarget) -> Result<Vec<ExistingCase>>;
+
+    /// Create a test case at a target, returning its ID or key
+    async fn create_case(&self, target: &TcmTarget, case: &TestCase) -> Result<String>;
+}
+
+/// Create a client for a tool from configuration
+pub fn client_for(tool: TcmTool, config: &TcmConfig) -> Result<Box<dyn TcmClient>> {
+    Ok(match tool {
+        TcmTool::TestRail => Box::new(TestRailClient::from_config(config.testrail.as_ref(), config.proxy.as_deref())?),
+        TcmTool::Xray => Box::new(XrayClient::from_config(config.xray.as_ref(), config.proxy.as_deref())?),
+        TcmTool::Zephyr => Box::new(ZephyrClient::from_config(config.zephyr.as_ref(), config.proxy.as_deref())?),
+    })
+}
+
+/// Outcome of pushing test cases
+#[derive(Debug, Clone, Default)]
+pub struct PushReport {
+    /// Created cases as (ID, title)
+    pub created: Vec<(String, String)>,
+
+    /// Titles skipped because the target already has them
+    pub skipped: Vec<String>,
+}
+
+/// Title normalized for duplicate detection
+fn normalize_title(title: &str) -> String {
+    title.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Push test cases to a target, skipping ones whose title it already has
+pub async fn push(client: &dyn TcmClient, target: &TcmTarget, cases: &[TestCase]) -> Result<PushReport> {
+    let mut seen: HashSet<String> = client.list_cases(target).await?
+        .iter()
+        .map(|case| normalize_title(&case.title))
+        .collect();
+
+    let mut report = PushReport::default();
+    for case in cases {
+        if !seen.insert(normalize_title(&case.title)) {
+            report.skipped.push(case.title.clone());
+            continue;
+        }
+
+        let result = client.create_case(target, case).await;
+
+        let mut event = AuditEvent::new(audit::current_actor(), AuditAction::TestCaseCreated, target.to_string())
+            .with_outcome(&result);
+        if let Ok(id) = &result {
+            event.details = Some(format!("{}: {}", id, case.title));
+        }
+        audit::record(event);
+
+        report.created.push((result?, case.title.clone()));
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,314 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::agent::test_gen::TestFormat;
+use crate::integrations::tcm::TestCase;
+use crate::llm::postprocess::unfence;
+
+/// Part of a test case the following lines belong to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Description,
+    Preconditions,
+    Steps,
+    Expected,
+}
+
+impl Field {
+    /// Field named by a label such as `**Steps:**` or `### Expected Result`
+    fn from_label(label: &str) -> Option<Self> {
+        let label = label.trim().trim_matches(|c: char| c == '*' || c == '_' || c == ':' || c.is_whitespace()).to_lowercase();
+        if label.starts_with("precondition") || label.starts_with("prerequisite") || label == "setup" || label == "given" {
+            Some(Field::Preconditions)
+        } else if label.starts_with("step") || label.starts_with("test step") || label == "actions" || label == "when" {
+            Some(Field::Steps)
+        } else if label.starts_with("expected") || label == "then" {
+            Some(Field::Expected)
+        } else if label.starts_with("description") || label.starts_with("objective") {
+            Some(Field::Description)
+        } else {
+            None
+        }
+    }
+}
+
+/// Add a line of text to a test case field
+fn append(case: &mut TestCase, field: Field, text: &str) {
+    let text = text.trim();
+    if text.is_empty() {
+        return;
+    }
+
+    let join = |existing: &mut Option<String>| {
+        *existing = Some(match existing.take() {
+            Some(existing) => format!("{}\n{}", existing, text),
+            None => text.to_string(),
+        });
+    };
+
+    match field {
+        Field::Steps => case.steps.push(text.to_string()),
+        Field::Preconditions => join(&mut case.preconditions),
+        Field::Expected => join(&mut case.expected),
+        Field::Description => {},
+    }
+}
+
+/// Extract test cases from generated output
+pub fn parse_test_cases(text: &str, format: TestFormat) -> Vec<TestCase> {
+    let text = text.trim();
+    let text = unfence(text).unwrap_or(text);
+    let cases = match format {
+        TestFormat::Markdown => parse_markdown(text),
+        TestFormat::Yaml => parse_yaml(text),
+        TestFormat::Robot => parse_robot(text),
+        TestFormat::Playwright | TestFormat::Cypress => parse_spec(text),
+        TestFormat::RustTest | TestFormat::Pytest => parse_unit_tests(text),
+    };
+
+    cases.into_iter().filter(|case| !case.title.is_empty()).collect()
+}
+
+/// Markdown: each heading naming a test case starts one; labels and lists fill its fields
+fn parse_markdown(text: &str) -> Vec<TestCase> {
+    static HEADING: OnceLock<Regex> = OnceLock::new();
+    static CASE_TITLE: OnceLock<Regex> = OnceLock::new();
+    static LABEL: OnceLock<Regex> = OnceLock::new();
+    static ITEM: OnceLock<Regex> = OnceLock::new();
+    let heading = HEADING.get_or_init(|| Regex::new(r"^(#{1,6})\s+(.+?)\s*#*$").unwrap());
+    let case_title = CASE_TITLE.get_or_init(|| {
+        Regex::new(r"(?i)^(?:test\s*case|tc|scenario)\s*[-_#]?\s*[\w.-]*?\d*\s*[:.\-–]\s*(.+)$").unwrap()
+    });
+    let label = LABEL.get_or_init(|| Regex::new(r"^\s*[*_]{0,2}([A-Za-z][A-Za-z ]{1,30}?)[*_]{0,2}\s*:\s*[*_]{0,2}\s*(.*)$").unwrap());
+    let item = ITEM.get_or_init(|| Regex::new(r"^\s*(?:\d+[.)]|[-*+])\s+(.+)$").unwrap());
+
+    // Headings that name test cases, or if none do, the deepest headings with lists under them
+    let headings: Vec<(usize, usize, &str)> = text.lines().enumerate()
+        .filter_map(|(i, line)| heading.captures(line).map(|c| (i, c[1].len(), c.get(2).unwrap().as_str())))
+        .collect();
+    let named: Vec<usize> = headings.iter()
+        .filter(|(_, _, title)| case_title.is_match(title))
+        .map(|(i, _, _)| *i)
+        .collect();
+    let case_lines: Vec<usize> = if named.is_empty() {
+        let deepest = headings.iter()
+            .filter(|(_, _, title)| Field::from_label(title).is_none())
+            .map(|(_, level, _)| *level)
+            .max();
+        headings.iter()
+            .filter(|(_, level, title)| Some(*level) == deepest && Field::from_label(title).is_none())
+            .map(|(i, _, _)| *i)
+            .collect()
+    } else {
+        named
+    };
+
+    let mut cases = Vec::new();
+    let mut current: Option<TestCase> = None;
+    let mut field = Field::Description;
+
+    for (i, line) in text.lines().enumerate() {
+        if case_lines.contains(&i) {
+            cases.extend(current.take());
+            let title = heading.captures(line).map(|c| c[2].to_string()).unwrap_or_default();
+            let title = case_title.captures(&title).map(|c| c[1].to_string()).unwrap_or(title);
+            current = Some(TestCase { title: title.trim_matches(|c| c == '*' || c == '`').trim().to_string(), ..Default::default() });
+            field = Field::Description;
+            continue;
+        }
+
+        let Some(case) = current.as_mut() else {
+            continue;
+        };
+
+        if let Some(captures) = heading.captures(line) {
+            match Field::from_label(&captures[2]) {
+                Some(label_field) => field = label_field,
+                // A heading of another kind ends the test case
+                None => cases.extend(current.take()),
+            }
+            continue;
+        }
+
+        if let Some(captures) = label.captures(line)
+            && let Some(label_field) = Field::from_label(&captures[1])
+        {
+            field = label_field;
+            append(case, field, &captures[2]);
+            continue;
+        }
+
+        if let Some(captures) = item.captures(line) {
+            // List items outside a labeled section are steps
+            let target = if field == Field::Description { Field::Steps } else { field };
+            append(case, target, &captures[1]);
+        } else if field != Field::Steps {
+            append(case, field, line);
+        }
+    }
+    cases.extend(current);
+
+    cases
+}
+
+/// YAML: each `name`/`title` key starts a test case, with `steps`, `expected` and
+/// `preconditions` keys filling it
+fn parse_yaml(text: &str) -> Vec<TestCase> {
+    static KEY: OnceLock<Regex> = OnceLock::new();
+    static ITEM: OnceLock<Regex> = OnceLock::new();
+    let key = KEY.get_or_init(|| Regex::new(r"^(\s*)(?:-\s+)?([A-Za-z_]+)\s*:\s*(.*)$").unwrap());
+    let item = ITEM.get_or_init(|| Regex::new(r"^\s*-\s+(?:(?:step|action|description)\s*:\s*)?(.+)$").unwrap());
+
+    let unquote = |value: &str| value.trim().trim_matches(|c| c == '"' || c == '\'').to_string();
+
+    let mut cases = Vec::new();
+    let mut current: Option<TestCase> = None;
+    let mut field = Field::Description;
+
+    for line in text.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        if let Some(captures) = key.captures(line) {
+            let name = captures[2].to_lowercase();
+            let value = unquote(&captures[3]);
+
+            if matches!(name.as_str(), "name" | "title" | "test_case" | "test_name" | "scenario") && !value.is_empty() {
+                cases.extend(current.take());
+                current = Some(TestCase { title: value, ..Default::default() });
+                field = Field::Description;
+                continue;
+            }
+
+            if let Some(case) = current.as_mut() {
+                if matches!(name.as_str(), "expected" | "expected_result" | "expected_results" | "result") {
+                    // Inside steps, an expected result is that of the last step
+                    append(case, Field::Expected, &value);
+                    if field != Field::Steps {
+                        field = Field::Expected;
+                    }
+                    continue;
+                }
+                if let Some(key_field) = Field::from_label(&name.replace('_', " ")) {
+                    field = key_field;
+                    if !value.is_empty() && value != "|" && value != ">" {
+                        append(case, field, &value);
+                    }
+                    continue;
+                }
+                // A step given as a mapping such as `- action: ...`
+                if field == Field::Steps && matches!(name.as_str(), "step" | "action" | "description") {
+                    append(case, Field::Steps, &value);
+                    continue;
+                }
+            }
+        }
+
+        if let Some(case) = current.as_mut() {
+            match item.captures(line) {
+                Some(captures) => append(case, field, &unquote(&captures[1])),
+                None => append(case, field, line),
+            }
+        }
+    }
+    cases.extend(current);
+
+    cases
+}
+
+/// Robot Framework: each unindented line of the test case table is a test case and its
+/// indented keyword lines are steps
+fn parse_robot(text: &str) -> Vec<TestCase> {
+    let mut cases = Vec::new();
+    let mut current: Option<TestCase> = None;
+    let mut in_cases = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("***") {
+            cases.extend(current.take());
+            in_cases = trimmed.to_lowercase().contains("test case");
+            continue;
+        }
+        if !in_cases || trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if !line.starts_with([' ', '\t']) {
+            cases.extend(current.take());
+            current = Some(TestCase { title: trimmed.to_string(), ..Default::default() });
+            continue;
+        }
+
+        let Some(case) = current.as_mut() else {
+            continue;
+        };
+
+        // Keyword arguments are separated by two or more spaces
+        let step = trimmed.split("  ")
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if let Some(setup) = step.strip_prefix("[Setup] ") {
+            append(case, Field::Preconditions, setup);
+        } else if !step.starts_with('[') {
+            append(case, Field::Steps, &step);
+        }
+    }
+    cases.extend(current);
+
+    cases
+}
+
+/// Playwright and Cypress specs: each `test`/`it` call is a case, named by its title
+///
+/// Steps and expectations are code, so only the title and the enclosing `describe` blocks
+/// (as preconditions) are kept.
+fn parse_spec(text: &str) -> Vec<TestCase> {
+    static CALL: OnceLock<Regex> = OnceLock::new();
+    let call = CALL.get_or_init(|| {
+        Regex::new(r#"^\s*(test\.describe|describe|context|test|it)(?:\.only|\.skip|\.fixme)?\(\s*['"`]([^'"`]+)['"`]"#).unwrap()
+    });
+
+    let mut cases = Vec::new();
+    let mut suites: Vec<(usize, String)> = Vec::new();
+    for line in text.lines() {
+        let indent = line.len() - line.trim_start().len();
+        suites.retain(|(suite_indent, _)| *suite_indent < indent);
+
+        let Some(captures) = call.captures(line) else {
+            continue;
+        };
+        let title = captures[2].trim().to_string();
+        match &captures[1] {
+            "test" | "it" => cases.push(TestCase {
+                title,
+                preconditions: (!suites.is_empty()).then(|| suites.iter().map(|(_, suite)| suite.as_str()).collect::<Vec<_>>().join(" > ")),
+                ..Default::default()
+            }),
+            _ => suites.push((indent, title)),
+        }
+    }
+
+    cases
+}
+
+/// Rust and pytest tests: each test function is a case, titled by its name in words
+fn parse_unit_tests(text: &str) -> Vec<TestCase> {
+    static TEST_FN: OnceLock<Regex> = OnceLock::new();
+    let test_fn = TEST_FN.get_or_init(|| {
+        Regex::new(r"(?m)^\s*(?:#\[(?:tokio::)?test[^\]]*\]\s*(?:#\[[^\]]*\]\s*)*(?:pub\s+)?(?:async\s+)?fn|(?:async\s+)?def)\s+(\w+)").unwrap()
+    });
+
+    // Rust tests are marked by their attribute, pytest ones by their name
+    test_fn.captures_iter(text)
+        .filter(|captures| captures[0].contains("#[") || captures[1].starts_with("test"))
+        .map(|captures| captures[1].to_string())
+        .map(|name| TestCase {
+            title: name.trim_start_matches("test_").replace('_', " "),
+            ..Default::default()
+        })
+        .collect()
+}
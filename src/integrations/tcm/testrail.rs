@@ -0,0 +1,165 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+
+use crate::integrations::setting;
+use crate::integrations::tcm::config::TestRailConfig;
+use crate::integrations::tcm::{ExistingCase, TcmClient, TcmTarget, TestCase};
+
+/// Section that generated cases are added to
+const SECTION_NAME: &str = "QitOps Generated";
+
+/// Page size of case listings
+const PAGE_SIZE: usize = 250;
+
+/// TestRail API v2 client
+pub struct TestRailClient {
+    http_client: crate::proxy::RemoteClient,
+    base_url: String,
+    username: String,
+    api_key: String,
+}
+
+impl TestRailClient {
+    /// Create a client
+    pub fn new(base_url: &str, username: String, api_key: String) -> Self {
+        Self {
+            http_client: crate::proxy::RemoteClient::new("TestRail", reqwest::Client::new()),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            username,
+            api_key,
+        }
+    }
+
+    /// Create a client from configuration or `TESTRAIL_URL`, `TESTRAIL_USERNAME` and `TESTRAIL_API_KEY`
+    pub fn from_config(config: Option<&TestRailConfig>, proxy: Option<&str>) -> Result<Self> {
+        Ok(Self {
+            http_client: crate::proxy::remote("TestRail", proxy)?,
+            ..Self::new(
+                &setting(config.map(|c| c.url.as_str()), "TESTRAIL_URL", "TestRail URL", "qitops tcm config")?,
+                setting(config.map(|c| c.username.as_str()), "TESTRAIL_USERNAME", "TestRail username", "qitops tcm config")?,
+                setting(config.map(|c| c.api_key.as_str()), "TESTRAIL_API_KEY", "TestRail API key", "qitops tcm config")?,
+            )
+        })
+    }
+
+    /// Send an API request
+    async fn request(&self, method: reqwest::Method, endpoint: &str, body: Option<Value>) -> Result<Value> {
+        let url = format!("{}/index.php?/api/v2/{}", self.base_url, endpoint);
+
+        let mut request = self.http_client.request(method, &url)?
+            .basic_auth(&self.username, Some(&self.api_key))
+            .header("Content-Type", "application/json");
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request.send().await
+            .map_err(|e| anyhow!("Failed to send request to TestRail: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "Could not read error response".to_string());
+
+            return match status.as_u16() {
+                401 => Err(anyhow!("TestRail authentication error: {}", error_text)),
+                403 => Err(anyhow!("TestRail access denied: {}", error_text)),
+                404 => Err(anyhow!("TestRail resource not found: {}", error_text)),
+                _ => Err(anyhow!("TestRail API error ({}): {}", status, error_text)),
+            };
+        }
+
+        response.json().await
+            .map_err(|e| anyhow!("Failed to parse TestRail response: {}", e))
+    }
+
+    /// Suite ID query parameter
+    fn suite_param(target: &TcmTarget) -> String {
+        target.suite.as_ref().map(|suite| format!("&suite_id={}", suite)).unwrap_or_default()
+    }
+
+    /// Find or create the section generated cases are added to
+    async fn section_id(&self, target: &TcmTarget) -> Result<u64> {
+        let sections = self.request(
+            reqwest::Method::GET,
+            &format!("get_sections/{}{}", target.project, Self::suite_param(target)),
+            None,
+        ).await?;
+
+        // Newer TestRail versions wrap lists in an object
+        let sections = sections.get("sections").unwrap_or(&sections);
+        let existing = sections.as_array()
+            .and_then(|sections| sections.iter().find(|s| s["name"].as_str() == Some(SECTION_NAME)))
+            .and_then(|section| section["id"].as_u64());
+        if let Some(id) = existing {
+            return Ok(id);
+        }
+
+        let mut body = json!({ "name": SECTION_NAME });
+        if let Some(suite) = &target.suite {
+            body["suite_id"] = json!(suite.parse::<u64>().map_err(|_| anyhow!("TestRail suite must be a numeric ID: {}", suite))?);
+        }
+        let section = self.request(reqwest::Method::POST, &format!("add_section/{}", target.project), Some(body)).await?;
+
+        section["id"].as_u64()
+            .ok_or_else(|| anyhow!("TestRail did not return a section ID"))
+    }
+}
+
+#[async_trait]
+impl TcmClient for TestRailClient {
+    async fn list_cases(&self, target: &TcmTarget) -> Result<Vec<ExistingCase>> {
+        let mut cases = Vec::new();
+        let mut offset = 0;
+
+        loop {
+            let page = self.request(
+                reqwest::Method::GET,
+                &format!("get_cases/{}{}&limit={}&offset={}", target.project, Self::suite_param(target), PAGE_SIZE, offset),
+                None,
+            ).await?;
+
+            let items = page.get("cases").unwrap_or(&page).as_array().cloned().unwrap_or_default();
+            let count = items.len();
+            cases.extend(items.iter().map(|case| ExistingCase {
+                id: format!("C{}", case["id"].as_u64().unwrap_or_default()),
+                title: case["title"].as_str().unwrap_or_default().to_string(),
+            }));
+
+            // Older versions return every case at once, without pagination links
+            let has_next = page.get("_links").is_some_and(|links| !links["next"].is_null());
+            if !has_next || count == 0 {
+                break;
+            }
+            offset += count;
+        }
+
+        Ok(cases)
+    }
+
+    async fn create_case(&self, target: &TcmTarget, case: &TestCase) -> Result<String> {
+        let section_id = self.section_id(target).await?;
+
+        let steps = case.steps.iter()
+            .enumerate()
+            .map(|(i, step)| format!("{}. {}", i + 1, step))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let created = self.request(
+            reqwest::Method::POST,
+            &format!("add_case/{}", section_id),
+            Some(json!({
+                "title": case.title,
+                "custom_preconds": case.preconditions,
+                "custom_steps": steps,
+                "custom_expected": case.expected,
+            })),
+        ).await?;
+
+        created["id"].as_u64()
+            .map(|id| format!("C{}", id))
+            .ok_or_else(|| anyhow!("TestRail did not return a case ID"))
+    }
+}
@@ -0,0 +1,195 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use tokio::sync::Mutex;
+
+use crate::integrations::setting;
+use crate::integrations::tcm::config::XrayConfig;
+use crate::integrations::tcm::{ExistingCase, TcmClient, TcmTarget, TestCase};
+
+/// Xray cloud API
+const DEFAULT_BASE_URL: &str = "https://xray.cloud.getxray.app";
+
+/// Page size of test listings (the API maximum)
+const PAGE_SIZE: u64 = 100;
+
+/// Xray cloud client using the GraphQL API
+pub struct XrayClient {
+    http_client: crate::proxy::RemoteClient,
+    base_url: String,
+    client_id: String,
+    client_secret: String,
+
+    /// Token from authenticating, reused for later requests
+    token: Mutex<Option<String>>,
+}
+
+impl XrayClient {
+    /// Create a client
+    pub fn new(base_url: &str, client_id: String, client_secret: String) -> Self {
+        Self {
+            http_client: crate::proxy::RemoteClient::new("Xray", reqwest::Client::new()),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client_id,
+            client_secret,
+            token: Mutex::new(None),
+        }
+    }
+
+    /// Create a client from configuration or `XRAY_CLIENT_ID` and `XRAY_CLIENT_SECRET`
+    pub fn from_config(config: Option<&XrayConfig>, proxy: Option<&str>) -> Result<Self> {
+        let base_url = config.and_then(|c| c.base_url.clone()).unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+
+        Ok(Self {
+            http_client: crate::proxy::remote("Xray", proxy)?,
+            ..Self::new(
+                &base_url,
+                setting(config.map(|c| c.client_id.as_str()), "XRAY_CLIENT_ID", "Xray client ID", "qitops tcm config")?,
+                setting(config.map(|c| c.client_secret.as_str()), "XRAY_CLIENT_SECRET", "Xray client secret", "qitops tcm config")?,
+            )
+        })
+    }
+
+    /// Get an API token, authenticating on first use
+    async fn token(&self) -> Result<String> {
+        let mut token = self.token.lock().await;
+        if let Some(token) = token.as_ref() {
+            return Ok(token.clone());
+        }
+
+        let response = self.http_client.post(format!("{}/api/v2/authenticate", self.base_url))?
+            .json(&json!({ "client_id": self.client_id, "client_secret": self.client_secret }))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to send request to Xray: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "Could not read error response".to_string());
+            return Err(anyhow!("Xray authentication failed ({}): {}", status, error_text));
+        }
+
+        // The token is returned as a JSON string
+        let value: String = response.json().await
+            .map_err(|e| anyhow!("Failed to parse Xray authentication response: {}", e))?;
+        *token = Some(value.clone());
+
+        Ok(value)
+    }
+
+    /// Run a GraphQL query
+    async fn graphql(&self, query: &str, variables: Value) -> Result<Value> {
+        let response = self.http_client.post(format!("{}/api/v2/graphql", self.base_url))?
+            .bearer_auth(self.token().await?)
+            .json(&json!({ "query": query, "variables": variables }))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to send request to Xray: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "Could not read error response".to_string());
+            return Err(anyhow!("Xray API error ({}): {}", status, error_text));
+        }
+
+        let body: Value = response.json().await
+            .map_err(|e| anyhow!("Failed to parse Xray response: {}", e))?;
+
+        if let Some(errors) = body["errors"].as_array().filter(|errors| !errors.is_empty()) {
+            let messages: Vec<&str> = errors.iter().filter_map(|e| e["message"].as_str()).collect();
+            return Err(anyhow!("Xray API error: {}", messages.join("; ")));
+        }
+
+        Ok(body["data"].clone())
+    }
+
+    /// Folder path of a target's suite
+    fn folder_path(target: &TcmTarget) -> Option<String> {
+        target.suite.as_ref().map(|suite| format!("/{}", suite.trim_start_matches('/')))
+    }
+}
+
+#[async_trait]
+impl TcmClient for XrayClient {
+    async fn list_cases(&self, target: &TcmTarget) -> Result<Vec<ExistingCase>> {
+        const QUERY: &str = "query($jql: String, $folder: FolderSearchInput, $limit: Int!, $start: Int) {
+            getTests(jql: $jql, folder: $folder, limit: $limit, start: $start) {
+                total
+                results { jira(fields: [\"key\", \"summary\"]) }
+            }
+        }";
+
+        let folder = Self::folder_path(target).map(|path| json!({ "path": path, "includeDescendants": true }));
+        let mut cases = Vec::new();
+        let mut start = 0;
+
+        loop {
+            let data = self.graphql(QUERY, json!({
+                "jql": format!("project = \"{}\"", target.project),
+                "folder": folder,
+                "limit": PAGE_SIZE,
+                "start": start,
+            })).await?;
+
+            let results = data["getTests"]["results"].as_array().cloned().unwrap_or_default();
+            cases.extend(results.iter().map(|test| ExistingCase {
+                id: test["jira"]["key"].as_str().unwrap_or_default().to_string(),
+                title: test["jira"]["summary"].as_str().unwrap_or_default().to_string(),
+            }));
+
+            start += results.len() as u64;
+            let total = data["getTests"]["total"].as_u64().unwrap_or_default();
+            if results.is_empty() || start >= total {
+                break;
+            }
+        }
+
+        Ok(cases)
+    }
+
+    async fn create_case(&self, target: &TcmTarget, case: &TestCase) -> Result<String> {
+        const MUTATION: &str = "mutation($testType: UpdateTestTypeInput, $steps: [CreateStepInput], $folderPath: String, $jira: JSON!) {
+            createTest(testType: $testType, steps: $steps, folderPath: $folderPath, jira: $jira) {
+                test { jira(fields: [\"key\"]) }
+                warnings
+            }
+        }";
+
+        // The expected result belongs to the last step
+        let last = case.steps.len().saturating_sub(1);
+        let steps: Vec<Value> = case.steps.iter()
+            .enumerate()
+            .map(|(i, step)| json!({
+                "action": step,
+                "result": if i == last { case.expected.clone() } else { None },
+            }))
+            .collect();
+
+        let mut fields = json!({
+            "summary": case.title,
+            "project": { "key": target.project },
+        });
+        if let Some(preconditions) = &case.preconditions {
+            fields["description"] = json!(format!("Preconditions: {}", preconditions));
+        }
+
+        let data = self.graphql(MUTATION, json!({
+            "testType": { "name": "Manual" },
+            "steps": steps,
+            "folderPath": Self::folder_path(target),
+            "jira": { "fields": fields },
+        })).await?;
+
+        if let Some(warnings) = data["createTest"]["warnings"].as_array() {
+            for warning in warnings.iter().filter_map(|w| w.as_str()) {
+                tracing::warn!("Xray: {}", warning);
+            }
+        }
+
+        data["createTest"]["test"]["jira"]["key"].as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("Xray did not return a test key"))
+    }
+}
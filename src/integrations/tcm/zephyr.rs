@@ -0,0 +1,156 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+
+use crate::integrations::setting;
+use crate::integrations::tcm::config::ZephyrConfig;
+use crate::integrations::tcm::{ExistingCase, TcmClient, TcmTarget, TestCase};
+
+/// Zephyr Scale cloud API
+const DEFAULT_BASE_URL: &str = "https://api.zephyrscale.smartbear.com/v2";
+
+/// Page size of case listings
+const PAGE_SIZE: usize = 100;
+
+/// Zephyr Scale cloud REST API client
+pub struct ZephyrClient {
+    http_client: crate::proxy::RemoteClient,
+    base_url: String,
+    api_token: String,
+}
+
+impl ZephyrClient {
+    /// Create a client
+    pub fn new(base_url: &str, api_token: String) -> Self {
+        Self {
+            http_client: crate::proxy::RemoteClient::new("Zephyr Scale", reqwest::Client::new()),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_token,
+        }
+    }
+
+    /// Create a client from configuration or `ZEPHYR_API_TOKEN`
+    pub fn from_config(config: Option<&ZephyrConfig>, proxy: Option<&str>) -> Result<Self> {
+        let base_url = config.and_then(|c| c.base_url.clone()).unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+
+        Ok(Self {
+            http_client: crate::proxy::remote("Zephyr Scale", proxy)?,
+            ..Self::new(
+                &base_url,
+                setting(config.map(|c| c.api_token.as_str()), "ZEPHYR_API_TOKEN", "Zephyr Scale API token", "qitops tcm config")?,
+            )
+        })
+    }
+
+    /// Send an API request
+    async fn request(&self, method: reqwest::Method, path: &str, body: Option<Value>) -> Result<Value> {
+        let mut request = self.http_client.request(method, format!("{}{}", self.base_url, path))?
+            .bearer_auth(&self.api_token);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request.send().await
+            .map_err(|e| anyhow!("Failed to send request to Zephyr Scale: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "Could not read error response".to_string());
+
+            return match status.as_u16() {
+                401 => Err(anyhow!("Zephyr Scale authentication error: {}", error_text)),
+                404 => Err(anyhow!("Zephyr Scale resource not found: {}", error_text)),
+                _ => Err(anyhow!("Zephyr Scale API error ({}): {}", status, error_text)),
+            };
+        }
+
+        // Some endpoints answer with an empty body
+        let text = response.text().await
+            .map_err(|e| anyhow!("Failed to read Zephyr Scale response: {}", e))?;
+        if text.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+        serde_json::from_str(&text)
+            .map_err(|e| anyhow!("Failed to parse Zephyr Scale response: {}", e))
+    }
+
+    /// Folder ID of a target's suite
+    fn folder_id(target: &TcmTarget) -> Result<Option<u64>> {
+        target.suite.as_ref()
+            .map(|suite| suite.parse().map_err(|_| anyhow!("Zephyr Scale folder must be a numeric ID: {}", suite)))
+            .transpose()
+    }
+}
+
+#[async_trait]
+impl TcmClient for ZephyrClient {
+    async fn list_cases(&self, target: &TcmTarget) -> Result<Vec<ExistingCase>> {
+        let folder = Self::folder_id(target)?
+            .map(|id| format!("&folderId={}", id))
+            .unwrap_or_default();
+        let mut cases = Vec::new();
+        let mut start = 0;
+
+        loop {
+            let page = self.request(
+                reqwest::Method::GET,
+                &format!("/testcases?projectKey={}{}&maxResults={}&startAt={}", target.project, folder, PAGE_SIZE, start),
+                None,
+            ).await?;
+
+            let values = page["values"].as_array().cloned().unwrap_or_default();
+            cases.extend(values.iter().map(|case| ExistingCase {
+                id: case["key"].as_str().unwrap_or_default().to_string(),
+                title: case["name"].as_str().unwrap_or_default().to_string(),
+            }));
+
+            start += values.len();
+            if values.is_empty() || page["isLast"].as_bool().unwrap_or(true) {
+                break;
+            }
+        }
+
+        Ok(cases)
+    }
+
+    async fn create_case(&self, target: &TcmTarget, case: &TestCase) -> Result<String> {
+        let mut body = json!({
+            "projectKey": target.project,
+            "name": case.title,
+        });
+        if let Some(preconditions) = &case.preconditions {
+            body["precondition"] = json!(preconditions);
+        }
+        if let Some(folder_id) = Self::folder_id(target)? {
+            body["folderId"] = json!(folder_id);
+        }
+
+        let created = self.request(reqwest::Method::POST, "/testcases", Some(body)).await?;
+        let key = created["key"].as_str()
+            .ok_or_else(|| anyhow!("Zephyr Scale did not return a test case key"))?
+            .to_string();
+
+        if !case.steps.is_empty() {
+            // The expected result belongs to the last step
+            let last = case.steps.len() - 1;
+            let items: Vec<Value> = case.steps.iter()
+                .enumerate()
+                .map(|(i, step)| json!({
+                    "inline": {
+                        "description": step,
+                        "expectedResult": if i == last { case.expected.clone() } else { None },
+                    }
+                }))
+                .collect();
+
+            self.request(
+                reqwest::Method::POST,
+                &format!("/testcases/{}/teststeps", key),
+                Some(json!({ "mode": "OVERWRITE", "items": items })),
+            ).await?;
+        }
+
+        Ok(key)
+    }
+}
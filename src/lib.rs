@@ -4,8 +4,41 @@
 pub mod agent;
 pub mod cli;
 pub mod llm;
+#[cfg(feature = "plugins")]
 pub mod plugin;
 pub mod ci;
+pub mod config;
+pub mod masking;
+pub mod schema;
+#[cfg(feature = "bot")]
+pub mod bot;
+pub mod prompt;
+pub mod severity;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+pub mod daemon;
+pub mod audit;
+pub mod integrations;
+pub mod notification;
+pub mod debug;
+pub mod offline;
+pub mod output;
+pub mod proxy;
+pub mod secrets;
+pub mod attest;
+pub mod scan;
+pub mod history;
+#[cfg(feature = "monitoring")]
+pub mod telemetry;
+pub mod runs;
+pub mod bench;
+pub mod checkpoint;
+pub mod hash;
+pub mod git;
+pub mod context;
+pub mod capabilities;
+pub mod error;
+pub mod i18n;
 
 // Version information
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,482 @@
+// Approval of sensitive prompts before they're sent to remote LLM providers
+
+use anyhow::{Result, anyhow};
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::audit::{self, AuditAction, AuditEvent};
+use crate::hash::sha256_hex;
+use crate::llm::client::{LlmError, LlmRequest};
+use crate::severity::Severity;
+
+/// Name of the approval queue database in the config directory
+const DATABASE_FILE: &str = "approvals.db";
+
+/// How often a held prompt checks for a decision
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Actor recorded for prompts that expired without a decision
+const EXPIRY_ACTOR: &str = "qitops";
+
+/// A word that looks like a file path: it has a slash or an extension
+static PATH_WORD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[A-Za-z0-9_.~@-]*(?:/[A-Za-z0-9_.@-]+)+|[A-Za-z0-9_-]+\.[A-Za-z0-9]{1,8}\b").unwrap()
+});
+
+/// Prompt approval configuration (`approval` in `config.json`)
+///
+/// Prompts at or above `threshold` are held in a queue until someone approves them. Prompts to
+/// providers on this machine are never held.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalConfig {
+    /// Sensitivity at which a prompt is held for approval
+    #[serde(default = "default_threshold")]
+    pub threshold: Severity,
+
+    /// What makes a prompt sensitive
+    #[serde(default)]
+    pub rules: Vec<SensitivityRule>,
+
+    /// Seconds to wait for a decision before the prompt is rejected
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+
+    /// Tasks whose prompts are checked (e.g. `test-gen`); empty means all
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tasks: Vec<String>,
+
+    /// Approval queue database, by default `approvals.db` in the config directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue: Option<PathBuf>,
+}
+
+fn default_threshold() -> Severity {
+    Severity::High
+}
+
+fn default_timeout_secs() -> u64 {
+    3600
+}
+
+/// A rule marking prompts that contain a pattern or mention a path as sensitive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensitivityRule {
+    /// Regular expression matched against the prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+
+    /// Glob matched against each path mentioned in the prompt, e.g. `**/billing/**`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    /// Sensitivity of a prompt the rule matches
+    #[serde(default = "default_threshold")]
+    pub sensitivity: Severity,
+}
+
+/// A compiled sensitivity rule
+enum Matcher {
+    Pattern(Regex),
+    Path(GlobMatcher),
+}
+
+/// Why a prompt is sensitive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sensitivity {
+    /// Highest sensitivity of the matching rules
+    pub level: Severity,
+
+    /// What each matching rule found
+    pub reasons: Vec<String>,
+}
+
+/// Sensitivity rules, compiled
+pub struct SensitivityPolicy {
+    rules: Vec<(Matcher, String, Severity)>,
+}
+
+impl SensitivityPolicy {
+    /// Compile the rules of an approval configuration
+    pub fn new(rules: &[SensitivityRule]) -> Result<Self> {
+        let mut compiled = Vec::new();
+        for rule in rules {
+            let (matcher, source) = match (&rule.pattern, &rule.path) {
+                (Some(pattern), None) => (
+                    Matcher::Pattern(Regex::new(pattern).map_err(|e| anyhow!("Invalid approval pattern {}: {}", pattern, e))?),
+                    pattern.clone(),
+                ),
+                (None, Some(path)) => (
+                    Matcher::Path(Glob::new(path).map_err(|e| anyhow!("Invalid approval path {}: {}", path, e))?.compile_matcher()),
+                    path.clone(),
+                ),
+                _ => return Err(anyhow!("An approval rule needs either a pattern or a path")),
+            };
+            compiled.push((matcher, source, rule.sensitivity));
+        }
+
+        Ok(Self { rules: compiled })
+    }
+
+    /// How sensitive a prompt is, or `None` if no rule matches it
+    pub fn assess(&self, prompt: &str) -> Option<Sensitivity> {
+        let mut level = None;
+        let mut reasons = Vec::new();
+
+        for (matcher, source, sensitivity) in &self.rules {
+            let reason = match matcher {
+                Matcher::Pattern(regex) => regex.is_match(prompt).then(|| format!("matches `{}`", source)),
+                Matcher::Path(glob) => mentioned_paths(prompt)
+                    .find(|path| glob.is_match(path))
+                    .map(|path| format!("mentions `{}` (`{}`)", path, source)),
+            };
+            if let Some(reason) = reason {
+                level = level.max(Some(*sensitivity));
+                reasons.push(reason);
+            }
+        }
+
+        level.map(|level| Sensitivity { level, reasons })
+    }
+}
+
+/// Paths mentioned in a prompt, including diff paths without their `a/` or `b/` prefix
+fn mentioned_paths(prompt: &str) -> impl Iterator<Item = &str> {
+    PATH_WORD.find_iter(prompt).flat_map(|word| {
+        let word = word.as_str().trim_end_matches('.');
+        let unprefixed = word.strip_prefix("a/").or_else(|| word.strip_prefix("b/"));
+        unprefixed.into_iter().chain(std::iter::once(word))
+    })
+}
+
+/// Where a queued prompt stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalStatus {
+    /// Waiting for a decision
+    Pending,
+    /// Approved and sent
+    Approved,
+    /// Rejected; the prompt was not sent
+    Rejected,
+    /// Not decided in time; the prompt was not sent
+    Expired,
+}
+
+impl ApprovalStatus {
+    /// Lowercase name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApprovalStatus::Pending => "pending",
+            ApprovalStatus::Approved => "approved",
+            ApprovalStatus::Rejected => "rejected",
+            ApprovalStatus::Expired => "expired",
+        }
+    }
+}
+
+impl fmt::Display for ApprovalStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ApprovalStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "pending" => Ok(ApprovalStatus::Pending),
+            "approved" => Ok(ApprovalStatus::Approved),
+            "rejected" => Ok(ApprovalStatus::Rejected),
+            "expired" => Ok(ApprovalStatus::Expired),
+            _ => Err(anyhow!("Unknown approval status: {}", s)),
+        }
+    }
+}
+
+/// A prompt held for approval
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest {
+    /// Short ID to approve or reject it by
+    pub id: String,
+
+    /// Task the prompt is for, e.g. `test-gen`
+    pub task: Option<String>,
+
+    /// Provider it would be sent to
+    pub provider: String,
+
+    /// Model it would be sent to
+    pub model: String,
+
+    /// Why it was held
+    pub sensitivity: Sensitivity,
+
+    /// The messages of the prompt, as they would be sent
+    pub prompt: String,
+
+    /// Who ran the command that sent it
+    pub requested_by: String,
+
+    /// When it was queued (seconds since the Unix epoch)
+    pub requested_at: u64,
+
+    /// Where it stands
+    pub status: ApprovalStatus,
+
+    /// Who decided
+    pub decided_by: Option<String>,
+
+    /// When it was decided
+    pub decided_at: Option<u64>,
+
+    /// The reviewer's note or reason
+    pub note: Option<String>,
+}
+
+/// Prompts held for approval, in a SQLite database shared by the processes that send prompts
+/// and the ones that decide on them
+pub struct ApprovalQueue {
+    /// Database
+    connection: Connection,
+
+    /// Database path
+    path: PathBuf,
+}
+
+impl ApprovalQueue {
+    /// Open the approval queue of a configuration
+    pub fn open(config: &ApprovalConfig) -> Result<Self> {
+        match &config.queue {
+            Some(path) => Self::at(path),
+            None => Self::at(&crate::config::config_dir()?.join(DATABASE_FILE)),
+        }
+    }
+
+    /// Open an approval queue in a database file, creating it if needed
+    pub fn at(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let connection = Connection::open(path)
+            .map_err(|e| anyhow!("Failed to open approval queue {}: {}", path.display(), e))?;
+
+        connection.busy_timeout(Duration::from_secs(5))?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS approvals (
+                id TEXT PRIMARY KEY,
+                task TEXT,
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL,
+                sensitivity TEXT NOT NULL,
+                reasons TEXT NOT NULL,
+                prompt TEXT NOT NULL,
+                requested_by TEXT NOT NULL,
+                requested_at INTEGER NOT NULL,
+                status TEXT NOT NULL,
+                decided_by TEXT,
+                decided_at INTEGER,
+                note TEXT
+            );
+            CREATE INDEX IF NOT EXISTS approvals_status ON approvals (status, requested_at);",
+        )?;
+
+        Ok(Self { connection, path: path.to_path_buf() })
+    }
+
+    /// Database path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Hold a prompt for approval, returning its ID
+    pub fn enqueue(&self, request: &LlmRequest, provider: &str, task: Option<&str>, sensitivity: &Sensitivity, requested_by: &str) -> Result<String> {
+        let prompt = prompt_text(request);
+        let requested_at = now();
+        let seed = format!("{}\0{}\0{}\0{:?}", prompt, provider, std::process::id(), SystemTime::now());
+        let id = sha256_hex(seed.as_bytes())[..8].to_string();
+
+        self.connection.execute(
+            "INSERT INTO approvals (id, task, provider, model, sensitivity, reasons, prompt, requested_by, requested_at, status)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 'pending')",
+            params![id, task, provider, request.model, sensitivity.level.as_str(), serde_json::to_string(&sensitivity.reasons)?,
+                prompt, requested_by, requested_at],
+        )?;
+
+        Ok(id)
+    }
+
+    /// A queued prompt by ID
+    pub fn get(&self, id: &str) -> Result<Option<ApprovalRequest>> {
+        Ok(self.select("WHERE id = ?1", params![id])?.pop())
+    }
+
+    /// Queued prompts, oldest first; only pending ones unless `all`
+    pub fn list(&self, all: bool) -> Result<Vec<ApprovalRequest>> {
+        match all {
+            true => self.select("ORDER BY requested_at, id", params![]),
+            false => self.select("WHERE status = 'pending' ORDER BY requested_at, id", params![]),
+        }
+    }
+
+    /// Approve or reject a pending prompt, recording the decision in the audit log
+    ///
+    /// Whoever sent the prompt can't decide on it; that takes a second person.
+    pub fn decide(&self, id: &str, approve: bool, actor: &str, note: Option<&str>) -> Result<ApprovalRequest> {
+        let held = self.get(id)?.ok_or_else(|| anyhow!("No queued prompt {}", id))?;
+        if held.requested_by == actor {
+            return Err(anyhow!("Prompt {} was sent by {}, who can't decide on it; ask someone else to review it", id, actor));
+        }
+
+        let status = if approve { ApprovalStatus::Approved } else { ApprovalStatus::Rejected };
+        self.finish(id, status, actor, note)?;
+
+        let request = self.get(id)?.ok_or_else(|| anyhow!("No queued prompt {}", id))?;
+        let action = if approve { AuditAction::PromptApproved } else { AuditAction::PromptRejected };
+        let mut event = AuditEvent::new(actor, action, format!("prompt {} to {}", id, request.provider));
+        event.details = note.map(str::to_string);
+        audit::record(event);
+
+        Ok(request)
+    }
+
+    /// Move a pending prompt to a final status
+    fn finish(&self, id: &str, status: ApprovalStatus, actor: &str, note: Option<&str>) -> Result<()> {
+        let updated = self.connection.execute(
+            "UPDATE approvals SET status = ?2, decided_by = ?3, decided_at = ?4, note = ?5 WHERE id = ?1 AND status = 'pending'",
+            params![id, status.as_str(), actor, now(), note],
+        )?;
+        if updated == 0 {
+            return match self.get(id)? {
+                Some(request) => Err(anyhow!("Prompt {} is already {}", id, request.status)),
+                None => Err(anyhow!("No queued prompt {}", id)),
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Status of a queued prompt
+    fn status(&self, id: &str) -> Result<Option<ApprovalStatus>> {
+        let status: Option<String> = self.connection
+            .query_row("SELECT status FROM approvals WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()?;
+        status.map(|status| status.parse()).transpose()
+    }
+
+    /// Queued prompts matching a condition
+    fn select(&self, condition: &str, values: impl rusqlite::Params) -> Result<Vec<ApprovalRequest>> {
+        let sql = format!(
+            "SELECT id, task, provider, model, sensitivity, reasons, prompt, requested_by, requested_at, status, decided_by, decided_at, note
+             FROM approvals {}", condition,
+        );
+        let mut statement = self.connection.prepare(&sql)?;
+        let rows = statement.query_map(values, |row| {
+            Ok(ApprovalRequest {
+                id: row.get(0)?,
+                task: row.get(1)?,
+                provider: row.get(2)?,
+                model: row.get(3)?,
+                sensitivity: Sensitivity {
+                    level: Severity::from(row.get::<_, String>(4)?),
+                    reasons: serde_json::from_str(&row.get::<_, String>(5)?).unwrap_or_default(),
+                },
+                prompt: row.get(6)?,
+                requested_by: row.get(7)?,
+                requested_at: row.get(8)?,
+                status: row.get::<_, String>(9)?.parse().unwrap_or(ApprovalStatus::Pending),
+                decided_by: row.get(10)?,
+                decided_at: row.get(11)?,
+                note: row.get(12)?,
+            })
+        })?;
+
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+}
+
+/// Holds sensitive prompts to remote providers until they're approved
+pub struct ApprovalGate {
+    config: ApprovalConfig,
+    policy: SensitivityPolicy,
+}
+
+impl ApprovalGate {
+    /// Create a gate from its configuration
+    pub fn new(config: ApprovalConfig) -> Result<Self> {
+        let policy = SensitivityPolicy::new(&config.rules)?;
+        Ok(Self { config, policy })
+    }
+
+    /// Wait until a prompt may be sent to a remote provider
+    ///
+    /// Prompts below the threshold pass right away. A sensitive one is queued and the call waits
+    /// for someone to approve it; a rejected prompt, or one not decided within `timeout_secs`,
+    /// fails with [`LlmError::NotApproved`].
+    pub async fn wait(&self, request: &LlmRequest, provider: &str, task: Option<&str>) -> Result<()> {
+        if !self.config.tasks.is_empty() && !task.is_some_and(|task| self.config.tasks.iter().any(|t| t == task)) {
+            return Ok(());
+        }
+        let Some(sensitivity) = self.policy.assess(&prompt_text(request)).filter(|s| s.level >= self.config.threshold) else {
+            return Ok(());
+        };
+
+        let queue = ApprovalQueue::open(&self.config)?;
+        let id = queue.enqueue(request, provider, task, &sensitivity, &audit::current_actor())?;
+        tracing::warn!(
+            "Prompt {} to {} is {} sensitivity ({}); waiting for approval with `qitops approvals approve {}`",
+            id, provider, sensitivity.level, sensitivity.reasons.join(", "), id,
+        );
+
+        let deadline = Instant::now() + Duration::from_secs(self.config.timeout_secs);
+        loop {
+            match queue.status(&id)? {
+                Some(ApprovalStatus::Approved) => {
+                    tracing::info!("Prompt {} was approved", id);
+                    return Ok(());
+                },
+                Some(ApprovalStatus::Pending) if Instant::now() < deadline => tokio::time::sleep(POLL_INTERVAL).await,
+                Some(ApprovalStatus::Pending) => {
+                    let note = format!("not decided within {}s", self.config.timeout_secs);
+                    // Someone may decide just as the wait runs out
+                    if queue.finish(&id, ApprovalStatus::Expired, EXPIRY_ACTOR, Some(&note)).is_err() {
+                        continue;
+                    }
+                    let mut event = AuditEvent::new(EXPIRY_ACTOR, AuditAction::PromptRejected, format!("prompt {} to {}", id, provider));
+                    event.details = Some(note.clone());
+                    audit::record(event);
+                    return Err(LlmError::NotApproved(format!("prompt {} was {}", id, note)).into());
+                },
+                _ => {
+                    let request = queue.get(&id)?.ok_or_else(|| anyhow!("Prompt {} left the approval queue", id))?;
+                    let reason = request.note.map(|note| format!(": {}", note)).unwrap_or_default();
+                    return Err(LlmError::NotApproved(format!(
+                        "prompt {} was rejected by {}{}", id, request.decided_by.unwrap_or_default(), reason,
+                    )).into());
+                },
+            }
+        }
+    }
+}
+
+/// A request's messages as one text, the way a reviewer reads them
+fn prompt_text(request: &LlmRequest) -> String {
+    request.messages.iter()
+        .map(|message| format!("[{}]\n{}", message.role, message.content))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,51 @@
+use anyhow::Result;
+use futures_util::StreamExt;
+use std::future::Future;
+
+/// Requests in flight at once when `batch_concurrency` isn't configured
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 4;
+
+/// Counts reported as the requests of a batch complete
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchProgress {
+    /// Requests in the batch
+    pub total: usize,
+
+    /// Requests finished, successfully or not
+    pub completed: usize,
+
+    /// Requests that failed
+    pub failed: usize,
+}
+
+/// Run `send` over the items with at most `concurrency` in flight, returning results in item order
+///
+/// One failure doesn't stop the batch; each item gets its own result. `progress` is called
+/// after every completion.
+pub async fn run_batch<I, R, S, Fut, P>(items: Vec<I>, concurrency: usize, send: S, mut progress: P) -> Vec<Result<R>>
+where
+    S: Fn(I) -> Fut,
+    Fut: Future<Output = Result<R>>,
+    P: FnMut(&BatchProgress),
+{
+    let mut counts = BatchProgress { total: items.len(), ..BatchProgress::default() };
+    let mut results: Vec<Option<Result<R>>> = items.iter().map(|_| None).collect();
+
+    let mut in_flight = futures_util::stream::iter(items.into_iter().enumerate())
+        .map(|(index, item)| {
+            let sent = send(item);
+            async move { (index, sent.await) }
+        })
+        .buffer_unordered(concurrency.max(1));
+
+    while let Some((index, result)) = in_flight.next().await {
+        counts.completed += 1;
+        if result.is_err() {
+            counts.failed += 1;
+        }
+        results[index] = Some(result);
+        progress(&counts);
+    }
+
+    results.into_iter().map(|result| result.expect("every batch item completes")).collect()
+}
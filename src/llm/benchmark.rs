@@ -0,0 +1,241 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::llm::{LlmRequest, LlmRouter, TokenCounter};
+
+/// A prompt in the benchmark suite, with a scorer for the answer
+pub struct BenchmarkCase {
+    /// Case name
+    pub name: &'static str,
+
+    /// System message
+    pub system: &'static str,
+
+    /// Prompt
+    pub prompt: &'static str,
+
+    /// Score an answer from 0 to 1
+    pub score: fn(&str) -> f64,
+}
+
+/// The fixed prompt suite, covering the kinds of answers QitOps relies on
+pub const SUITE: &[BenchmarkCase] = &[
+    BenchmarkCase {
+        name: "risk",
+        system: "You are a risk assessment expert. Analyze code changes and give an overall risk level (Low, Medium, High, or Critical) with reasons and recommendations.",
+        prompt: "Assess the risk of this change:\n\n```diff\n def find_user(db, name):\n-    return db.execute(\"SELECT * FROM users WHERE name = ?\", (name,))\n+    return db.execute(\"SELECT * FROM users WHERE name = '\" + name + \"'\")\n```",
+        score: score_risk,
+    },
+    BenchmarkCase {
+        name: "test-gen",
+        system: "You are a QA engineer. Write concise test cases in markdown.",
+        prompt: "Generate test cases for this function:\n\n```python\ndef divide(a, b):\n    return a / b\n```",
+        score: score_test_gen,
+    },
+    BenchmarkCase {
+        name: "findings-json",
+        system: "You are a code reviewer. Reply only with JSON.",
+        prompt: "Review this code and reply only with JSON of the form {\"findings\": [{\"line\": 1, \"severity\": \"low|medium|high|critical\", \"title\": \"short title\"}]}.\n\n```python\n1 def connect():\n2     password = \"hunter2\"\n3     return db.connect(user=\"admin\", password=password)\n```",
+        score: score_findings,
+    },
+    BenchmarkCase {
+        name: "test-data",
+        system: "You generate realistic test data. Reply only with JSON.",
+        prompt: "Generate exactly 3 user records as a JSON array of objects with the fields name (string), email (string) and age (integer).",
+        score: score_test_data,
+    },
+];
+
+/// Outcome of one case
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseResult {
+    /// Case name
+    pub case: String,
+
+    /// Response latency in milliseconds
+    pub latency_ms: u64,
+
+    /// Tokens in the answer
+    pub completion_tokens: usize,
+
+    /// Estimated cost in USD
+    pub cost_usd: f64,
+
+    /// Quality score from 0 to 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+
+    /// Error, if the request failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Benchmark results of one provider
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderBenchmark {
+    /// Provider name
+    pub provider: String,
+
+    /// Model benchmarked
+    pub model: String,
+
+    /// Results of every case and run
+    pub results: Vec<CaseResult>,
+}
+
+impl ProviderBenchmark {
+    /// Successful results
+    fn succeeded(&self) -> impl Iterator<Item = &CaseResult> {
+        self.results.iter().filter(|r| r.error.is_none())
+    }
+
+    /// Number of failed requests
+    pub fn failures(&self) -> usize {
+        self.results.iter().filter(|r| r.error.is_some()).count()
+    }
+
+    /// Mean latency in milliseconds of successful requests
+    pub fn mean_latency_ms(&self) -> Option<u64> {
+        let latencies: Vec<u64> = self.succeeded().map(|r| r.latency_ms).collect();
+        (!latencies.is_empty()).then(|| latencies.iter().sum::<u64>() / latencies.len() as u64)
+    }
+
+    /// Completion tokens generated per second
+    pub fn tokens_per_second(&self) -> Option<f64> {
+        let tokens: usize = self.succeeded().map(|r| r.completion_tokens).sum();
+        let millis: u64 = self.succeeded().map(|r| r.latency_ms).sum();
+        (millis > 0).then(|| tokens as f64 * 1000.0 / millis as f64)
+    }
+
+    /// Total estimated cost in USD
+    pub fn cost_usd(&self) -> f64 {
+        self.results.iter().map(|r| r.cost_usd).sum()
+    }
+
+    /// Quality score from 0 to 100; failed requests score 0
+    pub fn quality(&self) -> f64 {
+        if self.results.is_empty() {
+            return 0.0;
+        }
+        self.results.iter().map(|r| r.score.unwrap_or(0.0)).sum::<f64>() * 100.0 / self.results.len() as f64
+    }
+}
+
+/// Run the suite `runs` times against the router's default provider, bypassing the cache
+pub async fn run(router: &LlmRouter, runs: usize) -> ProviderBenchmark {
+    let provider = router.default_provider().to_string();
+    let model = router.default_model().unwrap_or_default();
+    let counter = TokenCounter::for_model(&model);
+    let mut results = Vec::new();
+
+    for _ in 0..runs {
+        for case in SUITE {
+            let request = LlmRequest::new(case.prompt.to_string(), model.clone())
+                .with_system_message(case.system.to_string())
+                .with_cache(false);
+
+            let started = std::time::Instant::now();
+            results.push(match router.send(request, None).await {
+                Ok(response) => CaseResult {
+                    case: case.name.to_string(),
+                    latency_ms: response.latency_ms.unwrap_or_else(|| started.elapsed().as_millis() as u64),
+                    completion_tokens: counter.count(&response.text),
+                    cost_usd: response.metadata.get("cost_usd").and_then(Value::as_f64).unwrap_or(0.0),
+                    score: Some((case.score)(&response.text)),
+                    error: None,
+                },
+                Err(e) => CaseResult {
+                    case: case.name.to_string(),
+                    latency_ms: started.elapsed().as_millis() as u64,
+                    completion_tokens: 0,
+                    cost_usd: 0.0,
+                    score: None,
+                    error: Some(e.to_string()),
+                },
+            });
+        }
+    }
+
+    ProviderBenchmark { provider, model, results }
+}
+
+/// Fraction of keyword groups with at least one keyword in the text
+fn keyword_score(text: &str, groups: &[&[&str]]) -> f64 {
+    let text = text.to_lowercase();
+    let found = groups.iter()
+        .filter(|group| group.iter().any(|keyword| text.contains(keyword)))
+        .count();
+
+    found as f64 / groups.len() as f64
+}
+
+/// The JSON value in an answer, allowing for code fences and surrounding prose
+fn extract_json(text: &str) -> Option<Value> {
+    serde_json::from_str(crate::llm::json::extract_json(text)).ok()
+}
+
+/// The change introduces SQL injection: expect a high rating, the vulnerability and the fix
+fn score_risk(text: &str) -> f64 {
+    keyword_score(text, &[
+        &["high", "critical"],
+        &["injection"],
+        &["parameteri", "prepared statement", "placeholder", "bind"],
+    ])
+}
+
+/// Expect division by zero, sign and fraction cases, and checkable expectations
+fn score_test_gen(text: &str) -> f64 {
+    keyword_score(text, &[
+        &["zero"],
+        &["negative"],
+        &["float", "decimal", "fraction"],
+        &["expected", "assert"],
+    ])
+}
+
+/// Expect valid JSON with the hardcoded password on line 2
+fn score_findings(text: &str) -> f64 {
+    let Some(json) = extract_json(text) else {
+        return 0.0;
+    };
+    let Some(findings) = json["findings"].as_array() else {
+        return 0.25;
+    };
+
+    let mut score = 0.5;
+    if findings.iter().any(|f| f["severity"].is_string()) {
+        score += 0.25;
+    }
+    if findings.iter().any(|f| {
+        f["line"].as_u64() == Some(2)
+            || f["title"].as_str().is_some_and(|title| {
+                let title = title.to_lowercase();
+                title.contains("password") || title.contains("credential") || title.contains("secret")
+            })
+    }) {
+        score += 0.25;
+    }
+
+    score
+}
+
+/// Expect a JSON array of exactly three well-formed users
+fn score_test_data(text: &str) -> f64 {
+    let Some(Value::Array(users)) = extract_json(text) else {
+        return 0.0;
+    };
+
+    let mut score = 0.4;
+    if users.len() == 3 {
+        score += 0.3;
+    }
+    if !users.is_empty() && users.iter().all(|user| {
+        user["name"].is_string()
+            && user["email"].as_str().is_some_and(|email| email.contains('@'))
+            && user["age"].is_u64()
+    }) {
+        score += 0.3;
+    }
+
+    score
+}
@@ -1,36 +1,55 @@
-use anyhow::Result;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use anyhow::{Result, anyhow};
+use rusqlite::{Connection, OptionalExtension, params};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::{Duration, SystemTime};
-
-use crate::llm::client::{LlmRequest, LlmResponse};
-
-/// Cache entry for LLM responses
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct CacheEntry {
-    /// Response data
-    response: LlmResponse,
-    
-    /// Timestamp when the entry was created
-    created_at: u64,
-    
-    /// Timestamp when the entry expires
-    expires_at: u64,
+use std::time::SystemTime;
+
+use crate::hash::sha256_hex;
+use crate::llm::client::{CacheConfig, LlmRequest, LlmResponse};
+
+/// Name of the cache database in the cache directory
+const DATABASE_FILE: &str = "cache.db";
+
+/// Cache statistics
+#[derive(Debug, Clone, Default)]
+pub struct CacheStats {
+    /// Number of cached responses
+    pub entries: u64,
+
+    /// Number of those that have expired but not yet been evicted
+    pub expired: u64,
+
+    /// Total size of cached responses in bytes
+    pub size_bytes: u64,
+
+    /// Number of times a cached response was served
+    pub hits: u64,
+
+    /// When the oldest entry was cached (seconds since the Unix epoch)
+    pub oldest: Option<u64>,
+
+    /// When the newest entry was cached (seconds since the Unix epoch)
+    pub newest: Option<u64>,
 }
 
-/// LLM response cache
+/// LLM response cache, stored in SQLite so it survives restarts
+///
+/// Entries expire after the TTL, and the least recently used entries are evicted when the
+/// cache grows beyond its maximum size. Without the disk cache the database is kept in memory.
 pub struct ResponseCache {
     /// Cache directory
     cache_dir: PathBuf,
-    
-    /// In-memory cache
-    memory_cache: HashMap<String, CacheEntry>,
-    
+
+    /// Cache database
+    connection: Connection,
+
     /// Cache TTL in seconds
     ttl: u64,
-    
+
+    /// Maximum total size of cached responses in bytes, or 0 for no limit
+    max_size: u64,
+
     /// Whether to use disk cache
     use_disk: bool,
 }
@@ -39,174 +58,249 @@ impl ResponseCache {
     /// Create a new response cache
     pub fn new(ttl_seconds: u64, use_disk: bool) -> Result<Self> {
         let cache_dir = Self::get_cache_dir()?;
-        
-        // Create the cache directory if it doesn't exist
-        if use_disk && !cache_dir.exists() {
-            fs::create_dir_all(&cache_dir)?;
-        }
-        
+        let connection = Self::open(&cache_dir, use_disk)?;
+
         Ok(Self {
             cache_dir,
-            memory_cache: HashMap::new(),
+            connection,
             ttl: ttl_seconds,
+            max_size: 0,
             use_disk,
         })
     }
-    
+
+    /// Create a response cache from configuration
+    pub fn from_config(config: &CacheConfig) -> Result<Self> {
+        let cache = Self::new(config.ttl_seconds, config.use_disk)?
+            .with_max_size(config.max_size_mb * 1024 * 1024);
+
+        match &config.namespace {
+            Some(namespace) => cache.with_namespace(namespace),
+            None => Ok(cache),
+        }
+    }
+
     /// Get the cache directory
     fn get_cache_dir() -> Result<PathBuf> {
         let cache_dir = dirs::cache_dir()
             .ok_or_else(|| anyhow::anyhow!("Could not determine cache directory"))?
             .join("qitops")
             .join("llm_cache");
-            
+
         Ok(cache_dir)
     }
-    
+
+    /// Open the cache database in a directory, or in memory
+    fn open(cache_dir: &Path, use_disk: bool) -> Result<Connection> {
+        let connection = if use_disk {
+            fs::create_dir_all(cache_dir)?;
+            let path = cache_dir.join(DATABASE_FILE);
+            Connection::open(&path)
+                .map_err(|e| anyhow!("Failed to open cache database {}: {}", path.display(), e))?
+        } else {
+            Connection::open_in_memory()?
+        };
+
+        // Several processes may share the cache, so wait for locks instead of failing
+        connection.busy_timeout(std::time::Duration::from_secs(5))?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS responses (
+                key TEXT PRIMARY KEY,
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL,
+                response TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                created_at INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL,
+                last_accessed INTEGER NOT NULL,
+                hits INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE INDEX IF NOT EXISTS responses_last_accessed ON responses (last_accessed);
+            CREATE INDEX IF NOT EXISTS responses_expires_at ON responses (expires_at);",
+        )?;
+
+        Ok(connection)
+    }
+
     /// Generate a cache key for a request
+    ///
+    /// The key is a SHA-256 digest of everything that changes the answer: the provider, model,
+    /// messages, sampling parameters, provider options, tools, JSON mode and seed. Unlike
+    /// `DefaultHasher`, it is stable across builds, so keys stay valid for a persistent cache.
     fn generate_key(&self, request: &LlmRequest, provider: &str) -> String {
-        // Create a simple hash of the request and provider
-        // In a real implementation, we would use a more sophisticated hashing algorithm
-        let mut key = format!("{}-{}", provider, request.model);
-        
-        // Add messages to the key
-        for message in &request.messages {
-            key.push_str(&format!("-{}-{}", message.role, message.content));
+        let options: BTreeMap<&String, &serde_json::Value> = request.options.iter().collect();
+        let key = serde_json::json!({
+            "provider": provider,
+            "model": request.model,
+            "messages": request.messages,
+            "max_tokens": request.max_tokens,
+            "temperature": request.temperature,
+            "top_p": request.top_p,
+            "frequency_penalty": request.frequency_penalty,
+            "presence_penalty": request.presence_penalty,
+            "stop": request.stop,
+            "options": options,
+            "tools": request.tools,
+            "json_mode": request.json_mode,
+            "seed": request.seed,
+        });
+
+        sha256_hex(key.to_string().as_bytes())
+    }
+
+    /// Keep this cache in a subdirectory of the cache directory
+    pub fn with_namespace(mut self, namespace: &str) -> Result<Self> {
+        self.cache_dir = self.cache_dir.join(namespace);
+        if self.use_disk {
+            self.connection = Self::open(&self.cache_dir, true)?;
         }
-        
-        // Hash the key
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-        
-        let mut hasher = DefaultHasher::new();
-        key.hash(&mut hasher);
-        format!("{:x}", hasher.finish())
+
+        Ok(self)
     }
-    
-    /// Get the path to a cache file
-    fn get_cache_file(&self, key: &str) -> PathBuf {
-        self.cache_dir.join(format!("{}.json", key))
+
+    /// Limit the total size of cached responses, evicting the least recently used first
+    pub fn with_max_size(mut self, max_size_bytes: u64) -> Self {
+        self.max_size = max_size_bytes;
+        self
     }
-    
+
+    /// Cache database path, or `None` for an in-memory cache
+    pub fn path(&self) -> Option<PathBuf> {
+        self.use_disk.then(|| self.cache_dir.join(DATABASE_FILE))
+    }
+
     /// Get a response from the cache
     pub fn get(&self, request: &LlmRequest, provider: &str) -> Option<LlmResponse> {
         let key = self.generate_key(request, provider);
-        
-        // Check memory cache first
-        if let Some(entry) = self.memory_cache.get(&key) {
-            let now = SystemTime::now()
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs();
-                
-            if entry.expires_at > now {
-                return Some(entry.response.clone());
-            }
-        }
-        
-        // If not in memory cache and disk cache is enabled, check disk
-        if self.use_disk {
-            let cache_file = self.get_cache_file(&key);
-            if cache_file.exists() {
-                if let Ok(content) = fs::read_to_string(&cache_file) {
-                    if let Ok(entry) = serde_json::from_str::<CacheEntry>(&content) {
-                        let now = SystemTime::now()
-                            .duration_since(SystemTime::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs();
-                            
-                        if entry.expires_at > now {
-                            return Some(entry.response.clone());
-                        } else {
-                            // Entry is expired, remove it
-                            let _ = fs::remove_file(&cache_file);
-                        }
-                    }
-                }
-            }
-        }
-        
-        None
+        let now = now();
+
+        let response: Option<String> = self.connection
+            .query_row(
+                "SELECT response FROM responses WHERE key = ?1 AND expires_at > ?2",
+                params![key, now],
+                |row| row.get(0),
+            )
+            .optional()
+            .inspect_err(|e| tracing::warn!("Failed to read from LLM cache: {}", e))
+            .ok()
+            .flatten();
+
+        let response = serde_json::from_str(&response?).ok()?;
+
+        let _ = self.connection.execute(
+            "UPDATE responses SET last_accessed = ?1, hits = hits + 1 WHERE key = ?2",
+            params![now, key],
+        );
+
+        Some(response)
     }
-    
+
     /// Put a response in the cache
     pub fn put(&mut self, request: &LlmRequest, provider: &str, response: LlmResponse) -> Result<()> {
         let key = self.generate_key(request, provider);
-        
-        let now = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
-            
-        let entry = CacheEntry {
-            response: response.clone(),
-            created_at: now,
-            expires_at: now + self.ttl,
-        };
-        
-        // Add to memory cache
-        self.memory_cache.insert(key.clone(), entry.clone());
-        
-        // If disk cache is enabled, write to disk
-        if self.use_disk {
-            let cache_file = self.get_cache_file(&key);
-            let content = serde_json::to_string(&entry)?;
-            fs::write(cache_file, content)?;
+        let content = serde_json::to_string(&response)?;
+        let now = now();
+
+        self.connection.execute(
+            "INSERT OR REPLACE INTO responses (key, provider, model, response, size, created_at, expires_at, last_accessed, hits)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?6, 0)",
+            params![key, provider, request.model, content, content.len() as u64, now, now + self.ttl],
+        )?;
+
+        self.clean_expired()?;
+        self.evict()
+    }
+
+    /// Evict the least recently used entries until the cache fits its maximum size
+    ///
+    /// Entries are visited oldest first through the `last_accessed` index, stopping as soon as
+    /// enough space is freed, so a put doesn't read the whole cache.
+    fn evict(&mut self) -> Result<()> {
+        if self.max_size == 0 {
+            return Ok(());
+        }
+
+        let total: u64 = self.connection.query_row("SELECT COALESCE(SUM(size), 0) FROM responses", [], |row| row.get(0))?;
+        if total <= self.max_size {
+            return Ok(());
+        }
+
+        let mut excess = total - self.max_size;
+        let mut oldest = Vec::new();
+        {
+            let mut statement = self.connection.prepare("SELECT key, size FROM responses ORDER BY last_accessed, rowid")?;
+            let mut rows = statement.query([])?;
+            while excess > 0
+                && let Some(row) = rows.next()?
+            {
+                let size: u64 = row.get(1)?;
+                excess = excess.saturating_sub(size);
+                oldest.push(row.get::<_, String>(0)?);
+            }
+        }
+
+        let transaction = self.connection.transaction()?;
+        for key in &oldest {
+            transaction.execute("DELETE FROM responses WHERE key = ?1", params![key])?;
         }
-        
+        transaction.commit()?;
+
+        tracing::debug!("Evicted {} entries from the LLM cache", oldest.len());
         Ok(())
     }
-    
+
     /// Clear the cache
     pub fn clear(&mut self) -> Result<()> {
-        // Clear memory cache
-        self.memory_cache.clear();
-        
-        // If disk cache is enabled, clear disk cache
-        if self.use_disk {
-            if self.cache_dir.exists() {
-                for entry in fs::read_dir(&self.cache_dir)? {
-                    let entry = entry?;
-                    let path = entry.path();
-                    if path.is_file() && path.extension().map_or(false, |ext| ext == "json") {
-                        fs::remove_file(path)?;
-                    }
+        self.connection.execute("DELETE FROM responses", [])?;
+
+        // Remove files left by the JSON file cache of earlier versions
+        if self.use_disk && self.cache_dir.exists() {
+            for entry in fs::read_dir(&self.cache_dir)? {
+                let path = entry?.path();
+                if path.is_file() && path.extension().is_some_and(|ext| ext == "json") {
+                    fs::remove_file(path)?;
                 }
             }
         }
-        
+
+        if self.use_disk {
+            self.connection.execute_batch("VACUUM")?;
+        }
+
         Ok(())
     }
-    
+
     /// Clean expired entries
     pub fn clean_expired(&mut self) -> Result<()> {
-        let now = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
-            
-        // Clean memory cache
-        self.memory_cache.retain(|_, entry| entry.expires_at > now);
-        
-        // If disk cache is enabled, clean disk cache
-        if self.use_disk {
-            if self.cache_dir.exists() {
-                for entry in fs::read_dir(&self.cache_dir)? {
-                    let entry = entry?;
-                    let path = entry.path();
-                    if path.is_file() && path.extension().map_or(false, |ext| ext == "json") {
-                        if let Ok(content) = fs::read_to_string(&path) {
-                            if let Ok(entry) = serde_json::from_str::<CacheEntry>(&content) {
-                                if entry.expires_at <= now {
-                                    fs::remove_file(path)?;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        
+        self.connection.execute("DELETE FROM responses WHERE expires_at <= ?1", params![now()])?;
         Ok(())
     }
+
+    /// Cache statistics
+    pub fn stats(&self) -> Result<CacheStats> {
+        let stats = self.connection.query_row(
+            "SELECT COUNT(*), COUNT(CASE WHEN expires_at <= ?1 THEN 1 END), COALESCE(SUM(size), 0),
+                    COALESCE(SUM(hits), 0), MIN(created_at), MAX(created_at)
+             FROM responses",
+            params![now()],
+            |row| Ok(CacheStats {
+                entries: row.get(0)?,
+                expired: row.get(1)?,
+                size_bytes: row.get(2)?,
+                hits: row.get(3)?,
+                oldest: row.get(4)?,
+                newest: row.get(5)?,
+            }),
+        )?;
+
+        Ok(stats)
+    }
+}
+
+/// Current time in seconds since the Unix epoch
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
 }
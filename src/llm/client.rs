@@ -1,15 +1,25 @@
-use anyhow::{Result, Context, anyhow};
+use anyhow::{Result, anyhow};
 use async_trait::async_trait;
-use reqwest::Client as HttpClient;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use thiserror::Error;
 use tokio::sync::Mutex;
 
+use crate::llm::approval::{ApprovalConfig, ApprovalGate};
+use crate::llm::batch::{self, BatchProgress};
+use crate::llm::cost::CostTracker;
+use crate::llm::ensemble::{self, MergeStrategy};
+use crate::llm::json;
+use crate::llm::pool::{self, ProviderQueue, QueueMetrics};
+use crate::llm::postprocess::{Pipeline, ProcessorConfig, ResponseProcessor};
+use crate::llm::rate_limit::RateLimiter;
+use crate::llm::tokens::{self, TokenCounter, SOURCES_MARKER};
+
 /// LLM client error
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum LlmError {
     /// API error
     #[error("API error: {0}")]
@@ -34,6 +44,37 @@ pub enum LlmError {
     /// Configuration error
     #[error("Configuration error: {0}")]
     ConfigurationError(String),
+
+    /// Server error (5xx)
+    #[error("Server error: {0}")]
+    ServerError(String),
+
+    /// Request timed out
+    #[error("Timeout: {0}")]
+    Timeout(String),
+
+    /// Spending budget used up
+    #[error("LLM budget exceeded: {0}")]
+    BudgetExceeded(String),
+
+    /// Response did not match the expected structure, even after re-prompting
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+
+    /// Sensitive prompt rejected, or not approved in time
+    #[error("Prompt not approved: {0}")]
+    NotApproved(String),
+}
+
+impl LlmError {
+    /// Whether an error is transient (timeout, rate limit, server or network error) and worth
+    /// retrying or handing to the next provider
+    pub fn is_transient(error: &anyhow::Error) -> bool {
+        matches!(
+            error.downcast_ref::<LlmError>(),
+            Some(LlmError::Timeout(_) | LlmError::RateLimitError(_) | LlmError::ServerError(_) | LlmError::NetworkError(_))
+        )
+    }
 }
 
 /// Message role for chat models
@@ -67,6 +108,43 @@ pub struct ChatMessage {
     pub content: String,
 }
 
+/// A function the model may call instead of answering in text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolDefinition {
+    /// Tool name
+    pub name: String,
+
+    /// What the tool does, so the model knows when to call it
+    pub description: String,
+
+    /// JSON schema of the tool's arguments
+    pub parameters: serde_json::Value,
+}
+
+impl ToolDefinition {
+    /// Create a new tool definition
+    pub fn new(name: &str, description: &str, parameters: serde_json::Value) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            parameters,
+        }
+    }
+}
+
+/// A tool invocation returned by the model
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ToolCall {
+    /// Provider-assigned call ID
+    pub id: String,
+
+    /// Name of the tool to call
+    pub name: String,
+
+    /// Arguments, matching the tool's parameter schema
+    pub arguments: serde_json::Value,
+}
+
 /// LLM request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LlmRequest {
@@ -105,6 +183,18 @@ pub struct LlmRequest {
     /// Additional request options
     #[serde(default)]
     pub options: HashMap<String, serde_json::Value>,
+
+    /// Tools the model may call (ignored by providers without function calling)
+    #[serde(default)]
+    pub tools: Vec<ToolDefinition>,
+
+    /// Ask for a JSON document (enforced by providers with a JSON mode, otherwise up to the prompt)
+    #[serde(default)]
+    pub json_mode: bool,
+
+    /// Sampling seed, for reproducible answers from providers that support it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
 }
 
 /// Default top-p value
@@ -144,6 +234,9 @@ impl LlmRequest {
             stop: Vec::new(),
             use_cache: default_use_cache(),
             options: HashMap::new(),
+            tools: Vec::new(),
+            json_mode: false,
+            seed: None,
         }
     }
 
@@ -216,11 +309,40 @@ impl LlmRequest {
         self
     }
 
+    /// Add source documents to the system message
+    ///
+    /// Sources are the same for every request of a command, so they go with the system prompt,
+    /// ahead of the per-request prompt, where providers with prompt caching can reuse them.
+    pub fn with_source_context(self, sources: &str) -> Self {
+        if sources.trim().is_empty() {
+            return self;
+        }
+        self.with_additional_context(format!("{}{}", SOURCES_MARKER.trim_start(), sources))
+    }
+
     /// Add an option
     pub fn with_option(mut self, key: &str, value: serde_json::Value) -> Self {
         self.options.insert(key.to_string(), value);
         self
     }
+
+    /// Offer a tool the model may call
+    pub fn with_tool(mut self, tool: ToolDefinition) -> Self {
+        self.tools.push(tool);
+        self
+    }
+
+    /// Ask for a JSON document
+    pub fn with_json_mode(mut self, json_mode: bool) -> Self {
+        self.json_mode = json_mode;
+        self
+    }
+
+    /// Set the sampling seed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
 }
 
 /// LLM response
@@ -252,6 +374,10 @@ pub struct LlmResponse {
     /// Additional response metadata
     #[serde(default)]
     pub metadata: HashMap<String, serde_json::Value>,
+
+    /// Tool calls the model made instead of (or alongside) answering in text
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCall>,
 }
 
 /// Default timestamp value
@@ -275,6 +401,7 @@ impl LlmResponse {
             latency_ms: None,
             cached: false,
             metadata: HashMap::new(),
+            tool_calls: Vec::new(),
         }
     }
 
@@ -301,6 +428,12 @@ impl LlmResponse {
         self.metadata.insert(key.to_string(), value);
         self
     }
+
+    /// Set the tool calls the model made
+    pub fn with_tool_calls(mut self, tool_calls: Vec<ToolCall>) -> Self {
+        self.tool_calls = tool_calls;
+        self
+    }
 }
 
 /// LLM provider configuration
@@ -321,8 +454,109 @@ pub struct ProviderConfig {
     /// Additional provider-specific configuration
     #[serde(default)]
     pub options: HashMap<String, String>,
+
+    /// Maximum requests per minute; further requests wait in a queue
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<u32>,
+
+    /// Maximum prompt and completion tokens per minute; further requests wait in a queue
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens_per_minute: Option<u32>,
+
+    /// Most requests in flight at once, shared by every router of the process; further requests
+    /// wait in a queue. Defaults to 4 for Ollama and no limit otherwise; `0` means no limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_requests: Option<u32>,
+
+    /// HTTP(S) proxy URL, overriding the `HTTPS_PROXY`/`HTTP_PROXY` environment variables
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+
+    /// Seconds to wait for a response, overriding `fallback.timeout_secs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+
+    /// Seconds to wait for a connection (default 10)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_secs: Option<u64>,
+
+    /// Retries of transient failures, overriding `fallback.max_retries`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+
+    /// Static headers sent with every request, e.g. for an LLM gateway; values may use `${ENV_VAR}`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+
+    /// Authentication sent instead of the provider's own bearer token, e.g. for an LLM gateway
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<ProviderAuth>,
+}
+
+/// How requests to a provider authenticate when a gateway sits in front of it
+///
+/// Credentials may use `${ENV_VAR}` so they can be kept out of the configuration file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "scheme", rename_all = "lowercase")]
+pub enum ProviderAuth {
+    /// `Authorization: Bearer <token>`
+    Bearer { token: String },
+
+    /// `Authorization: Basic <username:password>`
+    Basic {
+        username: String,
+        #[serde(default)]
+        password: String,
+    },
+}
+
+impl ProviderAuth {
+    /// Name of the scheme, as shown by `qitops llm list`
+    pub fn scheme(&self) -> &'static str {
+        match self {
+            ProviderAuth::Bearer { .. } => "bearer",
+            ProviderAuth::Basic { .. } => "basic",
+        }
+    }
+
+    /// Value of the `Authorization` header, with environment variables resolved
+    pub fn header_value(&self) -> Result<String> {
+        use base64::Engine;
+
+        Ok(match self {
+            ProviderAuth::Bearer { token } => format!("Bearer {}", expand_env(token)?),
+            ProviderAuth::Basic { username, password } => format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", expand_env(username)?, expand_env(password)?))
+            ),
+        })
+    }
+}
+
+/// Replace `${VAR}` references in a configured value with environment variables
+pub fn expand_env(value: &str) -> Result<String> {
+    let mut expanded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let end = rest[start..].find('}')
+            .ok_or_else(|| anyhow!("Unclosed ${{ in configured value: {}", value))?;
+        let name = &rest[start + 2..start + end];
+        let resolved = std::env::var(name)
+            .map_err(|_| LlmError::ConfigurationError(format!("Environment variable {} is not set", name)))?;
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&resolved);
+        rest = &rest[start + end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
 }
 
+/// Seconds a provider waits for a connection when `connect_timeout_secs` isn't set
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+
+/// How long a request runs before a "still waiting" warning
+const SLOW_REQUEST_NOTICE: std::time::Duration = std::time::Duration::from_secs(15);
+
 /// LLM router configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RouterConfig {
@@ -339,6 +573,98 @@ pub struct RouterConfig {
     /// Cache configuration
     #[serde(default)]
     pub cache: CacheConfig,
+
+    /// Retry and fallback behavior for failed requests
+    #[serde(default)]
+    pub fallback: FallbackConfig,
+
+    /// Providers that all answer each request, with the answers merged (ensemble mode)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ensemble: Vec<String>,
+
+    /// Requests in flight at once when a command sends a batch (multi-file test-gen, large test-data)
+    #[serde(default = "default_batch_concurrency")]
+    pub batch_concurrency: usize,
+
+    /// Sampling seed for requests that don't set one, for reproducible runs (e.g. in CI)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+
+    /// Steps run in order on every answer before it reaches the agent (see `postprocess`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_process: Vec<ProcessorConfig>,
+
+    /// Sensitive prompts held for approval before they go to remote providers (see `approval`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval: Option<ApprovalConfig>,
+}
+
+fn default_batch_concurrency() -> usize {
+    batch::DEFAULT_BATCH_CONCURRENCY
+}
+
+/// Retry and fallback configuration
+///
+/// A request that times out or fails with a rate limit, server or network error is retried
+/// with exponential backoff, then handed to the next provider in the chain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FallbackConfig {
+    /// Providers to try after the primary one, in order; empty means all other providers in
+    /// configuration order
+    #[serde(default)]
+    pub chain: Vec<String>,
+
+    /// Retries per provider before falling through
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+
+    /// Delay before the first retry in milliseconds, doubled on each further retry
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+
+    /// Upper bound for the retry delay in milliseconds
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+
+    /// Per-request timeout in seconds
+    #[serde(default = "default_request_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_max_retries() -> u32 {
+    2
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    500
+}
+
+fn default_max_backoff_ms() -> u64 {
+    8000
+}
+
+fn default_request_timeout_secs() -> u64 {
+    120
+}
+
+impl Default for FallbackConfig {
+    fn default() -> Self {
+        Self {
+            chain: Vec::new(),
+            max_retries: default_max_retries(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+            timeout_secs: default_request_timeout_secs(),
+        }
+    }
+}
+
+impl FallbackConfig {
+    /// Delay before a retry (0-based)
+    pub fn backoff(&self, retry: u32) -> std::time::Duration {
+        let delay = self.initial_backoff_ms.saturating_mul(1u64 << retry.min(16));
+        std::time::Duration::from_millis(delay.min(self.max_backoff_ms))
+    }
 }
 
 /// Cache configuration
@@ -355,6 +681,15 @@ pub struct CacheConfig {
     /// Whether to use disk cache
     #[serde(default = "default_cache_disk")]
     pub use_disk: bool,
+
+    /// Subdirectory of the disk cache, so separate tenants never share cached responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+
+    /// Maximum cache size in megabytes before the least recently used responses are evicted
+    /// (0 for no limit)
+    #[serde(default = "default_cache_max_size_mb")]
+    pub max_size_mb: u64,
 }
 
 /// Default cache enabled value
@@ -372,12 +707,19 @@ fn default_cache_disk() -> bool {
     true
 }
 
+/// Default maximum cache size
+fn default_cache_max_size_mb() -> u64 {
+    100
+}
+
 impl Default for CacheConfig {
     fn default() -> Self {
         Self {
             enabled: default_cache_enabled(),
             ttl_seconds: default_cache_ttl(),
             use_disk: default_cache_disk(),
+            namespace: None,
+            max_size_mb: default_cache_max_size_mb(),
         }
     }
 }
@@ -392,6 +734,15 @@ impl Default for RouterConfig {
                     api_base: Some("http://localhost:11434".to_string()),
                     default_model: "mistral".to_string(),
                     options: HashMap::new(),
+                    requests_per_minute: None,
+                    tokens_per_minute: None,
+                    max_concurrent_requests: None,
+                    proxy: None,
+                    timeout_secs: None,
+                    connect_timeout_secs: None,
+                    max_retries: None,
+                    headers: HashMap::new(),
+                    auth: None,
                 },
                 ProviderConfig {
                     provider_type: "openai".to_string(),
@@ -399,11 +750,26 @@ impl Default for RouterConfig {
                     api_base: None,
                     default_model: "gpt-3.5-turbo".to_string(),
                     options: HashMap::new(),
+                    requests_per_minute: None,
+                    tokens_per_minute: None,
+                    max_concurrent_requests: None,
+                    proxy: None,
+                    timeout_secs: None,
+                    connect_timeout_secs: None,
+                    max_retries: None,
+                    headers: HashMap::new(),
+                    auth: None,
                 },
             ],
             default_provider: "ollama".to_string(),
             task_providers: HashMap::new(),
+            fallback: FallbackConfig::default(),
             cache: CacheConfig::default(),
+            ensemble: Vec::new(),
+            batch_concurrency: batch::DEFAULT_BATCH_CONCURRENCY,
+            seed: None,
+            post_process: Vec::new(),
+            approval: None,
         }
     }
 }
@@ -419,34 +785,61 @@ pub trait LlmClient: Send + Sync {
 
     /// Check if the client is available
     async fn is_available(&self) -> bool;
+
+    /// List the models the provider can serve
+    async fn list_models(&self) -> Result<Vec<String>> {
+        Err(anyhow!("{} does not support listing models", self.name()))
+    }
 }
 
 // LLM client implementations are now in providers.rs
 
 /// LLM router that manages multiple LLM clients
+#[derive(Clone)]
 pub struct LlmRouter {
     clients: HashMap<String, Arc<dyn LlmClient>>,
     config: RouterConfig,
     default_client: String,
     cache: Option<Arc<Mutex<crate::llm::cache::ResponseCache>>>,
+
+    /// Tokens used by responses from providers (cache hits are free), shared between clones
+    tokens_used: Arc<AtomicU64>,
+
+    /// Cost of requests, shared by clones
+    costs: Arc<CostTracker>,
+
+    /// Rate limiters of providers with limits, shared by clones
+    limiters: HashMap<String, Arc<RateLimiter>>,
+
+    /// Request queues of the providers, shared by clones and by routers over the same endpoint
+    queues: HashMap<String, Arc<ProviderQueue>>,
+
+    /// Post-processing steps run on every answer
+    post_process: Pipeline,
+
+    /// Approval of sensitive prompts to remote providers, if configured
+    approval: Option<Arc<ApprovalGate>>,
 }
 
 impl LlmRouter {
     /// Create a new LLM router with the given configuration
     pub async fn new(config: RouterConfig) -> Result<Self> {
+        let started = std::time::Instant::now();
         let mut clients = HashMap::new();
+        let mut initialized = Vec::new();
         let mut default_client = config.default_provider.clone();
         let mut any_client_available = false;
+        // Why providers can't be used, for the error if none can
+        let mut problems = Vec::new();
 
         // Initialize all providers
         for provider_config in &config.providers {
             // Try to initialize the provider
-            let client_result = match provider_config.provider_type.as_str() {
-                "openai" => crate::llm::providers::OpenAiClient::new(provider_config).map(|c| Arc::new(c) as Arc<dyn LlmClient>),
-                "ollama" => crate::llm::providers::OllamaClient::new(provider_config).map(|c| Arc::new(c) as Arc<dyn LlmClient>),
-                "anthropic" => crate::llm::providers::AnthropicClient::new(provider_config).map(|c| Arc::new(c) as Arc<dyn LlmClient>),
-                _ => {
+            let client_result = match crate::llm::providers::client_for(provider_config) {
+                Some(client) => client,
+                None => {
                     eprintln!("Warning: Unknown provider type: {}", provider_config.provider_type);
+                    problems.push(format!("{}: unknown provider type", provider_config.provider_type));
                     continue;
                 }
             };
@@ -454,6 +847,7 @@ impl LlmRouter {
             // If initialization failed, log the error and continue
             if let Err(e) = &client_result {
                 eprintln!("Warning: Failed to initialize {} client: {}", provider_config.provider_type, e);
+                problems.push(format!("{}: {}", provider_config.provider_type, e));
                 continue;
             }
 
@@ -461,9 +855,29 @@ impl LlmRouter {
             let client = client_result.unwrap();
             let provider_name = client.name().to_string();
             clients.insert(provider_name.clone(), client.clone());
+            initialized.push((provider_name, client));
+        }
 
-            // Check if this client is available
-            if client.is_available().await {
+        // In offline mode a remote default provider is skipped, so fall back to a local one
+        if crate::offline::is_enabled() && !clients.contains_key(&default_client) {
+            default_client.clear();
+        }
+
+        // Check availability of all clients concurrently, so startup costs the slowest check
+        // rather than the sum of them
+        let availability = futures_util::future::join_all(
+            initialized.iter().map(|(_, client)| client.is_available())
+        ).await;
+
+        for ((provider_name, _), available) in initialized.into_iter().zip(availability) {
+            if !available
+                && let Some(provider_config) = config.providers.iter().find(|p| p.provider_type == provider_name)
+            {
+                let (reason, hint) = crate::llm::probe::unavailable_reason(provider_config);
+                tracing::debug!("{} is not available: {}", provider_name, reason);
+                problems.push(format!("{}: {} ({})", provider_name, reason, hint));
+            }
+            if available {
                 any_client_available = true;
 
                 // If this is the default provider, or we haven't found an available client yet,
@@ -474,13 +888,29 @@ impl LlmRouter {
             }
         }
 
+        tracing::debug!("LLM router initialized in {:?}", started.elapsed());
+
         if !any_client_available {
-            return Err(anyhow!("No LLM providers are available"));
+            let details = match problems.is_empty() {
+                true => String::new(),
+                false => format!("\n  - {}", problems.join("\n  - ")),
+            };
+            if crate::offline::is_enabled() {
+                return Err(LlmError::ProviderNotAvailable(format!("no local LLM providers are available; offline mode (--offline) needs a local model or an Ollama server on this machine{}", details)).into());
+            }
+            return Err(LlmError::ProviderNotAvailable(format!("no LLM providers are available{}", details)).into());
+        }
+
+        // Falling back from the default provider quietly would be surprising
+        if default_client != config.default_provider
+            && let Some(problem) = problems.iter().find(|problem| problem.starts_with(&format!("{}:", config.default_provider)))
+        {
+            tracing::warn!("Default provider unavailable, using {} instead. {}", default_client, problem);
         }
 
         // Initialize cache if enabled
         let cache = if config.cache.enabled {
-            match crate::llm::cache::ResponseCache::new(config.cache.ttl_seconds, config.cache.use_disk) {
+            match crate::llm::cache::ResponseCache::from_config(&config.cache) {
                 Ok(cache) => Some(Arc::new(Mutex::new(cache))),
                 Err(e) => {
                     eprintln!("Warning: Failed to initialize cache: {}", e);
@@ -491,79 +921,432 @@ impl LlmRouter {
             None
         };
 
+        // Keep API keys and gateway credentials out of debug bundles
+        crate::debug::add_secrets(config.providers.iter().filter_map(|p| p.api_key.clone()));
+        crate::debug::add_secrets(config.providers.iter().flat_map(|p| {
+            let auth = p.auth.as_ref().and_then(|auth| auth.header_value().ok());
+            p.headers.values().filter_map(|value| expand_env(value).ok()).chain(auth)
+        }));
+
+        let post_process = Pipeline::from_config(&config.post_process)
+            .map_err(|e| LlmError::ConfigurationError(format!("{:#}", e)))?;
+        let approval = config.approval.clone()
+            .map(|approval| ApprovalGate::new(approval).map(Arc::new))
+            .transpose()
+            .map_err(|e| LlmError::ConfigurationError(format!("{:#}", e)))?;
+
+        let limiters = config.providers.iter()
+            .filter_map(|p| {
+                RateLimiter::new(p.requests_per_minute, p.tokens_per_minute)
+                    .map(|limiter| (p.provider_type.clone(), Arc::new(limiter)))
+            })
+            .collect();
+        let queues = Self::queues(&config);
+
         Ok(Self {
             clients,
             config,
             default_client,
             cache,
+            tokens_used: Arc::new(AtomicU64::new(0)),
+            costs: Arc::new(CostTracker::load()),
+            limiters,
+            queues,
+            post_process,
+            approval,
         })
     }
 
+    /// Create a router over the given clients, without availability checks, a cache or a usage ledger
+    ///
+    /// Used where the clients are known up front, such as replaying recorded answers in
+    /// `qitops bench agents`. The first client is the default unless `config` names another.
+    pub fn with_clients(config: RouterConfig, clients: Vec<Arc<dyn LlmClient>>) -> Self {
+        let default_client = clients.iter()
+            .map(|client| client.name().to_string())
+            .find(|name| *name == config.default_provider)
+            .or_else(|| clients.first().map(|client| client.name().to_string()))
+            .unwrap_or_default();
+        let post_process = Pipeline::from_config(&config.post_process).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring invalid post-processing configuration: {:#}", e);
+            Pipeline::default()
+        });
+        let approval = config.approval.clone().and_then(|approval| match ApprovalGate::new(approval) {
+            Ok(gate) => Some(Arc::new(gate)),
+            Err(e) => {
+                tracing::warn!("Ignoring invalid approval configuration: {:#}", e);
+                None
+            },
+        });
+
+        Self {
+            clients: clients.into_iter().map(|client| (client.name().to_string(), client)).collect(),
+            queues: Self::queues(&config),
+            config,
+            default_client,
+            cache: None,
+            tokens_used: Arc::new(AtomicU64::new(0)),
+            costs: Arc::new(CostTracker::new(crate::llm::BudgetConfig::default(), None)),
+            limiters: HashMap::new(),
+            post_process,
+            approval,
+        }
+    }
+
+    /// Request queues of the configured providers
+    fn queues(config: &RouterConfig) -> HashMap<String, Arc<ProviderQueue>> {
+        config.providers.iter()
+            .map(|p| (p.provider_type.clone(), pool::queue_for(p)))
+            .collect()
+    }
+
+    /// Concurrency and queue wait metrics of each provider, in configuration order
+    ///
+    /// Queues are shared by routers over the same endpoint, so the counts cover every request
+    /// of the process to the provider.
+    pub fn queue_metrics(&self) -> Vec<QueueMetrics> {
+        self.config.providers.iter()
+            .filter_map(|p| self.queues.get(&p.provider_type))
+            .map(|queue| queue.metrics())
+            .collect()
+    }
+
     /// Send a request to the LLM using the appropriate client
-    pub async fn send(&self, request: LlmRequest, task: Option<&str>) -> Result<LlmResponse> {
+    pub async fn send(&self, mut request: LlmRequest, task: Option<&str>) -> Result<LlmResponse> {
         // Determine which provider to use based on the task
-        let provider = if let Some(task) = task {
-            self.config.task_providers.get(task)
-                .map(|s| s.as_str())
-                .unwrap_or(&self.default_client)
+        let provider = self.task_provider(task);
+
+        if request.seed.is_none() {
+            request.seed = self.config.seed;
+        }
+
+        if !self.clients.contains_key(provider) {
+            return Err(LlmError::ConfigurationError(format!("provider not found: {}", provider)).into());
+        }
+
+        let response = if self.config.ensemble.len() > 1 {
+            self.send_ensemble(request.clone(), task).await?
         } else {
-            &self.default_client
+            self.dispatch(request.clone(), provider, self.fallback_chain(provider), task).await?
         };
 
-        // Try to get the client
-        let client = self.clients.get(provider)
-            .ok_or_else(|| anyhow!("Provider not found: {}", provider))?;
+        self.post_process.apply(response, &request, task)
+    }
+
+    /// Provide a plugin's response processor to the post-processing steps that name it
+    pub fn register_processor(&mut self, processor: Arc<dyn ResponseProcessor>) {
+        self.post_process.register(processor);
+    }
+
+    /// Send a request in JSON mode and parse the answer into `T`
+    ///
+    /// Answers that aren't valid JSON, don't match `T` or fail `validate` are sent back to the
+    /// model with the error, up to `json::JSON_RETRIES` times.
+    pub async fn send_json<T, F>(&self, request: LlmRequest, task: Option<&str>, validate: F) -> Result<(T, LlmResponse)>
+    where
+        T: serde::de::DeserializeOwned,
+        F: Fn(&T) -> Result<()>,
+    {
+        let mut request = request.with_json_mode(true);
+        let mut attempt = 0;
+
+        loop {
+            let response = self.send(request.clone(), task).await?;
+            match json::parse_json(&response.text, &validate) {
+                Ok(value) => return Ok((value, response)),
+                Err(e) if attempt < json::JSON_RETRIES => {
+                    tracing::warn!("Invalid JSON response (attempt {}): {}", attempt + 1, e);
+                    request = json::repair_request(request, &response.text, &e);
+                    attempt += 1;
+                },
+                Err(e) => return Err(LlmError::InvalidResponse(e.to_string()).into()),
+            }
+        }
+    }
+
+    /// Send many requests with at most `batch_concurrency` in flight, returning results in request order
+    ///
+    /// Each request goes through [`send`](Self::send), so caching, rate limits and fallback apply
+    /// as usual. `progress` is called as requests complete.
+    pub async fn send_batch<P>(&self, requests: Vec<LlmRequest>, task: Option<&str>, progress: P) -> Vec<Result<LlmResponse>>
+    where
+        P: FnMut(&BatchProgress),
+    {
+        batch::run_batch(requests, self.config.batch_concurrency, |request| self.send(request, task), progress).await
+    }
+
+    /// Send the same request to every ensemble provider concurrently and merge the answers
+    ///
+    /// Providers that fail are left out of the merge; fallback providers are not used, so
+    /// each answer comes from a different provider.
+    async fn send_ensemble(&self, request: LlmRequest, task: Option<&str>) -> Result<LlmResponse> {
+        let mut members = Vec::new();
+        for name in &self.config.ensemble {
+            if self.clients.contains_key(name) {
+                members.push(name.as_str());
+            } else {
+                tracing::warn!("Ensemble provider {} is not configured", name);
+            }
+        }
+
+        let results = futures_util::future::join_all(members.iter().map(|name| {
+            let mut member_request = request.clone();
+            if *name != self.default_client
+                && let Some(model) = self.default_model_for_provider(name)
+            {
+                member_request.model = model;
+            }
+            self.dispatch(member_request, name, vec![name], task)
+        })).await;
+
+        let mut responses = Vec::new();
+        let mut last_error = None;
+        for (name, result) in members.iter().zip(results) {
+            match result {
+                Ok(response) => responses.push(response),
+                Err(e) => {
+                    tracing::warn!("Ensemble provider {} failed: {}", name, e);
+                    last_error = Some(e);
+                },
+            }
+        }
+
+        if responses.is_empty() {
+            return Err(last_error.unwrap_or_else(|| anyhow!("No ensemble providers are available")));
+        }
 
+        Ok(ensemble::merge(MergeStrategy::for_task(task), responses))
+    }
+
+    /// Send a request to a provider, falling back along `chain` when it fails
+    async fn dispatch(&self, request: LlmRequest, provider: &str, chain: Vec<&str>, task: Option<&str>) -> Result<LlmResponse> {
         // Check cache if enabled and request allows caching
         if request.use_cache && self.cache.is_some() {
             if let Some(cache) = &self.cache {
                 let cache_guard = cache.lock().await;
                 if let Some(cached_response) = cache_guard.get(&request, provider) {
-                    return Ok(cached_response.with_cached(true));
+                    let cached_response = cached_response.with_cached(true);
+                    crate::debug::record_exchange(provider, &request, std::time::Instant::now(), Ok(&cached_response));
+                    return Ok(cached_response);
                 }
             }
         }
 
-        // Check if the client is available
-        if !client.is_available().await {
-            // If not, try to find an available client
-            for (name, client) in &self.clients {
-                if client.is_available().await {
-                    let start_time = std::time::Instant::now();
-                    let response = client.send(request.clone()).await?;
-                    let latency = start_time.elapsed().as_millis() as u64;
+        self.costs.check()?;
 
-                    // Add latency to response
-                    let response = response.with_latency(latency);
+        let fallback = &self.config.fallback;
+        let mut last_error = None;
+        // Whether a sensitive prompt may leave the machine, decided once per request
+        let mut approval: Option<std::result::Result<(), LlmError>> = None;
 
-                    return Ok(response);
+        for name in chain {
+            let Some(client) = self.clients.get(name) else {
+                continue;
+            };
+
+            if !client.is_available().await {
+                tracing::debug!("Skipping unavailable provider {}", name);
+                continue;
+            }
+
+            // Fallback providers use their own default model
+            let mut provider_request = request.clone();
+            if name != provider
+                && let Some(model) = self.default_model_for_provider(name)
+            {
+                provider_request.model = model;
+            }
+
+            // Hold sensitive prompts to remote providers until approved; a rejected prompt can
+            // still go to a provider on this machine
+            let provider_config = self.config.providers.iter().find(|p| p.provider_type == name);
+            if let Some(gate) = &self.approval
+                && !provider_config.is_some_and(crate::llm::providers::is_local)
+            {
+                if approval.is_none() {
+                    approval = Some(gate.wait(&provider_request, name, task).await.map_err(|e| match e.downcast::<LlmError>() {
+                        Ok(e) => e,
+                        Err(e) => LlmError::NotApproved(format!("{:#}", e)),
+                    }));
+                }
+                if let Some(Err(e)) = &approval {
+                    tracing::warn!("Not sending to {}: {}", name, e);
+                    last_error = Some(e.clone().into());
+                    continue;
                 }
             }
 
-            return Err(anyhow!("No LLM providers are available"));
+            // Keep the prompt within this provider's context window
+            let timeout = std::time::Duration::from_secs(
+                provider_config.and_then(|p| p.timeout_secs).unwrap_or(fallback.timeout_secs)
+            );
+            let max_retries = provider_config.and_then(|p| p.max_retries).unwrap_or(fallback.max_retries);
+            let window = tokens::context_window(provider_config, &provider_request.model);
+            let prompt_tokens = match tokens::fit_to_context(&mut provider_request, window) {
+                Ok(fit) => {
+                    if fit.truncated_tokens > 0 {
+                        tracing::warn!(
+                            "Truncated {} prompt tokens to fit the {}-token context window of {}",
+                            fit.truncated_tokens, window, provider_request.model
+                        );
+                    }
+                    fit.prompt_tokens
+                },
+                Err(e) => {
+                    tracing::warn!("Skipping provider {}: {}", name, e);
+                    last_error = Some(e);
+                    continue;
+                },
+            };
+
+            let mut retry = 0;
+            loop {
+                // Wait for a slot among the provider's requests in flight, held until the
+                // response arrives so a batch can't flood the provider
+                let queue = self.queues.get(name);
+                let slot = match queue {
+                    Some(queue) => Some(queue.enter().await),
+                    None => None,
+                };
+                if let Some(slot) = &slot
+                    && slot.waited >= std::time::Duration::from_secs(1)
+                {
+                    tracing::info!("Waited {:?} for one of the {} concurrent request slots", slot.waited, name);
+                }
+
+                // Queue behind other requests to this provider rather than trip its rate limit;
+                // completions are reserved at their maximum length
+                if let Some(limiter) = self.limiters.get(name) {
+                    let tokens = prompt_tokens.saturating_add(provider_request.max_tokens);
+                    let waited = limiter.acquire(u32::try_from(tokens).unwrap_or(u32::MAX)).await;
+                    if let Some(queue) = queue {
+                        queue.record_wait(waited);
+                    }
+                    if waited >= std::time::Duration::from_secs(1) {
+                        tracing::info!("Waited {:?} for the {} rate limit", waited, name);
+                    }
+                }
+
+                // Measure latency
+                let start_time = std::time::Instant::now();
+
+                let result = match Self::send_with_notice(client.as_ref(), provider_request.clone(), timeout).await {
+                    Ok(result) => result,
+                    Err(_) => Err(LlmError::Timeout(format!("{} did not respond within {:?}", name, timeout)).into()),
+                };
+                crate::debug::record_exchange(name, &provider_request, start_time, result.as_ref());
+                drop(slot);
+
+                let error = match result {
+                    Ok(response) => {
+                        // Add latency to response
+                        let latency = start_time.elapsed().as_millis() as u64;
+                        let mut response = response.with_latency(latency);
+                        self.record_tokens(&response);
+                        if let Some(cached) = response.metadata.get(crate::llm::providers::CACHED_PROMPT_TOKENS) {
+                            tracing::debug!("{} served {} prompt tokens from its prompt cache", name, cached);
+                        }
+
+                        let completion_tokens = TokenCounter::for_model(&provider_request.model).count(&response.text);
+                        let cost = self.costs.record(name, &provider_request.model, prompt_tokens, completion_tokens);
+                        response = response.with_metadata("cost_usd", serde_json::json!(cost));
+
+                        if name != provider {
+                            tracing::info!("Request served by fallback provider {}", name);
+                            response = response.with_metadata("fallback_from", serde_json::json!(provider));
+                        }
+
+                        // Cache the response if caching is enabled
+                        if request.use_cache && let Some(cache) = &self.cache {
+                            let mut cache_guard = cache.lock().await;
+                            let _ = cache_guard.put(&request, provider, response.clone());
+                        }
+
+                        return Ok(response);
+                    },
+                    Err(e) => e,
+                };
+
+                if !LlmError::is_transient(&error) {
+                    return Err(error);
+                }
+
+                if retry < max_retries {
+                    let delay = fallback.backoff(retry);
+                    retry += 1;
+                    tracing::warn!("{} request failed ({}), retry {}/{} in {:?}", name, error, retry, max_retries, delay);
+                    tokio::time::sleep(delay).await;
+                    continue;
+                }
+
+                tracing::warn!("{} request failed after {} retries: {}", name, max_retries, error);
+                last_error = Some(error);
+                break;
+            }
         }
 
-        // Measure latency
-        let start_time = std::time::Instant::now();
+        Err(last_error.unwrap_or_else(|| anyhow!("No LLM providers are available")))
+    }
 
-        // Send the request
-        let response = client.send(request.clone()).await?;
+    /// Send a request within `timeout`, warning once if it's slow so the command doesn't look hung
+    async fn send_with_notice(client: &dyn LlmClient, request: LlmRequest, timeout: std::time::Duration) -> Result<Result<LlmResponse>, tokio::time::error::Elapsed> {
+        let send = tokio::time::timeout(timeout, client.send(request));
+        tokio::pin!(send);
+
+        if timeout > SLOW_REQUEST_NOTICE {
+            tokio::select! {
+                result = &mut send => return result,
+                _ = tokio::time::sleep(SLOW_REQUEST_NOTICE) => {
+                    tracing::warn!(
+                        "Still waiting for {} after {:?}; giving up after {:?} (set timeout_secs on the provider to change this)",
+                        client.name(), SLOW_REQUEST_NOTICE, timeout
+                    );
+                },
+            }
+        }
 
-        // Calculate latency
-        let latency = start_time.elapsed().as_millis() as u64;
+        send.await
+    }
 
-        // Add latency to response
-        let response = response.with_latency(latency);
+    /// Providers to try for a request, starting with the primary provider
+    fn fallback_chain<'a>(&'a self, primary: &'a str) -> Vec<&'a str> {
+        let mut chain = vec![primary];
 
-        // Cache the response if caching is enabled
-        if request.use_cache && self.cache.is_some() {
-            if let Some(cache) = &self.cache {
-                let mut cache_guard = cache.lock().await;
-                let _ = cache_guard.put(&request, provider, response.clone());
+        let rest: Vec<&str> = if self.config.fallback.chain.is_empty() {
+            self.config.providers.iter().map(|p| p.provider_type.as_str()).collect()
+        } else {
+            self.config.fallback.chain.iter().map(String::as_str).collect()
+        };
+
+        for name in rest {
+            if !chain.contains(&name) {
+                chain.push(name);
             }
         }
 
-        Ok(response)
+        chain
+    }
+
+    /// Add a response's tokens to the usage counter
+    fn record_tokens(&self, response: &LlmResponse) {
+        if let Some(tokens) = response.tokens_used {
+            self.tokens_used.fetch_add(tokens as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Total tokens used by this router and its clones
+    pub fn tokens_used(&self) -> u64 {
+        self.tokens_used.load(Ordering::Relaxed)
+    }
+
+    /// Prompt approval configuration, if sensitive prompts are held for approval
+    pub fn approval_config(&self) -> Option<&ApprovalConfig> {
+        self.config.approval.as_ref()
+    }
+
+    /// Estimated spend in USD by this router and its clones
+    pub fn cost_usd(&self) -> f64 {
+        self.costs.command_spend()
     }
 
     /// Get the available providers
@@ -579,6 +1362,11 @@ impl LlmRouter {
         available
     }
 
+    /// Requests a batch keeps in flight at once
+    pub fn batch_concurrency(&self) -> usize {
+        self.config.batch_concurrency
+    }
+
     /// Get the default provider
     pub fn default_provider(&self) -> &str {
         &self.default_client
@@ -596,8 +1384,159 @@ impl LlmRouter {
         self.default_model_for_provider(&self.default_client)
     }
 
+    /// Provider that serves a task
+    fn task_provider(&self, task: Option<&str>) -> &str {
+        task.and_then(|task| self.config.task_providers.get(task))
+            .map(|s| s.as_str())
+            .unwrap_or(&self.default_client)
+    }
+
+    /// Model the agents use for a task
+    pub fn task_model(&self, task: Option<&str>) -> String {
+        self.default_model_for_provider(self.task_provider(task)).unwrap_or_else(|| "tinyllama".to_string())
+    }
+
+    /// Context window of the model that serves a task
+    pub fn context_window(&self, task: Option<&str>) -> usize {
+        let provider = self.task_provider(task);
+        let provider_config = self.config.providers.iter().find(|p| p.provider_type == provider);
+        tokens::context_window(provider_config, &self.task_model(task))
+    }
+
     /// Get a client by provider name
     pub fn get_client(&self, provider: &str) -> Option<&Arc<dyn LlmClient>> {
         self.clients.get(provider)
     }
 }
+
+/// LLM router that is only created when first needed
+///
+/// Long-running modes (watch, language server, daemon, bot) use this so they start immediately and
+/// only pay for provider initialization once, on the first request that needs an LLM. A router
+/// loaded from the configuration file is rebuilt when the file changes, so provider settings and
+/// rotated API keys take effect without a restart.
+#[derive(Clone, Default)]
+pub struct LazyRouter {
+    router: Arc<tokio::sync::RwLock<Option<LoadedRouter>>>,
+
+    /// Where the configuration comes from
+    source: RouterSource,
+}
+
+/// Where a lazy router's configuration comes from
+#[derive(Clone, Default)]
+enum RouterSource {
+    /// The configuration file found by `ConfigManager::new`
+    #[default]
+    DefaultFile,
+
+    /// A configuration that never changes
+    Fixed(Arc<RouterConfig>),
+}
+
+/// An initialized router and the configuration file it was loaded from
+struct LoadedRouter {
+    router: LlmRouter,
+
+    /// Path and version of the configuration file (unless the configuration is fixed)
+    file: Option<(std::path::PathBuf, Option<ConfigVersion>)>,
+}
+
+/// Modification time and size of a configuration file, `None` if it doesn't exist
+type ConfigVersion = (std::time::SystemTime, u64);
+
+/// Current version of a configuration file
+fn config_version(path: &std::path::Path) -> Option<ConfigVersion> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+impl LazyRouter {
+    /// Create an uninitialized router
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an uninitialized router with an explicit configuration
+    pub fn with_config(config: RouterConfig) -> Self {
+        Self {
+            router: Arc::default(),
+            source: RouterSource::Fixed(Arc::new(config)),
+        }
+    }
+
+    /// Get the router, loading the LLM configuration and initializing providers on first use
+    ///
+    /// If the configuration file changed since the router was created, the router is rebuilt
+    /// from the new configuration. When the new configuration can't be loaded, the current
+    /// router is kept and the error is logged.
+    pub async fn get(&self) -> Result<LlmRouter> {
+        {
+            let loaded = self.router.read().await;
+            if let Some(loaded) = loaded.as_ref() && !Self::changed(loaded) {
+                return Ok(loaded.router.clone());
+            }
+        }
+
+        let mut loaded = self.router.write().await;
+        if let Some(current) = loaded.as_ref() && !Self::changed(current) {
+            return Ok(current.router.clone());
+        }
+
+        match self.load().await {
+            Ok(new) => {
+                if let Some((path, _)) = loaded.as_ref().and_then(|current| current.file.as_ref()) {
+                    tracing::info!("Reloaded LLM configuration from {}", path.display());
+                }
+                let router = new.router.clone();
+                *loaded = Some(new);
+                Ok(router)
+            }
+            Err(e) => match loaded.as_mut() {
+                Some(current) => {
+                    // Keep serving with the old configuration until the file changes again
+                    if let Some((path, version)) = current.file.as_mut() {
+                        tracing::warn!("Keeping the previous LLM configuration; failed to reload {}: {:#}", path.display(), e);
+                        *version = config_version(path);
+                    }
+                    Ok(current.router.clone())
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    /// Whether the configuration file of a loaded router changed
+    fn changed(loaded: &LoadedRouter) -> bool {
+        loaded.file.as_ref().is_some_and(|(path, version)| config_version(path) != *version)
+    }
+
+    /// Load the configuration and initialize a router from it
+    async fn load(&self) -> Result<LoadedRouter> {
+        let (config, file) = match &self.source {
+            RouterSource::Fixed(config) => (config.as_ref().clone(), None),
+            RouterSource::DefaultFile => {
+                let path = crate::llm::ConfigManager::get_config_path()?;
+                // Take the version before reading, so a write during the load triggers another reload
+                let version = config_version(&path);
+                let manager = crate::llm::ConfigManager::with_path(&path)?;
+                (manager.get_config().clone(), Some((path, version)))
+            }
+        };
+
+        Ok(LoadedRouter {
+            router: LlmRouter::new(config).await?,
+            file,
+        })
+    }
+
+    /// Get the router only if it has already been initialized
+    pub fn get_if_initialized(&self) -> Option<LlmRouter> {
+        self.router.try_read().ok()?.as_ref().map(|loaded| loaded.router.clone())
+    }
+
+    /// Whether the router has been initialized
+    pub fn is_initialized(&self) -> bool {
+        self.router.try_read().is_ok_and(|loaded| loaded.is_some())
+    }
+}
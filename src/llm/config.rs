@@ -1,6 +1,4 @@
 use anyhow::{Result, Context};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -16,12 +14,7 @@ impl ConfigManager {
     /// Create a new configuration manager
     pub fn new() -> Result<Self> {
         let config_path = Self::get_config_path()?;
-        let config = Self::load_config(&config_path)?;
-        
-        Ok(Self {
-            config_path,
-            config,
-        })
+        Self::with_path(config_path)
     }
     
     /// Create a new configuration manager with a custom config path
@@ -29,14 +22,49 @@ impl ConfigManager {
         let config_path = path.as_ref().to_path_buf();
         let config = Self::load_config(&config_path)?;
         
-        Ok(Self {
+        let mut manager = Self {
             config_path,
             config,
-        })
+        };
+        manager.migrate_api_keys();
+        manager.load_api_keys();
+        
+        Ok(manager)
+    }
+    
+    /// Move API keys saved in plaintext by earlier versions into the OS keyring
+    fn migrate_api_keys(&self) {
+        if !crate::secrets::is_enabled() || self.config.providers.iter().all(|p| p.api_key.is_none()) {
+            return;
+        }
+        
+        let mut config = self.config.clone();
+        for provider in &mut config.providers {
+            if let Some(api_key) = provider.api_key.take()
+                && let Err(e) = crate::secrets::set(&crate::secrets::provider_account(&provider.provider_type), &api_key)
+            {
+                tracing::debug!("Keeping API keys in {}: {}", self.config_path.display(), e);
+                return;
+            }
+        }
+        
+        match self.write_config(&config) {
+            Ok(()) => tracing::info!("Moved API keys from {} to the OS keyring", self.config_path.display()),
+            Err(e) => tracing::warn!("API keys were copied to the OS keyring but {} still holds them: {}", self.config_path.display(), e),
+        }
+    }
+    
+    /// Fill in API keys held by the OS keyring
+    fn load_api_keys(&mut self) {
+        for provider in &mut self.config.providers {
+            if provider.api_key.is_none() {
+                provider.api_key = crate::secrets::get(&crate::secrets::provider_account(&provider.provider_type));
+            }
+        }
     }
     
     /// Get the default configuration path
-    fn get_config_path() -> Result<PathBuf> {
+    pub(crate) fn get_config_path() -> Result<PathBuf> {
         // Try to find the config in the following locations:
         // 1. Current directory
         // 2. User's home directory
@@ -86,8 +114,20 @@ impl ConfigManager {
     }
     
     /// Save the configuration to the given path
+    ///
+    /// API keys go to the OS keyring unless it's disabled or can't be written.
     pub fn save_config(&self) -> Result<()> {
-        let config_str = serde_json::to_string_pretty(&self.config)
+        let mut config = self.config.clone();
+        for provider in &mut config.providers {
+            provider.api_key = crate::secrets::store(&crate::secrets::provider_account(&provider.provider_type), provider.api_key.as_deref());
+        }
+        
+        self.write_config(&config)
+    }
+    
+    /// Write a configuration to the config path
+    fn write_config(&self, config: &RouterConfig) -> Result<()> {
+        let config_str = serde_json::to_string_pretty(config)
             .context("Failed to serialize config")?;
             
         fs::write(&self.config_path, config_str)
@@ -141,6 +181,7 @@ impl ConfigManager {
         }
         
         self.config.providers.retain(|p| p.provider_type != provider_type);
+        crate::secrets::delete(&crate::secrets::provider_account(provider_type))?;
         
         // Remove any task mappings to this provider
         self.config.task_providers.retain(|_, v| v != provider_type);
@@ -159,6 +200,19 @@ impl ConfigManager {
         Ok(())
     }
     
+    /// Set the fallback configuration
+    pub fn set_fallback(&mut self, fallback: crate::llm::client::FallbackConfig) -> Result<()> {
+        // Check that every provider in the chain exists
+        for provider in &fallback.chain {
+            if !self.config.providers.iter().any(|p| p.provider_type == *provider) {
+                return Err(anyhow::anyhow!("Provider not found: {}", provider));
+            }
+        }
+
+        self.config.fallback = fallback;
+        Ok(())
+    }
+
     /// Remove a task provider mapping
     pub fn remove_task_provider(&mut self, task: &str) -> Result<()> {
         if !self.config.task_providers.contains_key(task) {
@@ -0,0 +1,147 @@
+// Multi-turn conversations: message history, system prompt and token-aware truncation
+
+use serde::{Deserialize, Serialize};
+
+use crate::llm::client::{LlmRequest, MessageRole};
+use crate::llm::tokens::TokenCounter;
+
+/// A message in a conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationTurn {
+    /// Who wrote the message, as shown in the transcript
+    pub author: String,
+
+    /// Whether a person or the model wrote the message
+    pub role: MessageRole,
+
+    /// Message text
+    pub text: String,
+}
+
+/// History of a conversation with the model, rendered into a request for each reply
+///
+/// The whole history is kept (up to `max_turns`), but only the most recent turns that fit in
+/// the model's context window are sent.
+#[derive(Debug, Clone)]
+pub struct Conversation {
+    /// System prompt sent with every request
+    system_prompt: String,
+
+    /// Messages, oldest first
+    turns: Vec<ConversationTurn>,
+
+    /// Messages kept; older ones are forgotten
+    max_turns: Option<usize>,
+}
+
+impl Conversation {
+    /// Start a conversation with a system prompt
+    pub fn new(system_prompt: String) -> Self {
+        Self {
+            system_prompt,
+            turns: Vec::new(),
+            max_turns: None,
+        }
+    }
+
+    /// Forget all but the last `max_turns` messages
+    pub fn with_max_turns(mut self, max_turns: usize) -> Self {
+        self.max_turns = Some(max_turns);
+        self.forget();
+        self
+    }
+
+    /// System prompt sent with every request
+    pub fn system_prompt(&self) -> &str {
+        &self.system_prompt
+    }
+
+    /// Messages, oldest first
+    pub fn turns(&self) -> &[ConversationTurn] {
+        &self.turns
+    }
+
+    /// Number of messages
+    pub fn len(&self) -> usize {
+        self.turns.len()
+    }
+
+    /// Whether nothing has been said yet
+    pub fn is_empty(&self) -> bool {
+        self.turns.is_empty()
+    }
+
+    /// Add a message from a person
+    pub fn push_user(&mut self, author: &str, text: String) {
+        self.push(author, MessageRole::User, text);
+    }
+
+    /// Add a reply from the model
+    pub fn push_assistant(&mut self, author: &str, text: String) {
+        self.push(author, MessageRole::Assistant, text);
+    }
+
+    fn push(&mut self, author: &str, role: MessageRole, text: String) {
+        self.turns.push(ConversationTurn { author: author.to_string(), role, text });
+        self.forget();
+    }
+
+    /// Drop the oldest messages beyond `max_turns`
+    fn forget(&mut self) {
+        if let Some(max_turns) = self.max_turns
+            && self.turns.len() > max_turns
+        {
+            self.turns.drain(..self.turns.len() - max_turns);
+        }
+    }
+
+    /// Render messages as `Author: text` lines
+    pub fn render(turns: &[ConversationTurn]) -> String {
+        turns.iter()
+            .map(|turn| format!("{}: {}", turn.author, turn.text))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// The whole conversation as `Author: text` lines
+    pub fn transcript(&self) -> String {
+        Self::render(&self.turns)
+    }
+
+    /// A request for the next reply, with an optional instruction after the history
+    ///
+    /// The oldest messages that don't fit in `context_window`, next to the system prompt, the
+    /// instruction and the reply, are left out, and a note says how many were.
+    pub fn request(&self, model: String, instruction: Option<&str>, context_window: usize) -> LlmRequest {
+        let counter = TokenCounter::for_model(&model);
+        let instruction = instruction.map(|instruction| format!("\n\nInstruction: {}", instruction)).unwrap_or_default();
+        let mut request = LlmRequest::new(instruction.clone(), model).with_system_message(self.system_prompt.clone());
+        let mut budget = context_window
+            .saturating_sub(request.max_tokens.min(context_window / 2))
+            .saturating_sub(counter.count_messages(&request.messages));
+
+        // Keep the newest messages that fit, and always the latest one
+        let mut start = self.turns.len();
+        while start > 0 {
+            let tokens = counter.count(&self.turns[start - 1].author) + counter.count(&self.turns[start - 1].text) + 2;
+            if tokens > budget && start < self.turns.len() {
+                break;
+            }
+            budget = budget.saturating_sub(tokens);
+            start -= 1;
+        }
+
+        let mut prompt = String::new();
+        if start > 0 {
+            tracing::debug!("Leaving the {} oldest conversation messages out of the prompt to fit the context window", start);
+            prompt.push_str(&format!("[{} earlier messages omitted]\n", start));
+        }
+        prompt.push_str(&Self::render(&self.turns[start..]));
+        prompt.push_str(&instruction);
+
+        if let Some(message) = request.messages.last_mut() {
+            message.content = prompt;
+        }
+        request
+    }
+}
@@ -0,0 +1,556 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::audit::format_timestamp;
+use crate::llm::client::LlmError;
+
+/// Prices in USD per million tokens, by model name prefix; longer prefixes must come first
+const PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("gpt-4-turbo", 10.00, 30.00),
+    ("gpt-4-32k", 60.00, 120.00),
+    ("gpt-4", 30.00, 60.00),
+    ("gpt-3.5-turbo", 0.50, 1.50),
+    ("o1-mini", 1.10, 4.40),
+    ("o1", 15.00, 60.00),
+    ("o3-mini", 1.10, 4.40),
+    ("o3", 2.00, 8.00),
+    ("o4-mini", 1.10, 4.40),
+    ("claude-3-opus", 15.00, 75.00),
+    ("claude-3-5-sonnet", 3.00, 15.00),
+    ("claude-3-7-sonnet", 3.00, 15.00),
+    ("claude-3-sonnet", 3.00, 15.00),
+    ("claude-3-5-haiku", 0.80, 4.00),
+    ("claude-3-haiku", 0.25, 1.25),
+];
+
+/// Providers that run models locally, at no cost per token
+const FREE_PROVIDERS: &[&str] = &["ollama", "local", "mock"];
+
+/// Price of a model in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    /// Price of a million prompt tokens
+    pub input: f64,
+
+    /// Price of a million completion tokens
+    pub output: f64,
+}
+
+impl ModelPrice {
+    /// Cost of a request in USD
+    pub fn cost(&self, input_tokens: usize, output_tokens: usize) -> f64 {
+        (input_tokens as f64 * self.input + output_tokens as f64 * self.output) / 1_000_000.0
+    }
+}
+
+/// LLM spending limits and price overrides
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetConfig {
+    /// Maximum spend in USD for one command
+    #[serde(default)]
+    pub per_command_usd: Option<f64>,
+
+    /// Maximum spend in USD per day (UTC)
+    #[serde(default)]
+    pub per_day_usd: Option<f64>,
+
+    /// Prices by model name prefix, overriding the built-in table
+    #[serde(default)]
+    pub prices: HashMap<String, ModelPrice>,
+}
+
+impl BudgetConfig {
+    /// Price of a model, or `None` if it is unknown
+    pub fn price(&self, provider: &str, model: &str) -> Option<ModelPrice> {
+        if FREE_PROVIDERS.contains(&provider) {
+            return Some(ModelPrice { input: 0.0, output: 0.0 });
+        }
+
+        let model = model.to_lowercase();
+
+        // The longest matching override wins
+        let configured = self.prices.iter()
+            .filter(|(prefix, _)| model.starts_with(&prefix.to_lowercase()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, price)| *price);
+
+        configured.or_else(|| {
+            PRICES.iter()
+                .find(|(prefix, _, _)| model.starts_with(prefix))
+                .map(|(_, input, output)| ModelPrice { input: *input, output: *output })
+        })
+    }
+}
+
+/// Command whose LLM usage is recorded, set once at startup
+static COMMAND: OnceLock<String> = OnceLock::new();
+
+/// Attribute this process's LLM usage to a command, e.g. `run risk`
+pub fn set_command(command: &str) {
+    let _ = COMMAND.set(command.to_string());
+}
+
+/// Command this process's LLM usage is attributed to, empty when not set
+pub fn current_command() -> &'static str {
+    COMMAND.get().map(String::as_str).unwrap_or_default()
+}
+
+/// Pull request this process's LLM usage is attributed to, as (`owner/repo`, number)
+static PULL_REQUEST: OnceLock<(String, String)> = OnceLock::new();
+
+/// Attribute this process's LLM usage to a pull request, once the command knows which one
+pub fn set_pull_request(owner: &str, repo: &str, number: &str) {
+    let _ = PULL_REQUEST.set((format!("{}/{}", owner, repo), number.to_string()));
+}
+
+/// Pull request this process's LLM usage is attributed to, as `owner/repo#number`; empty when none
+pub fn current_pull_request() -> String {
+    PULL_REQUEST.get().map(|(repo, number)| format!("{}#{}", repo, number)).unwrap_or_default()
+}
+
+/// Repository this process's LLM usage is attributed to: the pull request's, or the `origin`
+/// remote of the working directory as `owner/repo`; empty outside a repository
+pub fn current_repo() -> String {
+    static ORIGIN: OnceLock<String> = OnceLock::new();
+
+    if let Some((repo, _)) = PULL_REQUEST.get() {
+        return repo.clone();
+    }
+    ORIGIN.get_or_init(|| {
+        std::process::Command::new("git")
+            .args(["remote", "get-url", "origin"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| repo_from_remote(&String::from_utf8_lossy(&output.stdout)))
+            .unwrap_or_default()
+    }).clone()
+}
+
+/// `owner/repo` of a git remote URL (`https://host/owner/repo.git` or `git@host:owner/repo.git`)
+pub fn repo_from_remote(url: &str) -> Option<String> {
+    let path = url.trim().trim_end_matches('/').trim_end_matches(".git");
+    let mut parts = path.rsplit(['/', ':']);
+    let repo = parts.next().filter(|part| !part.is_empty())?;
+    let owner = parts.next().filter(|part| !part.is_empty() && !part.contains('@'))?;
+    Some(format!("{}/{}", owner, repo))
+}
+
+/// Usage of one model by one command on one day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageEntry {
+    /// Day (UTC) as `YYYY-MM-DD`
+    pub day: String,
+
+    /// Command that made the requests, e.g. `run risk`; empty in entries from older versions
+    #[serde(default)]
+    pub command: String,
+
+    /// Repository worked on as `owner/repo`; empty outside a repository and in older entries
+    #[serde(default)]
+    pub repo: String,
+
+    /// Pull request worked on as `owner/repo#number`; empty when the command had none
+    #[serde(default)]
+    pub pr: String,
+
+    /// Who ran the command (see `audit::current_actor`); empty in older entries
+    #[serde(default)]
+    pub user: String,
+
+    /// Provider name
+    pub provider: String,
+
+    /// Model name
+    pub model: String,
+
+    /// Number of requests
+    pub requests: u64,
+
+    /// Prompt tokens
+    pub input_tokens: u64,
+
+    /// Completion tokens
+    pub output_tokens: u64,
+
+    /// Estimated cost in USD
+    pub cost_usd: f64,
+}
+
+/// Cumulative LLM usage, stored as JSON in `~/.qitops/usage.json`
+pub struct UsageLedger {
+    path: PathBuf,
+}
+
+impl UsageLedger {
+    /// Default usage ledger
+    pub fn default_path() -> Result<PathBuf> {
+        dirs::home_dir()
+            .map(|home| home.join(".qitops").join("usage.json"))
+            .ok_or_else(|| anyhow!("Could not determine home directory"))
+    }
+
+    /// Open the default usage ledger
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::open(Self::default_path()?))
+    }
+
+    /// Open a usage ledger at a path
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Ledger file path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read all entries, oldest day first
+    pub fn read(&self) -> Result<Vec<UsageEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| anyhow!("Failed to read usage ledger {}: {}", self.path.display(), e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Failed to parse usage ledger {}: {}", self.path.display(), e))
+    }
+
+    /// Add a request to the entry for its day, command, repository, pull request, user and model
+    pub fn record(&self, provider: &str, model: &str, input_tokens: usize, output_tokens: usize, cost_usd: f64) -> Result<()> {
+        let day = today();
+        let command = current_command();
+        let repo = current_repo();
+        let pr = current_pull_request();
+        let user = crate::audit::current_actor();
+        let mut entries = self.read()?;
+
+        let same = |e: &UsageEntry| e.day == day && e.command == command && e.repo == repo && e.pr == pr
+            && e.user == user && e.provider == provider && e.model == model;
+        match entries.iter_mut().find(|e| same(e)) {
+            Some(entry) => {
+                entry.requests += 1;
+                entry.input_tokens += input_tokens as u64;
+                entry.output_tokens += output_tokens as u64;
+                entry.cost_usd += cost_usd;
+            },
+            None => entries.push(UsageEntry {
+                day,
+                command: command.to_string(),
+                repo,
+                pr,
+                user,
+                provider: provider.to_string(),
+                model: model.to_string(),
+                requests: 1,
+                input_tokens: input_tokens as u64,
+                output_tokens: output_tokens as u64,
+                cost_usd,
+            }),
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&entries)?)
+            .map_err(|e| anyhow!("Failed to write usage ledger {}: {}", self.path.display(), e))
+    }
+
+    /// Spend in USD on a day
+    pub fn spent_on(&self, day: &str) -> Result<f64> {
+        Ok(self.read()?.iter().filter(|e| e.day == day).fold(0.0, |sum, e| sum + e.cost_usd))
+    }
+
+    /// Entries from a day (`YYYY-MM-DD`) onwards
+    pub fn since(&self, day: &str) -> Result<Vec<UsageEntry>> {
+        Ok(self.read()?.into_iter().filter(|e| e.day.as_str() >= day).collect())
+    }
+}
+
+/// Usage added up over several entries
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UsageTotals {
+    /// Number of requests
+    pub requests: u64,
+
+    /// Prompt tokens
+    pub input_tokens: u64,
+
+    /// Completion tokens
+    pub output_tokens: u64,
+
+    /// Estimated cost in USD
+    pub cost_usd: f64,
+}
+
+/// Add up entries grouped by a key, e.g. command or provider; most requests first
+pub fn totals_by(entries: &[UsageEntry], key: impl Fn(&UsageEntry) -> String) -> Vec<(String, UsageTotals)> {
+    let mut groups: Vec<(String, UsageTotals)> = Vec::new();
+    for entry in entries {
+        let name = key(entry);
+        let index = match groups.iter().position(|(group, _)| *group == name) {
+            Some(index) => index,
+            None => {
+                groups.push((name, UsageTotals::default()));
+                groups.len() - 1
+            },
+        };
+
+        let totals = &mut groups[index].1;
+        totals.requests += entry.requests;
+        totals.input_tokens += entry.input_tokens;
+        totals.output_tokens += entry.output_tokens;
+        totals.cost_usd += entry.cost_usd;
+    }
+
+    groups.sort_by(|a, b| b.1.requests.cmp(&a.1.requests).then_with(|| a.0.cmp(&b.0)));
+    groups
+}
+
+/// What usage is rolled up by in a cost report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostDimension {
+    Command,
+    Repo,
+    Pr,
+    User,
+    Provider,
+    Day,
+}
+
+impl FromStr for CostDimension {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "command" => Ok(CostDimension::Command),
+            "repo" | "repository" => Ok(CostDimension::Repo),
+            "pr" | "pull-request" => Ok(CostDimension::Pr),
+            "user" => Ok(CostDimension::User),
+            "provider" | "model" => Ok(CostDimension::Provider),
+            "day" => Ok(CostDimension::Day),
+            _ => Err(anyhow!("Unknown cost dimension: {} (expected command, repo, pr, user, provider or day)", s)),
+        }
+    }
+}
+
+impl CostDimension {
+    /// Column heading in reports
+    pub fn heading(&self) -> &'static str {
+        match self {
+            CostDimension::Command => "Command",
+            CostDimension::Repo => "Repository",
+            CostDimension::Pr => "Pull request",
+            CostDimension::User => "User",
+            CostDimension::Provider => "Provider",
+            CostDimension::Day => "Day",
+        }
+    }
+
+    /// Group of an entry; `(unknown)` where older entries have no value
+    pub fn key(&self, entry: &UsageEntry) -> String {
+        let value = match self {
+            CostDimension::Command => entry.command.clone(),
+            CostDimension::Repo => entry.repo.clone(),
+            CostDimension::Pr => entry.pr.clone(),
+            CostDimension::User => entry.user.clone(),
+            CostDimension::Provider => format!("{}/{}", entry.provider, entry.model),
+            CostDimension::Day => entry.day.clone(),
+        };
+        if value.is_empty() {
+            match self {
+                CostDimension::Pr => "(no pull request)".to_string(),
+                _ => "(unknown)".to_string(),
+            }
+        } else {
+            value
+        }
+    }
+}
+
+/// Roll entries up by one or more dimensions, most expensive first
+///
+/// Groups are keyed by the dimensions' values in order, e.g. `["run risk", "acme/shop"]`.
+pub fn roll_up(entries: &[UsageEntry], by: &[CostDimension]) -> Vec<(Vec<String>, UsageTotals)> {
+    const SEPARATOR: char = '\u{1f}';
+
+    let mut groups: Vec<(Vec<String>, UsageTotals)> = totals_by(entries, |entry| {
+        by.iter().map(|dimension| dimension.key(entry)).collect::<Vec<_>>().join(&SEPARATOR.to_string())
+    })
+        .into_iter()
+        .map(|(key, totals)| (key.split(SEPARATOR).map(str::to_string).collect(), totals))
+        .collect();
+
+    groups.sort_by(|a, b| b.1.cost_usd.total_cmp(&a.1.cost_usd)
+        .then_with(|| b.1.requests.cmp(&a.1.requests))
+        .then_with(|| a.0.cmp(&b.0)));
+    groups
+}
+
+/// Write a roll-up as CSV, with a column per dimension followed by the totals
+pub fn roll_up_csv(groups: &[(Vec<String>, UsageTotals)], by: &[CostDimension]) -> String {
+    let mut csv = by.iter().map(|dimension| dimension.heading().to_lowercase().replace(' ', "_")).collect::<Vec<_>>().join(",");
+    csv.push_str(",requests,input_tokens,output_tokens,cost_usd\n");
+
+    for (keys, totals) in groups {
+        for key in keys {
+            csv.push_str(&csv_field(key));
+            csv.push(',');
+        }
+        csv.push_str(&format!("{},{},{},{:.6}\n", totals.requests, totals.input_tokens, totals.output_tokens, totals.cost_usd));
+    }
+    csv
+}
+
+/// Quote a CSV field if it holds a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// First day (`YYYY-MM-DD`) of a period given as days or weeks back (`7d`, `2w`) or a date
+///
+/// `1d` is today, `7d` today and the six days before it.
+pub fn since_day(since: &str) -> Result<String> {
+    let since = since.trim();
+    if since.len() == 10 && since.chars().enumerate().all(|(i, c)| if i == 4 || i == 7 { c == '-' } else { c.is_ascii_digit() }) {
+        return Ok(since.to_string());
+    }
+
+    let invalid = || anyhow!("Invalid period '{}': use days or weeks (e.g. 7d, 2w) or a date (YYYY-MM-DD)", since);
+    let (number, unit) = since.split_at(since.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?);
+    let days: u64 = match (number.parse::<u64>().map_err(|_| invalid())?, unit) {
+        (n, "d") => n,
+        (n, "w") => n * 7,
+        _ => return Err(invalid()),
+    };
+    if days == 0 {
+        return Err(invalid());
+    }
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+    Ok(format_timestamp(now.saturating_sub((days - 1) * 86_400))[..10].to_string())
+}
+
+/// Current day (UTC) as `YYYY-MM-DD`
+pub fn today() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    format_timestamp(now)[..10].to_string()
+}
+
+/// LLM usage of this process across all routers
+static PROCESS_USAGE: Mutex<UsageTotals> = Mutex::new(UsageTotals { requests: 0, input_tokens: 0, output_tokens: 0, cost_usd: 0.0 });
+
+/// LLM usage of this process so far, e.g. for the summary at the end of a run
+pub fn process_usage() -> UsageTotals {
+    PROCESS_USAGE.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Tracks the cost of a command's LLM requests and enforces its budget
+pub struct CostTracker {
+    budget: BudgetConfig,
+    ledger: Option<UsageLedger>,
+    command_spend: Mutex<f64>,
+}
+
+impl CostTracker {
+    /// Create a tracker recording usage in a ledger
+    pub fn new(budget: BudgetConfig, ledger: Option<UsageLedger>) -> Self {
+        Self {
+            budget,
+            ledger,
+            command_spend: Mutex::new(0.0),
+        }
+    }
+
+    /// Tracker for the budget in the QitOps configuration and the default ledger
+    pub fn load() -> Self {
+        let budget = crate::config::QitOpsConfigManager::new()
+            .map(|manager| manager.get_config().budget.clone())
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to load LLM budget, spending is not limited: {}", e);
+                BudgetConfig::default()
+            });
+        let ledger = UsageLedger::open_default()
+            .inspect_err(|e| tracing::warn!("LLM usage will not be recorded: {}", e))
+            .ok();
+
+        Self::new(budget, ledger)
+    }
+
+    /// Spend in USD by this command so far
+    pub fn command_spend(&self) -> f64 {
+        *self.command_spend.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Fail with [`LlmError::BudgetExceeded`] if a budget has been used up
+    pub fn check(&self) -> Result<()> {
+        if let Some(limit) = self.budget.per_command_usd {
+            let spent = self.command_spend();
+            if spent >= limit {
+                return Err(LlmError::BudgetExceeded(format!(
+                    "this command has spent ${:.4} of its ${:.2} budget (budget.per_command_usd)",
+                    spent, limit
+                )).into());
+            }
+        }
+
+        if let (Some(limit), Some(ledger)) = (self.budget.per_day_usd, &self.ledger) {
+            let spent = ledger.spent_on(&today())?;
+            if spent >= limit {
+                return Err(LlmError::BudgetExceeded(format!(
+                    "${:.4} has been spent today of the ${:.2} daily budget (budget.per_day_usd); see `qitops llm usage`",
+                    spent, limit
+                )).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Record a request, returning its estimated cost in USD
+    pub fn record(&self, provider: &str, model: &str, input_tokens: usize, output_tokens: usize) -> f64 {
+        let cost = match self.budget.price(provider, model) {
+            Some(price) => price.cost(input_tokens, output_tokens),
+            None => {
+                tracing::debug!("No price known for {} model {}, counting it as free", provider, model);
+                0.0
+            },
+        };
+
+        *self.command_spend.lock().unwrap_or_else(|e| e.into_inner()) += cost;
+        {
+            let mut usage = PROCESS_USAGE.lock().unwrap_or_else(|e| e.into_inner());
+            usage.requests += 1;
+            usage.input_tokens += input_tokens as u64;
+            usage.output_tokens += output_tokens as u64;
+            usage.cost_usd += cost;
+        }
+
+        if let Some(ledger) = &self.ledger
+            && let Err(e) = ledger.record(provider, model, input_tokens, output_tokens, cost)
+        {
+            tracing::warn!("Failed to record LLM usage: {}", e);
+        }
+
+        cost
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::llm::LlmResponse;
+
+/// Risk levels in increasing order of severity
+const RISK_LEVELS: [&str; 4] = ["Low", "Medium", "High", "Critical"];
+
+/// How the answers of an ensemble are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Majority vote on the overall risk level, ties going to the higher level
+    RiskVote,
+
+    /// Union of the test cases, deduplicated by title
+    Union,
+
+    /// The answer that agrees most with the others
+    Rank,
+}
+
+impl MergeStrategy {
+    /// Strategy for a task
+    pub fn for_task(task: Option<&str>) -> Self {
+        match task {
+            Some("risk") => MergeStrategy::RiskVote,
+            Some("test-gen") => MergeStrategy::Union,
+            _ => MergeStrategy::Rank,
+        }
+    }
+}
+
+/// Merge the answers of an ensemble, given in ensemble order
+///
+/// Falls back to ranking when the answers can't be merged with the strategy, e.g. when no
+/// answer states a risk level or the test cases aren't split into markdown sections.
+pub fn merge(strategy: MergeStrategy, mut responses: Vec<LlmResponse>) -> LlmResponse {
+    let providers: Vec<String> = responses.iter().map(|r| r.provider.clone()).collect();
+    if responses.len() == 1 {
+        return responses.remove(0);
+    }
+
+    let merged = match strategy {
+        MergeStrategy::RiskVote => vote_risk(&responses),
+        MergeStrategy::Union => union_sections(&responses),
+        MergeStrategy::Rank => None,
+    };
+    let merged = merged.unwrap_or_else(|| {
+        let best = rank(&responses);
+        responses.swap_remove(best)
+    });
+
+    merged.with_metadata("ensemble", serde_json::json!(providers))
+}
+
+/// Overall risk level stated in an answer, as an index into `RISK_LEVELS`
+pub fn risk_level(text: &str) -> Option<usize> {
+    let lower = text.to_lowercase();
+
+    ["overall risk level", "overall risk", "overall_risk", "risk level"].iter().find_map(|anchor| {
+        let start = lower.find(anchor)? + anchor.len();
+        let window: String = lower[start..].chars().take(60).collect();
+        RISK_LEVELS.iter().enumerate()
+            .filter_map(|(index, level)| window.find(&level.to_lowercase()).map(|position| (position, index)))
+            .min()
+            .map(|(_, index)| index)
+    })
+}
+
+/// Pick the answer with the most common risk level and note how each provider voted
+fn vote_risk(responses: &[LlmResponse]) -> Option<LlmResponse> {
+    let levels: Vec<Option<usize>> = responses.iter().map(|r| risk_level(&r.text)).collect();
+
+    let mut counts = HashMap::new();
+    for level in levels.iter().flatten() {
+        *counts.entry(*level).or_insert(0) += 1;
+    }
+    let (winner, _) = counts.into_iter().max_by_key(|(level, count)| (*count, *level))?;
+
+    let chosen = levels.iter().position(|level| *level == Some(winner))?;
+    let votes: Vec<String> = responses.iter().zip(&levels)
+        .map(|(response, level)| format!("{}: {}", response.provider, level.map_or("unclear", |l| RISK_LEVELS[l])))
+        .collect();
+
+    let mut response = responses[chosen].clone();
+    response.text = format!(
+        "{}\n\nEnsemble risk level: {} ({})",
+        response.text.trim_end(), RISK_LEVELS[winner], votes.join(", ")
+    );
+    Some(response.with_metadata("risk_votes", serde_json::json!(votes)))
+}
+
+/// Add the test cases of the other answers that the first answer is missing
+fn union_sections(responses: &[LlmResponse]) -> Option<LlmResponse> {
+    let (preamble, base) = sections(&responses[0].text)?;
+
+    let mut seen: HashSet<String> = base.iter().map(|(key, _)| key.clone()).collect();
+    let mut text = preamble;
+    for (_, section) in &base {
+        text.push_str(section);
+    }
+
+    let mut added = 0;
+    for response in &responses[1..] {
+        let Some((_, other)) = sections(&response.text) else {
+            continue;
+        };
+        for (key, section) in other {
+            if seen.insert(key) {
+                if !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                text.push_str(&section);
+                added += 1;
+            }
+        }
+    }
+
+    let mut response = responses[0].clone();
+    response.text = text;
+    Some(response.with_metadata("ensemble_added", serde_json::json!(added)))
+}
+
+/// Test cases of a markdown answer as (normalized key, heading) pairs, empty with fewer than two
+pub fn test_cases(text: &str) -> Vec<(String, String)> {
+    sections(text)
+        .map(|(_, found)| found.into_iter()
+            .map(|(key, section)| {
+                let heading = section.lines().next().unwrap_or_default().trim_start_matches('#').trim().to_string();
+                (key, heading)
+            })
+            .collect())
+        .unwrap_or_default()
+}
+
+/// Split markdown into the text before the first test case and the test cases, keyed by title
+///
+/// Test cases are the sections under the most frequent heading level. Returns `None` when
+/// there are fewer than two of them.
+fn sections(text: &str) -> Option<(String, Vec<(String, String)>)> {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+
+    // Heading level of each line, skipping code blocks
+    let mut in_fence = false;
+    let levels: Vec<Option<usize>> = lines.iter().map(|line| {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            return None;
+        }
+        if in_fence {
+            return None;
+        }
+        let level = line.chars().take_while(|c| *c == '#').count();
+        (level > 0 && line[level..].starts_with(' ')).then_some(level)
+    }).collect();
+
+    let mut counts: HashMap<usize, usize> = HashMap::new();
+    for level in levels.iter().flatten() {
+        *counts.entry(*level).or_insert(0) += 1;
+    }
+    let (level, count) = counts.into_iter().max_by_key(|(level, count)| (*count, std::cmp::Reverse(*level)))?;
+    if count < 2 {
+        return None;
+    }
+
+    let mut preamble = String::new();
+    let mut found: Vec<(String, String)> = Vec::new();
+    for (line, line_level) in lines.iter().zip(&levels) {
+        if *line_level == Some(level) {
+            found.push((section_key(&line[level..]), line.to_string()));
+        } else if let Some((_, section)) = found.last_mut() {
+            section.push_str(line);
+        } else {
+            preamble.push_str(line);
+        }
+    }
+
+    Some((preamble, found))
+}
+
+/// Normalized test case title, without numbering like "Test Case 3:"
+fn section_key(heading: &str) -> String {
+    let title = match heading.split_once(':') {
+        Some((label, rest)) if label.chars().any(|c| c.is_ascii_digit()) => rest,
+        _ => heading,
+    };
+
+    let mut words: Vec<String> = title.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    // "Test Case 3 - Title" and "3. Title"
+    let numeric = |word: &String| word.chars().all(|c| c.is_ascii_digit());
+    if words.len() > 3 && words[0] == "test" && words[1] == "case" && numeric(&words[2]) {
+        words.drain(..3);
+    }
+    while words.len() > 1 && numeric(&words[0]) {
+        words.remove(0);
+    }
+
+    words.join(" ")
+}
+
+/// Index of the answer sharing the most words with the others, preferring earlier answers
+fn rank(responses: &[LlmResponse]) -> usize {
+    let words: Vec<HashSet<String>> = responses.iter()
+        .map(|r| r.text.split_whitespace().map(str::to_lowercase).collect())
+        .collect();
+
+    let similarity = |a: &HashSet<String>, b: &HashSet<String>| {
+        let union = a.union(b).count();
+        if union == 0 { 0.0 } else { a.intersection(b).count() as f64 / union as f64 }
+    };
+
+    let scores: Vec<f64> = words.iter().enumerate()
+        .map(|(i, a)| words.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, b)| similarity(a, b)).sum())
+        .collect();
+
+    let mut best = 0;
+    for (index, score) in scores.iter().enumerate() {
+        if *score > scores[best] {
+            best = index;
+        }
+    }
+    best
+}
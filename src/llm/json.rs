@@ -0,0 +1,60 @@
+// Structured JSON output
+
+use anyhow::{Result, anyhow};
+use serde::de::DeserializeOwned;
+
+use crate::llm::client::{ChatMessage, LlmRequest, MessageRole};
+use crate::llm::postprocess::fenced_block;
+
+/// How many times an invalid JSON answer is sent back to the model for correction
+pub const JSON_RETRIES: usize = 2;
+
+/// The JSON document in a response, without surrounding prose or a Markdown code fence
+pub fn extract_json(text: &str) -> &str {
+    let text = text.trim();
+
+    // Prefer a fenced block when the model wrapped its answer
+    if let Some(block) = fenced_block(text) {
+        return block.strip_prefix("json").unwrap_or(block).trim();
+    }
+
+    let start = text.find(['{', '[']);
+    let end = text.rfind(['}', ']']);
+    match (start, end) {
+        (Some(start), Some(end)) if end >= start => &text[start..=end],
+        _ => text,
+    }
+}
+
+/// Parse a response into `T`, then check it with `validate`
+///
+/// The error message is what gets shown to the model when asking it to correct its answer.
+pub fn parse_json<T, F>(text: &str, validate: F) -> Result<T>
+where
+    T: DeserializeOwned,
+    F: Fn(&T) -> Result<()>,
+{
+    let value = serde_json::from_str(extract_json(text))
+        .map_err(|e| anyhow!("The response is not valid JSON for the expected schema: {}", e))?;
+    validate(&value)?;
+    Ok(value)
+}
+
+/// The request asking the model to correct an invalid answer
+pub fn repair_request(mut request: LlmRequest, answer: &str, error: &anyhow::Error) -> LlmRequest {
+    request.messages.push(ChatMessage {
+        role: MessageRole::Assistant,
+        content: answer.to_string(),
+    });
+    request.messages.push(ChatMessage {
+        role: MessageRole::User,
+        content: format!(
+            "{}\n\nRespond again with only the corrected JSON document, no explanation or code fence.",
+            error
+        ),
+    });
+
+    // A cached answer would repeat the same mistake
+    request.use_cache = false;
+    request
+}
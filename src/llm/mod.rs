@@ -1,10 +1,25 @@
 // LLM integration
+pub mod approval;
+pub mod batch;
 pub mod client;
 pub mod config;
+pub mod conversation;
 pub mod cache;
+pub mod cost;
+pub mod benchmark;
+pub mod ensemble;
+pub mod json;
+pub mod pool;
+pub mod postprocess;
+pub mod probe;
 pub mod providers;
+pub mod rate_limit;
+pub mod summarize;
+pub mod tokens;
 
 // Re-export commonly used types
-pub use client::{LlmClient, LlmRequest, LlmResponse, LlmRouter, RouterConfig, ProviderConfig, CacheConfig};
+pub use client::{LlmClient, LlmRequest, LlmResponse, LlmRouter, LazyRouter, RouterConfig, ProviderConfig, ProviderAuth};
 pub use config::ConfigManager;
-pub use providers::{OpenAiClient, AnthropicClient, OllamaClient};
+pub use conversation::Conversation;
+pub use tokens::{TokenCounter, SOURCES_MARKER};
+pub use cost::{BudgetConfig, UsageLedger};
@@ -0,0 +1,212 @@
+use anyhow::{Context, Result};
+use reqwest::Client as HttpClient;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::llm::client::ProviderConfig;
+
+/// Requests in flight at once to an Ollama server when `max_concurrent_requests` isn't set
+///
+/// Ollama queues requests beyond its own parallelism in memory, and a large batch can exhaust
+/// it; a few at a time keeps the server busy without crashing it.
+pub const DEFAULT_OLLAMA_CONCURRENCY: u32 = 4;
+
+/// How long an idle pooled connection is kept open
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Most idle connections kept per host when the provider has no concurrency limit
+const DEFAULT_POOL_MAX_IDLE: usize = 16;
+
+/// HTTP clients by the settings they were built with, so routers share connection pools
+static CLIENTS: LazyLock<Mutex<HashMap<String, HttpClient>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Request queues by provider endpoint, shared by every router in the process
+static QUEUES: LazyLock<Mutex<HashMap<String, Arc<ProviderQueue>>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Concurrency limit of a provider: its `max_concurrent_requests`, or the Ollama default
+///
+/// `0` means no limit.
+pub fn max_concurrent(config: &ProviderConfig) -> Option<usize> {
+    let limit = match config.max_concurrent_requests {
+        Some(limit) => limit,
+        None if config.provider_type == "ollama" => DEFAULT_OLLAMA_CONCURRENCY,
+        None => 0,
+    };
+    (limit > 0).then_some(limit as usize)
+}
+
+/// The HTTP client for a provider's settings, building it on first use
+///
+/// reqwest clients pool their connections, so providers with the same proxy, timeouts and
+/// headers reuse one client, and routers rebuilt after a configuration change (or one per bot
+/// tenant) keep their warm connections instead of opening new ones.
+pub fn shared_client(config: &ProviderConfig, build: impl FnOnce() -> Result<reqwest::ClientBuilder>) -> Result<HttpClient> {
+    let key = serde_json::json!([
+        config.proxy, config.timeout_secs, config.connect_timeout_secs, config.headers, config.auth, max_concurrent(config),
+    ]).to_string();
+
+    let mut clients = CLIENTS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(client) = clients.get(&key) {
+        return Ok(client.clone());
+    }
+
+    let client = build()?
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .pool_max_idle_per_host(max_concurrent(config).unwrap_or(DEFAULT_POOL_MAX_IDLE))
+        .tcp_keepalive(Duration::from_secs(60))
+        .build()
+        .context("Failed to create HTTP client")?;
+    clients.insert(key, client.clone());
+    Ok(client)
+}
+
+/// The request queue of a provider endpoint
+///
+/// Routers over the same endpoint and limit share a queue, so the limit holds across every
+/// router of the process, e.g. concurrent batch jobs or bot tenants.
+pub fn queue_for(config: &ProviderConfig) -> Arc<ProviderQueue> {
+    let max = max_concurrent(config);
+    let key = format!("{}@{}#{}", config.provider_type, config.api_base.as_deref().unwrap_or_default(), max.unwrap_or(0));
+
+    QUEUES.lock().unwrap_or_else(|e| e.into_inner())
+        .entry(key)
+        .or_insert_with(|| Arc::new(ProviderQueue::new(&config.provider_type, max)))
+        .clone()
+}
+
+/// Requests to one provider: caps how many are in flight and measures how long they wait
+pub struct ProviderQueue {
+    /// Provider name
+    provider: String,
+
+    /// Most requests in flight at once, if limited
+    max_concurrent: Option<usize>,
+
+    /// One permit per request allowed in flight
+    permits: Option<Arc<Semaphore>>,
+
+    /// Requests in flight
+    in_flight: Arc<AtomicUsize>,
+
+    /// Requests waiting for a permit
+    waiting: AtomicUsize,
+
+    /// Requests admitted
+    requests: AtomicU64,
+
+    /// Total time requests waited, for a permit or a rate limit, in microseconds
+    wait_micros: AtomicU64,
+
+    /// Longest time a request waited, in microseconds
+    max_wait_micros: AtomicU64,
+}
+
+/// A request's place among the provider's requests in flight; frees it when dropped
+pub struct QueueSlot {
+    /// Permit of a limited provider
+    _permit: Option<OwnedSemaphorePermit>,
+
+    /// Counter of the requests in flight
+    in_flight: Arc<AtomicUsize>,
+
+    /// How long the request waited for its slot
+    pub waited: Duration,
+}
+
+impl Drop for QueueSlot {
+    fn drop(&mut self) {
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Queue metrics of a provider
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueueMetrics {
+    /// Provider name
+    pub provider: String,
+
+    /// Most requests in flight at once, if limited
+    pub max_concurrent: Option<usize>,
+
+    /// Requests in flight now
+    pub in_flight: usize,
+
+    /// Requests waiting for a slot now
+    pub waiting: usize,
+
+    /// Requests admitted so far
+    pub requests: u64,
+
+    /// Total time requests waited for a slot or a rate limit, in milliseconds
+    pub total_wait_ms: u64,
+
+    /// Average wait per request, in milliseconds
+    pub avg_wait_ms: f64,
+
+    /// Longest wait of a request, in milliseconds
+    pub max_wait_ms: u64,
+}
+
+impl ProviderQueue {
+    /// Create a queue allowing `max_concurrent` requests in flight, or any number
+    pub fn new(provider: &str, max_concurrent: Option<usize>) -> Self {
+        Self {
+            provider: provider.to_string(),
+            max_concurrent,
+            permits: max_concurrent.map(|max| Arc::new(Semaphore::new(max))),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            waiting: AtomicUsize::new(0),
+            requests: AtomicU64::new(0),
+            wait_micros: AtomicU64::new(0),
+            max_wait_micros: AtomicU64::new(0),
+        }
+    }
+
+    /// Wait for a slot to send a request in; waiters are admitted in order
+    pub async fn enter(&self) -> QueueSlot {
+        let started = tokio::time::Instant::now();
+        let permit = match &self.permits {
+            Some(permits) => {
+                self.waiting.fetch_add(1, Ordering::Relaxed);
+                let permit = permits.clone().acquire_owned().await.expect("provider queue semaphore is never closed");
+                self.waiting.fetch_sub(1, Ordering::Relaxed);
+                Some(permit)
+            },
+            None => None,
+        };
+
+        let waited = started.elapsed();
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.record_wait(waited);
+
+        QueueSlot { _permit: permit, in_flight: self.in_flight.clone(), waited }
+    }
+
+    /// Add time a request spent waiting, e.g. for the provider's rate limit
+    pub fn record_wait(&self, waited: Duration) {
+        let micros = u64::try_from(waited.as_micros()).unwrap_or(u64::MAX);
+        self.wait_micros.fetch_add(micros, Ordering::Relaxed);
+        self.max_wait_micros.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    /// Current counts and wait times
+    pub fn metrics(&self) -> QueueMetrics {
+        let requests = self.requests.load(Ordering::Relaxed);
+        let wait_micros = self.wait_micros.load(Ordering::Relaxed);
+        QueueMetrics {
+            provider: self.provider.clone(),
+            max_concurrent: self.max_concurrent,
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+            waiting: self.waiting.load(Ordering::Relaxed),
+            requests,
+            total_wait_ms: wait_micros / 1_000,
+            avg_wait_ms: if requests == 0 { 0.0 } else { wait_micros as f64 / requests as f64 / 1_000.0 },
+            max_wait_ms: self.max_wait_micros.load(Ordering::Relaxed) / 1_000,
+        }
+    }
+}
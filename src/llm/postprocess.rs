@@ -0,0 +1,362 @@
+// Post-processing of model answers before they reach the agents
+
+use anyhow::{Result, anyhow};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::llm::client::{LlmError, LlmRequest, LlmResponse};
+
+/// Names of the built-in response processors
+pub const BUILTIN_PROCESSORS: &[&str] = &["strip_fences", "redact_secrets", "max_length", "profanity"];
+
+/// Words masked by the profanity filter in addition to configured ones
+const PROFANITY: &[&str] = &[
+    "arse", "arsehole", "asshole", "bastard", "bitch", "bollocks", "bullshit", "crap", "damn",
+    "dick", "fuck", "fucked", "fucking", "motherfucker", "piss", "pissed", "shit", "shitty", "wanker",
+];
+
+/// Prefix of response processor executables on the PATH, e.g. `qitops-process-spellcheck`
+pub const PROCESSOR_COMMAND_PREFIX: &str = "qitops-process-";
+
+/// A step of the post-processing pipeline, applied to the text of each answer
+///
+/// Steps naming no built-in processor run a plugin program, see [`CommandProcessor`].
+pub trait ResponseProcessor: Send + Sync {
+    /// Name the processor is configured by
+    fn name(&self) -> &str;
+
+    /// Process the text of an answer to a request
+    fn process(&self, text: &str, request: &LlmRequest) -> Result<String>;
+}
+
+/// A configured post-processing step
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessorConfig {
+    /// Processor name: a built-in one or one added by a plugin
+    pub name: String,
+
+    /// Processor options
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub options: HashMap<String, String>,
+
+    /// Tasks the step applies to (e.g. `test-gen`); empty means all
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tasks: Vec<String>,
+}
+
+impl ProcessorConfig {
+    /// Configure a processor without options
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), options: HashMap::new(), tasks: Vec::new() }
+    }
+
+    /// Set an option
+    pub fn with_option(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.insert(key.into(), value.into());
+        self
+    }
+
+    /// Limit the step to some tasks
+    pub fn with_tasks(mut self, tasks: Vec<String>) -> Self {
+        self.tasks = tasks;
+        self
+    }
+
+    /// Whether the step applies to a task
+    fn applies_to(&self, task: Option<&str>) -> bool {
+        self.tasks.is_empty() || task.is_some_and(|task| self.tasks.iter().any(|t| t == task))
+    }
+}
+
+/// Removes a markdown code fence around the whole answer
+pub struct StripFences;
+
+impl ResponseProcessor for StripFences {
+    fn name(&self) -> &str {
+        "strip_fences"
+    }
+
+    fn process(&self, text: &str, _request: &LlmRequest) -> Result<String> {
+        Ok(unfence(text).unwrap_or(text).to_string())
+    }
+}
+
+/// The content of a markdown code fence around the whole text, or None when it isn't fenced
+///
+/// Only a single fence is unwrapped; text with several code blocks is left alone.
+pub fn unfence(text: &str) -> Option<&str> {
+    let body = text.trim().strip_prefix("```")?.strip_suffix("```")?;
+
+    // Fences on one line, "```code```", have no info string
+    let Some((_, body)) = body.split_once('\n') else {
+        return Some(body.trim());
+    };
+
+    if body.contains("\n```") {
+        return None;
+    }
+
+    Some(body.trim_end())
+}
+
+/// The content of the first markdown code fence in a text, allowing for prose around it and
+/// a missing closing fence, or None when the text has no fence
+pub fn fenced_block(text: &str) -> Option<&str> {
+    let rest = &text[text.find("```")? + 3..];
+
+    // Fences on one line, "```code```", have no info string
+    let line_end = rest.find('\n').unwrap_or(rest.len());
+    if let Some(end) = rest[..line_end].find("```") {
+        return Some(rest[..end].trim());
+    }
+
+    let body = rest.get(line_end + 1..).unwrap_or("");
+    Some(match body.find("\n```") {
+        Some(end) => &body[..end],
+        None => body.trim_end().trim_end_matches("```").trim_end(),
+    })
+}
+
+/// Redacts credentials the model echoed from its input, such as tokens in a diff
+pub struct RedactSecrets;
+
+impl ResponseProcessor for RedactSecrets {
+    fn name(&self) -> &str {
+        "redact_secrets"
+    }
+
+    fn process(&self, text: &str, _request: &LlmRequest) -> Result<String> {
+        Ok(crate::debug::redact_credentials(text))
+    }
+}
+
+/// Cuts answers off at a number of characters
+///
+/// Answers in JSON mode are left alone, since a cut document no longer parses.
+pub struct MaxLength {
+    /// Characters kept
+    pub max_chars: usize,
+
+    /// Appended to a cut answer
+    pub marker: String,
+}
+
+impl ResponseProcessor for MaxLength {
+    fn name(&self) -> &str {
+        "max_length"
+    }
+
+    fn process(&self, text: &str, request: &LlmRequest) -> Result<String> {
+        if request.json_mode || text.chars().count() <= self.max_chars {
+            return Ok(text.to_string());
+        }
+
+        let end = text.char_indices().nth(self.max_chars).map_or(text.len(), |(index, _)| index);
+        Ok(format!("{}{}", text[..end].trim_end(), self.marker))
+    }
+}
+
+/// Masks profane words, keeping their first letter
+pub struct ProfanityFilter {
+    /// Whole-word pattern of the masked words
+    pattern: Regex,
+}
+
+impl ProfanityFilter {
+    /// Filter the built-in words and some more
+    pub fn new(extra_words: &[String]) -> Result<Self> {
+        let words = PROFANITY.iter().copied()
+            .chain(extra_words.iter().map(String::as_str))
+            .filter(|word| !word.is_empty())
+            .map(regex::escape)
+            .collect::<Vec<_>>()
+            .join("|");
+        let pattern = Regex::new(&format!(r"(?i)\b(?:{})\b", words))
+            .map_err(|e| anyhow!("Invalid profanity word list: {}", e))?;
+
+        Ok(Self { pattern })
+    }
+}
+
+impl ResponseProcessor for ProfanityFilter {
+    fn name(&self) -> &str {
+        "profanity"
+    }
+
+    fn process(&self, text: &str, _request: &LlmRequest) -> Result<String> {
+        Ok(self.pattern.replace_all(text, |captures: &regex::Captures| {
+            let word = &captures[0];
+            let mut chars = word.chars();
+            let first = chars.next().map(String::from).unwrap_or_default();
+            format!("{}{}", first, "*".repeat(chars.count()))
+        }).into_owned())
+    }
+}
+
+/// Response processor plugin implemented by an external program
+///
+/// The program receives the answer on stdin and prints the processed answer. Programs are
+/// looked up in the processor plugin directory by the name of the step, then on the PATH as
+/// `qitops-process-<name>`.
+#[cfg(feature = "plugins")]
+pub struct CommandProcessor {
+    /// Processor name
+    name: String,
+
+    /// Program to run
+    program: std::path::PathBuf,
+}
+
+#[cfg(feature = "plugins")]
+impl CommandProcessor {
+    /// Create a processor backed by a program
+    pub fn new(name: &str, program: std::path::PathBuf) -> Self {
+        Self { name: name.to_string(), program }
+    }
+
+    /// The processor plugin of a step, if its program is installed
+    pub fn find(name: &str) -> Option<Self> {
+        let dir = crate::plugin::program::plugin_dir("processors").ok()?;
+        crate::plugin::program::find_program(&dir, PROCESSOR_COMMAND_PREFIX, name).map(|program| Self::new(name, program))
+    }
+}
+
+#[cfg(feature = "plugins")]
+impl ResponseProcessor for CommandProcessor {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn process(&self, text: &str, _request: &LlmRequest) -> Result<String> {
+        let output = crate::plugin::program::run(&self.program, text.as_bytes().to_vec())?;
+        if !output.status.success() {
+            return Err(anyhow!("plugin {} exited with {}", self.program.display(), output.status));
+        }
+
+        // Programs end their output with a newline the answer may not have had
+        let processed = String::from_utf8_lossy(&output.stdout).into_owned();
+        match processed.strip_suffix('\n') {
+            Some(stripped) if !text.ends_with('\n') => Ok(stripped.to_string()),
+            _ => Ok(processed),
+        }
+    }
+}
+
+/// Create a built-in processor from its configuration; `None` if the name isn't built in
+pub fn builtin_processor(config: &ProcessorConfig) -> Option<Result<Arc<dyn ResponseProcessor>>> {
+    let processor: Result<Arc<dyn ResponseProcessor>> = match config.name.as_str() {
+        "strip_fences" => Ok(Arc::new(StripFences)),
+        "redact_secrets" => Ok(Arc::new(RedactSecrets)),
+        "max_length" => config.options.get("max_chars")
+            .ok_or_else(|| anyhow!("The max_length response processor needs a max_chars option"))
+            .and_then(|value| value.parse::<usize>().map_err(|_| anyhow!("Invalid max_chars for max_length: {}", value)))
+            .map(|max_chars| -> Arc<dyn ResponseProcessor> {
+                let marker = config.options.get("marker").cloned().unwrap_or_else(|| "\n\n[truncated]".to_string());
+                Arc::new(MaxLength { max_chars, marker })
+            }),
+        "profanity" => {
+            let words: Vec<String> = config.options.get("words")
+                .map(|words| words.split(',').map(|word| word.trim().to_string()).collect())
+                .unwrap_or_default();
+            ProfanityFilter::new(&words).map(|filter| -> Arc<dyn ResponseProcessor> { Arc::new(filter) })
+        },
+        _ => return None,
+    };
+
+    Some(processor)
+}
+
+/// The installed plugin program of a processor
+#[cfg(feature = "plugins")]
+fn plugin_processor(name: &str) -> Option<Arc<dyn ResponseProcessor>> {
+    CommandProcessor::find(name).map(|processor| -> Arc<dyn ResponseProcessor> { Arc::new(processor) })
+}
+
+/// Processor plugins aren't built in without the `plugins` feature
+#[cfg(not(feature = "plugins"))]
+fn plugin_processor(_name: &str) -> Option<Arc<dyn ResponseProcessor>> {
+    None
+}
+
+/// A configured step and the processor that runs it, once known
+#[derive(Clone)]
+struct Stage {
+    config: ProcessorConfig,
+    processor: Option<Arc<dyn ResponseProcessor>>,
+}
+
+/// The configured post-processing steps, run in order on every answer
+#[derive(Clone, Default)]
+pub struct Pipeline {
+    stages: Vec<Stage>,
+}
+
+impl Pipeline {
+    /// Build the pipeline of a configuration
+    ///
+    /// Steps that name no built-in processor run the installed plugin program of that name, or
+    /// one [`register`](Self::register)ed later.
+    pub fn from_config(configs: &[ProcessorConfig]) -> Result<Self> {
+        let stages = configs.iter()
+            .map(|config| Ok(Stage {
+                config: config.clone(),
+                processor: match builtin_processor(config).transpose()? {
+                    Some(processor) => Some(processor),
+                    None => plugin_processor(&config.name),
+                },
+            }))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { stages })
+    }
+
+    /// Provide a plugin processor to the steps that name it
+    ///
+    /// Built-in processors can't be replaced.
+    pub fn register(&mut self, processor: Arc<dyn ResponseProcessor>) {
+        if BUILTIN_PROCESSORS.contains(&processor.name()) {
+            tracing::warn!("Ignoring response processor plugin '{}': a built-in processor has that name", processor.name());
+            return;
+        }
+
+        for stage in self.stages.iter_mut().filter(|stage| stage.config.name == processor.name()) {
+            stage.processor = Some(processor.clone());
+        }
+    }
+
+    /// Whether no steps are configured
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Run the steps that apply to a task on an answer
+    ///
+    /// The names of the steps that changed the text are listed in the `post_processed`
+    /// metadata of the answer.
+    pub fn apply(&self, mut response: LlmResponse, request: &LlmRequest, task: Option<&str>) -> Result<LlmResponse> {
+        let mut applied = Vec::new();
+
+        for stage in self.stages.iter().filter(|stage| stage.config.applies_to(task)) {
+            let processor = stage.processor.as_ref().ok_or_else(|| LlmError::ConfigurationError(format!(
+                "unknown response processor '{}' (built-in: {}; plugins are `{}<name>` programs)",
+                stage.config.name, BUILTIN_PROCESSORS.join(", "), PROCESSOR_COMMAND_PREFIX
+            )))?;
+
+            let text = processor.process(&response.text, request)
+                .map_err(|e| anyhow!("Response processor '{}' failed: {}", stage.config.name, e))?;
+            if text != response.text {
+                response.text = text;
+                applied.push(stage.config.name.clone());
+            }
+        }
+
+        if !applied.is_empty() {
+            tracing::debug!("Post-processed answer with {}", applied.join(", "));
+            response.metadata.insert("post_processed".to_string(), serde_json::json!(applied));
+        }
+
+        Ok(response)
+    }
+}
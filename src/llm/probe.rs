@@ -0,0 +1,234 @@
+// Provider probing for `qitops llm doctor` and router startup
+
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::llm::client::{LlmClient, LlmError, LlmRequest, ProviderConfig};
+use crate::llm::providers::model_listed;
+
+/// Outcome of a check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    /// The check passed
+    Ok,
+
+    /// The check couldn't be made, or passed with a caveat
+    Warning,
+
+    /// The check failed; the provider won't work
+    Failed,
+}
+
+/// One check of a provider, with what to do about a problem
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeCheck {
+    /// What was checked: configuration, reachability, auth, model or completion
+    pub check: &'static str,
+
+    /// Outcome
+    pub status: CheckStatus,
+
+    /// What was found
+    pub detail: String,
+
+    /// What to do about a warning or failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl ProbeCheck {
+    fn ok(check: &'static str, detail: impl Into<String>) -> Self {
+        Self { check, status: CheckStatus::Ok, detail: detail.into(), hint: None }
+    }
+
+    fn warning(check: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self { check, status: CheckStatus::Warning, detail: detail.into(), hint: Some(hint.into()) }
+    }
+
+    fn failed(check: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self { check, status: CheckStatus::Failed, detail: detail.into(), hint: Some(hint.into()) }
+    }
+}
+
+/// Checks of one provider
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderProbe {
+    /// Provider type
+    pub provider: String,
+
+    /// Configured model
+    pub model: String,
+
+    /// Checks in the order they were made; probing stops at the first failure
+    pub checks: Vec<ProbeCheck>,
+
+    /// Round trip of the slowest request made, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+}
+
+impl ProviderProbe {
+    /// Whether no check failed
+    pub fn healthy(&self) -> bool {
+        self.checks.iter().all(|check| check.status != CheckStatus::Failed)
+    }
+
+    /// The failed check, summarized with its hint
+    pub fn problem(&self) -> Option<String> {
+        self.checks.iter()
+            .find(|check| check.status == CheckStatus::Failed)
+            .map(|check| match &check.hint {
+                Some(hint) => format!("{}: {} ({})", self.provider, check.detail, hint),
+                None => format!("{}: {}", self.provider, check.detail),
+            })
+    }
+
+    fn record_latency(&mut self, started: Instant) -> u64 {
+        let latency = started.elapsed().as_millis() as u64;
+        self.latency_ms = Some(self.latency_ms.unwrap_or(0).max(latency));
+        latency
+    }
+}
+
+/// Where a provider's credentials come from, for hints
+fn key_hint(config: &ProviderConfig) -> String {
+    let env = match config.provider_type.as_str() {
+        "openai" => Some("OPENAI_API_KEY"),
+        "anthropic" => Some("ANTHROPIC_API_KEY"),
+        "huggingface" => Some("HF_TOKEN"),
+        _ => None,
+    };
+
+    match env {
+        Some(env) => format!("set {} or run `qitops llm add --provider {} --api-key <key>`", env, config.provider_type),
+        None => format!("check the provider with `qitops llm list` and re-add it with `qitops llm add --provider {}`", config.provider_type),
+    }
+}
+
+/// Why a provider whose client reports it unavailable can't be used, and what to do about it
+///
+/// Only looks at the configuration, so it's cheap enough for router startup.
+pub fn unavailable_reason(config: &ProviderConfig) -> (String, String) {
+    match config.provider_type.as_str() {
+        "ollama" => {
+            let api_base = config.api_base.as_deref().unwrap_or("http://localhost:11434");
+            (
+                format!("Ollama is not reachable at {}", api_base),
+                "start it with `ollama serve`, or set its address with `qitops llm add --provider ollama --api-base <url>`".to_string(),
+            )
+        },
+        "local" => (
+            format!("the model file {} or the llama.cpp server binary is missing", config.default_model),
+            "check the model path with `qitops llm list` and that `llama-server` is on the PATH (or set the `server_binary` option or QITOPS_LLAMA_SERVER)".to_string(),
+        ),
+        "huggingface" => (
+            "no API token or endpoint is configured".to_string(),
+            key_hint(config),
+        ),
+        "mock" => (
+            "the fixture directory doesn't exist and no upstream provider is configured".to_string(),
+            "record fixtures first or check the `fixtures` option".to_string(),
+        ),
+        _ => ("no API key is configured".to_string(), key_hint(config)),
+    }
+}
+
+/// What to do about a failed request to a provider
+fn error_check(check: &'static str, config: &ProviderConfig, error: &anyhow::Error) -> ProbeCheck {
+    let detail = format!("{:#}", error);
+    match error.downcast_ref::<LlmError>() {
+        Some(LlmError::AuthError(_)) => ProbeCheck::failed("auth", detail, format!("the credentials were rejected; {}", key_hint(config))),
+        Some(LlmError::NetworkError(_)) | Some(LlmError::Timeout(_)) => ProbeCheck::failed(
+            "reachability",
+            detail,
+            "check the network, the provider's api_base and proxy settings, or raise `--connect-timeout`",
+        ),
+        Some(LlmError::RateLimitError(_)) => ProbeCheck::warning(check, detail, "the provider is rate limiting; set `--rpm`/`--tpm` so requests are queued"),
+        Some(LlmError::ServerError(_)) => ProbeCheck::warning(check, detail, "the provider is having problems; configure a fallback with `qitops llm set-fallback`"),
+        _ => ProbeCheck::failed(check, detail, format!("check the model with `qitops llm models --provider {}`", config.provider_type)),
+    }
+}
+
+/// Probe a provider: build its client, check it's reachable, its credentials and its model
+///
+/// With `completion`, a one-word completion is also requested to check the model answers;
+/// this costs a few tokens.
+pub async fn probe_provider(config: &ProviderConfig, completion: bool) -> ProviderProbe {
+    let mut probe = ProviderProbe {
+        provider: config.provider_type.clone(),
+        model: config.default_model.clone(),
+        checks: Vec::new(),
+        latency_ms: None,
+    };
+
+    let client: Arc<dyn LlmClient> = match crate::llm::providers::client_for(config) {
+        Some(Ok(client)) => client,
+        Some(Err(e)) => {
+            probe.checks.push(ProbeCheck::failed("configuration", format!("{:#}", e), key_hint(config)));
+            return probe;
+        },
+        None => {
+            probe.checks.push(ProbeCheck::failed(
+                "configuration",
+                format!("unknown provider type '{}'", config.provider_type),
+                "remove it with `qitops llm remove` and add a supported provider",
+            ));
+            return probe;
+        },
+    };
+    probe.checks.push(ProbeCheck::ok("configuration", "client created"));
+
+    let started = Instant::now();
+    if !client.is_available().await {
+        probe.record_latency(started);
+        let (detail, hint) = unavailable_reason(config);
+        probe.checks.push(ProbeCheck::failed("reachability", detail, hint));
+        return probe;
+    }
+
+    // Listing models needs valid credentials, so it checks auth and the model at once
+    let started = Instant::now();
+    match client.list_models().await {
+        Ok(models) => {
+            let latency = probe.record_latency(started);
+            probe.checks.push(ProbeCheck::ok("auth", format!("credentials accepted ({} ms)", latency)));
+            if model_listed(&models, &config.default_model) {
+                probe.checks.push(ProbeCheck::ok("model", format!("'{}' is available", config.default_model)));
+            } else {
+                probe.checks.push(ProbeCheck::failed(
+                    "model",
+                    format!("'{}' is not among the {} model(s) served", config.default_model, models.len()),
+                    format!("pick one from `qitops llm models --provider {}`", config.provider_type),
+                ));
+            }
+        },
+        // Not every provider lists models; only typed errors are problems with the provider
+        Err(e) if e.downcast_ref::<LlmError>().is_none() => {
+            probe.checks.push(ProbeCheck::warning("model", format!("{:#}", e), "use --completion to check the model answers"));
+        },
+        Err(e) => {
+            probe.record_latency(started);
+            probe.checks.push(error_check("auth", config, &e));
+        },
+    }
+
+    if completion && probe.healthy() {
+        let request = LlmRequest::new("Reply with the word OK.".to_string(), config.default_model.clone())
+            .with_max_tokens(5);
+        let started = Instant::now();
+        match client.send(request).await {
+            Ok(_) => {
+                let latency = probe.record_latency(started);
+                probe.checks.push(ProbeCheck::ok("completion", format!("model answered in {} ms", latency)));
+            },
+            Err(e) => {
+                probe.record_latency(started);
+                probe.checks.push(error_check("completion", config, &e));
+            },
+        }
+    }
+
+    probe
+}
@@ -1,16 +1,200 @@
 use anyhow::{Result, Context, anyhow};
 use async_trait::async_trait;
 use reqwest::Client as HttpClient;
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue};
 use serde_json::json;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(feature = "local-llm")]
+use std::{path::Path, process::Stdio, time::Instant};
+#[cfg(feature = "local-llm")]
+use tokio::{process::{Child, Command}, sync::Mutex};
 
-use crate::llm::client::{LlmClient, LlmRequest, LlmResponse, MessageRole, ProviderConfig};
+use crate::llm::pool;
+use crate::llm::client::{DEFAULT_CONNECT_TIMEOUT_SECS, expand_env, LlmClient, LlmError, LlmRequest, LlmResponse, MessageRole, ProviderConfig, ToolCall};
+use crate::error::QitOpsError;
+use crate::offline;
+
+/// Classify a request that failed to complete, so the router can retry transient failures
+fn request_error(api: &str, e: reqwest::Error) -> anyhow::Error {
+    let message = format!("Failed to send request to {}: {}", api, e);
+    if e.is_timeout() {
+        LlmError::Timeout(message).into()
+    } else {
+        LlmError::NetworkError(message).into()
+    }
+}
+
+/// How long an availability check waits for a local server
+#[cfg(feature = "local-llm")]
+const AVAILABILITY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Response metadata key for prompt tokens served from the provider's prompt cache
+pub const CACHED_PROMPT_TOKENS: &str = "cached_prompt_tokens";
+
+/// HTTP client for a provider, with its proxy, connect timeout, request timeout, and the
+/// static headers and authentication of a gateway in front of it
+///
+/// Clients are shared between providers with the same settings; see [`pool::shared_client`].
+fn http_client(config: &ProviderConfig) -> Result<HttpClient> {
+    pool::shared_client(config, || http_client_builder(config))
+}
+
+/// Builder of a provider's HTTP client, before pool settings
+fn http_client_builder(config: &ProviderConfig) -> Result<reqwest::ClientBuilder> {
+    let connect_timeout = config.connect_timeout_secs.unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS);
+    let mut builder = crate::proxy::builder(config.proxy.as_deref())?
+        .connect_timeout(Duration::from_secs(connect_timeout));
+    if let Some(timeout) = config.timeout_secs {
+        builder = builder.timeout(Duration::from_secs(timeout));
+    }
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in &config.headers {
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|_| LlmError::ConfigurationError(format!("Invalid header name for {}: {}", config.provider_type, name)))?;
+        headers.insert(name, sensitive_header(&expand_env(value)?, &config.provider_type)?);
+    }
+    if let Some(auth) = &config.auth {
+        headers.insert(AUTHORIZATION, sensitive_header(&auth.header_value()?, &config.provider_type)?);
+    }
+
+    Ok(builder.default_headers(headers))
+}
+
+/// Header value kept out of debug output
+fn sensitive_header(value: &str, provider_type: &str) -> Result<HeaderValue> {
+    let mut value = HeaderValue::from_str(value)
+        .map_err(|_| LlmError::ConfigurationError(format!("Invalid header value for {}", provider_type)))?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+/// Classify an unsuccessful response by status code
+fn status_error(api: &str, status: reqwest::StatusCode, error_text: String) -> anyhow::Error {
+    match status.as_u16() {
+        429 => LlmError::RateLimitError(error_text).into(),
+        500..=599 => LlmError::ServerError(format!("{} ({}): {}", api, status, error_text)).into(),
+        _ => LlmError::ApiError(format!("{} ({}): {}", api, status, error_text)).into(),
+    }
+}
+
+/// Create the client for a provider, or `None` for an unknown provider type
+///
+/// In offline mode only providers running on this machine are allowed.
+pub fn client_for(config: &ProviderConfig) -> Option<Result<Arc<dyn LlmClient>>> {
+    if offline::is_enabled() && !is_local(config) {
+        return Some(Err(QitOpsError::Offline(format!("{} is disabled in offline mode (--offline): it is not running on this machine", config.provider_type)).into()));
+    }
+
+    Some(match config.provider_type.as_str() {
+        "openai" => OpenAiClient::new(config).map(|c| Arc::new(c) as Arc<dyn LlmClient>),
+        #[cfg(feature = "local-llm")]
+        "ollama" => OllamaClient::new(config).map(|c| Arc::new(c) as Arc<dyn LlmClient>),
+        "anthropic" => AnthropicClient::new(config).map(|c| Arc::new(c) as Arc<dyn LlmClient>),
+        "huggingface" => HuggingFaceClient::new(config).map(|c| Arc::new(c) as Arc<dyn LlmClient>),
+        #[cfg(feature = "local-llm")]
+        "local" => LocalClient::new(config).map(|c| Arc::new(c) as Arc<dyn LlmClient>),
+        #[cfg(not(feature = "local-llm"))]
+        "ollama" | "local" => Err(LlmError::ConfigurationError(format!(
+            "{} is not built into this binary; rebuild with the `local-llm` feature", config.provider_type,
+        )).into()),
+        "mock" => MockClient::new(config).map(|c| Arc::new(c) as Arc<dyn LlmClient>),
+        _ => return None,
+    })
+}
+
+/// Whether a provider runs on this machine: a local model, or an API on a loopback address
+pub fn is_local(config: &ProviderConfig) -> bool {
+    match config.provider_type.as_str() {
+        // Recording sends requests to the recorded provider
+        "mock" => config.options.get("mode").is_none_or(|mode| mode != "record"),
+        "local" => true,
+        // Ollama defaults to localhost
+        "ollama" => config.api_base.as_deref().is_none_or(offline::is_local_url),
+        _ => config.api_base.as_deref().is_some_and(offline::is_local_url),
+    }
+}
+
+/// Whether a model is in a provider's model list
+///
+/// Ollama lists models with their tag, and an untagged model means `latest`.
+pub fn model_listed(models: &[String], model: &str) -> bool {
+    models.iter().any(|listed| listed == model || listed.strip_suffix(":latest") == Some(model))
+}
+
+/// Get a JSON model listing, failing on unsuccessful responses
+async fn get_model_list(request: reqwest::RequestBuilder, api: &str) -> Result<serde_json::Value> {
+    let response = request.send().await
+        .map_err(|e| request_error(api, e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await
+            .unwrap_or_else(|_| "Could not read error response".to_string());
+        return Err(match status.as_u16() {
+            401 | 403 => LlmError::AuthError(error_text).into(),
+            _ => status_error(api, status, error_text),
+        });
+    }
+
+    response.json().await
+        .map_err(|e| anyhow!("Failed to parse {} model list: {}", api, e))
+}
+
+/// Sorted string values of a field in each element of a JSON array
+fn model_names(list: &serde_json::Value, field: &str) -> Vec<String> {
+    let mut names: Vec<String> = list.as_array()
+        .map(|items| items.iter().filter_map(|item| item[field].as_str().map(str::to_string)).collect())
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// Tool calls in an OpenAI chat completion message, whose arguments arrive as a JSON string
+pub fn openai_tool_calls(message: &serde_json::Value) -> Vec<ToolCall> {
+    message["tool_calls"].as_array()
+        .map(|calls| calls.iter().filter_map(|call| {
+            let function = &call["function"];
+            let arguments = function["arguments"].as_str()
+                .and_then(|arguments| serde_json::from_str(arguments).ok())
+                .unwrap_or_else(|| json!({}));
+            Some(ToolCall {
+                id: call["id"].as_str().unwrap_or_default().to_string(),
+                name: function["name"].as_str()?.to_string(),
+                arguments,
+            })
+        }).collect())
+        .unwrap_or_default()
+}
+
+/// Tool calls in the `tool_use` content blocks of an Anthropic message
+pub fn anthropic_tool_calls(blocks: &[serde_json::Value]) -> Vec<ToolCall> {
+    blocks.iter()
+        .filter(|block| block["type"] == "tool_use")
+        .filter_map(|block| Some(ToolCall {
+            id: block["id"].as_str().unwrap_or_default().to_string(),
+            name: block["name"].as_str()?.to_string(),
+            arguments: block["input"].clone(),
+        }))
+        .collect()
+}
 
 /// OpenAI LLM client
 pub struct OpenAiClient {
     api_key: String,
     api_base: String,
     http_client: HttpClient,
+
+    /// Organization billed for requests, sent as `OpenAI-Organization` (option `organization`)
+    organization: Option<String>,
+
+    /// Project requests are scoped to, sent as `OpenAI-Project` (option `project`)
+    project: Option<String>,
+
+    /// A gateway's `auth` replaces the API key
+    gateway_auth: bool,
 }
 
 impl OpenAiClient {
@@ -18,17 +202,50 @@ impl OpenAiClient {
     pub fn new(config: &ProviderConfig) -> Result<Self> {
         let api_key = config.api_key.clone()
             .or_else(|| std::env::var("OPENAI_API_KEY").ok())
+            .or_else(|| config.auth.as_ref().map(|_| String::new()))
             .context("OpenAI API key not found in config or OPENAI_API_KEY environment variable")?;
 
         let api_base = config.api_base.clone()
             .unwrap_or_else(|| "https://api.openai.com/v1".to_string());
 
+        // Same environment variables as the official OpenAI SDKs
+        let scope = |option: &str, env: &str| config.options.get(option).cloned()
+            .or_else(|| std::env::var(env).ok())
+            .filter(|value| !value.trim().is_empty());
+
         Ok(Self {
             api_key,
             api_base,
-            http_client: HttpClient::new(),
+            http_client: http_client(config)?,
+            organization: scope("organization", "OPENAI_ORG_ID"),
+            project: scope("project", "OPENAI_PROJECT_ID"),
+            gateway_auth: config.auth.is_some(),
         })
     }
+
+    /// Add the API key and the organization and project headers to a request
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        // A gateway's authorization is a default header of the HTTP client
+        let mut request = match self.gateway_auth {
+            true => request,
+            false => request.bearer_auth(&self.api_key),
+        };
+        if let Some(organization) = &self.organization {
+            request = request.header("OpenAI-Organization", organization);
+        }
+        if let Some(project) = &self.project {
+            request = request.header("OpenAI-Project", project);
+        }
+        request
+    }
+
+    /// Hint added to authentication errors when the key may need a project or organization
+    fn scope_hint(&self) -> &'static str {
+        match (&self.organization, &self.project) {
+            (None, None) => " (if your account requires project scoping, set the provider's `organization` and `project` options or use a project API key)",
+            _ => " (check that the API key belongs to the configured organization and project)",
+        }
+    }
     
     /// Build the OpenAI API request
     async fn build_request(&self, request: &LlmRequest) -> Result<serde_json::Value> {
@@ -59,6 +276,28 @@ impl OpenAiClient {
         if !request.stop.is_empty() {
             body["stop"] = json!(request.stop);
         }
+
+        // Ask for a JSON object
+        if request.json_mode {
+            body["response_format"] = json!({ "type": "json_object" });
+        }
+
+        // Best-effort determinism
+        if let Some(seed) = request.seed {
+            body["seed"] = json!(seed);
+        }
+
+        // Add tools if any
+        if !request.tools.is_empty() {
+            body["tools"] = request.tools.iter().map(|tool| json!({
+                "type": "function",
+                "function": {
+                    "name": tool.name,
+                    "description": tool.description,
+                    "parameters": tool.parameters,
+                }
+            })).collect();
+        }
         
         // Add any additional options
         for (key, value) in &request.options {
@@ -73,8 +312,8 @@ impl OpenAiClient {
 impl LlmClient for OpenAiClient {
     async fn send(&self, request: LlmRequest) -> Result<LlmResponse> {
         // Check if API key is available
-        if self.api_key.is_empty() {
-            return Err(anyhow!("OpenAI API key not found in config or OPENAI_API_KEY environment variable"));
+        if self.api_key.is_empty() && !self.gateway_auth {
+            return Err(LlmError::ConfigurationError("OpenAI API key not found in config or OPENAI_API_KEY environment variable".to_string()).into());
         }
         
         // Build the request body
@@ -83,13 +322,12 @@ impl LlmClient for OpenAiClient {
         // Send the request to the OpenAI API
         let url = format!("{}/chat/completions", self.api_base);
         
-        let response = self.http_client.post(&url)
+        let response = self.authorize(self.http_client.post(&url))
             .header("Content-Type", "application/json")
-            .header("Authorization", format!("Bearer {}", self.api_key))
             .json(&body)
             .send()
             .await
-            .map_err(|e| anyhow!("Failed to send request to OpenAI API: {}", e))?;
+            .map_err(|e| request_error("OpenAI API", e))?;
             
         // Check if the request was successful
         if !response.status().is_success() {
@@ -98,10 +336,10 @@ impl LlmClient for OpenAiClient {
                 .unwrap_or_else(|_| "Could not read error response".to_string());
                 
             return match status.as_u16() {
-                401 => Err(anyhow!("Authentication error: {}", error_text)),
-                429 => Err(anyhow!("Rate limit exceeded: {}", error_text)),
-                500..=599 => Err(anyhow!("OpenAI server error: {}", error_text)),
-                _ => Err(anyhow!("OpenAI API error ({}): {}", status, error_text)),
+                401 | 403 => Err(LlmError::AuthError(format!("{}{}", error_text, self.scope_hint())).into()),
+                429 => Err(LlmError::RateLimitError(error_text).into()),
+                500..=599 => Err(LlmError::ServerError(format!("OpenAI: {}", error_text)).into()),
+                _ => Err(LlmError::ApiError(format!("OpenAI ({}): {}", status, error_text)).into()),
             };
         }
         
@@ -119,8 +357,14 @@ impl LlmClient for OpenAiClient {
         }
         
         let message = &choices[0]["message"];
-        let content = message["content"].as_str()
-            .ok_or_else(|| anyhow!("Invalid response format: 'content' field is missing or not a string"))?;
+        let tool_calls = openai_tool_calls(message);
+
+        // Content is null when the model only calls tools
+        let content = match message["content"].as_str() {
+            Some(content) => content,
+            None if !tool_calls.is_empty() => "",
+            None => return Err(anyhow!("Invalid response format: 'content' field is missing or not a string")),
+        };
             
         // Extract token usage
         let usage = response_json["usage"].as_object();
@@ -139,11 +383,15 @@ impl LlmClient for OpenAiClient {
             content.to_string(),
             model,
             self.name().to_string()
-        );
+        ).with_tool_calls(tool_calls);
         
         if let Some(tokens) = tokens_used {
             llm_response = llm_response.with_tokens(tokens);
         }
+        // OpenAI caches long prompt prefixes automatically and reports how much was reused
+        if let Some(cached) = response_json["usage"]["prompt_tokens_details"]["cached_tokens"].as_u64().filter(|cached| *cached > 0) {
+            llm_response = llm_response.with_metadata(CACHED_PROMPT_TOKENS, json!(cached));
+        }
         
         Ok(llm_response)
     }
@@ -153,7 +401,13 @@ impl LlmClient for OpenAiClient {
     }
 
     async fn is_available(&self) -> bool {
-        !self.api_key.is_empty()
+        !self.api_key.is_empty() || self.gateway_auth
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        let request = self.authorize(self.http_client.get(format!("{}/models", self.api_base)));
+        let list = get_model_list(request, "OpenAI API").await?;
+        Ok(model_names(&list["data"], "id"))
     }
 }
 
@@ -162,6 +416,12 @@ pub struct AnthropicClient {
     api_key: String,
     api_base: String,
     http_client: HttpClient,
+
+    /// Mark the system prompt for prompt caching (option `prompt_caching`, on by default)
+    prompt_caching: bool,
+
+    /// A gateway's `auth` authenticates requests, so the API key is optional
+    gateway_auth: bool,
 }
 
 impl AnthropicClient {
@@ -169,6 +429,7 @@ impl AnthropicClient {
     pub fn new(config: &ProviderConfig) -> Result<Self> {
         let api_key = config.api_key.clone()
             .or_else(|| std::env::var("ANTHROPIC_API_KEY").ok())
+            .or_else(|| config.auth.as_ref().map(|_| String::new()))
             .context("Anthropic API key not found in config or ANTHROPIC_API_KEY environment variable")?;
 
         let api_base = config.api_base.clone()
@@ -177,9 +438,20 @@ impl AnthropicClient {
         Ok(Self {
             api_key,
             api_base,
-            http_client: HttpClient::new(),
+            http_client: http_client(config)?,
+            prompt_caching: config.options.get("prompt_caching").is_none_or(|value| value != "false"),
+            gateway_auth: config.auth.is_some(),
         })
     }
+
+    /// Add the API key, if there is one, and the API version to a request
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let request = request.header("anthropic-version", "2023-06-01");
+        match self.api_key.is_empty() {
+            true => request,
+            false => request.header("X-API-Key", &self.api_key),
+        }
+    }
     
     /// Build the Anthropic API request
     async fn build_request(&self, request: &LlmRequest) -> Result<serde_json::Value> {
@@ -216,15 +488,34 @@ impl AnthropicClient {
             "top_p": request.top_p,
         });
         
-        // Add system prompt if present
+        // Add system prompt if present. The system prompt (with the tools before it) is the same
+        // across requests of a command, and holds the source context, so it's marked for caching;
+        // prompts shorter than the model's minimum are simply not cached.
         if !system_prompt.is_empty() {
-            body["system"] = json!(system_prompt);
+            body["system"] = match self.prompt_caching {
+                true => json!([{ "type": "text", "text": system_prompt, "cache_control": { "type": "ephemeral" } }]),
+                false => json!(system_prompt),
+            };
         }
         
         // Add stop sequences if any
         if !request.stop.is_empty() {
             body["stop_sequences"] = json!(request.stop);
         }
+
+        // The Messages API has no seed; answers at a given temperature may still vary
+        if request.seed.is_some() {
+            tracing::debug!("Anthropic doesn't support sampling seeds; ignoring the seed");
+        }
+
+        // Add tools if any
+        if !request.tools.is_empty() {
+            body["tools"] = request.tools.iter().map(|tool| json!({
+                "name": tool.name,
+                "description": tool.description,
+                "input_schema": tool.parameters,
+            })).collect();
+        }
         
         // Add any additional options
         for (key, value) in &request.options {
@@ -239,8 +530,8 @@ impl AnthropicClient {
 impl LlmClient for AnthropicClient {
     async fn send(&self, request: LlmRequest) -> Result<LlmResponse> {
         // Check if API key is available
-        if self.api_key.is_empty() {
-            return Err(anyhow!("Anthropic API key not found in config or ANTHROPIC_API_KEY environment variable"));
+        if self.api_key.is_empty() && !self.gateway_auth {
+            return Err(LlmError::ConfigurationError("Anthropic API key not found in config or ANTHROPIC_API_KEY environment variable".to_string()).into());
         }
         
         // Build the request body
@@ -249,14 +540,12 @@ impl LlmClient for AnthropicClient {
         // Send the request to the Anthropic API
         let url = format!("{}/v1/messages", self.api_base);
         
-        let response = self.http_client.post(&url)
+        let response = self.authorize(self.http_client.post(&url))
             .header("Content-Type", "application/json")
-            .header("X-API-Key", &self.api_key)
-            .header("anthropic-version", "2023-06-01")
             .json(&body)
             .send()
             .await
-            .map_err(|e| anyhow!("Failed to send request to Anthropic API: {}", e))?;
+            .map_err(|e| request_error("Anthropic API", e))?;
             
         // Check if the request was successful
         if !response.status().is_success() {
@@ -265,10 +554,10 @@ impl LlmClient for AnthropicClient {
                 .unwrap_or_else(|_| "Could not read error response".to_string());
                 
             return match status.as_u16() {
-                401 => Err(anyhow!("Authentication error: {}", error_text)),
-                429 => Err(anyhow!("Rate limit exceeded: {}", error_text)),
-                500..=599 => Err(anyhow!("Anthropic server error: {}", error_text)),
-                _ => Err(anyhow!("Anthropic API error ({}): {}", status, error_text)),
+                401 => Err(LlmError::AuthError(error_text).into()),
+                429 => Err(LlmError::RateLimitError(error_text).into()),
+                500..=599 => Err(LlmError::ServerError(format!("Anthropic: {}", error_text)).into()),
+                _ => Err(LlmError::ApiError(format!("Anthropic ({}): {}", status, error_text)).into()),
             };
         }
         
@@ -277,17 +566,25 @@ impl LlmClient for AnthropicClient {
             .await
             .map_err(|e| anyhow!("Failed to parse Anthropic API response: {}", e))?;
             
-        // Extract the response text
-        let content = response_json["content"].as_array()
-            .and_then(|arr| arr.first())
-            .and_then(|first| first["text"].as_str())
+        // Extract the response text and tool calls from the content blocks
+        let blocks = response_json["content"].as_array()
             .ok_or_else(|| anyhow!("Invalid response format: 'content' field is missing or not properly formatted"))?;
+        let content = blocks.iter()
+            .filter_map(|block| block["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let tool_calls = anthropic_tool_calls(blocks);
+
+        if content.is_empty() && tool_calls.is_empty() {
+            return Err(anyhow!("Invalid response format: 'content' field is missing or not properly formatted"));
+        }
             
         // Extract token usage if available
-        let tokens_used = response_json["usage"]["input_tokens"].as_u64()
-            .and_then(|input| {
-                response_json["usage"]["output_tokens"].as_u64().map(|output| input + output)
-            })
+        // With prompt caching, `input_tokens` only counts the prompt after the last cache breakpoint
+        let usage = &response_json["usage"];
+        let tokens_used = usage["input_tokens"].as_u64()
+            .and_then(|input| usage["output_tokens"].as_u64().map(|output| input + output))
+            .map(|tokens| tokens + usage["cache_read_input_tokens"].as_u64().unwrap_or(0) + usage["cache_creation_input_tokens"].as_u64().unwrap_or(0))
             .map(|t| t as usize);
             
         // Extract model info
@@ -297,14 +594,17 @@ impl LlmClient for AnthropicClient {
             
         // Create the response
         let mut llm_response = LlmResponse::new(
-            content.to_string(),
+            content,
             model,
             self.name().to_string()
-        );
+        ).with_tool_calls(tool_calls);
         
         if let Some(tokens) = tokens_used {
             llm_response = llm_response.with_tokens(tokens);
         }
+        if let Some(cached) = usage["cache_read_input_tokens"].as_u64().filter(|cached| *cached > 0) {
+            llm_response = llm_response.with_metadata(CACHED_PROMPT_TOKENS, json!(cached));
+        }
         
         Ok(llm_response)
     }
@@ -314,16 +614,24 @@ impl LlmClient for AnthropicClient {
     }
 
     async fn is_available(&self) -> bool {
-        !self.api_key.is_empty()
+        !self.api_key.is_empty() || self.gateway_auth
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        let request = self.authorize(self.http_client.get(format!("{}/v1/models?limit=1000", self.api_base)));
+        let list = get_model_list(request, "Anthropic API").await?;
+        Ok(model_names(&list["data"], "id"))
     }
 }
 
 /// Ollama LLM client
+#[cfg(feature = "local-llm")]
 pub struct OllamaClient {
     api_base: String,
     http_client: HttpClient,
 }
 
+#[cfg(feature = "local-llm")]
 impl OllamaClient {
     /// Create a new Ollama client
     pub fn new(config: &ProviderConfig) -> Result<Self> {
@@ -332,7 +640,7 @@ impl OllamaClient {
 
         Ok(Self {
             api_base,
-            http_client: HttpClient::new(),
+            http_client: http_client(config)?,
         })
     }
     
@@ -367,6 +675,15 @@ impl OllamaClient {
                 "num_predict": request.max_tokens,
             }
         });
+
+        // Constrain the output to JSON
+        if request.json_mode {
+            body["format"] = json!("json");
+        }
+
+        if let Some(seed) = request.seed {
+            body["options"]["seed"] = json!(seed);
+        }
         
         // Add any additional options
         for (key, value) in &request.options {
@@ -377,6 +694,7 @@ impl OllamaClient {
     }
 }
 
+#[cfg(feature = "local-llm")]
 #[async_trait]
 impl LlmClient for OllamaClient {
     async fn send(&self, request: LlmRequest) -> Result<LlmResponse> {
@@ -391,7 +709,7 @@ impl LlmClient for OllamaClient {
             .json(&body)
             .send()
             .await
-            .map_err(|e| anyhow!("Failed to send request to Ollama API: {}", e))?;
+            .map_err(|e| request_error("Ollama API", e))?;
             
         // Check if the request was successful
         if !response.status().is_success() {
@@ -399,7 +717,7 @@ impl LlmClient for OllamaClient {
             let error_text = response.text().await
                 .unwrap_or_else(|_| "Could not read error response".to_string());
                 
-            return Err(anyhow!("Ollama API error ({}): {}", status, error_text));
+            return Err(status_error("Ollama API", status, error_text));
         }
         
         // Parse the response
@@ -437,9 +755,612 @@ impl LlmClient for OllamaClient {
         // Check if Ollama is running by sending a simple request
         let url = format!("{}/api/version", self.api_base);
         
-        match self.http_client.get(&url).send().await {
+        match self.http_client.get(&url).timeout(AVAILABILITY_TIMEOUT).send().await {
             Ok(response) => response.status().is_success(),
             Err(_) => false,
         }
     }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        let request = self.http_client.get(format!("{}/api/tags", self.api_base));
+        let list = get_model_list(request, "Ollama API").await?;
+        Ok(model_names(&list["models"], "name"))
+    }
+}
+
+/// HuggingFace Inference API client, also used for Text Generation Inference (TGI) endpoints
+///
+/// Without an API base, requests go to the hosted Inference API for the configured model.
+/// With one, they go to that endpoint directly, which is how dedicated Inference Endpoints
+/// and self-hosted TGI servers are used.
+pub struct HuggingFaceClient {
+    api_key: Option<String>,
+    api_base: Option<String>,
+    task: String,
+    parameters: serde_json::Map<String, serde_json::Value>,
+    max_retries: u32,
+    http_client: HttpClient,
+}
+
+/// Longest we wait for a model to load before retrying
+const HF_MAX_LOADING_WAIT: Duration = Duration::from_secs(60);
+
+impl HuggingFaceClient {
+    /// Create a new HuggingFace client
+    ///
+    /// Provider options: `task` (`text-generation` or `text2text-generation`), `max_retries`
+    /// for model-loading retries, and `param.<name>` for extra task parameters such as
+    /// `param.repetition_penalty=1.1`. Parameter values are parsed as JSON where possible.
+    pub fn new(config: &ProviderConfig) -> Result<Self> {
+        // A gateway's `auth` replaces the token
+        let api_key = config.api_key.clone()
+            .or_else(|| std::env::var("HF_TOKEN").ok())
+            .or_else(|| std::env::var("HUGGINGFACE_API_KEY").ok())
+            .filter(|_| config.auth.is_none());
+
+        let task = config.options.get("task").cloned()
+            .unwrap_or_else(|| "text-generation".to_string());
+        if !matches!(task.as_str(), "text-generation" | "text2text-generation") {
+            return Err(anyhow!("Unsupported HuggingFace task: {} (expected text-generation or text2text-generation)", task));
+        }
+
+        let parameters = config.options.iter()
+            .filter_map(|(key, value)| {
+                let name = key.strip_prefix("param.")?;
+                let value = serde_json::from_str(value).unwrap_or_else(|_| json!(value));
+                Some((name.to_string(), value))
+            })
+            .collect();
+
+        Ok(Self {
+            api_key,
+            api_base: config.api_base.clone(),
+            task,
+            parameters,
+            max_retries: parse_option(config, "max_retries")?.unwrap_or(5),
+            http_client: http_client(config)?,
+        })
+    }
+
+    /// URL to send requests for a model to
+    fn url(&self, model: &str) -> String {
+        match &self.api_base {
+            Some(api_base) => api_base.trim_end_matches('/').to_string(),
+            None => format!("https://api-inference.huggingface.co/models/{}", model),
+        }
+    }
+
+    /// Build the Inference API request
+    fn build_request(&self, request: &LlmRequest) -> serde_json::Value {
+        // Text generation models take a single prompt
+        let inputs = request.messages.iter()
+            .map(|msg| match msg.role {
+                MessageRole::System => format!("System: {}", msg.content),
+                MessageRole::User => format!("User: {}", msg.content),
+                MessageRole::Assistant => format!("Assistant: {}", msg.content),
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n") + "\n\nAssistant:";
+
+        let mut parameters = json!({
+            "max_new_tokens": request.max_tokens,
+            "temperature": request.temperature,
+            "top_p": request.top_p,
+        });
+
+        // Only text generation can echo the prompt back
+        if self.task == "text-generation" {
+            parameters["return_full_text"] = json!(false);
+        }
+
+        if !request.stop.is_empty() {
+            parameters["stop"] = json!(request.stop);
+        }
+
+        // Other tasks reject parameters they don't know
+        if let Some(seed) = request.seed
+            && self.task == "text-generation"
+        {
+            parameters["seed"] = json!(seed);
+        }
+
+        // Configured task parameters, then per-request options
+        for (key, value) in self.parameters.iter().chain(&request.options) {
+            parameters[key] = value.clone();
+        }
+
+        json!({
+            "inputs": inputs,
+            "parameters": parameters,
+        })
+    }
+}
+
+#[async_trait]
+impl LlmClient for HuggingFaceClient {
+    async fn send(&self, request: LlmRequest) -> Result<LlmResponse> {
+        let body = self.build_request(&request);
+        let url = self.url(&request.model);
+
+        let mut attempt = 0;
+        let response_json: serde_json::Value = loop {
+            let mut http_request = self.http_client.post(&url).json(&body);
+            if let Some(api_key) = &self.api_key {
+                http_request = http_request.bearer_auth(api_key);
+            }
+
+            let response = http_request.send()
+                .await
+                .map_err(|e| request_error("HuggingFace API", e))?;
+
+            let status = response.status();
+            if status.is_success() {
+                break response.json()
+                    .await
+                    .map_err(|e| anyhow!("Failed to parse HuggingFace API response: {}", e))?;
+            }
+
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "Could not read error response".to_string());
+
+            // A 503 while the model is loaded onto a worker includes an estimated wait
+            if status.as_u16() == 503 && attempt < self.max_retries {
+                attempt += 1;
+                let estimated = serde_json::from_str::<serde_json::Value>(&error_text).ok()
+                    .and_then(|error| error["estimated_time"].as_f64())
+                    .map(Duration::from_secs_f64)
+                    .unwrap_or(Duration::from_secs(2u64.pow(attempt)));
+                let wait = estimated.min(HF_MAX_LOADING_WAIT);
+
+                tracing::info!("HuggingFace model {} is loading, retrying in {:?} ({}/{})", request.model, wait, attempt, self.max_retries);
+                tokio::time::sleep(wait).await;
+                continue;
+            }
+
+            return match status.as_u16() {
+                401 | 403 => Err(LlmError::AuthError(error_text).into()),
+                429 => Err(LlmError::RateLimitError(error_text).into()),
+                503 => Err(anyhow!("HuggingFace model {} did not finish loading after {} retries: {}", request.model, self.max_retries, error_text)),
+                500..=599 => Err(LlmError::ServerError(format!("HuggingFace: {}", error_text)).into()),
+                _ => Err(LlmError::ApiError(format!("HuggingFace ({}): {}", status, error_text)).into()),
+            };
+        };
+
+        // The Inference API returns a list of generations, TGI a single object
+        let generation = match &response_json {
+            serde_json::Value::Array(generations) => generations.first()
+                .ok_or_else(|| anyhow!("No generations returned from HuggingFace API"))?,
+            generation => generation,
+        };
+
+        let content = generation["generated_text"].as_str()
+            .ok_or_else(|| anyhow!("Invalid response format: 'generated_text' field is missing or not a string"))?;
+
+        let mut llm_response = LlmResponse::new(
+            content.trim().to_string(),
+            request.model.clone(),
+            self.name().to_string()
+        );
+
+        if let Some(tokens) = generation["details"]["generated_tokens"].as_u64() {
+            llm_response = llm_response.with_tokens(tokens as usize);
+        }
+
+        Ok(llm_response)
+    }
+
+    fn name(&self) -> &str {
+        "huggingface"
+    }
+
+    async fn is_available(&self) -> bool {
+        // The hosted API needs a token; custom endpoints may not
+        self.api_key.is_some() || self.api_base.is_some()
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        // A TGI endpoint serves a single model, which it reports in /info; the hosted API
+        // serves any public model, so there is nothing to list
+        let api_base = self.api_base.as_deref()
+            .ok_or_else(|| anyhow!("The hosted HuggingFace Inference API does not list models; any public model ID can be used"))?;
+
+        let mut request = self.http_client.get(format!("{}/info", api_base.trim_end_matches('/')));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let info = get_model_list(request, "HuggingFace endpoint").await?;
+        Ok(info["model_id"].as_str().map(|id| vec![id.to_string()]).unwrap_or_default())
+    }
+}
+
+/// Local GGUF model served by a llama.cpp server that QitOps starts and stops itself
+///
+/// Configured with `qitops llm add --provider local --model ./model.gguf` and the options
+/// `context_size`, `threads`, `gpu_layers`, `server_binary` and `startup_timeout_secs`.
+#[cfg(feature = "local-llm")]
+pub struct LocalClient {
+    model_path: PathBuf,
+    server_binary: String,
+    context_size: u32,
+    threads: Option<u32>,
+    gpu_layers: Option<u32>,
+    startup_timeout: Duration,
+    http_client: HttpClient,
+    server: Mutex<Option<LocalServer>>,
+}
+
+/// A running llama.cpp server
+#[cfg(feature = "local-llm")]
+struct LocalServer {
+    /// Server process, killed when dropped
+    child: Child,
+
+    /// Base URL of the server
+    api_base: String,
+}
+
+/// Parse an optional numeric provider option
+fn parse_option<T: std::str::FromStr>(config: &ProviderConfig, key: &str) -> Result<Option<T>> {
+    config.options.get(key)
+        .map(|value| value.parse::<T>().map_err(|_| anyhow!("Invalid value for option '{}': {}", key, value)))
+        .transpose()
+}
+
+/// Find an executable by path or on PATH
+#[cfg(feature = "local-llm")]
+fn find_executable(name: &str) -> Option<PathBuf> {
+    let path = Path::new(name);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+
+    std::env::var_os("PATH").and_then(|paths| {
+        std::env::split_paths(&paths)
+            .flat_map(|dir| [dir.join(name), dir.join(format!("{}.exe", name))])
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+#[cfg(feature = "local-llm")]
+impl LocalClient {
+    /// Create a new local client
+    pub fn new(config: &ProviderConfig) -> Result<Self> {
+        let server_binary = config.options.get("server_binary").cloned()
+            .or_else(|| std::env::var("QITOPS_LLAMA_SERVER").ok())
+            .unwrap_or_else(|| "llama-server".to_string());
+
+        Ok(Self {
+            model_path: PathBuf::from(&config.default_model),
+            server_binary,
+            context_size: parse_option(config, "context_size")?.unwrap_or(4096),
+            threads: parse_option(config, "threads")?,
+            gpu_layers: parse_option(config, "gpu_layers")?,
+            startup_timeout: Duration::from_secs(parse_option(config, "startup_timeout_secs")?.unwrap_or(120)),
+            http_client: http_client(config)?,
+            server: Mutex::new(None),
+        })
+    }
+
+    /// Start the server if it isn't running and return its base URL
+    ///
+    /// Loading a model can take a while, so the server is started on the first request
+    /// and reused for the lifetime of the client.
+    async fn ensure_server(&self) -> Result<String> {
+        let mut server = self.server.lock().await;
+
+        if let Some(running) = server.as_mut() {
+            match running.child.try_wait() {
+                Ok(None) => return Ok(running.api_base.clone()),
+                Ok(Some(status)) => tracing::warn!("Local model server exited ({}), restarting", status),
+                Err(e) => tracing::warn!("Failed to check local model server: {}, restarting", e),
+            }
+        }
+
+        let binary = find_executable(&self.server_binary)
+            .ok_or_else(|| anyhow!("llama.cpp server not found: {} (install llama.cpp or set the server_binary option)", self.server_binary))?;
+
+        // Let the OS pick a free port
+        let port = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+        let api_base = format!("http://127.0.0.1:{}", port);
+
+        let mut command = Command::new(binary);
+        command
+            .arg("--model").arg(&self.model_path)
+            .args(["--ctx-size", &self.context_size.to_string()])
+            .args(["--host", "127.0.0.1", "--port", &port.to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+        if let Some(threads) = self.threads {
+            command.args(["--threads", &threads.to_string()]);
+        }
+        if let Some(gpu_layers) = self.gpu_layers {
+            command.args(["--n-gpu-layers", &gpu_layers.to_string()]);
+        }
+
+        tracing::info!("Starting local model server for {}", self.model_path.display());
+        let mut child = command.spawn()
+            .map_err(|e| anyhow!("Failed to start llama.cpp server: {}", e))?;
+
+        // The health endpoint returns 503 until the model is loaded
+        let started = Instant::now();
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Err(anyhow!("llama.cpp server exited while loading {} ({})", self.model_path.display(), status));
+            }
+
+            if let Ok(response) = self.http_client.get(format!("{}/health", api_base)).send().await
+                && response.status().is_success()
+            {
+                break;
+            }
+
+            if started.elapsed() > self.startup_timeout {
+                return Err(anyhow!("Timed out after {:?} waiting for the local model to load", self.startup_timeout));
+            }
+
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+
+        tracing::info!("Local model loaded in {:?}", started.elapsed());
+        *server = Some(LocalServer { child, api_base: api_base.clone() });
+
+        Ok(api_base)
+    }
+
+    /// Build the chat completion request
+    fn build_request(&self, request: &LlmRequest) -> serde_json::Value {
+        let messages: Vec<serde_json::Value> = request.messages.iter().map(|msg| {
+            json!({
+                "role": msg.role.to_string(),
+                "content": msg.content
+            })
+        }).collect();
+
+        let mut body = json!({
+            "messages": messages,
+            "max_tokens": request.max_tokens,
+            "temperature": request.temperature,
+            "top_p": request.top_p,
+        });
+
+        if !request.stop.is_empty() {
+            body["stop"] = json!(request.stop);
+        }
+
+        if let Some(seed) = request.seed {
+            body["seed"] = json!(seed);
+        }
+
+        for (key, value) in &request.options {
+            body[key] = value.clone();
+        }
+
+        body
+    }
+}
+
+#[cfg(feature = "local-llm")]
+#[async_trait]
+impl LlmClient for LocalClient {
+    async fn send(&self, request: LlmRequest) -> Result<LlmResponse> {
+        let api_base = self.ensure_server().await?;
+        let body = self.build_request(&request);
+
+        // llama.cpp serves an OpenAI-compatible API
+        let response = self.http_client.post(format!("{}/v1/chat/completions", api_base))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| request_error("local model server", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "Could not read error response".to_string());
+
+            return Err(status_error("Local model server", status, error_text));
+        }
+
+        let response_json: serde_json::Value = response.json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse local model server response: {}", e))?;
+
+        let content = response_json["choices"][0]["message"]["content"].as_str()
+            .ok_or_else(|| anyhow!("Invalid response format: missing message content"))?;
+
+        let mut llm_response = LlmResponse::new(
+            content.to_string(),
+            self.model_path.to_string_lossy().to_string(),
+            self.name().to_string()
+        );
+
+        if let Some(tokens) = response_json["usage"]["total_tokens"].as_u64() {
+            llm_response = llm_response.with_tokens(tokens as usize);
+        }
+
+        Ok(llm_response)
+    }
+
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    async fn is_available(&self) -> bool {
+        // Don't load the model just to check availability
+        self.model_path.is_file() && find_executable(&self.server_binary).is_some()
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>> {
+        // The GGUF files next to the configured model
+        let dir = self.model_path.parent().unwrap_or_else(|| Path::new("."));
+        let entries = std::fs::read_dir(dir)
+            .map_err(|e| anyhow!("Failed to read model directory {}: {}", dir.display(), e))?;
+
+        let mut models: Vec<String> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gguf")))
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        models.sort();
+        Ok(models)
+    }
+}
+
+/// Whether the mock provider answers from its fixtures or records them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockMode {
+    /// Answer with recorded responses; requests without one fail
+    Replay,
+    /// Send requests to another provider and save its responses as fixtures
+    Record,
+}
+
+/// A recorded response, saved as `<key>.json` in the fixtures directory
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MockFixture {
+    /// Key of the request, see [`MockClient::fixture_key`]
+    pub key: String,
+
+    /// Messages of the recorded request, to tell fixtures apart when reviewing them
+    #[serde(default)]
+    pub messages: Vec<crate::llm::client::ChatMessage>,
+
+    /// Response text
+    pub response: String,
+
+    /// Tool calls in the response
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+}
+
+/// Mock client that replays canned responses from fixture files, or records them from another provider
+///
+/// Requests are matched on their messages, JSON mode and tools, not the model, so recorded
+/// fixtures replay under any configured model.
+pub struct MockClient {
+    fixtures: PathBuf,
+    mode: MockMode,
+    upstream: Option<Arc<dyn LlmClient>>,
+
+    /// Model of the provider recorded from, used instead of the mock's model
+    upstream_model: Option<String>,
+}
+
+impl MockClient {
+    /// Create a mock client from the `fixtures`, `mode` and `record_from` options
+    pub fn new(config: &ProviderConfig) -> Result<Self> {
+        let fixtures = match config.options.get("fixtures") {
+            Some(dir) => PathBuf::from(dir),
+            None => crate::config::config_dir()?.join("mock"),
+        };
+
+        match config.options.get("mode").map(String::as_str) {
+            None | Some("replay") => Ok(Self::replaying(fixtures)),
+            Some("record") => {
+                let source = config.options.get("record_from")
+                    .ok_or_else(|| anyhow!("The mock provider needs the record_from option in record mode"))?;
+                if source == "mock" {
+                    return Err(anyhow!("The mock provider can't record from itself"));
+                }
+
+                let llm_config = crate::llm::ConfigManager::new()?.get_config().clone();
+                let source_config = llm_config.providers.iter()
+                    .find(|p| p.provider_type == *source)
+                    .ok_or_else(|| anyhow!("Provider '{}' to record from is not configured", source))?;
+                let upstream = client_for(source_config)
+                    .ok_or_else(|| anyhow!("Unknown provider type to record from: {}", source))??;
+
+                Ok(Self { upstream_model: Some(source_config.default_model.clone()), ..Self::recording(fixtures, upstream) })
+            },
+            Some(mode) => Err(anyhow!("Invalid mock mode '{}': use replay or record", mode)),
+        }
+    }
+
+    /// Mock client answering from the fixtures in a directory
+    pub fn replaying(fixtures: PathBuf) -> Self {
+        Self { fixtures, mode: MockMode::Replay, upstream: None, upstream_model: None }
+    }
+
+    /// Mock client sending requests to `upstream` and saving its responses in a directory
+    pub fn recording(fixtures: PathBuf, upstream: Arc<dyn LlmClient>) -> Self {
+        Self { fixtures, mode: MockMode::Record, upstream: Some(upstream), upstream_model: None }
+    }
+
+    /// Whether the client replays or records
+    pub fn mode(&self) -> MockMode {
+        self.mode
+    }
+
+    /// Key a request is recorded under
+    pub fn fixture_key(request: &LlmRequest) -> String {
+        let mut key = format!("json={}", request.json_mode);
+        for message in &request.messages {
+            key.push_str(&format!("-{}-{}", message.role, message.content));
+        }
+        for tool in &request.tools {
+            key.push_str(&format!("-tool-{}", tool.name));
+        }
+
+        // FNV-1a, stable across builds so fixtures stay valid
+        let hash = key.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        });
+        format!("{:016x}", hash)
+    }
+
+    /// Fixture file of a request
+    pub fn fixture_path(&self, request: &LlmRequest) -> PathBuf {
+        self.fixtures.join(format!("{}.json", Self::fixture_key(request)))
+    }
+}
+
+#[async_trait]
+impl LlmClient for MockClient {
+    async fn send(&self, request: LlmRequest) -> Result<LlmResponse> {
+        let path = self.fixture_path(&request);
+
+        if let Some(upstream) = &self.upstream {
+            let mut upstream_request = request.clone();
+            if let Some(model) = &self.upstream_model {
+                upstream_request.model = model.clone();
+            }
+
+            let response = upstream.send(upstream_request).await?;
+            let fixture = MockFixture {
+                key: Self::fixture_key(&request),
+                messages: request.messages.clone(),
+                response: response.text.clone(),
+                tool_calls: response.tool_calls.clone(),
+            };
+
+            std::fs::create_dir_all(&self.fixtures)?;
+            std::fs::write(&path, serde_json::to_string_pretty(&fixture)?)
+                .with_context(|| format!("Failed to write mock fixture {}", path.display()))?;
+            tracing::info!("Recorded mock fixture {}", path.display());
+
+            return Ok(response);
+        }
+
+        let content = std::fs::read_to_string(&path).map_err(|_| LlmError::ApiError(format!(
+            "No mock fixture for this request ({}); record one with the mock provider's mode=record option",
+            path.display()
+        )))?;
+        let fixture: MockFixture = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid mock fixture {}", path.display()))?;
+
+        Ok(LlmResponse::new(fixture.response, request.model, self.name().to_string())
+            .with_tool_calls(fixture.tool_calls))
+    }
+
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    async fn is_available(&self) -> bool {
+        match &self.upstream {
+            Some(upstream) => upstream.is_available().await,
+            None => self.fixtures.is_dir(),
+        }
+    }
 }
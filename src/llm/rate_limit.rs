@@ -0,0 +1,103 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Window that per-minute limits are measured over
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Requests-per-minute and tokens-per-minute limiter for one provider
+///
+/// Limits are enforced over a sliding one-minute window. Callers wait their turn in a FIFO
+/// queue, so a batch of concurrent requests is spread out rather than all retrying at once.
+pub struct RateLimiter {
+    /// Maximum requests per minute
+    requests_per_minute: Option<u32>,
+
+    /// Maximum tokens per minute
+    tokens_per_minute: Option<u32>,
+
+    /// Requests admitted in the current window, with their token reservations
+    window: Mutex<VecDeque<(Instant, u32)>>,
+
+    /// Held by the caller at the front of the queue while it waits for capacity
+    queue: tokio::sync::Mutex<()>,
+}
+
+impl RateLimiter {
+    /// Create a limiter, or `None` when neither limit is set
+    pub fn new(requests_per_minute: Option<u32>, tokens_per_minute: Option<u32>) -> Option<Self> {
+        let requests_per_minute = requests_per_minute.filter(|limit| *limit > 0);
+        let tokens_per_minute = tokens_per_minute.filter(|limit| *limit > 0);
+        if requests_per_minute.is_none() && tokens_per_minute.is_none() {
+            return None;
+        }
+
+        Some(Self {
+            requests_per_minute,
+            tokens_per_minute,
+            window: Mutex::new(VecDeque::new()),
+            queue: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Wait until a request using `tokens` tokens fits within the limits, then admit it
+    ///
+    /// Returns how long the caller waited. A request larger than the token limit is admitted
+    /// once the window is empty, so it is slowed down rather than blocked forever.
+    pub async fn acquire(&self, tokens: u32) -> Duration {
+        let started = Instant::now();
+        let _turn = self.queue.lock().await;
+
+        loop {
+            let wait = {
+                let mut window = self.window.lock().unwrap_or_else(|e| e.into_inner());
+                let now = Instant::now();
+                while window.front().is_some_and(|(admitted, _)| now.duration_since(*admitted) >= WINDOW) {
+                    window.pop_front();
+                }
+
+                match self.wait_time(&window, tokens, now) {
+                    None => {
+                        window.push_back((now, tokens));
+                        return started.elapsed();
+                    },
+                    Some(wait) => wait,
+                }
+            };
+
+            tracing::debug!("Rate limit reached, waiting {:?}", wait);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// How long until a request fits, or `None` if it fits now
+    fn wait_time(&self, window: &VecDeque<(Instant, u32)>, tokens: u32, now: Instant) -> Option<Duration> {
+        let expires = |admitted: Instant| WINDOW.saturating_sub(now.duration_since(admitted));
+
+        // Wait for enough of the oldest requests to leave the window
+        let mut wait = Duration::ZERO;
+        if let Some(limit) = self.requests_per_minute
+            && window.len() >= limit as usize
+        {
+            let (admitted, _) = window[window.len() - limit as usize];
+            wait = wait.max(expires(admitted));
+        }
+
+        if let Some(limit) = self.tokens_per_minute
+            && !window.is_empty()
+        {
+            let mut used: u64 = window.iter().map(|(_, t)| *t as u64).sum();
+            let needed = (tokens as u64).min(limit as u64);
+            for (admitted, reserved) in window {
+                if used + needed <= limit as u64 {
+                    break;
+                }
+                used -= *reserved as u64;
+                wait = wait.max(expires(*admitted));
+            }
+        }
+
+        (!wait.is_zero()).then_some(wait)
+    }
+}
@@ -0,0 +1,132 @@
+use anyhow::Result;
+
+use crate::llm::client::{LlmRequest, LlmRouter};
+use crate::llm::tokens::TokenCounter;
+
+/// Share of the context window an input may take before it is summarized in chunks
+///
+/// The rest is left for the prompt around the input, source context and the completion.
+pub const INPUT_SHARE: usize = 3;
+
+/// Rounds of summarizing summaries before giving up on shrinking an input further
+const MAX_ROUNDS: usize = 3;
+
+/// An input, condensed to fit a prompt if it was too large
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condensed {
+    /// The input, or the summaries of its chunks
+    pub text: String,
+
+    /// Number of chunks the input was split into; 0 if it was used as is
+    pub chunks: usize,
+}
+
+impl Condensed {
+    /// Whether the input was summarized
+    pub fn is_summarized(&self) -> bool {
+        self.chunks > 0
+    }
+}
+
+/// Split a text into chunks of at most `max_tokens`, breaking between lines
+///
+/// A chunk ends early at a line where `boundary` returns true once it is half full, so
+/// chunks of a diff tend to hold whole files. Lines longer than a chunk are split.
+pub fn split_chunks(text: &str, max_tokens: usize, counter: &TokenCounter, boundary: impl Fn(&str) -> bool) -> Vec<String> {
+    let max_tokens = max_tokens.max(1);
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    let mut chunk_tokens = 0;
+
+    for line in text.split_inclusive('\n') {
+        let tokens = counter.count(line);
+        let full = chunk_tokens + tokens > max_tokens;
+        let at_boundary = boundary(line) && chunk_tokens >= max_tokens / 2;
+        if !chunk.is_empty() && (full || at_boundary) {
+            chunks.push(std::mem::take(&mut chunk));
+            chunk_tokens = 0;
+        }
+
+        if tokens > max_tokens {
+            // Roughly three bytes per token; split on character boundaries
+            let mut rest = line;
+            while !rest.is_empty() {
+                let end = rest.floor_char_boundary((max_tokens * 3).min(rest.len())).max(rest.ceil_char_boundary(1));
+                chunks.push(rest[..end].to_string());
+                rest = &rest[end..];
+            }
+            continue;
+        }
+
+        chunk.push_str(line);
+        chunk_tokens += tokens;
+    }
+
+    if !chunk.trim().is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+impl LlmRouter {
+    /// Whether an input fits in one prompt for a task, so [`condense`](Self::condense) returns it unchanged
+    pub fn fits_prompt(&self, input: &str, task: Option<&str>) -> bool {
+        TokenCounter::for_model(&self.task_model(task)).count(input) <= self.context_window(task) / INPUT_SHARE
+    }
+
+    /// Summarize an input in chunks if it is too large for one prompt
+    ///
+    /// `what` names the input in the prompts (e.g. "diff") and `focus` says what the summaries
+    /// must keep. Inputs within the task provider's budget are returned unchanged. Larger ones
+    /// are split into chunks that are summarized concurrently; if the summaries are still too
+    /// large, they are summarized again.
+    pub async fn condense(&self, input: &str, what: &str, focus: &str, task: Option<&str>) -> Result<Condensed> {
+        if self.fits_prompt(input, task) {
+            return Ok(Condensed { text: input.to_string(), chunks: 0 });
+        }
+
+        let model = self.task_model(task);
+        let counter = TokenCounter::for_model(&model);
+        let budget = self.context_window(task) / INPUT_SHARE;
+
+        let mut text = input.to_string();
+        let mut chunks = 0;
+        for round in 0..MAX_ROUNDS {
+            let parts = split_chunks(&text, budget, &counter, |line| line.starts_with("diff --git ") || line.trim().is_empty());
+            if round == 0 {
+                chunks = parts.len();
+            }
+            tracing::info!("Summarizing the {} in {} chunk(s)", what, parts.len());
+
+            let total = parts.len();
+            let requests = parts.iter().enumerate().map(|(index, part)| {
+                let prompt = format!(
+                    "Summarize part {} of {} of a {}. Keep everything needed for {}: names of files, functions and settings, behavior changes, and anything risky or unusual. Answer with the summary only.\n\n{}",
+                    index + 1, total, what, focus, crate::prompt::wrap_untrusted(what, part)
+                );
+                LlmRequest::new(prompt, model.clone())
+                    .with_system_message(format!("You summarize a {} that is too large to review at once, without losing details that matter.", what))
+            }).collect();
+
+            let summaries = self.send_batch(requests, task, |_| {}).await
+                .into_iter()
+                .collect::<Result<Vec<_>>>()?;
+
+            text = format!(
+                "The {} was too large to include in full; these are summaries of its {} parts.\n\n{}",
+                what,
+                total,
+                summaries.iter().enumerate()
+                    .map(|(index, summary)| format!("Part {}/{}:\n{}", index + 1, total, summary.text.trim()))
+                    .collect::<Vec<_>>()
+                    .join("\n\n")
+            );
+
+            if counter.count(&text) <= budget {
+                break;
+            }
+        }
+
+        Ok(Condensed { text, chunks })
+    }
+}
@@ -0,0 +1,248 @@
+use anyhow::{Result, anyhow};
+use std::sync::OnceLock;
+use tiktoken_rs::{CoreBPE, Rank};
+
+use crate::llm::client::{ChatMessage, LlmRequest, ProviderConfig};
+
+/// Heading agents put before source content; that section is truncated first
+pub const SOURCES_MARKER: &str = "\n\nAdditional context from sources:\n";
+
+/// Context window used when the model is unknown
+pub const DEFAULT_CONTEXT_WINDOW: usize = 8_192;
+
+/// Tokens added per chat message for role and separators
+const TOKENS_PER_MESSAGE: usize = 4;
+
+/// Tokens added to every request to prime the reply
+const TOKENS_PER_REQUEST: usize = 3;
+
+/// Known context windows by model name prefix; longer prefixes must come first
+const CONTEXT_WINDOWS: &[(&str, usize)] = &[
+    ("gpt-4o", 128_000),
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4-32k", 32_768),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo-instruct", 4_096),
+    ("gpt-3.5-turbo", 16_385),
+    ("o1", 200_000),
+    ("o3", 200_000),
+    ("o4", 200_000),
+    ("claude", 200_000),
+    ("llama3.1", 128_000),
+    ("llama3.2", 128_000),
+    ("llama3", 8_192),
+    ("llama2", 4_096),
+    ("codellama", 16_384),
+    ("mixtral", 32_768),
+    ("mistral", 32_768),
+    ("qwen", 32_768),
+    ("gemma", 8_192),
+    ("phi3", 4_096),
+];
+
+/// Tokenizer encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    /// GPT-4o and o-series models
+    O200k,
+    /// GPT-4 and GPT-3.5 models
+    Cl100k,
+}
+
+/// Shared tokenizer for an encoding, loaded on first use
+fn bpe(encoding: Encoding) -> &'static CoreBPE {
+    static O200K: OnceLock<CoreBPE> = OnceLock::new();
+    static CL100K: OnceLock<CoreBPE> = OnceLock::new();
+
+    match encoding {
+        Encoding::O200k => O200K.get_or_init(|| tiktoken_rs::o200k_base().expect("o200k_base encoding is embedded")),
+        Encoding::Cl100k => CL100K.get_or_init(|| tiktoken_rs::cl100k_base().expect("cl100k_base encoding is embedded")),
+    }
+}
+
+/// Counts tokens the way a model's tokenizer does
+///
+/// OpenAI models are counted exactly. Other models are estimated with `cl100k_base` plus a
+/// 15% margin, since their tokenizers tend to produce more tokens for the same text.
+#[derive(Debug, Clone, Copy)]
+pub struct TokenCounter {
+    encoding: Encoding,
+    exact: bool,
+}
+
+impl TokenCounter {
+    /// Counter for a model
+    pub fn for_model(model: &str) -> Self {
+        let model = model.to_lowercase();
+        let o200k = ["gpt-4o", "gpt-4.1", "o1", "o3", "o4"].iter().any(|prefix| model.starts_with(prefix));
+        let openai = o200k || model.starts_with("gpt-");
+
+        Self {
+            encoding: if o200k { Encoding::O200k } else { Encoding::Cl100k },
+            exact: openai,
+        }
+    }
+
+    /// Count the tokens in a text
+    pub fn count(&self, text: &str) -> usize {
+        self.estimate(self.encode(text).len())
+    }
+
+    /// Count the tokens of a chat request's messages
+    pub fn count_messages(&self, messages: &[ChatMessage]) -> usize {
+        messages.iter()
+            .map(|message| self.count(&message.content) + TOKENS_PER_MESSAGE)
+            .sum::<usize>() + TOKENS_PER_REQUEST
+    }
+
+    fn encode(&self, text: &str) -> Vec<Rank> {
+        bpe(self.encoding).encode_with_special_tokens(text)
+    }
+
+    /// Model tokens for a number of encoded tokens
+    fn estimate(&self, tokens: usize) -> usize {
+        if self.exact { tokens } else { (tokens * 115).div_ceil(100) }
+    }
+
+    /// Encoded tokens that fit in a number of model tokens
+    fn encoded_budget(&self, max_tokens: usize) -> usize {
+        if self.exact { max_tokens } else { max_tokens * 100 / 115 }
+    }
+
+    /// Length in bytes of decoded tokens
+    fn byte_len(&self, tokens: &[Rank]) -> usize {
+        bpe(self.encoding)._decode_native_and_split(tokens.to_vec()).map(|bytes| bytes.len()).sum()
+    }
+
+    /// Cut a text's tail so it fits in a number of tokens, noting what was removed
+    fn truncate_end(&self, text: &str, max_tokens: usize) -> String {
+        let tokens = self.encode(text);
+        let keep = self.encoded_budget(max_tokens);
+        if keep >= tokens.len() {
+            return text.to_string();
+        }
+
+        let end = text.floor_char_boundary(self.byte_len(&tokens[..keep]));
+        format!(
+            "{}\n[... {} tokens truncated to fit the context window ...]",
+            &text[..end],
+            self.estimate(tokens.len() - keep)
+        )
+    }
+
+    /// Cut the middle of a text so it fits in a number of tokens, keeping the start and the
+    /// end, where instructions usually are
+    fn truncate_middle(&self, text: &str, max_tokens: usize) -> String {
+        let tokens = self.encode(text);
+        let keep = self.encoded_budget(max_tokens);
+        if keep >= tokens.len() {
+            return text.to_string();
+        }
+
+        let head = keep / 2;
+        let tail = keep - head;
+        let head_end = text.floor_char_boundary(self.byte_len(&tokens[..head]));
+        let tail_start = text.ceil_char_boundary(text.len() - self.byte_len(&tokens[tokens.len() - tail..]));
+
+        format!(
+            "{}\n[... {} tokens truncated to fit the context window ...]\n{}",
+            &text[..head_end],
+            self.estimate(tokens.len() - keep),
+            &text[tail_start..]
+        )
+    }
+}
+
+/// Context window of a model served by a provider
+///
+/// The provider's `context_window` option takes precedence, then the `context_size` the local
+/// provider loads the model with, then the known window of the model.
+pub fn context_window(provider: Option<&ProviderConfig>, model: &str) -> usize {
+    let configured = provider.and_then(|provider| {
+        ["context_window", "context_size"].iter()
+            .find_map(|key| provider.options.get(*key)?.parse().ok())
+    });
+
+    configured.unwrap_or_else(|| {
+        // Ollama tags (`llama3:8b`) and HuggingFace names (`meta-llama/Llama-3`) share the base name
+        let model = model.to_lowercase();
+        let name = model.rsplit('/').next().unwrap_or(&model);
+        CONTEXT_WINDOWS.iter()
+            .find(|(prefix, _)| name.starts_with(prefix))
+            .map(|(_, window)| *window)
+            .unwrap_or(DEFAULT_CONTEXT_WINDOW)
+    })
+}
+
+/// Result of fitting a request into a context window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextFit {
+    /// Prompt tokens after fitting
+    pub prompt_tokens: usize,
+
+    /// Prompt tokens removed
+    pub truncated_tokens: usize,
+}
+
+/// Shrink a request so its prompt and completion fit in a context window
+///
+/// Up to half of the window is kept for the completion. Source context is truncated first,
+/// then the middle of the longest message, and `max_tokens` is lowered to what is left. Fails
+/// with a clear error if the request can't be made to fit.
+pub fn fit_to_context(request: &mut LlmRequest, window: usize) -> Result<ContextFit> {
+    let counter = TokenCounter::for_model(&request.model);
+    let original = counter.count_messages(&request.messages);
+    let budget = window.saturating_sub(request.max_tokens.min(window / 2));
+
+    if original > budget {
+        // Source context is the least important part of a prompt
+        let mut excess = original - budget;
+        for message in request.messages.iter_mut().rev() {
+            if excess == 0 {
+                break;
+            }
+            let sources_start = match message.content.find(SOURCES_MARKER) {
+                Some(start) => Some(start + SOURCES_MARKER.len()),
+                None => message.content.starts_with(SOURCES_MARKER.trim_start()).then(|| SOURCES_MARKER.trim_start().len()),
+            };
+            if let Some(sources_start) = sources_start {
+                let sources = &message.content[sources_start..];
+                let tokens = counter.count(sources);
+                let keep = tokens.saturating_sub(excess + 20);
+                let truncated = counter.truncate_end(sources, keep);
+                excess = excess.saturating_sub(tokens.saturating_sub(counter.count(&truncated)));
+                message.content = format!("{}{}", &message.content[..sources_start], truncated);
+            }
+        }
+
+        // Then the middle of the longest messages
+        let mut order: Vec<usize> = (0..request.messages.len()).collect();
+        order.sort_by_key(|&index| std::cmp::Reverse(request.messages[index].content.len()));
+        for index in order {
+            let current = counter.count_messages(&request.messages);
+            if current <= budget {
+                break;
+            }
+            let message = &mut request.messages[index];
+            let tokens = counter.count(&message.content);
+            let keep = tokens.saturating_sub(current - budget + 20);
+            message.content = counter.truncate_middle(&message.content, keep);
+        }
+    }
+
+    let prompt_tokens = counter.count_messages(&request.messages);
+    if prompt_tokens > budget {
+        return Err(anyhow!(
+            "Prompt needs {} tokens but {} has a {}-token context window",
+            prompt_tokens, request.model, window
+        ));
+    }
+
+    request.max_tokens = request.max_tokens.min(window - prompt_tokens);
+
+    Ok(ContextFit {
+        prompt_tokens,
+        truncated_tokens: original.saturating_sub(prompt_tokens),
+    })
+}
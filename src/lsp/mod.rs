@@ -0,0 +1,337 @@
+use anyhow::anyhow;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+use tower_lsp::jsonrpc::{self, Result as RpcResult};
+use tower_lsp::lsp_types::*;
+use tower_lsp::{Client, LanguageServer, LspService, Server};
+
+use crate::agent::baseline::BaselineMode;
+use crate::agent::findings::Finding;
+use crate::agent::traits::Agent;
+use crate::agent::{RiskAgent, TestGenAgent};
+use crate::config::QitOpsConfigManager;
+use crate::llm::LazyRouter;
+use crate::severity::{AnnotationLevel, SeverityPolicy};
+
+/// Command that generates tests for a function: arguments are the document URI and function name
+pub const GENERATE_TESTS_COMMAND: &str = "qitops.generateTests";
+
+/// Command that assesses the risk of a document's uncommitted changes: the argument is the document URI
+pub const ASSESS_RISK_COMMAND: &str = "qitops.assessRisk";
+
+/// QitOps language server
+struct QitOpsLanguageServer {
+    /// LSP client handle
+    client: Client,
+
+    /// Open documents by URI
+    documents: Mutex<HashMap<Url, String>>,
+
+    /// Workspace root, used as the working directory for git
+    root: Mutex<Option<PathBuf>>,
+
+    /// Whether to assess risk every time a document is saved
+    risk_on_save: Mutex<bool>,
+
+    /// LLM router, created on first use so the server starts without a configured provider
+    router: LazyRouter,
+
+    /// Severity mapping for diagnostics
+    policy: SeverityPolicy,
+
+    /// Function definition pattern for the common languages
+    function_pattern: Regex,
+}
+
+/// Convert an error into a JSON-RPC error that keeps its message
+fn rpc_error(e: anyhow::Error) -> jsonrpc::Error {
+    jsonrpc::Error {
+        code: jsonrpc::ErrorCode::InternalError,
+        message: e.to_string().into(),
+        data: None,
+    }
+}
+
+/// Map an annotation level to an LSP diagnostic severity
+fn diagnostic_severity(level: AnnotationLevel) -> DiagnosticSeverity {
+    match level {
+        AnnotationLevel::Failure => DiagnosticSeverity::ERROR,
+        AnnotationLevel::Warning => DiagnosticSeverity::WARNING,
+        AnnotationLevel::Notice => DiagnosticSeverity::INFORMATION,
+    }
+}
+
+impl QitOpsLanguageServer {
+    /// Create a language server for a client connection
+    fn new(client: Client) -> Self {
+        let policy = QitOpsConfigManager::new()
+            .map(|manager| manager.get_config().severity.clone())
+            .unwrap_or_default();
+
+        Self {
+            client,
+            documents: Mutex::new(HashMap::new()),
+            root: Mutex::new(None),
+            risk_on_save: Mutex::new(true),
+            router: LazyRouter::new(),
+            policy,
+            function_pattern: Regex::new(
+                r"^\s*(?:export\s+)?(?:pub(?:\([^)]*\))?\s+)?(?:async\s+)?(?:fn|def|function|func)\s+(?:\([^)]*\)\s*)?([A-Za-z_][A-Za-z0-9_]*)"
+            ).expect("valid function pattern"),
+        }
+    }
+
+    /// Find the name of the function enclosing a line
+    fn enclosing_function(&self, text: &str, line: u32) -> Option<String> {
+        let lines: Vec<&str> = text.lines().collect();
+        let start = (line as usize).min(lines.len().checked_sub(1)?);
+
+        lines[..=start].iter().rev()
+            .find_map(|l| self.function_pattern.captures(l))
+            .and_then(|captures| captures.get(1))
+            .map(|name| name.as_str().to_string())
+    }
+
+    /// Get the uncommitted diff for a file
+    async fn file_diff(&self, path: &Path) -> anyhow::Result<String> {
+        let root = self.root.lock().await.clone()
+            .or_else(|| path.parent().map(Path::to_path_buf))
+            .unwrap_or_else(|| PathBuf::from("."));
+
+        let output = tokio::process::Command::new("git")
+            .args(["diff", "HEAD", "--"])
+            .arg(path)
+            .current_dir(&root)
+            .output()
+            .await
+            .map_err(|e| anyhow!("Failed to run git diff: {}", e))?;
+
+        if !output.status.success() {
+            return Err(anyhow!("git diff failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Build a diagnostic for a finding in a document
+    fn diagnostic(&self, finding: &Finding, text: Option<&str>) -> Diagnostic {
+        let line = finding.line.unwrap_or(1).saturating_sub(1);
+        let length = text
+            .and_then(|text| text.lines().nth(line as usize))
+            .map(|l| l.encode_utf16().count() as u32)
+            .unwrap_or(0);
+
+        let message = if finding.description.is_empty() {
+            finding.title.clone()
+        } else {
+            format!("{}\n{}", finding.title, finding.description)
+        };
+
+        Diagnostic {
+            range: Range::new(Position::new(line, 0), Position::new(line, length)),
+            severity: Some(diagnostic_severity(self.policy.annotation_level(finding.severity))),
+            code: Some(NumberOrString::String(finding.fingerprint())),
+            source: Some("qitops".to_string()),
+            message,
+            ..Default::default()
+        }
+    }
+
+    /// Assess the risk of a document's uncommitted changes and publish the findings as diagnostics
+    async fn assess_risk(&self, uri: Url) -> anyhow::Result<usize> {
+        let path = uri.to_file_path().map_err(|_| anyhow!("Not a file URI: {}", uri))?;
+        let diff = self.file_diff(&path).await?;
+
+        if diff.trim().is_empty() {
+            self.client.publish_diagnostics(uri, Vec::new(), None).await;
+            return Ok(0);
+        }
+
+        // The risk agent reads diffs from disk
+        let diff_path = std::env::temp_dir().join(format!("qitops-lsp-{}.diff", std::process::id()));
+        tokio::fs::write(&diff_path, &diff).await?;
+
+        let agent = RiskAgent::new_from_diff(diff_path.to_string_lossy().to_string(), Vec::new(), Vec::new(), self.router.get().await?)
+            .await?
+            .with_baseline(BaselineMode::Use);
+        let result = agent.execute().await?;
+
+        let findings: Vec<Finding> = result.data.as_ref()
+            .and_then(|data| data.get("findings"))
+            .and_then(|findings| serde_json::from_value(findings.clone()).ok())
+            .unwrap_or_default();
+
+        // Findings use repository-relative paths
+        let documents = self.documents.lock().await;
+        let text = documents.get(&uri).map(String::as_str);
+        let diagnostics: Vec<Diagnostic> = findings.iter()
+            .filter(|finding| finding.file.as_deref().is_none_or(|file| path.ends_with(file)))
+            .map(|finding| self.diagnostic(finding, text))
+            .collect();
+        drop(documents);
+
+        let count = diagnostics.len();
+        self.client.publish_diagnostics(uri, diagnostics, None).await;
+
+        Ok(count)
+    }
+
+    /// Generate tests for a function and open the result
+    async fn generate_tests(&self, uri: Url, function: Option<String>) -> anyhow::Result<String> {
+        let path = uri.to_file_path().map_err(|_| anyhow!("Not a file URI: {}", uri))?;
+
+        let agent = TestGenAgent::new(path.to_string_lossy().to_string(), "markdown", None, None, self.router.get().await?)
+            .await?
+            .with_function(function);
+        let result = agent.execute().await?;
+
+        result.data.as_ref()
+            .and_then(|data| data.get("output_file"))
+            .and_then(|file| file.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("{}", result.message))
+    }
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for QitOpsLanguageServer {
+    async fn initialize(&self, params: InitializeParams) -> RpcResult<InitializeResult> {
+        #[allow(deprecated)]
+        let root = params.workspace_folders
+            .and_then(|folders| folders.first().and_then(|folder| folder.uri.to_file_path().ok()))
+            .or_else(|| params.root_uri.and_then(|uri| uri.to_file_path().ok()));
+        *self.root.lock().await = root;
+
+        // Editors can pass {"riskOnSave": false} to only assess risk on demand
+        if let Some(risk_on_save) = params.initialization_options
+            .as_ref()
+            .and_then(|options| options.get("riskOnSave"))
+            .and_then(Value::as_bool)
+        {
+            *self.risk_on_save.lock().await = risk_on_save;
+        }
+
+        Ok(InitializeResult {
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Options(TextDocumentSyncOptions {
+                    open_close: Some(true),
+                    change: Some(TextDocumentSyncKind::FULL),
+                    save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions { include_text: Some(false) })),
+                    ..Default::default()
+                })),
+                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                execute_command_provider: Some(ExecuteCommandOptions {
+                    commands: vec![GENERATE_TESTS_COMMAND.to_string(), ASSESS_RISK_COMMAND.to_string()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            server_info: Some(ServerInfo {
+                name: "qitops".to_string(),
+                version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            }),
+        })
+    }
+
+    async fn initialized(&self, _: InitializedParams) {
+        self.client.log_message(MessageType::INFO, "QitOps language server initialized").await;
+    }
+
+    async fn shutdown(&self) -> RpcResult<()> {
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        self.documents.lock().await.insert(params.text_document.uri, params.text_document.text);
+    }
+
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        // Full sync: the last change holds the whole document
+        if let Some(change) = params.content_changes.into_iter().last() {
+            self.documents.lock().await.insert(params.text_document.uri, change.text);
+        }
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        self.documents.lock().await.remove(&params.text_document.uri);
+    }
+
+    async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        if !*self.risk_on_save.lock().await {
+            return;
+        }
+
+        if let Err(e) = self.assess_risk(params.text_document.uri).await {
+            self.client.log_message(MessageType::WARNING, format!("Risk assessment failed: {}", e)).await;
+        }
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> RpcResult<Option<CodeActionResponse>> {
+        let uri = params.text_document.uri;
+        let mut actions = Vec::new();
+
+        let function = self.documents.lock().await
+            .get(&uri)
+            .and_then(|text| self.enclosing_function(text, params.range.start.line));
+        if let Some(function) = function {
+            actions.push(CodeActionOrCommand::Command(Command {
+                title: format!("QitOps: Generate tests for `{}`", function),
+                command: GENERATE_TESTS_COMMAND.to_string(),
+                arguments: Some(vec![Value::String(uri.to_string()), Value::String(function)]),
+            }));
+        }
+
+        actions.push(CodeActionOrCommand::Command(Command {
+            title: "QitOps: Assess risk of changes in this file".to_string(),
+            command: ASSESS_RISK_COMMAND.to_string(),
+            arguments: Some(vec![Value::String(uri.to_string())]),
+        }));
+
+        Ok(Some(actions))
+    }
+
+    async fn execute_command(&self, params: ExecuteCommandParams) -> RpcResult<Option<Value>> {
+        let uri = params.arguments.first()
+            .and_then(Value::as_str)
+            .and_then(|uri| Url::parse(uri).ok())
+            .ok_or_else(|| jsonrpc::Error::invalid_params("Expected a document URI as the first argument"))?;
+
+        match params.command.as_str() {
+            GENERATE_TESTS_COMMAND => {
+                let function = params.arguments.get(1).and_then(Value::as_str).map(str::to_string);
+                let output_file = self.generate_tests(uri, function).await.map_err(rpc_error)?;
+
+                if let Ok(output_uri) = Url::from_file_path(&output_file) {
+                    let _ = self.client.show_document(ShowDocumentParams {
+                        uri: output_uri,
+                        external: None,
+                        take_focus: Some(true),
+                        selection: None,
+                    }).await;
+                }
+                self.client.show_message(MessageType::INFO, format!("Generated tests saved to {}", output_file)).await;
+
+                Ok(Some(Value::String(output_file)))
+            },
+            ASSESS_RISK_COMMAND => {
+                let count = self.assess_risk(uri).await.map_err(rpc_error)?;
+                self.client.show_message(MessageType::INFO, format!("QitOps risk assessment: {} finding(s)", count)).await;
+
+                Ok(Some(Value::from(count)))
+            },
+            command => Err(jsonrpc::Error::invalid_params(format!("Unknown command: {}", command))),
+        }
+    }
+}
+
+/// Run the language server over stdin/stdout
+pub async fn run_stdio() -> anyhow::Result<()> {
+    let (service, socket) = LspService::new(QitOpsLanguageServer::new);
+    Server::new(tokio::io::stdin(), tokio::io::stdout(), socket).serve(service).await;
+
+    // The blocking stdin reader would keep the runtime alive until the client closes the pipe
+    std::process::exit(0)
+}
@@ -1,41 +1,134 @@
 mod agent;
 mod cli;
 mod llm;
+#[cfg(feature = "plugins")]
 mod plugin;
 mod ci;
 mod source;
 mod persona;
 mod config;
+#[cfg(feature = "bot")]
 mod bot;
+mod masking;
+mod schema;
+mod prompt;
+mod severity;
+#[cfg(feature = "lsp")]
+mod lsp;
+mod daemon;
+mod audit;
+mod integrations;
+mod notification;
+mod debug;
+mod offline;
+mod output;
+mod proxy;
+mod secrets;
+mod attest;
+mod scan;
+mod history;
+#[cfg(feature = "monitoring")]
+mod telemetry;
+mod runs;
+mod bench;
+mod checkpoint;
+mod hash;
+mod git;
+mod context;
+mod capabilities;
+mod error;
+mod i18n;
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
+use clap::{CommandFactory, FromArgMatches};
 use cli::commands::{Cli, Command, RunCommand};
 use cli::llm::handle_llm_command;
+#[cfg(feature = "github")]
 use cli::github::handle_github_command;
 use cli::source::handle_source_command;
 use cli::persona::handle_persona_command;
+#[cfg(feature = "bot")]
 use cli::bot::handle_bot_command;
+use cli::schema::handle_schema_command;
+use cli::daemon::handle_daemon_command;
+use cli::audit::handle_audit_command;
+use cli::tcm::handle_tcm_command;
+use cli::jira::handle_jira_command;
+use cli::publish::handle_publish_command;
+use cli::notify::handle_notify_command;
+use cli::prompt::handle_prompt_command;
+use cli::verify::handle_verify_command;
+use cli::history::handle_history_command;
+#[cfg(feature = "monitoring")]
+use cli::telemetry::handle_telemetry_command;
+use cli::diff_runs::handle_diff_runs_command;
+use cli::bench::handle_bench_command;
+use cli::costs::handle_costs_command;
+use cli::triage::handle_triage_command;
+use cli::digest::handle_digest_command;
+use cli::capabilities::handle_capabilities_command;
+use cli::findings::handle_findings_command;
+use cli::approvals::handle_approvals_command;
+use cli::context::handle_context_command;
 use cli::branding;
 use cli::progress::ProgressIndicator;
-use tracing::{info, error};
+use tracing::info;
 use tracing_subscriber;
 
-use agent::{TestGenAgent, PrAnalyzeAgent, RiskAgent, TestDataAgent, AgentStatus};
+use agent::{TestGenAgent, PrAnalyzeAgent, RiskAgent, TestDataAgent, TestReviewAgent, PerfGenAgent, SessionAgent, AgentStatus};
+#[cfg(feature = "github")]
+use agent::ReleaseNotesQaAgent;
 use agent::traits::Agent;
+use output::OutputFormatter;
 use llm::{ConfigManager, LlmRouter};
 use config::QitOpsConfigManager;
+#[cfg(feature = "github")]
+use ci::github::GitHubError;
+use error::QitOpsError;
+use severity::gate::{GateVerdict, QualityGate};
+use i18n::{Msg, t, tf};
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt::init();
+async fn main() {
+    if let Err(e) = run().await {
+        branding::print_error(&format!("{:#}", e));
+        if let Some(hint) = error::hint(&e) {
+            branding::print_hint(hint);
+        }
+        std::process::exit(error::exit_code(&e));
+    }
+}
 
+async fn run() -> Result<()> {
     // Parse command line arguments
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    // Servers speak a protocol on stdout, so logs go to stderr and the banner is skipped
+    #[cfg(feature = "lsp")]
+    let stdio_server = matches!(cli.command, Command::Lsp | Command::Daemon(_));
+    #[cfg(not(feature = "lsp"))]
+    let stdio_server = matches!(cli.command, Command::Daemon(_));
+
+    // Likewise for commands printing machine-readable output
+    let machine_output = stdio_server || cli.command.json_output();
+    if machine_output {
+        branding::status_to_stderr();
+    }
+
+    // Initialize logging
+    if machine_output {
+        tracing_subscriber::fmt().with_writer(std::io::stderr).init();
+    } else {
+        tracing_subscriber::fmt::init();
+    }
+
+    // Choose the message language before anything is printed
+    let configured_locale = QitOpsConfigManager::new().ok().and_then(|manager| manager.get_config().locale.clone());
+    i18n::init(configured_locale.as_deref());
 
     // Display banner (unless help or version is requested)
-    if std::env::args().len() > 1 && !std::env::args().any(|arg| arg == "-h" || arg == "--help" || arg == "-V" || arg == "--version") {
+    if !machine_output && std::env::args().len() > 1 && !std::env::args().any(|arg| arg == "-h" || arg == "--help" || arg == "-V" || arg == "--version") {
         branding::print_banner();
     }
 
@@ -44,45 +137,178 @@ async fn main() -> Result<()> {
         info!("Verbose logging enabled");
     }
 
-    // Execute the requested command
-    match cli.command {
-        Command::Run { command } => {
-            handle_run_command(command, cli.verbose).await?
+    // Offline mode is enforced where clients are created, so it covers every command
+    if cli.offline || offline::requested_by_env() {
+        offline::enable();
+        info!("Offline mode enabled");
+    }
+
+    // Secrets move to the OS keyring when config files are loaded, so decide before any are read
+    if cli.no_keyring {
+        secrets::disable();
+    }
+
+    // Execute the requested command, recording it when telemetry is on
+    let command_name = cli::commands::command_name(&matches);
+    llm::cost::set_command(&command_name);
+    #[cfg(feature = "monitoring")]
+    let started = std::time::Instant::now();
+    let result = execute(cli.command, cli.verbose, machine_output).await;
+    #[cfg(feature = "monitoring")]
+    telemetry::record(&command_name, started.elapsed(), result.as_ref().err());
+    result
+}
+
+async fn execute(command: Command, verbose: bool, machine_output: bool) -> Result<()> {
+    match command {
+        Command::Run { command, ensemble, debug_bundle, format, output } => {
+            if debug_bundle.is_some() {
+                debug::start();
+            }
+
+            let started = std::time::Instant::now();
+            let result = handle_run_command(command, verbose, ensemble, format, output).await;
+
+            // The bundle is written even when the run fails, since that's when it is needed
+            if let Some(bundle) = &debug_bundle {
+                debug::write_bundle(std::path::Path::new(bundle), result.as_ref().err())?;
+                branding::print_info(&tf(Msg::DebugBundleWritten, &[bundle]));
+            }
+            result?;
+
+            // End every run with the same at-a-glance result block
+            if !machine_output && let Some(summary) = cli::summary::take() {
+                let summary = summary.with_usage(llm::cost::process_usage()).with_duration(started.elapsed());
+                println!("\n{}", summary.render());
+            }
         }
         Command::Llm(llm_args) => {
-            branding::print_command_header("LLM Management");
+            if !machine_output {
+                branding::print_command_header(t(Msg::LlmManagement));
+            }
             handle_llm_command(&llm_args).await?
         }
+        #[cfg(feature = "github")]
         Command::GitHub(github_args) => {
-            branding::print_command_header("GitHub Integration");
+            branding::print_command_header(t(Msg::GitHubIntegration));
             handle_github_command(&github_args).await?
         }
         Command::Source(source_args) => {
-            branding::print_command_header("Source Management");
+            branding::print_command_header(t(Msg::SourceManagement));
             handle_source_command(&source_args).await?
         }
         Command::Persona(persona_args) => {
-            branding::print_command_header("Persona Management");
+            branding::print_command_header(t(Msg::PersonaManagement));
             handle_persona_command(&persona_args).await?
         }
+        Command::Schema(schema_args) => {
+            branding::print_command_header(t(Msg::SchemaRegistry));
+            handle_schema_command(&schema_args).await?
+        }
+        Command::Prompt(prompt_args) => {
+            branding::print_command_header(t(Msg::PromptTemplates));
+            handle_prompt_command(&prompt_args).await?
+        }
+        #[cfg(feature = "bot")]
         Command::Bot(bot_args) => {
             branding::print_command_header("QitOps Bot");
             handle_bot_command(&bot_args).await?
         }
+        #[cfg(feature = "lsp")]
+        Command::Lsp => {
+            lsp::run_stdio().await?
+        }
+        Command::Daemon(daemon_args) => {
+            handle_daemon_command(&daemon_args).await?
+        }
+        Command::Audit(audit_args) => {
+            if !machine_output {
+                branding::print_command_header(t(Msg::AuditLog));
+            }
+            handle_audit_command(&audit_args).await?
+        }
+        Command::Tcm(tcm_args) => {
+            branding::print_command_header(t(Msg::TestCaseManagement));
+            handle_tcm_command(&tcm_args).await?
+        }
+        Command::Jira(jira_args) => {
+            branding::print_command_header(t(Msg::JiraIntegration));
+            handle_jira_command(&jira_args).await?
+        }
+        Command::Publish(publish_args) => {
+            branding::print_command_header(t(Msg::ReportPublishing));
+            handle_publish_command(&publish_args).await?
+        }
+        Command::Notify(notify_args) => {
+            branding::print_command_header(t(Msg::Notifications));
+            handle_notify_command(&notify_args).await?
+        }
+        Command::Verify(verify_args) => {
+            branding::print_command_header(t(Msg::ResultVerification));
+            handle_verify_command(&verify_args).await?
+        }
+        Command::History(history_args) => {
+            handle_history_command(&history_args).await?
+        }
+        #[cfg(feature = "monitoring")]
+        Command::Telemetry(telemetry_args) => {
+            handle_telemetry_command(&telemetry_args).await?
+        }
+        Command::DiffRuns(diff_runs_args) => {
+            handle_diff_runs_command(&diff_runs_args).await?
+        }
+        Command::Bench(bench_args) => {
+            handle_bench_command(&bench_args).await?
+        }
+        Command::Costs(costs_args) => {
+            handle_costs_command(&costs_args).await?
+        }
+        Command::Triage(triage_args) => {
+            handle_triage_command(&triage_args).await?
+        }
+        Command::Digest(digest_args) => {
+            handle_digest_command(&digest_args).await?
+        }
+        Command::Capabilities(capabilities_args) => {
+            handle_capabilities_command(&capabilities_args).await?
+        }
+        Command::Findings(findings_args) => {
+            handle_findings_command(&findings_args).await?
+        }
+        Command::Context(context_args) => {
+            handle_context_command(&context_args).await?
+        }
+        Command::Approvals(approvals_args) => {
+            handle_approvals_command(&approvals_args).await?
+        }
         Command::Version => {
-            println!("QitOps Agent v{}", env!("CARGO_PKG_VERSION"));
-            println!("Developed by {}", env!("CARGO_PKG_AUTHORS"));
+            println!("{}", tf(Msg::VersionLine, &[&env!("CARGO_PKG_VERSION")]));
+            println!("{}", tf(Msg::DevelopedBy, &[&env!("CARGO_PKG_AUTHORS")]));
         }
     }
 
     Ok(())
 }
 
-async fn handle_run_command(command: RunCommand, verbose: bool) -> Result<()> {
+async fn handle_run_command(command: RunCommand, verbose: bool, ensemble: Option<String>, format: Option<String>, output: Option<String>) -> Result<()> {
+    let ensemble: Vec<String> = ensemble
+        .map(|e| e.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+    if ensemble.len() == 1 {
+        return Err(QitOpsError::InvalidInput("--ensemble needs at least two providers".to_string()).into());
+    }
+
+    // Check the output format before spending tokens
+    let report_output = report_output(format.as_deref(), output.clone());
+    if let Some(name) = report_output.as_ref().and_then(|o| o.formatter.as_deref()) {
+        output::FormatterRegistry::load()?.resolve(name)?;
+    }
+    let format = format.filter(|f| cli::commands::is_test_case_format(f)).unwrap_or_else(|| "markdown".to_string());
+
     match command {
-        RunCommand::TestGen { path, format, sources, personas } => {
-            branding::print_command_header("Generating Test Cases");
-            info!("Generating test cases for {} in {} format", path, format);
+        RunCommand::TestGen { path, sources, personas, watch, push_to, seed, conventions, resume, since, repo_summary } => {
+            branding::print_command_header(t(Msg::GeneratingTestCases));
+            info!("Generating test cases for {} in {} format", path.as_deref().or(watch.as_deref()).unwrap_or_default(), format);
 
             if let Some(sources) = &sources {
                 info!("Using sources: {}", sources);
@@ -92,12 +318,6 @@ async fn handle_run_command(command: RunCommand, verbose: bool) -> Result<()> {
                 info!("Using personas: {}", personas);
             }
 
-            // Initialize LLM router
-            let progress = ProgressIndicator::new("Initializing LLM router...");
-            let config_manager = ConfigManager::new()?;
-            let router = LlmRouter::new(config_manager.get_config().clone()).await?;
-            progress.finish();
-
             // Get QitOps configuration
             let qitops_config_manager = QitOpsConfigManager::new()?;
 
@@ -130,28 +350,90 @@ async fn handle_run_command(command: RunCommand, verbose: bool) -> Result<()> {
                 }
             };
 
+            check_references(sources_vec.as_deref().unwrap_or_default(), personas_vec.as_deref().unwrap_or_default())?;
+            if let Some(conventions) = &conventions
+                && !std::path::Path::new(conventions).is_file()
+            {
+                return Err(QitOpsError::InvalidInput(format!("Test conventions file not found: {}", conventions)).into());
+            }
+
+            if let Some(watch) = watch {
+                return watch_test_gen(&watch, &format, sources_vec, personas_vec, conventions, seeded_router_config(&ensemble, seed)?).await;
+            }
+            let path = path.or_else(|| since.as_ref().map(|_| ".".to_string()))
+                .ok_or_else(|| QitOpsError::InvalidInput("--path is required unless --watch or --since is given".to_string()))?;
+            let exists = std::path::Path::new(&path).exists();
+            let glob = !exists && agent::test_gen::is_glob(&path);
+            if !exists && !glob {
+                return Err(QitOpsError::InvalidInput(format!("File not found: {}", path)).into());
+            }
+            // With --since, the changed files of even a single path are generated for as a batch
+            let batch = glob || std::path::Path::new(&path).is_dir() || since.is_some();
+
+            // Check the push target before spending tokens on generation
+            let push_target = push_to.map(|target| target.parse::<integrations::tcm::TcmTarget>()).transpose()?;
+            if push_target.is_some() && batch {
+                return Err(QitOpsError::InvalidInput("--push-to needs a single source file, not a directory or glob".to_string()).into());
+            }
+
+            // For a directory or glob, --output is where each file's tests go, not a report file
+            let output_dir = output.filter(|_| batch).map(std::path::PathBuf::from);
+            let report_output = match &output_dir {
+                Some(_) => report_output.filter(|o| o.formatter.is_some()).map(|o| ReportOutput { path: None, ..o }),
+                None => report_output,
+            };
+
+            // Initialize LLM router
+            let progress = ProgressIndicator::new("Initializing LLM router...");
+            let router = LlmRouter::new(seeded_router_config(&ensemble, seed)?).await?;
+            progress.finish();
+
             // Create and execute the test generation agent
             let progress = ProgressIndicator::new("Generating test cases...");
-            let agent = TestGenAgent::new(path, &format, sources_vec, personas_vec, router).await?;
+            let source = path.clone();
+            let agent = TestGenAgent::new(path, &format, sources_vec, personas_vec, router).await?
+                .with_conventions(conventions)
+                .with_resume(resume.map(std::path::PathBuf::from))
+                .with_output_dir(output_dir)
+                .with_since(since)
+                .with_repo_summary(repo_summary)
+                .with_progress(progress.reporter());
             let result = agent.execute().await?;
             progress.finish();
 
             match result.status {
                 AgentStatus::Success => {
-                    branding::print_success(&result.message);
+                    let emitted = emit_report(report_output.as_ref(), "test-gen", &format!("Test Cases: {}", source), &result)?;
+                    if !emitted {
+                        branding::print_success(&result.message);
+                    }
                     if let Some(data) = result.data {
+                        if !emitted && let Some(results) = data.get("results") {
+                            let results: Vec<agent::test_gen::FileResult> = serde_json::from_value(results.clone()).unwrap_or_default();
+                            println!();
+                            print!("{}", agent::test_gen::results_table(&results));
+                        }
                         if let Some(test_cases) = data.get("test_cases") {
-                            println!("\nTest Cases:\n");
-                            println!("{}", test_cases);
+                            if !emitted {
+                                println!("\n{}:\n", t(Msg::TestCases));
+                                println!("{}", test_cases);
+                            }
+
+                            if let Some(target) = &push_target {
+                                push_test_cases(target, test_cases.as_str().unwrap_or_default(), &format).await?;
+                            }
                         }
                     }
                 },
                 _ => branding::print_error(&result.message),
             }
         }
-        RunCommand::PrAnalyze { pr, sources, personas } => {
-            branding::print_command_header("Analyzing Pull Request");
-            info!("Analyzing PR: {}", pr);
+        RunCommand::PrAnalyze { #[cfg(feature = "github")] pr, patch, sources, personas, baseline, annotate, jira, #[cfg(feature = "github")] full, #[cfg(feature = "github")] comment, #[cfg(feature = "github")] review, keep_noise, gate, fail_on, min_score } => {
+            branding::print_command_header(t(Msg::AnalyzingPullRequest));
+            #[cfg(feature = "github")]
+            info!("Analyzing {}", patch.as_deref().or(pr.as_deref()).unwrap_or_default());
+            #[cfg(not(feature = "github"))]
+            info!("Analyzing {}", patch.as_deref().unwrap_or_default());
 
             // Get QitOps configuration
             let qitops_config_manager = QitOpsConfigManager::new()?;
@@ -187,80 +469,139 @@ async fn handle_run_command(command: RunCommand, verbose: bool) -> Result<()> {
                 }
             };
 
-            // Get GitHub configuration
-            let github_config_manager = ci::GitHubConfigManager::new()?;
+            let sources_vec = agent::sources::SourceSelection::new(sources_vec, qitops_config_manager.get_config().sources.rules.clone());
+            check_references(&sources_vec.all_ids(), &personas_vec)?;
+
+            let quality_gate = QualityGate::from_args(gate, &fail_on, min_score)?;
+            if quality_gate.as_ref().is_some_and(QualityGate::needs_risk) {
+                return Err(QitOpsError::InvalidInput("Risk conditions such as `high-risk` only apply to `qitops run risk`".to_string()).into());
+            }
 
-            // Try to extract repository information from PR URL
-            let (owner, repo, pr_number) = match ci::GitHubClient::extract_repo_info(&pr) {
-                Ok((owner, repo)) => {
-                    // Try to extract PR number
-                    let pr_number = match ci::GitHubClient::extract_pr_number(&pr) {
-                        Ok(number) => number,
+            // Resolve the PR and create a GitHub client, unless analyzing a patch series
+            #[cfg(feature = "github")]
+            let target = match pr {
+                Some(pr) => {
+                    // Get GitHub configuration
+                    let github_config_manager = ci::GitHubConfigManager::new()?;
+
+                    // Try to extract repository information from PR URL
+                    let (owner, repo, pr_number) = match ci::GitHubClient::extract_repo_info(&pr) {
+                        Ok((owner, repo)) => {
+                            // Try to extract PR number
+                            let pr_number = match ci::GitHubClient::extract_pr_number(&pr) {
+                                Ok(number) => number,
+                                Err(_) => {
+                                    branding::print_error("Could not extract PR number from URL");
+                                    return Ok(());
+                                }
+                            };
+                            (owner, repo, pr_number.to_string())
+                        },
                         Err(_) => {
-                            branding::print_error("Could not extract PR number from URL");
-                            return Ok(());
+                            // If not a URL, use default repository and treat input as PR number
+                            let owner = github_config_manager.get_default_owner()
+                                .ok_or_else(|| GitHubError::ConfigurationError(
+                                    "default repository owner not configured; configure with: qitops github config --owner <owner>".to_string()
+                                ))?;
+
+                            let repo = github_config_manager.get_default_repo()
+                                .ok_or_else(|| GitHubError::ConfigurationError(
+                                    "default repository name not configured; configure with: qitops github config --repo <repo>".to_string()
+                                ))?;
+
+                            (owner, repo, pr.clone())
                         }
                     };
-                    (owner, repo, pr_number.to_string())
-                },
-                Err(_) => {
-                    // If not a URL, use default repository and treat input as PR number
-                    let owner = github_config_manager.get_default_owner()
-                        .ok_or_else(|| {
-                            branding::print_error("Default repository owner not configured");
-                            branding::print_info("Configure with: qitops github config --owner <owner>");
-                            anyhow::anyhow!("Default repository owner not configured")
-                        })?;
-
-                    let repo = github_config_manager.get_default_repo()
-                        .ok_or_else(|| {
-                            branding::print_error("Default repository name not configured");
-                            branding::print_info("Configure with: qitops github config --repo <repo>");
-                            anyhow::anyhow!("Default repository name not configured")
-                        })?;
-
-                    (owner, repo, pr.clone())
-                }
-            };
 
-            // Create GitHub client
-            let github_client = match ci::GitHubClient::from_config(github_config_manager.get_config()) {
-                Ok(client) => client,
-                Err(e) => {
-                    branding::print_error(&format!("Failed to create GitHub client: {}", e));
-                    branding::print_info("Configure GitHub token with: qitops github config --token <token>");
-                    return Ok(());
+                    // Create GitHub client
+                    let github_client = ci::GitHubClient::from_config(github_config_manager.get_config())
+                        .context("Failed to create GitHub client")?;
+
+                    llm::cost::set_pull_request(&owner, &repo, &pr_number);
+                    Some((owner, repo, pr_number, github_client))
                 }
+                None => None,
             };
 
             // Initialize LLM router
             let progress = ProgressIndicator::new("Initializing LLM router...");
-            let config_manager = ConfigManager::new()?;
-            let router = LlmRouter::new(config_manager.get_config().clone()).await?;
+            let router = LlmRouter::new(router_config(&ensemble)?).await?;
             progress.finish();
 
             // Create and execute the PR analysis agent
             let progress = ProgressIndicator::new("Analyzing pull request...");
-            let agent = PrAnalyzeAgent::new(pr_number, None, owner, repo, github_client, router).await?;
+            #[cfg(feature = "github")]
+            let comment_target = target.as_ref()
+                .filter(|_| comment)
+                .map(|(owner, repo, pr_number, github_client)| (owner.clone(), repo.clone(), pr_number.clone(), github_client.clone()));
+            #[cfg(feature = "github")]
+            let (source, agent) = match (target, patch) {
+                (Some((owner, repo, pr_number, github_client)), _) => (
+                    format!("{}/{}#{}", owner, repo, pr_number),
+                    PrAnalyzeAgent::new(pr_number, None, owner, repo, github_client, router).await?
+                        .with_incremental(!full)
+                        .with_review(review)
+                        .with_large_pr(qitops_config_manager.get_config().large_pr.clone()),
+                ),
+                (None, Some(patch)) => (patch.clone(), PrAnalyzeAgent::new_from_patches(patch, None, router).await?),
+                (None, None) => return Err(QitOpsError::InvalidInput("Either --pr or --patch is required".to_string()).into()),
+            };
+            #[cfg(not(feature = "github"))]
+            let (source, agent) = match patch {
+                Some(patch) => (patch.clone(), PrAnalyzeAgent::new_from_patches(patch, None, router).await?),
+                None => return Err(QitOpsError::InvalidInput("--patch is required".to_string()).into()),
+            };
+            let agent = agent.with_baseline(baseline.parse()?)
+                .with_sources(sources_vec)
+                .with_diff_filters(diff_filters(qitops_config_manager.get_config(), keep_noise)?);
             let result = agent.execute().await?;
             progress.finish();
+            let verdict = quality_gate.map(|gate| evaluate_gate(&gate, &result, &qitops_config_manager.get_config().severity));
 
             match result.status {
                 AgentStatus::Success => {
-                    branding::print_success(&result.message);
+                    let emitted = emit_report(report_output.as_ref(), "pr-analyze", &format!("PR Analysis: {}", source), &result)?;
+                    if !emitted {
+                        branding::print_success(&result.message);
+                    }
+                    #[cfg(feature = "github")]
+                    if let Some((owner, repo, pr_number, github_client)) = &comment_target {
+                        let number = ci::GitHubClient::extract_pr_number(pr_number)?;
+                        let (comment, updated) = comment_analysis(github_client, owner, repo, number, &format!("PR Analysis: {}", source), &result).await?;
+                        let message = format!("{} analysis comment {} on {}/{}#{}", if updated { "Updated" } else { "Posted" }, comment.id, owner, repo, number);
+                        if emitted {
+                            info!("{}", message);
+                        } else {
+                            branding::print_success(&message);
+                        }
+                    }
                     if let Some(data) = result.data {
-                        if let Some(analysis) = data.get("analysis") {
-                            println!("\nAnalysis:\n");
-                            println!("{}", analysis);
+                        if !emitted {
+                            print_degraded(&data);
+                            print_source_rules(&data);
+                            print_filtered(&data);
+                            print_file_analyses(&data);
+                            print_review(&data);
+                            if let Some(analysis) = data.get("analysis").and_then(|a| a.as_str()) {
+                                println!("\n{}:\n", t(Msg::Analysis));
+                                println!("{}", qitops_config_manager.get_config().report.decorate(analysis, &ci::CiContext::detect()));
+                            }
+                            print_findings(&data, &qitops_config_manager.get_config().severity, annotate);
+                        }
+                        if jira {
+                            file_jira_issues(&data, &source).await?;
                         }
                     }
                 },
                 _ => branding::print_error(&result.message),
             }
+            if let Some(verdict) = verdict {
+                enforce_gate(verdict)?;
+            }
         }
-        RunCommand::Risk { diff, components, focus, sources, personas } => {
-            branding::print_command_header("Estimating Risk");
-            info!("Estimating risk for diff: {}", diff);
+        RunCommand::Risk { diff, components, focus, sources, personas, baseline, annotate, watch, jira, publish, notify, keep_noise, gate, fail_on, min_score } => {
+            branding::print_command_header(t(Msg::EstimatingRisk));
+            info!("Estimating risk for diff: {}", diff.as_deref().or(watch.as_deref()).unwrap_or_default());
 
             // Get QitOps configuration
             let qitops_config_manager = QitOpsConfigManager::new()?;
@@ -296,6 +637,9 @@ async fn handle_run_command(command: RunCommand, verbose: bool) -> Result<()> {
                 }
             };
 
+            let sources_vec = agent::sources::SourceSelection::new(sources_vec, qitops_config_manager.get_config().sources.rules.clone());
+            check_references(&sources_vec.all_ids(), &personas_vec)?;
+
             // Parse components and focus areas
             let components = components
                 .map(|c| c.split(',').map(|s| s.trim().to_string()).collect())
@@ -313,110 +657,70 @@ async fn handle_run_command(command: RunCommand, verbose: bool) -> Result<()> {
                 info!("Focus areas: {}", focus_areas.join(", "));
             }
 
+            let qitops_config = qitops_config_manager.get_config().clone();
+            let filters = diff_filters(&qitops_config, keep_noise)?;
+            qitops_config.risk_scoring.validate()?;
+            if let Some(watch) = watch {
+                let settings = RiskSettings { components, focus_areas, sources: sources_vec, baseline: baseline.parse()?, filters };
+                return watch_risk(&watch, settings, annotate, &qitops_config, router_config(&ensemble)?).await;
+            }
+            let diff = diff.ok_or_else(|| QitOpsError::InvalidInput("--diff is required unless --watch is given".to_string()))?;
+            let source = diff.clone();
+            let quality_gate = QualityGate::from_args(gate, &fail_on, min_score)?;
+            let publish_target = publish.map(|target| target.parse::<integrations::publish::PublishTarget>()).transpose()?;
+            let notify_channel = notify.map(|channel| channel.parse::<notification::ChannelKind>()).transpose()?;
+
             // Initialize LLM router
             let progress = ProgressIndicator::new("Initializing LLM router...");
-            let config_manager = ConfigManager::new()?;
-            let router = LlmRouter::new(config_manager.get_config().clone()).await?;
+            let router = LlmRouter::new(router_config(&ensemble)?).await?;
             progress.finish();
 
-            // Check if diff is a file or a PR URL/number
-            let agent = if diff.contains("github.com") || diff.contains("/") {
-                // Try to extract repository information from PR URL
-                let github_config_manager = ci::GitHubConfigManager::new()?;
-
-                match ci::GitHubClient::extract_repo_info(&diff) {
-                    Ok((owner, repo)) => {
-                        // Try to extract PR number
-                        match ci::GitHubClient::extract_pr_number(&diff) {
-                            Ok(pr_number) => {
-                                // Create GitHub client
-                                match ci::GitHubClient::from_config(github_config_manager.get_config()) {
-                                    Ok(github_client) => {
-                                        branding::print_info(&format!("Analyzing PR #{} in {}/{}", pr_number, owner, repo));
-                                        RiskAgent::new_from_pr(
-                                            pr_number.to_string(),
-                                            components,
-                                            focus_areas,
-                                            owner,
-                                            repo,
-                                            github_client,
-                                            router
-                                        ).await?
-                                    },
-                                    Err(e) => {
-                                        branding::print_error(&format!("Failed to create GitHub client: {}", e));
-                                        branding::print_info("Using diff as a file path instead");
-                                        RiskAgent::new_from_diff(diff, components, focus_areas, router).await?
-                                    }
-                                }
-                            },
-                            Err(_) => {
-                                branding::print_error("Could not extract PR number from URL");
-                                branding::print_info("Using diff as a file path instead");
-                                RiskAgent::new_from_diff(diff, components, focus_areas, router).await?
-                            }
-                        }
-                    },
-                    Err(_) => {
-                        // If not a GitHub URL, treat as a file path
-                        RiskAgent::new_from_diff(diff, components, focus_areas, router).await?
-                    }
-                }
-            } else {
-                // Try to parse as a PR number with default repository
-                let github_config_manager = ci::GitHubConfigManager::new()?;
+            let agent = risk_agent(diff, components, focus_areas, router).await?;
 
-                if let (Some(owner), Some(repo)) = (github_config_manager.get_default_owner(), github_config_manager.get_default_repo()) {
-                    if let Ok(pr_number) = diff.parse::<u64>() {
-                        // Create GitHub client
-                        match ci::GitHubClient::from_config(github_config_manager.get_config()) {
-                            Ok(github_client) => {
-                                branding::print_info(&format!("Analyzing PR #{} in {}/{}", pr_number, owner, repo));
-                                RiskAgent::new_from_pr(
-                                    pr_number.to_string(),
-                                    components,
-                                    focus_areas,
-                                    owner,
-                                    repo,
-                                    github_client,
-                                    router
-                                ).await?
-                            },
-                            Err(_) => {
-                                branding::print_info("Using diff as a file path");
-                                RiskAgent::new_from_diff(diff, components, focus_areas, router).await?
-                            }
-                        }
-                    } else {
-                        // Not a PR number, treat as a file path
-                        RiskAgent::new_from_diff(diff, components, focus_areas, router).await?
-                    }
-                } else {
-                    // No default repository configured, treat as a file path
-                    RiskAgent::new_from_diff(diff, components, focus_areas, router).await?
-                }
-            };
+            let agent = agent.with_baseline(baseline.parse()?)
+                .with_sources(sources_vec)
+                .with_diff_filters(filters)
+                .with_scoring(qitops_config.risk_scoring.clone());
 
             // Execute the risk assessment agent
             let progress = ProgressIndicator::new("Estimating risk...");
             let result = agent.execute().await?;
             progress.finish();
+            let verdict = quality_gate.map(|gate| evaluate_gate(&gate, &result, &qitops_config.severity));
 
-            match result.status {
-                AgentStatus::Success => {
-                    branding::print_success(&result.message);
-                    if let Some(data) = result.data {
-                        if let Some(risk_assessment) = data.get("risk_assessment") {
-                            println!("\nRisk Assessment:\n");
-                            println!("{}", risk_assessment);
-                        }
+            let data = result.data.clone().filter(|_| matches!(result.status, AgentStatus::Success));
+            if data.is_none() || !emit_report(report_output.as_ref(), "risk", &format!("Risk Assessment: {}", source), &result)? {
+                print_risk_result(result, &qitops_config, annotate);
+            }
+            if let Some(data) = &data {
+                if jira {
+                    file_jira_issues(data, &source).await?;
+                }
+                if (publish_target.is_some() || notify_channel.is_some())
+                    && let Some(assessment) = data.get("assessment").and_then(|a| a.as_str())
+                {
+                    let mut body = qitops_config.report.decorate(assessment, &ci::CiContext::detect());
+                    if let Some(reason) = data.get("degraded").and_then(|d| d.as_str()) {
+                        body = format!("> **Degraded analysis:** {}\n\n{}", reason, body);
                     }
-                },
-                _ => branding::print_error(&result.message),
+                    let report = integrations::publish::Report::markdown(format!("Risk Assessment: {}", source), body);
+                    if let Some(target) = &publish_target {
+                        cli::publish::publish_report(target, &report).await?;
+                    }
+                    if let Some(channel) = notify_channel {
+                        cli::notify::send_report(channel, &report).await?;
+                    }
+                }
+            }
+            if let Some(verdict) = verdict {
+                enforce_gate(verdict)?;
+            }
+            if let Some(data) = &data {
+                enforce_risk_threshold(data)?;
             }
         }
-        RunCommand::TestData { schema, count, sources, personas } => {
-            branding::print_command_header("Generating Test Data");
+        RunCommand::TestData { schema, count, mask, sources, personas, seed } => {
+            branding::print_command_header(t(Msg::GeneratingTestData));
             info!("Generating {} test data records for schema: {}", count, schema);
 
             // Get QitOps configuration
@@ -453,24 +757,35 @@ async fn handle_run_command(command: RunCommand, verbose: bool) -> Result<()> {
                 }
             };
 
+            check_references(&sources_vec, &personas_vec)?;
+
             // Initialize LLM router
             let progress = ProgressIndicator::new("Initializing LLM router...");
-            let config_manager = ConfigManager::new()?;
-            let router = LlmRouter::new(config_manager.get_config().clone()).await?;
+            let router = LlmRouter::new(seeded_router_config(&ensemble, seed)?).await?;
             progress.finish();
 
             // Create and execute the test data generation agent
             let progress = ProgressIndicator::new("Generating test data...");
-            let agent = TestDataAgent::new(schema, count, sources_vec, "json".to_string(), router).await?;
+            let mask_fields = mask
+                .map(|m| m.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+                .unwrap_or_default();
+            let source = schema.clone();
+            let agent = TestDataAgent::new(schema, count, sources_vec, "json".to_string(), router).await?
+                .with_mask_fields(mask_fields)
+                .with_progress(progress.reporter());
             let result = agent.execute().await?;
             progress.finish();
 
             match result.status {
+                AgentStatus::Success if emit_report(report_output.as_ref(), "test-data", &format!("Test Data: {}", source), &result)? => {},
                 AgentStatus::Success => {
                     branding::print_success(&result.message);
                     if let Some(data) = result.data {
+                        if let Some(size) = data.get("masking_dictionary_size").and_then(|s| s.as_u64()) {
+                            branding::print_info(&format!("Masking dictionary now holds {} mapping(s)", size));
+                        }
                         if let Some(test_data) = data.get("test_data") {
-                            println!("\nTest Data:\n");
+                            println!("\n{}:\n", t(Msg::TestData));
                             println!("{}", test_data);
                         }
                     }
@@ -478,8 +793,171 @@ async fn handle_run_command(command: RunCommand, verbose: bool) -> Result<()> {
                 _ => branding::print_error(&result.message),
             }
         }
-        RunCommand::Session { name, sources, personas } => {
-            branding::print_command_header("Starting Interactive Testing Session");
+        RunCommand::TestReview { path, sources, personas } => {
+            branding::print_command_header(t(Msg::ReviewingExistingTests));
+            info!("Reviewing tests in {}", path);
+
+            // Get QitOps configuration
+            let qitops_config_manager = QitOpsConfigManager::new()?;
+
+            // Parse sources and personas
+            let sources_vec = if let Some(sources) = sources {
+                // Use sources from command line
+                info!("Using sources: {}", sources);
+                Some(sources.split(',').map(|s| s.trim().to_string()).collect())
+            } else {
+                // Use default sources from configuration
+                let default_sources = qitops_config_manager.get_default_sources("test-review");
+                if !default_sources.is_empty() {
+                    info!("Using default sources: {}", default_sources.join(", "));
+                    Some(default_sources)
+                } else {
+                    None
+                }
+            };
+
+            let personas_vec = if let Some(personas) = personas {
+                // Use personas from command line
+                info!("Using personas: {}", personas);
+                Some(personas.split(',').map(|s| s.trim().to_string()).collect())
+            } else {
+                // Use default personas from configuration
+                let default_personas = qitops_config_manager.get_default_personas("test-review");
+                if !default_personas.is_empty() {
+                    info!("Using default personas: {}", default_personas.join(", "));
+                    Some(default_personas)
+                } else {
+                    None
+                }
+            };
+
+            check_references(sources_vec.as_deref().unwrap_or_default(), personas_vec.as_deref().unwrap_or_default())?;
+
+            // Initialize LLM router
+            let progress = ProgressIndicator::new("Initializing LLM router...");
+            let router = LlmRouter::new(router_config(&ensemble)?).await?;
+            progress.finish();
+
+            // Create and execute the test review agent
+            let progress = ProgressIndicator::new("Reviewing tests...");
+            let source = path.clone();
+            let agent = TestReviewAgent::new(path, sources_vec, personas_vec, router).await?
+                .with_progress(progress.reporter());
+            let result = agent.execute().await?;
+            progress.finish();
+
+            match result.status {
+                AgentStatus::Success if emit_report(report_output.as_ref(), "test-review", &format!("Test Review: {}", source), &result)? => {},
+                AgentStatus::Success => {
+                    branding::print_success(&result.message);
+                    if let Some(data) = result.data {
+                        if let Some(limit) = data.get("scan_limit").and_then(|l| l.as_str()) {
+                            branding::print_warning(&tf(Msg::ScanIncomplete, &[&limit]));
+                        }
+                        if let Some(review) = data.get("review").and_then(|r| r.as_str()) {
+                            println!("\n{}:\n", t(Msg::TestReview));
+                            println!("{}", review);
+                        }
+                    }
+                },
+                _ => branding::print_error(&result.message),
+            }
+        }
+        RunCommand::PerfGen { openapi, profile, tool, data_schema, data_count } => {
+            branding::print_command_header(t(Msg::GeneratingPerformanceTests));
+            info!("Generating {} {} script for {}", profile, tool, openapi);
+
+            // Initialize LLM router
+            let progress = ProgressIndicator::new("Initializing LLM router...");
+            let router = LlmRouter::new(router_config(&ensemble)?).await?;
+            progress.finish();
+
+            // Create and execute the performance test generation agent
+            let progress = ProgressIndicator::new("Generating performance test script...");
+            let source = openapi.clone();
+            let agent = PerfGenAgent::new(openapi, &profile, &tool, data_schema, data_count, router).await?;
+            let result = agent.execute().await?;
+            progress.finish();
+
+            match result.status {
+                AgentStatus::Success if emit_report(report_output.as_ref(), "perf-gen", &format!("Performance Test: {}", source), &result)? => {},
+                AgentStatus::Success => {
+                    branding::print_success(&result.message);
+                    if let Some(data) = result.data {
+                        if let Some(data_file) = data.get("data_file").and_then(|f| f.as_str()) {
+                            branding::print_info(&format!("Data feed saved to {}", data_file));
+                        }
+                        if let Some(script) = data.get("script").and_then(|s| s.as_str()) {
+                            println!("\n{}:\n", t(Msg::Script));
+                            println!("{}", script);
+                        }
+                    }
+                },
+                _ => branding::print_error(&result.message),
+            }
+        }
+        #[cfg(feature = "github")]
+        RunCommand::ReleaseNotesQa { milestone, repo } => {
+            branding::print_command_header(t(Msg::SummarizingRelease));
+
+            let github_config_manager = ci::GitHubConfigManager::new()?;
+            let (owner, repo) = match repo {
+                Some(repo) => repo.split_once('/')
+                    .map(|(owner, repo)| (owner.to_string(), repo.to_string()))
+                    .ok_or_else(|| QitOpsError::InvalidInput(format!("--repo must be owner/repo, got {}", repo)))?,
+                None => (
+                    github_config_manager.get_default_owner().ok_or_else(|| GitHubError::ConfigurationError(
+                        "default repository owner not configured; configure with: qitops github config --owner <owner>".to_string()
+                    ))?,
+                    github_config_manager.get_default_repo().ok_or_else(|| GitHubError::ConfigurationError(
+                        "default repository name not configured; configure with: qitops github config --repo <repo>".to_string()
+                    ))?,
+                ),
+            };
+            info!("Summarizing milestone {} of {}/{}", milestone, owner, repo);
+            let github_client = ci::GitHubClient::from_config(github_config_manager.get_config())
+                .context("Failed to create GitHub client")?;
+
+            // Initialize LLM router
+            let progress = ProgressIndicator::new("Initializing LLM router...");
+            let router = LlmRouter::new(router_config(&ensemble)?).await?;
+            progress.finish();
+
+            let progress = ProgressIndicator::new("Summarizing merged pull requests...");
+            let title = format!("Release QA: {}/{} {}", owner, repo, milestone);
+            let (history, findings) = ReleaseNotesQaAgent::load_saved();
+            let agent = ReleaseNotesQaAgent::new(milestone, owner, repo, github_client, router, history, findings).await?;
+            let result = agent.execute().await?;
+            progress.finish();
+
+            match result.status {
+                AgentStatus::Success if emit_report(report_output.as_ref(), "release-notes-qa", &title, &result)? => {},
+                AgentStatus::Success => {
+                    branding::print_success(&result.message);
+                    if let Some(appendix) = result.data.as_ref().and_then(|data| data.get("appendix")).and_then(|a| a.as_str()) {
+                        println!("\n{}", appendix);
+                    }
+                },
+                _ => branding::print_error(&result.message),
+            }
+        }
+        RunCommand::Session { name, #[cfg(feature = "pairing")] host, #[cfg(feature = "pairing")] join, participant, objectives, charter, sources, personas, publish } => {
+            let participant = participant
+                .or_else(|| std::env::var("USER").ok())
+                .or_else(|| std::env::var("USERNAME").ok())
+                .unwrap_or_else(|| "Tester".to_string());
+
+            // Join a remote session instead of starting one
+            #[cfg(feature = "pairing")]
+            if let Some(url) = join {
+                branding::print_command_header(t(Msg::JoiningSession));
+                agent::pairing::join_session(&url, &participant).await?;
+                return Ok(());
+            }
+
+            let name = name.unwrap_or_default();
+            let publish_target = publish.map(|target| target.parse::<integrations::publish::PublishTarget>()).transpose()?;
+            branding::print_command_header(t(Msg::StartingSession));
             info!("Starting interactive testing session: {}", name);
 
             // Get QitOps configuration
@@ -515,8 +993,663 @@ async fn handle_run_command(command: RunCommand, verbose: bool) -> Result<()> {
                     Vec::new()
                 }
             };
-            // TODO: Implement interactive testing session
-            branding::print_info("This feature is coming soon!");
+
+            check_references(&sources_vec, &personas_vec)?;
+
+            // Initialize LLM router
+            let progress = ProgressIndicator::new("Initializing LLM router...");
+            let router = LlmRouter::new(router_config(&ensemble)?).await?;
+            progress.finish();
+
+            // Create and run the session agent
+            let objectives = objectives
+                .map(|o| o.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+                .unwrap_or_default();
+            let agent = SessionAgent::new(name, sources_vec, personas_vec, router).await?
+                .with_objectives(objectives)
+                .with_charter(charter)
+                .with_participant(participant);
+            #[cfg(feature = "pairing")]
+            let agent = agent.with_host(host);
+            let result = agent.execute().await?;
+
+            match result.status {
+                AgentStatus::Success => {
+                    branding::print_success(&result.message);
+                    let output_file = result.data.as_ref()
+                        .and_then(|data| data.get("output_file"))
+                        .and_then(|f| f.as_str());
+                    if let (Some(target), Some(output_file)) = (&publish_target, output_file) {
+                        let report = integrations::publish::Report::from_file(std::path::Path::new(output_file), None)?;
+                        cli::publish::publish_report(target, &report).await?;
+                    }
+                },
+                _ => branding::print_error(&result.message),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Where `--format` and `--output` send the result of a run
+struct ReportOutput {
+    /// Output formatter, or `None` to write the result's text as is
+    formatter: Option<String>,
+
+    /// File to write, or `None` for stdout
+    path: Option<String>,
+}
+
+/// The report output asked for on the command line, if any
+///
+/// Test case formats (`yaml`, `robot`) are handled by `test-gen` itself and only write the
+/// test cases when `--output` is given. Without `--format`, the extension of the `--output`
+/// file picks the format, and Markdown is the default.
+fn report_output(format: Option<&str>, path: Option<String>) -> Option<ReportOutput> {
+    match (format, path) {
+        (None, None) => None,
+        (Some(format), None) if cli::commands::is_test_case_format(format) => None,
+        (Some(format), path) if cli::commands::is_test_case_format(format) => Some(ReportOutput { formatter: None, path }),
+        (Some(format), path) => Some(ReportOutput { formatter: Some(output::format_name(format).to_string()), path }),
+        // Without --format, the file's extension picks it
+        (None, path) => {
+            let format = path.as_deref().and_then(output::format_for_path).unwrap_or("markdown");
+            Some(ReportOutput { formatter: Some(format.to_string()), path })
+        },
+    }
+}
+
+/// Post a PR analysis as a Markdown comment on the pull request, editing the one an earlier run posted
+///
+/// Returns the comment and whether an existing one was edited.
+#[cfg(feature = "github")]
+async fn comment_analysis(github_client: &ci::GitHubClient, owner: &str, repo: &str, number: u64, title: &str, result: &agent::AgentResponse) -> Result<(ci::PullRequestComment, bool)> {
+    let config = QitOpsConfigManager::new()?.get_config().clone();
+    let report = output::Report::from_response("pr-analyze", title, result).with_gate(&config.severity);
+    let body = output::MarkdownFormatter.format(&report)?;
+    github_client.upsert_pull_request_comment(owner, repo, number, "pr-analyze", &body).await
+}
+
+/// Save a run's result to the run history, and format and print or write it if report output was asked for
+///
+/// Returns whether the result was emitted; otherwise it should be printed as usual.
+fn emit_report(output: Option<&ReportOutput>, command: &str, title: &str, result: &agent::AgentResponse) -> Result<bool> {
+    let config = QitOpsConfigManager::new()?.get_config().clone();
+    let mut report = output::Report::from_response(command, title, result);
+    if GATED_COMMANDS.contains(&command) {
+        report = report.with_gate(&config.severity);
+    }
+
+    // Save the run for `qitops diff-runs`; a failure here never fails the run
+    let mut summary = cli::summary::RunSummary::from_report(&report);
+    let run_id = match runs::RunHistory::open().and_then(|history| history.record(audit::run_id(), &report)) {
+        Ok(id) => {
+            info!("Saved run {}", id);
+            summary = summary.with_run_id(id.clone());
+            id
+        },
+        Err(e) => {
+            tracing::debug!("Could not save run: {}", e);
+            audit::run_id().to_string()
+        },
+    };
+
+    // Track its findings for `qitops findings`, so fixed ones are marked resolved
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+    if GATED_COMMANDS.contains(&command) || !report.findings.is_empty() {
+        match runs::findings::FindingsStore::open().and_then(|mut store| store.record(&llm::cost::current_repo(), &run_id, &report, now)) {
+            Ok(outcome) => info!("Findings: {} new, {} reopened, {} resolved", outcome.new, outcome.reopened, outcome.resolved),
+            Err(e) => tracing::debug!("Could not record findings: {}", e),
+        }
+    }
+
+    // Keep risk assessments for `qitops history risk` trends
+    if command == "risk" {
+        let pr = llm::cost::current_pull_request().rsplit_once('#').and_then(|(_, number)| number.parse().ok());
+        if let Err(e) = runs::risk::RiskStore::open().and_then(|mut store| store.record(&llm::cost::current_repo(), pr, &run_id, &report, now)) {
+            tracing::debug!("Could not record the risk assessment: {}", e);
+        }
+    }
+
+    if let Some(path) = output.and_then(|output| output.path.as_deref()) {
+        summary = summary.with_output(path);
+    }
+    cli::summary::record(summary);
+
+    let Some(output) = output else {
+        return Ok(false);
+    };
+
+    let text = match &output.formatter {
+        Some(name) if name.eq_ignore_ascii_case("json") => report_json(&report, &config.signing)?,
+        Some(name) => output::FormatterRegistry::load()?.resolve(name)?.format(&report)?,
+        None => report.body.clone(),
+    };
+
+    match &output.path {
+        Some(path) => {
+            std::fs::write(path, text).with_context(|| format!("Failed to write {}", path))?;
+            branding::print_success(&format!("Output written to {}", path));
+        },
+        None => println!("{}", text),
+    }
+
+    Ok(true)
+}
+
+/// Run commands whose findings are evaluated against the severity policy
+const GATED_COMMANDS: &[&str] = &["pr-analyze", "risk"];
+
+/// The report as JSON, signed when a signing key is configured
+fn report_json(report: &output::Report, signing: &attest::SigningConfig) -> Result<String> {
+    let Some(key) = attest::SigningKey::from_config(signing)? else {
+        return output::JsonFormatter.format(report);
+    };
+
+    let mut document = serde_json::to_value(report)?;
+    attest::sign(&mut document, &key)?;
+    Ok(serde_json::to_string_pretty(&document)?)
+}
+
+/// Print new findings and a summary of baselined and suppressed ones
+fn print_findings(data: &serde_json::Value, policy: &severity::SeverityPolicy, annotate: bool) {
+    let read = |key: &str| {
+        let mut findings: Vec<agent::findings::Finding> = data.get(key)
+            .and_then(|f| serde_json::from_value(f.clone()).ok())
+            .unwrap_or_default();
+        findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+        findings
+    };
+    let findings = read("findings");
+    // Findings of an earlier analysis of the PR on files that haven't changed since
+    let carried_over = read("carried_over");
+    let count = |key: &str| data.get(key).and_then(|v| v.as_array()).map(|a| a.len()).unwrap_or(0);
+
+    if annotate {
+        let diff = data.get("diff").and_then(|d| d.as_str()).unwrap_or_default();
+        println!("\n{}:\n", t(Msg::AnnotatedDiff));
+        println!("{}", cli::annotate::render_annotated_diff(&agent::diff::parse_diff(diff), &findings));
+    } else {
+        for (heading, findings) in [(Msg::NewFindings, &findings), (Msg::CarriedOverFindings, &carried_over)] {
+            if findings.is_empty() {
+                continue;
+            }
+            println!("\n{}:\n", t(heading));
+            for finding in findings {
+                let severity = branding::colorize(&format!("[{}]", finding.severity), branding::severity_color(finding.severity));
+                let location = finding.location();
+                if location.is_empty() {
+                    println!("  {} {}", severity, finding.title);
+                } else {
+                    println!("  {} {} ({})", severity, finding.title, location);
+                }
+            }
+        }
+    }
+
+    // Surface findings as annotations when running in GitHub Actions
+    if std::env::var("GITHUB_ACTIONS").is_ok_and(|v| v == "true") {
+        for finding in findings.iter().chain(&carried_over) {
+            let level = policy.annotation_level(finding.severity).workflow_command();
+            match (&finding.file, finding.line) {
+                (Some(file), Some(line)) => println!("::{} file={},line={}::{}", level, file, line, finding.title),
+                (Some(file), None) => println!("::{} file={}::{}", level, file, finding.title),
+                _ => println!("::{}::{}", level, finding.title),
+            }
+        }
+    }
+
+    if data.get("baseline_written").and_then(|v| v.as_bool()).unwrap_or(false) {
+        branding::print_info(&format!("Wrote {} finding(s) to {}", count("baselined"), agent::baseline::BASELINE_FILE));
+        return;
+    }
+
+    if let Some(incremental) = data.get("incremental").filter(|i| !i.is_null()) {
+        branding::print_info(&format!(
+            "{} new finding(s), {} unchanged since {}, {} baselined, {} suppressed inline",
+            findings.len(), carried_over.len(), incremental["since"].as_str().map_or("", |sha| &sha[..sha.len().min(7)]),
+            count("baselined"), count("suppressed")
+        ));
+    } else {
+        branding::print_info(&format!(
+            "{} new finding(s), {} baselined, {} suppressed inline",
+            findings.len(), count("baselined"), count("suppressed")
+        ));
+    }
+
+    match policy.evaluate(findings.iter().chain(&carried_over).map(|f| f.severity)) {
+        severity::GateAction::Fail => branding::print_error(&format!("Findings at or above {} severity", policy.fail_at)),
+        severity::GateAction::Warn => branding::print_warning(&format!("Findings at or above {} severity", policy.warn_at)),
+        severity::GateAction::Pass => {},
+    }
+}
+
+/// Check a run's result against a quality gate: its new and carried-over findings, and its overall risk
+///
+/// A run that didn't succeed fails the gate, since its result can't be checked.
+fn evaluate_gate(gate: &QualityGate, result: &agent::AgentResponse, policy: &severity::SeverityPolicy) -> GateVerdict {
+    let data = match (&result.status, &result.data) {
+        (AgentStatus::Success, Some(data)) => data,
+        _ => return GateVerdict { score: 0.0, failures: vec![format!("the run did not complete: {}", result.message)] },
+    };
+
+    let severities: Vec<severity::Severity> = ["findings", "carried_over"].iter()
+        .filter_map(|key| data.get(*key).and_then(|f| serde_json::from_value::<Vec<agent::findings::Finding>>(f.clone()).ok()))
+        .flatten()
+        .map(|finding| finding.severity)
+        .collect();
+    let overall_risk = data["risk"]["overall_risk"].as_str().and_then(|risk| risk.parse().ok());
+
+    gate.evaluate(&severities, overall_risk, policy)
+}
+
+/// Report a quality gate verdict, failing the command with its own exit code if the gate failed
+fn enforce_gate(verdict: GateVerdict) -> Result<()> {
+    if !verdict.passed() {
+        return Err(QitOpsError::GateFailed(tf(Msg::GateFailed, &[&format!("{:.1}", verdict.score), &verdict.failures.join("; ")])).into());
+    }
+
+    branding::print_success(&tf(Msg::GatePassed, &[&format!("{:.1}", verdict.score)]));
+    Ok(())
+}
+
+/// Fail with the exit code of the risk score's range, if it has one
+fn enforce_risk_threshold(data: &serde_json::Value) -> Result<()> {
+    let Some(score) = data.get("score").and_then(|score| serde_json::from_value::<agent::risk_score::RiskScore>(score.clone()).ok()) else {
+        return Ok(());
+    };
+
+    match score.exit_code {
+        Some(exit_code) => Err(QitOpsError::RiskThreshold {
+            message: format!("Risk score {}/100 is {}", score.score, score.label),
+            exit_code,
+        }.into()),
+        None => Ok(()),
+    }
+}
+
+/// Print the outcome of a risk assessment
+fn print_risk_result(result: agent::AgentResponse, config: &config::QitOpsConfig, annotate: bool) {
+    match result.status {
+        AgentStatus::Success => {
+            branding::print_success(&result.message);
+            if let Some(data) = result.data {
+                print_degraded(&data);
+                print_source_rules(&data);
+                print_filtered(&data);
+                if let Some(risk_assessment) = data.get("assessment").and_then(|a| a.as_str()) {
+                    println!("\n{}:\n", t(Msg::RiskAssessment));
+                    println!("{}", config.report.decorate(risk_assessment, &ci::CiContext::detect()));
+                }
+                print_findings(&data, &config.severity, annotate);
+            }
+        },
+        _ => branding::print_error(&result.message),
+    }
+}
+
+/// Risk agent for a diff file, or for a PR given by URL or by number in the default repository
+#[cfg(feature = "github")]
+async fn risk_agent(diff: String, components: Vec<String>, focus_areas: Vec<String>, router: LlmRouter) -> Result<RiskAgent> {
+    // Check if diff is a file or a PR URL/number
+    let agent = if diff.contains("github.com") || diff.contains("/") {
+        // Try to extract repository information from PR URL
+        let github_config_manager = ci::GitHubConfigManager::new()?;
+
+        match ci::GitHubClient::extract_repo_info(&diff) {
+            Ok((owner, repo)) => {
+                // Try to extract PR number
+                match ci::GitHubClient::extract_pr_number(&diff) {
+                    Ok(pr_number) => {
+                        // Create GitHub client
+                        match ci::GitHubClient::from_config(github_config_manager.get_config()) {
+                            Ok(github_client) => {
+                                branding::print_info(&format!("Analyzing PR #{} in {}/{}", pr_number, owner, repo));
+                                llm::cost::set_pull_request(&owner, &repo, &pr_number.to_string());
+                                RiskAgent::new_from_pr(
+                                    pr_number.to_string(),
+                                    components,
+                                    focus_areas,
+                                    owner,
+                                    repo,
+                                    github_client,
+                                    router
+                                ).await?
+                            },
+                            Err(e) => {
+                                branding::print_error(&format!("Failed to create GitHub client: {}", e));
+                                branding::print_info("Using diff as a file path instead");
+                                RiskAgent::new_from_diff(diff, components, focus_areas, router).await?
+                            }
+                        }
+                    },
+                    Err(_) => {
+                        branding::print_error("Could not extract PR number from URL");
+                        branding::print_info("Using diff as a file path instead");
+                        RiskAgent::new_from_diff(diff, components, focus_areas, router).await?
+                    }
+                }
+            },
+            Err(_) => {
+                // If not a GitHub URL, treat as a file path
+                RiskAgent::new_from_diff(diff, components, focus_areas, router).await?
+            }
+        }
+    } else {
+        // Try to parse as a PR number with default repository
+        let github_config_manager = ci::GitHubConfigManager::new()?;
+
+        if let (Some(owner), Some(repo)) = (github_config_manager.get_default_owner(), github_config_manager.get_default_repo()) {
+            if let Ok(pr_number) = diff.parse::<u64>() {
+                // Create GitHub client
+                match ci::GitHubClient::from_config(github_config_manager.get_config()) {
+                    Ok(github_client) => {
+                        branding::print_info(&format!("Analyzing PR #{} in {}/{}", pr_number, owner, repo));
+                        llm::cost::set_pull_request(&owner, &repo, &pr_number.to_string());
+                        RiskAgent::new_from_pr(
+                            pr_number.to_string(),
+                            components,
+                            focus_areas,
+                            owner,
+                            repo,
+                            github_client,
+                            router
+                        ).await?
+                    },
+                    Err(_) => {
+                        branding::print_info("Using diff as a file path");
+                        RiskAgent::new_from_diff(diff, components, focus_areas, router).await?
+                    }
+                }
+            } else {
+                // Not a PR number, treat as a file path
+                RiskAgent::new_from_diff(diff, components, focus_areas, router).await?
+            }
+        } else {
+            // No default repository configured, treat as a file path
+            RiskAgent::new_from_diff(diff, components, focus_areas, router).await?
+        }
+    };
+
+    Ok(agent)
+}
+
+/// Risk agent for a diff file; assessing PRs needs the `github` feature
+#[cfg(not(feature = "github"))]
+async fn risk_agent(diff: String, components: Vec<String>, focus_areas: Vec<String>, router: LlmRouter) -> Result<RiskAgent> {
+    RiskAgent::new_from_diff(diff, components, focus_areas, router).await
+}
+
+/// Warn that an analysis worked from fallback data instead of GitHub
+fn print_degraded(data: &serde_json::Value) {
+    if let Some(reason) = data.get("degraded").and_then(|d| d.as_str()) {
+        branding::print_warning(&tf(Msg::DegradedAnalysis, &[&reason]));
+    }
+}
+
+/// Diff filters to run before analysis, unless noise is kept
+fn diff_filters(config: &config::QitOpsConfig, keep_noise: bool) -> Result<agent::diff_filter::DiffFilters> {
+    if keep_noise {
+        return Ok(agent::diff_filter::DiffFilters::none());
+    }
+    agent::diff_filter::DiffFilters::from_config(&config.diff_filters)
+}
+
+/// Say what the diff filters left out of an analysis
+fn print_filtered(data: &serde_json::Value) {
+    if let Some(report) = data.get("filtered")
+        .and_then(|f| serde_json::from_value::<agent::diff_filter::FilterReport>(f.clone()).ok())
+        && let Some(summary) = report.summary()
+    {
+        branding::print_info(&summary);
+    }
+}
+
+/// Say when a PR was too large for one prompt and was analyzed file by file
+fn print_file_analyses(data: &serde_json::Value) {
+    if let Some(analyses) = data["file_analyses"].as_array() {
+        let skipped = data["skipped_files"].as_array().map_or(0, Vec::len);
+        let mut message = format!("The pull request was too large for one prompt; {} file(s) were analyzed separately", analyses.len());
+        if skipped > 0 {
+            message.push_str(&format!(" and {} skipped (large_pr.max_files)", skipped));
+        }
+        branding::print_info(&message);
+    }
+}
+
+/// Say what review a PR analysis posted
+fn print_review(data: &serde_json::Value) {
+    let review = &data["review"];
+    if let Some(id) = review["id"].as_u64() {
+        branding::print_success(&tf(Msg::ReviewPosted, &[
+            &id, &review["comments"].as_u64().unwrap_or_default(), &review["unanchored"].as_u64().unwrap_or_default(),
+        ]));
+    }
+}
+
+/// List the sources that path rules attached to an analysis
+fn print_source_rules(data: &serde_json::Value) {
+    let rules: Vec<serde_json::Value> = data.get("source_rules")
+        .and_then(|r| r.as_array().cloned())
+        .unwrap_or_default();
+
+    for rule in rules {
+        let list = |field: &str| rule[field].as_array()
+            .map(|items| items.iter().filter_map(|item| item.as_str()).collect::<Vec<_>>().join(", "))
+            .unwrap_or_default();
+        branding::print_info(&format!(
+            "Attached {} ({} matched {})",
+            list("sources"), rule["path"].as_str().unwrap_or_default(), list("files")
+        ));
+    }
+}
+
+/// File Jira issues for the new findings of an analysis
+async fn file_jira_issues(data: &serde_json::Value, source: &str) -> Result<()> {
+    use integrations::jira;
+
+    let findings: Vec<agent::findings::Finding> = data.get("findings")
+        .and_then(|f| serde_json::from_value(f.clone()).ok())
+        .unwrap_or_default();
+
+    let config_manager = jira::JiraConfigManager::new()?;
+    let config = config_manager.get_config();
+    let client = jira::JiraClient::from_config(config)?;
+
+    let progress = ProgressIndicator::new("Filing Jira issues...");
+    let filed = jira::file_findings(&client, config, &findings, source).await;
+    progress.finish();
+    let filed = filed?;
+
+    if filed.created.is_empty() && filed.existing.is_empty() {
+        branding::print_info(&format!("No findings at or above {} severity to file in Jira", config.min_severity));
+        return Ok(());
+    }
+
+    for (key, title) in &filed.created {
+        branding::print_success(&format!("Filed {}: {}", key, title));
+    }
+    for (key, title) in &filed.existing {
+        branding::print_info(&format!("Already filed as {}: {}", key, title));
+    }
+
+    Ok(())
+}
+
+/// Push generated test cases to a test case management tool
+async fn push_test_cases(target: &integrations::tcm::TcmTarget, test_cases: &str, format: &str) -> Result<()> {
+    use integrations::tcm;
+
+    let cases = tcm::parse_test_cases(test_cases, agent::test_gen::TestFormat::from_str(format)?);
+    if cases.is_empty() {
+        branding::print_warning("No test cases found in the generated output; nothing pushed");
+        return Ok(());
+    }
+
+    let config_manager = tcm::TcmConfigManager::new()?;
+    let client = tcm::client_for(target.tool, config_manager.get_config())?;
+
+    let progress = ProgressIndicator::new(&format!("Pushing {} test cases to {}...", cases.len(), target));
+    let report = tcm::push(client.as_ref(), target, &cases).await;
+    progress.finish();
+    let report = report?;
+
+    branding::print_success(&format!("Pushed {} test cases to {}", report.created.len(), target));
+    for (id, title) in &report.created {
+        println!("  {:<12} {}", id, title);
+    }
+    if !report.skipped.is_empty() {
+        branding::print_info(&format!("Skipped {} already in {}:", report.skipped.len(), target));
+        for title in &report.skipped {
+            println!("  {}", title);
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that the sources and personas a command uses exist, before any LLM call
+fn check_references(sources: &[String], personas: &[String]) -> Result<()> {
+    if !sources.is_empty() {
+        cli::source::SourceManager::new()?.validate_ids(sources)?;
+    }
+    if !personas.is_empty() {
+        cli::persona::PersonaManager::new()?.validate_ids(personas)?;
+    }
+    Ok(())
+}
+
+/// LLM configuration, using the providers from `--ensemble` when given
+fn router_config(ensemble: &[String]) -> Result<llm::RouterConfig> {
+    let mut config = ConfigManager::new()?.get_config().clone();
+    if !ensemble.is_empty() {
+        config.ensemble = ensemble.to_vec();
+    }
+
+    Ok(config)
+}
+
+/// LLM configuration for a generation command, with `--seed` taking precedence over the configured seed
+fn seeded_router_config(ensemble: &[String], seed: Option<u64>) -> Result<llm::RouterConfig> {
+    let mut config = router_config(ensemble)?;
+    if seed.is_some() {
+        config.seed = seed;
+    }
+    if let Some(seed) = config.seed {
+        info!("Using sampling seed {}", seed);
+    }
+
+    Ok(config)
+}
+
+/// Regenerate test cases for source files as they are saved
+async fn watch_test_gen(
+    watch: &str,
+    format: &str,
+    sources: Option<Vec<String>>,
+    personas: Option<Vec<String>>,
+    conventions: Option<String>,
+    router_config: llm::RouterConfig,
+) -> Result<()> {
+    // The router is only initialized on the first change and then shared, so its response cache is reused
+    let router = llm::LazyRouter::with_config(router_config);
+    let mut watcher = cli::watch::FileWatcher::new(std::path::Path::new(watch), cli::watch::DEFAULT_DEBOUNCE)?;
+    // Test files written by this loop must not trigger another run
+    let mut generated = std::collections::HashSet::new();
+
+    branding::print_info(&tf(Msg::WatchingForChanges, &[&watch]));
+
+    while let Some(changes) = watcher.next_changes().await {
+        for path in changes {
+            if generated.contains(&path) {
+                continue;
+            }
+            let path = path.to_string_lossy().to_string();
+            branding::print_command_header(&tf(Msg::GeneratingTestCasesFor, &[&path]));
+
+            let progress = ProgressIndicator::new("Generating test cases...");
+            let result = match router.get().await {
+                Ok(router) => match TestGenAgent::new(path, format, sources.clone(), personas.clone(), router).await {
+                    Ok(agent) => agent.with_conventions(conventions.clone()).execute().await,
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            };
+            progress.finish();
+
+            match result {
+                Ok(result) => {
+                    if let Some(output_file) = result.data.as_ref().and_then(|d| d.get("output_file")).and_then(|f| f.as_str()) {
+                        generated.insert(std::path::PathBuf::from(output_file));
+                    }
+                    branding::print_success(&result.message);
+                },
+                Err(e) => branding::print_error(&format!("Test generation failed: {}", e)),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// What each risk assessment of a watch looks at
+struct RiskSettings {
+    /// Components to focus on
+    components: Vec<String>,
+
+    /// Focus areas
+    focus_areas: Vec<String>,
+
+    /// Sources to include
+    sources: agent::sources::SourceSelection,
+
+    /// Whether to read or write the findings baseline
+    baseline: agent::baseline::BaselineMode,
+
+    /// Diff filters leaving out noise
+    filters: agent::diff_filter::DiffFilters,
+}
+
+/// Re-assess the uncommitted changes under a directory whenever files are saved
+async fn watch_risk(watch: &str, settings: RiskSettings, annotate: bool, config: &config::QitOpsConfig, router_config: llm::RouterConfig) -> Result<()> {
+    // The router is only initialized on the first change and then shared, so its response cache is reused
+    let router = llm::LazyRouter::with_config(router_config);
+    let mut watcher = cli::watch::FileWatcher::new(std::path::Path::new(watch), cli::watch::DEFAULT_DEBOUNCE)?;
+    let diff_path = std::env::temp_dir().join(format!("qitops-watch-{}.diff", std::process::id()));
+
+    branding::print_info(&tf(Msg::WatchingForChanges, &[&watch]));
+
+    while let Some(changes) = watcher.next_changes().await {
+        branding::print_command_header(&tf(Msg::EstimatingRiskForChanges, &[&changes.len()]));
+
+        let output = std::process::Command::new("git")
+            .args(["diff", "HEAD", "--", watch])
+            .output()
+            .map_err(|e| anyhow::anyhow!("Failed to run git diff: {}", e))?;
+        if !output.status.success() {
+            branding::print_error(&format!("git diff failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+            continue;
+        }
+        if output.stdout.is_empty() {
+            branding::print_info(t(Msg::NoUncommittedChanges));
+            continue;
+        }
+        std::fs::write(&diff_path, &output.stdout)?;
+
+        let progress = ProgressIndicator::new("Estimating risk...");
+        let agent = RiskAgent::new_from_diff(diff_path.to_string_lossy().to_string(), settings.components.clone(), settings.focus_areas.clone(), router.get().await?)
+            .await?
+            .with_baseline(settings.baseline)
+            .with_sources(settings.sources.clone())
+            .with_diff_filters(settings.filters.clone())
+            .with_scoring(config.risk_scoring.clone());
+        let result = agent.execute().await;
+        progress.finish();
+
+        match result {
+            Ok(result) => print_risk_result(result, config, annotate),
+            Err(e) => branding::print_error(&format!("Risk assessment failed: {}", e)),
         }
     }
 
@@ -0,0 +1,262 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{Result, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Environment variable holding a base64-encoded 256-bit masking key, instead of the keyring
+pub const MASKING_KEY_ENV: &str = "QITOPS_MASKING_KEY";
+
+/// Keyring account holding the masking key
+pub const KEY_ACCOUNT: &str = "masking";
+
+/// Key file written by earlier versions next to the dictionary
+const LEGACY_KEY_FILE: &str = "masking.key";
+
+/// Masking dictionary contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct MaskingData {
+    /// Real value to fake value
+    #[serde(default)]
+    mappings: HashMap<String, String>,
+
+    /// Counter used to generate unique fake values
+    #[serde(default)]
+    next_id: u64,
+}
+
+/// Encrypted file format
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedFile {
+    /// Nonce (base64)
+    nonce: String,
+
+    /// Ciphertext (base64)
+    ciphertext: String,
+}
+
+/// Encrypt with AES-256-GCM, returning the contents of an encrypted file; `what` names it in errors
+pub fn encrypt_file(plaintext: &[u8], key: &Key<Aes256Gcm>, what: &str) -> Result<String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new(key)
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| anyhow!("Failed to encrypt {}", what))?;
+
+    Ok(serde_json::to_string_pretty(&EncryptedFile {
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    })?)
+}
+
+/// Decrypt the contents of an encrypted file; `what` names it in errors
+pub fn decrypt_file(contents: &str, key: &Key<Aes256Gcm>, what: &str) -> Result<Vec<u8>> {
+    let file: EncryptedFile = serde_json::from_str(contents)
+        .map_err(|e| anyhow!("Failed to parse {}: {}", what, e))?;
+
+    let nonce = BASE64.decode(&file.nonce)?;
+    let ciphertext = BASE64.decode(&file.ciphertext)?;
+    if nonce.len() != 12 {
+        return Err(anyhow!("Invalid nonce in {}", what));
+    }
+
+    Aes256Gcm::new(key)
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+        .map_err(|_| anyhow!("Failed to decrypt {} (wrong key?)", what))
+}
+
+/// A key from the environment variable `env`, else from the keyring `account`, if either is set
+///
+/// `what` names the key in errors, e.g. `masking`.
+pub fn stored_key(account: &str, env: &str, what: &str) -> Result<Option<Key<Aes256Gcm>>> {
+    if let Ok(encoded) = std::env::var(env) {
+        return decode_key(&encoded).map(Some).map_err(|e| anyhow!("Invalid {}: {}", env, e));
+    }
+
+    match crate::secrets::get(account) {
+        Some(encoded) => decode_key(&encoded).map(Some)
+            .map_err(|e| anyhow!("Invalid {} key in the OS keyring: {}", what, e)),
+        None => Ok(None),
+    }
+}
+
+/// Generate a key and save it to the keyring `account`
+///
+/// The key is never written to disk, so without a keyring the key has to come from `env`.
+pub fn create_key(account: &str, env: &str, what: &str) -> Result<Key<Aes256Gcm>> {
+    let key = Aes256Gcm::generate_key(OsRng);
+    crate::secrets::set(account, &BASE64.encode(key))
+        .map_err(|e| anyhow!("Cannot create a {} key: {}; set {} instead", what, e, env))?;
+
+    Ok(key)
+}
+
+/// Decode a base64-encoded 256-bit key
+pub fn decode_key(encoded: &str) -> Result<Key<Aes256Gcm>> {
+    let bytes = BASE64.decode(encoded.trim())?;
+    if bytes.len() != 32 {
+        return Err(anyhow!("Key must be 32 bytes, got {}", bytes.len()));
+    }
+
+    Ok(*Key::<Aes256Gcm>::from_slice(&bytes))
+}
+
+/// Pseudonymization dictionary shared across runs
+///
+/// The same real value always maps to the same fake value, so independently
+/// generated datasets stay referentially consistent. The dictionary is stored
+/// encrypted with AES-256-GCM in the config directory, readable by its owner only, and
+/// its key is kept in the OS keyring.
+pub struct MaskingDictionary {
+    /// Mappings
+    data: MaskingData,
+
+    /// Encryption key
+    key: Key<Aes256Gcm>,
+
+    /// Dictionary file path
+    path: PathBuf,
+
+    /// Whether the dictionary changed since it was loaded
+    dirty: bool,
+}
+
+impl MaskingDictionary {
+    /// Load the masking dictionary from the config directory
+    pub fn load() -> Result<Self> {
+        let config_dir = crate::config::config_dir()?;
+        let key = Self::load_key(&config_dir.join(LEGACY_KEY_FILE))?;
+        Self::open(config_dir.join("masking.dict"), key)
+    }
+
+    /// Open a masking dictionary at the given path with the given key
+    pub fn open(path: PathBuf, key: Key<Aes256Gcm>) -> Result<Self> {
+        let data = if path.exists() {
+            let file_str = fs::read_to_string(&path)
+                .map_err(|e| anyhow!("Failed to read masking dictionary: {}", e))?;
+            let plaintext = decrypt_file(&file_str, &key, "masking dictionary")?;
+
+            serde_json::from_slice(&plaintext)
+                .map_err(|e| anyhow!("Failed to parse masking dictionary: {}", e))?
+        } else {
+            MaskingData::default()
+        };
+
+        Ok(Self {
+            data,
+            key,
+            path,
+            dirty: false,
+        })
+    }
+
+    /// The masking key: `QITOPS_MASKING_KEY`, else the keyring entry, else a new key saved to the keyring
+    ///
+    /// The key is never stored next to the dictionary it encrypts. A key file left by earlier
+    /// versions is moved into the keyring, and only used in place while the keyring is unavailable.
+    fn load_key(legacy_key_path: &Path) -> Result<Key<Aes256Gcm>> {
+        if let Some(key) = stored_key(KEY_ACCOUNT, MASKING_KEY_ENV, "masking")? {
+            return Ok(key);
+        }
+
+        if legacy_key_path.exists() {
+            let encoded = fs::read_to_string(legacy_key_path)
+                .map_err(|e| anyhow!("Failed to read masking key: {}", e))?;
+            let key = decode_key(&encoded)?;
+            match crate::secrets::set(KEY_ACCOUNT, encoded.trim()) {
+                Ok(()) => {
+                    fs::remove_file(legacy_key_path)?;
+                    tracing::info!("Moved the masking key from {} to the OS keyring", legacy_key_path.display());
+                },
+                Err(e) => tracing::warn!("Keeping the masking key in {}: {}", legacy_key_path.display(), e),
+            }
+            return Ok(key);
+        }
+
+        create_key(KEY_ACCOUNT, MASKING_KEY_ENV, "masking")
+    }
+
+    /// Get the fake value for a real value, creating one if it hasn't been seen before
+    pub fn mask(&mut self, field: &str, value: &str) -> String {
+        if let Some(fake) = self.data.mappings.get(value) {
+            return fake.clone();
+        }
+
+        self.data.next_id += 1;
+        let fake = Self::fake_value(field, value, self.data.next_id);
+        self.data.mappings.insert(value.to_string(), fake.clone());
+        self.dirty = true;
+
+        fake
+    }
+
+    /// Generate a fake value with the same shape as the real one
+    fn fake_value(field: &str, value: &str, id: u64) -> String {
+        if value.contains('@') {
+            format!("user{}@example.com", id)
+        } else if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+            format!("{:0>width$}", id, width = value.len())
+        } else {
+            let prefix: String = field.chars()
+                .filter(|c| c.is_ascii_alphanumeric())
+                .collect();
+            let prefix = if prefix.is_empty() { "value".to_string() } else { prefix };
+            format!("{}_{}", prefix, id)
+        }
+    }
+
+    /// Mask the given fields in a JSON value, recursing into arrays and objects
+    pub fn mask_json(&mut self, value: &mut Value, fields: &[String]) {
+        match value {
+            Value::Array(items) => {
+                for item in items {
+                    self.mask_json(item, fields);
+                }
+            },
+            Value::Object(map) => {
+                for (key, item) in map.iter_mut() {
+                    if !fields.iter().any(|f| f.eq_ignore_ascii_case(key)) {
+                        self.mask_json(item, fields);
+                        continue;
+                    }
+
+                    match item {
+                        Value::String(s) => *s = self.mask(key, s),
+                        Value::Number(n) => {
+                            let fake = self.mask(key, &n.to_string());
+                            *item = fake.parse::<u64>()
+                                .map(Value::from)
+                                .unwrap_or(Value::String(fake));
+                        },
+                        _ => self.mask_json(item, fields),
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+
+    /// Number of mappings in the dictionary
+    pub fn mapping_count(&self) -> usize {
+        self.data.mappings.len()
+    }
+
+    /// Save the dictionary if it changed
+    pub fn save(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let plaintext = serde_json::to_vec(&self.data)?;
+        let file_str = encrypt_file(&plaintext, &self.key, "masking dictionary")?;
+        crate::secrets::write_private(&self.path, file_str.as_bytes())
+            .map_err(|e| anyhow!("Failed to write masking dictionary: {}", e))?;
+
+        self.dirty = false;
+        Ok(())
+    }
+}
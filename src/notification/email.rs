@@ -0,0 +1,173 @@
+use anyhow::{Result, anyhow};
+#[cfg(feature = "email")]
+use async_trait::async_trait;
+#[cfg(feature = "email")]
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart, header::ContentType};
+#[cfg(feature = "email")]
+use lettre::transport::smtp::authentication::Credentials;
+#[cfg(feature = "email")]
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "email")]
+use crate::notification::{Notification, NotificationChannel};
+
+/// Environment variable holding the SMTP password when it isn't configured
+pub const PASSWORD_ENV: &str = "QITOPS_SMTP_PASSWORD";
+
+/// How the SMTP connection is secured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Upgrade a plain connection with STARTTLS (usually port 587)
+    #[default]
+    Starttls,
+    /// Implicit TLS (usually port 465)
+    Tls,
+    /// No encryption, e.g. a local relay
+    None,
+}
+
+impl fmt::Display for SmtpSecurity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SmtpSecurity::Starttls => write!(f, "starttls"),
+            SmtpSecurity::Tls => write!(f, "tls"),
+            SmtpSecurity::None => write!(f, "none"),
+        }
+    }
+}
+
+impl FromStr for SmtpSecurity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "starttls" => Ok(SmtpSecurity::Starttls),
+            "tls" | "ssl" => Ok(SmtpSecurity::Tls),
+            "none" | "plain" => Ok(SmtpSecurity::None),
+            _ => Err(anyhow!("Unknown SMTP security: {} (expected starttls, tls or none)", s)),
+        }
+    }
+}
+
+/// Email delivery configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailConfig {
+    /// SMTP server host
+    pub host: String,
+
+    /// SMTP server port (defaults to the usual port for the security mode)
+    #[serde(default)]
+    pub port: Option<u16>,
+
+    /// Connection security
+    #[serde(default)]
+    pub security: SmtpSecurity,
+
+    /// SMTP username
+    #[serde(default)]
+    pub username: Option<String>,
+
+    /// SMTP password (falls back to `QITOPS_SMTP_PASSWORD`)
+    #[serde(default)]
+    pub password: Option<String>,
+
+    /// Sender address, e.g. `QitOps <qitops@example.com>`
+    pub from: String,
+
+    /// Recipients (the distribution list)
+    pub to: Vec<String>,
+}
+
+impl EmailConfig {
+    /// Port to connect to
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(match self.security {
+            SmtpSecurity::Starttls => 587,
+            SmtpSecurity::Tls => 465,
+            SmtpSecurity::None => 25,
+        })
+    }
+}
+
+/// Delivers notifications by email over SMTP
+#[cfg(feature = "email")]
+pub struct EmailChannel {
+    config: EmailConfig,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+#[cfg(feature = "email")]
+impl EmailChannel {
+    /// Create a channel, validating the addresses
+    pub fn new(config: EmailConfig) -> Result<Self> {
+        let from = config.from.parse()
+            .map_err(|e| anyhow!("Invalid sender address {}: {}", config.from, e))?;
+        let to = config.to.iter()
+            .map(|address| address.parse().map_err(|e| anyhow!("Invalid recipient address {}: {}", address, e)))
+            .collect::<Result<Vec<Mailbox>>>()?;
+        if to.is_empty() {
+            return Err(anyhow!("No email recipients configured"));
+        }
+
+        Ok(Self { config, from, to })
+    }
+
+    /// Build the message
+    fn message(&self, notification: &Notification) -> Result<Message> {
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(&notification.subject);
+        for recipient in &self.to {
+            builder = builder.to(recipient.clone());
+        }
+
+        let text = SinglePart::plain(notification.text.clone());
+        let message = match &notification.html {
+            Some(html) => builder.multipart(
+                MultiPart::mixed()
+                    .singlepart(text)
+                    .singlepart(Attachment::new(notification.attachment_name.clone()).body(html.clone(), ContentType::TEXT_HTML)),
+            ),
+            None => builder.singlepart(text),
+        };
+
+        message.map_err(|e| anyhow!("Failed to build email: {}", e))
+    }
+
+    /// SMTP transport for the configured server
+    fn transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>> {
+        let host = self.config.host.as_str();
+        let builder = match self.config.security {
+            SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host),
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host),
+            SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)),
+        }.map_err(|e| anyhow!("Invalid SMTP server {}: {}", host, e))?;
+
+        let mut builder = builder.port(self.config.port());
+        if let Some(username) = &self.config.username {
+            let password = self.config.password.clone()
+                .or_else(|| std::env::var(PASSWORD_ENV).ok())
+                .ok_or_else(|| anyhow!("SMTP password not configured; run `qitops notify config email --password` or set {}", PASSWORD_ENV))?;
+            builder = builder.credentials(Credentials::new(username.clone(), password));
+        }
+
+        Ok(builder.build())
+    }
+}
+
+#[cfg(feature = "email")]
+#[async_trait]
+impl NotificationChannel for EmailChannel {
+    async fn send(&self, notification: &Notification) -> Result<String> {
+        let message = self.message(notification)?;
+        self.transport()?.send(message).await
+            .map_err(|e| anyhow!("Failed to send email via {}: {}", self.config.host, e))?;
+
+        Ok(self.config.to.join(", "))
+    }
+}
@@ -0,0 +1,188 @@
+// Notification delivery
+pub mod email;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::audit::{self, AuditAction, AuditEvent};
+use crate::integrations::publish::{Report, ReportBody};
+
+pub use email::{EmailConfig, SmtpSecurity};
+#[cfg(feature = "email")]
+pub use email::EmailChannel;
+
+/// Notification channel kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelKind {
+    /// Email over SMTP
+    Email,
+}
+
+impl fmt::Display for ChannelKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChannelKind::Email => write!(f, "email"),
+        }
+    }
+}
+
+impl FromStr for ChannelKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "email" | "smtp" => Ok(ChannelKind::Email),
+            _ => Err(anyhow!("Unknown notification channel: {} (expected email)", s)),
+        }
+    }
+}
+
+/// A report to deliver
+#[derive(Debug, Clone)]
+pub struct Notification {
+    /// Subject line
+    pub subject: String,
+
+    /// Plain-text body
+    pub text: String,
+
+    /// HTML report, attached as a file
+    pub html: Option<String>,
+
+    /// File name of the attachment
+    pub attachment_name: String,
+}
+
+impl Notification {
+    /// Notification carrying a report, with the report as both the body and an HTML attachment
+    pub fn from_report(report: &Report) -> Self {
+        let text = match &report.body {
+            ReportBody::Markdown(markdown) => markdown.clone(),
+            ReportBody::Html(_) => format!("{}\n\nThe report is attached.", report.title),
+        };
+
+        Self {
+            subject: report.title.clone(),
+            text,
+            html: Some(report.html()),
+            attachment_name: report.file_name("html"),
+        }
+    }
+}
+
+/// Channel that delivers notifications
+#[async_trait]
+pub trait NotificationChannel: Send + Sync {
+    /// Deliver a notification, returning a description of the recipients
+    async fn send(&self, notification: &Notification) -> Result<String>;
+}
+
+/// Notification configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotifyConfig {
+    /// Email delivery
+    #[serde(default)]
+    pub email: Option<EmailConfig>,
+}
+
+/// Notification configuration manager, storing `notify.json` in the QitOps config directory
+pub struct NotifyConfigManager {
+    /// Configuration file path
+    config_path: PathBuf,
+
+    /// Configuration
+    config: NotifyConfig,
+}
+
+impl NotifyConfigManager {
+    /// Create a new configuration manager
+    pub fn new() -> Result<Self> {
+        let config_path = crate::config::config_dir()?.join("notify.json");
+
+        let config = if config_path.exists() {
+            let config_str = fs::read_to_string(&config_path)
+                .map_err(|e| anyhow!("Failed to read config file: {}", e))?;
+
+            serde_json::from_str(&config_str)
+                .map_err(|e| anyhow!("Failed to parse config file: {}", e))?
+        } else {
+            NotifyConfig::default()
+        };
+
+        Ok(Self {
+            config_path,
+            config,
+        })
+    }
+
+    /// Get the configuration
+    pub fn get_config(&self) -> &NotifyConfig {
+        &self.config
+    }
+
+    /// Set the email configuration
+    pub fn set_email(&mut self, config: EmailConfig) -> Result<()> {
+        self.config.email = Some(config);
+        self.save_config()
+    }
+
+    /// Save the configuration
+    pub fn save_config(&self) -> Result<()> {
+        let config_str = serde_json::to_string_pretty(&self.config)
+            .map_err(|e| anyhow!("Failed to serialize config: {}", e))?;
+
+        fs::write(&self.config_path, config_str)
+            .map_err(|e| anyhow!("Failed to write config file: {}", e))?;
+
+        Ok(())
+    }
+}
+
+/// Create a channel from configuration
+pub fn channel_for(kind: ChannelKind, config: &NotifyConfig) -> Result<Box<dyn NotificationChannel>> {
+    crate::offline::ensure_online(&format!("Sending {} notifications", kind))?;
+
+    Ok(match kind {
+        ChannelKind::Email => {
+            let email = config.email.as_ref()
+                .ok_or_else(|| anyhow!("Email delivery not configured; run `qitops notify config email`"))?;
+            email_channel(email)?
+        },
+    })
+}
+
+/// Email channel for a configuration
+#[cfg(feature = "email")]
+fn email_channel(config: &EmailConfig) -> Result<Box<dyn NotificationChannel>> {
+    Ok(Box::new(EmailChannel::new(config.clone())?))
+}
+
+/// Email channel for a configuration, in builds without the `email` feature
+#[cfg(not(feature = "email"))]
+fn email_channel(_config: &EmailConfig) -> Result<Box<dyn NotificationChannel>> {
+    Err(anyhow!("Email delivery is not built into this binary; rebuild with the `email` feature"))
+}
+
+/// Deliver a notification over a configured channel, recording it in the audit log
+pub async fn send(kind: ChannelKind, notification: &Notification) -> Result<String> {
+    let config_manager = NotifyConfigManager::new()?;
+    let channel = channel_for(kind, config_manager.get_config())?;
+
+    let result = channel.send(notification).await;
+
+    let target = match &result {
+        Ok(recipients) => format!("{}:{}", kind, recipients),
+        Err(_) => kind.to_string(),
+    };
+    let mut event = AuditEvent::new(audit::current_actor(), AuditAction::NotificationSent, target)
+        .with_outcome(&result);
+    event.details = Some(notification.subject.clone());
+    audit::record(event);
+
+    result
+}
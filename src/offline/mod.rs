@@ -0,0 +1,61 @@
+// Offline mode: nothing is sent off the machine
+
+use anyhow::Result;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::error::QitOpsError;
+
+/// Whether offline mode is on
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Turn on offline mode for the rest of the process
+pub fn enable() {
+    OFFLINE.store(true, Ordering::Relaxed);
+}
+
+/// Whether offline mode is on
+pub fn is_enabled() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Whether the `QITOPS_OFFLINE` environment variable asks for offline mode
+pub fn requested_by_env() -> bool {
+    std::env::var("QITOPS_OFFLINE")
+        .is_ok_and(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+}
+
+/// Fail if offline mode is on, naming the service that needs the network
+pub fn ensure_online(service: &str) -> Result<()> {
+    if is_enabled() {
+        return Err(QitOpsError::Offline(format!("{} is disabled in offline mode (--offline)", service)).into());
+    }
+
+    Ok(())
+}
+
+/// Fail if offline mode is on and a URL points off the machine
+pub fn ensure_local_url(service: &str, url: &str) -> Result<()> {
+    if is_enabled() && !is_local_url(url) {
+        return Err(QitOpsError::Offline(format!("{} is disabled in offline mode (--offline): {} is not on this machine", service, url)).into());
+    }
+
+    Ok(())
+}
+
+/// Whether a URL's host is a loopback address
+pub fn is_local_url(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+
+    match url.host_str() {
+        Some(host) => {
+            let host = host.trim_start_matches('[').trim_end_matches(']');
+            host.eq_ignore_ascii_case("localhost")
+                || host.to_lowercase().ends_with(".localhost")
+                || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+        },
+        None => false,
+    }
+}
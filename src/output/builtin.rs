@@ -0,0 +1,213 @@
+use anyhow::Result;
+use serde_json::json;
+
+use crate::output::{OutputFormatter, Report};
+use crate::severity::Severity;
+
+/// Escape text for XML and HTML
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Markdown, as printed by default
+pub struct MarkdownFormatter;
+
+impl OutputFormatter for MarkdownFormatter {
+    fn name(&self) -> &str {
+        "markdown"
+    }
+
+    fn description(&self) -> &str {
+        "Markdown report"
+    }
+
+    fn format(&self, report: &Report) -> Result<String> {
+        let mut markdown = format!("# {}\n\n{}\n", report.title, report.summary);
+
+        if !report.body.trim().is_empty() {
+            markdown.push_str(&format!("\n{}\n", report.body.trim()));
+        }
+
+        if !report.findings.is_empty() {
+            markdown.push_str("\n## Findings\n\n");
+            for finding in &report.findings {
+                let location = finding.location();
+                markdown.push_str(&format!("- **[{}]** {}", finding.severity, finding.title));
+                if !location.is_empty() {
+                    markdown.push_str(&format!(" (`{}`)", location));
+                }
+                if !finding.description.is_empty() {
+                    markdown.push_str(&format!(": {}", finding.description));
+                }
+                if report.is_carried_over(finding) {
+                    markdown.push_str(" _(unchanged since the last analysis)_");
+                }
+                markdown.push('\n');
+            }
+        }
+
+        Ok(markdown)
+    }
+}
+
+/// The report as JSON
+pub struct JsonFormatter;
+
+impl OutputFormatter for JsonFormatter {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn description(&self) -> &str {
+        "JSON document with the report and the agent's raw data"
+    }
+
+    fn format(&self, report: &Report) -> Result<String> {
+        Ok(serde_json::to_string_pretty(report)?)
+    }
+}
+
+/// JUnit XML, with one failing test case per finding
+pub struct JunitFormatter;
+
+impl OutputFormatter for JunitFormatter {
+    fn name(&self) -> &str {
+        "junit"
+    }
+
+    fn description(&self) -> &str {
+        "JUnit XML with a failing test case per finding"
+    }
+
+    fn format(&self, report: &Report) -> Result<String> {
+        let suite = format!("qitops.{}", report.command);
+        let mut cases = String::new();
+
+        if report.findings.is_empty() {
+            cases.push_str(&format!("    <testcase classname=\"{}\" name=\"{}\"/>\n", escape_xml(&suite), escape_xml(&report.title)));
+        }
+
+        for finding in &report.findings {
+            let classname = finding.file.clone().unwrap_or_else(|| suite.clone());
+            cases.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"{}\">\n      <failure type=\"{}\" message=\"{}\">{}</failure>\n    </testcase>\n",
+                escape_xml(&classname),
+                escape_xml(&finding.title),
+                finding.severity,
+                escape_xml(&finding.title),
+                escape_xml(format!("{}\n{}", finding.location(), finding.description).trim()),
+            ));
+        }
+
+        let tests = report.findings.len().max(1);
+        Ok(format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\">\n  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n{}  </testsuite>\n</testsuites>\n",
+            escape_xml(&report.title), tests, report.findings.len(),
+            escape_xml(&suite), tests, report.findings.len(),
+            cases,
+        ))
+    }
+}
+
+/// SARIF 2.1.0, for code scanning dashboards
+pub struct SarifFormatter;
+
+impl SarifFormatter {
+    /// SARIF result level for a severity
+    fn level(severity: Severity) -> &'static str {
+        match severity {
+            Severity::Critical | Severity::High => "error",
+            Severity::Medium => "warning",
+            Severity::Low | Severity::Info => "note",
+        }
+    }
+}
+
+impl OutputFormatter for SarifFormatter {
+    fn name(&self) -> &str {
+        "sarif"
+    }
+
+    fn description(&self) -> &str {
+        "SARIF 2.1.0 log for code scanning"
+    }
+
+    fn format(&self, report: &Report) -> Result<String> {
+        let rule_id = format!("qitops/{}", report.command);
+
+        let results: Vec<serde_json::Value> = report.findings.iter().map(|finding| {
+            let mut result = json!({
+                "ruleId": rule_id,
+                "level": Self::level(finding.severity),
+                "message": {
+                    "text": if finding.description.is_empty() {
+                        finding.title.clone()
+                    } else {
+                        format!("{}: {}", finding.title, finding.description)
+                    },
+                },
+                "partialFingerprints": { "qitops/v1": finding.fingerprint() },
+            });
+
+            if let Some(file) = &finding.file {
+                let mut location = json!({ "artifactLocation": { "uri": file } });
+                if let Some(line) = finding.line {
+                    location["region"] = json!({ "startLine": line });
+                }
+                result["locations"] = json!([{ "physicalLocation": location }]);
+            }
+
+            result
+        }).collect();
+
+        let sarif = json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "QitOps Agent",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": [{
+                            "id": rule_id,
+                            "shortDescription": { "text": report.title },
+                        }],
+                    }
+                },
+                "results": results,
+            }]
+        });
+
+        Ok(serde_json::to_string_pretty(&sarif)?)
+    }
+}
+
+/// Standalone HTML page
+pub struct HtmlFormatter;
+
+impl OutputFormatter for HtmlFormatter {
+    fn name(&self) -> &str {
+        "html"
+    }
+
+    fn description(&self) -> &str {
+        "Standalone HTML page"
+    }
+
+    fn format(&self, report: &Report) -> Result<String> {
+        let markdown = MarkdownFormatter.format(report)?;
+        let parser = pulldown_cmark::Parser::new_ext(&markdown, pulldown_cmark::Options::ENABLE_TABLES | pulldown_cmark::Options::ENABLE_STRIKETHROUGH);
+        let mut body = String::new();
+        pulldown_cmark::html::push_html(&mut body, parser);
+
+        Ok(format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+            escape_xml(&report.title),
+            body,
+        ))
+    }
+}
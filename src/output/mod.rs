@@ -0,0 +1,230 @@
+// Report output formatters
+
+mod builtin;
+#[cfg(feature = "plugins")]
+mod plugin;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub use builtin::{HtmlFormatter, JsonFormatter, JunitFormatter, MarkdownFormatter, SarifFormatter};
+#[cfg(feature = "plugins")]
+pub use plugin::{CommandFormatter, FORMATTER_COMMAND_PREFIX, plugin_dir};
+
+use crate::agent::findings::Finding;
+use crate::agent::traits::AgentResponse;
+use crate::severity::{GateAction, SeverityPolicy};
+
+/// Data fields holding the main text of each agent's result, in lookup order
+const BODY_FIELDS: &[&str] = &["test_cases", "analysis", "assessment", "review", "script", "test_data", "appendix"];
+
+/// Short names of built-in formats
+const FORMAT_ALIASES: &[(&str, &str)] = &[("md", "markdown"), ("htm", "html")];
+
+/// Built-in format for each output file extension
+const FORMAT_EXTENSIONS: &[(&str, &str)] = &[
+    ("md", "markdown"),
+    ("markdown", "markdown"),
+    ("json", "json"),
+    ("xml", "junit"),
+    ("sarif", "sarif"),
+    ("html", "html"),
+    ("htm", "html"),
+];
+
+/// The format a name refers to, resolving short names such as `md`
+pub fn format_name(name: &str) -> &str {
+    FORMAT_ALIASES.iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+        .map(|(_, format)| *format)
+        .unwrap_or(name)
+}
+
+/// The built-in format an output file's extension implies, e.g. `html` for `report.html`
+pub fn format_for_path(path: &str) -> Option<&'static str> {
+    let extension = Path::new(path).extension()?.to_str()?;
+    FORMAT_EXTENSIONS.iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(extension))
+        .map(|(_, format)| *format)
+}
+
+/// The result of a run, in a shape every formatter understands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    /// Run command that produced the report (e.g. `risk`)
+    pub command: String,
+
+    /// Report title
+    pub title: String,
+
+    /// One-line outcome
+    pub summary: String,
+
+    /// Main text of the result, as Markdown
+    pub body: String,
+
+    /// New findings
+    pub findings: Vec<Finding>,
+
+    /// Gate outcome of the findings under the severity policy, for commands that gate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gate: Option<GateAction>,
+
+    /// Everything else the agent returned
+    pub data: serde_json::Value,
+}
+
+impl Report {
+    /// Build a report from an agent response
+    pub fn from_response(command: &str, title: &str, response: &AgentResponse) -> Self {
+        let data = response.data.clone().unwrap_or(serde_json::Value::Null);
+
+        let body = BODY_FIELDS.iter()
+            .find_map(|field| data.get(*field).and_then(|value| value.as_str()))
+            .unwrap_or_default()
+            .to_string();
+
+        // Findings carried over from an earlier analysis of the same PR are still open
+        let findings = ["findings", "carried_over"].iter()
+            .filter_map(|key| data.get(*key).and_then(|findings| serde_json::from_value::<Vec<Finding>>(findings.clone()).ok()))
+            .flatten()
+            .collect();
+
+        Self {
+            command: command.to_string(),
+            title: title.to_string(),
+            summary: response.message.clone(),
+            body,
+            findings,
+            gate: None,
+            data,
+        }
+    }
+
+    /// Whether a finding was carried over from an earlier analysis rather than found in this run
+    pub fn is_carried_over(&self, finding: &Finding) -> bool {
+        let fingerprint = finding.fingerprint();
+        self.data.get("carried_over")
+            .and_then(|carried| serde_json::from_value::<Vec<Finding>>(carried.clone()).ok())
+            .is_some_and(|carried| carried.iter().any(|c| c.fingerprint() == fingerprint))
+    }
+
+    /// Evaluate the findings against a severity policy
+    pub fn with_gate(mut self, policy: &SeverityPolicy) -> Self {
+        self.gate = Some(policy.evaluate(self.findings.iter().map(|f| f.severity)));
+        self
+    }
+}
+
+/// Renders a report in one output format
+pub trait OutputFormatter: Send + Sync {
+    /// Name selected with `--format`
+    fn name(&self) -> &str;
+
+    /// Short description shown in listings
+    fn description(&self) -> &str;
+
+    /// Render the report
+    fn format(&self, report: &Report) -> Result<String>;
+
+    /// Program implementing a plugin formatter, or `None` for one built in
+    fn program(&self) -> Option<&Path> {
+        None
+    }
+}
+
+/// Output formatters available to `--format`
+pub struct FormatterRegistry {
+    /// Registered formatters, built-ins first
+    formatters: Vec<Box<dyn OutputFormatter>>,
+
+    /// Number of built-in formatters at the start of `formatters`
+    builtins: usize,
+}
+
+impl Default for FormatterRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FormatterRegistry {
+    /// A registry holding the built-in formatters
+    pub fn new() -> Self {
+        let formatters: Vec<Box<dyn OutputFormatter>> = vec![
+            Box::new(MarkdownFormatter),
+            Box::new(JsonFormatter),
+            Box::new(JunitFormatter),
+            Box::new(SarifFormatter),
+            Box::new(HtmlFormatter),
+        ];
+
+        Self { builtins: formatters.len(), formatters }
+    }
+
+    /// The built-in formatters plus the plugins in the formatter plugin directory
+    ///
+    /// Each executable file in the directory is a formatter named after its file stem. Without
+    /// the `plugins` feature, just the built-in formatters.
+    pub fn load() -> Result<Self> {
+        let registry = Self::new();
+        #[cfg(feature = "plugins")]
+        let registry = plugin::with_programs(registry, &plugin_dir()?);
+
+        Ok(registry)
+    }
+
+    /// Add a formatter, replacing a plugin formatter of the same name
+    ///
+    /// Built-in formatters can't be replaced.
+    pub fn register(&mut self, formatter: Box<dyn OutputFormatter>) {
+        if let Some(index) = self.formatters.iter().position(|f| f.name() == formatter.name()) {
+            if index < self.builtins {
+                tracing::warn!("Ignoring formatter plugin '{}': a built-in formatter has that name", formatter.name());
+                return;
+            }
+            self.formatters[index] = formatter;
+        } else {
+            self.formatters.push(formatter);
+        }
+    }
+
+    /// Look up a registered formatter
+    pub fn get(&self, name: &str) -> Option<&dyn OutputFormatter> {
+        let name = format_name(name);
+        self.formatters.iter()
+            .find(|f| f.name().eq_ignore_ascii_case(name))
+            .map(|f| f.as_ref())
+    }
+
+    /// Look up a formatter, falling back to a `qitops-format-<name>` program on the PATH
+    pub fn resolve(&mut self, name: &str) -> Result<&dyn OutputFormatter> {
+        #[cfg(feature = "plugins")]
+        if self.get(name).is_none()
+            && let Some(program) = crate::plugin::program::find_on_path(&format!("{}{}", FORMATTER_COMMAND_PREFIX, name))
+        {
+            self.register(Box::new(CommandFormatter::new(name, program)));
+        }
+
+        let names = self.names().join(", ");
+        self.get(name).ok_or_else(|| {
+            crate::error::QitOpsError::InvalidInput(format!("Unknown output format '{}' (available: {})", name, names)).into()
+        })
+    }
+
+    /// Names of the registered formatters
+    pub fn names(&self) -> Vec<&str> {
+        self.formatters.iter().map(|f| f.name()).collect()
+    }
+
+    /// The registered formatters
+    pub fn formatters(&self) -> &[Box<dyn OutputFormatter>] {
+        &self.formatters
+    }
+
+    /// Whether a registered formatter is built in rather than a plugin
+    pub fn is_builtin(&self, name: &str) -> bool {
+        self.formatters[..self.builtins].iter().any(|f| f.name().eq_ignore_ascii_case(name))
+    }
+}
@@ -0,0 +1,67 @@
+// Formatter plugins implemented by external programs
+
+use anyhow::{Result, anyhow};
+use std::path::{Path, PathBuf};
+
+use super::{FormatterRegistry, OutputFormatter, Report};
+use crate::plugin::program;
+
+/// Prefix of formatter executables on the PATH, e.g. `qitops-format-asciidoc`
+pub const FORMATTER_COMMAND_PREFIX: &str = "qitops-format-";
+
+/// Formatter plugin implemented by an external program
+///
+/// The program receives the report as JSON on stdin and prints the formatted output.
+pub struct CommandFormatter {
+    /// Formatter name
+    name: String,
+
+    /// Program to run
+    program: PathBuf,
+}
+
+impl CommandFormatter {
+    /// Create a formatter backed by a program
+    pub fn new(name: &str, program: PathBuf) -> Self {
+        Self { name: name.to_string(), program }
+    }
+}
+
+impl OutputFormatter for CommandFormatter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Plugin"
+    }
+
+    fn program(&self) -> Option<&Path> {
+        Some(&self.program)
+    }
+
+    fn format(&self, report: &Report) -> Result<String> {
+        let output = program::run(&self.program, serde_json::to_vec(report)?)?;
+        if !output.status.success() {
+            return Err(anyhow!("Formatter plugin {} failed ({})", self.name, output.status));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+/// Directory searched for formatter plugin programs
+pub fn plugin_dir() -> Result<PathBuf> {
+    program::plugin_dir("formatters")
+}
+
+/// A registry with the executable files in a directory added as formatters named after their file stems
+pub(super) fn with_programs(mut registry: FormatterRegistry, dir: &Path) -> FormatterRegistry {
+    for program in program::programs_in(dir) {
+        if let Some(name) = program.file_stem().and_then(|stem| stem.to_str()).map(str::to_string) {
+            registry.register(Box::new(CommandFormatter::new(&name, program)));
+        }
+    }
+
+    registry
+}
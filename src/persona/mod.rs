@@ -1,8 +1,10 @@
-use anyhow::{Result, anyhow, Context};
+use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
+
+use crate::error::QitOpsError;
 
 /// Persona
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -304,7 +306,7 @@ impl PersonaManager {
     /// Remove a persona
     pub fn remove_persona(&mut self, id: &str) -> Result<()> {
         if self.personas.remove(id).is_none() {
-            return Err(anyhow!("Persona not found: {}", id));
+            return Err(QitOpsError::Source(format!("Persona not found: {}", id)).into());
         }
 
         // Save config
@@ -317,7 +319,7 @@ impl PersonaManager {
 
         for id in ids {
             let persona = self.get_persona(id)
-                .ok_or_else(|| anyhow!("Persona not found: {}", id))?;
+                .ok_or_else(|| QitOpsError::Source(format!("Persona not found: {}", id)))?;
 
             prompt.push_str(&persona.get_prompt());
         }
@@ -71,6 +71,11 @@ impl PluginLoader {
         Ok(())
     }
     
+    /// Get the plugin directory
+    pub fn plugin_dir(&self) -> &str {
+        &self.plugin_dir
+    }
+    
     /// Get a plugin by name
     pub fn get_plugin(&self, name: &str) -> Option<&Box<dyn Plugin>> {
         self.plugins.iter().find(|p| p.metadata().name == name)
@@ -1,2 +1,3 @@
 // Plugin management
 pub mod loader;
+pub mod program;
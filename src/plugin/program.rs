@@ -0,0 +1,81 @@
+// Plugins implemented by external programs
+
+use anyhow::{Result, anyhow};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+/// Directory of the plugin programs of a kind, e.g. `formatters`
+pub fn plugin_dir(kind: &str) -> Result<PathBuf> {
+    Ok(crate::config::config_dir()?.join("plugins").join(kind))
+}
+
+/// The executable files in a directory, by name
+pub fn programs_in(dir: &Path) -> Vec<PathBuf> {
+    let mut programs: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect())
+        .unwrap_or_default();
+    programs.retain(|path| is_executable(path));
+    programs.sort();
+    programs
+}
+
+/// The program of a plugin: a file named after it in `dir`, or `<prefix><name>` on the PATH
+pub fn find_program(dir: &Path, prefix: &str, name: &str) -> Option<PathBuf> {
+    programs_in(dir).into_iter()
+        .find(|program| program.file_stem().and_then(|stem| stem.to_str()) == Some(name))
+        .or_else(|| find_on_path(&format!("{}{}", prefix, name)))
+}
+
+/// Whether a path is a file that can be run
+pub fn is_executable(path: &Path) -> bool {
+    let Ok(metadata) = path.metadata() else {
+        return false;
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+    }
+
+    #[cfg(not(unix))]
+    {
+        metadata.is_file()
+    }
+}
+
+/// Find a program on the PATH
+pub fn find_on_path(program: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .flat_map(|dir| [dir.join(program), dir.join(format!("{}.exe", program))])
+        .find(|candidate| is_executable(candidate))
+}
+
+/// Run a plugin program with some input on stdin and collect what it prints
+///
+/// The input is written from another thread, so a program that answers before reading all of
+/// it can't block on a full stdout pipe.
+pub fn run(program: &Path, input: Vec<u8>) -> Result<Output> {
+    let mut child = Command::new(program)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to run plugin {}: {}", program.display(), e))?;
+
+    let writer = child.stdin.take().map(|mut stdin| std::thread::spawn(move || stdin.write_all(&input)));
+    let output = child.wait_with_output()?;
+
+    // A program may exit without reading its input; that's its call
+    if let Some(writer) = writer {
+        match writer.join() {
+            Ok(Err(e)) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e.into()),
+            Ok(_) => {},
+            Err(_) => return Err(anyhow!("Failed to write to plugin {}", program.display())),
+        }
+    }
+
+    Ok(output)
+}
@@ -0,0 +1,52 @@
+pub mod templates;
+pub mod untrusted;
+
+pub use templates::render_prompt;
+pub use untrusted::wrap_untrusted;
+
+use anyhow::{Result, anyhow};
+use std::fs;
+use std::path::PathBuf;
+
+/// Maximum size of a system prompt override file
+const MAX_OVERRIDE_BYTES: usize = 32 * 1024;
+
+/// Candidate locations for an agent's system prompt override, in priority order
+pub fn system_prompt_override_paths(agent: &str) -> Result<Vec<PathBuf>> {
+    let file_name = format!("{}.system.md", agent);
+
+    Ok(vec![
+        // Per-project override
+        PathBuf::from(".qitops").join("prompts").join(&file_name),
+        // Per-user override
+        crate::config::config_dir()?.join("prompts").join(&file_name),
+    ])
+}
+
+/// Load and validate the system prompt override for an agent, if one exists
+pub fn load_system_prompt_override(agent: &str) -> Result<Option<String>> {
+    for path in system_prompt_override_paths(agent)? {
+        if !path.exists() {
+            continue;
+        }
+
+        let prompt = fs::read_to_string(&path)
+            .map_err(|e| anyhow!("Failed to read system prompt override {}: {}", path.display(), e))?;
+
+        if prompt.trim().is_empty() {
+            return Err(anyhow!("System prompt override is empty: {}", path.display()));
+        }
+
+        if prompt.len() > MAX_OVERRIDE_BYTES {
+            return Err(anyhow!(
+                "System prompt override {} is too large ({} bytes, maximum {})",
+                path.display(), prompt.len(), MAX_OVERRIDE_BYTES
+            ));
+        }
+
+        tracing::info!("Using system prompt override for {}: {}", agent, path.display());
+        return Ok(Some(prompt.trim().to_string()));
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,136 @@
+use anyhow::{Result, anyhow};
+use handlebars::{Handlebars, handlebars_helper};
+use std::fs;
+use std::path::PathBuf;
+
+/// A built-in prompt template
+#[derive(Debug, Clone, Copy)]
+pub struct PromptTemplate {
+    /// Template name (also the override file name without `.hbs`)
+    pub name: &'static str,
+
+    /// What the prompt is used for
+    pub description: &'static str,
+
+    /// Variables passed to the template
+    pub variables: &'static [&'static str],
+
+    /// Template text
+    pub template: &'static str,
+}
+
+/// Built-in prompt templates
+pub const BUILTIN_TEMPLATES: &[PromptTemplate] = &[
+    PromptTemplate {
+        name: "test-gen",
+        description: "Test case generation for a source file",
+        variables: &["code", "path", "function", "changed", "format", "personas", "conventions", "e2e", "repository", "overview"],
+        template: "{{#if personas}}{{personas}}\n\n{{/if}}Generate comprehensive test cases for the following code. Focus on edge cases, error handling, and important functionality.\n\nCode:\n```\n{{code}}\n```{{#if function}}\n\nOnly generate test cases for the function `{{function}}`; use the rest of the code as context.{{/if}}{{#if changed}}\n\n{{changed}}{{/if}}{{#if conventions}}\n\n{{conventions}}{{/if}}{{#if e2e}}\n\n{{e2e}}{{/if}}{{#if repository}}\n\n{{repository}}{{/if}}{{#if overview}}\n\n{{overview}}{{/if}}",
+    },
+    PromptTemplate {
+        name: "pr-analyze",
+        description: "Pull request analysis",
+        variables: &["title", "description", "files", "diff", "since", "focus", "findings_instructions"],
+        template: "Analyze the following pull request.\n\n{{title}}\n\n{{description}}\n\nFiles Changed:\n{{files}}\n\n{{#if since}}This pull request was analyzed before, at commit {{since}}. Only the files changed since then are shown; analyze and report findings for these files only.\n\n{{/if}}{{diff}}\n\n{{findings_instructions}}",
+    },
+    PromptTemplate {
+        name: "pr-analyze-file",
+        description: "Analysis of one file of a pull request too large to analyze at once",
+        variables: &["title", "description", "path", "index", "total", "diff", "focus", "findings_instructions"],
+        template: "Analyze the changes to one file of the following pull request. The pull request is too large to analyze at once, so each of its {{total}} files is analyzed on its own; this is file {{index}}, `{{path}}`.\n\n{{title}}\n\n{{description}}\n\n{{diff}}\n\nStart with a short summary of what changed in this file and how it could go wrong.\n\n{{findings_instructions}}",
+    },
+    PromptTemplate {
+        name: "pr-analyze-synthesis",
+        description: "Combined review of a large pull request from the analyses of its files",
+        variables: &["title", "description", "files", "analyses", "skipped", "focus"],
+        template: "Write the review of the following pull request from the analyses of its files, which were each analyzed on their own because the pull request is too large to analyze at once.\n\n{{title}}\n\n{{description}}\n\nFiles Changed:\n{{files}}\n\n{{analyses}}\n\n{{#if skipped}}These files were not analyzed: {{join skipped \", \"}}.\n\n{{/if}}Give an overall assessment of the pull request, covering how the changes to different files fit together and any problems that only show across files. Don't repeat the findings of single files.\n\nIf there are problems that span files, list them after your review in a fenced ```json block of the form {\"findings\": [{\"file\": \"path/to/file\", \"line\": null, \"severity\": \"info|low|medium|high|critical\", \"title\": \"short title\", \"description\": \"details\"}]}.",
+    },
+    PromptTemplate {
+        name: "risk",
+        description: "Risk assessment of code changes",
+        variables: &["diff", "components", "focus_areas", "infra", "ownership", "findings_instructions"],
+        template: "Assess the risk of the following code changes. Focus on {{#if components}}the following components: {{join components \", \"}}{{else}}all components{{/if}} and {{#if focus_areas}}the following risk areas: {{join focus_areas \", \"}}{{else}}general risk factors{{/if}}.\n\n{{diff}}\n\n{{#if infra}}{{infra}}\n\n{{/if}}{{#if ownership}}{{ownership}}\n\n{{/if}}Provide a risk assessment with an overall risk level (Low, Medium, High, or Critical), component-specific risks, a summary, and recommendations.\n\n{{findings_instructions}}",
+    },
+    PromptTemplate {
+        name: "test-data",
+        description: "Test data generation for a schema",
+        variables: &["count", "schema", "constraints", "format"],
+        template: "Generate {{count}} test data records for the following schema: {{schema}}{{#if constraints}}\n\nApply the following constraints: {{join constraints \", \"}}{{/if}}\n\nProvide the data in {{format}} format.",
+    },
+];
+
+handlebars_helper!(join: |list: array, separator: str| {
+    list.iter()
+        .map(|item| item.as_str().map(str::to_string).unwrap_or_else(|| item.to_string()))
+        .collect::<Vec<_>>()
+        .join(separator)
+});
+
+/// Look up a built-in template by name
+pub fn builtin_template(name: &str) -> Result<&'static PromptTemplate> {
+    BUILTIN_TEMPLATES.iter()
+        .find(|template| template.name == name)
+        .ok_or_else(|| anyhow!(
+            "Unknown prompt template: {} (available: {})",
+            name,
+            BUILTIN_TEMPLATES.iter().map(|template| template.name).collect::<Vec<_>>().join(", ")
+        ))
+}
+
+/// Candidate locations for a prompt template override, in priority order
+pub fn template_override_paths(name: &str) -> Result<Vec<PathBuf>> {
+    let file_name = format!("{}.hbs", name);
+
+    Ok(vec![
+        // Per-project override
+        PathBuf::from(".qitops").join("prompts").join(&file_name),
+        // Per-user override
+        crate::config::config_dir()?.join("prompts").join(&file_name),
+    ])
+}
+
+/// The override file that is used for a template, if any
+pub fn find_template_override(name: &str) -> Result<Option<PathBuf>> {
+    Ok(template_override_paths(name)?.into_iter().find(|path| path.exists()))
+}
+
+/// The template text in effect: the override if there is one, otherwise the built-in
+pub fn template_source(name: &str) -> Result<String> {
+    let builtin = builtin_template(name)?;
+
+    match find_template_override(name)? {
+        Some(path) => {
+            let template = fs::read_to_string(&path)
+                .map_err(|e| anyhow!("Failed to read prompt template {}: {}", path.display(), e))?;
+            tracing::info!("Using prompt template override for {}: {}", name, path.display());
+            Ok(template)
+        },
+        None => Ok(builtin.template.to_string()),
+    }
+}
+
+/// Create a template engine that renders prompts as plain text
+fn engine() -> Handlebars<'static> {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(handlebars::no_escape);
+    handlebars.register_helper("join", Box::new(join));
+    handlebars
+}
+
+/// Check that a template parses
+pub fn validate_template(name: &str, template: &str) -> Result<()> {
+    engine().register_template_string(name, template)
+        .map_err(|e| anyhow!("Invalid prompt template {}: {}", name, e))
+}
+
+/// Render the named prompt template with the given variables
+pub fn render_prompt(name: &str, variables: &serde_json::Value) -> Result<String> {
+    let template = template_source(name)?;
+
+    let mut handlebars = engine();
+    handlebars.register_template_string(name, &template)
+        .map_err(|e| anyhow!("Invalid prompt template {}: {}", name, e))?;
+
+    handlebars.render(name, variables)
+        .map_err(|e| anyhow!("Failed to render prompt template {}: {}", name, e))
+}
@@ -0,0 +1,73 @@
+// HTTP proxy configuration
+
+use anyhow::{Context, Result};
+use reqwest::{Client, ClientBuilder, IntoUrl, Method, NoProxy, Proxy, RequestBuilder};
+
+/// Build an HTTP client that goes through `proxy`, if set
+///
+/// Without a configured proxy the client follows the `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY`
+/// and `NO_PROXY` environment variables. A configured proxy is used for every request except
+/// the hosts listed in `NO_PROXY`.
+pub fn client(proxy: Option<&str>) -> Result<Client> {
+    builder(proxy)?.build().context("Failed to create HTTP client")
+}
+
+/// Like [`client`], returning the builder so callers can add settings such as timeouts
+pub fn builder(proxy: Option<&str>) -> Result<ClientBuilder> {
+    let mut builder = Client::builder();
+
+    if let Some(url) = proxy.filter(|url| !url.trim().is_empty()) {
+        let proxy = Proxy::all(url.trim())
+            .with_context(|| format!("Invalid proxy URL: {}", url))?
+            .no_proxy(NoProxy::from_env());
+        builder = builder.proxy(proxy);
+    }
+
+    Ok(builder)
+}
+
+/// Build an HTTP client for a service off this machine, like [`client`]
+pub fn remote(service: &'static str, proxy: Option<&str>) -> Result<RemoteClient> {
+    Ok(RemoteClient::new(service, client(proxy)?))
+}
+
+/// HTTP client for a service off this machine
+///
+/// Every request is started here, so offline mode is enforced in one place: in offline mode
+/// starting a request fails with an error naming the service, before anything is sent.
+#[derive(Debug, Clone)]
+pub struct RemoteClient {
+    /// Service name used in errors, e.g. `GitHub`
+    service: &'static str,
+
+    /// Underlying client
+    client: Client,
+}
+
+impl RemoteClient {
+    /// Wrap a client for a service
+    pub fn new(service: &'static str, client: Client) -> Self {
+        Self { service, client }
+    }
+
+    /// Start a request, unless offline mode is on
+    pub fn request(&self, method: Method, url: impl IntoUrl) -> Result<RequestBuilder> {
+        crate::offline::ensure_online(self.service)?;
+        Ok(self.client.request(method, url))
+    }
+
+    /// Start a GET request, unless offline mode is on
+    pub fn get(&self, url: impl IntoUrl) -> Result<RequestBuilder> {
+        self.request(Method::GET, url)
+    }
+
+    /// Start a POST request, unless offline mode is on
+    pub fn post(&self, url: impl IntoUrl) -> Result<RequestBuilder> {
+        self.request(Method::POST, url)
+    }
+
+    /// Start a DELETE request, unless offline mode is on
+    pub fn delete(&self, url: impl IntoUrl) -> Result<RequestBuilder> {
+        self.request(Method::DELETE, url)
+    }
+}
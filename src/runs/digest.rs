@@ -0,0 +1,230 @@
+// QA digest: what the saved runs of a period found, for notification channels and standups
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::agent::findings::Finding;
+use crate::audit::format_timestamp;
+use crate::integrations::publish;
+use crate::runs::RunRecord;
+use crate::severity::{GateAction, Severity};
+
+/// Overall risk levels counted as high-risk changes
+const HIGH_RISK_LEVELS: &[&str] = &["high", "critical"];
+
+/// Most open findings listed in the digest; the rest are counted
+const MAX_LISTED_FINDINGS: usize = 20;
+
+/// A change assessed as high risk, or whose run failed its gate
+#[derive(Debug, Clone, Serialize)]
+pub struct HighRiskChange {
+    /// Run ID
+    pub run_id: String,
+
+    /// Day of the run, `YYYY-MM-DD`
+    pub day: String,
+
+    /// Report title, naming the change
+    pub title: String,
+
+    /// Overall risk level, for risk assessments
+    pub overall_risk: Option<String>,
+
+    /// Whether the run failed its severity gate
+    pub gate_failed: bool,
+}
+
+/// A finding still reported by the latest run of its report
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenFinding {
+    /// The finding
+    pub finding: Finding,
+
+    /// Run that last reported it
+    pub run_id: String,
+
+    /// Title of that run's report
+    pub report: String,
+}
+
+/// QA activity over a period of the run history
+#[derive(Debug, Clone, Serialize)]
+pub struct Digest {
+    /// First day of the period, `YYYY-MM-DD`
+    pub since: String,
+
+    /// Last day of the period, `YYYY-MM-DD`
+    pub until: String,
+
+    /// Runs in the period
+    pub runs: usize,
+
+    /// Runs per command, most frequent first
+    pub analyses: Vec<(String, usize)>,
+
+    /// High-risk changes, newest first
+    pub high_risk: Vec<HighRiskChange>,
+
+    /// Test generation runs
+    pub test_runs: usize,
+
+    /// Test files written by those runs
+    pub test_files: Vec<String>,
+
+    /// Findings of the latest run of each report, most severe first
+    pub open_findings: Vec<OpenFinding>,
+}
+
+impl Digest {
+    /// Summarize the runs recorded from `since` (`YYYY-MM-DD`) to `until`
+    ///
+    /// Runs are matched to their report by command and title, so a finding fixed by a later
+    /// run of the same report isn't open anymore.
+    pub fn from_runs(records: &[RunRecord], since: &str, until: &str) -> Self {
+        let mut records: Vec<&RunRecord> = records.iter()
+            .filter(|record| {
+                let day = day(record.recorded_at);
+                day.as_str() >= since && day.as_str() <= until
+            })
+            .collect();
+        records.sort_by(|a, b| b.recorded_at.cmp(&a.recorded_at).then_with(|| b.id.cmp(&a.id)));
+
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for record in &records {
+            *counts.entry(record.report.command.as_str()).or_default() += 1;
+        }
+        let mut analyses: Vec<(String, usize)> = counts.into_iter().map(|(command, count)| (command.to_string(), count)).collect();
+        analyses.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let high_risk = records.iter()
+            .filter_map(|record| {
+                let overall_risk = record.report.data.pointer("/risk/overall_risk").and_then(|r| r.as_str()).map(str::to_string);
+                let gate_failed = record.report.gate == Some(GateAction::Fail);
+                let risky = overall_risk.as_deref().is_some_and(|risk| HIGH_RISK_LEVELS.contains(&risk.to_lowercase().as_str()));
+                (risky || gate_failed).then(|| HighRiskChange {
+                    run_id: record.id.clone(),
+                    day: day(record.recorded_at),
+                    title: record.report.title.clone(),
+                    overall_risk,
+                    gate_failed,
+                })
+            })
+            .collect();
+
+        let test_gen: Vec<&&RunRecord> = records.iter().filter(|record| record.report.command == "test-gen").collect();
+        let mut test_files = Vec::new();
+        for record in &test_gen {
+            match record.report.data.get("output_file").or_else(|| record.report.data.get("output_files")) {
+                Some(serde_json::Value::String(path)) => test_files.push(path.clone()),
+                Some(serde_json::Value::Array(paths)) => test_files.extend(paths.iter().filter_map(|p| p.as_str()).map(String::from)),
+                _ => {},
+            }
+        }
+        let mut seen = HashSet::new();
+        test_files.retain(|path| seen.insert(path.clone()));
+
+        // Newest first, so the first run of each report is its latest
+        let mut reports = HashSet::new();
+        let mut fingerprints = HashSet::new();
+        let mut open_findings = Vec::new();
+        for record in &records {
+            if !reports.insert((record.report.command.as_str(), record.report.title.as_str())) {
+                continue;
+            }
+            for finding in &record.report.findings {
+                if finding.severity > Severity::Info && fingerprints.insert(finding.fingerprint()) {
+                    open_findings.push(OpenFinding { finding: finding.clone(), run_id: record.id.clone(), report: record.report.title.clone() });
+                }
+            }
+        }
+        open_findings.sort_by_key(|open| std::cmp::Reverse(open.finding.severity));
+
+        Self {
+            since: since.to_string(),
+            until: until.to_string(),
+            runs: records.len(),
+            analyses,
+            high_risk,
+            test_runs: test_gen.len(),
+            test_files,
+            open_findings,
+        }
+    }
+
+    /// Digest title, naming the period
+    pub fn title(&self) -> String {
+        format!("QA Digest: {} to {}", self.since, self.until)
+    }
+
+    /// The digest as Markdown
+    pub fn markdown(&self) -> String {
+        let mut out = format!("# {}\n\n", self.title());
+        out.push_str(&format!(
+            "{} run(s), {} high-risk change(s), {} test file(s) generated, {} open finding(s).\n",
+            self.runs, self.high_risk.len(), self.test_files.len(), self.open_findings.len(),
+        ));
+
+        out.push_str("\n## Analyses Performed\n\n");
+        if self.analyses.is_empty() {
+            out.push_str("No runs in this period.\n");
+        } else {
+            out.push_str("| Command | Runs |\n|---|---|\n");
+            for (command, count) in &self.analyses {
+                out.push_str(&format!("| {} | {} |\n", command, count));
+            }
+        }
+
+        out.push_str("\n## High-Risk Changes\n\n");
+        if self.high_risk.is_empty() {
+            out.push_str("None.\n");
+        }
+        for change in &self.high_risk {
+            let mut notes = Vec::new();
+            if let Some(risk) = &change.overall_risk {
+                notes.push(format!("{} risk", risk));
+            }
+            if change.gate_failed {
+                notes.push("gate failed".to_string());
+            }
+            out.push_str(&format!("- **{}** ({}) on {}, run `{}`\n", change.title, notes.join(", "), change.day, change.run_id));
+        }
+
+        out.push_str("\n## Tests Generated\n\n");
+        if self.test_runs == 0 {
+            out.push_str("None.\n");
+        } else {
+            out.push_str(&format!("{} test file(s) from {} run(s):\n\n", self.test_files.len(), self.test_runs));
+            for path in &self.test_files {
+                out.push_str(&format!("- `{}`\n", path));
+            }
+        }
+
+        out.push_str("\n## Open Findings\n\n");
+        if self.open_findings.is_empty() {
+            out.push_str("None.\n");
+        }
+        for open in self.open_findings.iter().take(MAX_LISTED_FINDINGS) {
+            let location = match (&open.finding.file, open.finding.line) {
+                (Some(file), Some(line)) => format!(" in `{}:{}`", file, line),
+                (Some(file), None) => format!(" in `{}`", file),
+                _ => String::new(),
+            };
+            out.push_str(&format!("- **{}** {}{} ({})\n", open.finding.severity.as_str().to_uppercase(), open.finding.title, location, open.report));
+        }
+        if self.open_findings.len() > MAX_LISTED_FINDINGS {
+            out.push_str(&format!("- ...and {} more\n", self.open_findings.len() - MAX_LISTED_FINDINGS));
+        }
+
+        out
+    }
+
+    /// The digest as a report for publishing and notification channels
+    pub fn report(&self) -> publish::Report {
+        publish::Report::markdown(self.title(), self.markdown())
+    }
+}
+
+/// Day of a timestamp, `YYYY-MM-DD`
+fn day(timestamp: u64) -> String {
+    format_timestamp(timestamp)[..10].to_string()
+}
@@ -0,0 +1,329 @@
+// Findings store: every finding of pr-analyze and risk runs, tracked until it's addressed
+
+use anyhow::{Result, anyhow};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::agent::baseline::SuppressedFinding;
+use crate::agent::findings::Finding;
+use crate::output::Report;
+use crate::severity::Severity;
+
+/// Name of the findings database in the config directory
+const DATABASE_FILE: &str = "findings.db";
+
+/// Where a tracked finding stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FindingStatus {
+    /// Reported by the latest run of its scope
+    Open,
+    /// Left out of a later run of its scope, so addressed
+    Resolved,
+    /// Matched by the baseline or suppressed inline in the latest run of its scope
+    Suppressed,
+}
+
+impl FindingStatus {
+    /// Lowercase name, as stored
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FindingStatus::Open => "open",
+            FindingStatus::Resolved => "resolved",
+            FindingStatus::Suppressed => "suppressed",
+        }
+    }
+}
+
+impl fmt::Display for FindingStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for FindingStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "open" => Ok(FindingStatus::Open),
+            "resolved" => Ok(FindingStatus::Resolved),
+            "suppressed" => Ok(FindingStatus::Suppressed),
+            _ => Err(anyhow!("Unknown finding status: {} (expected open, resolved or suppressed)", s)),
+        }
+    }
+}
+
+/// A finding as tracked across runs
+#[derive(Debug, Clone, Serialize)]
+pub struct TrackedFinding {
+    /// Fingerprint matching the finding across runs
+    pub fingerprint: String,
+
+    /// Repository as `owner/repo`; empty outside a repository
+    pub repo: String,
+
+    /// Command that reported it
+    pub command: String,
+
+    /// Title of the report it was in, naming the PR or change
+    pub scope: String,
+
+    /// The finding as last reported
+    pub finding: Finding,
+
+    /// Where it stands
+    pub status: FindingStatus,
+
+    /// When it was first reported, in seconds since the Unix epoch
+    pub first_seen: u64,
+
+    /// When it was last reported
+    pub last_seen: u64,
+
+    /// Runs that reported it
+    pub times_seen: u64,
+
+    /// Run that first reported it
+    pub first_run: String,
+
+    /// Run that last reported it
+    pub last_run: String,
+
+    /// When a later run no longer reported it
+    pub resolved_at: Option<u64>,
+
+    /// The run that no longer reported it
+    pub resolved_run: Option<String>,
+}
+
+/// Which tracked findings to list
+#[derive(Debug, Clone, Default)]
+pub struct FindingsQuery {
+    /// Lowest severity to include
+    pub min_severity: Option<Severity>,
+
+    /// Repository, as `owner/repo`
+    pub repo: Option<String>,
+
+    /// Status to include
+    pub status: Option<FindingStatus>,
+
+    /// Command that reported them
+    pub command: Option<String>,
+
+    /// Only findings last reported at or after this time, in seconds since the Unix epoch
+    pub seen_since: Option<u64>,
+
+    /// Most findings to return
+    pub limit: Option<usize>,
+}
+
+/// What recording a run changed in the store
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RecordOutcome {
+    /// Findings reported for the first time
+    pub new: usize,
+
+    /// Resolved findings reported again
+    pub reopened: usize,
+
+    /// Open findings the run no longer reported
+    pub resolved: usize,
+}
+
+/// Findings of every analysis, in SQLite in the config directory
+///
+/// A finding is tracked per repository and scope (the command and report title, e.g. one PR's
+/// analysis), so a later run of the same scope that no longer reports it marks it resolved.
+pub struct FindingsStore {
+    /// Database
+    connection: Connection,
+
+    /// Database path
+    path: PathBuf,
+}
+
+impl FindingsStore {
+    /// Open the findings store in the config directory
+    pub fn open() -> Result<Self> {
+        Self::at(&crate::config::config_dir()?.join(DATABASE_FILE))
+    }
+
+    /// Open a findings store in a database file, creating it if needed
+    pub fn at(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let connection = Connection::open(path)
+            .map_err(|e| anyhow!("Failed to open findings database {}: {}", path.display(), e))?;
+
+        // CI jobs may record runs concurrently, so wait for locks instead of failing
+        connection.busy_timeout(std::time::Duration::from_secs(5))?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS findings (
+                repo TEXT NOT NULL,
+                command TEXT NOT NULL,
+                scope TEXT NOT NULL,
+                fingerprint TEXT NOT NULL,
+                file TEXT,
+                line INTEGER,
+                severity TEXT NOT NULL,
+                severity_rank INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                description TEXT NOT NULL,
+                status TEXT NOT NULL,
+                first_seen INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL,
+                times_seen INTEGER NOT NULL,
+                first_run TEXT NOT NULL,
+                last_run TEXT NOT NULL,
+                resolved_at INTEGER,
+                resolved_run TEXT,
+                PRIMARY KEY (repo, command, scope, fingerprint)
+            );
+            CREATE INDEX IF NOT EXISTS findings_status ON findings (status, severity_rank);",
+        )?;
+
+        Ok(Self { connection, path: path.to_path_buf() })
+    }
+
+    /// Database path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record a run's findings: new ones are added, known ones updated, and open ones of the
+    /// same scope that the run no longer reports are resolved
+    ///
+    /// Baselined and inline-suppressed findings are kept as suppressed rather than resolved. A
+    /// degraded run (one whose analysis fell back after an LLM failure) resolves nothing.
+    pub fn record(&mut self, repo: &str, run_id: &str, report: &Report, recorded_at: u64) -> Result<RecordOutcome> {
+        let suppressed: Vec<Finding> = ["baselined", "suppressed"].iter()
+            .filter_map(|key| report.data.get(*key).and_then(|value| serde_json::from_value::<Vec<SuppressedFinding>>(value.clone()).ok()))
+            .flatten()
+            .map(|suppressed| suppressed.finding)
+            .collect();
+        let degraded = report.data.get("degraded").is_some_and(|degraded| !degraded.is_null());
+
+        let transaction = self.connection.transaction()?;
+        let mut outcome = RecordOutcome::default();
+        let mut seen = HashSet::new();
+        let reported = report.findings.iter().map(|finding| (finding, FindingStatus::Open));
+        let accepted = suppressed.iter().map(|finding| (finding, FindingStatus::Suppressed));
+        for (finding, status) in reported.chain(accepted) {
+            let fingerprint = finding.fingerprint();
+            if !seen.insert(fingerprint.clone()) {
+                continue;
+            }
+
+            let key = params![repo, report.command, report.title, fingerprint];
+            let previous: Option<String> = transaction
+                .query_row("SELECT status FROM findings WHERE repo = ?1 AND command = ?2 AND scope = ?3 AND fingerprint = ?4", key, |row| row.get(0))
+                .optional()?;
+            match previous.as_deref() {
+                None => {
+                    transaction.execute(
+                        "INSERT INTO findings (repo, command, scope, fingerprint, file, line, severity, severity_rank, title, description, status, first_seen, last_seen, times_seen, first_run, last_run)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?12, 1, ?13, ?13)",
+                        params![repo, report.command, report.title, fingerprint, finding.file, finding.line, finding.severity.as_str(), finding.severity as i64,
+                            finding.title, finding.description, status.as_str(), recorded_at, run_id],
+                    )?;
+                    if status == FindingStatus::Open {
+                        outcome.new += 1;
+                    }
+                },
+                Some(previous) => {
+                    if previous == FindingStatus::Resolved.as_str() && status == FindingStatus::Open {
+                        outcome.reopened += 1;
+                    }
+                    transaction.execute(
+                        "UPDATE findings SET file = ?5, line = ?6, severity = ?7, severity_rank = ?8, title = ?9, description = ?10, status = ?11,
+                         last_seen = ?12, times_seen = times_seen + 1, last_run = ?13, resolved_at = NULL, resolved_run = NULL
+                         WHERE repo = ?1 AND command = ?2 AND scope = ?3 AND fingerprint = ?4",
+                        params![repo, report.command, report.title, fingerprint, finding.file, finding.line, finding.severity.as_str(), finding.severity as i64,
+                            finding.title, finding.description, status.as_str(), recorded_at, run_id],
+                    )?;
+                },
+            }
+        }
+
+        if !degraded {
+            let open: Vec<String> = {
+                let mut statement = transaction.prepare("SELECT fingerprint FROM findings WHERE repo = ?1 AND command = ?2 AND scope = ?3 AND status = 'open'")?;
+                let rows = statement.query_map(params![repo, report.command, report.title], |row| row.get(0))?;
+                rows.collect::<rusqlite::Result<Vec<String>>>()?
+            };
+            for fingerprint in open.iter().filter(|fingerprint| !seen.contains(*fingerprint)) {
+                transaction.execute(
+                    "UPDATE findings SET status = 'resolved', resolved_at = ?5, resolved_run = ?6
+                     WHERE repo = ?1 AND command = ?2 AND scope = ?3 AND fingerprint = ?4",
+                    params![repo, report.command, report.title, fingerprint, recorded_at, run_id],
+                )?;
+                outcome.resolved += 1;
+            }
+        }
+
+        transaction.commit()?;
+        Ok(outcome)
+    }
+
+    /// Tracked findings matching a query, most severe and most recently seen first
+    pub fn query(&self, query: &FindingsQuery) -> Result<Vec<TrackedFinding>> {
+        let mut sql = "SELECT repo, command, scope, fingerprint, file, line, severity, title, description, status,
+                       first_seen, last_seen, times_seen, first_run, last_run, resolved_at, resolved_run
+                       FROM findings WHERE severity_rank >= ?1".to_string();
+        let mut values: Vec<rusqlite::types::Value> = vec![(query.min_severity.unwrap_or(Severity::Info) as i64).into()];
+        let mut filter = |column: &str, value: rusqlite::types::Value| {
+            values.push(value);
+            sql.push_str(&format!(" AND {} ?{}", column, values.len()));
+        };
+        if let Some(repo) = &query.repo {
+            filter("repo =", repo.clone().into());
+        }
+        if let Some(status) = query.status {
+            filter("status =", status.as_str().to_string().into());
+        }
+        if let Some(command) = &query.command {
+            filter("command =", command.clone().into());
+        }
+        if let Some(since) = query.seen_since {
+            filter("last_seen >=", (since as i64).into());
+        }
+        sql.push_str(" ORDER BY severity_rank DESC, last_seen DESC, title");
+        if let Some(limit) = query.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        let mut statement = self.connection.prepare(&sql)?;
+        let rows = statement.query_map(rusqlite::params_from_iter(values), |row| {
+            Ok(TrackedFinding {
+                repo: row.get(0)?,
+                command: row.get(1)?,
+                scope: row.get(2)?,
+                fingerprint: row.get(3)?,
+                finding: Finding {
+                    file: row.get(4)?,
+                    line: row.get(5)?,
+                    severity: Severity::from(row.get::<_, String>(6)?),
+                    title: row.get(7)?,
+                    description: row.get(8)?,
+                },
+                status: row.get::<_, String>(9)?.parse().unwrap_or(FindingStatus::Open),
+                first_seen: row.get(10)?,
+                last_seen: row.get(11)?,
+                times_seen: row.get(12)?,
+                first_run: row.get(13)?,
+                last_run: row.get(14)?,
+                resolved_at: row.get(15)?,
+                resolved_run: row.get(16)?,
+            })
+        })?;
+
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+}
@@ -0,0 +1,266 @@
+// Run history and structured comparison of runs
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::agent::findings::Finding;
+use crate::history::{self, HistoryConfig};
+use crate::output::Report;
+use crate::severity::{GateAction, Severity};
+
+pub mod digest;
+pub mod findings;
+pub mod risk;
+pub mod triage;
+
+/// Most runs kept in the history; older ones are deleted
+pub const MAX_RUNS: usize = 200;
+
+/// A run saved in the history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// Run ID, as in the audit log
+    pub id: String,
+
+    /// When the run finished, in seconds since the Unix epoch
+    pub recorded_at: u64,
+
+    /// Report of the run
+    pub report: Report,
+}
+
+/// Saved runs, in `runs/` under the config directory
+pub struct RunHistory {
+    /// Directory holding one file per run
+    dir: PathBuf,
+
+    /// Whether run files are encrypted
+    config: HistoryConfig,
+}
+
+impl RunHistory {
+    /// Open the run history in the config directory
+    pub fn open() -> Result<Self> {
+        let config = crate::config::QitOpsConfigManager::new()?.get_config().history.clone();
+        Ok(Self::at(crate::config::config_dir()?.join("runs"), config))
+    }
+
+    /// Open a run history stored in a directory
+    pub fn at(dir: PathBuf, config: HistoryConfig) -> Self {
+        Self { dir, config }
+    }
+
+    /// Save a run's report under `run_id`, returning the ID it was saved as
+    ///
+    /// A process that runs several times (watch mode) gets `-2`, `-3`... suffixes.
+    pub fn record(&self, run_id: &str, report: &Report) -> Result<String> {
+        fs::create_dir_all(&self.dir)?;
+
+        let taken: HashSet<String> = self.files()?.into_iter().map(|(id, _)| id).collect();
+        let id = (1..).map(|n| if n == 1 { run_id.to_string() } else { format!("{}-{}", run_id, n) })
+            .find(|id| !taken.contains(id))
+            .unwrap_or_default();
+
+        let record = RunRecord {
+            id: id.clone(),
+            recorded_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default(),
+            report: report.clone(),
+        };
+        history::write(&self.dir.join(format!("{}.json", id)), &serde_json::to_string_pretty(&record)?, &self.config)?;
+
+        self.prune()?;
+        Ok(id)
+    }
+
+    /// Saved runs, newest first
+    pub fn list(&self) -> Result<Vec<RunRecord>> {
+        let mut records = Vec::new();
+        for (id, path) in self.files()? {
+            match history::read(&path).and_then(|text| Ok(serde_json::from_str::<RunRecord>(&text)?)) {
+                Ok(record) => records.push(record),
+                Err(e) => tracing::warn!("Skipping unreadable run {}: {}", id, e),
+            }
+        }
+
+        records.sort_by(|a, b| b.recorded_at.cmp(&a.recorded_at).then_with(|| b.id.cmp(&a.id)));
+        Ok(records)
+    }
+
+    /// A saved run by ID or unique ID prefix; `latest` is the newest run and `latest~N` the one N runs before
+    pub fn get(&self, id: &str) -> Result<RunRecord> {
+        if let Some(back) = id.strip_prefix("latest") {
+            let back: usize = match back.strip_prefix('~') {
+                Some(n) => n.parse().map_err(|_| anyhow!("Invalid run reference: {}", id))?,
+                None if back.is_empty() => 0,
+                None => return Err(anyhow!("Invalid run reference: {}", id)),
+            };
+            return self.list()?.into_iter().nth(back).ok_or_else(|| anyhow!("No run {} in the history", id));
+        }
+
+        let matches: Vec<PathBuf> = self.files()?.into_iter()
+            .filter(|(file_id, _)| file_id == id || file_id.starts_with(id))
+            .map(|(_, path)| path)
+            .collect();
+
+        let path = match matches.as_slice() {
+            [path] => path,
+            [] => return Err(anyhow!("No run {} in the history; list runs with `qitops history runs`", id)),
+            _ => match matches.iter().find(|path| Self::file_id(path).as_deref() == Some(id)) {
+                Some(path) => path,
+                None => return Err(anyhow!("Run ID {} is ambiguous; give more of it", id)),
+            },
+        };
+
+        Ok(serde_json::from_str(&history::read(path)?)?)
+    }
+
+    /// Newest run that failed its severity gate
+    pub fn latest_failed(&self) -> Result<RunRecord> {
+        self.list()?.into_iter()
+            .find(|record| record.failed())
+            .ok_or_else(|| anyhow!("No failed run in the history; pick a run with --run (see `qitops history runs`)"))
+    }
+
+    /// Run files with their IDs
+    fn files(&self) -> Result<Vec<(String, PathBuf)>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        Ok(fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| Self::file_id(&entry.path()).map(|id| (id, entry.path())))
+            .collect())
+    }
+
+    /// Run ID of a run file, plain or encrypted
+    fn file_id(path: &std::path::Path) -> Option<String> {
+        let name = path.file_name()?.to_str()?;
+        let name = name.strip_suffix(&format!(".{}", history::ENCRYPTED_EXTENSION)).unwrap_or(name);
+        name.strip_suffix(".json").map(str::to_string)
+    }
+
+    /// Delete the oldest runs beyond `MAX_RUNS`
+    fn prune(&self) -> Result<()> {
+        let mut files: Vec<(SystemTime, PathBuf)> = self.files()?.into_iter()
+            .filter_map(|(_, path)| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+            .collect();
+        if files.len() <= MAX_RUNS {
+            return Ok(());
+        }
+
+        files.sort();
+        for (_, path) in &files[..files.len() - MAX_RUNS] {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// A finding whose severity differs between two runs
+#[derive(Debug, Clone, Serialize)]
+pub struct SeverityChange {
+    /// The finding as reported by the second run
+    pub finding: Finding,
+
+    /// Severity in the first run
+    pub was: Severity,
+}
+
+/// Structured differences between two runs
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunDiff {
+    /// Findings only in the second run
+    pub findings_added: Vec<Finding>,
+
+    /// Findings only in the first run
+    pub findings_removed: Vec<Finding>,
+
+    /// Findings in both runs with a different severity
+    pub severity_changed: Vec<SeverityChange>,
+
+    /// Findings in both runs with the same severity
+    pub findings_unchanged: usize,
+
+    /// Test case titles only in the second run
+    pub cases_added: Vec<String>,
+
+    /// Test case titles only in the first run
+    pub cases_removed: Vec<String>,
+
+    /// Test cases in both runs
+    pub cases_unchanged: usize,
+
+    /// Overall risk level of each run, when either is a risk assessment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overall_risk: Option<(Option<String>, Option<String>)>,
+
+    /// Gate outcome of each run, when either was gated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gate: Option<(Option<GateAction>, Option<GateAction>)>,
+
+    /// Lines of the report body added and removed, for text without findings or test cases
+    pub body_lines_added: usize,
+    pub body_lines_removed: usize,
+}
+
+impl RunDiff {
+    /// Whether the runs differ in anything compared
+    pub fn is_empty(&self) -> bool {
+        self.findings_added.is_empty() && self.findings_removed.is_empty() && self.severity_changed.is_empty()
+            && self.cases_added.is_empty() && self.cases_removed.is_empty()
+            && self.overall_risk.as_ref().is_none_or(|(a, b)| a == b)
+            && self.gate.is_none_or(|(a, b)| a == b)
+            && self.body_lines_added == 0 && self.body_lines_removed == 0
+    }
+}
+
+/// Compare two reports: findings by fingerprint, test cases by title, and risk level and gate
+pub fn diff(a: &Report, b: &Report) -> RunDiff {
+    let mut result = RunDiff::default();
+
+    // Findings, matched the way the baseline matches them
+    let before: HashMap<String, &Finding> = a.findings.iter().map(|f| (f.fingerprint(), f)).collect();
+    let after: HashSet<String> = b.findings.iter().map(|f| f.fingerprint()).collect();
+    for finding in &b.findings {
+        match before.get(&finding.fingerprint()) {
+            None => result.findings_added.push(finding.clone()),
+            Some(old) if old.severity != finding.severity => {
+                result.severity_changed.push(SeverityChange { finding: finding.clone(), was: old.severity });
+            },
+            Some(_) => result.findings_unchanged += 1,
+        }
+    }
+    result.findings_removed = a.findings.iter().filter(|f| !after.contains(&f.fingerprint())).cloned().collect();
+
+    // Test cases, matched by normalized title
+    let cases_a = crate::llm::ensemble::test_cases(&a.body);
+    let cases_b = crate::llm::ensemble::test_cases(&b.body);
+    let keys_a: HashSet<&String> = cases_a.iter().map(|(key, _)| key).collect();
+    let keys_b: HashSet<&String> = cases_b.iter().map(|(key, _)| key).collect();
+    result.cases_added = cases_b.iter().filter(|(key, _)| !keys_a.contains(key)).map(|(_, title)| title.clone()).collect();
+    result.cases_removed = cases_a.iter().filter(|(key, _)| !keys_b.contains(key)).map(|(_, title)| title.clone()).collect();
+    result.cases_unchanged = cases_b.iter().filter(|(key, _)| keys_a.contains(key)).count();
+
+    let overall_risk = |report: &Report| report.data.pointer("/risk/overall_risk").and_then(|r| r.as_str()).map(str::to_string);
+    if a.command == "risk" || b.command == "risk" {
+        result.overall_risk = Some((overall_risk(a), overall_risk(b)));
+    }
+    if a.gate.is_some() || b.gate.is_some() {
+        result.gate = Some((a.gate, b.gate));
+    }
+
+    // Without structure to compare, count changed lines
+    if a.findings.is_empty() && b.findings.is_empty() && cases_a.is_empty() && cases_b.is_empty() {
+        let lines_a: HashSet<&str> = a.body.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+        let lines_b: HashSet<&str> = b.body.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+        result.body_lines_added = lines_b.difference(&lines_a).count();
+        result.body_lines_removed = lines_a.difference(&lines_b).count();
+    }
+
+    result
+}
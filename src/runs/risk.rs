@@ -0,0 +1,290 @@
+// Risk history: every risk assessment with its score and components, for trends across PRs
+
+use anyhow::{Result, anyhow};
+use rusqlite::{Connection, params};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::agent::risk::RiskAssessment;
+use crate::agent::risk_score::RiskScore;
+use crate::output::Report;
+use crate::severity::Severity;
+
+/// Name of the risk database in the config directory
+const DATABASE_FILE: &str = "risk.db";
+
+/// Fewest assessments of a component before it can be called recurring
+const RECURRING_MIN_ASSESSMENTS: usize = 3;
+
+/// A risk assessment as recorded
+#[derive(Debug, Clone, Serialize)]
+pub struct RiskRecord {
+    /// Repository as `owner/repo`; empty outside a repository
+    pub repo: String,
+
+    /// Pull request assessed, if the assessment was of one
+    pub pr: Option<u64>,
+
+    /// Title of the report, naming the PR or diff
+    pub scope: String,
+
+    /// Run that made the assessment
+    pub run_id: String,
+
+    /// Overall risk level
+    pub overall_risk: Severity,
+
+    /// Numeric score, for assessments that had one
+    pub score: Option<u32>,
+
+    /// Label of the score's range
+    pub label: Option<String>,
+
+    /// Risk of each component assessed
+    pub components: Vec<ComponentRecord>,
+
+    /// When it was recorded, in seconds since the Unix epoch
+    pub recorded_at: u64,
+
+    /// Day it was recorded (UTC) as `YYYY-MM-DD`
+    pub day: String,
+}
+
+/// Risk of one component in an assessment
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentRecord {
+    /// Component name
+    pub component: String,
+
+    /// Risk level
+    pub risk_level: Severity,
+
+    /// Sub-score, for assessments that had one
+    pub score: Option<u32>,
+}
+
+/// Which assessments to list
+#[derive(Debug, Clone, Default)]
+pub struct RiskQuery {
+    /// Repository, as `owner/repo`
+    pub repo: Option<String>,
+
+    /// Only assessments of this component (case-insensitive)
+    pub component: Option<String>,
+
+    /// Only assessments recorded on or after this day (`YYYY-MM-DD`)
+    pub since_day: Option<String>,
+
+    /// Most assessments to return
+    pub limit: Option<usize>,
+}
+
+/// How a component's risk developed over its assessments
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComponentTrend {
+    /// Component name, as last assessed
+    pub component: String,
+
+    /// Assessments that included it
+    pub assessments: usize,
+
+    /// Assessments that rated it high or critical
+    pub high_risk: usize,
+
+    /// Mean sub-score over the assessments that had one
+    pub average_score: Option<u32>,
+
+    /// Latest risk level
+    pub latest_level: Severity,
+
+    /// Latest sub-score
+    pub latest_score: Option<u32>,
+
+    /// Latest sub-score minus the mean of the earlier ones
+    pub change: Option<i64>,
+}
+
+impl ComponentTrend {
+    /// Whether the component keeps coming up high-risk: high or critical in most of at least three assessments
+    pub fn is_recurring(&self) -> bool {
+        self.assessments >= RECURRING_MIN_ASSESSMENTS && self.high_risk * 2 > self.assessments
+    }
+}
+
+/// Risk assessments of every run, in SQLite in the config directory
+pub struct RiskStore {
+    /// Database
+    connection: Connection,
+
+    /// Database path
+    path: PathBuf,
+}
+
+impl RiskStore {
+    /// Open the risk store in the config directory
+    pub fn open() -> Result<Self> {
+        Self::at(&crate::config::config_dir()?.join(DATABASE_FILE))
+    }
+
+    /// Open a risk store in a database file, creating it if needed
+    pub fn at(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let connection = Connection::open(path)
+            .map_err(|e| anyhow!("Failed to open risk database {}: {}", path.display(), e))?;
+
+        // CI jobs may record runs concurrently, so wait for locks instead of failing
+        connection.busy_timeout(std::time::Duration::from_secs(5))?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS assessments (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                repo TEXT NOT NULL,
+                pr INTEGER,
+                scope TEXT NOT NULL,
+                run_id TEXT NOT NULL,
+                overall_risk TEXT NOT NULL,
+                score INTEGER,
+                label TEXT,
+                recorded_at INTEGER NOT NULL,
+                day TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS components (
+                assessment_id INTEGER NOT NULL REFERENCES assessments (id),
+                component TEXT NOT NULL,
+                risk_level TEXT NOT NULL,
+                score INTEGER
+            );
+            CREATE INDEX IF NOT EXISTS components_name ON components (component COLLATE NOCASE);",
+        )?;
+
+        Ok(Self { connection, path: path.to_path_buf() })
+    }
+
+    /// Database path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record a risk run's assessment, returning whether the report had one
+    pub fn record(&mut self, repo: &str, pr: Option<u64>, run_id: &str, report: &Report, recorded_at: u64) -> Result<bool> {
+        let Some(assessment) = report.data.get("risk").and_then(|risk| serde_json::from_value::<RiskAssessment>(risk.clone()).ok()) else {
+            return Ok(false);
+        };
+        let score = report.data.get("score").and_then(|score| serde_json::from_value::<RiskScore>(score.clone()).ok());
+        let day = crate::audit::format_timestamp(recorded_at)[..10].to_string();
+
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO assessments (repo, pr, scope, run_id, overall_risk, score, label, recorded_at, day)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![repo, pr, report.title, run_id, assessment.overall_risk.severity().as_str(),
+                score.as_ref().map(|score| score.score), score.as_ref().map(|score| &score.label), recorded_at, day],
+        )?;
+        let id = transaction.last_insert_rowid();
+        for risk in &assessment.component_risks {
+            let sub_score = score.as_ref()
+                .and_then(|score| score.components.iter().find(|component| component.component == risk.component))
+                .map(|component| component.score);
+            transaction.execute(
+                "INSERT INTO components (assessment_id, component, risk_level, score) VALUES (?1, ?2, ?3, ?4)",
+                params![id, risk.component, risk.risk_level.severity().as_str(), sub_score],
+            )?;
+        }
+        transaction.commit()?;
+
+        Ok(true)
+    }
+
+    /// Recorded assessments matching a query, newest first
+    pub fn query(&self, query: &RiskQuery) -> Result<Vec<RiskRecord>> {
+        let mut sql = "SELECT id, repo, pr, scope, run_id, overall_risk, score, label, recorded_at, day FROM assessments WHERE 1 = 1".to_string();
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+        let mut filter = |condition: &str, value: rusqlite::types::Value| {
+            values.push(value);
+            sql.push_str(&format!(" AND {}", condition.replace('?', &format!("?{}", values.len()))));
+        };
+        if let Some(repo) = &query.repo {
+            filter("repo = ?", repo.clone().into());
+        }
+        if let Some(component) = &query.component {
+            filter("id IN (SELECT assessment_id FROM components WHERE component = ? COLLATE NOCASE)", component.clone().into());
+        }
+        if let Some(day) = &query.since_day {
+            filter("day >= ?", day.clone().into());
+        }
+        sql.push_str(" ORDER BY recorded_at DESC, id DESC");
+        if let Some(limit) = query.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        let mut statement = self.connection.prepare(&sql)?;
+        let rows = statement.query_map(rusqlite::params_from_iter(values), |row| {
+            Ok((row.get::<_, i64>(0)?, RiskRecord {
+                repo: row.get(1)?,
+                pr: row.get(2)?,
+                scope: row.get(3)?,
+                run_id: row.get(4)?,
+                overall_risk: Severity::from(row.get::<_, String>(5)?),
+                score: row.get(6)?,
+                label: row.get(7)?,
+                components: Vec::new(),
+                recorded_at: row.get(8)?,
+                day: row.get(9)?,
+            }))
+        })?;
+        let mut records = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut statement = self.connection.prepare("SELECT component, risk_level, score FROM components WHERE assessment_id = ?1 ORDER BY rowid")?;
+        for (id, record) in &mut records {
+            let rows = statement.query_map(params![*id], |row| Ok(ComponentRecord {
+                component: row.get(0)?,
+                risk_level: Severity::from(row.get::<_, String>(1)?),
+                score: row.get(2)?,
+            }))?;
+            record.components = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        }
+
+        Ok(records.into_iter().map(|(_, record)| record).collect())
+    }
+}
+
+/// Trend of each component across assessments (newest first, as queried), most often high-risk first
+///
+/// Components are matched by name without regard to case.
+pub fn component_trends(records: &[RiskRecord]) -> Vec<ComponentTrend> {
+    // Oldest first, so the last entry of each component is its latest
+    let mut by_component: HashMap<String, Vec<&ComponentRecord>> = HashMap::new();
+    let mut order = Vec::new();
+    for component in records.iter().rev().flat_map(|record| &record.components) {
+        let key = component.component.to_lowercase();
+        if !by_component.contains_key(&key) {
+            order.push(key.clone());
+        }
+        by_component.entry(key).or_default().push(component);
+    }
+
+    let mut trends: Vec<ComponentTrend> = order.iter().map(|key| {
+        let history = &by_component[key];
+        let latest = history[history.len() - 1];
+        let scores: Vec<u32> = history.iter().filter_map(|component| component.score).collect();
+        let mean = |scores: &[u32]| (!scores.is_empty()).then(|| scores.iter().sum::<u32>() as f64 / scores.len() as f64);
+
+        ComponentTrend {
+            component: latest.component.clone(),
+            assessments: history.len(),
+            high_risk: history.iter().filter(|component| component.risk_level >= Severity::High).count(),
+            average_score: mean(&scores).map(|mean| mean.round() as u32),
+            latest_level: latest.risk_level,
+            latest_score: latest.score,
+            change: match (latest.score, scores.split_last()) {
+                (Some(score), Some((_, earlier))) => mean(earlier).map(|mean| (score as f64 - mean).round() as i64),
+                _ => None,
+            },
+        }
+    }).collect();
+
+    trends.sort_by(|a, b| b.high_risk.cmp(&a.high_risk).then_with(|| b.average_score.cmp(&a.average_score)));
+    trends
+}
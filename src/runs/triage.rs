@@ -0,0 +1,299 @@
+// Failure triage: follow-up questions about a saved run, answered from its stored artifacts
+
+use anyhow::{Result, anyhow};
+use std::path::Path;
+
+use crate::agent::diff::{DiffHunk, DiffLineKind, FileDiff, parse_diff};
+use crate::agent::findings::Finding;
+use crate::llm::{Conversation, LlmRouter};
+use crate::runs::RunRecord;
+use crate::severity::GateAction;
+
+/// Author of the user's messages in the transcript
+pub const USER_NAME: &str = "User";
+
+/// Author of the answers in the transcript
+pub const TRIAGE_NAME: &str = "Triage";
+
+/// Most characters of flagged hunks put in the system prompt
+const MAX_HUNK_CHARS: usize = 40_000;
+
+/// Commands answered from the run without asking the model
+pub const HELP: &str = "/findings          List the run's findings
+/hunk <n>          Show the diff hunk of finding n
+/hunk <file[:line]> Show the diff hunks of a file, or the one with a line
+/summary           Show the run's outcome
+/help              Show this help
+exit               End triage
+
+Anything else is a question about the run, e.g. \"why was risk high?\"";
+
+/// A triage conversation about one saved run
+///
+/// The run's report, findings, risk assessment and the diff hunks its findings point at are
+/// given to the model up front, so questions are answered from what the run stored instead of
+/// running the analysis again.
+pub struct TriageSession {
+    /// Run being triaged
+    record: RunRecord,
+
+    /// Diff the run analyzed, if it stored one
+    files: Vec<FileDiff>,
+
+    /// Questions and answers so far
+    conversation: Conversation,
+}
+
+impl TriageSession {
+    /// Start triaging a run, with a description of the repository it ran in
+    pub fn new(record: RunRecord, repo_context: &str) -> Self {
+        let files = record.report.data.get("diff")
+            .and_then(|diff| diff.as_str())
+            .map(parse_diff)
+            .unwrap_or_default();
+
+        let mut session = Self { record, files, conversation: Conversation::new(String::new()) };
+        session.conversation = Conversation::new(session.system_prompt(repo_context)).with_max_turns(20);
+        session
+    }
+
+    /// Run being triaged
+    pub fn record(&self) -> &RunRecord {
+        &self.record
+    }
+
+    /// Findings of the run, numbered from 1 in `/hunk`
+    pub fn findings(&self) -> &[Finding] {
+        &self.record.report.findings
+    }
+
+    /// The run's outcome: title, summary, gate and overall risk
+    pub fn summary(&self) -> String {
+        let report = &self.record.report;
+        let mut summary = format!(
+            "Run {} ({}): {}\n{}",
+            self.record.id, crate::audit::format_timestamp(self.record.recorded_at), report.title, report.summary
+        );
+        if let Some(gate) = report.gate {
+            summary.push_str(&format!("\nGate: {}", format!("{:?}", gate).to_lowercase()));
+        }
+        if let Some(risk) = report.data.pointer("/risk/overall_risk").and_then(|risk| risk.as_str()) {
+            summary.push_str(&format!("\nOverall risk: {}", risk));
+        }
+        summary.push_str(&format!("\nFindings: {}", report.findings.len()));
+        summary
+    }
+
+    /// The findings as a numbered list
+    pub fn finding_list(&self) -> String {
+        if self.findings().is_empty() {
+            return "The run has no findings".to_string();
+        }
+
+        self.findings().iter().enumerate()
+            .map(|(index, finding)| format!("{}. [{}] {}{}", index + 1, finding.severity, finding.title, location(finding)))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Diff hunks of a finding number, a file, or a line of a file (`path:line`)
+    pub fn hunk(&self, reference: &str) -> Result<String> {
+        if self.files.is_empty() {
+            return Err(anyhow!("Run {} didn't store the diff it analyzed", self.record.id));
+        }
+
+        let (file, line) = match reference.parse::<usize>() {
+            Ok(number) => {
+                let finding = number.checked_sub(1).and_then(|index| self.findings().get(index))
+                    .ok_or_else(|| anyhow!("No finding {}; the run has {}", number, self.findings().len()))?;
+                let file = finding.file.clone()
+                    .ok_or_else(|| anyhow!("Finding {} isn't about a file", number))?;
+                (file, finding.line)
+            },
+            Err(_) => match reference.rsplit_once(':') {
+                Some((file, line)) if line.parse::<u32>().is_ok() => (file.to_string(), line.parse().ok()),
+                _ => (reference.to_string(), None),
+            },
+        };
+
+        let hunks = self.hunks_at(&file, line);
+        if hunks.is_empty() {
+            return Err(match line {
+                Some(line) => anyhow!("No hunk of the diff covers {}:{}", file, line),
+                None => anyhow!("{} isn't in the diff", file),
+            });
+        }
+        Ok(hunks.iter().map(|(path, hunk)| render_hunk(path, hunk)).collect::<Vec<String>>().join("\n"))
+    }
+
+    /// Hunks of a file, only the one covering `line` if given
+    fn hunks_at(&self, file: &str, line: Option<u32>) -> Vec<(&str, &DiffHunk)> {
+        let Some(diff) = self.files.iter().find(|diff| same_file(diff.path(), file)) else {
+            return Vec::new();
+        };
+
+        diff.hunks.iter()
+            .filter(|hunk| line.is_none_or(|line| hunk.lines.iter().any(|l| l.new_line == Some(line) || l.old_line == Some(line))))
+            .map(|hunk| (diff.path(), hunk))
+            .collect()
+    }
+
+    /// Answer a triage command without the model, or `None` for a question
+    pub fn command(&self, input: &str) -> Option<String> {
+        let (command, argument) = input.split_once(' ').map(|(c, a)| (c, a.trim())).unwrap_or((input, ""));
+        let output = match command {
+            "/findings" => self.finding_list(),
+            "/summary" => self.summary(),
+            "/help" => HELP.to_string(),
+            "/hunk" if argument.is_empty() => "Usage: /hunk <finding number | file[:line]>".to_string(),
+            "/hunk" => self.hunk(argument).unwrap_or_else(|e| e.to_string()),
+            _ if command.starts_with('/') => format!("Unknown command {}; /help lists the commands", command),
+            _ => return None,
+        };
+        Some(output)
+    }
+
+    /// Answer a question about the run
+    pub async fn ask(&mut self, router: &LlmRouter, question: &str) -> Result<String> {
+        self.conversation.push_user(USER_NAME, question.to_string());
+
+        let model = router.default_model().unwrap_or_else(|| "mistral".to_string());
+        let request = self.conversation.request(model, None, router.context_window(Some("triage")))
+            .with_cache(false);
+        let answer = router.send(request, Some("triage")).await?.text.trim().to_string();
+
+        self.conversation.push_assistant(TRIAGE_NAME, answer.clone());
+        Ok(answer)
+    }
+
+    /// Instructions and the run's artifacts
+    fn system_prompt(&self, repo_context: &str) -> String {
+        let report = &self.record.report;
+        let mut prompt = format!(
+            "You are helping a developer triage a failed QitOps run. Answer their questions from the run's stored artifacts below; \
+             don't re-analyze the change or guess beyond them, and say so when the artifacts don't answer a question. \
+             Quote diff hunks exactly as given. Refer to findings by number; the developer can print a finding's full hunk with `/hunk <n>`.\n\n\
+             ## Run\n\n{}\nCommand: qitops run {}\n",
+            self.summary(), report.command
+        );
+
+        prompt.push_str(&format!("\n## Findings\n\n{}\n", self.finding_list()));
+        for (index, finding) in self.findings().iter().enumerate() {
+            if !finding.description.is_empty() {
+                prompt.push_str(&format!("\nFinding {}: {}\n", index + 1, finding.description));
+            }
+        }
+
+        if let Some(risk) = report.data.get("risk").filter(|risk| !risk.is_null()) {
+            prompt.push_str(&format!("\n## Risk Assessment\n\n```json\n{}\n```\n", serde_json::to_string_pretty(risk).unwrap_or_default()));
+        }
+        if !report.body.is_empty() {
+            prompt.push_str(&format!("\n## Report\n\n{}\n", report.body));
+        }
+
+        if !self.files.is_empty() {
+            let paths: Vec<&str> = self.files.iter().map(|diff| diff.path()).collect();
+            prompt.push_str(&format!("\n## Diff\n\nChanged files: {}\n", paths.join(", ")));
+
+            // The hunks findings point at, or the whole diff when none do
+            let mut flagged: Vec<(&str, &DiffHunk)> = Vec::new();
+            for finding in self.findings() {
+                if let Some(file) = &finding.file {
+                    for hunk in self.hunks_at(file, finding.line) {
+                        if !flagged.iter().any(|(path, seen)| *path == hunk.0 && seen.header == hunk.1.header) {
+                            flagged.push(hunk);
+                        }
+                    }
+                }
+            }
+            if flagged.is_empty() {
+                flagged = self.files.iter().flat_map(|diff| diff.hunks.iter().map(move |hunk| (diff.path(), hunk))).collect();
+            }
+
+            let mut hunks = String::new();
+            for (path, hunk) in flagged {
+                let rendered = render_hunk(path, hunk);
+                if hunks.len() + rendered.len() > MAX_HUNK_CHARS {
+                    hunks.push_str("[further hunks omitted]\n");
+                    break;
+                }
+                hunks.push_str(&rendered);
+            }
+            prompt.push_str(&format!("\n```diff\n{}```\n", hunks));
+        }
+
+        if !repo_context.is_empty() {
+            prompt.push_str(&format!("\n## Repository\n\n{}\n", repo_context));
+        }
+        prompt
+    }
+}
+
+impl RunRecord {
+    /// Whether the run failed its severity gate
+    pub fn failed(&self) -> bool {
+        self.report.gate == Some(GateAction::Fail)
+    }
+}
+
+/// ` (file:line)` for a finding with a location
+fn location(finding: &Finding) -> String {
+    match (&finding.file, finding.line) {
+        (Some(file), Some(line)) => format!(" ({}:{})", file, line),
+        (Some(file), None) => format!(" ({})", file),
+        _ => String::new(),
+    }
+}
+
+/// Whether a diff path and a path from a finding or the user name the same file
+fn same_file(diff_path: &str, path: &str) -> bool {
+    let path = path.trim_start_matches("./");
+    diff_path == path || diff_path.ends_with(&format!("/{}", path)) || path.ends_with(&format!("/{}", diff_path))
+}
+
+/// A hunk as unified diff text, under its file name
+fn render_hunk(path: &str, hunk: &DiffHunk) -> String {
+    let mut text = format!("--- {}\n{}\n", path, hunk.header);
+    for line in &hunk.lines {
+        let marker = match line.kind {
+            DiffLineKind::Added => '+',
+            DiffLineKind::Removed => '-',
+            DiffLineKind::Context => ' ',
+        };
+        text.push(marker);
+        text.push_str(&line.content);
+        text.push('\n');
+    }
+    text
+}
+
+/// Branch, latest commit and uncommitted changes of the repository at `dir`, for the model
+///
+/// Empty outside a git repository.
+pub fn repo_context(dir: &Path) -> String {
+    let git = |args: &[&str]| {
+        std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    let Some(branch) = git(&["rev-parse", "--abbrev-ref", "HEAD"]) else {
+        return String::new();
+    };
+    let mut context = format!("Branch: {}", branch);
+    if let Some(commit) = git(&["log", "-1", "--format=%h %s"]) {
+        context.push_str(&format!("\nHEAD: {}", commit));
+    }
+    if let Some(status) = git(&["status", "--short"]).filter(|status| !status.is_empty()) {
+        let lines: Vec<&str> = status.lines().collect();
+        context.push_str(&format!("\nUncommitted changes:\n{}", lines.iter().take(20).copied().collect::<Vec<_>>().join("\n")));
+        if lines.len() > 20 {
+            context.push_str(&format!("\n... and {} more", lines.len() - 20));
+        }
+    }
+    context
+}
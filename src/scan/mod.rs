@@ -0,0 +1,370 @@
+// Bounded repository scanning
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::hash::sha256_hex;
+
+/// Limits on how much of a directory tree a scan reads (the `context` section of the config)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanLimits {
+    /// Most files a scan collects
+    pub max_files: usize,
+
+    /// Deepest directory level a scan descends into, counting the scanned directory as 0
+    pub max_depth: usize,
+
+    /// Most bytes of collected files, by size on disk
+    pub max_total_bytes: u64,
+
+    /// Longest a scan may take, in seconds
+    pub scan_timeout_secs: u64,
+}
+
+impl Default for ScanLimits {
+    fn default() -> Self {
+        Self {
+            max_files: 2_000,
+            max_depth: 16,
+            max_total_bytes: 64 * 1024 * 1024,
+            scan_timeout_secs: 30,
+        }
+    }
+}
+
+/// A limit a scan ran into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanLimit {
+    /// `context.max_files` files were collected
+    MaxFiles(usize),
+    /// Directories below `context.max_depth` were skipped
+    MaxDepth(usize),
+    /// Collected files reached `context.max_total_bytes`
+    MaxTotalBytes(u64),
+    /// The scan ran for `context.scan_timeout_secs`
+    Timeout(u64),
+}
+
+impl fmt::Display for ScanLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanLimit::MaxFiles(limit) => write!(f, "stopped after {} files (context.max_files)", limit),
+            ScanLimit::MaxDepth(limit) => write!(f, "skipped directories more than {} levels deep (context.max_depth)", limit),
+            ScanLimit::MaxTotalBytes(limit) => write!(f, "stopped at {} bytes of files (context.max_total_bytes)", limit),
+            ScanLimit::Timeout(secs) => write!(f, "stopped after {}s (context.scan_timeout_secs)", secs),
+        }
+    }
+}
+
+/// Files found by a scan
+#[derive(Debug, Clone, Default)]
+pub struct Scan {
+    /// Matching files, sorted
+    pub files: Vec<PathBuf>,
+
+    /// Size of the matching files on disk
+    pub total_bytes: u64,
+
+    /// Limits the scan ran into; when set, `files` is incomplete
+    pub limits_hit: Vec<ScanLimit>,
+}
+
+impl Scan {
+    /// One line describing the limits hit, if any
+    pub fn limit_summary(&self) -> Option<String> {
+        if self.limits_hit.is_empty() {
+            return None;
+        }
+
+        Some(self.limits_hit.iter().map(|limit| limit.to_string()).collect::<Vec<_>>().join("; "))
+    }
+}
+
+/// Counts reported while a scan runs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanProgress {
+    /// Directories read so far
+    pub directories: usize,
+
+    /// Matching files collected so far
+    pub files: usize,
+
+    /// Size of the matching files collected so far
+    pub bytes: u64,
+}
+
+/// How often a running scan reports progress
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Most threads a scan reads directories with
+const MAX_SCAN_THREADS: usize = 8;
+
+/// State shared between the scan workers
+struct ScanState {
+    /// Directories waiting to be read, with their depth
+    pending: Vec<(PathBuf, usize)>,
+
+    /// Workers currently reading a directory
+    active: usize,
+
+    /// Set when a limit is hit or the scan is abandoned
+    stopped: bool,
+
+    /// Failure reading the scanned directory itself
+    error: Option<anyhow::Error>,
+
+    progress: ScanProgress,
+    scan: Scan,
+}
+
+impl ScanState {
+    fn finished(&self) -> bool {
+        self.stopped || (self.pending.is_empty() && self.active == 0)
+    }
+
+    fn hit(&mut self, limit: ScanLimit) {
+        if !self.scan.limits_hit.contains(&limit) {
+            self.scan.limits_hit.push(limit);
+        }
+    }
+}
+
+struct Shared {
+    state: Mutex<ScanState>,
+    changed: Condvar,
+}
+
+/// Collect the files under `root` accepted by `include`, within the limits
+///
+/// Hidden entries are skipped and symbolic links aren't followed, so link cycles can't trap
+/// the scan. Hitting a limit isn't an error; it's reported in [`Scan::limits_hit`].
+pub fn scan_files<F>(root: &Path, limits: &ScanLimits, include: F) -> Result<Scan>
+where
+    F: Fn(&Path) -> bool + Send + Sync + 'static,
+{
+    scan_files_with_progress(root, limits, include, |_| {})
+}
+
+/// Like [`scan_files`], calling `progress` with running counts while the scan runs
+///
+/// Directories are read on several threads. The timeout holds even when a read blocks (e.g. on
+/// a stalled network share): the scan returns what it has and leaves the blocked read behind.
+/// Unreadable subdirectories are skipped; only an unreadable `root` is an error.
+pub fn scan_files_with_progress<F, P>(root: &Path, limits: &ScanLimits, include: F, mut progress: P) -> Result<Scan>
+where
+    F: Fn(&Path) -> bool + Send + Sync + 'static,
+    P: FnMut(&ScanProgress),
+{
+    let deadline = Instant::now() + Duration::from_secs(limits.scan_timeout_secs);
+    let shared = Arc::new(Shared {
+        state: Mutex::new(ScanState {
+            pending: vec![(root.to_path_buf(), 0)],
+            active: 0,
+            stopped: false,
+            error: None,
+            progress: ScanProgress::default(),
+            scan: Scan::default(),
+        }),
+        changed: Condvar::new(),
+    });
+    let include = Arc::new(include);
+
+    let workers = thread::available_parallelism().map(|n| n.get()).unwrap_or(1).min(MAX_SCAN_THREADS);
+    for _ in 0..workers {
+        let shared = Arc::clone(&shared);
+        let include = Arc::clone(&include);
+        let limits = limits.clone();
+        thread::spawn(move || scan_worker(&shared, &limits, &*include));
+    }
+
+    let mut state = shared.state.lock().unwrap_or_else(|e| e.into_inner());
+    while !state.finished() {
+        let now = Instant::now();
+        if now >= deadline {
+            state.hit(ScanLimit::Timeout(limits.scan_timeout_secs));
+            break;
+        }
+
+        state = shared.changed.wait_timeout(state, PROGRESS_INTERVAL.min(deadline - now))
+            .unwrap_or_else(|e| e.into_inner())
+            .0;
+        progress(&state.progress);
+    }
+
+    progress(&state.progress);
+
+    // Stop the workers; any still blocked on a read exit once it returns
+    state.stopped = true;
+    shared.changed.notify_all();
+    if let Some(error) = state.error.take() {
+        return Err(error);
+    }
+
+    let mut scan = std::mem::take(&mut state.scan);
+    drop(state);
+
+    scan.files.sort();
+    Ok(scan)
+}
+
+/// Read directories from the shared queue until the scan finishes
+fn scan_worker<F>(shared: &Shared, limits: &ScanLimits, include: &F)
+where
+    F: Fn(&Path) -> bool,
+{
+    loop {
+        let (dir, depth) = {
+            let mut state = shared.state.lock().unwrap_or_else(|e| e.into_inner());
+            loop {
+                if state.finished() {
+                    return;
+                }
+                if let Some(job) = state.pending.pop() {
+                    state.active += 1;
+                    break job;
+                }
+                state = shared.changed.wait(state).unwrap_or_else(|e| e.into_inner());
+            }
+        };
+
+        // Read without holding the lock so other workers keep going
+        let listing = list_directory(&dir, depth, limits, include);
+
+        let mut state = shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.active -= 1;
+        match listing {
+            Ok(listing) if !state.stopped => merge_listing(&mut state, listing, limits),
+            Ok(_) => {},
+            Err(error) if depth == 0 => {
+                state.error = Some(error);
+                state.stopped = true;
+            },
+            Err(error) => tracing::debug!("Skipping unreadable directory: {:#}", error),
+        }
+        drop(state);
+        shared.changed.notify_all();
+    }
+}
+
+/// What one directory contributes to a scan
+struct Listing {
+    /// Matching files with their sizes, in name order
+    files: Vec<(PathBuf, u64)>,
+
+    /// Subdirectories to visit, in name order
+    subdirs: Vec<(PathBuf, usize)>,
+
+    /// Whether subdirectories were skipped for being too deep
+    too_deep: bool,
+}
+
+fn list_directory<F>(dir: &Path, depth: usize, limits: &ScanLimits, include: &F) -> Result<Listing>
+where
+    F: Fn(&Path) -> bool,
+{
+    let mut entries: Vec<fs::DirEntry> = fs::read_dir(dir)
+        .context(format!("Failed to read directory: {}", dir.display()))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .collect();
+    entries.sort_by_key(|entry| entry.file_name());
+
+    let mut listing = Listing { files: Vec::new(), subdirs: Vec::new(), too_deep: false };
+    for entry in entries {
+        let Ok(file_type) = entry.file_type() else { continue };
+        let path = entry.path();
+
+        if file_type.is_dir() {
+            if depth < limits.max_depth {
+                listing.subdirs.push((path, depth + 1));
+            } else {
+                listing.too_deep = true;
+            }
+        } else if file_type.is_file() && include(&path) {
+            let size = entry.metadata().map(|metadata| metadata.len()).unwrap_or_default();
+            listing.files.push((path, size));
+        }
+    }
+
+    Ok(listing)
+}
+
+/// Add a directory's files to the scan, stopping at the file and byte limits
+fn merge_listing(state: &mut ScanState, listing: Listing, limits: &ScanLimits) {
+    state.progress.directories += 1;
+    if listing.too_deep {
+        state.hit(ScanLimit::MaxDepth(limits.max_depth));
+    }
+
+    for (path, size) in listing.files {
+        if state.scan.files.len() >= limits.max_files {
+            state.hit(ScanLimit::MaxFiles(limits.max_files));
+            state.stopped = true;
+            return;
+        }
+        if state.scan.total_bytes + size > limits.max_total_bytes {
+            state.hit(ScanLimit::MaxTotalBytes(limits.max_total_bytes));
+            state.stopped = true;
+            return;
+        }
+
+        state.scan.total_bytes += size;
+        state.scan.files.push(path);
+    }
+    state.progress.files = state.scan.files.len();
+    state.progress.bytes = state.scan.total_bytes;
+
+    // Visit subdirectories in name order
+    state.pending.extend(listing.subdirs.into_iter().rev());
+}
+
+/// A scanned file's contents with their hash
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileContent {
+    /// File contents
+    pub text: String,
+
+    /// SHA-256 of the contents, in hex
+    pub hash: String,
+}
+
+/// Read and hash scanned files on several threads, in the order given; unreadable files are `None`
+///
+/// The hash identifies files with the same contents, such as copied fixtures, without
+/// comparing the contents themselves.
+pub fn read_files_hashed(paths: &[PathBuf]) -> Vec<Option<FileContent>> {
+    map_files(paths, |path| {
+        let text = fs::read_to_string(path).ok()?;
+        let hash = sha256_hex(text.as_bytes());
+        Some(FileContent { text, hash })
+    })
+}
+
+/// Apply `f` to each path on several threads, keeping the order of the paths
+fn map_files<T, F>(paths: &[PathBuf], f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&Path) -> T + Sync,
+{
+    let workers = thread::available_parallelism().map(|n| n.get()).unwrap_or(1).min(MAX_SCAN_THREADS);
+    let chunk_size = paths.len().div_ceil(workers).max(1);
+    let f = &f;
+
+    thread::scope(|scope| {
+        let readers: Vec<_> = paths.chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(|path| f(path)).collect::<Vec<_>>()))
+            .collect();
+
+        readers.into_iter()
+            .flat_map(|reader| reader.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            .collect()
+    })
+}
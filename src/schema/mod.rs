@@ -0,0 +1,143 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Registered test data schema
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestDataSchema {
+    /// Schema name
+    pub name: String,
+
+    /// Schema definition (field descriptions, JSON Schema, SQL DDL, etc.)
+    pub definition: String,
+
+    /// Constraints applied to generated data
+    #[serde(default)]
+    pub constraints: Vec<String>,
+
+    /// Relationships to other schemas (e.g. "user_id references user-profile.id")
+    #[serde(default)]
+    pub relationships: Vec<String>,
+
+    /// Schema description
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl TestDataSchema {
+    /// Create a new schema
+    pub fn new(name: String, definition: String) -> Self {
+        Self {
+            name,
+            definition,
+            constraints: Vec::new(),
+            relationships: Vec::new(),
+            description: None,
+        }
+    }
+
+    /// Render the schema for use in a prompt
+    pub fn to_prompt(&self) -> String {
+        let mut prompt = format!("{}\n\n{}", self.name, self.definition.trim());
+
+        if !self.relationships.is_empty() {
+            prompt.push_str("\n\nRelationships:\n");
+            for relationship in &self.relationships {
+                prompt.push_str(&format!("- {}\n", relationship));
+            }
+        }
+
+        prompt
+    }
+}
+
+/// Schema registry configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchemaRegistryConfig {
+    /// Schemas
+    pub schemas: HashMap<String, TestDataSchema>,
+}
+
+/// Schema registry
+pub struct SchemaRegistry {
+    /// Schemas
+    schemas: HashMap<String, TestDataSchema>,
+
+    /// Configuration path
+    config_path: PathBuf,
+}
+
+impl SchemaRegistry {
+    /// Create a new schema registry
+    pub fn new() -> Result<Self> {
+        // Config file path
+        let config_path = crate::config::config_dir()?.join("schemas.json");
+
+        // Load config if it exists, otherwise create default
+        let config = if config_path.exists() {
+            let config_str = fs::read_to_string(&config_path)
+                .map_err(|e| anyhow!("Failed to read schema registry: {}", e))?;
+
+            serde_json::from_str(&config_str)
+                .map_err(|e| anyhow!("Failed to parse schema registry: {}", e))?
+        } else {
+            SchemaRegistryConfig::default()
+        };
+
+        Ok(Self {
+            schemas: config.schemas,
+            config_path,
+        })
+    }
+
+    /// Add or replace a schema
+    pub fn add_schema(&mut self, schema: TestDataSchema) -> Result<()> {
+        if schema.definition.trim().is_empty() {
+            return Err(anyhow!("Schema definition cannot be empty"));
+        }
+
+        self.schemas.insert(schema.name.clone(), schema);
+
+        // Save config
+        self.save_config()
+    }
+
+    /// Get a schema
+    pub fn get_schema(&self, name: &str) -> Option<&TestDataSchema> {
+        self.schemas.get(name)
+    }
+
+    /// List schemas, sorted by name
+    pub fn list_schemas(&self) -> Vec<&TestDataSchema> {
+        let mut schemas: Vec<&TestDataSchema> = self.schemas.values().collect();
+        schemas.sort_by(|a, b| a.name.cmp(&b.name));
+        schemas
+    }
+
+    /// Remove a schema
+    pub fn remove_schema(&mut self, name: &str) -> Result<()> {
+        if self.schemas.remove(name).is_none() {
+            return Err(anyhow!("Schema not found: {}", name));
+        }
+
+        // Save config
+        self.save_config()
+    }
+
+    /// Save config
+    fn save_config(&self) -> Result<()> {
+        let config = SchemaRegistryConfig {
+            schemas: self.schemas.clone(),
+        };
+
+        let config_str = serde_json::to_string_pretty(&config)
+            .map_err(|e| anyhow!("Failed to serialize schema registry: {}", e))?;
+
+        fs::write(&self.config_path, config_str)
+            .map_err(|e| anyhow!("Failed to write schema registry: {}", e))?;
+
+        Ok(())
+    }
+}
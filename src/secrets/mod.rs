@@ -0,0 +1,136 @@
+// API keys and tokens in the OS keyring
+
+use anyhow::{Result, anyhow};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Keyring service name for every QitOps secret
+pub const SERVICE: &str = "qitops";
+
+/// Keyring account holding the GitHub token
+pub const GITHUB_ACCOUNT: &str = "github";
+
+/// Environment variable scoping every keyring account to a namespace, so tenants of a shared
+/// deployment each read their own secrets
+pub const NAMESPACE_ENV: &str = "QITOPS_SECRETS_NAMESPACE";
+
+/// Whether the keyring was turned off with `--no-keyring`
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Stop using the keyring for the rest of the process; secrets stay in the config files
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether the `QITOPS_NO_KEYRING` environment variable turns off the keyring
+pub fn disabled_by_env() -> bool {
+    std::env::var("QITOPS_NO_KEYRING")
+        .is_ok_and(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+}
+
+/// Whether secrets are read from and written to the keyring
+pub fn is_enabled() -> bool {
+    !DISABLED.load(Ordering::Relaxed) && !disabled_by_env()
+}
+
+/// Keyring account holding an LLM provider's API key
+pub fn provider_account(provider_type: &str) -> String {
+    format!("llm.{}", provider_type)
+}
+
+/// Keyring account of a secret within a namespace
+pub fn namespaced_account(namespace: &str, account: &str) -> String {
+    format!("{}/{}", namespace, account)
+}
+
+/// Keyring account of a secret in the namespace of `QITOPS_SECRETS_NAMESPACE`, if set
+fn scoped(account: &str) -> String {
+    match std::env::var(NAMESPACE_ENV) {
+        Ok(namespace) if !namespace.is_empty() => namespaced_account(&namespace, account),
+        _ => account.to_string(),
+    }
+}
+
+/// Read a secret, or `None` if the keyring is off, unavailable or has no entry
+pub fn get(account: &str) -> Option<String> {
+    if !is_enabled() {
+        return None;
+    }
+
+    match keyring::Entry::new(SERVICE, &scoped(account)).and_then(|entry| entry.get_password()) {
+        Ok(secret) => Some(secret),
+        Err(keyring::Error::NoEntry) => None,
+        Err(e) => {
+            tracing::debug!("Could not read {} from the OS keyring: {}", account, e);
+            None
+        }
+    }
+}
+
+/// Write a secret
+pub fn set(account: &str, secret: &str) -> Result<()> {
+    if !is_enabled() {
+        return Err(anyhow!("the OS keyring is disabled (--no-keyring)"));
+    }
+
+    keyring::Entry::new(SERVICE, &scoped(account))
+        .and_then(|entry| entry.set_password(secret))
+        .map_err(|e| anyhow!("could not write {} to the OS keyring: {}", account, e))
+}
+
+/// Remove a secret; a missing entry is not an error
+pub fn delete(account: &str) -> Result<()> {
+    if !is_enabled() {
+        return Ok(());
+    }
+
+    match keyring::Entry::new(SERVICE, &scoped(account)).and_then(|entry| entry.delete_credential()) {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(anyhow!("could not remove {} from the OS keyring: {}", account, e)),
+    }
+}
+
+/// Move a secret from a config value into the keyring
+///
+/// Returns the value to write to the config file: `None` once the keyring holds the secret,
+/// or the secret itself when the keyring is off or can't be written.
+pub fn store(account: &str, secret: Option<&str>) -> Option<String> {
+    let secret = secret?;
+
+    if !is_enabled() {
+        return Some(secret.to_string());
+    }
+
+    match set(account, secret) {
+        Ok(()) => None,
+        Err(e) => {
+            tracing::warn!("Saving {} in the config file instead: {} (pass --no-keyring to keep secrets in config files)", account, e);
+            Some(secret.to_string())
+        }
+    }
+}
+
+/// Write a file only its owner can read, for secrets that can't go to the keyring
+///
+/// The file is created with mode 0600, so it is never readable by others, not even briefly,
+/// and an existing file is narrowed to 0600 before it is overwritten.
+pub fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(contents)?;
+
+    Ok(())
+}
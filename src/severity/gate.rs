@@ -0,0 +1,160 @@
+// Quality gates for CI: conditions on a run's structured result that fail the process
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::QitOpsError;
+use crate::severity::{GateAction, Severity, SeverityPolicy};
+
+/// Highest quality score, for a run without findings
+pub const MAX_SCORE: f64 = 10.0;
+
+/// A condition that fails a quality gate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailOn {
+    /// The severity policy's gate fails (`gate`)
+    Policy,
+    /// A finding at or above a severity (`high`)
+    Finding(Severity),
+    /// An overall risk at or above a level (`high-risk`)
+    Risk(Severity),
+}
+
+impl fmt::Display for FailOn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailOn::Policy => write!(f, "gate"),
+            FailOn::Finding(severity) => write!(f, "{}", severity),
+            FailOn::Risk(level) => write!(f, "{}-risk", level),
+        }
+    }
+}
+
+impl FromStr for FailOn {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim().to_lowercase();
+        if s == "gate" || s == "policy" {
+            return Ok(FailOn::Policy);
+        }
+        let condition = match s.strip_suffix("-risk") {
+            Some(level) => level.parse().map(FailOn::Risk),
+            None => s.parse().map(FailOn::Finding),
+        };
+
+        condition.map_err(|_| QitOpsError::InvalidInput(format!(
+            "Unknown gate condition: {} (expected a severity such as `high`, a risk level such as `high-risk`, or `gate`)", s,
+        )).into())
+    }
+}
+
+/// What fails a run in CI, set with `--gate`, `--fail-on` and `--min-score`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QualityGate {
+    /// Conditions that each fail the gate
+    pub fail_on: Vec<FailOn>,
+
+    /// Lowest passing quality score
+    pub min_score: Option<f64>,
+}
+
+impl QualityGate {
+    /// A gate from command line options, or `None` when none was given
+    ///
+    /// `--gate` alone fails on the severity policy; `--fail-on` and `--min-score` imply it.
+    pub fn from_args(gate: bool, fail_on: &[String], min_score: Option<f64>) -> Result<Option<Self>> {
+        if !gate && fail_on.is_empty() && min_score.is_none() {
+            return Ok(None);
+        }
+
+        let mut conditions = fail_on.iter()
+            .flat_map(|conditions| conditions.split(','))
+            .filter(|condition| !condition.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<FailOn>>>()?;
+        if conditions.is_empty() && min_score.is_none() {
+            conditions.push(FailOn::Policy);
+        }
+
+        if let Some(score) = min_score.filter(|score| !(0.0..=MAX_SCORE).contains(score)) {
+            return Err(QitOpsError::InvalidInput(format!("--min-score must be between 0 and {}, not {}", MAX_SCORE, score)).into());
+        }
+
+        Ok(Some(Self { fail_on: conditions, min_score }))
+    }
+
+    /// Whether a condition needs an overall risk, which only risk assessments have
+    pub fn needs_risk(&self) -> bool {
+        self.fail_on.iter().any(|condition| matches!(condition, FailOn::Risk(_)))
+    }
+
+    /// Check a run's findings, and its overall risk if it has one, against the gate
+    pub fn evaluate(&self, severities: &[Severity], overall_risk: Option<Severity>, policy: &SeverityPolicy) -> GateVerdict {
+        let score = quality_score(severities);
+
+        let mut failures = Vec::new();
+        for condition in &self.fail_on {
+            match *condition {
+                FailOn::Policy => {
+                    if policy.evaluate(severities.iter().copied()) == GateAction::Fail {
+                        failures.push(format!("findings at or above {} severity (the severity policy)", policy.fail_at));
+                    }
+                },
+                FailOn::Finding(at) => {
+                    let count = severities.iter().filter(|severity| **severity >= at).count();
+                    if count > 0 {
+                        failures.push(format!("{} finding(s) at or above {} severity", count, at));
+                    }
+                },
+                FailOn::Risk(at) => match overall_risk {
+                    Some(risk) if risk >= at => failures.push(format!("{} overall risk, at or above {}", risk, at)),
+                    Some(_) => {},
+                    None => failures.push("no overall risk was assessed".to_string()),
+                },
+            }
+        }
+        if let Some(min_score) = self.min_score.filter(|min_score| score < *min_score) {
+            failures.push(format!("quality score {:.1} is below {:.1}", score, min_score));
+        }
+
+        GateVerdict { score, failures }
+    }
+}
+
+/// Outcome of a quality gate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GateVerdict {
+    /// Quality score from 0 to 10
+    pub score: f64,
+
+    /// Why the gate failed, empty if it passed
+    pub failures: Vec<String>,
+}
+
+impl GateVerdict {
+    /// Whether the gate passed
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Quality score of a run from 0 to 10, lowered by each finding by its severity
+///
+/// Starting from 10, a critical finding costs 4 points, high 2, medium 1, low 0.5 and info
+/// nothing, so the score only depends on the findings and is the same on every run.
+pub fn quality_score(severities: &[Severity]) -> f64 {
+    let penalty: f64 = severities.iter()
+        .map(|severity| match severity {
+            Severity::Critical => 4.0,
+            Severity::High => 2.0,
+            Severity::Medium => 1.0,
+            Severity::Low => 0.5,
+            Severity::Info => 0.0,
+        })
+        .sum();
+
+    (MAX_SCORE - penalty).max(0.0)
+}
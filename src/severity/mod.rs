@@ -0,0 +1,172 @@
+pub mod gate;
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Severity of a finding, shared by all analysis agents
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", from = "String")]
+pub enum Severity {
+    /// Informational, no action needed
+    Info,
+    /// Minor issue
+    Low,
+    /// Should be addressed
+    #[default]
+    Medium,
+    /// Must be addressed before merging
+    High,
+    /// Must be addressed immediately
+    Critical,
+}
+
+impl Severity {
+    /// Lowercase name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = anyhow::Error;
+
+    /// Parse a severity, accepting common synonyms used by LLMs and other tools
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "info" | "informational" | "note" | "notice" | "none" => Ok(Severity::Info),
+            "low" | "minor" | "trivial" => Ok(Severity::Low),
+            "medium" | "moderate" | "warning" | "warn" => Ok(Severity::Medium),
+            "high" | "major" | "error" | "serious" => Ok(Severity::High),
+            "critical" | "blocker" | "severe" | "fatal" => Ok(Severity::Critical),
+            _ => Err(anyhow!("Unknown severity: {} (expected info, low, medium, high or critical)", s)),
+        }
+    }
+}
+
+impl From<String> for Severity {
+    /// Lenient conversion for model output: unknown words fall back to medium
+    fn from(s: String) -> Self {
+        s.parse().unwrap_or_else(|_| {
+            tracing::warn!("Unknown severity '{}', treating as medium", s);
+            Severity::Medium
+        })
+    }
+}
+
+/// What a gate does with a finding of a given severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GateAction {
+    /// Ignore the finding
+    Pass,
+    /// Report the finding without failing
+    Warn,
+    /// Fail the gate
+    Fail,
+}
+
+/// GitHub check run annotation level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationLevel {
+    /// Notice
+    Notice,
+    /// Warning
+    Warning,
+    /// Failure
+    Failure,
+}
+
+impl AnnotationLevel {
+    /// GitHub Actions workflow command for this level
+    pub fn workflow_command(&self) -> &'static str {
+        match self {
+            AnnotationLevel::Notice => "notice",
+            AnnotationLevel::Warning => "warning",
+            AnnotationLevel::Failure => "error",
+        }
+    }
+}
+
+/// Mapping from severities to gate behavior and annotation levels
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeverityPolicy {
+    /// Lowest severity that fails a gate
+    #[serde(default = "default_fail_at")]
+    pub fail_at: Severity,
+
+    /// Lowest severity that produces a gate warning
+    #[serde(default = "default_warn_at")]
+    pub warn_at: Severity,
+
+    /// Annotation level overrides per severity
+    #[serde(default)]
+    pub annotations: HashMap<Severity, AnnotationLevel>,
+}
+
+fn default_fail_at() -> Severity {
+    Severity::High
+}
+
+fn default_warn_at() -> Severity {
+    Severity::Medium
+}
+
+impl Default for SeverityPolicy {
+    fn default() -> Self {
+        Self {
+            fail_at: default_fail_at(),
+            warn_at: default_warn_at(),
+            annotations: HashMap::new(),
+        }
+    }
+}
+
+impl SeverityPolicy {
+    /// Gate action for a severity
+    pub fn gate_action(&self, severity: Severity) -> GateAction {
+        if severity >= self.fail_at {
+            GateAction::Fail
+        } else if severity >= self.warn_at {
+            GateAction::Warn
+        } else {
+            GateAction::Pass
+        }
+    }
+
+    /// GitHub annotation level for a severity
+    pub fn annotation_level(&self, severity: Severity) -> AnnotationLevel {
+        if let Some(level) = self.annotations.get(&severity) {
+            return *level;
+        }
+
+        match self.gate_action(severity) {
+            GateAction::Fail => AnnotationLevel::Failure,
+            GateAction::Warn => AnnotationLevel::Warning,
+            GateAction::Pass => AnnotationLevel::Notice,
+        }
+    }
+
+    /// Most severe gate action across a set of severities
+    pub fn evaluate<I: IntoIterator<Item = Severity>>(&self, severities: I) -> GateAction {
+        severities.into_iter()
+            .map(|severity| self.gate_action(severity))
+            .max()
+            .unwrap_or(GateAction::Pass)
+    }
+}
@@ -2,7 +2,9 @@ use anyhow::{Result, anyhow, Context};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
+
+use crate::error::QitOpsError;
 
 /// Source type
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -253,7 +255,7 @@ impl SourceManager {
     pub fn add_source(&mut self, source: Source) -> Result<()> {
         // Validate source path
         if !source.path.exists() {
-            return Err(anyhow!("Source path does not exist: {}", source.path.display()));
+            return Err(QitOpsError::Source(format!("Source path does not exist: {}", source.path.display())).into());
         }
 
         // Add source
@@ -276,7 +278,7 @@ impl SourceManager {
     /// Remove a source
     pub fn remove_source(&mut self, id: &str) -> Result<()> {
         if self.sources.remove(id).is_none() {
-            return Err(anyhow!("Source not found: {}", id));
+            return Err(QitOpsError::Source(format!("Source not found: {}", id)).into());
         }
 
         // Save config
@@ -289,7 +291,7 @@ impl SourceManager {
 
         for id in ids {
             let source = self.get_source(id)
-                .ok_or_else(|| anyhow!("Source not found: {}", id))?;
+                .ok_or_else(|| QitOpsError::Source(format!("Source not found: {}", id)))?;
 
             let source_content = source.get_content()?;
 
@@ -0,0 +1,225 @@
+// Opt-in anonymous usage telemetry
+
+use aes_gcm::aead::OsRng;
+use aes_gcm::aead::rand_core::RngCore;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Environment variable that turns telemetry off regardless of the setting (`0`, `false` or `off`)
+pub const TELEMETRY_ENV: &str = "QITOPS_TELEMETRY";
+
+/// Most events kept in the local queue; older ones are dropped
+pub const MAX_QUEUED_EVENTS: usize = 1_000;
+
+/// Saved telemetry settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TelemetrySettings {
+    /// Whether the user opted in
+    #[serde(default)]
+    enabled: bool,
+
+    /// Random ID of this installation, created on opt-in and deleted on opt-out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    install_id: Option<String>,
+}
+
+/// Whether a command succeeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Success,
+    Error,
+}
+
+/// One command run; holds names and numbers only, never arguments or content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryEvent {
+    /// Random installation ID
+    pub install_id: String,
+
+    /// Subcommand path, e.g. `run test-gen`
+    pub command: String,
+
+    /// How long the command ran, in milliseconds
+    pub duration_ms: u64,
+
+    /// Whether the command succeeded
+    pub outcome: Outcome,
+
+    /// Kind of error, e.g. `llm.rate_limit`, when the command failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_class: Option<String>,
+
+    /// QitOps version
+    pub version: String,
+
+    /// Operating system family
+    pub os: String,
+
+    /// When the command ended, in seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+/// Telemetry settings and the local event queue
+pub struct Telemetry {
+    /// Directory holding the settings and the queue
+    dir: PathBuf,
+
+    /// Current settings
+    settings: TelemetrySettings,
+}
+
+impl Telemetry {
+    /// Open telemetry in the config directory
+    pub fn open() -> Result<Self> {
+        Self::at(crate::config::config_dir()?.join("telemetry"))
+    }
+
+    /// Open telemetry stored in a directory
+    pub fn at(dir: PathBuf) -> Result<Self> {
+        let path = dir.join("settings.json");
+        let settings = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?)
+                .with_context(|| format!("Failed to parse {}", path.display()))?
+        } else {
+            TelemetrySettings::default()
+        };
+
+        Ok(Self { dir, settings })
+    }
+
+    /// Whether the user opted in
+    pub fn is_opted_in(&self) -> bool {
+        self.settings.enabled
+    }
+
+    /// Whether events are recorded: opted in and not turned off by `QITOPS_TELEMETRY` or `DO_NOT_TRACK`
+    pub fn is_enabled(&self) -> bool {
+        self.settings.enabled && !disabled_by_env()
+    }
+
+    /// Random ID of this installation, if opted in
+    pub fn install_id(&self) -> Option<&str> {
+        self.settings.install_id.as_deref()
+    }
+
+    /// File holding the queued events
+    pub fn queue_path(&self) -> PathBuf {
+        self.dir.join("queue.jsonl")
+    }
+
+    /// Opt in or out; opting out deletes the installation ID and the queued events
+    pub fn set_enabled(&mut self, enabled: bool) -> Result<()> {
+        if enabled {
+            if self.settings.install_id.is_none() {
+                let mut bytes = [0u8; 16];
+                OsRng.fill_bytes(&mut bytes);
+                self.settings.install_id = Some(crate::hash::hex(&bytes));
+            }
+        } else {
+            self.settings.install_id = None;
+            self.clear()?;
+        }
+
+        self.settings.enabled = enabled;
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.dir.join("settings.json"), serde_json::to_string_pretty(&self.settings)?)?;
+        Ok(())
+    }
+
+    /// Queue an event for a finished command, if telemetry is enabled
+    pub fn record(&self, command: &str, duration: Duration, error: Option<&anyhow::Error>) -> Result<()> {
+        let (true, Some(install_id)) = (self.is_enabled(), &self.settings.install_id) else {
+            return Ok(());
+        };
+
+        let event = TelemetryEvent {
+            install_id: install_id.clone(),
+            command: command.to_string(),
+            duration_ms: duration.as_millis() as u64,
+            outcome: if error.is_some() { Outcome::Error } else { Outcome::Success },
+            error_class: error.map(|e| crate::error::error_class(e).to_string()),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default(),
+        };
+
+        fs::create_dir_all(&self.dir)?;
+        let mut queue = fs::OpenOptions::new().create(true).append(true).open(self.queue_path())?;
+        writeln!(queue, "{}", serde_json::to_string(&event)?)?;
+        drop(queue);
+
+        self.trim_queue()
+    }
+
+    /// Drop the oldest events beyond `MAX_QUEUED_EVENTS`
+    fn trim_queue(&self) -> Result<()> {
+        let events = self.queued()?;
+        if events.len() <= MAX_QUEUED_EVENTS {
+            return Ok(());
+        }
+
+        let kept: Vec<String> = events[events.len() - MAX_QUEUED_EVENTS..].iter()
+            .map(serde_json::to_string)
+            .collect::<Result<_, _>>()?;
+        fs::write(self.queue_path(), kept.join("\n") + "\n")?;
+        Ok(())
+    }
+
+    /// Events waiting in the local queue, oldest first
+    pub fn queued(&self) -> Result<Vec<TelemetryEvent>> {
+        let path = self.queue_path();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        Ok(fs::read_to_string(&path)?
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// Delete the queued events
+    pub fn clear(&self) -> Result<()> {
+        match fs::remove_file(self.queue_path()) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Whether `QITOPS_TELEMETRY=0` or `DO_NOT_TRACK=1` turns telemetry off
+pub fn disabled_by_env() -> bool {
+    let off = |name: &str, values: &[&str]| std::env::var(name)
+        .is_ok_and(|value| values.contains(&value.to_lowercase().as_str()));
+
+    off(TELEMETRY_ENV, &["0", "false", "off", "no"]) || off("DO_NOT_TRACK", &["1", "true", "yes"])
+}
+
+/// Queue an event for a finished command; telemetry failures never affect the command
+pub fn record(command: &str, duration: Duration, error: Option<&anyhow::Error>) {
+    if disabled_by_env() || command.is_empty() {
+        return;
+    }
+
+    if let Err(e) = Telemetry::open().and_then(|telemetry| telemetry.record(command, duration, error)) {
+        tracing::debug!("Could not record telemetry: {}", e);
+    }
+}
+
+/// Write the queued events as JSON lines to a file, or to stdout when `path` is `None`
+pub fn export(telemetry: &Telemetry, path: Option<&Path>) -> Result<usize> {
+    let events = telemetry.queued()?;
+    let lines: Vec<String> = events.iter().map(serde_json::to_string).collect::<Result<_, _>>()?;
+
+    match path {
+        Some(path) => fs::write(path, lines.iter().map(|line| format!("{}\n", line)).collect::<String>())?,
+        None => lines.iter().for_each(|line| println!("{}", line)),
+    }
+
+    Ok(events.len())
+}
@@ -0,0 +1,31 @@
+use qitops_agent::attest::{self, SigningKey};
+
+#[test]
+fn signed_results_verify_until_edited() {
+    let key = SigningKey::new("0123456789abcdef0123456789abcdef").unwrap();
+    let mut result = serde_json::json!({
+        "command": "risk",
+        "gate": "fail",
+        "findings": [{"severity": "high", "title": "SQL injection"}],
+    });
+
+    attest::sign(&mut result, &key).unwrap();
+    let attestation = attest::verify(&result, &key).unwrap();
+    assert_eq!(attestation.key_id, key.key_id());
+
+    // Key order and whitespace don't matter
+    let reparsed: serde_json::Value = serde_json::from_str(&serde_json::to_string_pretty(&result).unwrap()).unwrap();
+    assert!(attest::verify(&reparsed, &key).is_ok());
+
+    let mut edited = result.clone();
+    edited["gate"] = serde_json::json!("pass");
+    assert!(attest::verify(&edited, &key).unwrap_err().to_string().contains("changed after it was signed"));
+
+    let other = SigningKey::new("fedcba9876543210fedcba9876543210").unwrap();
+    assert!(attest::verify(&result, &other).is_err());
+}
+
+#[test]
+fn short_keys_are_rejected() {
+    assert!(SigningKey::new("secret").is_err());
+}
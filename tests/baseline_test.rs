@@ -0,0 +1,96 @@
+use qitops_agent::agent::baseline::{Baseline, BaselineMode, apply_baseline};
+use qitops_agent::agent::findings::Finding;
+use qitops_agent::severity::Severity;
+
+mod common;
+use common::scratch;
+
+const DIFF: &str = "diff --git a/src/auth.rs b/src/auth.rs
+--- a/src/auth.rs
++++ b/src/auth.rs
+@@ -1,3 +1,6 @@
+ fn login() {
++    // qitops-ignore: test credentials for the local fixture
++    let password = \"hunter2\";
++    let token = request.header(\"token\");
+     check();
+ }
+";
+
+fn finding(file: &str, line: u32, title: &str) -> Finding {
+    Finding {
+        file: Some(file.to_string()),
+        line: Some(line),
+        severity: Severity::High,
+        title: title.to_string(),
+        description: String::new(),
+    }
+}
+
+fn titles(findings: &[Finding]) -> Vec<&str> {
+    findings.iter().map(|finding| finding.title.as_str()).collect()
+}
+
+#[test]
+fn baselined_findings_are_suppressed_and_new_ones_reported() {
+    let path = scratch("baseline-use").join(".qitops-baseline.json");
+
+    // Without a baseline everything is reported
+    let first = vec![finding("src/auth.rs", 4, "Token read without validation")];
+    let outcome = apply_baseline(first.clone(), DIFF, BaselineMode::Use, &path).unwrap();
+    assert_eq!(titles(&outcome.new), ["Token read without validation"]);
+
+    // Writing the baseline accepts the current findings
+    let outcome = apply_baseline(first, DIFF, BaselineMode::Write, &path).unwrap();
+    assert!(outcome.baseline_written && outcome.new.is_empty());
+    let mut baseline = Baseline::load(&path).unwrap().unwrap();
+    assert_eq!(baseline.findings.len(), 1);
+    baseline.findings[0].reason = Some("validated by the gateway".to_string());
+    baseline.save(&path).unwrap();
+
+    // A later run matches the baselined finding even on another line, with another title case
+    let later = vec![
+        finding("src/auth.rs", 9, "token read without  validation"),
+        finding("src/auth.rs", 5, "Login has no rate limit"),
+    ];
+    let outcome = apply_baseline(later.clone(), DIFF, BaselineMode::Use, &path).unwrap();
+    assert_eq!(titles(&outcome.new), ["Login has no rate limit"]);
+    assert_eq!(outcome.baselined.len(), 1);
+    assert_eq!(outcome.baselined[0].reason, "validated by the gateway");
+    assert!(!outcome.baseline_written);
+
+    // The same title in another file is a different finding
+    let elsewhere = vec![finding("src/admin.rs", 4, "Token read without validation")];
+    let outcome = apply_baseline(elsewhere, DIFF, BaselineMode::Use, &path).unwrap();
+    assert_eq!(outcome.new.len(), 1);
+
+    // Ignoring the baseline reports everything, and rewriting it keeps the recorded reasons
+    let outcome = apply_baseline(later.clone(), DIFF, BaselineMode::Ignore, &path).unwrap();
+    assert_eq!(outcome.new.len(), 2);
+    apply_baseline(later, DIFF, BaselineMode::Write, &path).unwrap();
+    let baseline = Baseline::load(&path).unwrap().unwrap();
+    let reasons: Vec<Option<&str>> = baseline.findings.iter().map(|entry| entry.reason.as_deref()).collect();
+    assert_eq!(reasons, [Some("validated by the gateway"), None]);
+}
+
+#[test]
+fn inline_suppressions_are_honored_in_every_mode() {
+    let path = scratch("baseline-inline").join(".qitops-baseline.json");
+    let findings = vec![
+        // The marker is on the line above the flagged one
+        finding("src/auth.rs", 3, "Hardcoded password"),
+        finding("src/auth.rs", 4, "Token read without validation"),
+    ];
+
+    for mode in [BaselineMode::Use, BaselineMode::Write, BaselineMode::Ignore] {
+        let outcome = apply_baseline(findings.clone(), DIFF, mode, &path).unwrap();
+        assert_eq!(outcome.suppressed.len(), 1, "{:?}", mode);
+        assert_eq!(outcome.suppressed[0].finding.title, "Hardcoded password");
+        assert_eq!(outcome.suppressed[0].reason, "test credentials for the local fixture");
+    }
+
+    // Suppressed findings are never written to the baseline
+    let baseline = Baseline::load(&path).unwrap().unwrap();
+    let titles: Vec<&str> = baseline.findings.iter().map(|entry| entry.title.as_str()).collect();
+    assert_eq!(titles, ["Token read without validation"]);
+}
@@ -0,0 +1,39 @@
+use qitops_agent::bench;
+use std::path::Path;
+
+#[tokio::test]
+async fn bundled_fixtures_score_full_marks_on_replay() {
+    let report = bench::run_suite(Path::new("fixtures"), None, |_| {}).await.unwrap();
+
+    assert_eq!(report.mode, "replay");
+    assert_eq!(report.cases.len(), 2);
+    for case in &report.cases {
+        assert_eq!(case.score, 1.0, "{} failed: {:?} {:?}", case.name, case.error, case.checks);
+    }
+    assert!(report.regressions.is_empty());
+}
+
+#[tokio::test]
+async fn lower_scores_than_the_baseline_are_regressions() {
+    let dir = std::env::temp_dir().join(format!("qitops-bench-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("source.py"), "def add(a, b):\n    return a + b\n").unwrap();
+    std::fs::write(dir.join("answer.md"), "## Test Case 1: Adds numbers\n\n## Test Case 2: Adds negatives\n").unwrap();
+    std::fs::write(dir.join("add.json"), r#"{
+        "agent": "test-gen",
+        "input": "source.py",
+        "replay": ["answer.md"],
+        "rubric": {"must_contain": ["adds numbers", "overflow"], "min_test_cases": 2}
+    }"#).unwrap();
+    std::fs::write(dir.join(bench::BASELINE_FILE), r#"{"add": 1.0}"#).unwrap();
+
+    let report = bench::run_suite(&dir, None, |_| {}).await.unwrap();
+    assert!((report.cases[0].score - 2.0 / 3.0).abs() < 1e-9);
+    assert_eq!(report.regressions.len(), 1);
+    assert_eq!(report.regressions[0].case, "add");
+
+    bench::save_baseline(&dir, &report).unwrap();
+    assert!(bench::regressions(&report.cases, &bench::load_baseline(&dir).unwrap()).is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,130 @@
+use qitops_agent::bot::{BotConfig, EXEC_DISABLED};
+use qitops_agent::bot::http::read_request_within;
+use qitops_agent::bot::quota::QuotaConfig;
+use qitops_agent::bot::server::{SharedBot, serve};
+use qitops_agent::llm::{LazyRouter, ProviderConfig, RouterConfig};
+
+mod common;
+use common::{Received, Reply, scratch};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+const API_KEY: &str = "bot-key";
+
+/// Start the bot API with a quota of one request per user, answering from a fake OpenAI server
+async fn start(name: &str, answers: &[&str]) -> (String, tokio::task::JoinHandle<Vec<Received>>) {
+    unsafe { std::env::set_var("HOME", scratch(name)) };
+    let (api_base, llm) = common::serve(answers.iter().map(|answer| Reply::completion(answer)).collect()).await;
+    let provider: ProviderConfig = serde_json::from_value(serde_json::json!({
+        "provider_type": "openai",
+        "api_key": "sk-test",
+        "api_base": api_base,
+        "default_model": "gpt-4o-mini",
+    })).unwrap();
+    let router = LazyRouter::with_config(RouterConfig {
+        providers: vec![provider],
+        default_provider: "openai".to_string(),
+        ..RouterConfig::default()
+    });
+    let config = BotConfig {
+        quotas: QuotaConfig { per_user: 1, ..QuotaConfig::default() },
+        ..BotConfig::default()
+    };
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(serve(listener, SharedBot::new(router, config, API_KEY.to_string())));
+    (address, llm)
+}
+
+async fn post(client: &reqwest::Client, address: &str, body: serde_json::Value) -> (u16, String) {
+    let response = client.post(format!("{}/v1/messages", address)).bearer_auth(API_KEY).json(&body).send().await.unwrap();
+    (response.status().as_u16(), response.text().await.unwrap())
+}
+
+#[tokio::test]
+async fn messages_over_quota_get_a_friendly_reply_without_calling_the_llm() {
+    let (address, llm) = start("bot-quota", &["Use qitops run risk.", "Pass --diff."]).await;
+    let client = reqwest::Client::new();
+
+    let (status, body) = post(&client, &address, serde_json::json!({"user": "U1", "channel": "C1", "message": "How do I assess risk?"})).await;
+    assert_eq!(status, 200);
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&body).unwrap()["reply"], "Use qitops run risk.");
+
+    // The second message of the same user is over quota, another user still gets an answer
+    let (status, body) = post(&client, &address, serde_json::json!({"user": "U1", "channel": "C1", "message": "And the diff?"})).await;
+    assert_eq!(status, 200);
+    let reply = serde_json::from_str::<serde_json::Value>(&body).unwrap()["reply"].as_str().unwrap().to_string();
+    assert!(reply.starts_with("You've reached your request limit for now."), "{}", reply);
+
+    let (_, body) = post(&client, &address, serde_json::json!({"user": "U2", "message": "Which flag takes the diff?"})).await;
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&body).unwrap()["reply"], "Pass --diff.");
+
+    let requests = llm.await.unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1].prompt().ends_with("Which flag takes the diff?"), "{}", requests[1].prompt());
+
+    let metrics: serde_json::Value = client.get(format!("{}/v1/quotas", address)).bearer_auth(API_KEY).send().await.unwrap().json().await.unwrap();
+    assert_eq!((metrics["accepted"].as_u64(), metrics["rejected_user"].as_u64()), (Some(2), Some(1)));
+}
+
+#[tokio::test]
+async fn invalid_requests_are_refused() {
+    let (address, _llm) = start("bot-invalid", &[]).await;
+    let client = reqwest::Client::new();
+
+    let (status, body) = post(&client, &address, serde_json::json!({"user": " ", "message": "Hi"})).await;
+    assert_eq!((status, body.as_str()), (400, "Invalid message: user is empty"));
+    let (status, _) = post(&client, &address, serde_json::json!({"message": "Hi"})).await;
+    assert_eq!(status, 400);
+
+    let response = client.get(format!("{}/v1/unknown", address)).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 404);
+
+    // Requests without the API key are refused
+    let message = serde_json::json!({"user": "U1", "message": "Hi"});
+    let response = client.post(format!("{}/v1/messages", address)).json(&message).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+    let response = client.post(format!("{}/v1/messages", address)).bearer_auth("wrong").json(&message).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+    let response = client.get(format!("{}/v1/quotas", address)).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn commands_are_not_executed_for_services() {
+    let (address, llm) = start("bot-exec", &[]).await;
+    let client = reqwest::Client::new();
+
+    let (status, body) = post(&client, &address, serde_json::json!({"user": "U1", "message": "!exec github config --token stolen"})).await;
+    assert_eq!(status, 200);
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&body).unwrap()["reply"], EXEC_DISABLED);
+    assert!(llm.await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn requests_not_sent_in_time_are_refused() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        let mut results = Vec::new();
+        for _ in 0..2 {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            results.push(read_request_within(&mut stream, Duration::from_millis(300)).await.map_err(|e| e.to_string()));
+        }
+        results
+    });
+
+    // Headers that never end, then a body shorter than its Content-Length
+    for partial in ["POST /v1/messages HTTP/1.1\r\nContent-Type: application/json\r\n", "POST /v1/messages HTTP/1.1\r\nContent-Length: 20\r\n\r\n{\"user\""] {
+        let mut client = tokio::net::TcpStream::connect(address).await.unwrap();
+        client.write_all(partial.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 408 Request Timeout\r\n"), "{}", response);
+    }
+
+    for result in server.await.unwrap() {
+        assert_eq!(result.unwrap_err(), "Timed out reading the request");
+    }
+}
@@ -0,0 +1,83 @@
+use qitops_agent::capabilities;
+use qitops_agent::llm::{ProviderConfig, RouterConfig};
+#[cfg(feature = "plugins")]
+use qitops_agent::output::{CommandFormatter, FormatterRegistry};
+
+#[test]
+fn agents_come_from_the_installation() {
+    let agents = capabilities::agents();
+    let test_gen = agents.iter().find(|agent| agent.name == "test-gen").unwrap();
+    assert_eq!(test_gen.description, "Generate test cases");
+    assert_eq!(test_gen.version, env!("CARGO_PKG_VERSION"));
+    assert!(test_gen.options.contains(&"--path".to_string()));
+    // Global options of `run` aren't the agent's own
+    assert!(!test_gen.options.contains(&"--format".to_string()));
+    assert_eq!(test_gen.daemon_method.as_deref(), Some("testGen"));
+    let pr_analyze = agents.iter().find(|agent| agent.name == "pr-analyze").unwrap();
+    assert_eq!(pr_analyze.daemon_method.as_deref(), cfg!(feature = "github").then_some("prAnalyze"));
+    assert_eq!(agents.iter().find(|agent| agent.name == "perf-gen").unwrap().daemon_method, None);
+}
+
+#[cfg(feature = "plugins")]
+#[test]
+fn formatters_and_plugins_come_from_the_registry() {
+    let mut registry = FormatterRegistry::new();
+    registry.register(Box::new(CommandFormatter::new("slack", std::path::PathBuf::from("/opt/qitops/plugins/slack"))));
+    let formatters = capabilities::formatters(&registry);
+    let names: Vec<(&str, bool)> = formatters.iter().map(|formatter| (formatter.name.as_str(), formatter.builtin)).collect();
+    assert_eq!(names, [("markdown", true), ("json", true), ("junit", true), ("sarif", true), ("html", true), ("slack", false)]);
+    assert_eq!(formatters[5].version, None);
+
+    let plugins = capabilities::plugins(&registry);
+    assert_eq!(plugins.len(), 1);
+    assert_eq!((plugins[0].name.as_str(), plugins[0].kind.as_str(), plugins[0].path.as_str()), ("slack", "formatter", "/opt/qitops/plugins/slack"));
+}
+
+#[test]
+fn providers_report_their_configuration_and_missing_settings() {
+    let openai: ProviderConfig = serde_json::from_value(serde_json::json!({
+        "provider_type": "openai",
+        "api_key": "sk-test",
+        "default_model": "gpt-4o-mini",
+    })).unwrap();
+    let config = RouterConfig { providers: vec![openai], default_provider: "openai".to_string(), ..RouterConfig::default() };
+    let providers = capabilities::providers(&config);
+
+    let openai = providers.iter().find(|provider| provider.provider_type == "openai").unwrap();
+    assert!(openai.configured && openai.default);
+    assert_eq!(openai.default_model.as_deref(), Some("gpt-4o-mini"));
+    assert!(openai.required_config.iter().all(|setting| setting.set));
+    assert_eq!(openai.required_config[0].env.as_deref(), Some("OPENAI_API_KEY"));
+
+    if cfg!(feature = "local-llm") {
+        let local = providers.iter().find(|provider| provider.provider_type == "local").unwrap();
+        assert!(!local.configured && !local.default);
+        assert_eq!(local.required_config.len(), 1);
+        assert_eq!((local.required_config[0].name.as_str(), local.required_config[0].set), ("default_model", false));
+
+        // Ollama needs nothing to be usable
+        let ollama = providers.iter().find(|provider| provider.provider_type == "ollama").unwrap();
+        assert!(ollama.required_config.is_empty());
+    }
+
+    let json = serde_json::to_value(&providers).unwrap();
+    assert_eq!(json[0]["provider_type"], "openai");
+}
+
+#[test]
+fn features_are_the_ones_compiled_in() {
+    let compiled: Vec<&str> = [
+        ("github", cfg!(feature = "github")),
+        ("monitoring", cfg!(feature = "monitoring")),
+        ("bot", cfg!(feature = "bot")),
+        ("plugins", cfg!(feature = "plugins")),
+        ("local-llm", cfg!(feature = "local-llm")),
+        ("lsp", cfg!(feature = "lsp")),
+        ("pairing", cfg!(feature = "pairing")),
+        ("email", cfg!(feature = "email")),
+    ].into_iter().filter(|(_, enabled)| *enabled).map(|(feature, _)| feature).collect();
+    assert_eq!(capabilities::FEATURES, compiled.as_slice());
+
+    let providers = capabilities::providers(&RouterConfig::default());
+    assert_eq!(providers.iter().any(|provider| provider.provider_type == "ollama"), cfg!(feature = "local-llm"));
+}
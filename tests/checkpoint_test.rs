@@ -0,0 +1,105 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use qitops_agent::agent::test_gen::TestGenAgent;
+use qitops_agent::agent::traits::Agent;
+use qitops_agent::checkpoint::Checkpoint;
+use qitops_agent::llm::client::FallbackConfig;
+use qitops_agent::llm::{LlmRouter, RouterConfig};
+
+mod common;
+use common::{Received, Reply, scratch};
+
+/// Serve a response per status, in order, returning the prompts that were sent
+async fn serve(statuses: Vec<u16>) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+    let replies = statuses.into_iter()
+        .map(|status| match status {
+            200 => Reply::completion("## Test Case 1"),
+            _ => Reply::status(status, serde_json::json!({"error": {"message": "Rate limit reached"}})),
+        })
+        .collect();
+    let (address, server) = common::serve(replies).await;
+    (address, tokio::spawn(async move { server.await.unwrap().iter().map(Received::prompt).collect() }))
+}
+
+/// Router sending one request at a time to `api_base`, without retries
+fn router(api_base: &str) -> LlmRouter {
+    common::router_with(api_base, RouterConfig {
+        fallback: FallbackConfig { max_retries: 0, ..FallbackConfig::default() },
+        batch_concurrency: 1,
+        ..RouterConfig::default()
+    })
+}
+
+#[test]
+fn checkpoints_record_units_and_only_resume_their_own_run() {
+    let dir = scratch("units");
+    let path = dir.join("run.json");
+
+    let mut checkpoint = Checkpoint::at(&path, "test-gen", "src (md)");
+    assert!(!path.exists(), "nothing is written before a unit completes");
+    checkpoint.record("src/a.py", &"src/tests/test_a.py.md").unwrap();
+    checkpoint.record("src/b.py", &"src/tests/test_b.py.md").unwrap();
+
+    let resumed = Checkpoint::resume(&path, "test-gen", "src (md)").unwrap();
+    assert_eq!(resumed.len(), 2);
+    assert!(resumed.is_done("src/a.py"));
+    assert!(!resumed.is_done("src/c.py"));
+    assert_eq!(resumed.output::<String>("src/b.py").unwrap(), "src/tests/test_b.py.md");
+
+    let error = Checkpoint::resume(&path, "test-gen", "lib (md)").unwrap_err().to_string();
+    assert!(error.contains("is of test-gen over src (md), not test-gen over lib (md)"), "{}", error);
+    assert!(Checkpoint::resume(&dir.join("missing.json"), "test-gen", "src (md)").is_err());
+
+    // An incomplete run keeps its checkpoint; a complete one deletes it
+    assert_eq!(resumed.finish(true).unwrap(), Some(path.clone()));
+    assert!(path.exists());
+    assert_eq!(resumed.finish(false).unwrap(), None);
+    assert!(!path.exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn interrupted_test_gen_resumes_where_it_stopped() {
+    let dir = scratch("test-gen");
+    for file in ["a.py", "b.py", "c.py"] {
+        fs::write(dir.join(file), format!("def {}():\n    pass\n", &file[..1])).unwrap();
+    }
+    let path = dir.to_string_lossy().to_string();
+    let prompt_for = |prompts: &[String], file: &str| prompts.iter().any(|prompt| prompt.contains(&format!("def {}():", file)));
+
+    // The second file hits the rate limit, so the third isn't sent
+    let (api_base, server) = serve(vec![200, 429]).await;
+    let agent = TestGenAgent::new(path.clone(), "markdown", None, None, router(&api_base)).await.unwrap();
+    let response = agent.execute().await.unwrap();
+    let prompts = server.await.unwrap();
+    let data = response.data.unwrap();
+
+    assert_eq!(prompts.len(), 2);
+    assert!(prompt_for(&prompts, "a"));
+    assert_eq!(data["output_files"].as_array().unwrap().len(), 1);
+    assert_eq!(data["failed"].as_array().unwrap().len(), 2);
+    assert!(data["failed"][1]["error"].as_str().unwrap().contains("Skipped after the LLM rate limit"), "{}", data);
+    let checkpoint = PathBuf::from(data["checkpoint"].as_str().unwrap());
+    assert!(checkpoint.exists());
+    assert!(response.message.ends_with(&format!("resume with --resume {}", checkpoint.display())), "{}", response.message);
+
+    // Resuming only generates the files that weren't done, then deletes the checkpoint
+    let (api_base, server) = serve(vec![200, 200]).await;
+    let agent = TestGenAgent::new(path.clone(), "markdown", None, None, router(&api_base)).await.unwrap()
+        .with_resume(Some(checkpoint.clone()));
+    let response = agent.execute().await.unwrap();
+    let prompts = server.await.unwrap();
+    let data = response.data.unwrap();
+
+    assert!(!prompt_for(&prompts, "a"));
+    assert!(prompt_for(&prompts, "b") && prompt_for(&prompts, "c"));
+    assert_eq!(data["output_files"].as_array().unwrap().len(), 3);
+    assert_eq!(data["resumed"], 1);
+    assert!(data["checkpoint"].is_null());
+    assert!(!checkpoint.exists());
+    assert!(Path::new(&dir).join("tests").join("test_c.py.md").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,52 @@
+use qitops_agent::bench::ReplayClient;
+use qitops_agent::llm::summarize::split_chunks;
+use qitops_agent::llm::{LlmRouter, ProviderConfig, RouterConfig, TokenCounter};
+use std::sync::Arc;
+
+/// A diff touching `files` files of `lines` changed lines each
+fn large_diff(files: usize, lines: usize) -> String {
+    (0..files).map(|file| {
+        let body: String = (0..lines).map(|line| format!("+    let value_{} = compute({}, {});\n", line, file, line)).collect();
+        format!("diff --git a/src/module_{file}.rs b/src/module_{file}.rs\n--- a/src/module_{file}.rs\n+++ b/src/module_{file}.rs\n@@ -1,0 +1,{lines} @@\n{body}")
+    }).collect()
+}
+
+#[test]
+fn chunks_stay_within_the_limit_and_keep_files_together() {
+    let counter = TokenCounter::for_model("gpt-4o");
+    let diff = large_diff(6, 20);
+
+    let chunks = split_chunks(&diff, 400, &counter, |line| line.starts_with("diff --git "));
+
+    assert!(chunks.len() > 1);
+    assert_eq!(chunks.concat(), diff);
+    for chunk in &chunks {
+        assert!(counter.count(chunk) <= 400, "chunk of {} tokens", counter.count(chunk));
+        assert!(chunk.starts_with("diff --git "), "chunk starts mid-file: {}", &chunk[..40]);
+    }
+}
+
+#[tokio::test]
+async fn large_inputs_are_summarized_in_chunks() {
+    let provider: ProviderConfig = serde_json::from_value(serde_json::json!({
+        "provider_type": "replay",
+        "default_model": "gpt-4o",
+        "options": { "context_window": "1500" },
+    })).unwrap();
+    let answers = (1..=20).map(|n| format!("Summary {}: adds compute() calls", n)).collect();
+    let router = LlmRouter::with_clients(
+        RouterConfig { providers: vec![provider], ..RouterConfig::default() },
+        vec![Arc::new(ReplayClient::new(answers))],
+    );
+
+    // Small inputs are used as they are, without a request
+    let small = router.condense("diff --git a/x b/x\n+fix\n", "diff", "a review", None).await.unwrap();
+    assert!(!small.is_summarized());
+    assert_eq!(small.text, "diff --git a/x b/x\n+fix\n");
+
+    let large = router.condense(&large_diff(8, 30), "diff", "a review", None).await.unwrap();
+    assert!(large.chunks > 1, "{} chunk(s)", large.chunks);
+    assert!(large.text.starts_with("The diff was too large to include in full"));
+    assert!(large.text.contains(&format!("Part {}/{}:\nSummary", large.chunks, large.chunks)));
+    assert!(!large.text.contains("value_29"));
+}
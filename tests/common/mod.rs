@@ -0,0 +1,202 @@
+// Helpers shared by the integration tests: a fake HTTP server, routers sending to it and
+// scratch directories
+//
+// Each test binary uses a different part of this module.
+#![allow(dead_code)]
+
+use qitops_agent::llm::providers::client_for;
+use qitops_agent::llm::{LlmRouter, ProviderConfig, RouterConfig};
+use std::path::PathBuf;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::task::JoinHandle;
+
+/// A request received by the fake server
+#[derive(Debug, Clone)]
+pub struct Received {
+    /// Method, e.g. `POST`
+    pub method: String,
+
+    /// Path with the query string
+    pub path: String,
+
+    /// Request line and headers, lowercased
+    pub headers: String,
+
+    /// Body
+    pub body: Vec<u8>,
+}
+
+impl Received {
+    /// The body as text
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+
+    /// The body as JSON, `Null` if it isn't JSON
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap_or_default()
+    }
+
+    /// Content of the last message of a chat completion request
+    pub fn prompt(&self) -> String {
+        self.json()["messages"].as_array()
+            .and_then(|messages| messages.last())
+            .and_then(|message| message["content"].as_str())
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    /// Contents of all messages of a chat completion request, one after the other
+    pub fn messages(&self) -> String {
+        self.json()["messages"].as_array().into_iter().flatten()
+            .filter_map(|message| message["content"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// A canned response of the fake server
+#[derive(Debug, Clone)]
+pub struct Reply {
+    /// HTTP status code
+    pub status: u16,
+
+    /// JSON body
+    pub body: serde_json::Value,
+}
+
+impl Reply {
+    /// A 200 response with a JSON body
+    pub fn json(body: serde_json::Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    /// A response with another status
+    pub fn status(status: u16, body: serde_json::Value) -> Self {
+        Self { status, body }
+    }
+
+    /// An OpenAI chat completion answering `content`
+    pub fn completion(content: &str) -> Self {
+        Self::json(serde_json::json!({
+            "model": "gpt-4o-mini",
+            "choices": [{"message": {"role": "assistant", "content": content}}],
+            "usage": {"total_tokens": 42},
+        }))
+    }
+}
+
+impl From<serde_json::Value> for Reply {
+    fn from(body: serde_json::Value) -> Self {
+        Self::json(body)
+    }
+}
+
+/// Answer one connection per reply, in order, returning the base URL and the requests received
+pub async fn serve(replies: Vec<Reply>) -> (String, JoinHandle<Vec<Received>>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+
+    let server = tokio::spawn(async move {
+        let mut received = Vec::new();
+        for reply in replies {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            received.push(read(&mut socket).await);
+
+            let body = reply.body.to_string();
+            let response = format!(
+                "HTTP/1.1 {} {}\r\ncontent-type: application/json\r\nconnection: close\r\ncontent-length: {}\r\n\r\n{}",
+                reply.status, reason(reply.status), body.len(), body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+        received
+    });
+
+    (address, server)
+}
+
+/// Answer a single connection, returning the base URL and the request received
+pub async fn serve_once(reply: impl Into<Reply>) -> (String, JoinHandle<Received>) {
+    let (address, server) = serve(vec![reply.into()]).await;
+    (address, tokio::spawn(async move { server.await.unwrap().remove(0) }))
+}
+
+/// Read a request's head and body
+async fn read(socket: &mut tokio::net::TcpStream) -> Received {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 4096];
+    let body_start = loop {
+        let read = socket.read(&mut buffer).await.unwrap();
+        request.extend_from_slice(&buffer[..read]);
+        if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+    };
+    let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+    let length: usize = headers.lines()
+        .find_map(|line| line.strip_prefix("content-length:"))
+        .map(|value| value.trim().parse().unwrap())
+        .unwrap_or(0);
+    while request.len() < body_start + length {
+        let read = socket.read(&mut buffer).await.unwrap();
+        request.extend_from_slice(&buffer[..read]);
+    }
+
+    let head = String::from_utf8_lossy(&request[..body_start]).to_string();
+    let mut request_line = head.split_whitespace();
+    Received {
+        method: request_line.next().unwrap_or_default().to_string(),
+        path: request_line.next().unwrap_or_default().to_string(),
+        headers,
+        body: request[body_start..].to_vec(),
+    }
+}
+
+/// Reason phrase of a status code
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Status",
+    }
+}
+
+/// An OpenAI provider at `api_base`, such as the fake server
+pub fn openai(api_base: &str) -> ProviderConfig {
+    serde_json::from_value(serde_json::json!({
+        "provider_type": "openai",
+        "api_key": "sk-test",
+        "api_base": api_base,
+        "default_model": "gpt-4o-mini",
+    })).unwrap()
+}
+
+/// A router sending every request to an OpenAI provider at `api_base`
+pub fn router(api_base: &str) -> LlmRouter {
+    router_with(api_base, RouterConfig::default())
+}
+
+/// A router sending every request to an OpenAI provider at `api_base`, with the other
+/// settings of `config`
+pub fn router_with(api_base: &str, config: RouterConfig) -> LlmRouter {
+    let provider = openai(api_base);
+    LlmRouter::with_clients(
+        RouterConfig { providers: vec![provider.clone()], default_provider: "openai".to_string(), ..config },
+        vec![client_for(&provider).unwrap().unwrap()],
+    )
+}
+
+/// An empty temporary directory for a test, unique to the test process, by its canonical path
+pub fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("qitops-test-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::canonicalize(dir).unwrap()
+}
@@ -0,0 +1,53 @@
+use qitops_agent::llm::{LazyRouter, ProviderConfig, RouterConfig};
+use std::collections::HashMap;
+use std::path::Path;
+
+mod common;
+
+fn write_config(path: &Path, model: &str) {
+    let config = RouterConfig {
+        providers: vec![ProviderConfig {
+            provider_type: "mock".to_string(),
+            api_key: None,
+            api_base: None,
+            default_model: model.to_string(),
+            options: HashMap::from([("fixtures".to_string(), std::env::temp_dir().to_string_lossy().to_string())]),
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            max_concurrent_requests: None,
+            proxy: None,
+            timeout_secs: None,
+            connect_timeout_secs: None,
+            max_retries: None,
+            headers: HashMap::new(),
+            auth: None,
+        }],
+        default_provider: "mock".to_string(),
+        ..RouterConfig::default()
+    };
+    std::fs::write(path, serde_json::to_string_pretty(&config).unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn router_is_rebuilt_when_the_config_file_changes() {
+    // The configuration file in the current directory is found first
+    let dir = common::scratch("config-reload");
+    std::env::set_current_dir(&dir).unwrap();
+    let path = dir.join("qitops-config.json");
+    write_config(&path, "first");
+
+    let router = LazyRouter::new();
+    assert!(!router.is_initialized());
+    assert_eq!(router.get().await.unwrap().default_model().as_deref(), Some("first"));
+    assert_eq!(router.get().await.unwrap().default_model().as_deref(), Some("first"));
+
+    write_config(&path, "rotated-model");
+    assert_eq!(router.get().await.unwrap().default_model().as_deref(), Some("rotated-model"));
+
+    // A broken edit keeps the previous configuration
+    std::fs::write(&path, "{ not json").unwrap();
+    assert_eq!(router.get().await.unwrap().default_model().as_deref(), Some("rotated-model"));
+    assert_eq!(router.get_if_initialized().unwrap().default_model().as_deref(), Some("rotated-model"));
+
+    std::fs::remove_file(&path).unwrap();
+}
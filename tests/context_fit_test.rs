@@ -0,0 +1,74 @@
+use qitops_agent::llm::tokens::fit_to_context;
+use qitops_agent::llm::{LlmRequest, SOURCES_MARKER, TokenCounter};
+
+/// Lines of filler text, numbered so no two are alike
+fn filler(label: &str, lines: usize) -> String {
+    (0..lines).map(|i| format!("{} line {} describes the checkout flow", label, i)).collect::<Vec<_>>().join("\n")
+}
+
+#[test]
+fn sources_are_truncated_before_the_prompt() {
+    let instructions = format!("Review this change.\n{}\nList the risks.", filler("diff", 50));
+    let prompt = format!("{}{}{}", instructions, SOURCES_MARKER, filler("source", 2_000));
+    let mut request = LlmRequest::new(prompt, "gpt-4o".to_string())
+        .with_system_message("You are a QA engineer.".to_string())
+        .with_max_tokens(500);
+
+    let fit = fit_to_context(&mut request, 4_000).unwrap();
+
+    // The instructions are untouched and only the end of the sources is cut
+    let content = &request.messages[1].content;
+    assert!(content.starts_with(&format!("{}{}source line 0 ", instructions, SOURCES_MARKER)));
+    assert!(content.contains("tokens truncated to fit the context window ...]"));
+    assert!(!content.contains("source line 1999 "));
+    assert_eq!(request.messages[0].content, "You are a QA engineer.");
+
+    let counter = TokenCounter::for_model("gpt-4o");
+    assert_eq!(fit.prompt_tokens, counter.count_messages(&request.messages));
+    assert!(fit.prompt_tokens <= 3_500 && fit.truncated_tokens > 0);
+    assert_eq!(request.max_tokens, 500);
+}
+
+#[test]
+fn long_prompts_lose_their_middle_and_stay_within_budget() {
+    for model in ["gpt-4o", "gpt-4", "llama3"] {
+        let prompt = format!("BEGIN instructions\n{}\nEND question", filler("log", 3_000));
+        let mut request = LlmRequest::new(prompt, model.to_string()).with_max_tokens(1_000);
+
+        let fit = fit_to_context(&mut request, 2_048).unwrap();
+
+        // The start and end are kept, and the prompt and completion fit the window together
+        let content = &request.messages[0].content;
+        assert!(content.starts_with("BEGIN instructions\nlog line 0 "), "{}", model);
+        assert!(content.ends_with("END question"), "{}", model);
+        assert!(content.contains("tokens truncated to fit the context window ...]"), "{}", model);
+        assert!(!content.contains("log line 1500 "), "{}", model);
+
+        let prompt_tokens = TokenCounter::for_model(model).count_messages(&request.messages);
+        assert_eq!(fit.prompt_tokens, prompt_tokens, "{}", model);
+        assert!(prompt_tokens <= 2_048 - 1_000, "{} used {} tokens", model, prompt_tokens);
+        assert!(prompt_tokens + request.max_tokens <= 2_048, "{}", model);
+    }
+}
+
+#[test]
+fn completions_give_way_to_prompts_up_to_half_the_window() {
+    let mut request = LlmRequest::new(filler("log", 100), "gpt-4o".to_string()).with_max_tokens(4_000);
+
+    let fit = fit_to_context(&mut request, 4_096).unwrap();
+
+    assert_eq!(fit.truncated_tokens, 0);
+    assert_eq!(request.max_tokens, 4_096 - fit.prompt_tokens);
+}
+
+#[test]
+fn requests_that_cannot_fit_are_refused() {
+    // Every message costs tokens for its role however short it is
+    let mut request = LlmRequest::new("ok".to_string(), "gpt-4o".to_string()).with_max_tokens(16);
+    for _ in 0..20 {
+        request = request.with_system_message("ok".to_string());
+    }
+
+    let error = fit_to_context(&mut request, 64).unwrap_err();
+    assert!(error.to_string().contains("gpt-4o has a 64-token context window"), "{}", error);
+}
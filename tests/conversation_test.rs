@@ -0,0 +1,73 @@
+use qitops_agent::bot::{BotConfig, QitOpsBot};
+use qitops_agent::llm::client::MessageRole;
+use qitops_agent::llm::Conversation;
+
+mod common;
+use common::{Received, Reply};
+
+/// Serve a chat completion per answer, returning the request bodies
+async fn serve(answers: Vec<&'static str>) -> (String, tokio::task::JoinHandle<Vec<serde_json::Value>>) {
+    let (address, server) = common::serve(answers.into_iter().map(Reply::completion).collect()).await;
+    (address, tokio::spawn(async move { server.await.unwrap().iter().map(Received::json).collect() }))
+}
+
+#[test]
+fn history_is_trimmed_by_turns_and_by_the_context_window() {
+    let mut conversation = Conversation::new("You are a pair tester.".to_string()).with_max_turns(4);
+    for turn in 1..=3 {
+        conversation.push_user("Ada", format!("Question {}", turn));
+        conversation.push_assistant("QitOps", format!("Answer {}", turn));
+    }
+
+    // Only the last four messages are kept
+    assert_eq!(conversation.len(), 4);
+    assert_eq!(conversation.turns()[0].text, "Question 2");
+    assert!(matches!(conversation.turns()[1].role, MessageRole::Assistant));
+    assert_eq!(conversation.transcript(), "Ada: Question 2\nQitOps: Answer 2\nAda: Question 3\nQitOps: Answer 3");
+
+    let request = conversation.request("gpt-4o-mini".to_string(), Some("Summarize."), 128_000);
+    assert!(matches!(request.messages[0].role, MessageRole::System));
+    assert_eq!(request.messages[0].content, "You are a pair tester.");
+    assert_eq!(request.messages[1].content, format!("{}\n\nInstruction: Summarize.", conversation.transcript()));
+
+    // A small window keeps the newest messages that fit, noting what was left out
+    let mut conversation = Conversation::new("You are a pair tester.".to_string());
+    for turn in 0..20 {
+        conversation.push_user("Ada", format!("Message {} {}", turn, "about the login page ".repeat(20)));
+    }
+    let request = conversation.request("gpt-4o-mini".to_string(), None, 1_000);
+    let prompt = &request.messages[1].content;
+    assert!(prompt.starts_with('['), "{}", prompt);
+    assert!(prompt.contains("earlier messages omitted]"));
+    assert!(prompt.ends_with(&format!("Ada: Message 19 {}", "about the login page ".repeat(20))));
+    assert!(!prompt.contains("Message 0 "));
+}
+
+#[tokio::test]
+async fn bot_sends_its_recent_history() {
+    let (api_base, server) = serve(vec!["Use qitops run risk.", "Pass --diff.", "Yes."]).await;
+    let router = common::router(&api_base);
+
+    let mut bot = QitOpsBot::new(router, Some(BotConfig { max_history_length: 1, ..BotConfig::default() })).await;
+    assert_eq!(bot.process_message("How do I assess risk?").await.unwrap(), "Use qitops run risk.");
+    bot.process_message("Which flag takes the diff?").await.unwrap();
+    bot.process_message("Does it take a PR URL?").await.unwrap();
+
+    let bodies = server.await.unwrap();
+    let prompt = |body: &serde_json::Value| body["messages"].as_array().unwrap().last().unwrap()["content"].as_str().unwrap().to_string();
+    assert!(bodies[0]["messages"][0]["content"].as_str().unwrap().starts_with("You are QitOps Bot"));
+    assert_eq!(prompt(&bodies[0]), "User: How do I assess risk?");
+
+    // One exchange of history is kept: the previous answer and the new question
+    assert_eq!(prompt(&bodies[1]), "QitOps Bot: Use qitops run risk.\nUser: Which flag takes the diff?");
+    assert_eq!(prompt(&bodies[2]), "QitOps Bot: Pass --diff.\nUser: Does it take a PR URL?");
+}
+
+#[tokio::test]
+async fn bot_never_decides_on_held_prompts() {
+    let router = common::router("http://127.0.0.1:9");
+
+    let mut bot = QitOpsBot::new(router, None).await;
+    let error = bot.process_message("!exec approvals approve a1b2c3d4").await.unwrap_err();
+    assert_eq!(error.to_string(), "The bot doesn't run `qitops approvals`; run it yourself");
+}
@@ -0,0 +1,80 @@
+use qitops_agent::llm::cost::{self, CostDimension, UsageEntry, UsageLedger};
+
+fn entry(command: &str, repo: &str, pr: &str, user: &str, cost_usd: f64) -> UsageEntry {
+    UsageEntry {
+        day: "2025-03-01".to_string(),
+        command: command.to_string(),
+        repo: repo.to_string(),
+        pr: pr.to_string(),
+        user: user.to_string(),
+        provider: "openai".to_string(),
+        model: "gpt-4o".to_string(),
+        requests: 2,
+        input_tokens: 1000,
+        output_tokens: 200,
+        cost_usd,
+    }
+}
+
+#[test]
+fn spend_rolls_up_by_repo_and_pull_request_and_exports_as_csv() {
+    let entries = vec![
+        entry("run pr-analyze", "acme/shop", "acme/shop#12", "dev", 0.40),
+        entry("run risk", "acme/shop", "acme/shop#12", "dev", 0.10),
+        entry("run test-gen", "acme/shop", "", "qa, lead", 0.05),
+        entry("run risk", "acme/api", "acme/api#3", "dev", 0.20),
+        entry("run risk", "", "", "", 0.01),
+    ];
+
+    let by_repo = cost::roll_up(&entries, &[CostDimension::Repo]);
+    assert_eq!(by_repo[0].0, vec!["acme/shop".to_string()]);
+    assert!((by_repo[0].1.cost_usd - 0.55).abs() < 1e-9);
+    assert_eq!(by_repo[0].1.requests, 6);
+    assert_eq!(by_repo.last().unwrap().0, vec!["(unknown)".to_string()]);
+
+    let by_pr = cost::roll_up(&entries, &[CostDimension::Pr]);
+    assert_eq!(by_pr[0].0, vec!["acme/shop#12".to_string()]);
+    assert_eq!(by_pr[1].0, vec!["acme/api#3".to_string()]);
+    assert_eq!(by_pr[2].0, vec!["(no pull request)".to_string()]);
+
+    let by = ["user".parse::<CostDimension>().unwrap(), "command".parse().unwrap()];
+    let csv = cost::roll_up_csv(&cost::roll_up(&entries, &by), &by);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "user,command,requests,input_tokens,output_tokens,cost_usd");
+    assert_eq!(lines[1], "dev,run pr-analyze,2,1000,200,0.400000");
+    assert_eq!(lines[2], "dev,run risk,4,2000,400,0.300000");
+    assert!(lines.contains(&"\"qa, lead\",run test-gen,2,1000,200,0.050000"));
+    assert_eq!(lines.len(), 5);
+
+    assert!("team".parse::<CostDimension>().is_err());
+}
+
+#[test]
+fn usage_is_attributed_to_the_pull_request_and_user() {
+    assert_eq!(cost::repo_from_remote("https://github.com/acme/shop.git\n").as_deref(), Some("acme/shop"));
+    assert_eq!(cost::repo_from_remote("git@github.com:acme/shop.git").as_deref(), Some("acme/shop"));
+    assert_eq!(cost::repo_from_remote("ssh://git@gitlab.example.com/team/acme/shop").as_deref(), Some("acme/shop"));
+    assert_eq!(cost::repo_from_remote("shop"), None);
+
+    let path = std::env::temp_dir().join(format!("qitops-cost-report-test-{}.json", std::process::id()));
+    let ledger = UsageLedger::open(&path);
+
+    cost::set_command("run risk");
+    cost::set_pull_request("acme", "shop", "42");
+    ledger.record("openai", "gpt-4o", 1000, 100, 0.02).unwrap();
+    ledger.record("openai", "gpt-4o", 500, 50, 0.01).unwrap();
+
+    let entries = ledger.read().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].repo, "acme/shop");
+    assert_eq!(entries[0].pr, "acme/shop#42");
+    assert_eq!(entries[0].user, qitops_agent::audit::current_actor());
+    assert_eq!(entries[0].requests, 2);
+
+    // Entries written before attribution existed still load
+    std::fs::write(&path, r#"[{"day":"2025-01-01","command":"run risk","provider":"openai","model":"gpt-4o","requests":1,"input_tokens":10,"output_tokens":5,"cost_usd":0.001}]"#).unwrap();
+    let old = ledger.read().unwrap();
+    assert_eq!(CostDimension::User.key(&old[0]), "(unknown)");
+
+    std::fs::remove_file(&path).unwrap();
+}
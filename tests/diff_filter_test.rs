@@ -0,0 +1,120 @@
+use qitops_agent::agent::baseline::BaselineMode;
+use qitops_agent::agent::diff_filter::{DiffFilterConfig, DiffFilters};
+use qitops_agent::agent::{Agent, RiskAgent};
+
+mod common;
+use common::Reply;
+
+const DIFF: &str = r#"diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+-use std::fs;
+ use std::io;
++use std::fs;
+ use std::path::Path;
+@@ -10,4 +10,4 @@ fn load(path: &Path) -> io::Result<String> {
+-    let text = fs::read_to_string(path)?;
+-    Ok(text)
++    let text =
++        fs::read_to_string(path)?;
++    Ok(text)
+@@ -20,3 +20,4 @@ fn save(path: &Path, text: &str) -> io::Result<()> {
++    // Overwrites the file
+     fs::write(path, text)
+@@ -30,2 +31,2 @@ fn greeting() -> &'static str {
+-    "hello world"
++    "hello  world"
+diff --git a/src/limits.py b/src/limits.py
+--- a/src/limits.py
++++ b/src/limits.py
+@@ -5,3 +5,3 @@ def check(order):
+     if order.total > LIMIT:
+-        raise LimitExceeded(order)
++    raise LimitExceeded(order)
+diff --git a/src/notes.py b/src/notes.py
+--- a/src/notes.py
++++ b/src/notes.py
+@@ -1,2 +1,2 @@
+-# Notes helpers
++# Helpers for notes
+ import os
+diff --git a/db/schema.sql b/db/schema.sql
+--- a/db/schema.sql
++++ b/db/schema.sql
+@@ -1,1 +1,1 @@
+-CREATE TABLE notes (id INT);
++CREATE TABLE notes ( id INT );
+"#;
+
+/// Serve one chat completion, returning the messages that were sent
+async fn serve_once(answer: &str) -> (String, tokio::task::JoinHandle<String>) {
+    let (address, server) = common::serve_once(Reply::completion(answer)).await;
+    (address, tokio::spawn(async move { server.await.unwrap().messages() }))
+}
+
+#[test]
+fn noise_hunks_are_filtered_per_language_and_reported() {
+    let (filtered, report) = DiffFilters::default().apply(DIFF);
+    let hunks: Vec<(&str, &str)> = report.hunks.iter().map(|hunk| (hunk.file.as_str(), hunk.filter.as_str())).collect();
+    assert_eq!(hunks, vec![
+        ("src/lib.rs", "imports"),
+        ("src/lib.rs", "formatting"),
+        ("src/lib.rs", "comments"),
+        ("src/notes.py", "comments"),
+    ]);
+    assert_eq!(report.files, vec!["src/notes.py"]);
+    assert!(!report.kept_whole);
+    assert_eq!(report.summary().unwrap(), "Filtered 4 noise hunk(s): 2 comments, 1 formatting, 1 imports; only noise in src/notes.py");
+
+    // Whitespace in strings, Python indentation and unknown languages are kept, with their line numbers
+    assert!(filtered.contains("@@ -30,2 +31,2 @@ fn greeting() -> &'static str {\n-    \"hello world\"\n+    \"hello  world\"\n"), "{}", filtered);
+    assert!(filtered.contains("+    raise LimitExceeded(order)"));
+    assert!(filtered.contains("+CREATE TABLE notes ( id INT );"));
+    assert!(!filtered.contains("use std::fs"));
+    assert!(!filtered.contains("src/notes.py"));
+
+    // Moving a statement is a change, not formatting
+    let moved = "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,3 +1,3 @@\n-    charge(order);\n     validate(order);\n+    charge(order);\n";
+    assert!(DiffFilters::default().apply(moved).1.is_empty());
+
+    // A diff that is all noise is analyzed as it is
+    let noise = "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,1 +1,2 @@\n+// A comment\n fn main() {}\n";
+    let (filtered, report) = DiffFilters::default().apply(noise);
+    assert_eq!(filtered, noise);
+    assert!(report.kept_whole);
+
+    // Filters are chosen by name
+    let config = DiffFilterConfig { filters: vec!["comments".to_string()] };
+    let (_, report) = DiffFilters::from_config(&config).unwrap().apply(DIFF);
+    assert_eq!(report.hunks.len(), 2);
+    let config = DiffFilterConfig { filters: vec!["semicolons".to_string()] };
+    assert!(DiffFilters::from_config(&config).err().unwrap().to_string().contains("Unknown diff filter 'semicolons'"));
+    assert!(DiffFilters::none().apply(DIFF).1.is_empty());
+}
+
+#[tokio::test]
+async fn risk_assessment_leaves_noise_out_of_the_prompt() {
+    let dir = std::env::temp_dir().join(format!("qitops-diff-filter-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let diff = dir.join("change.diff");
+    std::fs::write(&diff, DIFF).unwrap();
+
+    let (api_base, server) = serve_once(r#"{"overall_risk": "Medium", "component_risks": [], "summary": "Moves a raise out of the limit check.", "recommendations": [], "findings": []}"#).await;
+    let router = common::router(&api_base);
+
+    let agent = RiskAgent::new_from_diff(diff.to_string_lossy().to_string(), Vec::new(), Vec::new(), router).await.unwrap()
+        .with_baseline(BaselineMode::Ignore);
+    let data = agent.execute().await.unwrap().data.unwrap();
+    let prompt = server.await.unwrap();
+
+    assert!(prompt.contains("raise LimitExceeded(order)"), "{}", prompt);
+    assert!(!prompt.contains("// Overwrites the file"), "{}", prompt);
+    assert!(!prompt.contains("Helpers for notes"), "{}", prompt);
+
+    assert_eq!(data["filtered"]["hunks"].as_array().unwrap().len(), 4);
+    assert_eq!(data["filtered"]["hunks"][0]["hunk"], "@@ -1,3 +1,3 @@");
+    assert_eq!(data["filtered"]["files"][0], "src/notes.py");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
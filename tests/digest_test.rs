@@ -0,0 +1,86 @@
+use qitops_agent::output::Report;
+use qitops_agent::runs::RunRecord;
+use qitops_agent::runs::digest::Digest;
+
+/// Seconds since the epoch at midnight UTC of 2026-10-12
+const MONDAY: u64 = 1_791_763_200;
+
+const DAY: u64 = 86_400;
+
+fn record(id: &str, recorded_at: u64, report: serde_json::Value) -> RunRecord {
+    RunRecord { id: id.to_string(), recorded_at, report: serde_json::from_value::<Report>(report).unwrap() }
+}
+
+fn risk(title: &str, overall_risk: &str, findings: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "command": "risk",
+        "title": title,
+        "summary": "Risk assessment completed",
+        "body": "",
+        "findings": findings,
+        "gate": if overall_risk == "Low" { "pass" } else { "fail" },
+        "data": {"risk": {"overall_risk": overall_risk}},
+    })
+}
+
+fn history() -> Vec<RunRecord> {
+    let injection = serde_json::json!({"file": "src/users.py", "line": 11, "severity": "high", "title": "SQL injection in find_user"});
+    let timeout = serde_json::json!({"file": "src/http.rs", "severity": "medium", "title": "No request timeout"});
+    vec![
+        // Before the period
+        record("old", MONDAY - 3 * DAY, risk("Risk Assessment: old.diff", "Critical", serde_json::json!([{"title": "Stale"}]))),
+        record("r1", MONDAY + 3600, risk("Risk Assessment: users.diff", "High", serde_json::json!([injection, timeout]))),
+        // The same change again: the injection was fixed
+        record("r2", MONDAY + DAY, risk("Risk Assessment: users.diff", "Medium", serde_json::json!([timeout]))),
+        record("t1", MONDAY + 2 * DAY, serde_json::json!({
+            "command": "test-gen", "title": "Test Generation: src", "summary": "", "body": "", "findings": [],
+            "data": {"output_files": ["tests/test_users.py", "tests/test_http.py"]},
+        })),
+        record("t2", MONDAY + 3 * DAY, serde_json::json!({
+            "command": "test-gen", "title": "Test Generation: src/users.py", "summary": "", "body": "", "findings": [],
+            "data": {"output_file": "tests/test_users.py"},
+        })),
+    ]
+}
+
+#[test]
+fn digest_counts_the_period_and_keeps_findings_still_open() {
+    let digest = Digest::from_runs(&history(), "2026-10-12", "2026-10-18");
+
+    assert_eq!(digest.title(), "QA Digest: 2026-10-12 to 2026-10-18");
+    assert_eq!(digest.runs, 4);
+    assert_eq!(digest.analyses, [("risk".to_string(), 2), ("test-gen".to_string(), 2)]);
+
+    let high_risk: Vec<(&str, Option<&str>, bool)> = digest.high_risk.iter()
+        .map(|change| (change.run_id.as_str(), change.overall_risk.as_deref(), change.gate_failed))
+        .collect();
+    assert_eq!(high_risk, [("r2", Some("Medium"), true), ("r1", Some("High"), true)]);
+
+    assert_eq!(digest.test_runs, 2);
+    // Files written again by a later run are listed once
+    assert_eq!(digest.test_files, ["tests/test_users.py", "tests/test_http.py"]);
+
+    // Only the latest run of a report counts: the injection was fixed by r2
+    let open: Vec<(&str, &str)> = digest.open_findings.iter().map(|open| (open.finding.title.as_str(), open.run_id.as_str())).collect();
+    assert_eq!(open, [("No request timeout", "r2")]);
+}
+
+#[test]
+fn digest_renders_markdown_and_html_for_notifications() {
+    let digest = Digest::from_runs(&history(), "2026-10-12", "2026-10-18");
+    let markdown = digest.markdown();
+    assert!(markdown.starts_with("# QA Digest: 2026-10-12 to 2026-10-18\n\n4 run(s), 2 high-risk change(s), 2 test file(s) generated, 1 open finding(s).\n"), "{}", markdown);
+    assert!(markdown.contains("| risk | 2 |\n| test-gen | 2 |\n"));
+    assert!(markdown.contains("- **Risk Assessment: users.diff** (High risk, gate failed) on 2026-10-12, run `r1`\n"));
+    assert!(markdown.contains("- `tests/test_http.py`\n"));
+    assert!(markdown.contains("- **MEDIUM** No request timeout in `src/http.rs` (Risk Assessment: users.diff)\n"));
+
+    let report = digest.report();
+    assert_eq!(report.title, "QA Digest: 2026-10-12 to 2026-10-18");
+    assert!(report.html().contains("<h2>High-Risk Changes</h2>"));
+
+    // An empty period still renders every section
+    let empty = Digest::from_runs(&history(), "2026-11-01", "2026-11-07").markdown();
+    assert!(empty.contains("## Analyses Performed\n\nNo runs in this period.\n"));
+    assert_eq!(empty.matches("None.").count(), 3);
+}
@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use qitops_agent::agent::e2e::{E2eContext, E2eFramework};
+use qitops_agent::agent::test_gen::{TestFormat, TestGenAgent};
+use qitops_agent::agent::traits::Agent;
+use qitops_agent::integrations::tcm::parse_test_cases;
+
+mod common;
+use common::Reply;
+
+const LOGIN_PAGE: &str = r#"export function LoginPage() {
+  return (
+    <form aria-label="Sign in">
+      <input id="email" name="email" />
+      <input data-cy="password" type="password" />
+      <button data-cy="submit">Sign in</button>
+    </form>
+  );
+}
+"#;
+
+/// Serve one chat completion with `answer`, returning the request body
+async fn serve_once(answer: &'static str) -> (String, tokio::task::JoinHandle<serde_json::Value>) {
+    let (address, server) = common::serve_once(Reply::completion(answer)).await;
+    (address, tokio::spawn(async move { server.await.unwrap().json() }))
+}
+
+/// A repository with a web app, its login page at `src/pages/login.tsx`
+fn project(name: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("qitops-e2e-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join(".git")).unwrap();
+    fs::create_dir_all(dir.join("src").join("pages")).unwrap();
+    let page = dir.join("src").join("pages").join("login.tsx");
+    fs::write(&page, LOGIN_PAGE).unwrap();
+    (fs::canonicalize(&dir).unwrap(), fs::canonicalize(page).unwrap())
+}
+
+#[test]
+fn playwright_context_comes_from_the_config_and_the_page() {
+    let (dir, page) = project("playwright");
+    fs::write(dir.join("tsconfig.json"), "{}").unwrap();
+    fs::write(dir.join("playwright.config.ts"), "export default defineConfig({\n  testDir: './e2e',\n  use: {\n    baseURL: process.env.BASE_URL || 'http://localhost:3000',\n    testIdAttribute: 'data-cy',\n  },\n});\n").unwrap();
+
+    let context = E2eContext::detect(E2eFramework::Playwright, &page, LOGIN_PAGE);
+    assert_eq!(context.root, dir);
+    assert!(context.typescript);
+    assert_eq!(context.base_url.as_deref(), Some("http://localhost:3000"));
+    assert_eq!(context.spec_path(&page, None), dir.join("e2e").join("login.spec.ts"));
+    assert_eq!(context.spec_path(&page, Some("submit")), dir.join("e2e").join("login_submit.spec.ts"));
+
+    let prompt = context.prompt();
+    assert!(prompt.contains("runnable Playwright spec in TypeScript"), "{}", prompt);
+    assert!(prompt.contains("navigate with relative paths, e.g. `await page.goto('/')`"));
+    // Test IDs come first, through the configured test ID attribute
+    let selectors: Vec<&str> = prompt.lines().filter(|line| line.starts_with("- ")).collect();
+    assert_eq!(selectors, [
+        "- page.getByTestId('password')",
+        "- page.getByTestId('submit')",
+        "- page.getByLabel('Sign in')",
+        "- page.locator('#email')",
+        "- page.locator('[name=\"email\"]')",
+    ]);
+
+    // Generated specs can be pushed to a test case manager by their titles
+    let spec = "```ts\nimport { test, expect } from '@playwright/test';\n\ntest.describe('Login', () => {\n  test('signs in with valid credentials', async ({ page }) => {\n    await page.goto('/login');\n  });\n  test.skip('rejects a wrong password', async () => {});\n});\n\ntest('redirects to login when signed out', async () => {});\n```";
+    let cases = parse_test_cases(spec, TestFormat::from_str("playwright").unwrap());
+    let titles: Vec<(&str, Option<&str>)> = cases.iter().map(|case| (case.title.as_str(), case.preconditions.as_deref())).collect();
+    assert_eq!(titles, [
+        ("signs in with valid credentials", Some("Login")),
+        ("rejects a wrong password", Some("Login")),
+        ("redirects to login when signed out", None),
+    ]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn cypress_specs_are_saved_where_cypress_finds_them() {
+    let (dir, page) = project("cypress");
+    fs::write(dir.join("package.json"), r#"{"scripts": {"dev": "vite --port 5174"}}"#).unwrap();
+    let spec = "describe('Login', () => {\n  it('signs in', () => {\n    cy.visit('/login');\n    cy.get('[data-cy=\"submit\"]').click();\n  });\n});";
+    let (api_base, server) = serve_once("Here is the spec:\n\n```javascript\ndescribe('Login', () => {\n  it('signs in', () => {\n    cy.visit('/login');\n    cy.get('[data-cy=\"submit\"]').click();\n  });\n});\n```\n\nRun it with `npx cypress run`.").await;
+
+    let router = common::router(&api_base);
+
+    let agent = TestGenAgent::new(page.to_string_lossy().to_string(), "cypress", None, None, router).await.unwrap();
+    let response = agent.execute().await.unwrap();
+    let body = server.await.unwrap();
+    let data = response.data.unwrap();
+
+    let system = body["messages"][0]["content"].as_str().unwrap();
+    assert!(system.contains("runnable end-to-end tests with Cypress"), "{}", system);
+    let prompt = body["messages"].as_array().unwrap().last().unwrap()["content"].as_str().unwrap();
+    // The source is TSX, so the spec is TypeScript even without a tsconfig
+    assert!(prompt.contains("runnable Cypress spec in TypeScript"), "{}", prompt);
+    assert!(prompt.contains("The app is served at http://localhost:5174"));
+    assert!(prompt.contains("- cy.get('[data-cy=\"submit\"]')"));
+    assert!(prompt.contains("- cy.get('#email')"));
+
+    // Saved as Cypress's default spec pattern expects, with only the code
+    let output = Path::new(data["output_file"].as_str().unwrap());
+    assert_eq!(output, dir.join("cypress").join("e2e").join("login.cy.ts"));
+    assert_eq!(fs::read_to_string(output).unwrap(), format!("{}\n", spec));
+    assert_eq!(data["e2e"]["framework"], "cypress");
+    assert_eq!(data["e2e"]["test_id_attribute"], "data-cy");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,30 @@
+#[cfg(feature = "github")]
+use anyhow::Context;
+
+#[cfg(feature = "github")]
+use qitops_agent::ci::github::GitHubError;
+use qitops_agent::error::{self, QitOpsError, EXIT_FAILURE, EXIT_INVALID_INPUT, EXIT_LIMIT_EXCEEDED};
+use qitops_agent::llm::client::LlmError;
+
+#[test]
+fn exit_codes_follow_the_typed_error() {
+    let rate_limited: anyhow::Error = LlmError::RateLimitError("slow down".to_string()).into();
+    assert_eq!(error::exit_code(&rate_limited), EXIT_LIMIT_EXCEEDED);
+
+    let missing_file: anyhow::Error = QitOpsError::InvalidInput("File not found: a.rs".to_string()).into();
+    assert_eq!(error::exit_code(&missing_file), EXIT_INVALID_INPUT);
+
+    let untyped = anyhow::anyhow!("Authentication error: but only in the message");
+    assert_eq!(error::exit_code(&untyped), EXIT_FAILURE);
+    assert!(error::hint(&untyped).is_none());
+}
+
+#[cfg(feature = "github")]
+#[test]
+fn context_does_not_hide_the_typed_error() {
+    let result: anyhow::Result<()> = Err(GitHubError::from_status(reqwest::StatusCode::UNAUTHORIZED, "Bad credentials".to_string()).into());
+    let e = result.context("Failed to fetch PR").unwrap_err();
+
+    assert_eq!(error::exit_code(&e), error::EXIT_AUTHENTICATION);
+    assert!(error::hint(&e).unwrap().contains("GITHUB_TOKEN"));
+}
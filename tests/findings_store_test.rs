@@ -0,0 +1,117 @@
+use std::path::PathBuf;
+
+use qitops_agent::output::Report;
+use qitops_agent::runs::findings::{FindingStatus, FindingsQuery, FindingsStore, RecordOutcome};
+use qitops_agent::severity::Severity;
+
+/// Seconds since the epoch at midnight UTC of 2026-10-12
+const MONDAY: u64 = 1_791_763_200;
+
+fn database(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("qitops-findings-{}-{}.db", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn report(command: &str, title: &str, findings: serde_json::Value, data: serde_json::Value) -> Report {
+    serde_json::from_value(serde_json::json!({
+        "command": command,
+        "title": title,
+        "summary": "",
+        "body": "",
+        "findings": findings,
+        "data": data,
+    })).unwrap()
+}
+
+fn injection() -> serde_json::Value {
+    serde_json::json!({"file": "src/users.py", "line": 11, "severity": "high", "title": "SQL injection in find_user"})
+}
+
+fn timeout() -> serde_json::Value {
+    serde_json::json!({"file": "src/http.rs", "severity": "medium", "title": "No request timeout"})
+}
+
+fn status(store: &FindingsStore, title: &str) -> FindingStatus {
+    let findings = store.query(&FindingsQuery::default()).unwrap();
+    findings.iter().find(|tracked| tracked.finding.title == title).unwrap().status
+}
+
+#[test]
+fn findings_are_resolved_when_a_later_run_drops_them_and_reopened_when_they_return() {
+    let path = database("lifecycle");
+    let mut store = FindingsStore::at(&path).unwrap();
+    let pr = "PR Analysis: #42";
+
+    let outcome = store.record("acme/shop", "r1", &report("pr-analyze", pr, serde_json::json!([injection(), timeout()]), serde_json::Value::Null), MONDAY).unwrap();
+    assert_eq!(outcome, RecordOutcome { new: 2, reopened: 0, resolved: 0 });
+
+    // The injection was fixed; the timeout is still there
+    let outcome = store.record("acme/shop", "r2", &report("pr-analyze", pr, serde_json::json!([timeout()]), serde_json::Value::Null), MONDAY + 60).unwrap();
+    assert_eq!(outcome, RecordOutcome { new: 0, reopened: 0, resolved: 1 });
+    assert_eq!(status(&store, "SQL injection in find_user"), FindingStatus::Resolved);
+    let resolved = store.query(&FindingsQuery { status: Some(FindingStatus::Resolved), ..FindingsQuery::default() }).unwrap();
+    assert_eq!(resolved[0].resolved_run.as_deref(), Some("r2"));
+    assert_eq!(resolved[0].resolved_at, Some(MONDAY + 60));
+
+    // A degraded run resolves nothing, and a baselined finding is suppressed, not resolved
+    let data = serde_json::json!({"degraded": {"reason": "LLM unavailable"}});
+    let outcome = store.record("acme/shop", "r3", &report("pr-analyze", pr, serde_json::json!([]), data), MONDAY + 120).unwrap();
+    assert_eq!(outcome.resolved, 0);
+    assert_eq!(status(&store, "No request timeout"), FindingStatus::Open);
+    let data = serde_json::json!({"baselined": [{"finding": timeout(), "reason": "accepted in baseline"}]});
+    store.record("acme/shop", "r4", &report("pr-analyze", pr, serde_json::json!([]), data), MONDAY + 180).unwrap();
+    assert_eq!(status(&store, "No request timeout"), FindingStatus::Suppressed);
+
+    // The injection came back
+    let outcome = store.record("acme/shop", "r5", &report("pr-analyze", pr, serde_json::json!([injection()]), serde_json::Value::Null), MONDAY + 240).unwrap();
+    assert_eq!(outcome, RecordOutcome { new: 0, reopened: 1, resolved: 0 });
+    let injection = store.query(&FindingsQuery { status: Some(FindingStatus::Open), ..FindingsQuery::default() }).unwrap();
+    assert_eq!(injection.len(), 1);
+    assert_eq!((injection[0].times_seen, injection[0].first_run.as_str(), injection[0].last_run.as_str()), (2, "r1", "r5"));
+    assert_eq!(injection[0].resolved_run, None);
+
+    // Reopening the store keeps everything
+    drop(store);
+    assert_eq!(FindingsStore::at(&path).unwrap().query(&FindingsQuery::default()).unwrap().len(), 2);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn findings_are_filtered_by_severity_repository_status_and_command() {
+    let path = database("query");
+    let mut store = FindingsStore::at(&path).unwrap();
+
+    store.record("acme/shop", "r1", &report("pr-analyze", "PR Analysis: #42", serde_json::json!([injection(), timeout()]), serde_json::Value::Null), MONDAY).unwrap();
+    store.record("acme/api", "r2", &report("risk", "Risk Assessment: api.diff", serde_json::json!([injection()]), serde_json::Value::Null), MONDAY + 60).unwrap();
+    // The same finding in another PR is tracked separately, and resolving it there doesn't resolve it here
+    store.record("acme/shop", "r3", &report("pr-analyze", "PR Analysis: #43", serde_json::json!([injection()]), serde_json::Value::Null), MONDAY + 120).unwrap();
+    store.record("acme/shop", "r4", &report("pr-analyze", "PR Analysis: #43", serde_json::json!([]), serde_json::Value::Null), MONDAY + 180).unwrap();
+
+    let all = store.query(&FindingsQuery::default()).unwrap();
+    assert_eq!(all.len(), 4);
+    // Most severe first, then most recently seen
+    assert_eq!(all[0].scope, "PR Analysis: #43");
+    assert_eq!(all.last().unwrap().finding.severity, Severity::Medium);
+
+    let open_high = store.query(&FindingsQuery {
+        min_severity: Some(Severity::High),
+        repo: Some("acme/shop".to_string()),
+        status: Some(FindingStatus::Open),
+        ..FindingsQuery::default()
+    }).unwrap();
+    assert_eq!(open_high.len(), 1);
+    assert_eq!(open_high[0].scope, "PR Analysis: #42");
+    assert_eq!(open_high[0].finding.line, Some(11));
+
+    let risk = store.query(&FindingsQuery { command: Some("risk".to_string()), ..FindingsQuery::default() }).unwrap();
+    assert_eq!(risk.len(), 1);
+    assert_eq!(risk[0].repo, "acme/api");
+
+    let limited = store.query(&FindingsQuery { limit: Some(2), seen_since: Some(MONDAY + 60), ..FindingsQuery::default() }).unwrap();
+    assert_eq!(limited.len(), 2);
+    assert!(limited.iter().all(|tracked| tracked.last_seen >= MONDAY + 60));
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,140 @@
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use qitops_agent::ci::fallback::{self, PrCache, PrData, files_from_diff};
+use qitops_agent::ci::github::PullRequest;
+use qitops_agent::ci::{GitHubClient, GitHubConfig};
+
+mod common;
+use common::scratch;
+
+/// A GitHub client whose API can't be reached
+fn unreachable_client() -> GitHubClient {
+    let config = GitHubConfig {
+        token: Some("test-token".to_string()),
+        api_base: Some("http://127.0.0.1:9".to_string()),
+        default_owner: None,
+        default_repo: None,
+        proxy: None,
+    };
+
+    GitHubClient::from_config(&config).unwrap()
+}
+
+/// Run git in a repository
+fn git(repo: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["-c", "user.name=QitOps", "-c", "user.email=qitops@example.com", "-c", "commit.gpgsign=false"])
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {} failed", args.join(" "));
+}
+
+#[tokio::test]
+async fn test_falls_back_to_cached_pr_data() -> Result<()> {
+    let dir = scratch("cache");
+    let cache = PrCache::at(dir.join("cache"));
+
+    let data = PrData {
+        pull_request: PullRequest {
+            number: 42,
+            title: "Add login rate limiting".to_string(),
+            body: Some("Limits failed logins".to_string()),
+            author: "octocat".to_string(),
+            state: "open".to_string(),
+            base_branch: "main".to_string(),
+            head_branch: "rate-limit".to_string(),
+            created_at: String::new(),
+            updated_at: String::new(),
+            head_sha: String::new(),
+        },
+        diff: "diff --git a/login.py b/login.py\n--- a/login.py\n+++ b/login.py\n@@ -1 +1,2 @@\n+limit()\n login()\n".to_string(),
+        files: Vec::new(),
+    };
+    cache.store("octo", "app", &data)?;
+
+    let fetch = fallback::fetch_pr(&unreachable_client(), "octo", "app", 42, &cache, &dir).await?;
+
+    assert_eq!(fetch.data.pull_request.title, "Add login rate limiting");
+    assert_eq!(fetch.data.diff, data.diff);
+    let reason = fetch.degraded.expect("analysis should be marked degraded");
+    assert!(reason.contains("cached"), "unexpected reason: {}", reason);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_falls_back_to_local_clone_diff() -> Result<()> {
+    let dir = scratch("clone");
+    let repo = dir.join("repo");
+    fs::create_dir_all(&repo)?;
+
+    git(&repo, &["init", "--quiet", "--initial-branch=main"]);
+    fs::write(repo.join("app.py"), "def handler():\n    return 1\n")?;
+    git(&repo, &["add", "."]);
+    git(&repo, &["commit", "--quiet", "-m", "Initial commit"]);
+    git(&repo, &["checkout", "--quiet", "-b", "feature"]);
+    fs::write(repo.join("app.py"), "def handler():\n    return 2\n")?;
+    fs::write(repo.join("new.py"), "print('new')\n")?;
+    git(&repo, &["add", "."]);
+    git(&repo, &["commit", "--quiet", "-m", "Change handler"]);
+
+    let cache = PrCache::at(dir.join("cache"));
+    let fetch = fallback::fetch_pr(&unreachable_client(), "octo", "app", 7, &cache, &repo).await?;
+
+    assert!(fetch.data.diff.contains("+    return 2"));
+    assert_eq!(fetch.data.pull_request.number, 7);
+    assert_eq!(fetch.data.pull_request.base_branch, "main");
+
+    let files: Vec<(&str, &str)> = fetch.data.files.iter().map(|f| (f.filename.as_str(), f.status.as_str())).collect();
+    assert_eq!(files, vec![("app.py", "modified"), ("new.py", "added")]);
+
+    let reason = fetch.degraded.expect("analysis should be marked degraded");
+    assert!(reason.contains("local diff"), "unexpected reason: {}", reason);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fails_without_local_data() -> Result<()> {
+    let dir = scratch("none");
+    let cache = PrCache::at(dir.join("cache"));
+
+    let error = fallback::fetch_pr(&unreachable_client(), "octo", "app", 1, &cache, &dir).await
+        .expect_err("there is nothing to fall back to");
+    assert!(error.to_string().contains("GitHub unavailable"), "unexpected error: {}", error);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_files_from_diff() {
+    let diff = "diff --git a/src/old.rs b/src/old.rs\n\
+        deleted file mode 100644\n\
+        --- a/src/old.rs\n\
+        +++ /dev/null\n\
+        @@ -1,2 +0,0 @@\n\
+        -fn old() {}\n\
+        -\n\
+        diff --git a/src/lib.rs b/src/lib.rs\n\
+        --- a/src/lib.rs\n\
+        +++ b/src/lib.rs\n\
+        @@ -1 +1,2 @@\n\
+        -pub mod old;\n\
+        +pub mod new;\n\
+        +pub mod other;\n";
+
+    let files = files_from_diff(diff);
+
+    assert_eq!(files.len(), 2);
+    assert_eq!((files[0].filename.as_str(), files[0].status.as_str(), files[0].deletions), ("src/old.rs", "removed", 2));
+    assert_eq!((files[1].filename.as_str(), files[1].additions, files[1].deletions, files[1].changes), ("src/lib.rs", 2, 1, 3));
+}
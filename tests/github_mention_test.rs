@@ -0,0 +1,83 @@
+use hmac::{Hmac, Mac};
+use qitops_agent::bot::github::{parse_event, parse_mention, verify_signature, MentionCommand, RepoRole};
+use serde_json::json;
+use sha2::Sha256;
+
+#[test]
+fn mentions_are_parsed_from_new_comments() {
+    assert_eq!(parse_mention("Looks good.\n@qitops risk please"), Some(MentionCommand::Risk));
+    assert_eq!(parse_mention("@QitOps review"), Some(MentionCommand::PrAnalyze));
+    assert_eq!(
+        parse_mention("@qitops generate tests for `src/auth.rs`"),
+        Some(MentionCommand::TestGen { path: "src/auth.rs".to_string() })
+    );
+    assert_eq!(parse_mention("@qitops"), Some(MentionCommand::Help));
+    assert_eq!(parse_mention("@qitops deploy"), Some(MentionCommand::Unknown("deploy".to_string())));
+
+    // Quoted commands, code blocks and other accounts are not mentions
+    assert_eq!(parse_mention("> @qitops risk\nThanks!"), None);
+    assert_eq!(parse_mention("```\n@qitops risk\n```"), None);
+    assert_eq!(parse_mention("cc @qitopsbot"), None);
+
+    // Text whose lowercase is longer than it is doesn't shift the mention
+    assert_eq!(parse_mention("İİ @qitops é"), Some(MentionCommand::Unknown("é".to_string())));
+    assert_eq!(parse_mention("İ @QITOPS risk"), Some(MentionCommand::Risk));
+
+    let payload = json!({
+        "action": "created",
+        "issue": { "number": 42, "pull_request": { "url": "https://api.github.com/repos/acme/shop/pulls/42" } },
+        "comment": { "id": 7, "body": "@qitops risk", "user": { "login": "dev", "type": "User" } },
+        "repository": { "name": "shop", "owner": { "login": "acme" } }
+    });
+    let event = parse_event("issue_comment", &payload).unwrap();
+    assert_eq!((event.owner.as_str(), event.repo.as_str(), event.number), ("acme", "shop", 42));
+    assert!(event.is_pull_request);
+    assert!(!event.review_comment);
+    assert_eq!(event.author, "dev");
+    assert_eq!(event.command, MentionCommand::Risk);
+
+    // Plain issues, edits and the bot's own replies
+    let mut issue = payload.clone();
+    issue["issue"]["pull_request"] = json!(null);
+    assert!(!parse_event("issue_comment", &issue).unwrap().is_pull_request);
+
+    let mut edited = payload.clone();
+    edited["action"] = json!("edited");
+    assert!(parse_event("issue_comment", &edited).is_none());
+
+    let mut from_bot = payload.clone();
+    from_bot["comment"]["user"]["type"] = json!("Bot");
+    assert!(parse_event("issue_comment", &from_bot).is_none());
+
+    let review = json!({
+        "action": "created",
+        "pull_request": { "number": 42 },
+        "comment": { "id": 9, "body": "@qitops test-gen src/auth.rs", "user": { "login": "dev", "type": "User" } },
+        "repository": { "name": "shop", "owner": { "login": "acme" } }
+    });
+    let event = parse_event("pull_request_review_comment", &review).unwrap();
+    assert!(event.review_comment);
+    assert_eq!(event.comment_id, 9);
+}
+
+#[test]
+fn deliveries_are_signed_and_roles_are_ordered() {
+    let secret = b"webhook-secret";
+    let payload = br#"{"action":"created"}"#;
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+    mac.update(payload);
+    let signature: String = mac.finalize().into_bytes().iter().map(|byte| format!("{:02x}", byte)).collect();
+    let header = format!("sha256={}", signature);
+
+    assert!(verify_signature(secret, payload, Some(&header)));
+    assert!(!verify_signature(b"other-secret", payload, Some(&header)));
+    assert!(!verify_signature(secret, br#"{"action":"deleted"}"#, Some(&header)));
+    assert!(!verify_signature(secret, payload, Some(&signature)));
+    assert!(!verify_signature(secret, payload, None));
+
+    assert_eq!("push".parse::<RepoRole>().unwrap(), RepoRole::Write);
+    assert!(RepoRole::Maintain >= RepoRole::Write);
+    assert!(RepoRole::Triage < RepoRole::Write);
+    assert!("owner".parse::<RepoRole>().is_err());
+}
@@ -0,0 +1,53 @@
+use qitops_agent::audit::{AUDIT_LOG_ENV, AuditAction, AuditLog};
+use qitops_agent::ci::{GitHubClient, GitHubConfig};
+
+mod common;
+use common::Reply;
+
+fn client(api_base: String) -> GitHubClient {
+    GitHubClient::from_config(&GitHubConfig {
+        token: Some("test-token".to_string()),
+        api_base: Some(api_base),
+        default_owner: None,
+        default_repo: None,
+        proxy: None,
+    }).unwrap()
+}
+
+#[tokio::test]
+async fn review_replies_are_undone_through_the_review_comments_endpoint() {
+    let audit_log = common::scratch("github-undo").join("audit.log");
+    unsafe { std::env::set_var(AUDIT_LOG_ENV, &audit_log) };
+
+    let (base, server) = common::serve(vec![
+        Reply::json(serde_json::json!({"id": 31, "body": "Risk: low", "user": {"login": "qitops-bot"}})),
+        Reply::json(serde_json::json!({"id": 32, "body": "Risk: low", "user": {"login": "qitops-bot"}})),
+        Reply::status(204, serde_json::Value::Null),
+        Reply::status(404, serde_json::json!({"message": "Not Found"})),
+    ]).await;
+    let github = client(base);
+
+    github.reply_to_review_comment("acme", "shop", 12, 500, "Risk: low").await.unwrap();
+    github.create_pull_request_comment("acme", "shop", 12, "Risk: low").await.unwrap();
+
+    let posted = AuditLog::open(&audit_log).read().unwrap();
+    let actions: Vec<(AuditAction, Option<u64>)> = posted.iter().map(|event| (event.action, event.resource_id)).collect();
+    assert_eq!(actions, [(AuditAction::ReviewCommentPosted, Some(31)), (AuditAction::CommentPosted, Some(32))]);
+
+    // Each comment is deleted where it lives; a comment already gone is reported as such
+    assert!(github.undo(&posted[0]).await.unwrap());
+    assert!(!github.undo(&posted[1]).await.unwrap());
+
+    let requests = server.await.unwrap();
+    let calls: Vec<(&str, &str)> = requests.iter().map(|request| (request.method.as_str(), request.path.as_str())).collect();
+    assert_eq!(calls[2..], [
+        ("DELETE", "/repos/acme/shop/pulls/comments/31"),
+        ("DELETE", "/repos/acme/shop/issues/comments/32"),
+    ]);
+
+    let events = AuditLog::open(&audit_log).read().unwrap();
+    let deletions: Vec<(AuditAction, Option<u64>, bool)> = events[2..].iter().map(|event| (event.action, event.resource_id, event.success)).collect();
+    assert_eq!(deletions, [(AuditAction::ReviewCommentDeleted, Some(31), true), (AuditAction::CommentDeleted, Some(32), true)]);
+    assert_eq!(AuditAction::ReviewCommentPosted.undone_by(), Some(AuditAction::ReviewCommentDeleted));
+    assert_eq!(AuditAction::ReviewPosted.undone_by(), None);
+}
@@ -0,0 +1,29 @@
+use qitops_agent::history::{self, HistoryConfig, KEY_ENV};
+
+#[test]
+fn encrypted_transcripts_round_trip_and_hide_their_content() {
+    // A fixed key keeps the test off the OS keyring
+    unsafe { std::env::set_var(KEY_ENV, "MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=") };
+
+    let dir = std::env::temp_dir().join(format!("qitops-history-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let transcript = "**tester:** the staging password is hunter2\n";
+
+    let plain = history::write(&dir.join("plain.md"), transcript, &HistoryConfig::default()).unwrap();
+    assert_eq!(history::read(&plain).unwrap(), transcript);
+
+    let encrypted = history::write(&dir.join("secret.md"), transcript, &HistoryConfig { encrypt: true }).unwrap();
+    assert!(encrypted.ends_with("secret.md.enc"));
+    assert!(!std::fs::read_to_string(&encrypted).unwrap().contains("hunter2"));
+    assert_eq!(history::read(&encrypted).unwrap(), transcript);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(std::fs::metadata(&encrypted).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    let other_key = aes_gcm::Key::<aes_gcm::Aes256Gcm>::from_slice(&[7u8; 32]);
+    assert!(history::decrypt(&std::fs::read_to_string(&encrypted).unwrap(), other_key).is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
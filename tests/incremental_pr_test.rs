@@ -0,0 +1,87 @@
+use anyhow::Result;
+
+use qitops_agent::agent::findings::Finding;
+use qitops_agent::agent::incremental::{AnalysisStore, PriorAnalysis, diff_for_files, plan_delta};
+use qitops_agent::ci::{GitHubClient, GitHubConfig};
+use qitops_agent::severity::Severity;
+
+mod common;
+
+/// A finding on a file
+fn finding(file: Option<&str>, title: &str) -> Finding {
+    Finding {
+        file: file.map(str::to_string),
+        line: None,
+        severity: Severity::Medium,
+        title: title.to_string(),
+        description: String::new(),
+    }
+}
+
+/// Serve a single GitHub API answer, returning the requested path
+async fn serve_once(answer: serde_json::Value) -> (String, tokio::task::JoinHandle<String>) {
+    let (address, server) = common::serve_once(answer).await;
+    (address, tokio::spawn(async move { server.await.unwrap().path }))
+}
+
+#[test]
+fn test_plans_delta_and_carries_over_unchanged_findings() -> Result<()> {
+    let prior = PriorAnalysis {
+        head_sha: "abc123".to_string(),
+        focus: "general".to_string(),
+        analysis: "Looks fine".to_string(),
+        findings: vec![
+            finding(Some("src/login.rs"), "Unbounded retries"),
+            finding(Some("src/session.rs"), "Session not expired"),
+            finding(Some("src/dropped.rs"), "Dead code"),
+            finding(None, "Missing tests"),
+        ],
+    };
+    let changed_since = vec!["src/login.rs".to_string(), "README.base.md".to_string()];
+    let pr_files = vec!["src/login.rs".to_string(), "src/session.rs".to_string()];
+
+    let delta = plan_delta(&prior, &changed_since, &pr_files);
+
+    // Files merged in from the base branch aren't re-analyzed
+    assert_eq!(delta.files, vec!["src/login.rs".to_string()]);
+    // Only findings on unchanged files still in the PR carry over
+    let carried: Vec<&str> = delta.carried_over.iter().map(|f| f.title.as_str()).collect();
+    assert_eq!(carried, vec!["Session not expired"]);
+
+    let diff = "diff --git a/src/login.rs b/src/login.rs\n+retry()\ndiff --git a/src/session.rs b/src/session.rs\n+expire()\n";
+    assert_eq!(diff_for_files(diff, &delta.files), "diff --git a/src/login.rs b/src/login.rs\n+retry()\n");
+
+    let dir = std::env::temp_dir().join(format!("qitops-incremental-{}", std::process::id()));
+    let store = AnalysisStore::at(&dir);
+    assert!(store.load("octo", "app", 7).is_none());
+    store.store("octo", "app", 7, &prior)?;
+    let loaded = store.load("octo", "app", 7).expect("stored analysis should load");
+    assert_eq!(loaded.head_sha, "abc123");
+    assert_eq!(loaded.findings.len(), 4);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_compares_commits_since_last_analysis() -> Result<()> {
+    let (address, server) = serve_once(serde_json::json!({
+        "status": "ahead",
+        "files": [{ "filename": "src/login.rs" }, { "filename": "src/util.rs" }]
+    })).await;
+
+    let client = GitHubClient::from_config(&GitHubConfig {
+        token: Some("test-token".to_string()),
+        api_base: Some(address),
+        default_owner: None,
+        default_repo: None,
+        proxy: None,
+    })?;
+
+    let comparison = client.compare_commits("octo", "app", "abc123", "def456").await?;
+
+    assert_eq!(server.await?, "/repos/octo/app/compare/abc123...def456");
+    assert_eq!(comparison.status, "ahead");
+    assert_eq!(comparison.files, vec!["src/login.rs".to_string(), "src/util.rs".to_string()]);
+    Ok(())
+}
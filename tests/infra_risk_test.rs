@@ -0,0 +1,127 @@
+use qitops_agent::agent::baseline::BaselineMode;
+use qitops_agent::agent::infra::{ChangeAction, InfraKind, RiskSignal, infra_changes};
+use qitops_agent::agent::{Agent, RiskAgent};
+
+mod common;
+use common::Reply;
+
+const DIFF: &str = r#"diff --git a/infra/network.tf b/infra/network.tf
+--- a/infra/network.tf
++++ b/infra/network.tf
+@@ -12,7 +12,7 @@ resource "aws_security_group" "web" {
+   ingress {
+     from_port   = 443
+     to_port     = 443
+-    cidr_blocks = ["10.0.0.0/8"]
++    cidr_blocks = ["0.0.0.0/0"]
+   }
+ }
+
+@@ -30,0 +31,4 @@
++resource "aws_db_instance" "orders" {
++  instance_class      = "db.r6g.2xlarge"
++  password            = "hunter2"
++}
+diff --git a/k8s/api.yaml b/k8s/api.yaml
+--- a/k8s/api.yaml
++++ b/k8s/api.yaml
+@@ -1,9 +1,9 @@
+ apiVersion: apps/v1
+ kind: Deployment
+ metadata:
+   name: api
+ spec:
+-  replicas: 3
++  replicas: 1
+   strategy:
+-    type: RollingUpdate
++    type: Recreate
+diff --git a/Dockerfile b/Dockerfile
+--- a/Dockerfile
++++ b/Dockerfile
+@@ -1,3 +1,4 @@
+-FROM node:18-alpine AS build
++FROM node:latest AS build
++ENV NPM_TOKEN=abc123
+ WORKDIR /app
+ COPY . .
+diff --git a/.github/workflows/deploy.yml b/.github/workflows/deploy.yml
+--- a/.github/workflows/deploy.yml
++++ b/.github/workflows/deploy.yml
+@@ -5,5 +5,6 @@ on:
+ jobs:
+   deploy:
+     runs-on: ubuntu-latest
++    permissions: write-all
+     steps:
+       - uses: actions/checkout@v4
+diff --git a/src/app.py b/src/app.py
+--- a/src/app.py
++++ b/src/app.py
+@@ -1,1 +1,1 @@
+-print("hello")
++print("hello, world")
+"#;
+
+/// Serve one chat completion, returning the messages that were sent
+async fn serve_once(answer: &str) -> (String, tokio::task::JoinHandle<String>) {
+    let (address, server) = common::serve_once(Reply::completion(answer)).await;
+    (address, tokio::spawn(async move { server.await.unwrap().messages() }))
+}
+
+#[test]
+fn resource_level_changes_are_parsed_from_the_diff() {
+    let changes = infra_changes(DIFF);
+    let summary: Vec<(InfraKind, &str, ChangeAction)> = changes.iter()
+        .map(|change| (change.kind, change.resource.as_str(), change.action))
+        .collect();
+    assert_eq!(summary, vec![
+        (InfraKind::Terraform, "aws_security_group.web", ChangeAction::Modified),
+        (InfraKind::Terraform, "aws_db_instance.orders", ChangeAction::Added),
+        (InfraKind::Kubernetes, "Deployment/api", ChangeAction::Modified),
+        (InfraKind::Dockerfile, "stage build", ChangeAction::Modified),
+        (InfraKind::Ci, "job deploy", ChangeAction::Modified),
+    ]);
+
+    assert_eq!(changes[0].attributes, vec!["cidr_blocks"]);
+    assert_eq!(changes[0].signals, vec![RiskSignal::Exposure]);
+    assert_eq!(changes[1].attributes, vec!["instance_class", "password"]);
+    assert!(changes[1].signals.contains(&RiskSignal::Secrets));
+    assert_eq!(changes[2].attributes, vec!["replicas", "type"]);
+    assert_eq!(changes[2].signals, vec![RiskSignal::Quota, RiskSignal::Rollout]);
+    assert_eq!(changes[3].attributes, vec!["FROM", "ENV"]);
+    assert_eq!(changes[3].signals, vec![RiskSignal::Secrets, RiskSignal::Rollout]);
+    assert_eq!(changes[4].attributes, vec!["permissions"]);
+    assert_eq!(changes[4].signals, vec![RiskSignal::Rollout]);
+
+    // Application code has no resources
+    assert!(infra_changes("--- a/src/app.py\n+++ b/src/app.py\n@@ -1 +1 @@\n-a\n+b\n").is_empty());
+}
+
+#[tokio::test]
+async fn risk_prompt_gets_infrastructure_criteria() {
+    let dir = std::env::temp_dir().join(format!("qitops-infra-risk-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let diff = dir.join("change.diff");
+    std::fs::write(&diff, DIFF).unwrap();
+
+    let (api_base, server) = serve_once(r#"{"overall_risk": "High", "component_risks": [], "summary": "Opens the web security group to the internet.", "recommendations": [], "findings": []}"#).await;
+    let router = common::router(&api_base);
+
+    let agent = RiskAgent::new_from_diff(diff.to_string_lossy().to_string(), Vec::new(), Vec::new(), router).await.unwrap()
+        .with_baseline(BaselineMode::Ignore);
+    let data = agent.execute().await.unwrap().data.unwrap();
+    let prompt = server.await.unwrap();
+
+    assert!(prompt.contains("infrastructure and platform engineer"), "{}", prompt);
+    assert!(prompt.contains("- Terraform `aws_security_group.web` in infra/network.tf (modified): cidr_blocks [exposure]"), "{}", prompt);
+    assert!(prompt.contains("- Rollout strategy:"));
+
+    assert_eq!(data["infra_changes"].as_array().unwrap().len(), 5);
+    assert_eq!(data["infra_changes"][1]["action"], "added");
+    let assessment = data["assessment"].as_str().unwrap();
+    assert!(assessment.contains("## Infrastructure Changes"), "{}", assessment);
+    assert!(assessment.contains("Kubernetes `Deployment/api` in k8s/api.yaml (modified): replicas, type [quota, rollout]"), "{}", assessment);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,80 @@
+use qitops_agent::agent::findings::Finding;
+use qitops_agent::integrations::jira::{JiraClient, JiraConfig, file_findings, fingerprint_label};
+use qitops_agent::severity::Severity;
+use serde_json::json;
+
+mod common;
+use common::{Received, Reply, scratch};
+
+fn findings() -> Vec<Finding> {
+    [(Severity::Critical, "SQL built from request input"), (Severity::High, "Token logged"), (Severity::Low, "Typo in error message")].iter()
+        .map(|(severity, title)| Finding {
+            file: Some("src/orders.rs".to_string()),
+            line: Some(12),
+            severity: *severity,
+            title: title.to_string(),
+            description: String::new(),
+        })
+        .collect()
+}
+
+fn config() -> JiraConfig {
+    JiraConfig { project: Some("QA".to_string()), labels: vec!["qitops".to_string()], ..JiraConfig::default() }
+}
+
+/// Labels searched for in a run's requests
+fn searched_labels(requests: &[Received]) -> Vec<String> {
+    requests.iter()
+        .filter(|request| request.method == "GET")
+        .map(|request| request.path.rsplit("labels%20%3D%20%22").next().unwrap().split("%22").next().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn findings_are_filed_once_across_runs() {
+    unsafe { std::env::set_var("HOME", scratch("jira")) };
+    let findings = findings();
+
+    // First run: nothing is filed yet, so an issue is created for each finding at or above High
+    let (base_url, server) = common::serve(vec![
+        Reply::json(json!({ "issues": [] })),
+        Reply::json(json!({ "key": "QA-1" })),
+        Reply::json(json!({ "issues": [] })),
+        Reply::json(json!({ "key": "QA-2" })),
+    ]).await;
+    let client = JiraClient::new(&base_url, Some("qa@acme.io".to_string()), "token".to_string());
+    let filed = file_findings(&client, &config(), &findings, "acme/shop#42").await.unwrap();
+    assert_eq!(filed.created, [
+        ("QA-1".to_string(), "SQL built from request input".to_string()),
+        ("QA-2".to_string(), "Token logged".to_string()),
+    ]);
+    assert!(filed.existing.is_empty());
+
+    let first = server.await.unwrap();
+    let created_labels: Vec<serde_json::Value> = first.iter()
+        .filter(|request| request.method == "POST")
+        .map(|request| request.json()["fields"]["labels"].clone())
+        .collect();
+    assert_eq!(created_labels, [
+        json!(["qitops", fingerprint_label(&findings[0])]),
+        json!(["qitops", fingerprint_label(&findings[1])]),
+    ]);
+
+    // Second run: Jira finds the issues by the fingerprint labels they were filed with
+    let (base_url, server) = common::serve(vec![
+        Reply::json(json!({ "issues": [{ "key": "QA-1" }] })),
+        Reply::json(json!({ "issues": [{ "key": "QA-2" }] })),
+    ]).await;
+    let client = JiraClient::new(&base_url, Some("qa@acme.io".to_string()), "token".to_string());
+    let filed = file_findings(&client, &config(), &findings, "acme/shop#43").await.unwrap();
+    assert!(filed.created.is_empty());
+    assert_eq!(filed.existing, [
+        ("QA-1".to_string(), "SQL built from request input".to_string()),
+        ("QA-2".to_string(), "Token logged".to_string()),
+    ]);
+
+    let second = server.await.unwrap();
+    assert!(second.iter().all(|request| request.method == "GET"));
+    assert_eq!(searched_labels(&second), searched_labels(&first));
+    assert_eq!(searched_labels(&second), [fingerprint_label(&findings[0]), fingerprint_label(&findings[1])]);
+}
@@ -0,0 +1,28 @@
+use qitops_agent::llm::LlmRequest;
+use qitops_agent::llm::json::{extract_json, parse_json, repair_request};
+
+#[test]
+fn json_is_extracted_from_fences_and_prose() {
+    assert_eq!(extract_json("```json\n{\"a\": 1}\n```"), "{\"a\": 1}");
+    assert_eq!(extract_json("Here you go: [1, 2] Hope that helps."), "[1, 2]");
+    assert_eq!(extract_json("not json"), "not json");
+}
+
+#[test]
+fn invalid_answers_are_sent_back_for_correction() {
+    let count = |records: &Vec<u32>| match records.len() {
+        2 => Ok(()),
+        n => Err(anyhow::anyhow!("Expected 2 records but got {}", n)),
+    };
+
+    assert_eq!(parse_json::<Vec<u32>, _>("[1, 2]", count).unwrap(), vec![1, 2]);
+    assert!(parse_json::<Vec<u32>, _>("[\"a\"]", count).is_err());
+
+    let error = parse_json::<Vec<u32>, _>("[1]", count).unwrap_err();
+    let request = repair_request(LlmRequest::new("Generate".to_string(), "model".to_string()), "[1]", &error);
+
+    assert_eq!(request.messages.len(), 3);
+    assert_eq!(request.messages[1].content, "[1]");
+    assert!(request.messages[2].content.starts_with("Expected 2 records but got 1"));
+    assert!(!request.use_cache);
+}
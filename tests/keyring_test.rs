@@ -0,0 +1,34 @@
+use qitops_agent::llm::{ConfigManager, ProviderConfig};
+use qitops_agent::secrets;
+
+#[test]
+fn no_keyring_keeps_api_keys_in_the_config_file() {
+    secrets::disable();
+    assert!(!secrets::is_enabled());
+    assert_eq!(secrets::store(secrets::GITHUB_ACCOUNT, Some("ghp_token")).as_deref(), Some("ghp_token"));
+    assert_eq!(secrets::get(secrets::GITHUB_ACCOUNT), None);
+
+    let path = std::env::temp_dir().join(format!("qitops-keyring-test-{}.json", std::process::id()));
+    let mut manager = ConfigManager::with_path(&path).unwrap();
+    manager.add_provider(ProviderConfig {
+        provider_type: "anthropic".to_string(),
+        api_key: Some("sk-ant-test".to_string()),
+        api_base: None,
+        default_model: "claude-3-haiku".to_string(),
+        options: Default::default(),
+        requests_per_minute: None,
+        tokens_per_minute: None,
+        max_concurrent_requests: None,
+        proxy: None,
+        timeout_secs: None,
+        connect_timeout_secs: None,
+        max_retries: None,
+        headers: Default::default(),
+        auth: None,
+    }).unwrap();
+    manager.save_config().unwrap();
+
+    let saved = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(saved.contains("sk-ant-test"));
+}
@@ -0,0 +1,62 @@
+use qitops_agent::bot::knowledge::{KB_SCHEMA_VERSION, KbManifest, KnowledgeBase};
+use std::fs;
+use std::path::Path;
+
+mod common;
+use common::scratch;
+
+const FILES: [&str; 4] = ["commands.json", "config.json", "faq.json", "examples.json"];
+
+/// Copy of the knowledge base files as shipped before schema versions, without a manifest
+fn version_1(name: &str) -> std::path::PathBuf {
+    let dir = scratch(name);
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/knowledge-v1");
+    for file in FILES {
+        fs::copy(fixture.join(file), dir.join(file)).unwrap();
+    }
+    dir
+}
+
+fn read_json(path: &Path) -> serde_json::Value {
+    serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn version_1_knowledge_bases_load_and_upgrade() {
+    let dir = version_1("kb-v1");
+
+    let kb = KnowledgeBase::load(&dir).unwrap();
+    let test_gen = kb.get_command_doc("test-gen").unwrap();
+    assert_eq!(test_gen.usage, "qitops run test-gen --path <file_or_directory> [options]");
+    assert!(!kb.faq.is_empty() && !kb.examples.is_empty());
+    assert!(!dir.join("manifest.json").exists());
+
+    // Validating reports the migration, and writing it adds the manifest without changing the files
+    let report = KnowledgeBase::validate(&dir, false).unwrap();
+    assert_eq!(report.schema_version, 1);
+    assert!(report.migrated);
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert!(!dir.join("manifest.json").exists());
+
+    let before: Vec<serde_json::Value> = FILES.iter().map(|file| read_json(&dir.join(file))).collect();
+    KnowledgeBase::validate(&dir, true).unwrap();
+    let manifest: KbManifest = serde_json::from_value(read_json(&dir.join("manifest.json"))).unwrap();
+    assert_eq!(manifest.schema_version, KB_SCHEMA_VERSION);
+    let after: Vec<serde_json::Value> = FILES.iter().map(|file| read_json(&dir.join(file))).collect();
+    assert_eq!(before, after);
+
+    // The upgraded knowledge base needs no further migration and loads the same
+    let report = KnowledgeBase::validate(&dir, true).unwrap();
+    assert_eq!(report.schema_version, KB_SCHEMA_VERSION);
+    assert!(!report.migrated);
+    assert_eq!(KnowledgeBase::load(&dir).unwrap().commands.len(), kb.commands.len());
+}
+
+#[test]
+fn newer_knowledge_bases_are_refused() {
+    let dir = version_1("kb-newer");
+    fs::write(dir.join("manifest.json"), format!("{{\"schema_version\": {}}}", KB_SCHEMA_VERSION + 1)).unwrap();
+
+    let error = KnowledgeBase::load(&dir).unwrap_err();
+    assert!(error.to_string().contains("upgrade QitOps Agent"), "{}", error);
+}
@@ -0,0 +1,64 @@
+use qitops_agent::agent::large_pr::{FileAnalysis, LargePrConfig, file_summaries, select_files, split_by_file};
+use qitops_agent::bench::ReplayClient;
+use qitops_agent::config::QitOpsConfig;
+use qitops_agent::llm::{LlmRouter, ProviderConfig, RouterConfig};
+use std::sync::Arc;
+
+/// A diff touching one file per entry of `lines`, each with that many added lines
+fn diff(lines: &[usize]) -> String {
+    lines.iter().enumerate().map(|(file, lines)| {
+        let body: String = (0..*lines).map(|line| format!("+    let value_{} = compute({});\n", line, file)).collect();
+        format!("diff --git a/src/module_{file}.rs b/src/module_{file}.rs\n--- a/src/module_{file}.rs\n+++ b/src/module_{file}.rs\n@@ -1,0 +1,{lines} @@\n{body}")
+    }).collect()
+}
+
+#[test]
+fn diffs_split_per_file_and_keep_the_largest_files() {
+    let diff = diff(&[3, 40, 1, 12]);
+
+    let sections = split_by_file(&diff);
+    let paths: Vec<&str> = sections.iter().map(|section| section.path.as_str()).collect();
+    assert_eq!(paths, ["src/module_0.rs", "src/module_1.rs", "src/module_2.rs", "src/module_3.rs"]);
+    assert_eq!(sections.iter().map(|section| section.diff.as_str()).collect::<String>(), diff);
+    assert_eq!(sections[1].changed_lines(), 40);
+
+    // The largest files are kept in diff order; the others are listed
+    let (selected, skipped) = select_files(sections.clone(), 2);
+    let kept: Vec<&str> = selected.iter().map(|section| section.path.as_str()).collect();
+    assert_eq!(kept, ["src/module_1.rs", "src/module_3.rs"]);
+    assert_eq!(skipped, ["src/module_0.rs", "src/module_2.rs"]);
+    assert_eq!(select_files(sections, 10).1, Vec::<String>::new());
+
+    // Renamed files are named by their new path
+    let renamed = split_by_file("diff --git a/old name.rs b/new name.rs\nsimilarity index 100%\n");
+    assert_eq!(renamed[0].path, "new name.rs");
+}
+
+#[test]
+fn large_diffs_do_not_fit_one_prompt_and_get_file_summaries() {
+    let provider: ProviderConfig = serde_json::from_value(serde_json::json!({
+        "provider_type": "replay",
+        "default_model": "gpt-4o",
+        "options": { "context_window": "1500" },
+    })).unwrap();
+    let router = LlmRouter::with_clients(
+        RouterConfig { providers: vec![provider], ..RouterConfig::default() },
+        vec![Arc::new(ReplayClient::new(Vec::new()))],
+    );
+    assert!(router.fits_prompt(&diff(&[5, 5]), Some("pr-analyze")));
+    assert!(!router.fits_prompt(&diff(&[60, 60, 60]), Some("pr-analyze")));
+
+    let analyses = vec![
+        FileAnalysis { path: "src/cart.rs".to_string(), summary: "Adds tax to totals.\n".to_string(), findings: 1 },
+        FileAnalysis { path: "src/refund.rs".to_string(), summary: "Refunds whole orders.".to_string(), findings: 0 },
+    ];
+    let markdown = file_summaries(&analyses, &["docs/notes.md".to_string()]);
+    assert_eq!(markdown, "## File Summaries\n\n### `src/cart.rs`\n\nAdds tax to totals.\n\n### `src/refund.rs`\n\nRefunds whole orders.\n\n1 file(s) with the fewest changes were not analyzed (`large_pr.max_files`): `docs/notes.md`\n");
+    assert!(!file_summaries(&analyses, &[]).contains("not analyzed"));
+
+    // Configured in the `large_pr` section
+    assert_eq!(QitOpsConfig::default().large_pr.max_files, 50);
+    let config: QitOpsConfig = serde_json::from_value(serde_json::json!({"large_pr": {"max_files": 8}})).unwrap();
+    assert_eq!(config.large_pr.max_files, 8);
+    assert_eq!(serde_json::from_value::<LargePrConfig>(serde_json::json!({})).unwrap().max_files, 50);
+}
@@ -0,0 +1,41 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use qitops_agent::agent::TestDataAgent;
+use qitops_agent::llm::batch::BatchProgress;
+use qitops_agent::llm::batch::run_batch;
+
+#[tokio::test]
+async fn batches_keep_order_and_bound_concurrency() {
+    let in_flight = AtomicUsize::new(0);
+    let most_in_flight = AtomicUsize::new(0);
+    let mut updates = Vec::new();
+
+    let results = run_batch((0..10u64).collect(), 3, |n| {
+        let (in_flight, most_in_flight) = (&in_flight, &most_in_flight);
+        async move {
+            let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            most_in_flight.fetch_max(now, Ordering::SeqCst);
+            // Later items finish first, so completion order differs from item order
+            tokio::time::sleep(Duration::from_millis(30 - n * 3)).await;
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            if n == 4 { Err(anyhow::anyhow!("provider error")) } else { Ok(n * 10) }
+        }
+    }, |progress| updates.push(*progress)).await;
+
+    assert!(most_in_flight.load(Ordering::SeqCst) <= 3);
+    assert_eq!(results.len(), 10);
+    assert_eq!(results[2].as_ref().unwrap(), &20);
+    assert!(results[4].is_err());
+    assert_eq!(results[9].as_ref().unwrap(), &90);
+    assert_eq!(updates.len(), 10);
+    assert_eq!(updates.last(), Some(&BatchProgress { total: 10, completed: 10, failed: 1 }));
+}
+
+#[test]
+fn large_test_data_counts_are_split_into_batches() {
+    assert_eq!(TestDataAgent::batch_sizes(20), vec![20]);
+    assert_eq!(TestDataAgent::batch_sizes(120), vec![50, 50, 20]);
+    assert!(TestDataAgent::batch_sizes(0).is_empty());
+}
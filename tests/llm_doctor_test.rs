@@ -0,0 +1,74 @@
+use qitops_agent::llm::probe::{CheckStatus, probe_provider};
+use qitops_agent::llm::ProviderConfig;
+#[cfg(feature = "local-llm")]
+use qitops_agent::llm::{LlmRouter, RouterConfig};
+
+mod common;
+use common::Reply;
+
+/// Serve canned answers to consecutive requests
+async fn serve(replies: Vec<Reply>) -> String {
+    common::serve(replies).await.0
+}
+
+fn provider(provider_type: &str, api_base: &str, model: &str) -> ProviderConfig {
+    serde_json::from_value(serde_json::json!({
+        "provider_type": provider_type,
+        "api_key": "sk-test",
+        "api_base": api_base,
+        "default_model": model,
+    })).unwrap()
+}
+
+fn models() -> serde_json::Value {
+    serde_json::json!({ "data": [{ "id": "gpt-4o" }, { "id": "gpt-4o-mini" }] })
+}
+
+#[tokio::test]
+async fn healthy_provider_passes_every_check() {
+    let api_base = serve(vec![
+        Reply::json(models()),
+        Reply::completion("OK"),
+    ]).await;
+
+    let probe = probe_provider(&provider("openai", &api_base, "gpt-4o-mini"), true).await;
+
+    assert!(probe.healthy(), "{:?}", probe);
+    let checks: Vec<&str> = probe.checks.iter().map(|check| check.check).collect();
+    assert_eq!(checks, vec!["configuration", "auth", "model", "completion"]);
+    assert!(probe.checks.iter().all(|check| check.status == CheckStatus::Ok));
+    assert!(probe.latency_ms.is_some());
+
+    // A model the provider doesn't serve fails, pointing at the model list
+    let api_base = serve(vec![Reply::json(models())]).await;
+    let probe = probe_provider(&provider("openai", &api_base, "gpt-5-typo"), false).await;
+    assert!(!probe.healthy());
+    let problem = probe.problem().unwrap();
+    assert!(problem.contains("'gpt-5-typo' is not among the 2 model(s)"), "{}", problem);
+    assert!(problem.contains("qitops llm models --provider openai"), "{}", problem);
+}
+
+#[tokio::test]
+async fn problems_come_with_hints() {
+    let api_base = serve(vec![Reply::status(401, serde_json::json!({ "error": { "message": "Incorrect API key provided" } }))]).await;
+    let probe = probe_provider(&provider("openai", &api_base, "gpt-4o"), false).await;
+    let failed = probe.checks.iter().find(|check| check.status == CheckStatus::Failed).unwrap();
+    assert_eq!(failed.check, "auth");
+    assert!(failed.hint.as_deref().unwrap().contains("OPENAI_API_KEY"));
+}
+
+#[cfg(feature = "local-llm")]
+#[tokio::test]
+async fn unreachable_ollama_comes_with_a_hint_and_fails_router_startup() {
+    let ollama = provider("ollama", "http://127.0.0.1:9", "llama3");
+    let probe = probe_provider(&ollama, false).await;
+    let problem = probe.problem().unwrap();
+    assert!(problem.contains("Ollama is not reachable at http://127.0.0.1:9"), "{}", problem);
+    assert!(problem.contains("ollama serve"), "{}", problem);
+
+    // Router startup reports the same reasons instead of a bare "no providers"
+    let config = RouterConfig { providers: vec![ollama], default_provider: "ollama".to_string(), ..RouterConfig::default() };
+    let error = LlmRouter::new(config).await.err().expect("no provider is available").to_string();
+    assert!(error.contains("no LLM providers are available"), "{}", error);
+    assert!(error.contains("ollama: Ollama is not reachable at http://127.0.0.1:9"), "{}", error);
+}
@@ -0,0 +1,89 @@
+use aes_gcm::aead::{KeyInit, OsRng};
+use aes_gcm::Aes256Gcm;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use std::fs;
+
+use qitops_agent::masking::{MASKING_KEY_ENV, MaskingDictionary};
+
+mod common;
+use common::scratch;
+
+#[test]
+fn masked_values_are_consistent_and_keep_their_shape() {
+    let dir = scratch("masking-shape");
+    let mut dictionary = MaskingDictionary::open(dir.join("masking.dict"), Aes256Gcm::generate_key(OsRng)).unwrap();
+
+    let mut records = serde_json::json!([
+        {"email": "ann@acme.com", "phone": "5550123", "name": "Ann", "address": {"Name": "Ann"}},
+        {"email": "bob@acme.com", "phone": 5550199, "name": "Bob"},
+    ]);
+    dictionary.mask_json(&mut records, &["email".to_string(), "phone".to_string(), "name".to_string()]);
+
+    // Fields are masked in key order, so the nested name comes first and sets the fake value
+    assert_eq!(records[0]["address"]["Name"], "Name_1");
+    assert_eq!(records[0]["email"], "user2@example.com");
+    // The same real value gets the same fake value, in any field and at any depth
+    assert_eq!(records[0]["name"], "Name_1");
+    assert_eq!(records[0]["phone"], "0000003");
+    assert_eq!(records[1]["email"], "user4@example.com");
+    assert_eq!(records[1]["name"], "name_5");
+    assert_eq!(records[1]["phone"], 6);
+    assert_eq!(dictionary.mapping_count(), 6);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn the_dictionary_is_encrypted_private_and_needs_its_key() {
+    let dir = scratch("masking-crypto");
+    let path = dir.join("masking.dict");
+    let key = Aes256Gcm::generate_key(OsRng);
+
+    let mut dictionary = MaskingDictionary::open(path.clone(), key).unwrap();
+    assert_eq!(dictionary.mask("email", "ann@acme.com"), "user1@example.com");
+    dictionary.save().unwrap();
+
+    let contents = fs::read_to_string(&path).unwrap();
+    assert!(!contents.contains("ann@acme.com"));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    // Mappings survive a reload with the same key, and new values continue the numbering
+    let mut reloaded = MaskingDictionary::open(path.clone(), key).unwrap();
+    assert_eq!(reloaded.mask("email", "ann@acme.com"), "user1@example.com");
+    assert_eq!(reloaded.mask("email", "bob@acme.com"), "user2@example.com");
+
+    let error = MaskingDictionary::open(path, Aes256Gcm::generate_key(OsRng)).err().unwrap().to_string();
+    assert_eq!(error, "Failed to decrypt masking dictionary (wrong key?)");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn the_key_from_the_environment_is_never_written_next_to_the_dictionary() {
+    let home = scratch("masking-env");
+    unsafe {
+        std::env::set_var("HOME", &home);
+        std::env::set_var("APPDATA", &home);
+        std::env::set_var(MASKING_KEY_ENV, BASE64.encode(Aes256Gcm::generate_key(OsRng)));
+    }
+
+    let mut dictionary = MaskingDictionary::load().unwrap();
+    dictionary.mask("email", "ann@acme.com");
+    dictionary.save().unwrap();
+    assert_eq!(MaskingDictionary::load().unwrap().mapping_count(), 1);
+
+    let config_dir = qitops_agent::config::config_dir().unwrap();
+    assert!(config_dir.join("masking.dict").exists());
+    assert!(!config_dir.join("masking.key").exists());
+
+    unsafe { std::env::set_var(MASKING_KEY_ENV, BASE64.encode([0u8; 16])) };
+    assert!(MaskingDictionary::load().err().unwrap().to_string().starts_with("Invalid QITOPS_MASKING_KEY"));
+    unsafe { std::env::remove_var(MASKING_KEY_ENV) };
+
+    fs::remove_dir_all(home).unwrap();
+}
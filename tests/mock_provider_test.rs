@@ -0,0 +1,75 @@
+use qitops_agent::agent::{Agent, RiskAgent};
+use qitops_agent::agent::baseline::BaselineMode;
+use qitops_agent::bench::ReplayClient;
+use qitops_agent::llm::providers::{client_for, MockClient};
+use qitops_agent::llm::{LlmClient, LlmRequest, LlmRouter, ProviderConfig, RouterConfig};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn mock_config(fixtures: &std::path::Path) -> ProviderConfig {
+    ProviderConfig {
+        provider_type: "mock".to_string(),
+        api_key: None,
+        api_base: None,
+        default_model: "mock".to_string(),
+        options: HashMap::from([("fixtures".to_string(), fixtures.to_string_lossy().to_string())]),
+        requests_per_minute: None,
+        tokens_per_minute: None,
+        max_concurrent_requests: None,
+        proxy: None,
+        timeout_secs: None,
+        connect_timeout_secs: None,
+        max_retries: None,
+        headers: HashMap::new(),
+        auth: None,
+    }
+}
+
+#[tokio::test]
+async fn recorded_responses_replay_by_request() {
+    let dir = std::env::temp_dir().join(format!("qitops-mock-test-{}", std::process::id()));
+    let upstream = Arc::new(ReplayClient::new(vec!["first answer".to_string(), "second answer".to_string()]));
+    let recorder = MockClient::recording(dir.clone(), upstream);
+
+    let first = LlmRequest::new("What is 1 + 1?".to_string(), "any".to_string());
+    let second = LlmRequest::new("What is 2 + 2?".to_string(), "any".to_string());
+    recorder.send(first.clone()).await.unwrap();
+    recorder.send(second.clone()).await.unwrap();
+
+    let replayer = client_for(&mock_config(&dir)).unwrap().unwrap();
+    assert!(replayer.is_available().await);
+    assert_eq!(replayer.send(second).await.unwrap().text, "second answer");
+    assert_eq!(replayer.send(first.clone()).await.unwrap().text, "first answer");
+
+    // The model doesn't matter, the messages do
+    let other_model = LlmRequest { model: "other".to_string(), ..first };
+    assert_eq!(replayer.send(other_model).await.unwrap().text, "first answer");
+    assert!(replayer.send(LlmRequest::new("Unrecorded".to_string(), "any".to_string())).await.is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn agents_run_deterministically_on_replayed_fixtures() {
+    let dir = std::env::temp_dir().join(format!("qitops-mock-agent-test-{}", std::process::id()));
+    let answer = std::fs::read_to_string("fixtures/replays/risk-sql-injection.json").unwrap();
+    let router = |client: Arc<dyn LlmClient>| LlmRouter::with_clients(
+        RouterConfig { providers: Vec::new(), ..RouterConfig::default() },
+        vec![client],
+    );
+
+    let mut results = Vec::new();
+    for client in [
+        Arc::new(MockClient::recording(dir.clone(), Arc::new(ReplayClient::new(vec![answer])))) as Arc<dyn LlmClient>,
+        Arc::new(MockClient::replaying(dir.clone())),
+    ] {
+        let agent = RiskAgent::new_from_diff("fixtures/inputs/find_user.diff".to_string(), Vec::new(), Vec::new(), router(client)).await.unwrap()
+            .with_baseline(BaselineMode::Ignore);
+        results.push(agent.execute().await.unwrap().data.unwrap()["assessment"].clone());
+    }
+
+    assert_eq!(results[0], results[1]);
+    assert!(results[1].as_str().unwrap().contains("**Overall risk:** High"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,52 @@
+use qitops_agent::ci::fallback::{self, PrCache, PrData};
+use qitops_agent::ci::github::PullRequest;
+use qitops_agent::ci::{GitHubClient, GitHubConfig};
+use qitops_agent::error::{self, EXIT_UNAVAILABLE};
+use qitops_agent::offline;
+
+#[tokio::test]
+async fn offline_runs_use_cached_pr_data() {
+    offline::enable();
+    assert!(offline::is_local_url("http://localhost:11434"));
+    assert!(!offline::is_local_url("https://api.openai.com/v1"));
+
+    // No token is needed to read the cache
+    let client = GitHubClient::from_config(&GitHubConfig { token: None, ..GitHubConfig::default() }).unwrap();
+    let error = client.get_pull_request("octo", "app", 7).await.unwrap_err();
+    assert_eq!(error::exit_code(&error), EXIT_UNAVAILABLE);
+
+    let dir = std::env::temp_dir().join(format!("qitops-offline-{}", std::process::id()));
+    let cache = PrCache::at(dir.join("cache"));
+    cache.store("octo", "app", &PrData {
+        pull_request: PullRequest {
+            number: 7,
+            title: "Cached PR".to_string(),
+            body: None,
+            author: String::new(),
+            state: "open".to_string(),
+            base_branch: "main".to_string(),
+            head_branch: "feature".to_string(),
+            created_at: String::new(),
+            updated_at: String::new(),
+            head_sha: String::new(),
+        },
+        diff: String::new(),
+        files: Vec::new(),
+    }).unwrap();
+
+    let fetch = fallback::fetch_pr(&client, "octo", "app", 7, &cache, &dir).await.unwrap();
+    assert_eq!(fetch.data.pull_request.title, "Cached PR");
+    assert!(fetch.degraded.unwrap().contains("offline mode"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn remote_clients_refuse_to_start_requests_offline() {
+    offline::enable();
+
+    let client = qitops_agent::proxy::remote("Jira", None).unwrap();
+    let error = client.get("https://acme.atlassian.net/rest/api/2/myself").unwrap_err();
+    assert_eq!(error.to_string(), "Jira is disabled in offline mode (--offline)");
+    assert_eq!(error::exit_code(&error), EXIT_UNAVAILABLE);
+}
@@ -0,0 +1,60 @@
+use qitops_agent::llm::providers::client_for;
+use qitops_agent::llm::{LlmRequest, ProviderConfig};
+
+mod common;
+use common::Reply;
+
+/// Serve one request with a canned answer, returning the request headers (lowercased)
+async fn serve_once(reply: Reply) -> (String, tokio::task::JoinHandle<String>) {
+    let (address, server) = common::serve_once(reply).await;
+    (address, tokio::spawn(async move { server.await.unwrap().headers }))
+}
+
+fn openai(api_base: &str, options: &[(&str, &str)]) -> ProviderConfig {
+    serde_json::from_value(serde_json::json!({
+        "provider_type": "openai",
+        "api_key": "sk-proj-test",
+        "api_base": api_base,
+        "default_model": "gpt-4o-mini",
+        "options": options.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<std::collections::HashMap<_, _>>(),
+    })).unwrap()
+}
+
+fn request() -> LlmRequest {
+    LlmRequest::new("Generate tests for add()".to_string(), "gpt-4o-mini".to_string())
+}
+
+#[tokio::test]
+async fn organization_and_project_are_sent_as_headers() {
+    let (api_base, server) = serve_once(Reply::json(serde_json::json!({
+        "model": "gpt-4o-mini",
+        "choices": [{"message": {"role": "assistant", "content": "## Test Case 1"}}],
+        "usage": {"total_tokens": 42},
+    }))).await;
+
+    let config = openai(&api_base, &[("organization", "org-acme"), ("project", "proj_qa")]);
+    let client = client_for(&config).unwrap().unwrap();
+    let response = client.send(request()).await.unwrap();
+    let headers = server.await.unwrap();
+
+    assert_eq!(response.text, "## Test Case 1");
+    assert!(headers.contains("authorization: bearer sk-proj-test"));
+    assert!(headers.contains("openai-organization: org-acme"));
+    assert!(headers.contains("openai-project: proj_qa"));
+}
+
+#[tokio::test]
+async fn authentication_errors_point_at_project_scoping() {
+    let (api_base, server) = serve_once(Reply::status(401, serde_json::json!({
+        "error": {"message": "You must be a member of an organization to use the API.", "code": "no_organization"}
+    }))).await;
+
+    let client = client_for(&openai(&api_base, &[])).unwrap().unwrap();
+    let error = client.send(request()).await.unwrap_err().to_string();
+    let headers = server.await.unwrap();
+
+    assert!(!headers.contains("openai-organization"));
+    assert!(!headers.contains("openai-project"));
+    assert!(error.contains("no_organization"), "{}", error);
+    assert!(error.contains("`organization` and `project` options"), "{}", error);
+}
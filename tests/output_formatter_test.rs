@@ -0,0 +1,123 @@
+use qitops_agent::agent::traits::{AgentResponse, AgentStatus};
+use clap::Parser;
+
+use qitops_agent::cli::commands::{Cli, Command};
+use qitops_agent::output::{self, FormatterRegistry, OutputFormatter, Report};
+
+mod common;
+
+/// A risk result with one finding
+fn risk_report() -> Report {
+    let response = AgentResponse {
+        status: AgentStatus::Success,
+        message: "Risk assessment completed".to_string(),
+        data: Some(serde_json::json!({
+            "assessment": "**Overall risk:** High",
+            "findings": [{
+                "file": "src/auth.rs",
+                "line": 12,
+                "severity": "high",
+                "title": "Token compared with <==>",
+                "description": "Use a constant-time comparison",
+            }],
+        })),
+    };
+
+    Report::from_response("risk", "Risk Assessment: changes.diff", &response)
+}
+
+#[test]
+fn builtin_formatters_render_findings() {
+    let registry = FormatterRegistry::new();
+    let report = risk_report();
+    assert_eq!(report.body, "**Overall risk:** High");
+
+    let junit = registry.get("junit").unwrap().format(&report).unwrap();
+    assert!(junit.contains("failures=\"1\""));
+    assert!(junit.contains("name=\"Token compared with &lt;==&gt;\""));
+
+    let sarif: serde_json::Value = serde_json::from_str(&registry.get("sarif").unwrap().format(&report).unwrap()).unwrap();
+    let result = &sarif["runs"][0]["results"][0];
+    assert_eq!(result["level"], "error");
+    assert_eq!(result["locations"][0]["physicalLocation"]["region"]["startLine"], 12);
+
+    assert!(registry.get("html").unwrap().format(&report).unwrap().contains("<h2>Findings</h2>"));
+}
+
+/// A formatter registered by a plugin
+struct ShoutFormatter;
+
+impl OutputFormatter for ShoutFormatter {
+    fn name(&self) -> &str {
+        "shout"
+    }
+
+    fn description(&self) -> &str {
+        "Upper-case title"
+    }
+
+    fn format(&self, report: &Report) -> anyhow::Result<String> {
+        Ok(report.title.to_uppercase())
+    }
+}
+
+#[test]
+fn plugins_add_formatters_but_cannot_replace_builtins() {
+    let mut registry = FormatterRegistry::new();
+    assert!(registry.resolve("shout").is_err());
+
+    registry.register(Box::new(ShoutFormatter));
+    assert_eq!(registry.resolve("shout").unwrap().format(&risk_report()).unwrap(), "RISK ASSESSMENT: CHANGES.DIFF");
+
+    struct FakeJson;
+    impl OutputFormatter for FakeJson {
+        fn name(&self) -> &str { "json" }
+        fn description(&self) -> &str { "" }
+        fn format(&self, _: &Report) -> anyhow::Result<String> { Ok(String::new()) }
+    }
+    registry.register(Box::new(FakeJson));
+    assert!(registry.get("json").unwrap().format(&risk_report()).unwrap().starts_with('{'));
+}
+
+#[test]
+fn output_files_pick_their_format_by_extension_and_short_names_resolve() {
+    assert_eq!(output::format_for_path("reports/risk.html"), Some("html"));
+    assert_eq!(output::format_for_path("risk.JSON"), Some("json"));
+    assert_eq!(output::format_for_path("qitops-junit.xml"), Some("junit"));
+    assert_eq!(output::format_for_path("risk.md"), Some("markdown"));
+    assert_eq!(output::format_for_path("risk.adoc"), None);
+    assert_eq!(output::format_for_path("generated/"), None);
+
+    assert_eq!(output::format_name("md"), "markdown");
+    assert_eq!(output::format_name("asciidoc"), "asciidoc");
+    let registry = FormatterRegistry::new();
+    assert_eq!(registry.get("MD").unwrap().name(), "markdown");
+    assert!(registry.get("md").unwrap().format(&risk_report()).unwrap().contains("Token compared with <==>"));
+
+    // --output-format is another name for --format
+    let cli = Cli::try_parse_from(["qitops", "run", "--output-format", "md", "--output", "risk.txt", "risk", "--diff", "changes.diff"]).unwrap();
+    match cli.command {
+        Command::Run { format, output, .. } => assert_eq!((format.as_deref(), output.as_deref()), (Some("md"), Some("risk.txt"))),
+        other => panic!("Unexpected command {:?}", other),
+    }
+}
+
+#[cfg(all(unix, feature = "plugins"))]
+#[test]
+fn formatter_plugins_may_answer_before_reading_a_large_report() {
+    use std::os::unix::fs::PermissionsExt;
+
+    // The program fills its stdout pipe before reading stdin, which the report also fills
+    let dir = common::scratch("formatter-plugin");
+    let program = dir.join("banner");
+    std::fs::write(&program, "#!/bin/sh\nhead -c 200000 /dev/zero | tr '\\0' x\nwc -c\n").unwrap();
+    std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut report = risk_report();
+    report.summary = "y".repeat(200_000);
+    let formatted = output::CommandFormatter::new("banner", program).format(&report).unwrap();
+    assert!(formatted.starts_with(&"x".repeat(200_000)));
+    assert!(formatted[200_000..].trim().parse::<usize>().unwrap() > 200_000);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
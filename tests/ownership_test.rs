@@ -0,0 +1,144 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use qitops_agent::agent::ownership::{AuthorShare, Codeowners, FileOwnership, Ownership, suggest_reviewers};
+use qitops_agent::git::describe_age;
+
+mod common;
+use common::scratch;
+
+const DAY: u64 = 86_400;
+const NOW: u64 = 1_760_000_000;
+
+const CODEOWNERS: &str = "\
+# Default owners
+*                   @acme/core
+*.md                @acme/docs   # Documentation
+/src/payments/      @ann @acme/payments
+docs/*              @acme/writers
+/build              @ops
+vendor/
+";
+
+/// Commit everything as `author`, `days` before `NOW`
+fn commit(dir: &Path, author: &str, days: u64) {
+    let date = format!("@{} +0000", NOW - days * DAY);
+    for args in [vec!["add", "-A"], vec!["commit", "-q", "-m", "Change"]] {
+        let status = Command::new("git")
+            .args(["-c", &format!("user.name={}", author), "-c", "user.email=dev@example.com", "-c", "commit.gpgsign=false"])
+            .args(&args)
+            .env("GIT_AUTHOR_DATE", &date)
+            .env("GIT_COMMITTER_DATE", &date)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+}
+
+#[test]
+fn codeowners_follow_gitignore_patterns_and_the_last_match_wins() {
+    let codeowners = Codeowners::parse(CODEOWNERS);
+
+    assert_eq!(codeowners.owners("src/main.rs"), ["@acme/core"]);
+    assert_eq!(codeowners.owners("README.md"), ["@acme/docs"]);
+    assert_eq!(codeowners.owners("./src/guide/intro.md"), ["@acme/docs"]);
+    // Anchored directories match everything under them
+    assert_eq!(codeowners.owners("src/payments/stripe/charge.rs"), ["@ann", "@acme/payments"]);
+    assert_eq!(codeowners.owners("lib/src/payments/charge.rs"), ["@acme/core"]);
+    // `*` stays within a directory
+    assert_eq!(codeowners.owners("docs/setup.txt"), ["@acme/writers"]);
+    assert_eq!(codeowners.owners("docs/api/setup.txt"), ["@acme/core"]);
+    // A pattern without a trailing slash matches a file or a directory
+    assert_eq!(codeowners.owners("build/release.sh"), ["@ops"]);
+    // A rule without owners unassigns
+    assert!(codeowners.owners("vendor/lib.js").is_empty());
+}
+
+#[test]
+fn reviewers_are_owners_then_main_authors_without_the_change_author() {
+    let file = |path: &str, owners: &[&str], author: Option<&str>| FileOwnership {
+        path: path.to_string(),
+        owners: owners.iter().map(|owner| owner.to_string()).collect(),
+        authors: author.map(|name| AuthorShare { name: name.to_string(), lines: 10, percent: 100 }).into_iter().collect(),
+        ..FileOwnership::default()
+    };
+    let files = [
+        file("src/cli.rs", &[], Some("Bob")),
+        file("src/api.rs", &[], Some("Bob")),
+        file("src/db.rs", &[], Some("Cat")),
+        file("src/payments/charge.rs", &["@ann", "@acme/payments"], Some("Dan")),
+        file("src/payments/refund.rs", &["@acme/payments"], None),
+    ];
+
+    let reviewers = suggest_reviewers(&files, Some("ann"));
+    let names: Vec<&str> = reviewers.iter().map(|reviewer| reviewer.name.as_str()).collect();
+    assert_eq!(names, ["@acme/payments", "Bob", "Cat"]);
+    assert_eq!(reviewers[0].files, ["src/payments/charge.rs", "src/payments/refund.rs"]);
+    assert!(reviewers[0].owner && !reviewers[1].owner);
+}
+
+#[test]
+fn ages_are_described_in_the_largest_whole_unit() {
+    let ages: Vec<String> = [0, 59, 60, 7_200, DAY, 3 * DAY, 59 * DAY, 400 * DAY, 800 * DAY].iter()
+        .map(|seconds| describe_age(*seconds))
+        .collect();
+    assert_eq!(ages, [
+        "just now", "just now", "1 minute ago", "2 hours ago", "1 day ago", "3 days ago", "59 days ago", "13 months ago", "2 years ago",
+    ]);
+}
+
+#[test]
+fn ownership_is_collected_from_codeowners_blame_and_history() {
+    let dir = scratch("collect");
+    assert!(Command::new("git").args(["init", "-q"]).current_dir(&dir).status().unwrap().success());
+    fs::create_dir_all(dir.join(".github")).unwrap();
+    fs::write(dir.join(".github/CODEOWNERS"), "/src/pay.rs @acme/payments\n").unwrap();
+    fs::create_dir_all(dir.join("src")).unwrap();
+
+    // Ann wrote most of pay.rs long ago; Bob changed it often lately
+    fs::write(dir.join("src/pay.rs"), "a\nb\nc\nd\n").unwrap();
+    fs::write(dir.join("src/old.rs"), "old\n").unwrap();
+    commit(&dir, "Ann", 400);
+    for days in (1..=5).rev() {
+        fs::write(dir.join("src/pay.rs"), format!("a\nb\nc\n{}\n", days)).unwrap();
+        commit(&dir, "Bob", days);
+    }
+
+    let files = ["src/pay.rs", "src/old.rs", "src/new.rs"].map(str::to_string);
+    let ownership = Ownership::collect(&dir, &files, Some("bob"), NOW).unwrap();
+
+    let pay = &ownership.files[0];
+    assert_eq!(pay.owners, ["@acme/payments"]);
+    assert_eq!(pay.authors, [
+        AuthorShare { name: "Ann".to_string(), lines: 3, percent: 75 },
+        AuthorShare { name: "Bob".to_string(), lines: 1, percent: 25 },
+    ]);
+    assert_eq!((pay.last_changed, pay.last_author.as_deref(), pay.recent_commits), (Some(NOW - DAY), Some("Bob"), 5));
+    assert!(pay.is_hotspot());
+    assert_eq!(
+        pay.describe(NOW),
+        "src/pay.rs: owners @acme/payments; mostly written by Ann (75%), Bob (25%); last changed 1 day ago by Bob; 5 commit(s) in 90 days (hotspot)",
+    );
+
+    let old = &ownership.files[1];
+    assert_eq!((old.recent_commits, old.is_hotspot()), (0, false));
+    assert_eq!(old.describe(NOW), "src/old.rs: mostly written by Ann (100%); last changed 13 months ago by Ann");
+    assert_eq!(ownership.files[2].describe(NOW), "src/new.rs: new or uncommitted");
+
+    // The author isn't suggested, and the owner comes before other authors
+    let names: Vec<&str> = ownership.reviewers.iter().map(|reviewer| reviewer.name.as_str()).collect();
+    assert_eq!(names, ["@acme/payments", "Ann"]);
+    assert_eq!(ownership.hotspots().count(), 1);
+    assert!(ownership.prompt(NOW).contains("- src/pay.rs: owners @acme/payments"));
+    assert!(ownership.to_markdown(NOW).contains("**Suggested reviewers:** @acme/payments (src/pay.rs); Ann (src/old.rs)\n\n**Churn hotspots:** src/pay.rs (5 commits in 90 days)"));
+
+    // Outside a repository there is nothing to collect
+    let plain = scratch("plain");
+    assert!(Ownership::collect(&plain, &files, None, NOW).is_none());
+
+    fs::remove_dir_all(&dir).unwrap();
+    fs::remove_dir_all(&plain).unwrap();
+}
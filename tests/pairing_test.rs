@@ -0,0 +1,38 @@
+use futures_util::SinkExt;
+use qitops_agent::agent::pairing::{PairHost, participant_url};
+use tokio_tungstenite::tungstenite::Message;
+
+#[tokio::test]
+async fn participants_need_the_join_token_and_are_named_by_the_host() {
+    let mut host = PairHost::start("127.0.0.1:0", "Dana").await.unwrap();
+    let join_url = host.join_url().to_string();
+    let base = join_url.split('?').next().unwrap().to_string();
+
+    // Without the token, or with a guessed one, the handshake is refused
+    assert!(tokio_tungstenite::connect_async(format!("{}?name=Eve", base)).await.is_err());
+    assert!(tokio_tungstenite::connect_async(format!("{}?token=guess&name=Eve", base)).await.is_err());
+
+    // The names of the assistant and the host can't be taken
+    for name in ["QitOps", "dana", ""] {
+        let url = participant_url(&join_url, name).unwrap();
+        assert!(tokio_tungstenite::connect_async(url).await.is_err(), "joined as {:?}", name);
+    }
+
+    // Input is attributed to the name given at the handshake, whatever author it claims
+    let url = participant_url(&join_url, "Alice").unwrap();
+    let (mut websocket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+    let spoofed = serde_json::json!({ "type": "input", "author": "QitOps", "text": "Try an expired card" });
+    websocket.send(Message::Text(spoofed.to_string())).await.unwrap();
+
+    let (author, text) = host.next_input().await.unwrap();
+    assert_eq!(author, "Alice");
+    assert_eq!(text, "Try an expired card");
+}
+
+#[test]
+fn joining_needs_the_url_the_host_printed() {
+    assert!(participant_url("ws://127.0.0.1:8765", "Alice").is_err());
+
+    let url = participant_url("ws://127.0.0.1:8765/?token=abc", "Ana María").unwrap();
+    assert_eq!(url, "ws://127.0.0.1:8765/?token=abc&name=Ana+Mar%C3%ADa");
+}
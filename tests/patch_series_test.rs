@@ -0,0 +1,66 @@
+use qitops_agent::agent::baseline::BaselineMode;
+use qitops_agent::agent::patch;
+use qitops_agent::agent::{Agent, PrAnalyzeAgent, RiskAgent};
+use qitops_agent::bench::ReplayClient;
+use qitops_agent::llm::{LlmRouter, RouterConfig};
+use std::sync::Arc;
+
+const SERIES: &str = "fixtures/inputs/divide-series.mbox";
+
+fn replaying(answers: &[&str]) -> LlmRouter {
+    LlmRouter::with_clients(
+        RouterConfig { providers: Vec::new(), ..RouterConfig::default() },
+        vec![Arc::new(ReplayClient::new(answers.iter().map(|a| a.to_string()).collect()))],
+    )
+}
+
+#[test]
+fn format_patch_output_splits_into_patches() {
+    let text = std::fs::read_to_string(SERIES).unwrap();
+    assert!(patch::parse_series("diff --git a/x b/x\n").is_none());
+
+    let series = patch::parse_series(&text).unwrap();
+    assert_eq!(series.subject, "Guard divide against zero");
+    assert!(series.cover_letter.as_deref().unwrap().starts_with("Make divide reject a zero divisor"));
+    assert_eq!(series.patches.len(), 2);
+
+    let first = &series.patches[0];
+    assert_eq!((first.number, first.subject.as_str()), (1, "calc: reject division by zero"));
+    assert_eq!(first.author, "Ada Lovelace <a@b.c>");
+    assert_eq!(first.message, "Dividing by zero used to raise ZeroDivisionError.");
+    assert_eq!(first.files(), vec!["calc.py"]);
+    assert!(!first.diff.contains("2.39"), "signature leaked into the diff");
+    assert_eq!(series.patches[1].files(), vec!["main.py"]);
+}
+
+#[tokio::test]
+async fn series_get_per_patch_and_series_analysis() {
+    let patch_answer = |risk: &str, title: &str| format!(
+        r#"{{"overall_risk": "{risk}", "component_risks": [], "summary": "{title}", "recommendations": [],
+            "findings": [{{"file": "calc.py", "line": 2, "severity": "low", "title": "{title}", "description": "d"}}]}}"#
+    );
+    let answers = [
+        patch_answer("Medium", "Changes an exception type"),
+        patch_answer("Low", "Changes an exception type"),
+        patch_answer("Medium", "Series looks fine"),
+    ];
+    let router = replaying(&answers.iter().map(String::as_str).collect::<Vec<_>>());
+    let agent = RiskAgent::new_from_diff(SERIES.to_string(), Vec::new(), Vec::new(), router).await.unwrap()
+        .with_baseline(BaselineMode::Ignore);
+    let data = agent.execute().await.unwrap().data.unwrap();
+
+    let patches = data["patches"].as_array().unwrap();
+    assert_eq!(patches.len(), 2);
+    assert_eq!(patches[1]["subject"], "main: print a quotient");
+    assert!(data["assessment"].as_str().unwrap().contains("### Patch 1/2: calc: reject division by zero"));
+    // The same finding from the series and a patch is only reported once
+    assert_eq!(data["findings"].as_array().unwrap().len(), 2);
+
+    let router = replaying(&["Patch one looks fine.", "Patch two looks fine.", "The series is coherent."]);
+    let agent = PrAnalyzeAgent::new_from_patches(SERIES.to_string(), None, router).await.unwrap()
+        .with_baseline(BaselineMode::Ignore);
+    let data = agent.execute().await.unwrap().data.unwrap();
+    let analysis = data["analysis"].as_str().unwrap();
+    assert!(analysis.starts_with("The series is coherent."));
+    assert!(analysis.contains("## Patch 2/2: main: print a quotient"));
+}
@@ -0,0 +1,119 @@
+
+use qitops_agent::audit::{AUDIT_LOG_ENV, AuditAction, AuditLog};
+use qitops_agent::ci::github::comment_marker;
+use qitops_agent::ci::{GitHubClient, GitHubConfig};
+
+mod common;
+use common::Reply;
+
+/// Serve one answer per connection, in order, returning each request's method, path and body
+async fn serve(answers: Vec<serde_json::Value>) -> (String, tokio::task::JoinHandle<Vec<(String, String, String)>>) {
+    let (address, server) = common::serve(answers.into_iter().map(Reply::json).collect()).await;
+    (address, tokio::spawn(async move {
+        server.await.unwrap().into_iter()
+            .map(|request| {
+                let body = request.json()["body"].as_str().unwrap_or_default().to_string();
+                (request.method, request.path, body)
+            })
+            .collect()
+    }))
+}
+
+fn client(api_base: String) -> GitHubClient {
+    GitHubClient::from_config(&GitHubConfig {
+        token: Some("test-token".to_string()),
+        api_base: Some(api_base),
+        default_owner: None,
+        default_repo: None,
+        proxy: None,
+    }).unwrap()
+}
+
+#[tokio::test]
+async fn later_runs_edit_the_comment_instead_of_posting_another() {
+    let audit_log = std::env::temp_dir().join(format!("qitops-pr-comment-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&audit_log);
+    unsafe { std::env::set_var(AUDIT_LOG_ENV, &audit_log) };
+
+    let marker = comment_marker("pr-analyze");
+    let posted = serde_json::json!({"id": 7, "body": format!("{}\nFirst analysis", marker), "user": {"login": "qitops-bot"}});
+    let (base, server) = serve(vec![
+        // First run: only someone else's comment, so a new one is posted
+        serde_json::json!({"login": "qitops-bot"}),
+        serde_json::json!([{"id": 3, "body": "Looks good to me", "user": {"login": "ada"}}]),
+        posted.clone(),
+        // Second run: the earlier comment is found by its marker and edited
+        serde_json::json!({"login": "qitops-bot"}),
+        serde_json::json!([{"id": 3, "body": "Looks good to me", "user": {"login": "ada"}}, posted]),
+        serde_json::json!({"id": 7, "body": format!("{}\nSecond analysis", marker), "user": {"login": "qitops-bot"}}),
+    ]).await;
+    let github = client(base);
+
+    let (comment, updated) = github.upsert_pull_request_comment("acme", "shop", 12, "pr-analyze", "First analysis").await.unwrap();
+    assert_eq!((comment.id, updated), (7, false));
+    let (comment, updated) = github.upsert_pull_request_comment("acme", "shop", 12, "pr-analyze", "Second analysis").await.unwrap();
+    assert_eq!((comment.id, updated), (7, true));
+
+    let requests = server.await.unwrap();
+    let calls: Vec<(&str, &str)> = requests.iter().map(|(method, path, _)| (method.as_str(), path.as_str())).collect();
+    assert_eq!(calls, [
+        ("GET", "/user"),
+        ("GET", "/repos/acme/shop/issues/12/comments?per_page=100&page=1"),
+        ("POST", "/repos/acme/shop/issues/12/comments"),
+        ("GET", "/user"),
+        ("GET", "/repos/acme/shop/issues/12/comments?per_page=100&page=1"),
+        ("PATCH", "/repos/acme/shop/issues/comments/7"),
+    ]);
+    assert!(requests[2].2.contains(&format!("{}\nFirst analysis", marker)), "{}", requests[2].2);
+    assert!(requests[5].2.contains(&format!("{}\nSecond analysis", marker)), "{}", requests[5].2);
+
+    let events = AuditLog::open(&audit_log).read().unwrap();
+    let actions: Vec<(AuditAction, &str, Option<u64>)> = events.iter()
+        .filter(|event| event.target == "acme/shop#12")
+        .map(|event| (event.action, event.target.as_str(), event.resource_id))
+        .collect();
+    assert_eq!(actions, [
+        (AuditAction::CommentPosted, "acme/shop#12", Some(7)),
+        (AuditAction::CommentUpdated, "acme/shop#12", Some(7)),
+    ]);
+    assert_eq!("edit".parse::<AuditAction>().unwrap(), AuditAction::CommentUpdated);
+
+    std::fs::remove_file(&audit_log).unwrap();
+}
+
+#[tokio::test]
+async fn comments_are_searched_across_pages() {
+    // A full first page without the marker, then the marked comment on the second
+    let page: Vec<serde_json::Value> = (1..=100).map(|id| serde_json::json!({"id": id, "body": "+1"})).collect();
+    let marked = serde_json::json!([{"id": 501, "body": format!("Header\n\n{}\nOld analysis", comment_marker("pr-analyze")), "user": {"login": "qitops-bot"}}]);
+    let (base, server) = serve(vec![
+        serde_json::json!({"login": "qitops-bot"}),
+        serde_json::Value::Array(page),
+        marked,
+        serde_json::json!({"id": 501, "body": "New analysis"}),
+    ]).await;
+
+    let (comment, updated) = client(base).upsert_pull_request_comment("acme", "shop", 4, "pr-analyze", "New analysis").await.unwrap();
+    assert_eq!((comment.id, updated), (501, true));
+
+    let requests = server.await.unwrap();
+    assert_eq!(requests[2].1, "/repos/acme/shop/issues/4/comments?per_page=100&page=2");
+    assert_eq!((requests[3].0.as_str(), requests[3].1.as_str()), ("PATCH", "/repos/acme/shop/issues/comments/501"));
+}
+
+#[tokio::test]
+async fn marked_comments_by_other_accounts_are_left_alone() {
+    // Someone pasted the marker into their own comment
+    let pasted = serde_json::json!([{"id": 8, "body": format!("{}\nLooks fine", comment_marker("pr-analyze")), "user": {"login": "mallory"}}]);
+    let (base, server) = serve(vec![
+        serde_json::json!({"login": "qitops-bot"}),
+        pasted,
+        serde_json::json!({"id": 9, "body": "New analysis", "user": {"login": "qitops-bot"}}),
+    ]).await;
+
+    let (comment, updated) = client(base).upsert_pull_request_comment("acme", "shop", 5, "pr-analyze", "New analysis").await.unwrap();
+    assert_eq!((comment.id, updated), (9, false));
+
+    let requests = server.await.unwrap();
+    assert_eq!((requests[2].0.as_str(), requests[2].1.as_str()), ("POST", "/repos/acme/shop/issues/5/comments"));
+}
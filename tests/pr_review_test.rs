@@ -0,0 +1,112 @@
+use qitops_agent::agent::diff::patch_position;
+use qitops_agent::agent::findings::Finding;
+use qitops_agent::agent::pr_analyze::review_comments;
+use qitops_agent::audit::{AUDIT_LOG_ENV, AuditAction, AuditLog};
+use qitops_agent::ci::github::{PullRequestFile, ReviewComment};
+use qitops_agent::ci::{GitHubClient, GitHubConfig};
+use qitops_agent::severity::Severity;
+
+mod common;
+use common::Reply;
+
+const PATCH: &str = "@@ -10,4 +10,5 @@ fn checkout() {\n     let cart = load();\n-    let total = cart.sum();\n+    let total = cart.sum_with_tax();\n+    apply_discount(&code);\n     charge(total)\n@@ -40,2 +41,3 @@ fn refund() {\n     let order = find();\n+    order.refund_all();\n }";
+
+fn finding(file: Option<&str>, line: Option<u32>, title: &str) -> Finding {
+    Finding {
+        file: file.map(str::to_string),
+        line,
+        severity: Severity::High,
+        title: title.to_string(),
+        description: "Details".to_string(),
+    }
+}
+
+fn file(filename: &str, patch: Option<&str>) -> PullRequestFile {
+    PullRequestFile {
+        filename: filename.to_string(),
+        status: "modified".to_string(),
+        additions: 3,
+        deletions: 1,
+        changes: 4,
+        contents_url: String::new(),
+        patch: patch.map(str::to_string),
+    }
+}
+
+#[test]
+fn findings_are_anchored_at_their_position_in_the_patch() {
+    // Positions count from the first hunk header, through removed lines and later headers
+    assert_eq!(patch_position(PATCH, 10), Some(1));
+    assert_eq!(patch_position(PATCH, 11), Some(3));
+    assert_eq!(patch_position(PATCH, 12), Some(4));
+    assert_eq!(patch_position(PATCH, 13), Some(5));
+    assert_eq!(patch_position(PATCH, 42), Some(8));
+    // Outside the hunks
+    assert_eq!(patch_position(PATCH, 20), None);
+    assert_eq!(patch_position(PATCH, 44), None);
+
+    let files = vec![file("src/checkout.rs", Some(PATCH)), file("assets/logo.png", None)];
+    let findings = vec![
+        finding(Some("src/checkout.rs"), Some(12), "Unvalidated discount code"),
+        finding(Some("b/src/checkout.rs"), Some(42), "Refund not idempotent"),
+        finding(Some("src/checkout.rs"), Some(30), "Rounding"),
+        finding(Some("assets/logo.png"), Some(1), "Binary"),
+        finding(None, None, "Missing tests"),
+    ];
+    let (comments, unanchored) = review_comments(&files, &findings);
+
+    assert_eq!(comments, [
+        ReviewComment { path: "src/checkout.rs".to_string(), position: 4, body: "**[high]** Unvalidated discount code\n\nDetails".to_string() },
+        ReviewComment { path: "src/checkout.rs".to_string(), position: 8, body: "**[high]** Refund not idempotent\n\nDetails".to_string() },
+    ]);
+    let titles: Vec<&str> = unanchored.iter().map(|finding| finding.title.as_str()).collect();
+    assert_eq!(titles, ["Rounding", "Binary", "Missing tests"]);
+}
+
+#[tokio::test]
+async fn reviews_are_posted_on_the_head_commit_and_audited() {
+    let audit_log = std::env::temp_dir().join(format!("qitops-pr-review-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&audit_log);
+    unsafe { std::env::set_var(AUDIT_LOG_ENV, &audit_log) };
+
+    let (address, server) = common::serve(vec![
+        Reply::json(serde_json::json!({"id": 90, "state": "COMMENTED"})),
+        Reply::json(serde_json::json!([{"id": 901, "body": "Unvalidated discount code", "user": {"login": "qitops-bot"}}])),
+        Reply::status(204, serde_json::Value::Null),
+    ]).await;
+
+    let client = GitHubClient::from_config(&GitHubConfig {
+        token: Some("test-token".to_string()),
+        api_base: Some(address),
+        default_owner: None,
+        default_repo: None,
+        proxy: None,
+    }).unwrap();
+    let comments = vec![ReviewComment { path: "src/checkout.rs".to_string(), position: 4, body: "Unvalidated discount code".to_string() }];
+    let id = client.create_review("acme", "shop", 12, Some("def456"), "QitOps found 1 issue(s)", &comments).await.unwrap();
+    assert_eq!(id, 90);
+
+    // Undoing the run deletes the review's inline comments
+    let events = AuditLog::open(&audit_log).read().unwrap();
+    let posted: Vec<(AuditAction, &str, Option<u64>)> = events.iter().map(|event| (event.action, event.target.as_str(), event.resource_id)).collect();
+    assert_eq!(posted, [
+        (AuditAction::ReviewPosted, "acme/shop#12", Some(90)),
+        (AuditAction::ReviewCommentPosted, "acme/shop#12", Some(901)),
+    ]);
+    assert!(client.undo(&events[1]).await.unwrap());
+    assert!(client.undo(&events[0]).await.is_err());
+
+    let requests = server.await.unwrap();
+    let (path, payload) = (requests[0].path.clone(), requests[0].json());
+    assert_eq!(path, "/repos/acme/shop/pulls/12/reviews");
+    assert_eq!((payload["event"].as_str(), payload["commit_id"].as_str()), (Some("COMMENT"), Some("def456")));
+    assert!(payload["body"].as_str().unwrap().contains("QitOps found 1 issue(s)"));
+    assert_eq!(payload["comments"], serde_json::json!([{"path": "src/checkout.rs", "position": 4, "body": "Unvalidated discount code"}]));
+    assert_eq!(requests[1].path, "/repos/acme/shop/pulls/12/reviews/90/comments?per_page=100&page=1");
+    assert_eq!((requests[2].method.as_str(), requests[2].path.as_str()), ("DELETE", "/repos/acme/shop/pulls/comments/901"));
+
+    let events = AuditLog::open(&audit_log).read().unwrap();
+    assert_eq!((events[2].action, events[2].resource_id), (AuditAction::ReviewCommentDeleted, Some(901)));
+
+    std::fs::remove_file(&audit_log).unwrap();
+}
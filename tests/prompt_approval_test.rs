@@ -0,0 +1,139 @@
+use std::path::PathBuf;
+
+use qitops_agent::audit::{AUDIT_LOG_ENV, AuditAction, AuditLog};
+use qitops_agent::llm::approval::{ApprovalConfig, ApprovalGate, ApprovalQueue, ApprovalStatus, SensitivityPolicy, SensitivityRule};
+use qitops_agent::llm::client::LlmError;
+use qitops_agent::llm::providers::client_for;
+use qitops_agent::llm::{LlmRequest, LlmRouter, ProviderConfig, RouterConfig};
+use qitops_agent::severity::Severity;
+
+mod common;
+use common::{Received, Reply, scratch};
+
+/// Serve `count` chat completions as an HTTP proxy would, returning the prompts that were sent
+async fn serve(count: usize) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+    let (address, server) = common::serve(vec![Reply::completion("Test cases"); count]).await;
+    (address, tokio::spawn(async move { server.await.unwrap().iter().map(Received::prompt).collect() }))
+}
+
+fn approval(queue: PathBuf, timeout_secs: u64) -> ApprovalConfig {
+    serde_json::from_value(serde_json::json!({
+        "threshold": "high",
+        "rules": [
+            {"path": "**/billing/**", "sensitivity": "critical"},
+            {"pattern": "(?i)\\bssn\\b", "sensitivity": "low"},
+        ],
+        "timeout_secs": timeout_secs,
+        "queue": queue,
+    })).unwrap()
+}
+
+/// Wait until a prompt is queued
+async fn pending(queue: &ApprovalQueue) -> String {
+    for _ in 0..100 {
+        if let Some(request) = queue.list(false).unwrap().pop() {
+            return request.id;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    panic!("No prompt was queued");
+}
+
+#[tokio::test]
+async fn sensitive_prompts_to_remote_providers_wait_for_a_decision() {
+    let audit_log = scratch("approval-audit").join("audit.log");
+    unsafe { std::env::set_var(AUDIT_LOG_ENV, &audit_log) };
+    let queue_path = scratch("approval-queue").join("queue.db");
+
+    // A provider off this machine, reached through the fake server as its proxy
+    let (proxy, server) = serve(2).await;
+    let config: ProviderConfig = serde_json::from_value(serde_json::json!({
+        "provider_type": "openai",
+        "api_key": "sk-test",
+        "api_base": "http://llm.example.com/v1",
+        "default_model": "gpt-4o-mini",
+        "proxy": proxy,
+    })).unwrap();
+    let router = LlmRouter::with_clients(
+        RouterConfig { providers: vec![config.clone()], approval: Some(approval(queue_path.clone(), 30)), ..RouterConfig::default() },
+        vec![client_for(&config).unwrap().unwrap()],
+    );
+    let queue = ApprovalQueue::at(&queue_path).unwrap();
+
+    // Held until approved
+    let held = tokio::spawn({
+        let router = router.clone();
+        async move { router.send(LlmRequest::new("Generate tests for src/billing/invoice.rs".to_string(), "gpt-4o-mini".to_string()), Some("test-gen")).await }
+    });
+    let id = pending(&queue).await;
+    let request = queue.get(&id).unwrap().unwrap();
+    assert_eq!((request.task.as_deref(), request.provider.as_str(), request.sensitivity.level), (Some("test-gen"), "openai", Severity::Critical));
+    assert_eq!(request.sensitivity.reasons, ["mentions `src/billing/invoice.rs` (`**/billing/**`)"]);
+    assert!(request.prompt.contains("src/billing/invoice.rs"));
+    assert!(!held.is_finished());
+    // Whoever sent the prompt can't approve it
+    let own = queue.decide(&id, true, &request.requested_by, None).unwrap_err();
+    assert!(own.to_string().contains("who can't decide on it"), "{}", own);
+    queue.decide(&id, true, "grace", Some("fixtures only")).unwrap();
+    assert_eq!(held.await.unwrap().unwrap().text, "Test cases");
+
+    // Below the threshold, nothing is held
+    router.send(LlmRequest::new("Mask the ssn column".to_string(), "gpt-4o-mini".to_string()), None).await.unwrap();
+
+    // A rejected prompt is never sent
+    let held = tokio::spawn({
+        let router = router.clone();
+        async move { router.send(LlmRequest::new("diff --git a/billing/tax.py b/billing/tax.py".to_string(), "gpt-4o-mini".to_string()), Some("risk")).await }
+    });
+    let id = pending(&queue).await;
+    queue.decide(&id, false, "grace", Some("contains customer data")).unwrap();
+    let error = held.await.unwrap().unwrap_err();
+    assert!(matches!(error.downcast_ref::<LlmError>(), Some(LlmError::NotApproved(message)) if message.ends_with("rejected by grace: contains customer data")), "{}", error);
+
+    let prompts = server.await.unwrap();
+    assert_eq!(prompts, ["Generate tests for src/billing/invoice.rs", "Mask the ssn column"]);
+
+    // Decisions are kept in the queue and the audit log
+    let decided: Vec<ApprovalStatus> = queue.list(true).unwrap().iter().map(|request| request.status).collect();
+    assert_eq!(decided, [ApprovalStatus::Approved, ApprovalStatus::Rejected]);
+    assert!(queue.list(false).unwrap().is_empty());
+    let events = AuditLog::open(&audit_log).read().unwrap();
+    let actions: Vec<(AuditAction, &str)> = events.iter().map(|event| (event.action, event.actor.as_str())).collect();
+    assert_eq!(actions, [(AuditAction::PromptApproved, "grace"), (AuditAction::PromptRejected, "grace")]);
+    assert_eq!(events[1].details.as_deref(), Some("contains customer data"));
+
+    std::fs::remove_file(&queue_path).unwrap();
+    std::fs::remove_file(&audit_log).unwrap();
+}
+
+#[tokio::test]
+async fn prompts_are_rated_by_their_most_sensitive_rule_and_expire_undecided() {
+    let policy = SensitivityPolicy::new(&approval(PathBuf::new(), 0).rules).unwrap();
+    // Diff paths match without their a/ or b/ prefix
+    let sensitivity = policy.assess("--- a/src/billing/tax.py\n+++ b/src/billing/tax.py\n+ssn = row[3]").unwrap();
+    assert_eq!(sensitivity.level, Severity::Critical);
+    assert_eq!(sensitivity.reasons, ["mentions `src/billing/tax.py` (`**/billing/**`)", "matches `(?i)\\bssn\\b`"]);
+    assert_eq!(policy.assess("Print the SSN").unwrap().level, Severity::Low);
+    assert_eq!(policy.assess("Generate tests for src/cart.rs"), None);
+
+    let invalid = SensitivityRule { pattern: Some("(".to_string()), path: None, sensitivity: Severity::High };
+    assert!(SensitivityPolicy::new(&[invalid]).is_err());
+    let empty = SensitivityRule { pattern: None, path: None, sensitivity: Severity::High };
+    assert!(SensitivityPolicy::new(&[empty]).is_err());
+
+    // Nobody decides in time
+    let queue_path = scratch("approval-expiry").join("expiry.db");
+    let gate = ApprovalGate::new(approval(queue_path.clone(), 0)).unwrap();
+    let request = LlmRequest::new("Review billing/refunds.rs".to_string(), "gpt-4o-mini".to_string());
+    let error = gate.wait(&request, "anthropic", None).await.unwrap_err();
+    assert!(error.to_string().contains("was not decided within 0s"), "{}", error);
+
+    let queue = ApprovalQueue::at(&queue_path).unwrap();
+    let expired = queue.list(true).unwrap().pop().unwrap();
+    assert_eq!((expired.status, expired.decided_by.as_deref()), (ApprovalStatus::Expired, Some("qitops")));
+    let late = queue.decide(&expired.id, true, "grace", None).unwrap_err();
+    assert!(late.to_string().contains("already expired"), "{}", late);
+    assert!(queue.decide("missing", true, "grace", None).is_err());
+
+    std::fs::remove_file(&queue_path).unwrap();
+}
@@ -0,0 +1,62 @@
+use qitops_agent::llm::providers::{client_for, CACHED_PROMPT_TOKENS};
+use qitops_agent::llm::{LlmRequest, ProviderConfig};
+
+mod common;
+
+/// Serve one request with a canned JSON answer, returning the request body
+async fn serve_once(answer: serde_json::Value) -> (String, tokio::task::JoinHandle<serde_json::Value>) {
+    let (address, server) = common::serve_once(answer).await;
+    (address, tokio::spawn(async move { server.await.unwrap().json() }))
+}
+
+fn anthropic(api_base: &str, options: &[(&str, &str)]) -> ProviderConfig {
+    serde_json::from_value(serde_json::json!({
+        "provider_type": "anthropic",
+        "api_key": "test-key",
+        "api_base": api_base,
+        "default_model": "claude-3-5-sonnet-latest",
+        "options": options.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<std::collections::HashMap<_, _>>(),
+    })).unwrap()
+}
+
+fn request() -> LlmRequest {
+    LlmRequest::new("Generate tests for add()".to_string(), "claude-3-5-sonnet-latest".to_string())
+        .with_system_message("You are a test engineer.".to_string())
+        .with_source_context("The API spec says add() saturates on overflow.")
+}
+
+#[tokio::test]
+async fn anthropic_system_prompt_and_sources_are_marked_for_caching() {
+    let (api_base, server) = serve_once(serde_json::json!({
+        "model": "claude-3-5-sonnet-latest",
+        "content": [{"type": "text", "text": "## Test Case 1"}],
+        "usage": {"input_tokens": 12, "output_tokens": 30, "cache_read_input_tokens": 1500, "cache_creation_input_tokens": 0},
+    })).await;
+
+    let client = client_for(&anthropic(&api_base, &[])).unwrap().unwrap();
+    let response = client.send(request()).await.unwrap();
+    let body = server.await.unwrap();
+
+    let system = &body["system"][0];
+    assert!(system["text"].as_str().unwrap().ends_with("The API spec says add() saturates on overflow."));
+    assert_eq!(system["cache_control"]["type"], "ephemeral");
+    assert_eq!(body["messages"][0]["content"], "Generate tests for add()");
+
+    assert_eq!(response.tokens_used, Some(1542));
+    assert_eq!(response.metadata[CACHED_PROMPT_TOKENS], 1500);
+}
+
+#[tokio::test]
+async fn anthropic_prompt_caching_can_be_turned_off() {
+    let (api_base, server) = serve_once(serde_json::json!({
+        "content": [{"type": "text", "text": "ok"}],
+        "usage": {"input_tokens": 12, "output_tokens": 1},
+    })).await;
+
+    let client = client_for(&anthropic(&api_base, &[("prompt_caching", "false")])).unwrap().unwrap();
+    let response = client.send(request()).await.unwrap();
+    let body = server.await.unwrap();
+
+    assert!(body["system"].is_string());
+    assert!(!response.metadata.contains_key(CACHED_PROMPT_TOKENS));
+}
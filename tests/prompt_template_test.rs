@@ -0,0 +1,49 @@
+use qitops_agent::llm::{LlmRequest, SOURCES_MARKER};
+use qitops_agent::prompt::templates::{BUILTIN_TEMPLATES, validate_template};
+use qitops_agent::prompt::render_prompt;
+
+#[test]
+fn builtin_templates_are_valid() {
+    for template in BUILTIN_TEMPLATES {
+        validate_template(template.name, template.template).unwrap();
+    }
+}
+
+#[test]
+fn test_gen_prompt_puts_personas_first() {
+    let prompt = render_prompt("test-gen", &serde_json::json!({
+        "code": "fn add(a: i32, b: i32) -> i32 { a + b }",
+        "function": "add",
+        "personas": "You are a security analyst.",
+    })).unwrap();
+
+    assert!(prompt.starts_with("You are a security analyst.\n\nGenerate comprehensive test cases"));
+    assert!(prompt.contains("Only generate test cases for the function `add`"));
+}
+
+#[test]
+fn sources_follow_the_system_prompt() {
+    let request = LlmRequest::new("Generate tests".to_string(), "model".to_string())
+        .with_system_message("You are a test engineer.".to_string())
+        .with_source_context("Use property-based tests.");
+
+    assert_eq!(request.messages[0].content, format!("You are a test engineer.{}Use property-based tests.", SOURCES_MARKER));
+    assert_eq!(request.messages[1].content, "Generate tests");
+
+    let unchanged = LlmRequest::new("Generate tests".to_string(), "model".to_string()).with_source_context("  ");
+    assert_eq!(unchanged.messages.len(), 1);
+}
+
+#[test]
+fn risk_prompt_joins_lists() {
+    let prompt = render_prompt("risk", &serde_json::json!({
+        "diff": "+ <b>changed</b>",
+        "components": ["auth", "billing"],
+        "focus_areas": [],
+        "findings_instructions": "",
+    })).unwrap();
+
+    assert!(prompt.contains("Focus on the following components: auth, billing and general risk factors."));
+    // Prompts are plain text, so nothing is HTML-escaped
+    assert!(prompt.contains("+ <b>changed</b>"));
+}
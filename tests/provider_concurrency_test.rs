@@ -0,0 +1,121 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use qitops_agent::llm::pool::{self, max_concurrent};
+use qitops_agent::llm::providers::client_for;
+use qitops_agent::llm::{LlmRequest, LlmRouter, ProviderConfig, RouterConfig};
+
+/// Serve chat completions slowly, returning the most requests that were in flight at once
+async fn serve_slowly() -> (String, Arc<AtomicUsize>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    let active = Arc::new(AtomicUsize::new(0));
+    let most = Arc::new(AtomicUsize::new(0));
+
+    let peak = most.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let (active, most) = (active.clone(), most.clone());
+            tokio::spawn(async move {
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 4096];
+                let body_start = loop {
+                    let read = socket.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                    if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break end + 4;
+                    }
+                };
+                let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+                let length: usize = headers.lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .map(|value| value.trim().parse().unwrap())
+                    .unwrap_or(0);
+                while request.len() < body_start + length {
+                    let read = socket.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                }
+
+                let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                most.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(150)).await;
+                active.fetch_sub(1, Ordering::SeqCst);
+
+                let answer = serde_json::json!({
+                    "model": "gpt-4o-mini",
+                    "choices": [{"message": {"role": "assistant", "content": "ok"}}],
+                    "usage": {"total_tokens": 3},
+                }).to_string();
+                let response = format!("HTTP/1.1 200 OK\r\ncontent-type: application/json\r\nconnection: close\r\ncontent-length: {}\r\n\r\n{}", answer.len(), answer);
+                socket.write_all(response.as_bytes()).await.unwrap();
+            });
+        }
+    });
+
+    (address, peak)
+}
+
+fn provider(provider_type: &str, api_base: &str, max_concurrent_requests: Option<u32>) -> ProviderConfig {
+    serde_json::from_value(serde_json::json!({
+        "provider_type": provider_type,
+        "api_key": "sk-test",
+        "api_base": api_base,
+        "default_model": "gpt-4o-mini",
+        "max_concurrent_requests": max_concurrent_requests,
+    })).unwrap()
+}
+
+#[tokio::test]
+async fn batches_stay_within_the_provider_concurrency_limit() {
+    let (api_base, peak) = serve_slowly().await;
+    let config = provider("openai", &api_base, Some(2));
+    let router = LlmRouter::with_clients(
+        RouterConfig { providers: vec![config.clone()], batch_concurrency: 6, ..RouterConfig::default() },
+        vec![client_for(&config).unwrap().unwrap()],
+    );
+    // A second router over the same endpoint shares the limit
+    let other = LlmRouter::with_clients(
+        RouterConfig { providers: vec![config.clone()], batch_concurrency: 6, ..RouterConfig::default() },
+        vec![client_for(&config).unwrap().unwrap()],
+    );
+
+    let requests = |count: usize| (0..count).map(|i| LlmRequest::new(format!("request {}", i), "gpt-4o-mini".to_string()).with_cache(false)).collect::<Vec<_>>();
+    let (first, second) = tokio::join!(
+        router.send_batch(requests(4), None, |_| {}),
+        other.send_batch(requests(2), None, |_| {}),
+    );
+    assert!(first.iter().chain(&second).all(|result| result.as_ref().is_ok_and(|response| response.text == "ok")));
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+
+    let metrics = router.queue_metrics();
+    assert_eq!(metrics.len(), 1);
+    assert_eq!(metrics[0].provider, "openai");
+    assert_eq!(metrics[0].max_concurrent, Some(2));
+    assert_eq!(metrics[0].requests, 6);
+    assert_eq!((metrics[0].in_flight, metrics[0].waiting), (0, 0));
+    // Six requests of 150ms through two slots: the last pair waits for two rounds
+    assert!(metrics[0].max_wait_ms >= 250, "{:?}", metrics[0]);
+    assert!(metrics[0].total_wait_ms >= metrics[0].max_wait_ms);
+    assert_eq!(other.queue_metrics(), metrics);
+}
+
+#[test]
+fn ollama_is_limited_by_default_and_clients_are_shared() {
+    assert_eq!(max_concurrent(&provider("ollama", "http://localhost:11434", None)), Some(pool::DEFAULT_OLLAMA_CONCURRENCY as usize));
+    assert_eq!(max_concurrent(&provider("ollama", "http://localhost:11434", Some(0))), None);
+    assert_eq!(max_concurrent(&provider("openai", "https://api.openai.com/v1", None)), None);
+    assert_eq!(max_concurrent(&provider("openai", "https://api.openai.com/v1", Some(8))), Some(8));
+
+    // Saved only when set
+    let saved = serde_json::to_value(provider("openai", "https://api.openai.com/v1", None)).unwrap();
+    assert!(saved.get("max_concurrent_requests").is_none());
+
+    // Routers over the same endpoint and limit share a queue; a different limit gets its own
+    let config = provider("ollama", "http://127.0.0.1:11999", Some(3));
+    assert!(Arc::ptr_eq(&pool::queue_for(&config), &pool::queue_for(&config.clone())));
+    assert!(!Arc::ptr_eq(&pool::queue_for(&config), &pool::queue_for(&provider("ollama", "http://127.0.0.1:11999", Some(5)))));
+    assert_eq!(pool::queue_for(&config).metrics().max_concurrent, Some(3));
+}
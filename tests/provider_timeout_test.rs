@@ -0,0 +1,36 @@
+use std::time::{Duration, Instant};
+
+use qitops_agent::error::{self, EXIT_UNAVAILABLE};
+use qitops_agent::llm::providers::client_for;
+use qitops_agent::llm::{LlmRequest, ProviderConfig};
+
+#[tokio::test]
+async fn provider_timeouts_bound_a_server_that_never_answers() {
+    // Accepts connections but never responds, like a local model still loading
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((socket, _)) = listener.accept().await {
+            held.push(socket);
+        }
+    });
+
+    let config: ProviderConfig = serde_json::from_value(serde_json::json!({
+        "provider_type": "ollama",
+        "api_key": null,
+        "api_base": format!("http://{}", address),
+        "default_model": "llama3",
+        "timeout_secs": 1,
+        "connect_timeout_secs": 1,
+        "max_retries": 0,
+    })).unwrap();
+    assert_eq!(config.max_retries, Some(0));
+
+    let client = client_for(&config).unwrap().unwrap();
+    let started = Instant::now();
+    let error = client.send(LlmRequest::new("Hello".to_string(), "llama3".to_string())).await.unwrap_err();
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(error::exit_code(&error), EXIT_UNAVAILABLE);
+}
@@ -0,0 +1,26 @@
+use qitops_agent::integrations::jira::{JiraClient, JiraConfig};
+use qitops_agent::proxy;
+
+mod common;
+
+#[test]
+fn configured_proxy_must_be_a_url() {
+    assert!(proxy::client(None).is_ok());
+    assert!(proxy::client(Some("http://proxy.example.com:3128")).is_ok());
+    assert!(proxy::client(Some("not a proxy")).is_err());
+}
+
+#[tokio::test]
+async fn integrations_go_through_their_configured_proxy() {
+    let (proxy_url, proxy) = common::serve_once(serde_json::json!({"issues": [{"key": "QA-7"}]})).await;
+    let client = JiraClient::from_config(&JiraConfig {
+        url: Some("http://jira.example.com".to_string()),
+        api_token: Some("token".to_string()),
+        proxy: Some(proxy_url),
+        ..JiraConfig::default()
+    }).unwrap();
+
+    assert_eq!(client.find_issue_with_label("QA", "qitops-1").await.unwrap().as_deref(), Some("QA-7"));
+    let request = proxy.await.unwrap();
+    assert!(request.path.starts_with("http://jira.example.com/rest/api/2/search/jql?"), "{}", request.path);
+}
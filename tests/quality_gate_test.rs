@@ -0,0 +1,52 @@
+use qitops_agent::error::{self, EXIT_GATE_FAILED, EXIT_INVALID_INPUT, QitOpsError};
+use qitops_agent::severity::gate::{FailOn, QualityGate, quality_score};
+use qitops_agent::severity::{Severity, SeverityPolicy};
+
+#[test]
+fn gates_are_built_from_the_command_line_options() {
+    assert_eq!(QualityGate::from_args(false, &[], None).unwrap(), None);
+    // --gate alone follows the severity policy
+    assert_eq!(QualityGate::from_args(true, &[], None).unwrap().unwrap().fail_on, [FailOn::Policy]);
+
+    let gate = QualityGate::from_args(false, &["high-risk,critical".to_string(), "gate".to_string()], Some(7.0)).unwrap().unwrap();
+    assert_eq!(gate.fail_on, [FailOn::Risk(Severity::High), FailOn::Finding(Severity::Critical), FailOn::Policy]);
+    assert_eq!(gate.min_score, Some(7.0));
+    assert!(gate.needs_risk());
+    assert_eq!(gate.fail_on[0].to_string(), "high-risk");
+
+    // A score alone doesn't add the policy
+    assert_eq!(QualityGate::from_args(false, &[], Some(5.0)).unwrap().unwrap().fail_on, []);
+
+    for invalid in [QualityGate::from_args(true, &["severe-ish".to_string()], None), QualityGate::from_args(true, &["extreme-risk".to_string()], None), QualityGate::from_args(true, &[], Some(11.0))] {
+        assert_eq!(error::exit_code(&invalid.unwrap_err()), EXIT_INVALID_INPUT);
+    }
+}
+
+#[test]
+fn verdicts_depend_only_on_the_structured_result() {
+    let findings = [Severity::High, Severity::Medium, Severity::Low, Severity::Info];
+    assert_eq!(quality_score(&findings), 6.5);
+    assert_eq!(quality_score(&[]), 10.0);
+    assert_eq!(quality_score(&[Severity::Critical; 3]), 0.0);
+
+    let policy = SeverityPolicy::default();
+    let gate = QualityGate { fail_on: vec![FailOn::Risk(Severity::High), FailOn::Finding(Severity::Critical)], min_score: Some(7.0) };
+
+    let verdict = gate.evaluate(&findings, Some(Severity::High), &policy);
+    assert!(!verdict.passed());
+    assert_eq!(verdict.score, 6.5);
+    assert_eq!(verdict.failures, ["high overall risk, at or above high", "quality score 6.5 is below 7.0"]);
+
+    // The same result always gets the same verdict
+    assert_eq!(gate.evaluate(&findings, Some(Severity::High), &policy), verdict);
+    assert!(gate.evaluate(&[Severity::Low], Some(Severity::Medium), &policy).passed());
+
+    let policy_gate = QualityGate { fail_on: vec![FailOn::Policy], min_score: None };
+    assert_eq!(policy_gate.evaluate(&findings, None, &policy).failures, ["findings at or above high severity (the severity policy)"]);
+    assert!(policy_gate.evaluate(&[Severity::Medium], None, &policy).passed());
+
+    let failed: anyhow::Error = QitOpsError::GateFailed("Quality gate failed".to_string()).into();
+    assert_eq!(error::exit_code(&failed), EXIT_GATE_FAILED);
+    #[cfg(feature = "monitoring")]
+    assert_eq!(error::error_class(&failed), "gate_failed");
+}
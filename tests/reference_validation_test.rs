@@ -0,0 +1,41 @@
+use qitops_agent::cli::persona::PersonaManager;
+use qitops_agent::cli::source::{Source, SourceManager, SourceType};
+use qitops_agent::cli::suggest::did_you_mean;
+
+#[test]
+fn suggests_the_closest_id_for_a_typo() {
+    let ids = ["qa-engineer", "security-analyst", "performance-engineer"];
+
+    assert_eq!(did_you_mean("security-analst", ids), Some("security-analyst"));
+    assert_eq!(did_you_mean("QA-Enginer", ids), Some("qa-engineer"));
+    assert_eq!(did_you_mean("perf", ids), Some("performance-engineer"));
+    assert_eq!(did_you_mean("product-owner", ids), None);
+}
+
+#[test]
+fn unknown_references_are_reported_with_suggestions() {
+    let home = std::env::temp_dir().join(format!("qitops-reference-validation-{}", std::process::id()));
+    unsafe {
+        std::env::set_var("HOME", &home);
+        std::env::set_var("APPDATA", &home);
+    }
+
+    let personas = PersonaManager::new().unwrap();
+    assert!(personas.validate_ids(&["qa-engineer".to_string()]).is_ok());
+    let error = personas.validate_ids(&["qa-engineer".to_string(), "security-analist".to_string(), "lawyer".to_string()])
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("Unknown persona 'security-analist' (did you mean 'security-analyst'?)"), "{}", error);
+    assert!(error.contains("Unknown persona 'lawyer'"), "{}", error);
+    assert!(!error.contains("'qa-engineer'"), "{}", error);
+
+    // Sources registered with `qitops source add` are kept for later runs
+    let mut sources = SourceManager::new().unwrap();
+    sources.add_source(Source::new("api-spec".to_string(), SourceType::Documentation, "docs/api.md".into(), None)).unwrap();
+    let sources = SourceManager::new().unwrap();
+    assert!(sources.validate_ids(&["api-spec".to_string()]).is_ok());
+    let error = sources.validate_ids(&["api-specs".to_string()]).unwrap_err().to_string();
+    assert!(error.contains("Unknown source 'api-specs' (did you mean 'api-spec'?)"), "{}", error);
+
+    let _ = std::fs::remove_dir_all(&home);
+}
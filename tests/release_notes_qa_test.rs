@@ -0,0 +1,133 @@
+
+use qitops_agent::agent::release_notes::{ReleaseNotesQaAgent, ReleasePullRequest, ReleaseSummary, pull_request_of};
+use qitops_agent::agent::traits::Agent;
+use qitops_agent::ci::{GitHubClient, GitHubConfig};
+use qitops_agent::output::Report;
+use qitops_agent::runs::RunRecord;
+
+mod common;
+use common::Reply;
+
+/// Serve one answer per connection, in order, returning the request lines
+async fn serve(answers: Vec<serde_json::Value>) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+    let (address, server) = common::serve(answers.into_iter().map(Reply::json).collect()).await;
+    (address, tokio::spawn(async move { server.await.unwrap().into_iter().map(|request| request.path).collect() }))
+}
+
+fn record(id: &str, recorded_at: u64, report: serde_json::Value) -> RunRecord {
+    RunRecord { id: id.to_string(), recorded_at, report: serde_json::from_value::<Report>(report).unwrap() }
+}
+
+fn pull_request(number: u64, test_files: &[&str], analyzed: bool) -> ReleasePullRequest {
+    ReleasePullRequest {
+        number,
+        title: format!("Change {}", number),
+        author: "ada".to_string(),
+        merged_at: "2026-10-01T10:00:00Z".to_string(),
+        labels: Vec::new(),
+        changed_files: 3,
+        test_files: test_files.iter().map(|file| file.to_string()).collect(),
+        analysis_run: analyzed.then(|| format!("run-{}", number)),
+        risk_run: None,
+        overall_risk: None,
+        risky_components: Vec::new(),
+        open_findings: Vec::new(),
+        resolved_findings: 0,
+    }
+}
+
+#[tokio::test]
+async fn appendix_aggregates_the_stored_analyses_of_merged_pull_requests() {
+    let (github_base, github) = serve(vec![
+        serde_json::json!({"total_count": 2, "items": [
+            {"number": 15, "title": "Cache sessions", "user": {"login": "grace"}, "labels": [], "pull_request": {"merged_at": "2026-10-03T09:00:00Z"}},
+            {"number": 12, "title": "Rework checkout", "user": {"login": "ada"}, "labels": [{"name": "payments"}], "pull_request": {"merged_at": "2026-10-01T09:00:00Z"}},
+        ]}),
+        serde_json::json!([
+            {"filename": "src/checkout.py", "status": "modified"},
+            {"filename": "tests/test_checkout.py", "status": "added"},
+            {"filename": "tests/test_cart.py", "status": "removed"},
+        ]),
+        serde_json::json!([{"filename": "src/session.rs", "status": "modified"}]),
+    ]).await;
+    let (llm_base, llm) = serve(vec![serde_json::json!({
+        "model": "gpt-4o-mini",
+        "choices": [{"message": {"role": "assistant", "content": "Checkout is the riskiest change of v2.0."}}],
+        "usage": {"total_tokens": 42},
+    })]).await;
+
+    let router = common::router(&llm_base);
+    let github_client = GitHubClient::from_config(&GitHubConfig {
+        token: Some("test-token".to_string()),
+        api_base: Some(github_base),
+        default_owner: None,
+        default_repo: None,
+        proxy: None,
+    }).unwrap();
+
+    let injection = serde_json::json!({"file": "src/checkout.py", "line": 40, "severity": "high", "title": "Unvalidated discount code"});
+    let history = vec![
+        record("a1", 100, serde_json::json!({
+            "command": "pr-analyze", "title": "PR Analysis: acme/shop#12", "summary": "", "body": "",
+            "findings": [injection, {"severity": "info", "title": "Nit"}], "data": null,
+        })),
+        // An older analysis of the same PR, and one of another repository's #15
+        record("a0", 50, serde_json::json!({"command": "pr-analyze", "title": "PR Analysis: acme/shop#12", "summary": "", "body": "", "findings": [], "data": null})),
+        record("x1", 100, serde_json::json!({"command": "pr-analyze", "title": "PR Analysis: other/app#15", "summary": "", "body": "", "findings": [], "data": null})),
+        record("r1", 120, serde_json::json!({
+            "command": "risk", "title": "Risk Assessment: https://github.com/acme/shop/pull/12", "summary": "", "body": "", "findings": [],
+            "data": {"risk": {"overall_risk": "High", "summary": "", "recommendations": [], "component_risks": [
+                {"component": "payments", "risk_level": "Critical", "description": "Discount rules rewritten"},
+                {"component": "docs", "risk_level": "Low", "description": "Typos"},
+            ]}},
+        })),
+    ];
+
+    let agent = ReleaseNotesQaAgent::new("v2.0".to_string(), "acme".to_string(), "shop".to_string(), github_client, router, history, Vec::new()).await.unwrap();
+    let response = agent.execute().await.unwrap();
+    let data = response.data.unwrap();
+
+    let paths = github.await.unwrap();
+    assert!(paths[0].starts_with("/search/issues?q=repo%3Aacme%2Fshop+is%3Apr+is%3Amerged+milestone%3A%22v2.0%22"), "{}", paths[0]);
+    // In merge order
+    assert_eq!(paths[1..], ["/repos/acme/shop/pulls/12/files", "/repos/acme/shop/pulls/15/files"]);
+    llm.await.unwrap();
+
+    let prs = data["pull_requests"].as_array().unwrap();
+    assert_eq!(prs[0]["number"], 12);
+    assert_eq!(prs[0]["analysis_run"], "a1");
+    assert_eq!(prs[0]["risk_run"], "r1");
+    assert_eq!(prs[0]["test_files"], serde_json::json!(["tests/test_checkout.py"]));
+    assert_eq!(prs[1]["analysis_run"], serde_json::Value::Null);
+
+    let appendix = data["appendix"].as_str().unwrap();
+    assert!(appendix.starts_with("## QA Summary: v2.0\n\n2 merged pull request(s): 1 analyzed by QitOps, 1 high-risk, 1 with test changes, 1 open finding(s).\n\nCheckout is the riskiest change of v2.0.\n"), "{}", appendix);
+    assert!(appendix.contains("- **#12** Rework checkout (High risk)\n  - payments (Critical): Discount rules rewritten\n"));
+    assert!(!appendix.contains("docs (Low)"));
+    assert!(appendix.contains("| #12 Rework checkout | `tests/test_checkout.py` |"));
+    assert!(appendix.contains("- **HIGH** Unvalidated discount code in `src/checkout.py:40` (#12)\n"));
+    assert!(!appendix.contains("Nit"));
+    assert!(appendix.contains("- Not analyzed by QitOps: #15\n- No test changes: #15 Cache sessions\n"));
+}
+
+#[test]
+fn reports_are_matched_to_pull_requests_of_the_repository() {
+    assert_eq!(pull_request_of("PR Analysis: acme/shop#12", "acme", "shop"), Some(12));
+    assert_eq!(pull_request_of("PR Analysis: Acme/Shop#12", "acme", "shop"), Some(12));
+    assert_eq!(pull_request_of("PR Analysis: other/shop#12", "acme", "shop"), None);
+    assert_eq!(pull_request_of("Risk Assessment: https://github.com/acme/shop/pull/7", "acme", "shop"), Some(7));
+    assert_eq!(pull_request_of("Risk Assessment: https://github.com/other/shop/pull/7", "acme", "shop"), None);
+    // A bare number is a PR of the default repository
+    assert_eq!(pull_request_of("Risk Assessment: 31", "acme", "shop"), Some(31));
+    assert_eq!(pull_request_of("Risk Assessment: changes.diff", "acme", "shop"), None);
+
+    let summary = ReleaseSummary {
+        milestone: "v2.1".to_string(),
+        repository: "acme/shop".to_string(),
+        pull_requests: vec![pull_request(3, &["tests/test_login.py"], true), pull_request(4, &["src/login.test.ts"], true)],
+    };
+    let appendix = summary.appendix("");
+    assert!(appendix.starts_with("## QA Summary: v2.1\n\n2 merged pull request(s): 2 analyzed by QitOps, 0 high-risk, 2 with test changes, 0 open finding(s).\n\n### Risky Areas\n"), "{}", appendix);
+    assert!(appendix.contains("No pull request was assessed as high risk."));
+    assert!(appendix.ends_with("### Known Gaps\n\nNone known.\n"));
+}
@@ -0,0 +1,81 @@
+use qitops_agent::llm::cache::ResponseCache;
+use qitops_agent::llm::client::ToolDefinition;
+use qitops_agent::llm::{LlmRequest, LlmResponse};
+
+mod common;
+use common::scratch;
+
+fn request(prompt: &str) -> LlmRequest {
+    LlmRequest::new(prompt.to_string(), "gpt-4o-mini".to_string())
+}
+
+fn response(text: &str) -> LlmResponse {
+    LlmResponse::new(text.to_string(), "gpt-4o-mini".to_string(), "openai".to_string())
+}
+
+#[test]
+fn everything_that_changes_the_answer_is_part_of_the_key() {
+    let mut cache = ResponseCache::new(3600, false).unwrap();
+    let base = request("Generate tests for add()");
+    cache.put(&base, "openai", response("## Test Case 1")).unwrap();
+
+    assert_eq!(cache.get(&base, "openai").unwrap().text, "## Test Case 1");
+    assert_eq!(cache.get(&request("Generate tests for add()"), "openai").unwrap().text, "## Test Case 1");
+
+    let variants = [
+        ("provider", base.clone(), "anthropic"),
+        ("prompt", request("Generate tests for sub()"), "openai"),
+        ("json mode", base.clone().with_json_mode(true), "openai"),
+        ("temperature", base.clone().with_temperature(0.9), "openai"),
+        ("max tokens", base.clone().with_max_tokens(16), "openai"),
+        ("option", base.clone().with_option("reasoning_effort", serde_json::json!("high")), "openai"),
+        ("seed", base.clone().with_seed(7), "openai"),
+        ("tool", base.clone().with_tool(ToolDefinition::new("run", "Run a command", serde_json::json!({"type": "object"}))), "openai"),
+    ];
+    for (change, request, provider) in variants {
+        assert!(cache.get(&request, provider).is_none(), "a different {} hit the cache", change);
+    }
+    assert_eq!(cache.stats().unwrap().hits, 2);
+}
+
+#[test]
+fn expired_and_least_recently_used_entries_are_evicted() {
+    let mut cache = ResponseCache::new(0, false).unwrap();
+    cache.put(&request("first"), "openai", response("answer")).unwrap();
+    assert!(cache.get(&request("first"), "openai").is_none());
+    assert_eq!(cache.stats().unwrap().entries, 0);
+
+    let mut cache = ResponseCache::new(3600, false).unwrap();
+    cache.put(&request("first"), "openai", response("answer")).unwrap();
+    let size = cache.stats().unwrap().size_bytes;
+
+    // Room for two entries: the oldest goes when a third arrives
+    let mut cache = cache.with_max_size(size * 2);
+    cache.put(&request("second"), "openai", response("answer")).unwrap();
+    cache.put(&request("third"), "openai", response("answer")).unwrap();
+
+    let stats = cache.stats().unwrap();
+    assert_eq!((stats.entries, stats.size_bytes), (2, size * 2));
+    assert!(cache.get(&request("first"), "openai").is_none());
+    assert!(cache.get(&request("second"), "openai").is_some());
+    assert!(cache.get(&request("third"), "openai").is_some());
+
+    cache.clear().unwrap();
+    assert_eq!(cache.stats().unwrap().entries, 0);
+}
+
+#[test]
+fn the_disk_cache_survives_a_restart_in_its_namespace() {
+    unsafe { std::env::set_var("XDG_CACHE_HOME", scratch("response-cache")) };
+
+    let mut cache = ResponseCache::new(3600, true).unwrap().with_namespace("tenant-payments").unwrap();
+    cache.put(&request("Generate tests for add()"), "openai", response("## Test Case 1")).unwrap();
+    let path = cache.path().unwrap();
+    assert!(path.ends_with("qitops/llm_cache/tenant-payments/cache.db"), "{}", path.display());
+    drop(cache);
+
+    let reopened = ResponseCache::new(3600, true).unwrap().with_namespace("tenant-payments").unwrap();
+    assert_eq!(reopened.get(&request("Generate tests for add()"), "openai").unwrap().text, "## Test Case 1");
+    let other = ResponseCache::new(3600, true).unwrap().with_namespace("tenant-search").unwrap();
+    assert!(other.get(&request("Generate tests for add()"), "openai").is_none());
+}
@@ -0,0 +1,94 @@
+use qitops_agent::agent::traits::{AgentResponse, AgentStatus};
+use qitops_agent::output::Report;
+use qitops_agent::runs::risk::{RiskQuery, RiskStore, component_trends};
+use qitops_agent::severity::Severity;
+
+mod common;
+use common::scratch;
+
+const DAY: u64 = 86_400;
+
+/// A risk run's report with an overall level, its score and scored components
+fn report(title: &str, overall: &str, score: Option<u32>, components: &[(&str, &str, u32)]) -> Report {
+    let data = serde_json::json!({
+        "assessment": "Risk",
+        "risk": {
+            "overall_risk": overall,
+            "component_risks": components.iter().map(|(component, level, _)| serde_json::json!({
+                "component": component, "risk_level": level, "description": "Why",
+            })).collect::<Vec<_>>(),
+            "summary": "Summary",
+            "recommendations": [],
+        },
+        "score": score.map(|score| serde_json::json!({
+            "score": score,
+            "label": "high",
+            "components": components.iter().map(|(component, _, score)| serde_json::json!({
+                "component": component, "score": score, "weight": 1.0,
+            })).collect::<Vec<_>>(),
+        })),
+    });
+    let response = AgentResponse { status: AgentStatus::Success, message: "Risk assessment completed".to_string(), data: Some(data) };
+    Report::from_response("risk", title, &response)
+}
+
+#[test]
+fn assessments_are_recorded_with_their_components() {
+    let path = scratch("risk-record").join("risk.db");
+    let mut store = RiskStore::at(&path).unwrap();
+
+    assert!(store.record("acme/shop", Some(12), "run-1", &report("Risk Assessment: 12", "High", Some(70), &[("Payments", "High", 65), ("docs", "Low", 10)]), 1_700_000_000).unwrap());
+    assert!(store.record("acme/shop", None, "run-2", &report("Risk Assessment: fix.diff", "Low", None, &[("cli", "Low", 10)]), 1_700_000_000 + DAY).unwrap());
+    assert!(store.record("acme/other", Some(3), "run-3", &report("Risk Assessment: 3", "Medium", Some(35), &[]), 1_700_000_000 + 2 * DAY).unwrap());
+    // Reports of other commands have no assessment
+    let analysis = Report::from_response("pr-analyze", "PR Analysis", &AgentResponse { status: AgentStatus::Success, message: String::new(), data: Some(serde_json::json!({"analysis": "Fine"})) });
+    assert!(!store.record("acme/shop", Some(12), "run-4", &analysis, 1_700_000_000).unwrap());
+
+    // Newest first
+    let records = store.query(&RiskQuery::default()).unwrap();
+    let runs: Vec<&str> = records.iter().map(|record| record.run_id.as_str()).collect();
+    assert_eq!(runs, ["run-3", "run-2", "run-1"]);
+    let first = &records[2];
+    assert_eq!((first.repo.as_str(), first.pr, first.overall_risk, first.score, first.day.as_str()), ("acme/shop", Some(12), Severity::High, Some(70), "2023-11-14"));
+    assert_eq!((first.components[0].component.as_str(), first.components[0].risk_level, first.components[0].score), ("Payments", Severity::High, Some(65)));
+    assert_eq!((records[1].score, records[1].components[0].score), (None, None));
+
+    // Filtered by repository, component (without regard to case) and day
+    let by_repo = store.query(&RiskQuery { repo: Some("acme/shop".to_string()), limit: Some(1), ..RiskQuery::default() }).unwrap();
+    assert_eq!(by_repo.iter().map(|record| record.run_id.as_str()).collect::<Vec<_>>(), ["run-2"]);
+    let by_component = store.query(&RiskQuery { component: Some("payments".to_string()), ..RiskQuery::default() }).unwrap();
+    assert_eq!(by_component.iter().map(|record| record.run_id.as_str()).collect::<Vec<_>>(), ["run-1"]);
+    let since = store.query(&RiskQuery { since_day: Some("2023-11-15".to_string()), ..RiskQuery::default() }).unwrap();
+    assert_eq!(since.len(), 2);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn components_that_keep_coming_up_high_risk_are_recurring() {
+    let path = scratch("risk-trends").join("risk.db");
+    let mut store = RiskStore::at(&path).unwrap();
+    let assessments = [
+        ("High", vec![("payments", "High", 65), ("docs", "Low", 10)]),
+        ("Critical", vec![("Payments", "Critical", 90)]),
+        ("Medium", vec![("payments", "Medium", 35), ("docs", "Low", 10)]),
+        ("High", vec![("payments", "High", 80), ("docs", "Medium", 35)]),
+    ];
+    for (day, (overall, components)) in assessments.iter().enumerate() {
+        store.record("acme/shop", Some(day as u64), &format!("run-{}", day), &report("Risk", overall, Some(60), components), 1_700_000_000 + day as u64 * DAY).unwrap();
+    }
+
+    let trends = component_trends(&store.query(&RiskQuery::default()).unwrap());
+    assert_eq!(trends.len(), 2);
+    let payments = &trends[0];
+    assert_eq!((payments.component.as_str(), payments.assessments, payments.high_risk), ("payments", 4, 3));
+    // (65 + 90 + 35 + 80) / 4, and 80 against the mean of the earlier 63
+    assert_eq!((payments.average_score, payments.latest_level, payments.latest_score, payments.change), (Some(68), Severity::High, Some(80), Some(17)));
+    assert!(payments.is_recurring());
+
+    let docs = &trends[1];
+    assert_eq!((docs.assessments, docs.high_risk, docs.latest_level, docs.change), (3, 0, Severity::Medium, Some(25)));
+    assert!(!docs.is_recurring());
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,96 @@
+use qitops_agent::agent::findings::Finding;
+use qitops_agent::agent::risk::{ComponentRisk, RiskAssessment, RiskLevel};
+use qitops_agent::agent::risk_score::{ComponentScore, RiskScoring};
+use qitops_agent::config::QitOpsConfig;
+use qitops_agent::error::{self, QitOpsError};
+use qitops_agent::severity::Severity;
+
+fn assessment(overall_risk: RiskLevel, components: &[(&str, RiskLevel)]) -> RiskAssessment {
+    RiskAssessment {
+        overall_risk,
+        component_risks: components.iter().map(|(component, risk_level)| ComponentRisk {
+            component: component.to_string(),
+            risk_level: *risk_level,
+            description: "Why".to_string(),
+        }).collect(),
+        summary: "Summary".to_string(),
+        recommendations: Vec::new(),
+    }
+}
+
+fn finding(severity: Severity) -> Finding {
+    Finding { file: None, line: None, severity, title: "Title".to_string(), description: "Details".to_string() }
+}
+
+#[test]
+fn assessments_are_scored_by_the_default_rubric() {
+    let scoring = RiskScoring::default();
+    scoring.validate().unwrap();
+
+    // The overall level alone
+    let score = scoring.score(&assessment(RiskLevel::Medium, &[]), &[]);
+    assert_eq!((score.score, score.label.as_str(), score.exit_code), (35, "medium", None));
+    assert_eq!(score.to_markdown(), "**Risk score:** 35/100 (medium)");
+
+    // Components above the overall level raise the score, and findings add to it
+    let score = scoring.score(
+        &assessment(RiskLevel::Low, &[("payments", RiskLevel::Critical), ("docs", RiskLevel::Low)]),
+        &[finding(Severity::High), finding(Severity::Low), finding(Severity::Info)],
+    );
+    assert_eq!(score.components, [
+        ComponentScore { component: "payments".to_string(), score: 90, weight: 1.0 },
+        ComponentScore { component: "docs".to_string(), score: 10, weight: 1.0 },
+    ]);
+    assert_eq!((score.score, score.label.as_str()), (56, "high"));
+    assert_eq!(score.to_markdown(), "**Risk score:** 56/100 (high); components: payments 90, docs 10");
+
+    // Capped at 100
+    let findings = vec![finding(Severity::Critical); 5];
+    assert_eq!(scoring.score(&assessment(RiskLevel::Critical, &[]), &findings).score, 100);
+}
+
+#[test]
+fn rubrics_and_thresholds_come_from_the_config() {
+    let config: QitOpsConfig = serde_json::from_value(serde_json::json!({
+        "risk_scoring": {
+            "levels": {"low": 0, "medium": 20, "high": 60, "critical": 100},
+            "findings": {"high": 15},
+            "weights": {"Payments": 3},
+            "thresholds": [
+                {"min": 0, "label": "green"},
+                {"min": 40, "label": "amber", "exit_code": 10},
+                {"min": 80, "label": "red", "exit_code": 11},
+            ],
+        },
+    })).unwrap();
+    let scoring = config.risk_scoring;
+    scoring.validate().unwrap();
+
+    // Payments weighs three times docs: (3 * 60 + 0) / 4 = 45
+    let score = scoring.score(&assessment(RiskLevel::Medium, &[("payments", RiskLevel::High), ("docs", RiskLevel::Low)]), &[]);
+    assert_eq!((score.score, score.label.as_str(), score.exit_code), (45, "amber", Some(10)));
+    assert_eq!(score.components[0].weight, 3.0);
+    let score = scoring.score(&assessment(RiskLevel::High, &[]), &[finding(Severity::High), finding(Severity::Medium)]);
+    assert_eq!((score.score, score.exit_code), (75, Some(10)));
+
+    // Exit codes of the range are the process exit code
+    let error: anyhow::Error = QitOpsError::RiskThreshold { message: "Risk score 81/100 is red".to_string(), exit_code: 11 }.into();
+    assert_eq!(error::exit_code(&error), 11);
+    #[cfg(feature = "monitoring")]
+    assert_eq!(error::error_class(&error), "risk_threshold");
+
+    // Invalid rubrics
+    let invalid = |scoring: RiskScoring| scoring.validate().unwrap_err().to_string();
+    let mut scoring = RiskScoring::default();
+    scoring.thresholds.swap(1, 2);
+    assert!(invalid(scoring).contains("threshold 25 must be above 50"));
+    let mut scoring = RiskScoring::default();
+    scoring.thresholds.remove(0);
+    assert!(invalid(scoring).contains("first threshold must start at 0"));
+    let mut scoring = RiskScoring::default();
+    scoring.thresholds[3].exit_code = Some(0);
+    assert!(invalid(scoring).contains("exit code of critical"));
+    let mut scoring = RiskScoring::default();
+    scoring.levels.insert(Severity::High, 120.0);
+    assert!(invalid(scoring).contains("points for high"));
+}
@@ -0,0 +1,68 @@
+use qitops_agent::agent::findings::Finding;
+use qitops_agent::history::HistoryConfig;
+use qitops_agent::output::Report;
+use qitops_agent::runs::{self, RunHistory};
+use qitops_agent::severity::Severity;
+
+fn finding(file: &str, title: &str, severity: Severity) -> Finding {
+    Finding { file: Some(file.to_string()), line: Some(1), severity, title: title.to_string(), description: String::new() }
+}
+
+fn report(body: &str, findings: Vec<Finding>) -> Report {
+    Report {
+        command: "pr-analyze".to_string(),
+        title: "PR Analysis: acme/shop#7".to_string(),
+        summary: String::new(),
+        body: body.to_string(),
+        findings,
+        gate: None,
+        data: serde_json::Value::Null,
+    }
+}
+
+#[test]
+fn diff_matches_findings_and_test_cases_across_runs() {
+    let a = report(
+        "## Test Case 1: Login succeeds\nSteps\n\n## Test Case 2: Login fails\nSteps\n",
+        vec![
+            finding("src/auth.rs", "Unchecked token", Severity::Medium),
+            finding("src/db.rs", "SQL built from input", Severity::High),
+        ],
+    );
+    let b = report(
+        "## Test Case 1: Login succeeds\nOther steps\n\n## Test Case 2: Password reset\nSteps\n",
+        vec![
+            finding("src/auth.rs", "Unchecked  token", Severity::High),
+            finding("src/api.rs", "Missing rate limit", Severity::Low),
+        ],
+    );
+
+    let diff = runs::diff(&a, &b);
+    assert_eq!(diff.findings_added.iter().map(|f| f.title.as_str()).collect::<Vec<_>>(), ["Missing rate limit"]);
+    assert_eq!(diff.findings_removed.iter().map(|f| f.title.as_str()).collect::<Vec<_>>(), ["SQL built from input"]);
+    assert_eq!(diff.severity_changed.len(), 1);
+    assert_eq!(diff.severity_changed[0].was, Severity::Medium);
+    assert_eq!(diff.cases_added, ["Test Case 2: Password reset"]);
+    assert_eq!(diff.cases_removed, ["Test Case 2: Login fails"]);
+    assert_eq!(diff.cases_unchanged, 1);
+    assert!(runs::diff(&a, &a).is_empty());
+}
+
+#[test]
+fn run_history_saves_and_finds_runs_by_prefix_and_age() {
+    let dir = std::env::temp_dir().join(format!("qitops-runs-test-{}", std::process::id()));
+    let history = RunHistory::at(dir.clone(), HistoryConfig::default());
+
+    let first = history.record("run-abc", &report("first", Vec::new())).unwrap();
+    let second = history.record("run-abc", &report("second", Vec::new())).unwrap();
+    assert_eq!(first, "run-abc");
+    assert_eq!(second, "run-abc-2");
+
+    assert_eq!(history.get("run-abc").unwrap().report.body, "first");
+    assert_eq!(history.get("run-abc-").unwrap().report.body, "second");
+    assert_eq!(history.list().unwrap().len(), 2);
+    assert!(history.get("missing").is_err());
+    assert!(history.get("latest~5").is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,55 @@
+use qitops_agent::agent::AgentResponse;
+use qitops_agent::agent::AgentStatus;
+use qitops_agent::cli::summary::RunSummary;
+use qitops_agent::llm::cost::UsageTotals;
+use qitops_agent::output::Report;
+use qitops_agent::severity::SeverityPolicy;
+use std::time::Duration;
+
+fn report(data: serde_json::Value) -> Report {
+    let response = AgentResponse {
+        status: AgentStatus::Success,
+        message: "Risk assessment completed".to_string(),
+        data: Some(data),
+    };
+    Report::from_response("risk", "Risk Assessment: change.diff", &response)
+}
+
+#[test]
+fn summary_counts_findings_and_shows_gate_cost_and_outputs() {
+    colored::control::set_override(false);
+    let report = report(serde_json::json!({
+        "assessment": "**Overall risk:** High",
+        "findings": [
+            {"file": "db.py", "line": 3, "severity": "high", "title": "SQL injection", "description": "d"},
+            {"file": "db.py", "line": 9, "severity": "low", "title": "Unused import", "description": "d"},
+            {"file": null, "line": null, "severity": "high", "title": "No tests", "description": "d"},
+        ],
+        "baseline_written": false,
+    })).with_gate(&SeverityPolicy::default());
+
+    let summary = RunSummary::from_report(&report)
+        .with_output("risk.md")
+        .with_run_id("20261016-abc".to_string())
+        .with_usage(UsageTotals { requests: 2, input_tokens: 1000, output_tokens: 234, cost_usd: 0.0123 })
+        .with_duration(Duration::from_millis(4200));
+    let table = summary.render();
+
+    assert!(table.contains("0 critical  2 high  0 medium  1 low  0 info"), "{}", table);
+    assert!(table.contains("Gate       FAIL"), "{}", table);
+    assert!(table.contains("$0.0123 (2 request(s), 1234 tokens)"));
+    assert!(table.contains("Duration   4.2s"));
+    assert!(table.contains("Outputs    risk.md"));
+    assert!(table.contains("20261016-abc"));
+}
+
+#[test]
+fn runs_without_findings_or_gate_skip_those_rows() {
+    colored::control::set_override(false);
+    let report = report(serde_json::json!({"test_cases": "## Test Case 1", "output_files": ["a_test.md", "b_test.md"]}));
+    let table = RunSummary::from_report(&report).render();
+
+    assert!(!table.contains("Findings"));
+    assert!(!table.contains("Gate"));
+    assert!(table.contains("Outputs    a_test.md\n             b_test.md"), "{}", table);
+}
@@ -0,0 +1,64 @@
+use std::fs;
+
+use qitops_agent::scan::{ScanLimit, ScanLimits, ScanProgress, read_files_hashed, scan_files, scan_files_with_progress};
+
+#[test]
+fn scans_stop_at_the_configured_limits() {
+    let root = std::env::temp_dir().join(format!("qitops-scan-test-{}", std::process::id()));
+    fs::create_dir_all(root.join("a/b/c")).unwrap();
+    fs::create_dir_all(root.join(".git")).unwrap();
+    for (path, size) in [("one.rs", 10), ("two.rs", 10), ("a/three.rs", 10), ("a/b/c/deep.rs", 10), (".git/hidden.rs", 10), ("notes.txt", 10)] {
+        fs::write(root.join(path), "x".repeat(size)).unwrap();
+    }
+    let rust = |path: &std::path::Path| path.extension().is_some_and(|ext| ext == "rs");
+
+    let all = scan_files(&root, &ScanLimits::default(), rust).unwrap();
+    assert_eq!(all.files.len(), 4);
+    assert!(all.limits_hit.is_empty());
+
+    let shallow = scan_files(&root, &ScanLimits { max_depth: 1, ..ScanLimits::default() }, rust).unwrap();
+    assert_eq!(shallow.files.len(), 3);
+    assert_eq!(shallow.limits_hit, vec![ScanLimit::MaxDepth(1)]);
+
+    let few = scan_files(&root, &ScanLimits { max_files: 2, ..ScanLimits::default() }, rust).unwrap();
+    assert_eq!(few.files.len(), 2);
+    assert!(few.limit_summary().unwrap().contains("context.max_files"));
+
+    let small = scan_files(&root, &ScanLimits { max_total_bytes: 25, ..ScanLimits::default() }, rust).unwrap();
+    assert_eq!(small.total_bytes, 20);
+    assert_eq!(small.limits_hit, vec![ScanLimit::MaxTotalBytes(25)]);
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn parallel_scans_report_progress_and_read_in_order() {
+    let root = std::env::temp_dir().join(format!("qitops-scan-progress-{}", std::process::id()));
+    for dir in 0..8 {
+        fs::create_dir_all(root.join(format!("pkg{}/tests", dir))).unwrap();
+        for file in 0..5 {
+            fs::write(root.join(format!("pkg{}/tests/case{}.rs", dir, file)), format!("{}-{}", dir, file)).unwrap();
+        }
+    }
+
+    let mut last = ScanProgress::default();
+    let scan = scan_files_with_progress(&root, &ScanLimits::default(), |_| true, |progress| last = *progress).unwrap();
+    assert_eq!(scan.files.len(), 40);
+    assert!(scan.files.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(last, ScanProgress { directories: 17, files: 40, bytes: 120 });
+
+    let contents = read_files_hashed(&scan.files);
+    assert_eq!(contents[0].as_ref().map(|content| content.text.as_str()), Some("0-0"));
+    assert_eq!(contents[39].as_ref().map(|content| content.text.as_str()), Some("7-4"));
+
+    // Hashes are SHA-256 of the contents, equal for equal contents
+    fs::write(root.join("pkg7/tests/copy.rs"), "0-0").unwrap();
+    let hashed = read_files_hashed(&[scan.files[0].clone(), root.join("pkg7/tests/copy.rs"), scan.files[1].clone(), root.join("missing.rs")]);
+    assert_eq!(hashed[0].as_ref().unwrap().text, "0-0");
+    assert_eq!(hashed[0].as_ref().unwrap().hash, "22841ea360fc3c3676a38502aa9a90a1ae1fbdac1d937746358efe559d349b6f");
+    assert_eq!(hashed[1], hashed[0]);
+    assert_ne!(hashed[2].as_ref().unwrap().hash, hashed[0].as_ref().unwrap().hash);
+    assert_eq!(hashed[3], None);
+
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,70 @@
+use qitops_agent::llm::providers::client_for;
+use qitops_agent::llm::{LlmRequest, ProviderConfig, RouterConfig};
+
+mod common;
+
+/// Serve one request with a canned answer, returning the request body
+async fn serve_once(answer: serde_json::Value) -> (String, tokio::task::JoinHandle<serde_json::Value>) {
+    let (address, server) = common::serve_once(answer).await;
+    (address, tokio::spawn(async move { server.await.unwrap().json() }))
+}
+
+fn provider(provider_type: &str, api_base: &str) -> ProviderConfig {
+    serde_json::from_value(serde_json::json!({
+        "provider_type": provider_type,
+        "api_key": "sk-test",
+        "api_base": api_base,
+        "default_model": "test-model",
+    })).unwrap()
+}
+
+fn request() -> LlmRequest {
+    LlmRequest::new("Generate 5 user records".to_string(), "test-model".to_string())
+}
+
+fn openai_answer() -> serde_json::Value {
+    serde_json::json!({
+        "model": "test-model",
+        "choices": [{"message": {"role": "assistant", "content": "[]"}}],
+        "usage": {"total_tokens": 12},
+    })
+}
+
+#[tokio::test]
+async fn router_seed_is_sent_unless_the_request_sets_one() {
+    let (api_base, server) = serve_once(openai_answer()).await;
+    let router = common::router_with(&api_base, RouterConfig { seed: Some(42), ..RouterConfig::default() });
+
+    router.send(request(), None).await.unwrap();
+    assert_eq!(server.await.unwrap()["seed"], 42);
+
+    let (api_base, server) = serve_once(openai_answer()).await;
+    let router = common::router_with(&api_base, RouterConfig { seed: Some(42), ..RouterConfig::default() });
+
+    router.send(request().with_seed(7), None).await.unwrap();
+    assert_eq!(server.await.unwrap()["seed"], 7);
+
+    // Without a seed, none is sent
+    let (api_base, server) = serve_once(openai_answer()).await;
+    let client = client_for(&provider("openai", &api_base)).unwrap().unwrap();
+    client.send(request()).await.unwrap();
+    assert!(server.await.unwrap().get("seed").is_none());
+}
+
+#[cfg(feature = "local-llm")]
+#[tokio::test]
+async fn ollama_takes_the_seed_as_an_option() {
+    let (api_base, server) = serve_once(serde_json::json!({
+        "model": "test-model",
+        "response": "[]",
+        "eval_count": 12,
+    })).await;
+
+    let client = client_for(&provider("ollama", &api_base)).unwrap().unwrap();
+    let response = client.send(request().with_seed(1234)).await.unwrap();
+    let body = server.await.unwrap();
+
+    assert_eq!(response.text, "[]");
+    assert_eq!(body["options"]["seed"], 1234);
+    assert!(body.get("seed").is_none());
+}
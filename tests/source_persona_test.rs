@@ -1,286 +1,166 @@
 use anyhow::Result;
-use std::env;
 use std::fs;
 use std::path::PathBuf;
-use tempfile::tempdir;
+use std::sync::{Mutex, MutexGuard};
 
-use qitops::source::{Source, SourceManager, SourceType};
-use qitops::persona::{Persona, PersonaManager};
-use qitops::config::QitOpsConfigManager;
+use qitops_agent::cli::persona::{Persona, PersonaManager};
+use qitops_agent::cli::source::{Source, SourceManager, SourceType};
+use qitops_agent::config::QitOpsConfigManager;
 
-#[tokio::test]
-async fn test_source_manager() -> Result<()> {
-    // Create a temporary directory for testing
-    let temp_dir = tempdir()?;
-    let temp_path = temp_dir.path();
-    
-    // Create a test source file
-    let source_path = temp_path.join("requirements.md");
+/// Tests share `HOME`, so they take turns with a fresh config directory
+static HOME: Mutex<()> = Mutex::new(());
+
+fn isolated_home() -> (MutexGuard<'static, ()>, PathBuf) {
+    let guard = HOME.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let home = std::env::temp_dir().join(format!("qitops-source-persona-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&home);
+    fs::create_dir_all(&home).unwrap();
+    unsafe {
+        std::env::set_var("HOME", &home);
+        std::env::set_var("APPDATA", &home);
+    }
+    (guard, home)
+}
+
+fn security_analyst() -> Persona {
+    Persona::new(
+        "security-analyst".to_string(),
+        "Security Analyst".to_string(),
+        vec!["security".to_string(), "vulnerabilities".to_string(), "compliance".to_string()],
+        "Focus on security vulnerabilities and compliance issues.".to_string(),
+        None,
+    )
+}
+
+#[test]
+fn test_source_manager() -> Result<()> {
+    let (_guard, home) = isolated_home();
+    let source_path = home.join("requirements.md");
     fs::write(&source_path, "# Project Requirements\n\n- Requirement 1\n- Requirement 2")?;
-    
-    // Set up environment variables for testing
-    env::set_var("HOME", temp_path.to_str().unwrap());
-    env::set_var("APPDATA", temp_path.to_str().unwrap());
-    
-    // Create a source manager
+
     let mut source_manager = SourceManager::new()?;
-    
-    // Add a source
-    let source = Source::new(
+    source_manager.add_source(Source::new(
         "requirements".to_string(),
         SourceType::Requirements,
         source_path.clone(),
         Some("Project requirements".to_string()),
-    );
-    
-    source_manager.add_source(source)?;
-    
-    // Get the source
-    let retrieved_source = source_manager.get_source("requirements")
-        .ok_or_else(|| anyhow::anyhow!("Source not found"))?;
-        
-    assert_eq!(retrieved_source.id, "requirements");
-    assert_eq!(retrieved_source.source_type, SourceType::Requirements);
-    assert_eq!(retrieved_source.path, source_path);
-    assert_eq!(retrieved_source.description, Some("Project requirements".to_string()));
-    
-    // Get source content
-    let content = retrieved_source.get_content()?;
-    assert_eq!(content, "# Project Requirements\n\n- Requirement 1\n- Requirement 2");
-    
-    // List sources
-    let sources = source_manager.list_sources();
-    assert_eq!(sources.len(), 1);
-    
-    // Remove the source
+    ))?;
+
+    // Sources are persisted and survive a reload
+    let reloaded = SourceManager::new()?;
+    let source = reloaded.get_source("requirements").expect("source not saved");
+    assert_eq!(source.source_type.to_string(), "requirements");
+    assert_eq!(source.path, source_path);
+    assert_eq!(source.description.as_deref(), Some("Project requirements"));
+    assert_eq!(source.get_content()?, "# Project Requirements\n\n- Requirement 1\n- Requirement 2");
+    assert_eq!(reloaded.list_sources().len(), 1);
+
     source_manager.remove_source("requirements")?;
-    
-    // Check that the source was removed
-    let sources = source_manager.list_sources();
-    assert_eq!(sources.len(), 0);
-    
+    assert!(source_manager.list_sources().is_empty());
+    assert!(source_manager.remove_source("requirements").is_err());
     Ok(())
 }
 
-#[tokio::test]
-async fn test_persona_manager() -> Result<()> {
-    // Create a temporary directory for testing
-    let temp_dir = tempdir()?;
-    let temp_path = temp_dir.path();
-    
-    // Set up environment variables for testing
-    env::set_var("HOME", temp_path.to_str().unwrap());
-    env::set_var("APPDATA", temp_path.to_str().unwrap());
-    
-    // Create a persona manager
+#[test]
+fn test_persona_manager() -> Result<()> {
+    let (_guard, _home) = isolated_home();
+
+    // Built-in personas are available before anything is saved
     let mut persona_manager = PersonaManager::new()?;
-    
-    // Add a persona
-    let persona = Persona::new(
-        "security-analyst".to_string(),
-        "Security Analyst".to_string(),
-        vec!["security".to_string(), "vulnerabilities".to_string(), "compliance".to_string()],
-        "Focus on security vulnerabilities and compliance issues.".to_string(),
-        None,
-    );
-    
-    persona_manager.add_persona(persona)?;
-    
-    // Get the persona
-    let retrieved_persona = persona_manager.get_persona("security-analyst")
-        .ok_or_else(|| anyhow::anyhow!("Persona not found"))?;
-        
-    assert_eq!(retrieved_persona.id, "security-analyst");
-    assert_eq!(retrieved_persona.name, "Security Analyst");
-    assert_eq!(retrieved_persona.focus_areas, vec!["security", "vulnerabilities", "compliance"]);
-    assert_eq!(retrieved_persona.description, "Focus on security vulnerabilities and compliance issues.");
-    
-    // Get prompt for persona
-    let prompt = retrieved_persona.get_prompt();
+    assert!(persona_manager.get_persona("qa-engineer").is_some());
+
+    persona_manager.remove_persona("security-analyst")?;
+    assert!(persona_manager.get_persona("security-analyst").is_none());
+    assert!(PersonaManager::new()?.get_persona("security-analyst").is_none());
+
+    persona_manager.add_persona(security_analyst())?;
+    let persona = persona_manager.get_persona("security-analyst").expect("persona not added");
+    assert_eq!(persona.focus_areas, ["security", "vulnerabilities", "compliance"]);
+    let prompt = persona.get_prompt();
     assert!(prompt.contains("Security Analyst"));
     assert!(prompt.contains("security, vulnerabilities, compliance"));
-    
-    // List personas
-    let personas = persona_manager.list_personas();
-    assert!(personas.len() > 0); // There are default personas
-    
-    // Remove the persona
-    persona_manager.remove_persona("security-analyst")?;
-    
-    // Check that the persona was removed
-    let retrieved_persona = persona_manager.get_persona("security-analyst");
-    assert!(retrieved_persona.is_none());
-    
     Ok(())
 }
 
-#[tokio::test]
-async fn test_config_manager() -> Result<()> {
-    // Create a temporary directory for testing
-    let temp_dir = tempdir()?;
-    let temp_path = temp_dir.path();
-    
-    // Set up environment variables for testing
-    env::set_var("HOME", temp_path.to_str().unwrap());
-    env::set_var("APPDATA", temp_path.to_str().unwrap());
-    
-    // Create a config manager
-    let config_manager = QitOpsConfigManager::new()?;
-    
-    // Get default sources for a command
-    let default_sources = config_manager.get_default_sources("test-gen");
-    
-    // Get default personas for a command
-    let default_personas = config_manager.get_default_personas("test-gen");
-    
-    // Set environment variables for default sources and personas
-    env::set_var("QITOPS_DEFAULT_SOURCES", "requirements,standards");
-    env::set_var("QITOPS_DEFAULT_PERSONAS", "security-analyst");
-    
-    // Create a new config manager to pick up the environment variables
+#[test]
+fn test_default_sources_and_personas_from_environment() -> Result<()> {
+    let (_guard, _home) = isolated_home();
+    unsafe {
+        std::env::set_var("QITOPS_DEFAULT_SOURCES", "requirements, standards");
+        std::env::set_var("QITOPS_DEFAULT_PERSONAS", "security-analyst");
+    }
+
     let config_manager = QitOpsConfigManager::new()?;
-    
-    // Get default sources for a command
-    let default_sources = config_manager.get_default_sources("test-gen");
-    assert_eq!(default_sources, vec!["requirements", "standards"]);
-    
-    // Get default personas for a command
-    let default_personas = config_manager.get_default_personas("test-gen");
-    assert_eq!(default_personas, vec!["security-analyst"]);
-    
+    assert_eq!(config_manager.get_default_sources("test-gen"), ["requirements", "standards"]);
+    assert_eq!(config_manager.get_default_personas("test-gen"), ["security-analyst"]);
+
+    unsafe {
+        std::env::remove_var("QITOPS_DEFAULT_SOURCES");
+        std::env::remove_var("QITOPS_DEFAULT_PERSONAS");
+    }
     Ok(())
 }
 
-#[tokio::test]
-async fn test_environment_variables() -> Result<()> {
-    // Create a temporary directory for testing
-    let temp_dir = tempdir()?;
-    let temp_path = temp_dir.path();
-    
-    // Set up environment variables for testing
-    env::set_var("HOME", temp_path.to_str().unwrap());
-    env::set_var("APPDATA", temp_path.to_str().unwrap());
-    
-    // Set environment variables for sources
-    env::set_var("QITOPS_SOURCES", "requirements:requirements:docs/requirements.md:Project requirements,standards:standard:docs/standards.md:Coding standards");
-    
-    // Set environment variables for personas
-    env::set_var("QITOPS_PERSONAS", "security-analyst:Security Analyst:security;vulnerabilities;compliance:Focus on security vulnerabilities and compliance issues.");
-    
-    // Create a source manager
+#[test]
+fn test_sources_and_personas_from_environment() -> Result<()> {
+    let (_guard, _home) = isolated_home();
+    unsafe {
+        std::env::set_var("QITOPS_SOURCES", "requirements:requirements:docs/requirements.md:Project requirements,standards:standard:docs/standards.md:Coding standards,broken");
+        std::env::set_var("QITOPS_PERSONAS", "api-reviewer:API Reviewer:contracts;versioning:Focus on breaking API changes.");
+    }
+
     let source_manager = SourceManager::new()?;
-    
-    // Check that the sources were loaded from environment variables
-    let sources = source_manager.list_sources();
-    assert!(sources.len() >= 2);
-    
-    let requirements_source = source_manager.get_source("requirements");
-    assert!(requirements_source.is_some());
-    
-    let standards_source = source_manager.get_source("standards");
-    assert!(standards_source.is_some());
-    
-    // Create a persona manager
+    let requirements = source_manager.get_source("requirements").expect("source not loaded");
+    assert_eq!(requirements.source_type.to_string(), "requirements");
+    assert_eq!(requirements.path, PathBuf::from("docs/requirements.md"));
+    assert_eq!(requirements.description.as_deref(), Some("Project requirements"));
+    assert_eq!(source_manager.get_source("standards").unwrap().source_type.to_string(), "standard");
+    // Malformed entries are skipped
+    assert_eq!(source_manager.list_sources().len(), 2);
+
+    // Environment personas are added to the defaults
     let persona_manager = PersonaManager::new()?;
-    
-    // Check that the personas were loaded from environment variables
-    let security_analyst = persona_manager.get_persona("security-analyst");
-    assert!(security_analyst.is_some());
-    
+    let reviewer = persona_manager.get_persona("api-reviewer").expect("persona not loaded");
+    assert_eq!((reviewer.name.as_str(), reviewer.focus_areas.clone()), ("API Reviewer", vec!["contracts".to_string(), "versioning".to_string()]));
+    assert_eq!(reviewer.description, "Focus on breaking API changes.");
+    assert!(persona_manager.get_persona("qa-engineer").is_some());
+
+    unsafe {
+        std::env::remove_var("QITOPS_SOURCES");
+        std::env::remove_var("QITOPS_PERSONAS");
+    }
     Ok(())
 }
 
-#[tokio::test]
-async fn test_source_content_for_sources() -> Result<()> {
-    // Create a temporary directory for testing
-    let temp_dir = tempdir()?;
-    let temp_path = temp_dir.path();
-    
-    // Create test source files
-    let requirements_path = temp_path.join("requirements.md");
-    fs::write(&requirements_path, "# Project Requirements\n\n- Requirement 1\n- Requirement 2")?;
-    
-    let standards_path = temp_path.join("standards.md");
-    fs::write(&standards_path, "# Coding Standards\n\n- Standard 1\n- Standard 2")?;
-    
-    // Set up environment variables for testing
-    env::set_var("HOME", temp_path.to_str().unwrap());
-    env::set_var("APPDATA", temp_path.to_str().unwrap());
-    
-    // Create a source manager
+#[test]
+fn test_source_content_for_sources() -> Result<()> {
+    let (_guard, home) = isolated_home();
+    let requirements_path = home.join("requirements.md");
+    fs::write(&requirements_path, "# Project Requirements\n\n- Requirement 1")?;
+    let standards_path = home.join("standards.md");
+    fs::write(&standards_path, "# Coding Standards\n\n- Standard 1")?;
+
     let mut source_manager = SourceManager::new()?;
-    
-    // Add sources
-    let requirements_source = Source::new(
-        "requirements".to_string(),
-        SourceType::Requirements,
-        requirements_path.clone(),
-        Some("Project requirements".to_string()),
-    );
-    
-    let standards_source = Source::new(
-        "standards".to_string(),
-        SourceType::Standard,
-        standards_path.clone(),
-        Some("Coding standards".to_string()),
-    );
-    
-    source_manager.add_source(requirements_source)?;
-    source_manager.add_source(standards_source)?;
-    
-    // Get content for sources
+    source_manager.add_source(Source::new("requirements".to_string(), SourceType::Requirements, requirements_path, None))?;
+    source_manager.add_source(Source::new("standards".to_string(), SourceType::Standard, standards_path, None))?;
+
     let content = source_manager.get_content_for_sources(&["requirements".to_string(), "standards".to_string()])?;
-    
-    // Check that the content contains both sources
     assert!(content.contains("# Source: requirements (requirements)"));
     assert!(content.contains("# Project Requirements"));
     assert!(content.contains("# Source: standards (standard)"));
     assert!(content.contains("# Coding Standards"));
-    
     Ok(())
 }
 
-#[tokio::test]
-async fn test_prompt_for_personas() -> Result<()> {
-    // Create a temporary directory for testing
-    let temp_dir = tempdir()?;
-    let temp_path = temp_dir.path();
-    
-    // Set up environment variables for testing
-    env::set_var("HOME", temp_path.to_str().unwrap());
-    env::set_var("APPDATA", temp_path.to_str().unwrap());
-    
-    // Create a persona manager
-    let mut persona_manager = PersonaManager::new()?;
-    
-    // Add personas
-    let security_analyst = Persona::new(
-        "security-analyst".to_string(),
-        "Security Analyst".to_string(),
-        vec!["security".to_string(), "vulnerabilities".to_string(), "compliance".to_string()],
-        "Focus on security vulnerabilities and compliance issues.".to_string(),
-        None,
-    );
-    
-    let performance_engineer = Persona::new(
-        "performance-engineer".to_string(),
-        "Performance Engineer".to_string(),
-        vec!["performance".to_string(), "optimization".to_string(), "scalability".to_string()],
-        "Focus on performance implications and bottlenecks.".to_string(),
-        None,
-    );
-    
-    persona_manager.add_persona(security_analyst)?;
-    persona_manager.add_persona(performance_engineer)?;
-    
-    // Get prompt for personas
+#[test]
+fn test_prompt_for_personas() -> Result<()> {
+    let (_guard, _home) = isolated_home();
+    let persona_manager = PersonaManager::new()?;
+
     let prompt = persona_manager.get_prompt_for_personas(&["security-analyst".to_string(), "performance-engineer".to_string()])?;
-    
-    // Check that the prompt contains both personas
-    assert!(prompt.contains("Security Analyst"));
+    assert!(prompt.contains("# Persona: Security Analyst"));
     assert!(prompt.contains("security, vulnerabilities, compliance"));
-    assert!(prompt.contains("Performance Engineer"));
+    assert!(prompt.contains("# Persona: Performance Engineer"));
     assert!(prompt.contains("performance, optimization, scalability"));
-    
     Ok(())
 }
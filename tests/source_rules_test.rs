@@ -0,0 +1,46 @@
+use qitops_agent::agent::sources::{apply_rules, AppliedRule, SourceSelection};
+use qitops_agent::config::{QitOpsConfig, SourceRule};
+
+fn files(paths: &[&str]) -> Vec<String> {
+    paths.iter().map(|path| path.to_string()).collect()
+}
+
+#[test]
+fn rules_match_changed_paths_by_glob() {
+    let config: QitOpsConfig = serde_json::from_value(serde_json::json!({
+        "sources": {
+            "rules": [
+                { "path": "payments/**", "sources": ["pci-requirements"] },
+                { "path": "*.sql", "sources": ["schema-guide"] },
+                { "path": "docs/**", "sources": ["style-guide"] }
+            ]
+        }
+    })).unwrap();
+
+    let applied = apply_rules(&config.sources.rules, &files(&["payments/api/refund.rs", "db/migrate.sql", "README.md"])).unwrap();
+
+    // `*` does not cross directories, so the nested migration doesn't match `*.sql`
+    assert_eq!(applied, vec![AppliedRule {
+        path: "payments/**".to_string(),
+        sources: vec!["pci-requirements".to_string()],
+        files: files(&["payments/api/refund.rs"]),
+    }]);
+}
+
+#[test]
+fn rule_sources_are_added_after_explicit_ones_without_duplicates() {
+    let selection = SourceSelection::new(files(&["requirements"]), vec![
+        SourceRule { path: "payments/**".to_string(), sources: files(&["pci-requirements", "requirements"]) },
+        SourceRule { path: "auth/**".to_string(), sources: files(&["security-policy"]) },
+    ]);
+
+    assert_eq!(selection.all_ids(), files(&["requirements", "pci-requirements", "security-policy"]));
+
+    let (sources, applied) = selection.resolve(&files(&["payments/ledger.rs"])).unwrap();
+    assert_eq!(sources, files(&["requirements", "pci-requirements"]));
+    assert_eq!(applied.len(), 1);
+
+    let (sources, applied) = selection.resolve(&files(&["src/main.rs"])).unwrap();
+    assert_eq!(sources, files(&["requirements"]));
+    assert!(applied.is_empty());
+}
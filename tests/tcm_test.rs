@@ -0,0 +1,137 @@
+use qitops_agent::agent::test_gen::TestFormat;
+use qitops_agent::integrations::tcm::{self, TcmTarget, TestCase, TestRailClient, XrayClient, ZephyrClient, parse_test_cases};
+use serde_json::json;
+
+mod common;
+use common::{Reply, scratch};
+
+/// Cases to push: one the target already has, one new and the new one again, worded differently
+fn cases() -> Vec<TestCase> {
+    ["login succeeds!", "Logout clears the session", "logout: clears the session"].iter()
+        .map(|title| TestCase {
+            title: title.to_string(),
+            steps: vec!["Sign in".to_string(), "Click Log out".to_string()],
+            expected: Some("The sign-in page is shown".to_string()),
+            ..Default::default()
+        })
+        .collect()
+}
+
+#[test]
+fn markdown_cases_are_parsed_from_a_fenced_answer() {
+    let text = "```markdown\n# Login tests\n\n## Test Case 1: Valid login\n**Preconditions:** A registered user\n**Steps:**\n1. Open the login page\n2. Sign in\n**Expected Result:** The dashboard is shown\n\n## Test Case 2: Wrong password\n- Sign in with a wrong password\n\nExpected: An error is shown\n```";
+
+    let cases = parse_test_cases(text, TestFormat::Markdown);
+    assert_eq!(cases, [
+        TestCase {
+            title: "Valid login".to_string(),
+            preconditions: Some("A registered user".to_string()),
+            steps: vec!["Open the login page".to_string(), "Sign in".to_string()],
+            expected: Some("The dashboard is shown".to_string()),
+        },
+        TestCase {
+            title: "Wrong password".to_string(),
+            preconditions: None,
+            steps: vec!["Sign in with a wrong password".to_string()],
+            expected: Some("An error is shown".to_string()),
+        },
+    ]);
+}
+
+#[test]
+fn yaml_robot_and_unit_test_cases_are_parsed() {
+    let yaml = "test_cases:\n  - name: Valid login\n    preconditions: A registered user\n    steps:\n      - Open the login page\n      - Sign in\n    expected: The dashboard is shown\n";
+    let cases = parse_test_cases(yaml, TestFormat::Yaml);
+    assert_eq!(cases.len(), 1);
+    assert_eq!(cases[0].title, "Valid login");
+    assert_eq!(cases[0].preconditions.as_deref(), Some("A registered user"));
+    assert_eq!(cases[0].steps, ["Open the login page", "Sign in"]);
+    assert_eq!(cases[0].expected.as_deref(), Some("The dashboard is shown"));
+
+    let robot = "*** Settings ***\nLibrary    Browser\n\n*** Test Cases ***\nValid Login\n    [Setup]    Open Browser    /login\n    Fill Text    id=email    user@example.com\n    Click    text=Sign in\n\nLogout\n    Click    text=Log out\n";
+    let cases = parse_test_cases(robot, TestFormat::Robot);
+    let titles: Vec<&str> = cases.iter().map(|case| case.title.as_str()).collect();
+    assert_eq!(titles, ["Valid Login", "Logout"]);
+    assert_eq!(cases[0].preconditions.as_deref(), Some("Open Browser /login"));
+    assert_eq!(cases[0].steps, ["Fill Text id=email user@example.com", "Click text=Sign in"]);
+
+    let rust = "#[test]\nfn parses_empty_input() {}\n\nfn helper() {}\n\n#[tokio::test]\nasync fn rejects_bad_tokens() {}\n";
+    let titles: Vec<String> = parse_test_cases(rust, TestFormat::RustTest).into_iter().map(|case| case.title).collect();
+    assert_eq!(titles, ["parses empty input", "rejects bad tokens"]);
+
+    let pytest = "def helper():\n    pass\n\ndef test_parses_empty_input():\n    assert parse('') == []\n";
+    let titles: Vec<String> = parse_test_cases(pytest, TestFormat::Pytest).into_iter().map(|case| case.title).collect();
+    assert_eq!(titles, ["parses empty input"]);
+}
+
+#[tokio::test]
+async fn testrail_skips_cases_it_already_has() {
+    unsafe { std::env::set_var("HOME", scratch("tcm-testrail")) };
+    let (base_url, server) = common::serve(vec![
+        Reply::json(json!({ "cases": [{ "id": 1, "title": "Login succeeds" }], "_links": { "next": null } })),
+        Reply::json(json!({ "sections": [] })),
+        Reply::json(json!({ "id": 7 })),
+        Reply::json(json!({ "id": 42 })),
+    ]).await;
+    let client = TestRailClient::new(&base_url, "qa@acme.io".to_string(), "key".to_string());
+    let target: TcmTarget = "testrail:3".parse().unwrap();
+
+    let report = tcm::push(&client, &target, &cases()).await.unwrap();
+    assert_eq!(report.created, [("C42".to_string(), "Logout clears the session".to_string())]);
+    assert_eq!(report.skipped, ["login succeeds!", "logout: clears the session"]);
+
+    let requests = server.await.unwrap();
+    let paths: Vec<&str> = requests.iter().map(|request| request.path.as_str()).collect();
+    assert_eq!(paths, [
+        "/index.php?/api/v2/get_cases/3&limit=250&offset=0",
+        "/index.php?/api/v2/get_sections/3",
+        "/index.php?/api/v2/add_section/3",
+        "/index.php?/api/v2/add_case/7",
+    ]);
+    assert_eq!(requests[3].json()["custom_steps"], "1. Sign in\n2. Click Log out");
+}
+
+#[tokio::test]
+async fn xray_skips_cases_it_already_has() {
+    unsafe { std::env::set_var("HOME", scratch("tcm-xray")) };
+    let (base_url, server) = common::serve(vec![
+        Reply::json(json!("token")),
+        Reply::json(json!({ "data": { "getTests": { "total": 1, "results": [{ "jira": { "key": "QA-1", "summary": "Login succeeds" } }] } } })),
+        Reply::json(json!({ "data": { "createTest": { "test": { "jira": { "key": "QA-2" } }, "warnings": [] } } })),
+    ]).await;
+    let client = XrayClient::new(&base_url, "id".to_string(), "secret".to_string());
+    let target: TcmTarget = "xray:QA-Login".parse().unwrap();
+
+    let report = tcm::push(&client, &target, &cases()).await.unwrap();
+    assert_eq!(report.created, [("QA-2".to_string(), "Logout clears the session".to_string())]);
+    assert_eq!(report.skipped, ["login succeeds!", "logout: clears the session"]);
+
+    let requests = server.await.unwrap();
+    assert_eq!(requests[1].json()["variables"]["folder"]["path"], "/Login");
+    let created = requests[2].json();
+    assert!(requests[2].headers.contains("authorization: bearer token"));
+    assert_eq!(created["variables"]["jira"]["fields"]["summary"], "Logout clears the session");
+    assert_eq!(created["variables"]["steps"][1]["result"], "The sign-in page is shown");
+}
+
+#[tokio::test]
+async fn zephyr_skips_cases_it_already_has() {
+    unsafe { std::env::set_var("HOME", scratch("tcm-zephyr")) };
+    let (base_url, server) = common::serve(vec![
+        Reply::json(json!({ "values": [{ "key": "QA-T1", "name": "Login succeeds" }], "isLast": true })),
+        Reply::json(json!({ "key": "QA-T2" })),
+        Reply::json(json!({})),
+    ]).await;
+    let client = ZephyrClient::new(&base_url, "token".to_string());
+    let target: TcmTarget = "zephyr:QA-12".parse().unwrap();
+
+    let report = tcm::push(&client, &target, &cases()).await.unwrap();
+    assert_eq!(report.created, [("QA-T2".to_string(), "Logout clears the session".to_string())]);
+    assert_eq!(report.skipped, ["login succeeds!", "logout: clears the session"]);
+
+    let requests = server.await.unwrap();
+    let paths: Vec<&str> = requests.iter().map(|request| request.path.as_str()).collect();
+    assert_eq!(paths, ["/testcases?projectKey=QA&folderId=12&maxResults=100&startAt=0", "/testcases", "/testcases/QA-T2/teststeps"]);
+    assert_eq!(requests[1].json()["folderId"], 12);
+    assert_eq!(requests[2].json()["items"][1]["inline"]["expectedResult"], "The sign-in page is shown");
+}
@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+use qitops_agent::error::QitOpsError;
+use qitops_agent::telemetry::{Outcome, Telemetry};
+
+#[test]
+fn telemetry_is_opt_in_and_records_no_content() {
+    let dir = std::env::temp_dir().join(format!("qitops-telemetry-test-{}", std::process::id()));
+    let mut telemetry = Telemetry::at(dir.clone()).unwrap();
+
+    // Off by default
+    telemetry.record("run risk", Duration::from_millis(5), None).unwrap();
+    assert!(telemetry.queued().unwrap().is_empty());
+
+    telemetry.set_enabled(true).unwrap();
+    let error = anyhow::Error::from(QitOpsError::InvalidInput("File not found: secret/payroll.rs".to_string()));
+    telemetry.record("run test-gen", Duration::from_millis(1200), Some(&error)).unwrap();
+
+    let events = telemetry.queued().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].command, "run test-gen");
+    assert_eq!(events[0].outcome, Outcome::Error);
+    assert_eq!(events[0].error_class.as_deref(), Some("invalid_input"));
+    assert!(!std::fs::read_to_string(telemetry.queue_path()).unwrap().contains("payroll"));
+
+    // Settings persist, and opting out deletes the ID and the queue
+    assert!(Telemetry::at(dir.clone()).unwrap().is_opted_in());
+    telemetry.set_enabled(false).unwrap();
+    assert!(telemetry.install_id().is_none());
+    assert!(telemetry.queued().unwrap().is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
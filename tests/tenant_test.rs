@@ -0,0 +1,196 @@
+use std::fs;
+use std::path::Path;
+
+use qitops_agent::bot::{BotConfig, EXEC_DISABLED, confine_command};
+use qitops_agent::bot::server::serve;
+use qitops_agent::bot::tenant::{TenantRegistry, TenantsConfig};
+use qitops_agent::llm::RouterConfig;
+use qitops_agent::secrets;
+
+mod common;
+use common::{Received, Reply, scratch};
+
+/// A tenant answering from its own fake OpenAI server
+fn tenant(id: &str, api_base: &str, max_tokens: u64) -> serde_json::Value {
+    serde_json::json!({
+        "id": id,
+        "api_keys": [format!("key-{}", id)],
+        "llm": {
+            "providers": [{"provider_type": "openai", "api_key": format!("sk-{}", id), "api_base": api_base, "default_model": "gpt-4o-mini"}],
+            "default_provider": "openai",
+        },
+        "github": {"token": format!("ghp-{}", id), "api_base": null, "default_owner": id, "default_repo": null},
+        "budget": {"max_tokens": max_tokens},
+    })
+}
+
+/// Set up tenants in a scratch root, with the keyring off so credentials land in the tenant files
+fn registry(name: &str, tenants: Vec<serde_json::Value>) -> (TenantRegistry, std::path::PathBuf) {
+    static HOME: std::sync::Once = std::sync::Once::new();
+    HOME.call_once(|| unsafe { std::env::set_var("HOME", scratch("tenant-home")) });
+    secrets::disable();
+    let root = scratch(name);
+    let config: TenantsConfig = serde_json::from_value(serde_json::json!({ "tenants": tenants })).unwrap();
+    let registry = TenantRegistry::new(config, &root.join("tenants"), &RouterConfig::default(), &BotConfig::default()).unwrap();
+    (registry, root)
+}
+
+#[cfg(unix)]
+fn mode(path: &Path) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).unwrap().permissions().mode() & 0o777
+}
+
+#[tokio::test]
+async fn tenants_are_answered_with_their_own_llm_and_budget() {
+    let (payments_api, payments_llm) = common::serve(vec![Reply::completion("Run qitops run risk.")]).await;
+    let (search_api, search_llm) = common::serve(vec![Reply::completion("Try --since main."), Reply::completion("Yes.")]).await;
+    let (registry, root) = registry("tenant-budget", vec![tenant("payments", &payments_api, 40), tenant("search", &search_api, 0)]);
+
+    assert_eq!(registry.ids(), ["payments", "search"]);
+    assert!(registry.authenticate("key-payments").is_some_and(|tenant| tenant.id() == "payments"));
+    assert!(registry.authenticate("key-unknown").is_none());
+    assert!(registry.process_message("key-unknown", "ann", "api", "Hi").await.is_err());
+
+    assert_eq!(registry.process_message("key-payments", "ann", "api", "How do I assess risk?").await.unwrap(), "Run qitops run risk.");
+    assert_eq!(registry.process_message("key-search", "bob", "api", "How do I test a branch?").await.unwrap(), "Try --since main.");
+
+    // The completion used 42 tokens, over the payments budget of 40; search has no budget
+    let payments = registry.get("payments").unwrap();
+    assert_eq!(payments.tokens_used(), 42);
+    let refused = registry.process_message("key-payments", "ann", "api", "And for a PR?").await.unwrap();
+    assert!(refused.starts_with("Your team has used its LLM budget for this period."), "{}", refused);
+    assert_eq!(registry.process_message("key-search", "bob", "api", "For every file?").await.unwrap(), "Yes.");
+
+    // Each tenant's requests went to its own provider with its own key
+    let payments_requests: Vec<Received> = payments_llm.await.unwrap();
+    let search_requests = search_llm.await.unwrap();
+    assert_eq!((payments_requests.len(), search_requests.len()), (1, 2));
+    assert!(payments_requests[0].headers.contains("authorization: bearer sk-payments"));
+    assert!(search_requests[1].headers.contains("authorization: bearer sk-search"));
+
+    // Usage survives a restart
+    let usage: serde_json::Value = serde_json::from_str(&fs::read_to_string(payments.dir().join("usage.json")).unwrap()).unwrap();
+    assert_eq!(usage["tokens"], 42);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn tenant_credentials_are_namespaced_and_private() {
+    assert_eq!(secrets::namespaced_account("tenant-payments", secrets::GITHUB_ACCOUNT), "tenant-payments/github");
+
+    let (registry, root) = registry("tenant-files", vec![tenant("payments", "http://127.0.0.1:9", 0)]);
+    let dir = registry.get("payments").unwrap().dir().to_path_buf();
+    let llm = dir.join(".qitops/config.json");
+    let github = dir.join(".config/qitops/github.json");
+
+    // Without a keyring the credentials stay in the files, which only the owner can read
+    assert!(fs::read_to_string(&llm).unwrap().contains("sk-payments"));
+    assert!(fs::read_to_string(&github).unwrap().contains("ghp-payments"));
+    #[cfg(unix)]
+    assert_eq!((mode(&llm), mode(&github)), (0o600, 0o600));
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn paths_are_confined_to_the_tenant_workspace() {
+    let (registry, root) = registry("tenant-workspace", vec![tenant("payments", "http://127.0.0.1:9", 0)]);
+    let tenant = registry.get("payments").unwrap();
+    let workspace = fs::canonicalize(tenant.dir().join("workspace")).unwrap();
+    fs::create_dir_all(workspace.join("src")).unwrap();
+    fs::write(workspace.join("src/lib.rs"), "pub fn add() {}").unwrap();
+
+    assert_eq!(tenant.resolve_path("src/lib.rs").unwrap(), workspace.join("src/lib.rs").to_string_lossy());
+    assert_eq!(tenant.resolve_path("./src/**/*.rs").unwrap(), workspace.join("./src/**/*.rs").to_string_lossy());
+    assert!(tenant.resolve_path("src/new.rs").is_ok());
+
+    for outside in ["/etc/passwd", "../search/usage.json", "src/../../usage.json"] {
+        let error = tenant.resolve_path(outside).unwrap_err().to_string();
+        assert!(error.starts_with("Path must be relative to the tenant workspace"), "{}: {}", outside, error);
+    }
+
+    // Symlinks can't lead out either
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(root.join("tenants"), workspace.join("escape")).unwrap();
+        for outside in ["escape/search/.qitops/config.json", "escape/**/*.json"] {
+            let error = tenant.resolve_path(outside).unwrap_err().to_string();
+            assert!(error.starts_with("Path leads out of the tenant workspace"), "{}: {}", outside, error);
+        }
+    }
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn tenant_commands_stay_in_the_workspace_and_count_against_the_budget() {
+    let (registry, root) = registry("tenant-commands", vec![tenant("ops", "http://127.0.0.1:9", 100)]);
+    let tenant = registry.get("ops").unwrap();
+    let workspace = fs::canonicalize(tenant.dir().join("workspace")).unwrap();
+
+    // Tenants can't run commands themselves, and the model's commands only see the workspace
+    let reply = registry.process_message("key-ops", "ann", "api", "!exec run test-gen --path /etc/passwd").await.unwrap();
+    assert_eq!(reply, EXEC_DISABLED);
+    assert_eq!(
+        confine_command("run test-gen --path=src/lib.rs", &workspace).unwrap(),
+        format!("run test-gen '--path={}'", workspace.join("src/lib.rs").display()),
+    );
+    for command in ["run test-gen --path /etc/passwd", "run risk -d ../search/.qitops/config.json", "run pr-analyze --patch=../x.patch"] {
+        assert!(confine_command(command, &workspace).is_err(), "{}", command);
+    }
+
+    // Commands record their LLM usage in the tenant's home, which counts against its budget
+    let ledger = tenant.dir().join(".qitops/usage.json");
+    let entry = |tokens: u64| serde_json::json!({
+        "day": "2026-01-01", "provider": "openai", "model": "gpt-4o-mini",
+        "requests": 1, "input_tokens": tokens, "output_tokens": 10, "cost_usd": 0.0,
+    });
+    fs::write(&ledger, serde_json::json!([entry(50)]).to_string()).unwrap();
+    tenant.record_usage().unwrap();
+    assert_eq!(tenant.tokens_used(), 60);
+    assert!(tenant.check_budget().is_ok());
+
+    fs::write(&ledger, serde_json::json!([entry(50), entry(40)]).to_string()).unwrap();
+    tenant.record_usage().unwrap();
+    tenant.record_usage().unwrap();
+    assert_eq!(tenant.tokens_used(), 110);
+    assert!(tenant.check_budget().is_err());
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn the_bot_server_answers_each_tenant_by_api_key() {
+    let (api_base, _llm) = common::serve(vec![Reply::completion("Run qitops run risk.")]).await;
+    let (registry, root) = registry("tenant-server", vec![tenant("payments", &api_base, 0)]);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(serve(listener, registry));
+
+    let client = reqwest::Client::new();
+    let message = serde_json::json!({"user": "ann", "message": "How do I assess risk?"});
+    for key in [None, Some("key-unknown")] {
+        let mut request = client.post(format!("{}/v1/messages", address)).json(&message);
+        if let Some(key) = key {
+            request = request.bearer_auth(key);
+        }
+        assert_eq!(request.send().await.unwrap().status().as_u16(), 401);
+    }
+
+    let reply: serde_json::Value = client.post(format!("{}/v1/messages", address)).bearer_auth("key-payments").json(&message)
+        .send().await.unwrap()
+        .json().await.unwrap();
+    assert_eq!(reply["reply"], "Run qitops run risk.");
+
+    let quotas: serde_json::Value = client.get(format!("{}/v1/quotas", address)).bearer_auth("key-payments")
+        .send().await.unwrap()
+        .json().await.unwrap();
+    assert_eq!(quotas["accepted"], 1);
+    let response = client.get(format!("{}/v1/quotas", address)).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+
+    fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,94 @@
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use qitops_agent::agent::conventions::{TestSamples, is_test_file, project_root};
+use qitops_agent::agent::test_gen::TestGenAgent;
+use qitops_agent::agent::traits::Agent;
+use qitops_agent::scan::ScanLimits;
+
+mod common;
+use common::Reply;
+
+/// A scratch repository with a source file and some existing tests
+fn project(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("qitops-conventions-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    for (file, content) in [
+        (".git/HEAD", "ref: refs/heads/main\n"),
+        ("src/auth/login.py", "def login(user, password):\n    return user == 'admin'\n"),
+        ("tests/test_login.py", "import pytest\n\n@pytest.fixture\ndef admin():\n    return 'admin'\n\ndef test_login_accepts_admin(admin):\n    assert login(admin, 'x')\n"),
+        ("tests/test_billing.py", "def test_billing_totals():\n    assert total([]) == 0\n"),
+        ("tests/unit/test_misc.py", "def test_misc():\n    assert True\n"),
+        ("src/auth/helpers_test.py", "def test_helpers():\n    assert True\n"),
+        ("node_modules/lib/test_vendored.py", "def test_vendored():\n    assert True\n"),
+        ("tests/README.md", "How to run the tests\n"),
+    ] {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    dir
+}
+
+/// Serve one chat completion, returning the prompt that was sent
+async fn serve_once() -> (String, tokio::task::JoinHandle<String>) {
+    let (address, server) = common::serve_once(Reply::completion("## Test Case 1")).await;
+    (address, tokio::spawn(async move { server.await.unwrap().messages() }))
+}
+
+/// Generate tests for the project's login module, returning the prompt and the agent's data
+async fn generate(dir: &Path, conventions: Option<String>) -> Result<(String, serde_json::Value)> {
+    let (api_base, server) = serve_once().await;
+    let router = common::router(&api_base);
+
+    let source = dir.join("src/auth/login.py").to_string_lossy().to_string();
+    let response = TestGenAgent::new(source, "markdown", None, None, router).await?
+        .with_conventions(conventions)
+        .execute()
+        .await?;
+
+    Ok((server.await?, response.data.unwrap_or_default()))
+}
+
+#[test]
+fn test_samples_prefer_tests_of_the_same_module() -> Result<()> {
+    let dir = project("samples");
+    let root = project_root(&dir.join("src/auth"));
+    assert_eq!(root, fs::canonicalize(&dir)?);
+
+    assert!(is_test_file(&root, &root.join("tests/test_billing.py")));
+    assert!(is_test_file(&root, &root.join("src/auth/helpers_test.py")));
+    assert!(!is_test_file(&root, &root.join("src/auth/login.py")));
+
+    let samples = TestSamples::scan(&root, &ScanLimits::default(), vec!["py".to_string()])?;
+    let picked = samples.for_source(&dir.join("src/auth/login.py"));
+    let names: Vec<String> = picked.iter().map(|path| path.file_name().unwrap().to_string_lossy().to_string()).collect();
+
+    // Same module first, then the closest tests; vendored tests and other languages are left out
+    assert_eq!(names, vec!["test_login.py", "helpers_test.py", "test_billing.py"]);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_gen_follows_existing_tests_or_a_style_guide() -> Result<()> {
+    let dir = project("prompt");
+
+    let (prompt, data) = generate(&dir, None).await?;
+    assert!(prompt.contains("Match their conventions"), "{}", prompt);
+    assert!(prompt.contains("def test_login_accepts_admin(admin):"));
+    assert!(!prompt.contains("test_vendored"));
+    assert_eq!(data["conventions"]["samples"].as_array().map(Vec::len), Some(3));
+
+    let guide = dir.join("TESTING.md");
+    fs::write(&guide, "Name tests should_<behavior>_when_<condition> and use AssertJ-style assertions.\n")?;
+    let (prompt, data) = generate(&dir, Some(guide.to_string_lossy().to_string())).await?;
+    assert!(prompt.contains("should_<behavior>_when_<condition>"), "{}", prompt);
+    assert!(!prompt.contains("test_login_accepts_admin"));
+    assert_eq!(data["conventions"]["guide"], guide.to_string_lossy().as_ref());
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use qitops_agent::agent::test_gen::{self, FileResult, TestGenAgent};
+use qitops_agent::agent::traits::Agent;
+use qitops_agent::llm::RouterConfig;
+
+mod common;
+use common::{Received, Reply, scratch};
+
+const TEST_CASES: &str = "## Test Case 1: Adds two numbers\n\nSteps...\n\n## Test Case 2: Overflows\n\nSteps...\n";
+
+/// Serve `count` chat completions, returning the prompts that were sent
+async fn serve(count: usize) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+    let (address, server) = common::serve(vec![Reply::completion(TEST_CASES); count]).await;
+    (address, tokio::spawn(async move { server.await.unwrap().iter().map(Received::prompt).collect() }))
+}
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[tokio::test]
+async fn glob_input_generates_tests_per_file_into_the_output_directory() {
+    let dir = scratch("batch");
+    write(&dir.join("src/math/add.rs"), "pub fn add(a: u8, b: u8) -> u8 { a + b }\n");
+    write(&dir.join("src/text/upper.rs"), "pub fn upper(s: &str) -> String { s.to_uppercase() }\n");
+    write(&dir.join("src/text/upper_test.rs"), "#[test]\nfn upper_works() {}\n");
+    write(&dir.join("src/notes.py"), "def notes():\n    pass\n");
+
+    let (api_base, server) = serve(2).await;
+    let router = common::router_with(&api_base, RouterConfig { batch_concurrency: 2, ..RouterConfig::default() });
+
+    let pattern = format!("{}/src/**/*.rs", dir.display());
+    let out = dir.join("generated");
+    let agent = TestGenAgent::new(pattern, "markdown", None, None, router).await.unwrap()
+        .with_output_dir(Some(out.clone()));
+    let response = agent.execute().await.unwrap();
+    let prompts = server.await.unwrap();
+    let data = response.data.unwrap();
+
+    // Existing tests and files the glob doesn't match are left out
+    assert_eq!(prompts.len(), 2);
+    assert!(prompts.iter().any(|prompt| prompt.contains("pub fn add")) && prompts.iter().any(|prompt| prompt.contains("pub fn upper")));
+    assert!(prompts.iter().all(|prompt| !prompt.contains("def notes")));
+    assert!(response.message.starts_with("Generated test cases for 2 file(s)"), "{}", response.message);
+
+    // Each file's tests mirror the source layout under the output directory, not next to the sources
+    let results: Vec<FileResult> = serde_json::from_value(data["results"].clone()).unwrap();
+    let written: Vec<(PathBuf, Option<usize>)> = results.iter()
+        .map(|result| (PathBuf::from(result.output_file.as_ref().unwrap()), result.tests))
+        .collect();
+    assert_eq!(written, [
+        (out.join("math").join("test_add.rs.md"), Some(2)),
+        (out.join("text").join("test_upper.rs.md"), Some(2)),
+    ]);
+    assert_eq!(fs::read_to_string(out.join("math").join("test_add.rs.md")).unwrap(), TEST_CASES);
+    assert!(!dir.join("src/math/tests").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn globs_are_recognized_and_results_tabulated() {
+    assert!(test_gen::is_glob("src/**/*.rs"));
+    assert!(test_gen::is_glob("src/{api,db}/mod.rs"));
+    assert!(!test_gen::is_glob("src/main.rs"));
+    assert_eq!(test_gen::glob_base("src/**/*.rs"), Path::new("src"));
+    assert_eq!(test_gen::glob_base("crates/core/src/*.rs"), Path::new("crates/core/src"));
+    assert_eq!(test_gen::glob_base("**/*.py"), Path::new("."));
+
+    let table = test_gen::results_table(&[
+        FileResult { file: "src/lib.rs".to_string(), output_file: Some("out/test_lib.rs.md".to_string()), tests: Some(4), error: None },
+        FileResult { file: "src/api/handlers.rs".to_string(), output_file: None, tests: None, error: Some("Rate limit reached".to_string()) },
+        FileResult { file: "src/db.rs".to_string(), output_file: Some("out/test_db.rs.md".to_string()), tests: None, error: None },
+    ]);
+    assert_eq!(table, "\
+File                 Tests  Output
+src/lib.rs               4  out/test_lib.rs.md
+src/api/handlers.rs      -  failed: Rate limit reached
+src/db.rs                -  out/test_db.rs.md
+");
+}
@@ -0,0 +1,141 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use qitops_agent::agent::changes::{self, ChangedFile};
+use qitops_agent::agent::test_gen::TestGenAgent;
+use qitops_agent::agent::traits::Agent;
+
+mod common;
+use common::{Received, Reply, scratch};
+
+const TEST_CASES: &str = "## Test Case 1: Adds two numbers\n\nSteps...\n\n## Test Case 2: Overflows\n\nSteps...\n";
+
+/// Serve `count` chat completions, returning the prompts that were sent
+async fn serve(count: usize) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+    let (address, server) = common::serve(vec![Reply::completion(TEST_CASES); count]).await;
+    (address, tokio::spawn(async move { server.await.unwrap().iter().map(Received::prompt).collect() }))
+}
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(["-c", "user.name=QitOps", "-c", "user.email=qitops@example.com", "-c", "commit.gpgsign=false"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+const MATH: &str = "\
+pub fn add(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+pub fn sub(a: u8, b: u8) -> u8 {
+    a - b
+}
+";
+
+#[tokio::test]
+async fn only_files_changed_since_the_ref_are_generated_focused_on_their_functions() {
+    let dir = scratch("repo");
+    git(&dir, &["init", "-q"]);
+    write(&dir.join("src/math.rs"), MATH);
+    write(&dir.join("src/text.rs"), "pub fn upper(s: &str) -> String {\n    s.to_uppercase()\n}\n");
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "Initial"]);
+
+    // An uncommitted change to one function, and a new file
+    write(&dir.join("src/math.rs"), &MATH.replace("a - b", "a.saturating_sub(b)"));
+    write(&dir.join("src/div.rs"), "pub fn div(a: u8, b: u8) -> u8 {\n    a / b\n}\n");
+    git(&dir, &["add", "src/div.rs"]);
+
+    let changed = changes::changed_since(&dir.join("src"), "HEAD").unwrap();
+    let summary: Vec<(String, bool, Vec<String>)> = changed.iter()
+        .map(|file| (file.path.clone(), file.added, file.functions.clone()))
+        .collect();
+    assert_eq!(summary, [
+        (dir.join("src/div.rs").to_string_lossy().to_string(), true, vec!["div".to_string()]),
+        (dir.join("src/math.rs").to_string_lossy().to_string(), false, vec!["sub".to_string()]),
+    ]);
+
+    let (api_base, server) = serve(2).await;
+    let router = common::router(&api_base);
+
+    let out = dir.join("generated");
+    let agent = TestGenAgent::new(dir.join("src").to_string_lossy().to_string(), "markdown", None, None, router).await.unwrap()
+        .with_output_dir(Some(out.clone()))
+        .with_since(Some("HEAD".to_string()));
+    let response = agent.execute().await.unwrap();
+    let mut prompts = server.await.unwrap();
+    prompts.sort_by_key(|prompt| prompt.contains("pub fn add"));
+    let data = response.data.unwrap();
+
+    // The unchanged file is skipped, the new one is tested whole
+    assert_eq!(prompts.len(), 2);
+    assert!(prompts.iter().all(|prompt| !prompt.contains("pub fn upper")));
+    assert!(prompts[0].contains("pub fn div") && !prompts[0].contains("Only generate test cases"));
+    assert!(prompts[1].contains("Only generate test cases for the code changed since `HEAD`: the function(s) `sub`."), "{}", prompts[1]);
+    assert_eq!(data["since"], "HEAD");
+    assert_eq!(data["changed"].as_array().unwrap().len(), 2);
+    assert!(out.join("test_math.rs.md").exists());
+    assert!(!out.join("test_text.rs.md").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn changed_lines_are_attributed_to_their_enclosing_functions() {
+    let rust = "\
+pub struct Config {
+    pub retries: u32,
+}
+
+impl Config {
+    pub fn backoff(
+        &self,
+        attempt: u32,
+    ) -> u64 {
+        let base = 100;
+        base * u64::from(attempt)
+    }
+}
+";
+    // A line in the body and a line of a multi-line signature
+    assert_eq!(changes::enclosing_functions(rust, &[11, 8]), ["backoff"]);
+    // A struct field is in no function
+    assert!(changes::enclosing_functions(rust, &[2]).is_empty());
+    // The closing brace belongs to the function it closes
+    assert_eq!(changes::enclosing_functions(rust, &[12]), ["backoff"]);
+
+    let python = "\
+import os
+
+class Cache:
+    size = 10
+
+    def get(self, key):
+        return os.environ.get(key)
+
+def clear():
+    pass
+";
+    assert_eq!(changes::enclosing_functions(python, &[10, 7, 1, 4]), ["get", "clear"]);
+    assert_eq!(changes::enclosing_functions(python, &[6]), ["get"]);
+
+    let go = "func (s *Server) Start() error {\n\treturn nil\n}\n";
+    assert_eq!(changes::enclosing_functions(go, &[2]), ["Start"]);
+
+    let file = ChangedFile { path: "/repo/src/lib.rs".to_string(), added: false, lines: vec![3, 4, 5, 9], functions: Vec::new() };
+    assert_eq!(file.focus("main").unwrap(), "Only generate test cases for the code changed since `main` (lines 3-5, 9); use the rest of the code as context.");
+    let file = ChangedFile { functions: vec!["parse".to_string(), "render".to_string()], ..file };
+    assert_eq!(file.focus("main").unwrap(), "Only generate test cases for the code changed since `main`: the function(s) `parse`, `render`. Use the rest of the code as context.");
+    assert_eq!(ChangedFile { added: true, ..file }.focus("main"), None);
+}
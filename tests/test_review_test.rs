@@ -0,0 +1,34 @@
+use std::fs;
+
+use qitops_agent::agent::traits::Agent;
+use qitops_agent::agent::test_review::TestReviewAgent;
+
+mod common;
+use common::{Reply, scratch};
+
+#[tokio::test]
+async fn copies_of_a_test_file_are_reviewed_once() {
+    let dir = scratch("test-review-copies");
+    fs::create_dir_all(dir.join("tests/fixtures")).unwrap();
+    fs::write(dir.join("tests/login_test.py"), "def test_login():\n    time.sleep(1)\n    assert login()\n").unwrap();
+    fs::write(dir.join("tests/fixtures/login_test.py"), "def test_login():\n    time.sleep(1)\n    assert login()\n").unwrap();
+    fs::write(dir.join("tests/logout_test.py"), "def test_logout():\n    assert logout()\n").unwrap();
+
+    let (api_base, server) = common::serve_once(Reply::completion("Score: 6/10")).await;
+    let router = common::router(&api_base);
+
+    let agent = TestReviewAgent::new(dir.join("tests").to_string_lossy().to_string(), None, None, router).await.unwrap();
+    let response = agent.execute().await.unwrap();
+    let data = response.data.unwrap();
+    let prompt = server.await.unwrap().prompt();
+
+    assert_eq!(response.message, "Reviewed 2 test file(s)");
+    assert_eq!(data["files_duplicate"], serde_json::json!([{
+        "file": dir.join("tests/login_test.py").to_string_lossy(),
+        "same_as": dir.join("tests/fixtures/login_test.py").to_string_lossy(),
+    }]));
+    assert_eq!(prompt.matches("def test_login()").count(), 1);
+    assert_eq!(data["smells"].as_array().unwrap().len(), 1);
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,65 @@
+use serde_json::json;
+
+use qitops_agent::bot::{tool_command, RUN_COMMAND_TOOL};
+use qitops_agent::llm::client::ToolCall;
+use qitops_agent::llm::providers::{anthropic_tool_calls, openai_tool_calls};
+
+#[test]
+fn provider_tool_calls_are_parsed() {
+    let openai = json!({
+        "content": null,
+        "tool_calls": [{
+            "id": "call_1",
+            "type": "function",
+            "function": { "name": RUN_COMMAND_TOOL, "arguments": "{\"args\":[\"version\"]}" }
+        }]
+    });
+    let calls = openai_tool_calls(&openai);
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].arguments, json!({ "args": ["version"] }));
+
+    let anthropic = vec![
+        json!({ "type": "text", "text": "Running it now." }),
+        json!({ "type": "tool_use", "id": "toolu_1", "name": RUN_COMMAND_TOOL, "input": { "args": ["version"] } }),
+    ];
+    assert_eq!(anthropic_tool_calls(&anthropic), calls.into_iter().map(|call| ToolCall { id: "toolu_1".to_string(), ..call }).collect::<Vec<_>>());
+}
+
+#[test]
+fn bot_only_runs_allowed_commands() {
+    let call = |args: serde_json::Value| ToolCall {
+        id: "call_1".to_string(),
+        name: RUN_COMMAND_TOOL.to_string(),
+        arguments: json!({ "args": args }),
+    };
+
+    assert_eq!(tool_command(&call(json!(["run", "test-gen", "--path", "src/my file.rs"]))).unwrap(), "run test-gen --path 'src/my file.rs'");
+    assert_eq!(tool_command(&call(json!(["run", "risk", "--diff=changes.diff", "-f", "security"]))).unwrap(), "run risk '--diff=changes.diff' -f security");
+    assert_eq!(tool_command(&call(json!(["source", "list"]))).unwrap(), "source list");
+    assert!(tool_command(&call(json!(["publish", "report.md"]))).is_err());
+    assert!(tool_command(&call(json!([]))).is_err());
+
+    // Subcommands and options that change configuration or post externally are refused
+    for args in [
+        json!(["github", "config", "--token", "ghp_x"]),
+        json!(["github", "undo"]),
+        json!(["llm", "add", "--provider", "openai"]),
+        json!(["llm", "remove", "--provider", "openai"]),
+        json!(["approvals", "approve", "a1b2c3d4"]),
+        json!(["run"]),
+    ] {
+        let error = tool_command(&call(args.clone())).unwrap_err().to_string();
+        assert!(error.starts_with("The bot may not run `qitops"), "{}: {}", args, error);
+    }
+    for (args, option) in [
+        (json!(["run", "pr-analyze", "--pr", "12", "--comment"]), "--comment"),
+        (json!(["run", "pr-analyze", "--pr", "12", "--review"]), "--review"),
+        (json!(["run", "risk", "--diff", "changes.diff", "--jira"]), "--jira"),
+        (json!(["run", "test-gen", "--path", "src", "--push-to=testrail"]), "--push-to"),
+        (json!(["run", "test-gen", "--path", "src", "--output", "/etc/cron.d/x"]), "--output"),
+        (json!(["run", "risk", "--diff", "changes.diff", "--baseline", "update"]), "--baseline"),
+    ] {
+        let error = tool_command(&call(args)).unwrap_err().to_string();
+        assert_eq!(error, format!("The bot may not pass `{}`; use !exec to run the command yourself", option));
+    }
+}
@@ -0,0 +1,116 @@
+use qitops_agent::history::HistoryConfig;
+use qitops_agent::output::Report;
+use qitops_agent::runs::RunHistory;
+use qitops_agent::runs::triage::TriageSession;
+
+mod common;
+use common::{Received, Reply};
+
+const DIFF: &str = "diff --git a/src/users.py b/src/users.py
+--- a/src/users.py
++++ b/src/users.py
+@@ -10,3 +10,3 @@ def find_user(name):
+     cursor = db.cursor()
+-    cursor.execute(\"SELECT * FROM users WHERE name = %s\", (name,))
++    cursor.execute(\"SELECT * FROM users WHERE name = '\" + name + \"'\")
+     return cursor.fetchone()
+@@ -40,2 +40,3 @@ def list_users():
+     return db.all()
++
+diff --git a/README.md b/README.md
+--- a/README.md
++++ b/README.md
+@@ -1,1 +1,1 @@
+-# Users
++# User service
+";
+
+/// A risk assessment report, failing its gate or not
+fn report(failed: bool) -> Report {
+    serde_json::from_value(serde_json::json!({
+        "command": "risk",
+        "title": "Risk Assessment: change.diff",
+        "summary": "Risk assessment completed",
+        "body": "# Risk Assessment\n\nThe change builds SQL from user input.",
+        "findings": if failed {
+            serde_json::json!([{"file": "src/users.py", "line": 11, "severity": "high", "title": "SQL injection in find_user", "description": "The name is concatenated into the query."}])
+        } else {
+            serde_json::json!([])
+        },
+        "gate": if failed { "fail" } else { "pass" },
+        "data": {
+            "risk": {"overall_risk": if failed { "High" } else { "Low" }, "summary": "Query built from user input"},
+            "diff": DIFF,
+        },
+    })).unwrap()
+}
+
+/// Serve a chat completion per answer, returning the request bodies
+async fn serve(answers: Vec<&'static str>) -> (String, tokio::task::JoinHandle<Vec<serde_json::Value>>) {
+    let (address, server) = common::serve(answers.into_iter().map(Reply::completion).collect()).await;
+    (address, tokio::spawn(async move { server.await.unwrap().iter().map(Received::json).collect() }))
+}
+
+#[test]
+fn triage_opens_the_latest_failed_run_and_shows_its_hunks() {
+    let dir = std::env::temp_dir().join(format!("qitops-triage-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let history = RunHistory::at(dir.clone(), HistoryConfig { encrypt: false });
+    assert!(history.latest_failed().unwrap_err().to_string().contains("No failed run in the history"));
+
+    history.record("run-failed", &report(true)).unwrap();
+    history.record("run-passed", &report(false)).unwrap();
+    let record = history.latest_failed().unwrap();
+    assert_eq!(record.id, "run-failed");
+
+    let session = TriageSession::new(record, "");
+    assert!(session.summary().contains("Gate: fail\nOverall risk: High\nFindings: 1"), "{}", session.summary());
+    assert_eq!(session.command("/findings").unwrap(), "1. [high] SQL injection in find_user (src/users.py:11)");
+
+    // The hunk of a finding, of a file line, or every hunk of a file
+    let hunk = session.command("/hunk 1").unwrap();
+    assert!(hunk.starts_with("--- src/users.py\n@@ -10,3 +10,3 @@ def find_user(name):\n"), "{}", hunk);
+    assert!(hunk.contains("+    cursor.execute(\"SELECT * FROM users WHERE name = '\" + name + \"'\")"));
+    assert!(!hunk.contains("list_users"));
+    assert_eq!(session.command("/hunk users.py:11").unwrap(), hunk);
+    assert_eq!(session.hunk("src/users.py").unwrap().matches("--- src/users.py").count(), 2);
+
+    assert_eq!(session.command("/hunk 2").unwrap(), "No finding 2; the run has 1");
+    assert_eq!(session.command("/hunk src/users.py:30").unwrap(), "No hunk of the diff covers src/users.py:30");
+    assert!(session.command("/rerun").unwrap().starts_with("Unknown command /rerun"));
+    assert!(session.command("why was risk high?").is_none());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn questions_are_answered_from_the_stored_artifacts() {
+    let (api_base, server) = serve(vec!["The query concatenates the name (finding 1).", "Use a parameterized query."]).await;
+    let router = common::router(&api_base);
+
+    let record: qitops_agent::runs::RunRecord = serde_json::from_value(serde_json::json!({
+        "id": "run-failed",
+        "recorded_at": 1_700_000_000,
+        "report": report(true),
+    })).unwrap();
+    let mut session = TriageSession::new(record, "Branch: feature/users\nHEAD: abc1234 Search users by name");
+
+    assert_eq!(session.ask(&router, "Why was risk high?").await.unwrap(), "The query concatenates the name (finding 1).");
+    assert_eq!(session.ask(&router, "How do I fix it?").await.unwrap(), "Use a parameterized query.");
+
+    let bodies = server.await.unwrap();
+    let system = bodies[0]["messages"][0]["content"].as_str().unwrap();
+    assert!(system.contains("triage a failed QitOps run"), "{}", system);
+    assert!(system.contains("1. [high] SQL injection in find_user (src/users.py:11)"));
+    assert!(system.contains("Finding 1: The name is concatenated into the query."));
+    assert!(system.contains("\"overall_risk\": \"High\""));
+    assert!(system.contains("Changed files: src/users.py, README.md"));
+    assert!(system.contains("@@ -10,3 +10,3 @@ def find_user(name):"));
+    // Only the flagged hunk is included
+    assert!(!system.contains("# User service"));
+    assert!(system.contains("Branch: feature/users"));
+
+    let last = |body: &serde_json::Value| body["messages"].as_array().unwrap().last().unwrap()["content"].as_str().unwrap().to_string();
+    assert_eq!(last(&bodies[0]), "User: Why was risk high?");
+    assert_eq!(last(&bodies[1]), "User: Why was risk high?\nTriage: The query concatenates the name (finding 1).\nUser: How do I fix it?");
+}
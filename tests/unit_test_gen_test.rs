@@ -0,0 +1,93 @@
+use std::fs;
+use std::path::Path;
+
+use qitops_agent::agent::test_gen::{TestFormat, TestGenAgent};
+use qitops_agent::agent::traits::Agent;
+use qitops_agent::context::{RepositoryContext, SourceLanguage, TestPlacement};
+use qitops_agent::integrations::tcm::parse_test_cases;
+
+mod common;
+use common::{Reply, scratch};
+
+/// Serve one chat completion with `answer`, returning the request body
+async fn serve_once(answer: &'static str) -> (String, tokio::task::JoinHandle<serde_json::Value>) {
+    let (address, server) = common::serve_once(Reply::completion(answer)).await;
+    (address, tokio::spawn(async move { server.await.unwrap().json() }))
+}
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn rust_tests_import_public_modules_and_test_private_ones_inline() {
+    let dir = scratch("rust");
+    write(&dir.join("Cargo.toml"), "[package]\nname = \"billing-core\"\nversion = \"0.1.0\"\n");
+    write(&dir.join("src/lib.rs"), "pub mod billing;\nmod internal;\n");
+    write(&dir.join("src/billing/mod.rs"), "pub mod invoice;\n");
+    let invoice = "pub struct Invoice {\n    pub lines: Vec<u64>,\n}\n\npub fn total(invoice: &Invoice) -> u64 {\n    invoice.lines.iter().map(|line| round(*line)).sum()\n}\n\nfn round(amount: u64) -> u64 {\n    amount\n}\n";
+    write(&dir.join("src/billing/invoice.rs"), invoice);
+    write(&dir.join("src/internal.rs"), invoice);
+
+    let context = RepositoryContext::for_file(&dir.join("src/billing/invoice.rs"), invoice, SourceLanguage::Rust, None).unwrap();
+    assert_eq!(context.placement, TestPlacement::External);
+    assert_eq!(context.module, "billing_core::billing::invoice");
+    assert_eq!(context.imports, ["use billing_core::billing::invoice::{Invoice, total};"]);
+    assert_eq!(context.test_path, dir.join("tests").join("billing_invoice_test.rs"));
+    let prompt = context.prompt();
+    assert!(prompt.contains("Rust integration test file (`tests/billing_invoice_test.rs`) of the `billing_core` crate"), "{}", prompt);
+    assert!(prompt.contains("The module defines: struct Invoice, fn total."));
+    assert!(!prompt.contains("fn round"));
+
+    // A private module is tested from inside, where its private functions are in scope too
+    let context = RepositoryContext::for_file(&dir.join("src/internal.rs"), invoice, SourceLanguage::Rust, Some("total")).unwrap();
+    assert_eq!(context.imports, ["use super::*;"]);
+    assert_eq!(context.test_path, dir.join("src").join("internal_total_tests.rs"));
+    assert_eq!(context.placement, TestPlacement::Inline { declaration: "#[cfg(test)]\n#[path = \"internal_total_tests.rs\"]\nmod tests;".to_string() });
+    assert!(context.prompt().contains("fn round"));
+
+    let error = RepositoryContext::for_file(&dir.join("Cargo.toml"), "", SourceLanguage::Rust, None).unwrap_err();
+    assert!(error.to_string().contains("Rust tests can only be generated for .rs files"), "{}", error);
+
+    let tests = "use super::*;\n\n#[test]\nfn total_of_no_lines_is_zero() {}\n\n#[tokio::test]\nasync fn rounds_each_line() {}\n\nfn helper() {}\n";
+    let titles: Vec<String> = parse_test_cases(tests, TestFormat::from_str("rust-test").unwrap()).into_iter().map(|case| case.title).collect();
+    assert_eq!(titles, ["total of no lines is zero", "rounds each line"]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn pytest_files_import_the_module_by_its_package_path() {
+    let dir = scratch("pytest");
+    write(&dir.join("pyproject.toml"), "[project]\nname = \"app\"\n");
+    write(&dir.join("src/app/__init__.py"), "");
+    let users = "class User:\n    pass\n\n\ndef find_user(name):\n    return User()\n\n\ndef _cache_key(name):\n    return name\n";
+    let page = dir.join("src/app/users.py");
+    write(&page, users);
+
+    let tests = "from app.users import User, find_user\n\n\ndef make_user():\n    return User()\n\n\ndef test_find_user_returns_a_user():\n    assert isinstance(find_user(\"ada\"), User)\n";
+    let (api_base, server) = serve_once("```python\nfrom app.users import User, find_user\n\n\ndef make_user():\n    return User()\n\n\ndef test_find_user_returns_a_user():\n    assert isinstance(find_user(\"ada\"), User)\n```").await;
+    let router = common::router(&api_base);
+
+    let agent = TestGenAgent::new(page.to_string_lossy().to_string(), "pytest", None, None, router).await.unwrap();
+    let response = agent.execute().await.unwrap();
+    let body = server.await.unwrap();
+    let data = response.data.unwrap();
+
+    let prompt = body["messages"].as_array().unwrap().last().unwrap()["content"].as_str().unwrap();
+    assert!(prompt.contains("pytest file (`tests/test_users.py`)"), "{}", prompt);
+    assert!(prompt.contains("# src must be on the import path (e.g. `pythonpath = [\"src\"]` in the pytest config)\nfrom app.users import User, find_user"));
+    assert!(prompt.contains("The module defines: class User, def find_user."));
+
+    let output = Path::new(data["output_file"].as_str().unwrap());
+    assert_eq!(output, dir.join("tests").join("test_users.py"));
+    assert_eq!(fs::read_to_string(output).unwrap(), tests);
+    assert_eq!(data["repository"]["module"], "app.users");
+    assert_eq!(data["repository"]["placement"]["kind"], "external");
+
+    let titles: Vec<String> = parse_test_cases(tests, TestFormat::Pytest).into_iter().map(|case| case.title).collect();
+    assert_eq!(titles, ["find user returns a user"]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,37 @@
+use qitops_agent::llm::cost::{self, UsageLedger};
+
+#[test]
+fn ledger_breaks_usage_down_by_command_and_provider() {
+    let path = std::env::temp_dir().join(format!("qitops-usage-test-{}.json", std::process::id()));
+    let ledger = UsageLedger::open(&path);
+
+    cost::set_command("run risk");
+    ledger.record("openai", "gpt-4o-mini", 1000, 200, 0.01).unwrap();
+    ledger.record("openai", "gpt-4o-mini", 500, 100, 0.005).unwrap();
+    ledger.record("ollama", "mistral", 800, 300, 0.0).unwrap();
+
+    let entries = ledger.since(&cost::since_day("7d").unwrap()).unwrap();
+    assert!(entries.iter().all(|e| e.command == "run risk"));
+
+    let by_command = cost::totals_by(&entries, |e| e.command.clone());
+    assert_eq!(by_command.len(), 1);
+    assert_eq!(by_command[0].1.requests, 3);
+    assert_eq!(by_command[0].1.input_tokens, 2300);
+
+    let by_provider = cost::totals_by(&entries, |e| e.provider.clone());
+    assert_eq!(by_provider[0].0, "openai");
+    assert_eq!(by_provider[0].1.requests, 2);
+    assert_eq!(by_provider[1].1.output_tokens, 300);
+
+    assert!(ledger.since("2999-01-01").unwrap().is_empty());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn periods_are_days_weeks_or_dates() {
+    assert_eq!(cost::since_day("1d").unwrap(), cost::today());
+    assert_eq!(cost::since_day("2025-01-31").unwrap(), "2025-01-31");
+    assert!(cost::since_day("2w").unwrap() < cost::since_day("7d").unwrap());
+    assert!(cost::since_day("0d").is_err());
+    assert!(cost::since_day("7 days").is_err());
+}